@@ -69,6 +69,144 @@ pub struct PositionInfo {
     pub update_time: u64,                        // last update time
 }
 
+/// Represents the current risk of one position leg.
+/// This struct maps to an entry of the response from `GET /fapi/v3/positionRisk`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionRisk {
+    pub symbol: String,                          // trading pair symbol
+    pub position_side: String,                   // position side (BOTH, LONG, SHORT)
+    pub position_amt: String,                    // position amount; negative for shorts in one-way mode
+    pub entry_price: String,                     // average entry price
+    #[serde(default)]
+    pub break_even_price: String,                // break-even price including fees
+    pub mark_price: String,                      // current mark price
+    #[serde(rename = "unRealizedProfit")]
+    pub unrealized_profit: String,               // unrealized profit
+    pub liquidation_price: String,               // estimated liquidation price
+    pub notional: String,                        // notional value of the position
+    pub update_time: u64,                        // last update time
+}
+
+impl PositionRisk {
+    /// Returns the position amount as a number; positive for longs, negative for shorts.
+    pub fn amount(&self) -> f64 {
+        self.position_amt.parse::<f64>().unwrap_or_default()
+    }
+}
+
+/// Represents the position mode of the account.
+/// This struct maps to the response from `GET /fapi/v1/positionSide/dual`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionMode {
+    pub dual_side_position: bool,                // true: hedge mode, false: one-way mode
+}
+
+/// Represents the account configuration (fee tier and trading permissions).
+/// This struct maps to the response from `GET /fapi/v1/accountConfig`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountConfig {
+    pub fee_tier: u32,                           // account commission tier
+    pub can_trade: bool,                         // whether the API key may trade
+    pub can_deposit: bool,
+    pub can_withdraw: bool,
+    pub dual_side_position: bool,                // true: hedge mode, false: one-way mode
+    pub multi_assets_margin: bool,               // true: Multi-Assets mode
+    #[serde(default)]
+    pub trade_group_id: i64,                     // -1 when the account is not in a trade group
+    pub update_time: u64,
+}
+
+/// Represents the configuration of a symbol for the account (margin type and leverage).
+/// This struct maps to an element of the response from `GET /fapi/v1/symbolConfig`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolConfig {
+    pub symbol: String,
+    pub margin_type: String,                     // CROSSED or ISOLATED
+    pub is_auto_add_margin: bool,
+    pub leverage: u32,
+    pub max_notional_value: String,              // maximum notional at the current leverage ("INF" when unlimited)
+}
+
+/// Represents an order rate limit of the account.
+/// This struct maps to an element of the response from `GET /fapi/v1/rateLimit/order`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderRateLimit {
+    pub rate_limit_type: String,                 // ORDERS
+    pub interval: String,                        // SECOND, MINUTE or DAY
+    pub interval_num: u32,                       // number of intervals in the window, e.g. 10 for 10 seconds
+    pub limit: u32,                              // maximum number of orders per window
+}
+
+impl OrderRateLimit {
+    /// Returns the length of the rate limit window in seconds.
+    pub fn window_secs(&self) -> u64 {
+        let unit = match self.interval.as_str() {
+            "SECOND" => 1,
+            "MINUTE" => 60,
+            "HOUR" => 3_600,
+            "DAY" => 86_400,
+            _ => 1,
+        };
+        unit * self.interval_num as u64
+    }
+}
+
+/// Account diagnostics, fetched at startup or periodically and exposed on `/metrics`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AccountDiagnostics {
+    pub account_config: Option<AccountConfig>,
+    pub order_rate_limits: Vec<OrderRateLimit>,
+}
+
+impl AccountDiagnostics {
+    /// Fetches the account configuration and order rate limits.
+    pub async fn fetch(rest_client: &RestClient) -> Result<Self, String> {
+        Ok(Self {
+            account_config: Some(rest_client.get_account_config().await?),
+            order_rate_limits: rest_client.get_order_rate_limits().await?,
+        })
+    }
+
+    /// Renders the diagnostics in the Prometheus text exposition format.
+    pub fn render_metrics(&self) -> String {
+        let mut out = String::new();
+        if let Some(config) = &self.account_config {
+            out.push_str("# HELP trading_bot_account_fee_tier Commission tier of the account.
+");
+            out.push_str("# TYPE trading_bot_account_fee_tier gauge
+");
+            out.push_str(&format!("trading_bot_account_fee_tier {}
+", config.fee_tier));
+            out.push_str("# HELP trading_bot_account_can_trade Whether the API key may trade.
+");
+            out.push_str("# TYPE trading_bot_account_can_trade gauge
+");
+            out.push_str(&format!("trading_bot_account_can_trade {}
+", config.can_trade as u8));
+            out.push_str("# HELP trading_bot_account_hedge_mode Whether the account is in hedge (dual side) mode.
+");
+            out.push_str("# TYPE trading_bot_account_hedge_mode gauge
+");
+            out.push_str(&format!("trading_bot_account_hedge_mode {}
+", config.dual_side_position as u8));
+        }
+        if !self.order_rate_limits.is_empty() {
+            out.push_str("# HELP trading_bot_order_rate_limit Maximum number of orders per rate limit window.
+");
+            out.push_str("# TYPE trading_bot_order_rate_limit gauge
+");
+            for limit in &self.order_rate_limits {
+                out.push_str(&format!("trading_bot_order_rate_limit{{window_seconds=\"{}\"}} {}\n", limit.window_secs(), limit.limit));
+            }
+        }
+        out
+    }
+}
 
 impl RestClient {
     /// Fetches the current account information for the authenticated user on Binance Futures.
@@ -108,6 +246,137 @@ impl RestClient {
         Ok(balance)
     }
 
+    /// Fetches the current position mode (one-way or hedge) of the account.
+    ///
+    /// This method calls the `/fapi/v1/positionSide/dual` endpoint using a signed GET request.
+    ///
+    /// # Returns
+    /// A `Result` containing `PositionMode` on success, or a `String` error
+    /// if the request fails or JSON deserialization fails.
+    pub async fn get_position_mode(&self) -> Result<PositionMode, String> {
+        let endpoint = "/fapi/v1/positionSide/dual";
+        let params = vec![];
+        let response_value: Value = self.get_signed_rest_request(endpoint, params).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse position mode JSON: {}", e))
+    }
+
+    /// Changes the position mode of the account on every symbol.
+    ///
+    /// This method calls the `/fapi/v1/positionSide/dual` endpoint using a signed POST request.
+    /// The change is rejected by Binance while there are open positions or open orders.
+    ///
+    /// # Arguments
+    /// * `dual_side_position` - `true` for hedge mode (LONG/SHORT legs), `false` for one-way mode.
+    ///
+    /// # Returns
+    /// A `Result` containing the raw API response `Value` on success, or a `String` error.
+    pub async fn set_position_mode(&self, dual_side_position: bool) -> Result<Value, String> {
+        let endpoint = "/fapi/v1/positionSide/dual";
+        let dual_side_str = dual_side_position.to_string();
+        let params = vec![
+            ("dualSidePosition", dual_side_str.as_str()),
+        ];
+        self.post_signed_rest_request(endpoint, params).await
+    }
+
+    /// Fetches the account configuration (fee tier, trading permissions, position mode).
+    ///
+    /// This method calls the `/fapi/v1/accountConfig` endpoint using a signed GET request.
+    ///
+    /// # Returns
+    /// A `Result` containing `AccountConfig` on success, or a `String` error.
+    pub async fn get_account_config(&self) -> Result<AccountConfig, String> {
+        let endpoint = "/fapi/v1/accountConfig";
+        let params = vec![];
+        let response_value: Value = self.get_signed_rest_request(endpoint, params).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse account config JSON: {}", e))
+    }
+
+    /// Fetches the margin type and leverage of one symbol, or of all symbols.
+    ///
+    /// This method calls the `/fapi/v1/symbolConfig` endpoint using a signed GET request.
+    ///
+    /// # Arguments
+    /// * `symbol` - Optional. The symbol to query; all symbols when `None`.
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<SymbolConfig>` on success, or a `String` error.
+    pub async fn get_symbol_config(&self, symbol: Option<&str>) -> Result<Vec<SymbolConfig>, String> {
+        let endpoint = "/fapi/v1/symbolConfig";
+        let symbol_upper = symbol.map(|s| s.to_uppercase());
+        let mut params = vec![];
+        if let Some(s) = symbol_upper.as_deref() {
+            params.push(("symbol", s));
+        }
+        let response_value: Value = self.get_signed_rest_request(endpoint, params).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse symbol config JSON: {}", e))
+    }
+
+    /// Changes the initial leverage of a symbol.
+    ///
+    /// This method calls the `/fapi/v1/leverage` endpoint using a signed POST request.
+    ///
+    /// # Arguments
+    /// * `symbol` - The symbol whose leverage is changed.
+    /// * `leverage` - The new initial leverage (1-125, depending on the symbol's brackets).
+    ///
+    /// # Returns
+    /// A `Result` containing the raw API response `Value` on success, or a `String` error.
+    pub async fn set_leverage(&self, symbol: &str, leverage: u32) -> Result<Value, String> {
+        let endpoint = "/fapi/v1/leverage";
+        let symbol_upper = symbol.to_uppercase();
+        let leverage_str = leverage.to_string();
+        let params = vec![
+            ("symbol", symbol_upper.as_str()),
+            ("leverage", leverage_str.as_str()),
+        ];
+        self.post_signed_rest_request(endpoint, params).await
+    }
+
+    /// Fetches the open positions of one symbol, or of all symbols.
+    ///
+    /// This method calls the `/fapi/v3/positionRisk` endpoint using a signed GET request.
+    /// Only symbols with a position or open orders are returned.
+    ///
+    /// # Arguments
+    /// * `symbol` - Optional. The symbol to query; all symbols when `None`.
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<PositionRisk>` (one entry per position leg) on success, or a `String` error.
+    pub async fn get_position_risk(&self, symbol: Option<&str>) -> Result<Vec<PositionRisk>, String> {
+        let endpoint = "/fapi/v3/positionRisk";
+        let symbol_upper = symbol.map(|s| s.to_uppercase());
+        let mut params = vec![];
+        if let Some(s) = symbol_upper.as_deref() {
+            params.push(("symbol", s));
+        }
+        let response_value: Value = self.get_signed_rest_request(endpoint, params).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse position risk JSON: {}", e))
+    }
+
+    /// Fetches the order rate limits of the account, i.e. its actual order-rate budget.
+    ///
+    /// This method calls the `/fapi/v1/rateLimit/order` endpoint using a signed GET request.
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<OrderRateLimit>` on success, or a `String` error.
+    pub async fn get_order_rate_limits(&self) -> Result<Vec<OrderRateLimit>, String> {
+        let endpoint = "/fapi/v1/rateLimit/order";
+        let params = vec![];
+        let response_value: Value = self.get_signed_rest_request(endpoint, params).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse order rate limit JSON: {}", e))
+    }
+
     // You can add more account-related functions here, such as:
     // - get_position_information()
     // - get_commission_rate(symbol: &str)
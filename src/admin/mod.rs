@@ -0,0 +1,301 @@
+// src/admin/mod.rs
+
+//! This module serves the admin API: runtime introspection and control for operators, on its own
+//! port (`ADMIN_PORT`) so it can stay firewalled off while the webhook is public. Every request
+//! must send the admin token (`ADMIN_TOKEN`, distinct from the webhook secret) as
+//! `Authorization: Bearer <token>`, and with `ADMIN_ALLOWED_IPS` come from an allowed address.
+//!
+//! - `GET /admin/positions` lists the open position legs.
+//! - `GET /admin/orders` lists the open orders.
+//! - `GET /admin/balances` reports the account totals and the asset balances.
+//! - `GET /admin/connections` reports the exchange connections (WebSocket API sessions).
+//! - `GET /admin/signals` lists the recent webhook signals and their outcome.
+//! - `POST /admin/pause` disarms the bot, `POST /admin/resume` arms it again (see `arming`).
+//! - `GET /admin/arming`, `POST /admin/arming/arm` (`{"by": "alice"}`) and
+//!   `POST /admin/arming/disarm` (`{"reason": "..."}`) show and change the arming state on behalf
+//!   of a named operator (see `arming::router`).
+//! - `POST /admin/flatten` cancels every open order and closes every position with market orders.
+//! - `POST /admin/kill` is the kill switch: disarms the bot, then flattens like `/admin/flatten`,
+//!   logging and notifying every step (see `webhook::control::panic_close_all`).
+//! - `GET /admin/journal?format=csv|json&since=<time>` exports the trade journal (see `journal`),
+//!   JSON by default; `POST /admin/journal/{id}/note` with `{"note": "..."}` annotates a trade.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header::{AUTHORIZATION, CONTENT_TYPE}, HeaderMap},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::account_info::AccountInfo;
+use crate::arming::{self, ArmingState};
+use crate::journal::{load_journal, parse_time, to_csv, to_json};
+use crate::order::Order;
+use crate::webhook::allowlist::{enforce_allowlist, IpAllowlist};
+use crate::webhook::control::{flatten, panic_close_all, set_arming, ControlReport};
+use crate::webhook::queue::TrackedSignal;
+use crate::webhook::response::{ErrorCode, WebhookError, WebhookResponse};
+use crate::webhook::status::{summarize_positions, PositionSummary};
+use crate::webhook::{authorize_bearer, AppState};
+
+/// Operator who arms the bot through `POST /admin/resume`.
+pub const ADMIN_OPERATOR: &str = "admin API";
+
+/// Listen address, token and allowed source addresses of the admin API.
+#[derive(Clone, PartialEq)]
+pub struct AdminConfig {
+    pub listen_addr: String,
+    pub token: String,
+    pub allowlist: Option<IpAllowlist>, // Source addresses allowed to reach the API; `None` allows any
+}
+
+impl std::fmt::Debug for AdminConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminConfig") // Never prints the token
+            .field("listen_addr", &self.listen_addr)
+            .field("allowlist", &self.allowlist)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An open order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrderSummary {
+    pub symbol: String,
+    pub order_id: u64,
+    pub client_order_id: String,
+    pub side: String,
+    pub position_side: String,
+    pub order_type: String,
+    pub price: f64,
+    pub stop_price: f64,
+    pub quantity: f64,
+    pub executed_quantity: f64,
+    pub reduce_only: bool,
+    pub time: u64,
+}
+
+fn number(value: &str) -> f64 {
+    value.parse::<f64>().unwrap_or_default()
+}
+
+/// Summarizes open orders.
+pub fn summarize_orders(orders: &[Order]) -> Vec<OrderSummary> {
+    orders.iter().map(|o| OrderSummary {
+        symbol: o.symbol.clone(),
+        order_id: o.order_id,
+        client_order_id: o.client_order_id.clone(),
+        side: o.side.clone(),
+        position_side: o.position_side.to_string(),
+        order_type: o.order_type.clone(),
+        price: number(&o.price),
+        stop_price: number(&o.stop_price),
+        quantity: number(&o.orig_qty),
+        executed_quantity: number(&o.executed_qty),
+        reduce_only: o.reduce_only,
+        time: o.time,
+    }).collect()
+}
+
+/// The balance of one asset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssetSummary {
+    pub asset: String,
+    pub wallet_balance: f64,
+    pub available_balance: f64,
+    pub unrealized_pnl: f64,
+    pub margin_balance: f64,
+}
+
+/// Body of `GET /admin/balances`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BalanceSummary {
+    pub total_wallet_balance: f64,
+    pub total_unrealized_pnl: f64,
+    pub total_margin_balance: f64,
+    pub available_balance: f64,
+    pub assets: Vec<AssetSummary>, // Assets with a balance
+}
+
+/// Summarizes the account totals and the assets that hold a balance.
+pub fn summarize_balances(account: &AccountInfo) -> BalanceSummary {
+    BalanceSummary {
+        total_wallet_balance: number(&account.total_wallet_balance),
+        total_unrealized_pnl: number(&account.total_unrealized_profit),
+        total_margin_balance: number(&account.total_margin_balance),
+        available_balance: number(&account.available_balance),
+        assets: account.assets.iter()
+            .filter(|a| number(&a.wallet_balance) != 0.0 || number(&a.unrealized_profit) != 0.0)
+            .map(|a| AssetSummary {
+                asset: a.asset.clone(),
+                wallet_balance: number(&a.wallet_balance),
+                available_balance: number(&a.available_balance),
+                unrealized_pnl: number(&a.unrealized_profit),
+                margin_balance: number(&a.margin_balance),
+            })
+            .collect(),
+    }
+}
+
+/// An exchange connection.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectionState {
+    pub name: String, // `ws_api`, or `ws_api:<strategy>` for a strategy trading on its own account
+    pub connected: bool,
+}
+
+/// Rejects requests without the admin token.
+pub async fn require_token(State(token): State<Arc<str>>, headers: HeaderMap, request: Request, next: Next) -> Response {
+    let authorization = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok());
+    if let Err(reason) = authorize_bearer(&token, authorization) {
+        warn!("Rejected unauthenticated admin request to {}: {}", request.uri().path(), reason);
+        return WebhookResponse::from(WebhookError::new(ErrorCode::Unauthorized, reason)).into_response();
+    }
+    next.run(request).await
+}
+
+async fn positions(State(state): State<AppState>) -> Result<Json<Vec<PositionSummary>>, WebhookResponse> {
+    let positions = state.rest_client.get_position_risk(None).await.map_err(|e| {
+        error!("Failed to get positions: {}", e);
+        WebhookError::exchange(format!("Could not get positions: {}", e))
+    })?;
+    Ok(Json(summarize_positions(&positions)))
+}
+
+async fn orders(State(state): State<AppState>) -> Result<Json<Vec<OrderSummary>>, WebhookResponse> {
+    let orders = state.rest_client.get_open_orders(None).await.map_err(|e| {
+        error!("Failed to get open orders: {}", e);
+        WebhookError::exchange(format!("Could not get open orders: {}", e))
+    })?;
+    Ok(Json(summarize_orders(&orders)))
+}
+
+async fn balances(State(state): State<AppState>) -> Result<Json<BalanceSummary>, WebhookResponse> {
+    let account = state.rest_client.get_account_info().await.map_err(|e| {
+        error!("Failed to get the account: {}", e);
+        WebhookError::exchange(format!("Could not get balances: {}", e))
+    })?;
+    Ok(Json(summarize_balances(&account)))
+}
+
+async fn connections(State(state): State<AppState>) -> Json<Vec<ConnectionState>> {
+    let mut connections = vec![ConnectionState { name: "ws_api".to_string(), connected: state.ws_client.is_connected() }];
+    let mut strategies: Vec<_> = state.strategies.iter()
+        .filter_map(|(name, route)| route.clients.as_ref().map(|(ws_client, _)| ConnectionState {
+            name: format!("ws_api:{}", name),
+            connected: ws_client.is_connected(),
+        }))
+        .collect();
+    strategies.sort_by(|a, b| a.name.cmp(&b.name));
+    connections.extend(strategies);
+    Json(connections)
+}
+
+async fn signals(State(state): State<AppState>) -> Json<Vec<TrackedSignal>> {
+    Json(state.signals.recent())
+}
+
+async fn pause(State(state): State<AppState>) -> Result<Json<ArmingState>, WebhookResponse> {
+    Ok(Json(set_arming(&state, false, ADMIN_OPERATOR)?))
+}
+
+async fn resume(State(state): State<AppState>) -> Result<Json<ArmingState>, WebhookResponse> {
+    Ok(Json(set_arming(&state, true, ADMIN_OPERATOR)?))
+}
+
+async fn flatten_all(State(state): State<AppState>) -> Result<Json<ControlReport>, WebhookResponse> {
+    Ok(Json(flatten(&state).await?))
+}
+
+async fn kill_switch(State(state): State<AppState>) -> Result<Json<ControlReport>, WebhookResponse> {
+    Ok(Json(panic_close_all(&state, ADMIN_OPERATOR).await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct JournalQuery {
+    format: Option<String>,
+    since: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoteRequest {
+    note: String,
+}
+
+fn storage_error(e: String) -> WebhookResponse {
+    error!("{}", e);
+    WebhookError::new(ErrorCode::Unavailable, e).into()
+}
+
+async fn journal(State(state): State<AppState>, Query(query): Query<JournalQuery>) -> Result<Response, WebhookResponse> {
+    let Some(storage) = state.storage.as_ref() else {
+        return Err(WebhookError::new(ErrorCode::Unavailable, "History storage is disabled").into());
+    };
+    let since_ms = query.since.as_deref().map(parse_time).transpose().map_err(WebhookError::invalid)?.unwrap_or(0);
+    let trips = load_journal(storage.as_ref(), since_ms).await.map_err(storage_error)?;
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => Ok(([(CONTENT_TYPE, "application/json")], to_json(&trips).map_err(storage_error)?).into_response()),
+        "csv" => Ok(([(CONTENT_TYPE, "text/csv")], to_csv(&trips).map_err(storage_error)?).into_response()),
+        other => Err(WebhookError::invalid(format!("Unknown format '{}', expected csv or json", other)).into()),
+    }
+}
+
+async fn journal_note(State(state): State<AppState>, Path(trade_id): Path<String>, Json(request): Json<NoteRequest>) -> Result<Json<serde_json::Value>, WebhookResponse> {
+    let Some(storage) = state.storage.as_ref() else {
+        return Err(WebhookError::new(ErrorCode::Unavailable, "History storage is disabled").into());
+    };
+    storage.set_note(&trade_id, request.note.trim(), chrono::Utc::now().timestamp_millis()).await.map_err(storage_error)?;
+    info!("Annotated trade {}", trade_id);
+    Ok(Json(serde_json::json!({ "id": trade_id, "note": request.note.trim() })))
+}
+
+/// Returns the admin API acting on the webhook's state, guarded by the token and allowlist of `config`.
+/// Serve it with `into_make_service_with_connect_info::<SocketAddr>()` so the allowlist sees the peer.
+pub fn router(app_state: AppState, config: &AdminConfig) -> Router {
+    let interlock = app_state.interlock.clone();
+    let mut app = Router::new()
+        .route("/admin/positions", get(positions))
+        .route("/admin/orders", get(orders))
+        .route("/admin/balances", get(balances))
+        .route("/admin/connections", get(connections))
+        .route("/admin/signals", get(signals))
+        .route("/admin/pause", post(pause))
+        .route("/admin/resume", post(resume))
+        .route("/admin/flatten", post(flatten_all))
+        .route("/admin/kill", post(kill_switch))
+        .route("/admin/journal", get(journal))
+        .route("/admin/journal/{id}/note", post(journal_note))
+        .with_state(app_state);
+    if let Some(interlock) = interlock {
+        app = app.nest("/admin", arming::router(interlock));
+    }
+    app = app.route_layer(middleware::from_fn_with_state(Arc::<str>::from(config.token.as_str()), require_token));
+    if let Some(allowlist) = config.allowlist.clone() {
+        info!("Admin API restricted to {} allowed networks", allowlist.allowed.len());
+        app = app.route_layer(middleware::from_fn_with_state(Arc::new(allowlist), enforce_allowlist));
+    }
+    app
+}
+
+/// Runs the admin API until `shutdown` resolves, calling `on_ready` once the listener is bound.
+pub async fn serve_admin_until(
+    app_state: AppState,
+    config: &AdminConfig,
+    on_ready: impl FnOnce(),
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<(), String> {
+    let app = router(app_state, config);
+    let listener = tokio::net::TcpListener::bind(&config.listen_addr).await
+        .map_err(|e| format!("Failed to bind the admin API on {}: {}", config.listen_addr, e))?;
+    info!("Admin API listening on http://{}", config.listen_addr);
+    on_ready();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).with_graceful_shutdown(shutdown).await
+        .map_err(|e| format!("Admin API failed: {}", e))
+}
@@ -0,0 +1,209 @@
+// src/arming/mod.rs
+
+//! This module is the live trading safety interlock: the bot is either armed (signals are executed)
+//! or disarmed (signals are logged and recorded as rejected, but no order is placed).
+//!
+//! The state is persisted in the state directory (`arming.json`) so a plain restart keeps it, but
+//! the bot starts disarmed after a fresh install, after a new deployment (the stored deployment ID
+//! differs from the running one, see `DEPLOYMENT_ID`) and after a circuit breaker tripped
+//! (`Interlock::trip`, called by the daily loss breaker). An operator then arms it explicitly
+//! through the admin API (`router`, mounted under `/admin` behind the admin token and allowlist),
+//! a Telegram `/arm` command from an authorized user (`notify::telegram`) or, while the bot is
+//! stopped, with the `arming` binary (plain or as a TUI, `tui::display_with_arming`).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    routing::{get, post},
+    Router,
+};
+use tracing::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::notify::{Notification, Notifications};
+
+/// Default file name of the arming state inside the state directory.
+pub const ARMING_FILE: &str = "arming.json";
+
+/// The persisted arming state.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmingState {
+    pub armed: bool,
+    pub changed_at_ms: i64,
+    #[serde(default)]
+    pub armed_by: Option<String>, // Operator who armed the bot
+    #[serde(default)]
+    pub reason: Option<String>, // Why the bot is disarmed
+    #[serde(default)]
+    pub deployment_id: String, // Deployment the state was set in
+}
+
+impl ArmingState {
+    fn disarmed(reason: &str, deployment_id: &str, now_ms: i64) -> Self {
+        Self {
+            armed: false,
+            changed_at_ms: now_ms,
+            armed_by: None,
+            reason: Some(reason.to_string()),
+            deployment_id: deployment_id.to_string(),
+        }
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default()
+}
+
+/// Reads the arming state stored at `path` as seen by `deployment_id`: disarmed when the file is
+/// missing or unreadable, or when it was written by another deployment.
+pub fn read_state(path: &Path, deployment_id: &str, now_ms: i64) -> ArmingState {
+    let stored = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(_) => return ArmingState::disarmed("fresh install", deployment_id, now_ms),
+    };
+    match serde_json::from_str::<ArmingState>(&stored) {
+        Ok(state) if state.deployment_id != deployment_id => ArmingState::disarmed(
+            &format!("new deployment {} (was {})", deployment_id, state.deployment_id), deployment_id, now_ms,
+        ),
+        Ok(state) => state,
+        Err(e) => {
+            warn!("Invalid arming state in {}: {}", path.display(), e);
+            ArmingState::disarmed("invalid arming state file", deployment_id, now_ms)
+        }
+    }
+}
+
+/// Writes the arming state to `path`.
+pub fn write_state(path: &Path, state: &ArmingState) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(state).map_err(|e| format!("Failed to serialize the arming state: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The armed/disarmed switch shared by the executor and the admin endpoints.
+#[derive(Debug)]
+pub struct Interlock {
+    path: Option<PathBuf>, // `None` keeps the state in memory only
+    deployment_id: String,
+    state: Mutex<ArmingState>,
+    notifications: Option<Notifications>, // Told when a circuit breaker trips
+}
+
+impl Interlock {
+    /// Loads the persisted state for the running deployment, see `read_state`.
+    pub fn load(path: impl Into<PathBuf>, deployment_id: &str) -> Self {
+        let path = path.into();
+        let state = read_state(&path, deployment_id, now_ms());
+        Self { path: Some(path), deployment_id: deployment_id.to_string(), state: Mutex::new(state), notifications: None }
+    }
+
+    /// Creates an interlock that is not persisted, e.g. when the crate is embedded or in tests.
+    pub fn in_memory(armed: bool) -> Self {
+        let mut state = ArmingState::disarmed("fresh install", "", now_ms());
+        if armed {
+            state.armed = true;
+            state.reason = None;
+        }
+        Self { path: None, deployment_id: String::new(), state: Mutex::new(state), notifications: None }
+    }
+
+    /// Also sends a notification when a circuit breaker trips.
+    pub fn with_notifications(mut self, notifications: Notifications) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Returns true when signals may be executed.
+    pub fn is_armed(&self) -> bool {
+        self.state.lock().map(|s| s.armed).unwrap_or(false)
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> ArmingState {
+        self.state.lock().map(|s| s.clone())
+            .unwrap_or_else(|_| ArmingState::disarmed("interlock poisoned", &self.deployment_id, now_ms()))
+    }
+
+    fn set(&self, state: ArmingState) -> Result<ArmingState, String> {
+        if let Some(path) = &self.path {
+            write_state(path, &state)?;
+        }
+        let mut current = self.state.lock().map_err(|_| "Arming state lock poisoned".to_string())?;
+        *current = state.clone();
+        Ok(state)
+    }
+
+    /// Arms the bot on behalf of an operator.
+    pub fn arm(&self, by: &str) -> Result<ArmingState, String> {
+        if by.trim().is_empty() {
+            return Err("Arming needs the name of the operator.".to_string());
+        }
+        info!("Live trading ARMED by {}", by);
+        self.set(ArmingState {
+            armed: true,
+            changed_at_ms: now_ms(),
+            armed_by: Some(by.trim().to_string()),
+            reason: None,
+            deployment_id: self.deployment_id.clone(),
+        })
+    }
+
+    /// Disarms the bot; signals are logged but no longer executed.
+    pub fn disarm(&self, reason: &str) -> Result<ArmingState, String> {
+        warn!("Live trading DISARMED: {}", reason);
+        self.set(ArmingState::disarmed(reason, &self.deployment_id, now_ms()))
+    }
+
+    /// Disarms the bot because a circuit breaker tripped. Failing to persist the state is logged,
+    /// the bot is disarmed in memory regardless.
+    pub fn trip(&self, breaker: &str, reason: &str) {
+        if let Some(notifications) = &self.notifications {
+            notifications.notify(Notification::BreakerTripped { breaker: breaker.to_string(), reason: reason.to_string() });
+        }
+        let reason = format!("circuit breaker {}: {}", breaker, reason);
+        if let Err(e) = self.disarm(&reason) {
+            error!("{}", e);
+            if let Ok(mut state) = self.state.lock() {
+                *state = ArmingState::disarmed(&reason, &self.deployment_id, now_ms());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ArmRequest {
+    by: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DisarmRequest {
+    reason: String,
+}
+
+async fn get_arming(State(interlock): State<Arc<Interlock>>) -> Json<ArmingState> {
+    Json(interlock.state())
+}
+
+async fn post_arm(State(interlock): State<Arc<Interlock>>, Json(request): Json<ArmRequest>) -> Result<Json<ArmingState>, (StatusCode, String)> {
+    interlock.arm(&request.by).map(Json).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+async fn post_disarm(State(interlock): State<Arc<Interlock>>, Json(request): Json<DisarmRequest>) -> Result<Json<ArmingState>, (StatusCode, String)> {
+    interlock.disarm(&request.reason).map(Json).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// Admin endpoints: `GET /arming` returns the state, `POST /arming/arm` (`{"by": "alice"}`) arms
+/// and `POST /arming/disarm` (`{"reason": "..."}`) disarms the bot. The routes are not
+/// authenticated; serve them behind the admin API's token and allowlist (`admin::router`).
+pub fn router(interlock: Arc<Interlock>) -> Router {
+    Router::new()
+        .route("/arming", get(get_arming))
+        .route("/arming/arm", post(post_arm))
+        .route("/arming/disarm", post(post_disarm))
+        .with_state(interlock)
+}
@@ -0,0 +1,40 @@
+// src/bin/arming.rs
+
+//! Shows or changes the live trading interlock state while the bot is stopped. A running bot only
+//! reads the file at startup; use its `/admin/arming` endpoints or Telegram `/arm` instead.
+//!
+//! The deployment ID is taken from `DEPLOYMENT_ID` (default: the crate version), as in the bot.
+//!
+//! Usage:
+//! * `arming <arming.json>` - prints the state the bot would start with.
+//! * `arming <arming.json> arm <operator>` - arms the bot.
+//! * `arming <arming.json> disarm <reason>` - disarms the bot.
+//! * `arming <arming.json> tui <operator>` - shows the state in a TUI, `A` arms and `D` disarms.
+
+use std::env;
+use std::error::Error;
+
+use trading_bot::arming::Interlock;
+use trading_bot::tui::display_with_arming;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let usage = || {
+        eprintln!("Usage: {} <arming.json> [arm <operator> | disarm <reason> | tui <operator>]", args[0]);
+        std::process::exit(2);
+    };
+    let Some(path) = args.get(1) else { usage() };
+    let deployment_id = env::var("DEPLOYMENT_ID").ok().filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
+    let interlock = Interlock::load(path, deployment_id.trim());
+
+    let state = match (args.get(2).map(String::as_str), args.get(3)) {
+        (None, _) => interlock.state(),
+        (Some("arm"), Some(operator)) => interlock.arm(operator)?,
+        (Some("disarm"), Some(_)) => interlock.disarm(&args[3..].join(" "))?,
+        (Some("tui"), Some(operator)) => return display_with_arming(&interlock, operator),
+        _ => usage(),
+    };
+    println!("{}", serde_json::to_string_pretty(&state)?);
+    Ok(())
+}
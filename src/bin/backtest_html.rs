@@ -0,0 +1,24 @@
+// src/bin/backtest_html.rs
+
+//! Renders a JSON backtest report (written with `trading_bot backtest --output report.json`) as a
+//! self-contained HTML page, e.g. for archived runs.
+//!
+//! Usage: `backtest_html <report.json> <report.html>`
+
+use std::env;
+use std::error::Error;
+
+use trading_bot::strategy::html::write_html;
+use trading_bot::strategy::BacktestReport;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let (Some(input), Some(output)) = (args.get(1), args.get(2)) else {
+        eprintln!("Usage: {} <report.json> <report.html>", args[0]);
+        std::process::exit(2);
+    };
+    let json = std::fs::read_to_string(input).map_err(|e| format!("Failed to read {}: {}", input, e))?;
+    write_html(&BacktestReport::from_json(&json)?, output)?;
+    println!("HTML report written to {}", output);
+    Ok(())
+}
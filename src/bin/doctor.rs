@@ -0,0 +1,86 @@
+// src/bin/doctor.rs
+
+//! Checks that the bot can run with the current configuration and prints account diagnostics:
+//! API key permissions, fee tier, position mode, and the account's actual order rate limits.
+//!
+//! Usage: `doctor` (reads the same environment variables / secret files as the bot).
+//! Exits with status 1 when a check fails.
+
+use std::time::Duration;
+
+use dotenv::dotenv;
+use trading_bot::account_info::AccountDiagnostics;
+use trading_bot::config::RuntimeConfig;
+use trading_bot::rest_api::RestClient;
+use trading_bot::websocket::WebSocketClient;
+
+/// How long to wait for the WebSocket API logon.
+const WS_LOGON_TIMEOUT: Duration = Duration::from_secs(15);
+
+fn report(check: &str, result: Result<String, String>, failures: &mut u32) {
+    match result {
+        Ok(detail) => println!("[ OK ] {:<22} {}", check, detail),
+        Err(e) => {
+            *failures += 1;
+            println!("[FAIL] {:<22} {}", check, e);
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+    let mut failures = 0;
+
+    let config = match RuntimeConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("[FAIL] {:<22} {}", "Configuration", e);
+            std::process::exit(1);
+        }
+    };
+    report("Configuration", Ok(format!("REST {} / WS API {}", config.rest_api_base_url, config.ws_api_base_url)), &mut failures);
+
+    let http_client = match config.http.build_client() {
+        Ok(client) => client,
+        Err(e) => {
+            println!("[FAIL] {:<22} {}", "HTTP client", e);
+            std::process::exit(1);
+        }
+    };
+    let rest_client = RestClient::new(config.api_key.clone(), config.secret_key.clone(), config.rest_api_base_url.clone())
+        .with_recv_window(config.recv_window_ms)
+        .with_http_client(http_client);
+    match AccountDiagnostics::fetch(&rest_client).await {
+        Ok(diagnostics) => {
+            if let Some(account) = &diagnostics.account_config {
+                let permissions = if account.can_trade { Ok("canTrade=true".to_string()) } else { Err("canTrade=false: the API key cannot place orders".to_string()) };
+                report("Trading permission", permissions, &mut failures);
+                report("Fee tier", Ok(account.fee_tier.to_string()), &mut failures);
+                let mode = if account.dual_side_position { "hedge (dual side)" } else { "one-way" };
+                let margin = if account.multi_assets_margin { "multi-assets" } else { "single-asset" };
+                report("Position mode", Ok(format!("{}, {} margin", mode, margin)), &mut failures);
+            }
+            let limits = diagnostics.order_rate_limits.iter()
+                .map(|l| format!("{} orders / {}s", l.limit, l.window_secs()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            report("Order rate limits", Ok(limits), &mut failures);
+        },
+        Err(e) => report("Account diagnostics", Err(e), &mut failures),
+    }
+
+    let ws_client = WebSocketClient::new(config.api_key.clone(), config.secret_key.clone(), config.ws_api_base_url.clone()).await.with_recv_window(config.recv_window_ms);
+    let logon = match tokio::time::timeout(WS_LOGON_TIMEOUT, ws_client.session_logon()).await {
+        Ok(Ok(_)) => Ok("session.logon succeeded".to_string()),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(format!("no response within {:?}", WS_LOGON_TIMEOUT)),
+    };
+    report("WebSocket API", logon, &mut failures);
+
+    if failures > 0 {
+        println!("\n{} check(s) failed.", failures);
+        std::process::exit(1);
+    }
+    println!("\nAll checks passed.");
+}
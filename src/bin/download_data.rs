@@ -0,0 +1,66 @@
+// src/bin/download_data.rs
+
+//! Downloads historical klines (and optionally aggregated trades) from Binance for backtesting.
+//!
+//! Usage: `download_data <SYMBOL> <interval> <from> [to] [--agg-trades] [--out <dir>] [--format csv|parquet] [--weight <per minute>]`
+//! e.g. `download_data BTCUSDT 1m 2021-01-01 2021-02-01 --out data`. Dates are UTC (`YYYY-MM-DD`,
+//! `YYYY-MM-DD HH:MM:SS` or epoch milliseconds); `to` defaults to now. Interrupted downloads resume
+//! from their checkpoint when run again. The REST endpoint is `BINANCE_REST_API_BASE_URL` (mainnet by default).
+
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+
+use trading_bot::data::downloader::{download, DownloadRequest, OutputFormat};
+use trading_bot::market_data::KlineInterval;
+use trading_bot::rest_api::RestClient;
+use trading_bot::strategy::config::{parse_date_ms, DEFAULT_KLINES_BASE_URL};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    trading_bot::telemetry::init();
+    let args: Vec<String> = env::args().collect();
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--agg-trades" => options.push((arg.as_str(), "")),
+            "--out" | "--format" | "--weight" => {
+                let value = iter.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+                options.push((arg.as_str(), value.as_str()));
+            },
+            other if other.starts_with("--") => return Err(format!("Unknown option '{}'", other).into()),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    if positional.len() < 3 {
+        eprintln!("Usage: {} <SYMBOL> <interval> <from> [to] [--agg-trades] [--out <dir>] [--format csv|parquet] [--weight <per minute>]", args[0]);
+        std::process::exit(2);
+    }
+
+    let interval: KlineInterval = positional[1].parse()?;
+    let start_ms = parse_date_ms(positional[2])?;
+    let end_ms = match positional.get(3) {
+        Some(to) => parse_date_ms(to)?,
+        None => chrono::Utc::now().timestamp_millis() as u64,
+    };
+    let mut request = DownloadRequest::new(positional[0], interval, start_ms, end_ms);
+    for (option, value) in options {
+        match option {
+            "--agg-trades" => request.agg_trades = true,
+            "--out" => request.output_dir = PathBuf::from(value),
+            "--format" => request.format = OutputFormat::parse(value)?,
+            _ => request.weight_per_minute = value.parse().map_err(|e| format!("Invalid --weight '{}': {}", value, e))?,
+        }
+    }
+
+    let rest_base_url = env::var("BINANCE_REST_API_BASE_URL").unwrap_or_else(|_| DEFAULT_KLINES_BASE_URL.to_string());
+    let client = RestClient::new(String::new(), String::new(), rest_base_url);
+    let report = download(&client, &request).await?;
+    println!("{} new klines in {}", report.klines_rows, report.klines_path.display());
+    if let Some(path) = report.agg_trades_path {
+        println!("{} new aggregated trades in {}", report.agg_trades_rows, path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+// src/bin/journal.rs
+
+//! Exports the trade journal (round-trip trades built from the stored fills, see `journal`) and
+//! annotates its trades. The database is a SQLite file or a `postgres://` URL, as in the bot.
+//!
+//! Usage:
+//! * `journal <database>` - prints the journal as JSON.
+//! * `journal <database> csv [since <time>]` - prints it as CSV, for spreadsheets; `json` also works.
+//!   `time` is RFC 3339, e.g. `2024-05-01T00:00:00Z`.
+//! * `journal <database> note <trade-id> <text>` - sets the note of a trade; an empty text removes it.
+
+use std::env;
+use std::error::Error;
+
+use trading_bot::journal::{load_journal, parse_time, to_csv, to_json};
+use trading_bot::storage::{open_storage, StorageConfig};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let usage = || {
+        eprintln!("Usage: {} <database> [csv | json] [since <time>] | <database> note <trade-id> <text>", args[0]);
+        std::process::exit(2);
+    };
+    let Some(location) = args.get(1) else { usage() };
+    let storage = open_storage(&StorageConfig::parse(location)).await?;
+
+    if args.get(2).map(String::as_str) == Some("note") {
+        let Some(trade_id) = args.get(3) else { usage() };
+        let note = args[4..].join(" ");
+        storage.set_note(trade_id, note.trim(), chrono::Utc::now().timestamp_millis()).await?;
+        return Ok(());
+    }
+    let (format, rest) = match args.get(2).map(String::as_str) {
+        Some(format @ ("csv" | "json")) => (format, &args[3..]),
+        _ => ("json", &args[2..]),
+    };
+    let since_ms = match rest {
+        [] => 0,
+        [keyword, time] if keyword == "since" => parse_time(time)?,
+        _ => usage(),
+    };
+    let trips = load_journal(storage.as_ref(), since_ms).await?;
+    match format {
+        "csv" => print!("{}", to_csv(&trips)?),
+        _ => println!("{}", to_json(&trips)?),
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+// src/bin/performance_report.rs
+
+//! Prints weekly or monthly performance from a CSV export of closed trades.
+//!
+//! Usage: `performance_report <trades.csv> [week|month] [starting_equity]`
+//! The CSV needs the columns `closed_at_ms,symbol,pnl,fees`. The risk-free rate is read from `RISK_FREE_RATE`.
+//! With `REPORTING_CURRENCY=EUR|BTC`, amounts are converted at hourly prices downloaded from
+//! `BINANCE_REST_API_BASE_URL` (mainnet by default).
+
+use std::env;
+use std::error::Error;
+
+use trading_bot::currency::ReportingCurrency;
+use trading_bot::metrics::{self, MetricsConfig};
+use trading_bot::performance::{aggregate_converted, load_closed_trades_csv, print_period_summaries, Period};
+use trading_bot::rest_api::RestClient;
+use trading_bot::strategy::config::DEFAULT_KLINES_BASE_URL;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let Some(path) = args.get(1) else {
+        eprintln!("Usage: {} <trades.csv> [week|month] [starting_equity]", args[0]);
+        std::process::exit(2);
+    };
+    let period = match args.get(2) {
+        Some(p) => Period::from_str_opt(p).ok_or_else(|| format!("Invalid period '{}': expected week or month", p))?,
+        None => Period::Month,
+    };
+    let starting_equity = match args.get(3) {
+        Some(e) => e.parse::<f64>().map_err(|e| format!("Invalid starting equity: {}", e))?,
+        None => 5000.0,
+    };
+
+    let trades = load_closed_trades_csv(path)?;
+    let config = MetricsConfig::from_env(metrics::DAILY_PERIODS_PER_YEAR)?;
+    let currency = ReportingCurrency::from_env()?;
+    let start = trades.iter().map(|t| t.closed_at_ms).min().unwrap_or(0).max(0) as u64;
+    let end = trades.iter().map(|t| t.closed_at_ms).max().unwrap_or(0).max(0) as u64;
+    let rest_base_url = env::var("BINANCE_REST_API_BASE_URL").unwrap_or_else(|_| DEFAULT_KLINES_BASE_URL.to_string());
+    let converter = RestClient::new(String::new(), String::new(), rest_base_url)
+        .get_historical_currency_converter(currency, start, end).await?;
+    print_period_summaries(&aggregate_converted(&trades, period, starting_equity, &config, &converter), currency);
+    Ok(())
+}
@@ -0,0 +1,63 @@
+// src/bin/replay.rs
+
+//! Replays the bot's event log to answer questions about past decisions.
+//!
+//! Usage:
+//! * `replay <events.jsonl>` - prints the current state (positions, realized PnL, config).
+//! * `replay <events.jsonl> at <time>` - prints the state as it was at `time` (RFC 3339, e.g. `2024-05-01T03:12:00Z`).
+//! * `replay <events.jsonl> why <seq>` - prints the state the bot was in when event `seq` happened, and the event.
+//! * `replay <events.jsonl> list <from> <to>` - lists the events between two times.
+
+use std::env;
+use std::error::Error;
+
+use chrono::DateTime;
+use trading_bot::events::{load_events, records_between, replay, state_before, BotState, EventRecord};
+
+fn parse_time(s: &str) -> Result<i64, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.timestamp_millis())
+        .map_err(|e| format!("Invalid time '{}': {}", s, e))
+}
+
+fn print_record(record: &EventRecord) {
+    let time = DateTime::from_timestamp_millis(record.time_ms).map(|dt| dt.to_rfc3339()).unwrap_or_default();
+    println!("#{:<6} {} {}", record.seq, time, serde_json::to_string(&record.event).unwrap_or_default());
+}
+
+fn print_state(state: &BotState) {
+    println!("{}", serde_json::to_string_pretty(state).unwrap_or_default());
+    println!("Realized PnL today (UTC): {:.4}", state.risk.realized_pnl_on(state.time_ms));
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let Some(path) = args.get(1) else {
+        eprintln!("Usage: {} <events.jsonl> [at <time> | why <seq> | list <from> <to>]", args[0]);
+        std::process::exit(2);
+    };
+    let records = load_events(path)?;
+
+    match (args.get(2).map(String::as_str), args.get(3), args.get(4)) {
+        (None, _, _) => print_state(&replay(&records, None)),
+        (Some("at"), Some(time), _) => print_state(&replay(&records, Some(parse_time(time)?))),
+        (Some("why"), Some(seq), _) => {
+            let seq = seq.parse::<u64>().map_err(|e| format!("Invalid sequence number '{}': {}", seq, e))?;
+            let (state, record) = state_before(&records, seq).ok_or_else(|| format!("No event #{} in {}", seq, path))?;
+            println!("State before the event:");
+            print_state(&state);
+            println!("Event:");
+            print_record(record);
+        },
+        (Some("list"), Some(from), Some(to)) => {
+            for record in records_between(&records, parse_time(from)?, parse_time(to)?) {
+                print_record(record);
+            }
+        },
+        _ => {
+            eprintln!("Usage: {} <events.jsonl> [at <time> | why <seq> | list <from> <to>]", args[0]);
+            std::process::exit(2);
+        },
+    }
+    Ok(())
+}
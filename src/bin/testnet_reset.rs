@@ -0,0 +1,39 @@
+// src/bin/testnet_reset.rs
+
+//! Resets the Binance Futures testnet account and the local state to a known clean state:
+//! cancels open orders, closes positions, clears the state directory and verifies the balances.
+//!
+//! Usage: `cargo run --features testnet-tools --bin testnet_reset [--keep-state]`
+//! (reads the same environment variables / secret files as the bot; refuses non-testnet URLs).
+//! Exits with status 1 when the account is not clean afterwards.
+
+use dotenv::dotenv;
+use trading_bot::config::RuntimeConfig;
+use trading_bot::testnet::reset_testnet;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    trading_bot::telemetry::init();
+    let keep_state = std::env::args().any(|a| a == "--keep-state");
+
+    let config = RuntimeConfig::from_env()?;
+    let report = reset_testnet(&config, !keep_state).await?;
+
+    println!("Cancelled orders: {}", report.cancelled_orders);
+    println!("Closed positions: {}", report.closed_positions);
+    for path in &report.removed_files {
+        println!("Removed: {}", path.display());
+    }
+    for (asset, balance) in &report.balances {
+        println!("Balance: {} {}", balance, asset);
+    }
+    if !report.is_clean() {
+        for problem in &report.problems {
+            println!("[FAIL] {}", problem);
+        }
+        std::process::exit(1);
+    }
+    println!("\nTestnet state is clean.");
+    Ok(())
+}
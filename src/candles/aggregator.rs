@@ -0,0 +1,252 @@
+// src/candles/aggregator.rs
+
+//! This module builds OHLCV candles of arbitrary intervals from aggregated trades, including
+//! intervals Binance does not stream (e.g. 2m, 45m or 90s). `CandleAggregator` is the synchronous
+//! core: it is fed `AggTradeStream` messages (or normalized `Trade`s) and returns the candles that
+//! closed. `spawn_candle_aggregator` runs one in a task that forwards the closed candles as
+//! `MarketEvent::Kline` events, so live strategies and recorders consume them like streamed klines.
+//!
+//! * Buckets are aligned to multiples of the interval since the epoch, as Binance klines are. For
+//!   intervals that do not divide a day (e.g. 45m) the buckets therefore do not start at midnight,
+//!   and weekly buckets start on Thursdays rather than Mondays.
+//! * A candle closes when the first trade of a later bucket arrives, or when `flush` is called
+//!   after its close time; the service flushes every second, `FLUSH_GRACE_MS` after the close so
+//!   trades still in flight are counted.
+//! * Intervals without trades produce flat candles at the previous close with zero volume, as
+//!   Binance's klines do, so the series has no gaps once the first trade has been seen (unless more
+//!   than `MAX_GAP_CANDLES` are missing, e.g. after a long disconnection).
+//! * Trades older than the forming candle (delivered late, after their candle closed) are dropped.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::Utc;
+use tracing::{debug, info, warn};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::market_event::{interval_to_ms, Candle, MarketEvent, Symbol, Trade};
+use crate::streams::AggTradeStream;
+
+/// How often the service closes candles that received no later trade.
+const FLUSH_PERIOD: Duration = Duration::from_secs(1);
+
+/// How long after a candle's close time the service waits for late trades before closing it.
+pub const FLUSH_GRACE_MS: u64 = 1_000;
+
+/// Most flat candles closed for a gap without trades.
+pub const MAX_GAP_CANDLES: u64 = 1_000;
+
+/// The candles of one symbol and interval.
+#[derive(Debug, Clone, Default)]
+struct Series {
+    forming: Option<Candle>,
+    last_closed: Option<Candle>,
+}
+
+impl Series {
+    /// Closes flat candles for the buckets between the last closed candle and the one opening at
+    /// `open_time`. Longer gaps (e.g. after a disconnection) are not filled: the series restarts.
+    fn fill_gap(&mut self, open_time: u64, event_time: u64, closed: &mut Vec<Candle>) {
+        let Some(last) = self.last_closed else { return };
+        let missing = open_time.saturating_sub(last.open_time + last.interval_ms) / last.interval_ms;
+        if missing > MAX_GAP_CANDLES {
+            warn!("{} {}ms candles missing trades; restarting the series", missing, last.interval_ms);
+            self.last_closed = None;
+            return;
+        }
+        while let Some(previous) = self.last_closed.filter(|c| c.open_time + c.interval_ms < open_time) {
+            let candle = flat_candle(&previous, event_time);
+            closed.push(candle);
+            self.last_closed = Some(candle);
+        }
+    }
+}
+
+/// Builds candles of several intervals from trades of any number of symbols.
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    intervals_ms: Vec<u64>,
+    series: HashMap<(Symbol, u64), Series>,
+}
+
+/// Returns a closed candle with no trades following `previous`, at its close price.
+fn flat_candle(previous: &Candle, event_time: u64) -> Candle {
+    Candle {
+        event_time,
+        open_time: previous.open_time + previous.interval_ms,
+        close_time: previous.close_time + previous.interval_ms,
+        open: previous.close,
+        high: previous.close,
+        low: previous.close,
+        volume: 0.0,
+        quote_volume: 0.0,
+        trades: 0,
+        is_closed: true,
+        ..*previous
+    }
+}
+
+impl CandleAggregator {
+    /// Creates an aggregator for intervals given as Binance-style strings, e.g. `["2m", "45m", "4h"]`.
+    pub fn new(intervals: &[&str]) -> Result<Self, String> {
+        let intervals_ms = intervals.iter()
+            .map(|interval| interval_to_ms(interval).filter(|ms| *ms > 0).ok_or_else(|| format!("Invalid candle interval '{}'", interval)))
+            .collect::<Result<Vec<u64>, String>>()?;
+        Self::with_intervals_ms(intervals_ms)
+    }
+
+    /// Creates an aggregator for intervals in milliseconds.
+    pub fn with_intervals_ms(mut intervals_ms: Vec<u64>) -> Result<Self, String> {
+        if intervals_ms.is_empty() || intervals_ms.contains(&0) {
+            return Err("At least one positive candle interval is required.".to_string());
+        }
+        intervals_ms.sort_unstable();
+        intervals_ms.dedup();
+        Ok(Self { intervals_ms, series: HashMap::new() })
+    }
+
+    /// Returns the intervals in milliseconds, shortest first.
+    pub fn intervals_ms(&self) -> &[u64] {
+        &self.intervals_ms
+    }
+
+    /// Returns the forming (not yet closed) candle of `symbol` for `interval_ms`, if any.
+    pub fn forming(&self, symbol: Symbol, interval_ms: u64) -> Option<&Candle> {
+        self.series.get(&(symbol, interval_ms)).and_then(|s| s.forming.as_ref())
+    }
+
+    /// Adds an aggregated trade message and returns the candles it closed.
+    pub fn on_agg_trade(&mut self, message: &AggTradeStream) -> Result<Vec<Candle>, String> {
+        let symbol = Symbol::new(&message.symbol)?;
+        let price = message.price.parse::<f64>().map_err(|e| format!("Invalid aggTrade price '{}': {}", message.price, e))?;
+        let quantity = message.quantity.parse::<f64>().map_err(|e| format!("Invalid aggTrade quantity '{}': {}", message.quantity, e))?;
+        let trades = message.last_trade_id.saturating_sub(message.first_trade_id) + 1;
+        Ok(self.add(symbol, message.trade_time, message.event_time, price, quantity, trades))
+    }
+
+    /// Adds a normalized trade and returns the candles it closed.
+    pub fn on_trade(&mut self, trade: &Trade) -> Vec<Candle> {
+        self.add(trade.symbol, trade.trade_time, trade.event_time, trade.price, trade.quantity, 1)
+    }
+
+    fn add(&mut self, symbol: Symbol, time_ms: u64, event_time: u64, price: f64, quantity: f64, trades: u64) -> Vec<Candle> {
+        let mut closed = Vec::new();
+        for &interval_ms in &self.intervals_ms {
+            let series = self.series.entry((symbol, interval_ms)).or_default();
+            let open_time = time_ms - time_ms % interval_ms;
+            if let Some(forming) = series.forming.as_mut().filter(|c| c.open_time == open_time) {
+                forming.event_time = event_time;
+                forming.high = forming.high.max(price);
+                forming.low = forming.low.min(price);
+                forming.close = price;
+                forming.volume += quantity;
+                forming.quote_volume += price * quantity;
+                forming.trades += trades;
+                continue;
+            }
+            let is_late = match (&series.forming, &series.last_closed) {
+                (Some(forming), _) => open_time < forming.open_time,
+                (None, Some(last_closed)) => open_time <= last_closed.open_time,
+                (None, None) => false,
+            };
+            if is_late {
+                debug!("Dropping late {} trade at {} for the {}ms candles", symbol, time_ms, interval_ms);
+                continue;
+            }
+            if let Some(forming) = series.forming.take() {
+                let candle = Candle { event_time, is_closed: true, ..forming };
+                closed.push(candle);
+                series.last_closed = Some(candle);
+            }
+            // Buckets without trades between the last candle and this trade
+            series.fill_gap(open_time, event_time, &mut closed);
+            series.forming = Some(Candle {
+                symbol,
+                event_time,
+                interval_ms,
+                open_time,
+                close_time: open_time + interval_ms - 1,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: quantity,
+                quote_volume: price * quantity,
+                trades,
+                is_closed: false,
+            });
+        }
+        closed.sort_by_key(|c| (c.close_time, c.interval_ms));
+        closed
+    }
+
+    /// Closes the candles whose close time is before `now_ms`, including flat candles for
+    /// intervals that passed without trades, and returns them.
+    pub fn flush(&mut self, now_ms: u64) -> Vec<Candle> {
+        let mut closed = Vec::new();
+        for series in self.series.values_mut() {
+            if let Some(forming) = series.forming.take_if(|c| c.close_time < now_ms) {
+                let candle = Candle { event_time: now_ms, is_closed: true, ..forming };
+                closed.push(candle);
+                series.last_closed = Some(candle);
+            }
+            if series.forming.is_some() {
+                continue;
+            }
+            // Buckets that have fully elapsed without trades
+            if let Some(interval_ms) = series.last_closed.map(|c| c.interval_ms) {
+                series.fill_gap(now_ms - now_ms % interval_ms, now_ms, &mut closed);
+            }
+        }
+        closed.sort_by_key(|c| (c.close_time, c.interval_ms, c.symbol));
+        closed
+    }
+}
+
+/// Spawns a task aggregating trades into candles, which are sent as `MarketEvent::Kline` events.
+/// The task ends when either channel closes and returns the aggregator.
+///
+/// # Arguments
+/// * `aggregator` - The intervals to build.
+/// * `trade_receiver` - Messages of the `<symbol>@aggTrade` streams.
+/// * `candle_sender` - Receives every closed candle, shortest interval first among those closing together.
+pub fn spawn_candle_aggregator(
+    mut aggregator: CandleAggregator,
+    mut trade_receiver: mpsc::Receiver<AggTradeStream>,
+    candle_sender: mpsc::Sender<MarketEvent>,
+) -> JoinHandle<CandleAggregator> {
+    tokio::spawn(async move {
+        info!("Candle aggregator started for intervals {:?} ms", aggregator.intervals_ms());
+        let mut flush_interval = tokio::time::interval_at(Instant::now() + FLUSH_PERIOD, FLUSH_PERIOD);
+        loop {
+            let candles = tokio::select! {
+                message = trade_receiver.recv() => {
+                    let Some(message) = message else {
+                        info!("Trade channel closed. Stopping candle aggregator.");
+                        break;
+                    };
+                    match aggregator.on_agg_trade(&message) {
+                        Ok(candles) => candles,
+                        Err(e) => {
+                            warn!("Skipping aggTrade message: {}", e);
+                            continue;
+                        },
+                    }
+                },
+                _ = flush_interval.tick() => {
+                    let now_ms = Utc::now().timestamp_millis().max(0) as u64;
+                    aggregator.flush(now_ms.saturating_sub(FLUSH_GRACE_MS))
+                },
+            };
+            for candle in candles {
+                if candle_sender.send(MarketEvent::Kline(candle)).await.is_err() {
+                    info!("Candle channel closed. Stopping candle aggregator.");
+                    return aggregator;
+                }
+            }
+        }
+        aggregator
+    })
+}
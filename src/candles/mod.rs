@@ -0,0 +1,5 @@
+// src/candles/mod.rs
+
+//! This module groups the tools that build candles from live market data.
+
+pub mod aggregator;
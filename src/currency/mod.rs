@@ -0,0 +1,168 @@
+// src/currency/mod.rs
+
+//! This module converts PnL and balances from the contract's quote asset (USDT, counted as USD)
+//! into the reporting currency chosen by the user (`REPORTING_CURRENCY=USD|EUR|BTC`, or the
+//! backtest's `reporting_currency` setting), and formats amounts in it.
+//!
+//! A `CurrencyConverter` holds the price of the reporting currency in the quote asset over time
+//! (e.g. BTCUSDT or EURUSDT), so each amount is converted at the price of the moment it was
+//! realized. Rates come from the market-data module: the current ticker price, or hourly klines
+//! over a date range for historical reports.
+
+use serde::{Deserialize, Serialize};
+
+use crate::market_data::{Candlestick, KlineInterval};
+use crate::performance::ClosedTrade;
+use crate::rest_api::RestClient;
+
+/// Currency PnL and balances are reported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ReportingCurrency {
+    #[default]
+    Usd, // The quote asset itself (USDT)
+    Eur,
+    Btc,
+}
+
+impl ReportingCurrency {
+    /// Parses "USD" (or "USDT"), "EUR" or "BTC", case-insensitively.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_uppercase().as_str() {
+            "USD" | "USDT" => Ok(ReportingCurrency::Usd),
+            "EUR" => Ok(ReportingCurrency::Eur),
+            "BTC" => Ok(ReportingCurrency::Btc),
+            _ => Err(format!("Invalid reporting currency '{}': expected USD, EUR or BTC", value)),
+        }
+    }
+
+    /// Reads `REPORTING_CURRENCY`, defaulting to USD.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("REPORTING_CURRENCY") {
+            Ok(value) if !value.trim().is_empty() => Self::parse(&value),
+            _ => Ok(ReportingCurrency::Usd),
+        }
+    }
+
+    /// Returns the currency code, e.g. "EUR".
+    pub fn code(&self) -> &'static str {
+        match self {
+            ReportingCurrency::Usd => "USD",
+            ReportingCurrency::Eur => "EUR",
+            ReportingCurrency::Btc => "BTC",
+        }
+    }
+
+    /// Returns the symbol quoting the currency in the quote asset, or `None` for the quote asset itself.
+    pub fn conversion_symbol(&self) -> Option<&'static str> {
+        match self {
+            ReportingCurrency::Usd => None,
+            ReportingCurrency::Eur => Some("EURUSDT"),
+            ReportingCurrency::Btc => Some("BTCUSDT"),
+        }
+    }
+
+    /// Formats an amount already expressed in this currency, e.g. "$12.34", "€12.34" or "0.00012345 BTC".
+    pub fn format(&self, amount: f64) -> String {
+        match self {
+            ReportingCurrency::Usd => format!("${:.2}", amount),
+            ReportingCurrency::Eur => format!("€{:.2}", amount),
+            ReportingCurrency::Btc => format!("{:.8} BTC", amount),
+        }
+    }
+}
+
+/// Converts amounts in the quote asset into a reporting currency.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CurrencyConverter {
+    pub currency: ReportingCurrency,
+    rates: Vec<(i64, f64)>, // (time in ms, price of one unit of the currency in the quote asset), oldest first
+}
+
+impl CurrencyConverter {
+    /// Creates a converter using a single price for all amounts, e.g. the current one.
+    pub fn fixed(currency: ReportingCurrency, rate: f64) -> Result<Self, String> {
+        Self::historical(currency, vec![(0, rate)])
+    }
+
+    /// Creates a converter from prices over time; amounts are converted at the latest price at or
+    /// before their time (the first price for earlier amounts).
+    pub fn historical(currency: ReportingCurrency, mut rates: Vec<(i64, f64)>) -> Result<Self, String> {
+        if currency == ReportingCurrency::Usd {
+            return Ok(Self::default());
+        }
+        if rates.is_empty() || rates.iter().any(|(_, rate)| !(rate.is_finite() && *rate > 0.0)) {
+            return Err(format!("Converting to {} needs at least one positive price", currency.code()));
+        }
+        rates.sort_by_key(|(time_ms, _)| *time_ms);
+        Ok(Self { currency, rates })
+    }
+
+    /// Returns the price of one unit of the reporting currency in the quote asset at `time_ms`.
+    pub fn rate_at(&self, time_ms: i64) -> f64 {
+        let index = self.rates.partition_point(|(t, _)| *t <= time_ms);
+        self.rates.get(index.saturating_sub(1)).map(|(_, rate)| *rate).unwrap_or(1.0)
+    }
+
+    /// Converts an amount in the quote asset realized at `time_ms`.
+    pub fn convert(&self, amount: f64, time_ms: i64) -> f64 {
+        amount / self.rate_at(time_ms)
+    }
+
+    /// Converts an amount at the most recent price.
+    pub fn convert_latest(&self, amount: f64) -> f64 {
+        self.convert(amount, i64::MAX)
+    }
+
+    /// Converts the PnL and fees of closed trades at their closing times.
+    pub fn convert_trades(&self, trades: &[ClosedTrade]) -> Vec<ClosedTrade> {
+        trades.iter().map(|trade| ClosedTrade {
+            pnl: self.convert(trade.pnl, trade.closed_at_ms),
+            fees: self.convert(trade.fees, trade.closed_at_ms),
+            ..trade.clone()
+        }).collect()
+    }
+
+    /// Formats an amount already converted to the reporting currency.
+    pub fn format(&self, amount: f64) -> String {
+        self.currency.format(amount)
+    }
+}
+
+impl RestClient {
+    /// Builds a converter from the current price of the reporting currency.
+    ///
+    /// # Arguments
+    /// * `currency` - The reporting currency.
+    ///
+    /// # Returns
+    /// A `Result` containing the `CurrencyConverter`, or a `String` error if the price request fails.
+    pub async fn get_currency_converter(&self, currency: ReportingCurrency) -> Result<CurrencyConverter, String> {
+        let Some(symbol) = currency.conversion_symbol() else { return Ok(CurrencyConverter::default()) };
+        let ticker = self.get_current_price(symbol).await?;
+        let price = ticker.price.parse::<f64>().map_err(|e| format!("Invalid {} price '{}': {}", symbol, ticker.price, e))?;
+        CurrencyConverter::fixed(currency, price)
+    }
+
+    /// Builds a converter from hourly prices of the reporting currency between two times.
+    ///
+    /// # Arguments
+    /// * `currency` - The reporting currency.
+    /// * `start_time` - Start time in milliseconds.
+    /// * `end_time` - End time in milliseconds.
+    ///
+    /// # Returns
+    /// A `Result` containing the `CurrencyConverter`, or a `String` error if the klines request fails
+    /// or returns no prices.
+    pub async fn get_historical_currency_converter(&self, currency: ReportingCurrency, start_time: u64, end_time: u64) -> Result<CurrencyConverter, String> {
+        let Some(symbol) = currency.conversion_symbol() else { return Ok(CurrencyConverter::default()) };
+        let klines = self.get_klines_range(symbol, KlineInterval::H1, start_time, end_time).await?;
+        let rates = klines.iter()
+            .map(|Candlestick::Array(open_time, open, ..)| {
+                open.parse::<f64>().map(|price| (*open_time as i64, price))
+                    .map_err(|e| format!("Invalid {} price '{}': {}", symbol, open, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        CurrencyConverter::historical(currency, rates)
+    }
+}
@@ -0,0 +1,170 @@
+// src/dashboard/mod.rs
+
+//! This module pushes the bot's events to dashboards over a WebSocket, so a browser UI can follow
+//! signals, orders, fills and PnL live instead of polling. Clients connect to `GET /events` (with
+//! `?token=<DASHBOARD_TOKEN>` when a token is configured, as browsers cannot set headers on
+//! WebSocket requests) and receive JSON text messages:
+//!
+//! - `{"type": "snapshot", "state": {...}}` once, the state rebuilt from the event log (see `events::BotState`);
+//! - `{"type": "event", "seq": 12, "timeMs": ..., "event": {...}}` for every recorded event;
+//! - `{"type": "pnl", "timeMs": ..., "realizedPnl": ..., "positions": {...}}` after every fill;
+//! - `{"type": "lagged", "missed": 3}` when the client fell behind and events were skipped.
+//!
+//! Messages from clients are ignored.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use axum::{
+    extract::ws::{rejection::WebSocketUpgradeRejection, Message, WebSocket, WebSocketUpgrade},
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
+
+use crate::events::{load_events, replay, BotEvent, BotState, EventLog, EventRecord, PositionState};
+use crate::webhook::constant_time_eq;
+
+/// Listen address and optional token of the dashboard stream.
+#[derive(Clone, PartialEq)]
+pub struct DashboardConfig {
+    pub listen_addr: String,
+    pub token: Option<String>, // Clients must send it as `?token=`; `None` accepts any client
+}
+
+impl std::fmt::Debug for DashboardConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DashboardConfig") // Never prints the token
+            .field("listen_addr", &self.listen_addr)
+            .field("token", &self.token.is_some())
+            .finish()
+    }
+}
+
+/// A message pushed to dashboard clients.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PushMessage {
+    Snapshot {
+        state: BotState,
+    },
+    Event {
+        #[serde(flatten)]
+        record: EventRecord,
+    },
+    #[serde(rename_all = "camelCase")]
+    Pnl {
+        time_ms: i64,
+        realized_pnl: f64,
+        positions: BTreeMap<String, PositionState>,
+    },
+    Lagged {
+        missed: u64,
+    },
+}
+
+/// Applies a record to the client's state and returns the messages pushed for it.
+pub fn push_messages(state: &mut BotState, record: EventRecord) -> Vec<PushMessage> {
+    state.apply(&record);
+    let is_fill = matches!(record.event, BotEvent::Fill { .. });
+    let mut messages = vec![PushMessage::Event { record }];
+    if is_fill {
+        messages.push(PushMessage::Pnl { time_ms: state.time_ms, realized_pnl: state.realized_pnl, positions: state.positions.clone() });
+    }
+    messages
+}
+
+#[derive(Clone)]
+struct DashboardState {
+    event_log: Arc<EventLog>,
+    token: Option<Arc<str>>,
+}
+
+async fn send(socket: &mut WebSocket, message: &PushMessage) -> Result<(), String> {
+    let text = serde_json::to_string(message).map_err(|e| format!("Failed to serialize dashboard message: {}", e))?;
+    socket.send(Message::Text(text.into())).await.map_err(|e| format!("Dashboard client disconnected: {}", e))
+}
+
+/// Streams the snapshot and then every appended record to one client until it disconnects.
+async fn stream_events(mut socket: WebSocket, event_log: Arc<EventLog>) -> Result<(), String> {
+    let mut records = event_log.subscribe(); // Before reading the log, so no record falls in between
+    let mut state = replay(&load_events(event_log.path())?, None);
+    send(&mut socket, &PushMessage::Snapshot { state: state.clone() }).await?;
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}, // Pings are answered by the socket itself
+                Some(Err(e)) => return Err(format!("Dashboard client failed: {}", e)),
+            },
+            record = records.recv() => match record {
+                Ok(record) if record.seq <= state.last_seq => {}, // Already in the snapshot
+                Ok(record) => {
+                    for message in push_messages(&mut state, record) {
+                        send(&mut socket, &message).await?;
+                    }
+                },
+                Err(RecvError::Lagged(missed)) => send(&mut socket, &PushMessage::Lagged { missed }).await?,
+                Err(RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
+/// Upgrades `GET /events` to a WebSocket.
+async fn events(
+    State(state): State<DashboardState>,
+    Query(query): Query<HashMap<String, String>>,
+    upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Response {
+    let authorized = state.token.as_ref()
+        .is_none_or(|token| query.get("token").is_some_and(|sent| constant_time_eq(sent.as_bytes(), token.as_bytes())));
+    if !authorized {
+        warn!("Rejected dashboard client without a valid token");
+        return (StatusCode::UNAUTHORIZED, "Invalid or missing token").into_response();
+    }
+    let upgrade = match upgrade {
+        Ok(upgrade) => upgrade,
+        Err(rejection) => return rejection.into_response(),
+    };
+    upgrade
+        .on_failed_upgrade(|e| warn!("Dashboard WebSocket upgrade failed: {}", e))
+        .on_upgrade(move |socket| async move {
+            info!("Dashboard client connected");
+            match stream_events(socket, state.event_log).await {
+                Ok(()) => info!("Dashboard client disconnected"),
+                Err(e) => debug!("{}", e),
+            }
+        })
+}
+
+/// Returns the dashboard stream of `event_log`'s records.
+pub fn router(event_log: Arc<EventLog>, token: Option<&str>) -> Router {
+    Router::new()
+        .route("/events", get(events))
+        .with_state(DashboardState { event_log, token: token.map(Arc::from) })
+}
+
+/// Serves the dashboard stream until `shutdown` resolves, calling `on_ready` once the listener is bound.
+pub async fn serve_dashboard_until(
+    event_log: Arc<EventLog>,
+    config: &DashboardConfig,
+    on_ready: impl FnOnce(),
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<(), String> {
+    if config.token.is_none() {
+        warn!("No dashboard token configured: any client reaching {} can follow the bot's orders", config.listen_addr);
+    }
+    let app = router(event_log, config.token.as_deref());
+    let listener = tokio::net::TcpListener::bind(&config.listen_addr).await
+        .map_err(|e| format!("Failed to bind the dashboard stream on {}: {}", config.listen_addr, e))?;
+    info!("Dashboard event stream on ws://{}/events", config.listen_addr);
+    on_ready();
+    axum::serve(listener, app).with_graceful_shutdown(shutdown).await
+        .map_err(|e| format!("Dashboard stream failed: {}", e))
+}
@@ -0,0 +1,352 @@
+// src/data/columnar.rs
+
+//! This module stores candles in Parquet files (`.parquet`) for datasets too large to load as CSV,
+//! e.g. years of 1m data for several symbols. Any Parquet reader (pandas, polars, DuckDB) opens
+//! them too.
+//!
+//! * Columnar: every field is a Parquet column (`open_time`, `close_time` and `trades` as u64, the
+//!   prices and volumes as f64, Snappy-compressed), so a chunk is decoded column by column and
+//!   indicators can run over `close` without building candles.
+//! * Chunked: each `CandleChunk` is a row group, and readers iterate row group by row group
+//!   (`ChunkReader`) with bounded memory instead of reading the whole file.
+//! * Memory-mapped: `ChunkReader` maps the file and decodes row groups straight from the mapping,
+//!   so the OS pages data in on demand and shares it between backtests of the same file.
+//!
+//! The symbol is stored in the schema's metadata (`symbol`). Files are written by a `ChunkWriter`
+//! to a temporary file renamed over the destination when finished, so a file being read (or
+//! mapped) is never modified. Parquet files cannot be appended to in place: `ChunkWriter::append`
+//! copies the existing row groups once, then the writer stays open for any number of new ones.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use memmap2::Mmap;
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float64Type, UInt64Type};
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use crate::market_data::Candlestick;
+use crate::market_event::{Candle, Symbol};
+
+/// File extension of columnar candle files.
+pub const EXTENSION: &str = "parquet";
+
+/// Candles per chunk (row group) when writing a whole dataset.
+pub const DEFAULT_CHUNK_ROWS: usize = 10_000;
+
+/// Schema metadata key of the candles' symbol.
+const SYMBOL_KEY: &str = "symbol";
+const U64_COLUMNS: [&str; 3] = ["open_time", "close_time", "trades"];
+const F64_COLUMNS: [&str; 6] = ["open", "high", "low", "close", "volume", "quote_volume"];
+
+/// Returns true when `path` names a columnar candle file.
+pub fn is_columnar(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|ext| ext == EXTENSION)
+}
+
+/// Returns the schema of the candle files of `symbol`.
+pub fn schema(symbol: Symbol) -> SchemaRef {
+    let fields = [
+        Field::new("open_time", DataType::UInt64, false),
+        Field::new("close_time", DataType::UInt64, false),
+    ].into_iter()
+        .chain(F64_COLUMNS.iter().map(|name| Field::new(*name, DataType::Float64, false)))
+        .chain([Field::new("trades", DataType::UInt64, false)])
+        .collect::<Vec<_>>();
+    let metadata = HashMap::from([(SYMBOL_KEY.to_string(), symbol.as_str().to_string())]);
+    Arc::new(Schema::new_with_metadata(fields, metadata))
+}
+
+/// A chunk of candles stored column by column.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CandleChunk {
+    pub open_time: Vec<u64>,
+    pub close_time: Vec<u64>,
+    pub open: Vec<f64>,
+    pub high: Vec<f64>,
+    pub low: Vec<f64>,
+    pub close: Vec<f64>,
+    pub volume: Vec<f64>,
+    pub quote_volume: Vec<f64>,
+    pub trades: Vec<u64>,
+}
+
+impl CandleChunk {
+    /// Number of candles in the chunk.
+    pub fn len(&self) -> usize {
+        self.open_time.len()
+    }
+
+    /// Returns true when the chunk holds no candles.
+    pub fn is_empty(&self) -> bool {
+        self.open_time.is_empty()
+    }
+
+    /// Appends a candle.
+    pub fn push(&mut self, candle: &Candle) {
+        self.open_time.push(candle.open_time);
+        self.close_time.push(candle.close_time);
+        self.open.push(candle.open);
+        self.high.push(candle.high);
+        self.low.push(candle.low);
+        self.close.push(candle.close);
+        self.volume.push(candle.volume);
+        self.quote_volume.push(candle.quote_volume);
+        self.trades.push(candle.trades);
+    }
+
+    /// Builds a chunk from REST klines.
+    pub fn from_klines(klines: &[&Candlestick]) -> Result<Self, String> {
+        let mut chunk = Self::default();
+        let number = |value: &str| value.parse::<f64>().map_err(|e| format!("Invalid kline value '{}': {}", value, e));
+        for Candlestick::Array(open_time, open, high, low, close, volume, close_time, quote_volume, trades, ..) in klines {
+            chunk.open_time.push(*open_time);
+            chunk.close_time.push(*close_time);
+            chunk.open.push(number(open)?);
+            chunk.high.push(number(high)?);
+            chunk.low.push(number(low)?);
+            chunk.close.push(number(close)?);
+            chunk.volume.push(number(volume)?);
+            chunk.quote_volume.push(number(quote_volume)?);
+            chunk.trades.push(*trades);
+        }
+        Ok(chunk)
+    }
+
+    /// Returns the candle at `index` as a closed market candle.
+    pub fn candle(&self, index: usize, symbol: Symbol) -> Candle {
+        let (open_time, close_time) = (self.open_time[index], self.close_time[index]);
+        Candle {
+            symbol,
+            event_time: close_time,
+            interval_ms: close_time.saturating_sub(open_time) + 1,
+            open_time,
+            close_time,
+            open: self.open[index],
+            high: self.high[index],
+            low: self.low[index],
+            close: self.close[index],
+            volume: self.volume[index],
+            quote_volume: self.quote_volume[index],
+            trades: self.trades[index],
+            is_closed: true,
+        }
+    }
+
+    /// Returns all candles of the chunk.
+    pub fn to_candles(&self, symbol: Symbol) -> Vec<Candle> {
+        (0..self.len()).map(|i| self.candle(i, symbol)).collect()
+    }
+
+    /// Appends the candles of another chunk.
+    pub fn extend(&mut self, other: &CandleChunk) {
+        self.open_time.extend_from_slice(&other.open_time);
+        self.close_time.extend_from_slice(&other.close_time);
+        self.open.extend_from_slice(&other.open);
+        self.high.extend_from_slice(&other.high);
+        self.low.extend_from_slice(&other.low);
+        self.close.extend_from_slice(&other.close);
+        self.volume.extend_from_slice(&other.volume);
+        self.quote_volume.extend_from_slice(&other.quote_volume);
+        self.trades.extend_from_slice(&other.trades);
+    }
+
+    /// Converts the chunk into a record batch of `schema`.
+    fn to_batch(&self, schema: SchemaRef) -> Result<RecordBatch, String> {
+        let u64s = |values: &Vec<u64>| Arc::new(UInt64Array::from(values.clone())) as ArrayRef;
+        let f64s = |values: &Vec<f64>| Arc::new(Float64Array::from(values.clone())) as ArrayRef;
+        let columns = vec![
+            u64s(&self.open_time), u64s(&self.close_time),
+            f64s(&self.open), f64s(&self.high), f64s(&self.low), f64s(&self.close), f64s(&self.volume), f64s(&self.quote_volume),
+            u64s(&self.trades),
+        ];
+        RecordBatch::try_new(schema, columns).map_err(|e| format!("Invalid candle chunk: {}", e))
+    }
+
+    /// Appends the rows of a record batch read from a candle file.
+    fn extend_from_batch(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        let u64s = |name: &str| batch.column_by_name(name).and_then(|c| c.as_primitive_opt::<UInt64Type>())
+            .map(|c| c.values().to_vec())
+            .ok_or_else(|| format!("Missing u64 column '{}'", name));
+        let f64s = |name: &str| batch.column_by_name(name).and_then(|c| c.as_primitive_opt::<Float64Type>())
+            .map(|c| c.values().to_vec())
+            .ok_or_else(|| format!("Missing f64 column '{}'", name));
+        let [open_time, close_time, trades] = U64_COLUMNS.map(u64s);
+        let [open, high, low, close, volume, quote_volume] = F64_COLUMNS.map(f64s);
+        self.extend(&CandleChunk {
+            open_time: open_time?, close_time: close_time?,
+            open: open?, high: high?, low: low?, close: close?, volume: volume?, quote_volume: quote_volume?,
+            trades: trades?,
+        });
+        Ok(())
+    }
+}
+
+/// Writes candle chunks to a columnar file, one row group per chunk.
+///
+/// The chunks go to `<file>.tmp`, renamed over the file by `finish`; a writer dropped without
+/// finishing leaves the file as it was.
+pub struct ChunkWriter {
+    path: PathBuf,
+    temporary: PathBuf,
+    schema: SchemaRef,
+    writer: ArrowWriter<File>,
+}
+
+impl ChunkWriter {
+    /// Starts a new file of `symbol` candles, replacing any existing one when finished.
+    pub fn create(path: &Path, symbol: Symbol) -> Result<Self, String> {
+        let temporary = path.with_extension(format!("{}.tmp", EXTENSION));
+        let file = File::create(&temporary).map_err(|e| format!("Failed to write {}: {}", temporary.display(), e))?;
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let schema = self::schema(symbol);
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(properties)).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(Self { path: path.to_path_buf(), temporary, schema, writer })
+    }
+
+    /// Continues a file of `symbol` candles, creating it if needed. Its row groups are copied to
+    /// the new file once, so keep the writer open for all the chunks to append.
+    pub fn append(path: &Path, symbol: Symbol) -> Result<Self, String> {
+        let existing = match std::fs::metadata(path) {
+            Ok(metadata) if metadata.len() > 0 => Some(ChunkReader::open(path)?),
+            _ => None,
+        };
+        if let Some(existing) = existing.as_ref().filter(|existing| existing.symbol() != symbol) {
+            return Err(format!("{} holds {} candles, not {}", path.display(), existing.symbol(), symbol));
+        }
+        let mut writer = Self::create(path, symbol)?;
+        for old in existing.into_iter().flatten() {
+            writer.write(&old?)?;
+        }
+        Ok(writer)
+    }
+
+    /// Writes a chunk as a new row group.
+    pub fn write(&mut self, chunk: &CandleChunk) -> Result<(), String> {
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        let error = |e: parquet::errors::ParquetError| format!("Failed to write {}: {}", self.path.display(), e);
+        self.writer.write(&chunk.to_batch(self.schema.clone())?).map_err(error)?;
+        self.writer.flush().map_err(error) // Ends the row group
+    }
+
+    /// Writes the footer and moves the file into place.
+    pub fn finish(self) -> Result<(), String> {
+        self.writer.close().map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        std::fs::rename(&self.temporary, &self.path).map_err(|e| format!("Failed to replace {}: {}", self.path.display(), e))
+    }
+}
+
+/// Writes candles to a new columnar file (replacing any existing one), `chunk_rows` per chunk.
+pub fn write_candles(path: &Path, candles: &[Candle], chunk_rows: usize) -> Result<(), String> {
+    let symbol = candles.first().map(|c| c.symbol).ok_or("No candles to write")?;
+    let mut writer = ChunkWriter::create(path, symbol)?;
+    for rows in candles.chunks(chunk_rows.max(1)) {
+        let mut chunk = CandleChunk::default();
+        rows.iter().for_each(|c| chunk.push(c));
+        writer.write(&chunk)?;
+    }
+    writer.finish()
+}
+
+/// Iterates over the chunks (row groups) of a memory-mapped columnar file, decoding one chunk at
+/// a time.
+pub struct ChunkReader {
+    path: PathBuf,
+    symbol: Symbol,
+    data: Bytes, // The file's mapping
+    metadata: ArrowReaderMetadata,
+    next_row_group: usize,
+}
+
+impl ChunkReader {
+    /// Maps a columnar file and reads its footer.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        // SAFETY: candle files are only ever replaced by renaming a finished file over them
+        // (`ChunkWriter::finish`), never modified in place, so the mapped bytes cannot change.
+        let mapping = unsafe { Mmap::map(&file) }.map_err(|e| format!("Failed to map {}: {}", path.display(), e))?;
+        let data = Bytes::from_owner(mapping);
+        let metadata = ArrowReaderMetadata::load(&data, ArrowReaderOptions::default())
+            .map_err(|e| format!("{} is not a columnar candle file: {}", path.display(), e))?;
+        let symbol = metadata.schema().metadata().get(SYMBOL_KEY)
+            .ok_or_else(|| format!("{} is not a columnar candle file: no symbol", path.display()))?;
+        let symbol = Symbol::new(symbol)?;
+        Ok(Self { path: path.to_path_buf(), symbol, data, metadata, next_row_group: 0 })
+    }
+
+    /// Returns the symbol of the candles in the file.
+    pub fn symbol(&self) -> Symbol {
+        self.symbol
+    }
+
+    /// Number of candles in the file, from its footer.
+    pub fn rows(&self) -> usize {
+        self.metadata.metadata().file_metadata().num_rows().max(0) as usize
+    }
+
+    /// Returns the open time of the first candle and the close time of the last one, decoding only
+    /// the first and last chunks.
+    pub fn time_range(&self) -> Result<Option<(u64, u64)>, String> {
+        let Some(last) = self.metadata.metadata().num_row_groups().checked_sub(1) else {
+            return Ok(None);
+        };
+        let error = |e: String| format!("Failed to read {}: {}", self.path.display(), e);
+        let start = self.read_row_group(0).map_err(error)?.open_time.first().copied();
+        let end = self.read_row_group(last).map_err(error)?.close_time.last().copied();
+        Ok(start.zip(end))
+    }
+
+    /// Iterates over the candles of the file, decoding them chunk by chunk.
+    pub fn candles(self) -> impl Iterator<Item = Result<Candle, String>> {
+        let symbol = self.symbol;
+        self.flat_map(move |chunk| {
+            let candles = match chunk {
+                Ok(chunk) => chunk.to_candles(symbol).into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            };
+            candles.into_iter()
+        })
+    }
+
+    fn read_row_group(&self, index: usize) -> Result<CandleChunk, String> {
+        let rows = self.metadata.metadata().row_group(index).num_rows().max(1) as usize;
+        let reader = ParquetRecordBatchReaderBuilder::new_with_metadata(self.data.clone(), self.metadata.clone())
+            .with_row_groups(vec![index])
+            .with_batch_size(rows)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let mut chunk = CandleChunk::default();
+        for batch in reader {
+            chunk.extend_from_batch(&batch.map_err(|e| e.to_string())?)?;
+        }
+        Ok(chunk)
+    }
+}
+
+impl Iterator for ChunkReader {
+    type Item = Result<CandleChunk, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_row_group >= self.metadata.metadata().num_row_groups() {
+            return None;
+        }
+        let index = self.next_row_group;
+        self.next_row_group += 1;
+        Some(self.read_row_group(index).map_err(|e| format!("Failed to read {}: {}", self.path.display(), e)))
+    }
+}
+
+/// Loads all candles of a columnar file.
+pub fn read_candles(path: &Path) -> Result<Vec<Candle>, String> {
+    ChunkReader::open(path)?.candles().collect()
+}
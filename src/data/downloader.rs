@@ -0,0 +1,395 @@
+// src/data/downloader.rs
+
+//! This module downloads historical klines (and optionally aggregated trades) of a symbol over a
+//! date range from the Binance REST API into files the backtester can load.
+//!
+//! * Data is fetched in pages of 1000 rows; only closed candles are written.
+//! * Requests are paced by a `WeightLimiter` so a long download stays within the per-minute
+//!   request weight budget (klines pages weigh 5, aggTrades pages 20).
+//! * After every write the position is saved to a checkpoint file next to the output
+//!   (`<file>.checkpoint.json`), so an interrupted download resumes where it stopped, and running
+//!   it again later appends the data published since.
+//!
+//! Klines are written either as CSV with the headers of Binance's CSV export (epoch millisecond
+//! timestamps, appended page by page) or to a Parquet file (see `columnar`, one row group per
+//! `ROW_GROUP_ROWS` klines). A Parquet file is only readable once its footer is written, so one
+//! `ChunkWriter` stays open for the whole download and the checkpoint moves when it is finished;
+//! an interrupted Parquet download restarts from the previous run's end. Both formats are
+//! read directly by `strategy::load_candles` and the EMA backtest. Aggregated trades are written
+//! as CSV with the column names of Binance's public data dumps.
+
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use tracing::info;
+use serde::{Deserialize, Serialize};
+
+use super::columnar::{self, CandleChunk, ChunkWriter};
+use crate::market_data::{next_klines_page_start, AggTrade, Candlestick, KlineInterval};
+use crate::market_event::Symbol;
+use crate::rest_api::RestClient;
+
+/// Klines buffered into each row group of Parquet downloads.
+pub const ROW_GROUP_ROWS: usize = 100_000;
+
+/// Rows requested per page.
+pub const PAGE_SIZE: u16 = 1000;
+
+/// Request weight per minute the downloader allows itself, half of the futures limit of 2400 so
+/// a running bot sharing the IP keeps headroom.
+pub const DEFAULT_WEIGHT_PER_MINUTE: u32 = 1200;
+
+/// Request weight of an `/fapi/v1/aggTrades` page.
+pub const AGG_TRADES_WEIGHT: u32 = 20;
+
+/// Longest time window `/fapi/v1/aggTrades` accepts with `startTime`/`endTime`.
+const AGG_TRADES_WINDOW_MS: u64 = 3_600_000;
+
+/// Column headers of kline files, as in Binance's CSV export.
+pub const KLINE_CSV_HEADERS: [&str; 12] = [
+    "Open time", "Open", "High", "Low", "Close", "Volume", "Close time", "Quote asset volume",
+    "Number of trades", "Taker buy base asset volume", "Taker buy quote asset volume", "Ignore",
+];
+
+/// Column headers of aggregated trade files, as in Binance's public data dumps.
+pub const AGG_TRADE_CSV_HEADERS: [&str; 7] = [
+    "agg_trade_id", "price", "quantity", "first_trade_id", "last_trade_id", "transact_time", "is_buyer_maker",
+];
+
+/// Request weight of an `/fapi/v1/klines` page of `limit` rows.
+pub fn klines_weight(limit: u16) -> u32 {
+    match limit {
+        0..=99 => 1,
+        100..=499 => 2,
+        500..=1000 => 5,
+        _ => 10,
+    }
+}
+
+/// File format of downloaded data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Csv,
+    Parquet, // Columnar Parquet files, for large datasets
+}
+
+impl OutputFormat {
+    /// Parses "csv" or "parquet" ("columnar" is accepted as an alias).
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Ok(OutputFormat::Csv),
+            "parquet" | "columnar" => Ok(OutputFormat::Parquet),
+            _ => Err(format!("Invalid output format '{}': expected csv or parquet", value)),
+        }
+    }
+
+    /// Returns the file extension of kline files in the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "csv",
+            OutputFormat::Parquet => columnar::EXTENSION,
+        }
+    }
+}
+
+/// Paces requests so their total weight stays within a per-minute budget.
+#[derive(Debug, Clone)]
+pub struct WeightLimiter {
+    weight_per_minute: u32,
+    window_start: Option<Instant>,
+    used: u32, // Weight used in the current window
+}
+
+impl WeightLimiter {
+    /// Creates a limiter allowing `weight_per_minute` per one-minute window.
+    pub fn new(weight_per_minute: u32) -> Self {
+        Self { weight_per_minute: weight_per_minute.max(1), window_start: None, used: 0 }
+    }
+
+    /// Reserves `weight` for a request sent at `now` and returns how long to wait before sending it.
+    pub fn reserve(&mut self, weight: u32, now: Instant) -> Duration {
+        let start = *self.window_start.get_or_insert(now);
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed >= Duration::from_secs(60) {
+            self.window_start = Some(now);
+            self.used = 0;
+        } else if self.used > 0 && self.used + weight > self.weight_per_minute {
+            // The request opens the next window
+            let wait = Duration::from_secs(60) - elapsed;
+            self.window_start = Some(now + wait);
+            self.used = weight;
+            return wait;
+        }
+        self.used += weight;
+        Duration::ZERO
+    }
+
+    /// Waits until a request of `weight` fits in the budget.
+    pub async fn acquire(&mut self, weight: u32) {
+        let wait = self.reserve(weight, Instant::now());
+        if !wait.is_zero() {
+            info!("Request weight budget used up, waiting {:.1}s", wait.as_secs_f64());
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Position of a download, saved after every page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub next_start_ms: u64, // Next open time (klines) or trade time (aggTrades) to fetch
+    #[serde(default)]
+    pub next_from_id: Option<u64>, // Next aggregate trade ID, once the first trade has been found
+    pub rows: u64, // Rows written so far
+}
+
+impl Checkpoint {
+    /// Returns the checkpoint file of an output file.
+    pub fn path_for(output: &Path) -> PathBuf {
+        let mut path = output.as_os_str().to_owned();
+        path.push(".checkpoint.json");
+        PathBuf::from(path)
+    }
+
+    /// Loads a checkpoint, if the file exists.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map(Some)
+                .map_err(|e| format!("Invalid checkpoint {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read checkpoint {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Saves the checkpoint (written to a temporary file first, so it is never left half-written).
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| format!("Failed to serialize checkpoint: {}", e))?;
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, json)
+            .and_then(|_| fs::rename(&temporary, path))
+            .map_err(|e| format!("Failed to write checkpoint {}: {}", path.display(), e))
+    }
+}
+
+/// What to download and where.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadRequest {
+    pub symbol: String,
+    pub interval: KlineInterval,
+    pub start_ms: u64,
+    pub end_ms: u64, // Inclusive
+    pub agg_trades: bool, // Also download aggregated trades over the range
+    pub output_dir: PathBuf,
+    pub format: OutputFormat,
+    pub weight_per_minute: u32,
+}
+
+impl DownloadRequest {
+    /// Creates a request writing CSV files to `./data`.
+    pub fn new(symbol: &str, interval: KlineInterval, start_ms: u64, end_ms: u64) -> Self {
+        Self {
+            symbol: symbol.to_uppercase(),
+            interval,
+            start_ms,
+            end_ms,
+            agg_trades: false,
+            output_dir: PathBuf::from("data"),
+            format: OutputFormat::Csv,
+            weight_per_minute: DEFAULT_WEIGHT_PER_MINUTE,
+        }
+    }
+
+    /// Returns the kline file, e.g. `data/BTCUSDT-4h-1609459200000.csv`. The file is named after
+    /// the start of the range only, so later runs with a later end resume and extend it.
+    pub fn klines_path(&self) -> PathBuf {
+        self.output_dir.join(format!("{}-{}-{}.{}", self.symbol, self.interval.to_string(), self.start_ms, self.format.extension()))
+    }
+
+    /// Returns the aggregated trade file, e.g. `data/BTCUSDT-aggTrades-1609459200000.csv`.
+    pub fn agg_trades_path(&self) -> PathBuf {
+        self.output_dir.join(format!("{}-aggTrades-{}.csv", self.symbol, self.start_ms))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.start_ms > self.end_ms {
+            return Err(format!("The start ({}) must not be after the end ({}).", self.start_ms, self.end_ms));
+        }
+        Ok(())
+    }
+}
+
+/// Files written by a download and the rows added to them by this run.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DownloadReport {
+    pub klines_path: PathBuf,
+    pub klines_rows: u64,
+    pub agg_trades_path: Option<PathBuf>,
+    pub agg_trades_rows: u64,
+}
+
+/// Converts a kline into a CSV record.
+pub fn kline_record(kline: &Candlestick) -> Vec<String> {
+    let Candlestick::Array(open_time, open, high, low, close, volume, close_time, quote_volume, trades, taker_base, taker_quote, ignore) = kline;
+    vec![
+        open_time.to_string(), open.clone(), high.clone(), low.clone(), close.clone(), volume.clone(),
+        close_time.to_string(), quote_volume.clone(), trades.to_string(), taker_base.clone(), taker_quote.clone(), ignore.clone(),
+    ]
+}
+
+/// Converts an aggregated trade into a CSV record.
+pub fn agg_trade_record(trade: &AggTrade) -> Vec<String> {
+    vec![
+        trade.agg_trade_id.to_string(), trade.price.clone(), trade.quantity.clone(), trade.first_trade_id.to_string(),
+        trade.last_trade_id.to_string(), trade.transact_time.to_string(), trade.is_buyer_maker.to_string(),
+    ]
+}
+
+/// Appends records to a CSV file, writing the headers first when the file is new or empty.
+pub fn append_csv(path: &Path, headers: &[&str], records: &[Vec<String>]) -> Result<(), String> {
+    let is_new = fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+    let file = OpenOptions::new().create(true).append(true).open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut writer = csv::Writer::from_writer(file);
+    if is_new {
+        writer.write_record(headers).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    for record in records {
+        writer.write_record(record).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    writer.flush().map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Loads the checkpoint of `output`, starting over when there is none or the output is missing.
+fn resume(output: &Path, start_ms: u64) -> Result<Checkpoint, String> {
+    let fresh = Checkpoint { next_start_ms: start_ms, ..Default::default() };
+    if !output.exists() {
+        return Ok(fresh);
+    }
+    Ok(Checkpoint::load(&Checkpoint::path_for(output))?.unwrap_or(fresh))
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+/// Downloads the closed klines of the request's range, resuming from its checkpoint.
+pub async fn download_klines(client: &RestClient, request: &DownloadRequest, limiter: &mut WeightLimiter) -> Result<(PathBuf, u64), String> {
+    request.validate()?;
+    let path = request.klines_path();
+    let checkpoint_path = Checkpoint::path_for(&path);
+    let mut checkpoint = resume(&path, request.start_ms)?;
+    let symbol = Symbol::new(&request.symbol)?;
+    let mut rows = 0;
+    let mut pending = CandleChunk::default(); // Parquet klines not yet written
+    let mut parquet = match request.format {
+        OutputFormat::Parquet => Some(ChunkWriter::append(&path, symbol)?),
+        OutputFormat::Csv => None,
+    };
+    while checkpoint.next_start_ms <= request.end_ms {
+        limiter.acquire(klines_weight(PAGE_SIZE)).await;
+        let page = client.get_klines(&request.symbol, request.interval, Some(PAGE_SIZE), Some(checkpoint.next_start_ms), Some(request.end_ms)).await?;
+        let now = now_ms();
+        let closed: Vec<&Candlestick> = page.iter()
+            .take_while(|Candlestick::Array(_, _, _, _, _, _, close_time, ..)| *close_time < now)
+            .collect();
+        // A short page, the end of the range or a still-forming candle ends the download
+        let done = closed.len() < page.len() || next_klines_page_start(&page, PAGE_SIZE as usize, request.end_ms).is_none();
+        match parquet.as_mut() {
+            Some(writer) => {
+                pending.extend(&CandleChunk::from_klines(&closed)?);
+                if pending.len() >= ROW_GROUP_ROWS {
+                    writer.write(&std::mem::take(&mut pending))?;
+                }
+            },
+            None => {
+                let records: Vec<Vec<String>> = closed.iter().map(|k| kline_record(k)).collect();
+                append_csv(&path, &KLINE_CSV_HEADERS, &records)?;
+            },
+        }
+        rows += closed.len() as u64;
+        checkpoint.rows += closed.len() as u64;
+        if let Some(last) = closed.last() {
+            checkpoint.next_start_ms = last.open_time() + 1;
+        }
+        // The checkpoint only moves past klines in a readable file: every CSV page, the Parquet file once finished
+        if parquet.is_none() {
+            checkpoint.save(&checkpoint_path)?;
+            info!("{} {}: {} klines written to {}", request.symbol, request.interval.to_string(), checkpoint.rows, path.display());
+        }
+        if done {
+            break;
+        }
+    }
+    if let Some(mut writer) = parquet {
+        writer.write(&pending)?;
+        writer.finish()?;
+        checkpoint.save(&checkpoint_path)?;
+        info!("{} {}: {} klines written to {}", request.symbol, request.interval.to_string(), checkpoint.rows, path.display());
+    }
+    Ok((path, rows))
+}
+
+/// Downloads the aggregated trades of the request's range, resuming from its checkpoint.
+///
+/// The first trade is located with one-hour time windows; from there pages follow the aggregate
+/// trade IDs, which cannot skip or repeat trades.
+pub async fn download_agg_trades(client: &RestClient, request: &DownloadRequest, limiter: &mut WeightLimiter) -> Result<(PathBuf, u64), String> {
+    request.validate()?;
+    let path = request.agg_trades_path();
+    let checkpoint_path = Checkpoint::path_for(&path);
+    let mut checkpoint = resume(&path, request.start_ms)?;
+    let mut rows = 0;
+    loop {
+        let by_id = checkpoint.next_from_id.is_some();
+        if !by_id && checkpoint.next_start_ms > request.end_ms {
+            break;
+        }
+        limiter.acquire(AGG_TRADES_WEIGHT).await;
+        let page = match checkpoint.next_from_id {
+            Some(from_id) => client.get_agg_trades(&request.symbol, Some(from_id), None, None, Some(PAGE_SIZE)).await?,
+            None => {
+                let window_end = (checkpoint.next_start_ms + AGG_TRADES_WINDOW_MS - 1).min(request.end_ms);
+                let page = client.get_agg_trades(&request.symbol, None, Some(checkpoint.next_start_ms), Some(window_end), Some(PAGE_SIZE)).await?;
+                if page.is_empty() {
+                    checkpoint.next_start_ms = window_end + 1;
+                    checkpoint.save(&checkpoint_path)?;
+                    continue;
+                }
+                page
+            },
+        };
+        let in_range: Vec<&AggTrade> = page.iter().take_while(|t| t.transact_time <= request.end_ms).collect();
+        let records: Vec<Vec<String>> = in_range.iter().map(|t| agg_trade_record(t)).collect();
+        append_csv(&path, &AGG_TRADE_CSV_HEADERS, &records)?;
+        rows += records.len() as u64;
+        checkpoint.rows += records.len() as u64;
+        if let Some(last) = in_range.last() {
+            checkpoint.next_from_id = Some(last.agg_trade_id + 1);
+            checkpoint.next_start_ms = last.transact_time;
+        }
+        checkpoint.save(&checkpoint_path)?;
+        info!("{}: {} aggregated trades written to {}", request.symbol, checkpoint.rows, path.display());
+        if in_range.len() < page.len() || (by_id && page.len() < PAGE_SIZE as usize) {
+            break;
+        }
+    }
+    Ok((path, rows))
+}
+
+/// Downloads everything the request asks for.
+pub async fn download(client: &RestClient, request: &DownloadRequest) -> Result<DownloadReport, String> {
+    fs::create_dir_all(&request.output_dir)
+        .map_err(|e| format!("Failed to create {}: {}", request.output_dir.display(), e))?;
+    let mut limiter = WeightLimiter::new(request.weight_per_minute);
+    let (klines_path, klines_rows) = download_klines(client, request, &mut limiter).await?;
+    let mut report = DownloadReport { klines_path, klines_rows, ..Default::default() };
+    if request.agg_trades {
+        let (path, rows) = download_agg_trades(client, request, &mut limiter).await?;
+        report.agg_trades_path = Some(path);
+        report.agg_trades_rows = rows;
+    }
+    Ok(report)
+}
@@ -0,0 +1,6 @@
+// src/data/mod.rs
+
+//! This module groups the tools that manage historical market data on disk for the backtester.
+
+pub mod downloader;
+pub mod columnar;
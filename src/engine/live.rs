@@ -0,0 +1,166 @@
+// src/engine/live.rs
+
+//! This module executes engine orders on Binance USDⓈ-M Futures. `LiveExecution` is the
+//! synchronous half: it turns orders into `BracketAction`s (new orders and cancels) and
+//! `ORDER_TRADE_UPDATE` executions of its orders back into `FillEvent`s. `spawn_live_engine` runs
+//! an engine in a task that feeds it market events and user data stream messages, and executes
+//! the queued actions through the `WebSocketClient`, like the bracket service does.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use tracing::{error, info};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use super::{Engine, EventStrategy, ExecutionHandler, FillEvent, OrderKind, OrderRequest};
+use crate::market_event::{MarketEvent, Symbol};
+use crate::order::bracket::{execute_actions, order_update_from_message, BracketAction};
+use crate::order::{round_down_to_step, ExecutionType, NewOrderRequest, OrderSide, OrderStatus, OrderType, TimeInForce};
+use crate::streams::FuturesOrderUpdate;
+use crate::websocket::WebSocketClient;
+use crate::websocket_stream::BinanceWsMessage;
+
+/// Converts an engine order into an exchange order, rounding the quantity down to `step_size`.
+pub fn to_order_request(order: &OrderRequest, step_size: Option<f64>) -> Result<NewOrderRequest, String> {
+    let quantity = match step_size {
+        Some(step) => round_down_to_step(order.quantity, step),
+        None => order.quantity,
+    };
+    if quantity <= 0.0 {
+        return Err(format!("Quantity {} of {} rounds down to zero", order.quantity, order.client_id));
+    }
+    let request = match order.kind {
+        OrderKind::Market => NewOrderRequest::new(order.symbol.as_str(), order.side, OrderType::Market),
+        OrderKind::Limit(price) => NewOrderRequest::new(order.symbol.as_str(), order.side, OrderType::Limit)
+            .price(price)
+            .time_in_force(TimeInForce::Gtc),
+        OrderKind::StopMarket(stop) => NewOrderRequest::new(order.symbol.as_str(), order.side, OrderType::StopMarket).stop_price(stop),
+    };
+    Ok(request.quantity(quantity).reduce_only(order.reduce_only).new_client_order_id(&order.client_id))
+}
+
+/// Converts a trade execution from the user data stream into a fill. Commissions are assumed to be
+/// charged in the quote asset.
+pub fn fill_from_update(update: &FuturesOrderUpdate) -> Option<FillEvent> {
+    if update.execution_type != ExecutionType::Trade {
+        return None;
+    }
+    let side = match update.side.as_str() {
+        "BUY" => OrderSide::Buy,
+        "SELL" => OrderSide::Sell,
+        _ => return None,
+    };
+    Some(FillEvent {
+        client_id: update.client_order_id.clone(),
+        symbol: Symbol::new(&update.symbol).ok()?,
+        time_ms: update.trade_time,
+        side,
+        price: update.last_filled_price.parse().ok()?,
+        quantity: update.last_filled_quantity.parse().ok()?,
+        fee: update.commission.as_deref().and_then(|c| c.parse().ok()).unwrap_or(0.0),
+    })
+}
+
+/// Queues engine orders for the exchange and collects their fills.
+#[derive(Debug, Clone, Default)]
+pub struct LiveExecution {
+    step_sizes: HashMap<Symbol, f64>,
+    client_ids: HashSet<String>, // Orders placed by the engine; other orders' updates are ignored
+    actions: Vec<BracketAction>,
+    fills: Vec<FillEvent>,
+}
+
+impl LiveExecution {
+    /// Creates an executor that sends quantities unrounded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rounds the quantities of `symbol` down to the exchange's step size.
+    pub fn with_step_size(mut self, symbol: Symbol, step_size: f64) -> Self {
+        self.step_sizes.insert(symbol, step_size);
+        self
+    }
+
+    /// Returns the orders and cancels to send to the exchange, in order.
+    pub fn take_actions(&mut self) -> Vec<BracketAction> {
+        std::mem::take(&mut self.actions)
+    }
+
+    /// Records the fill of an engine order reported on the user data stream.
+    pub fn on_order_update(&mut self, update: &FuturesOrderUpdate) {
+        if !self.client_ids.contains(&update.client_order_id) {
+            return;
+        }
+        if let Some(fill) = fill_from_update(update) {
+            self.fills.push(fill);
+        }
+        if matches!(update.order_status, OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Expired | OrderStatus::ExpiredInMatch) {
+            self.client_ids.remove(&update.client_order_id);
+        }
+    }
+}
+
+impl ExecutionHandler for LiveExecution {
+    fn submit(&mut self, order: OrderRequest, _time_ms: u64) -> Result<(), String> {
+        let request = to_order_request(&order, self.step_sizes.get(&order.symbol).copied())?;
+        request.validate()?;
+        self.client_ids.insert(order.client_id);
+        self.actions.push(BracketAction::PlaceOrder(request));
+        Ok(())
+    }
+
+    fn cancel(&mut self, symbol: Symbol, client_id: &str) -> Result<(), String> {
+        if self.client_ids.contains(client_id) {
+            self.actions.push(BracketAction::CancelOrder { symbol: symbol.to_string(), client_order_id: client_id.to_string() });
+        }
+        Ok(())
+    }
+
+    fn drain_fills(&mut self) -> Vec<FillEvent> {
+        std::mem::take(&mut self.fills)
+    }
+}
+
+/// Spawns a task running the engine on live market events, placing its orders through
+/// `ws_client`. The task ends when either channel closes and returns the engine.
+///
+/// # Arguments
+/// * `engine` - The strategy, risk policies and portfolio to run.
+/// * `ws_client` - Used to place and cancel orders.
+/// * `market_events` - Market events of the traded symbols.
+/// * `user_stream_receiver` - Messages from the USDⓈ-M user data stream (must carry `ORDER_TRADE_UPDATE` events).
+pub fn spawn_live_engine<S: EventStrategy + Send + 'static>(
+    mut engine: Engine<S, LiveExecution>,
+    ws_client: Arc<WebSocketClient>,
+    mut market_events: mpsc::Receiver<MarketEvent>,
+    mut user_stream_receiver: mpsc::Receiver<BinanceWsMessage>,
+) -> JoinHandle<Engine<S, LiveExecution>> {
+    tokio::spawn(async move {
+        info!("Live engine started for strategy {}", engine.strategy.name());
+        loop {
+            tokio::select! {
+                event = market_events.recv() => {
+                    let Some(event) = event else { break };
+                    engine.on_market_event(&event);
+                },
+                message = user_stream_receiver.recv() => {
+                    let Some(message) = message else { break };
+                    if let Some(update) = order_update_from_message(&message) {
+                        engine.execution.on_order_update(&update);
+                        for fill in engine.poll() {
+                            info!("Engine fill: {} {:?} {} @ {}", fill.client_id, fill.side, fill.quantity, fill.price);
+                        }
+                    }
+                }
+            }
+            let actions = engine.execution.take_actions();
+            if let Err(e) = execute_actions(&ws_client, actions).await {
+                error!("Failed to execute engine orders: {}", e);
+            }
+        }
+        info!("Live engine stopped for strategy {}", engine.strategy.name());
+        engine
+    })
+}
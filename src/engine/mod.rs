@@ -0,0 +1,417 @@
+// src/engine/mod.rs
+
+//! This module is an event-driven trading engine that runs the same strategy and risk code in
+//! backtests, paper trading and live trading, so a strategy cannot behave differently live than
+//! it did in its backtest. Every market event flows through the same pipeline:
+//!
+//! `MarketEvent -> EventStrategy -> OrderEvent -> risk policies -> ExecutionHandler -> FillEvent -> Portfolio`
+//!
+//! Only the `ExecutionHandler` differs between modes: `SimulatedExecution` matches orders against
+//! the market events themselves (backtests replay historical candles, paper trading feeds live
+//! ones), while `LiveExecution` sends them to Binance and turns the user data stream's
+//! `ORDER_TRADE_UPDATE` events back into fills (see `live`).
+//!
+//! Orders that open or increase exposure are checked by the `risk::RiskPolicy`s configured on the
+//! engine, with the day's realized PnL tracked in a `RiskState` fed by the fills, exactly as the
+//! webhook executor does.
+//!
+//! Strategies see the predicted funding rate of the event's symbol in their `StrategyContext`, e.g.
+//! to skip longs while funding is extremely positive. It is taken from the `markPrice` events, and
+//! can be seeded from the premium index (`RestClient::get_premium_index`) with `Engine::set_funding`.
+//!
+//! Once a symbol's mark price is known, its position is valued at the mark price rather than the
+//! last trade price, as Binance margins (and liquidates) it, so the equity strategies and risk
+//! policies see matches the exchange's. Subscribe to `<symbol>@markPrice` for it
+//! (`MarketStreamClient::subscribe_mark_price`).
+
+pub mod simulated;
+pub mod live;
+
+use std::collections::HashMap;
+
+use tracing::warn;
+
+use crate::market_event::{Candle, MarketEvent, Symbol};
+use crate::metrics::{self, MetricsConfig, RiskMetrics};
+use crate::order::OrderSide;
+use crate::risk::{self, RiskContext, RiskPolicy, RiskState};
+
+pub use live::{spawn_live_engine, LiveExecution};
+pub use simulated::{spawn_paper_engine, SimulatedExecution};
+
+/// How an order is executed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderKind {
+    Market,
+    Limit(f64), // Fills at this price or better
+    StopMarket(f64), // Becomes a market order once the price trades through this trigger
+}
+
+/// An order emitted by a strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRequest {
+    pub client_id: String, // Unique per order; fills and cancels refer to it
+    pub symbol: Symbol,
+    pub side: OrderSide,
+    pub kind: OrderKind,
+    pub quantity: f64, // In the base asset
+    pub reduce_only: bool, // Only reduces the position; never opens or flips one
+}
+
+/// What a strategy asks the execution to do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderEvent {
+    Place(OrderRequest),
+    Cancel { symbol: Symbol, client_id: String },
+}
+
+/// An execution of (part of) an order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillEvent {
+    pub client_id: String,
+    pub symbol: Symbol,
+    pub time_ms: u64,
+    pub side: OrderSide,
+    pub price: f64,
+    pub quantity: f64,
+    pub fee: f64, // In the quote asset
+}
+
+/// What a strategy knows about its account when it is called.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StrategyContext {
+    pub time_ms: u64,
+    pub position: f64, // Signed position in the event's symbol (negative when short)
+    pub equity: f64, // Mark-to-market account equity in the quote asset
+    pub funding_rate: Option<f64>, // Predicted funding rate of the event's symbol, once known
+    pub next_funding_ms: Option<u64>, // Time of the next funding settlement of the event's symbol
+}
+
+/// A strategy driven by market events. The same implementation runs in every engine mode.
+pub trait EventStrategy {
+    /// Short name used in reports.
+    fn name(&self) -> &str;
+    /// Processes a market event and returns the orders to place or cancel.
+    fn on_market_event(&mut self, event: &MarketEvent, ctx: &StrategyContext) -> Vec<OrderEvent>;
+    /// Processes a fill of one of the strategy's orders, after the portfolio has been updated.
+    fn on_fill(&mut self, _fill: &FillEvent, _ctx: &StrategyContext) -> Vec<OrderEvent> {
+        vec![]
+    }
+}
+
+/// Executes orders and reports their fills.
+pub trait ExecutionHandler {
+    /// Accepts an order. Fills, immediate or not, are reported by `drain_fills`.
+    fn submit(&mut self, order: OrderRequest, time_ms: u64) -> Result<(), String>;
+    /// Cancels a working order.
+    fn cancel(&mut self, symbol: Symbol, client_id: &str) -> Result<(), String>;
+    /// Sees every market event before the strategy does, e.g. to match resting orders.
+    fn on_market_event(&mut self, _event: &MarketEvent) {}
+    /// Returns the fills reported since the last call.
+    fn drain_fills(&mut self) -> Vec<FillEvent>;
+}
+
+/// Returns the price carried by a market event: the trade price, the candle close, the mid of the
+/// best bid and ask or the mark price. Depth updates, liquidations and pair klines (continuous,
+/// index or mark price candles) carry none.
+pub fn event_price(event: &MarketEvent) -> Option<f64> {
+    match event {
+        MarketEvent::Trade(t) | MarketEvent::AggTrade(t) => Some(t.price),
+        MarketEvent::Kline(c) => Some(c.close),
+        MarketEvent::BookTicker(b) => Some((b.bid_price + b.ask_price) / 2.0),
+        MarketEvent::MarkPrice(m) => Some(m.mark_price),
+        MarketEvent::Depth(_) | MarketEvent::Liquidation(_) | MarketEvent::PriceKline(_) => None,
+    }
+}
+
+/// A net position in one symbol.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Position {
+    pub quantity: f64, // Signed: positive when long
+    pub entry_price: f64, // Average entry price
+}
+
+/// Cash, positions and marks of the account, updated from fills.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Portfolio {
+    pub cash: f64, // Starting equity plus realized PnL, net of fees
+    pub positions: HashMap<Symbol, Position>,
+    pub marks: HashMap<Symbol, f64>, // Last price of each symbol
+    pub mark_prices: HashMap<Symbol, f64>, // Mark price of each symbol, from the `markPrice` stream
+}
+
+impl Portfolio {
+    /// Creates a flat portfolio.
+    pub fn new(starting_equity: f64) -> Self {
+        Self { cash: starting_equity, ..Default::default() }
+    }
+
+    /// Returns the signed position in `symbol`.
+    pub fn position(&self, symbol: Symbol) -> f64 {
+        self.positions.get(&symbol).map(|p| p.quantity).unwrap_or(0.0)
+    }
+
+    /// Records the last price of a symbol.
+    pub fn mark(&mut self, symbol: Symbol, price: f64) {
+        self.marks.insert(symbol, price);
+    }
+
+    /// Records the mark price of a symbol. Once known, positions are valued at the mark price
+    /// rather than the last price, as Binance margins them.
+    pub fn set_mark_price(&mut self, symbol: Symbol, mark_price: f64) {
+        self.mark_prices.insert(symbol, mark_price);
+    }
+
+    /// Returns the price a symbol's position is valued at: its mark price, else its last price.
+    pub fn valuation_price(&self, symbol: Symbol) -> Option<f64> {
+        self.mark_prices.get(&symbol).or_else(|| self.marks.get(&symbol)).copied()
+    }
+
+    /// Returns the absolute notional of a symbol's position at its valuation price.
+    pub fn exposure(&self, symbol: Symbol) -> f64 {
+        let position = self.positions.get(&symbol).copied().unwrap_or_default();
+        let price = self.valuation_price(symbol).unwrap_or(position.entry_price);
+        position.quantity.abs() * price
+    }
+
+    /// Returns the absolute notional of all positions.
+    pub fn total_exposure(&self) -> f64 {
+        self.positions.keys().map(|&symbol| self.exposure(symbol)).sum()
+    }
+
+    /// Returns the unrealized PnL of all positions at their valuation prices.
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.positions.iter().map(|(&symbol, position)| {
+            let price = self.valuation_price(symbol).unwrap_or(position.entry_price);
+            (price - position.entry_price) * position.quantity
+        }).sum()
+    }
+
+    /// Returns cash plus the unrealized PnL of all positions.
+    pub fn equity(&self) -> f64 {
+        self.cash + self.unrealized_pnl()
+    }
+
+    /// Applies a fill and returns the PnL it realized, before fees.
+    pub fn apply(&mut self, fill: &FillEvent) -> f64 {
+        let signed = match fill.side {
+            OrderSide::Buy => fill.quantity,
+            OrderSide::Sell => -fill.quantity,
+        };
+        let position = self.positions.entry(fill.symbol).or_default();
+        let mut realized = 0.0;
+        if position.quantity == 0.0 || position.quantity.signum() == signed.signum() {
+            // Opening or adding: average the entry price
+            let quantity = position.quantity + signed;
+            position.entry_price = (position.entry_price * position.quantity.abs() + fill.price * fill.quantity) / quantity.abs();
+            position.quantity = quantity;
+        } else {
+            let closed = signed.abs().min(position.quantity.abs());
+            realized = (fill.price - position.entry_price) * closed * position.quantity.signum();
+            position.quantity += signed;
+            if position.quantity.abs() < 1e-12 {
+                *position = Position::default();
+            } else if position.quantity.signum() == signed.signum() {
+                // Flipped: the remainder opens at the fill price
+                position.entry_price = fill.price;
+            }
+        }
+        if position.quantity == 0.0 {
+            self.positions.remove(&fill.symbol);
+        }
+        self.cash += realized - fill.fee;
+        self.mark(fill.symbol, fill.price);
+        realized
+    }
+}
+
+/// An order the risk policies or the execution refused.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    pub time_ms: u64,
+    pub order: OrderRequest,
+    pub reason: String,
+}
+
+/// Runs a strategy against an execution handler.
+pub struct Engine<S: EventStrategy, E: ExecutionHandler> {
+    pub strategy: S,
+    pub execution: E,
+    pub portfolio: Portfolio,
+    pub risk_policies: Vec<Box<dyn RiskPolicy>>,
+    pub risk_state: RiskState,
+    pub fills: Vec<FillEvent>, // Every fill so far
+    pub rejections: Vec<Rejection>,
+    funding: HashMap<Symbol, (f64, u64)>, // Predicted funding rate and next funding time
+    time_ms: u64,
+}
+
+/// Bounds the strategy/fill feedback loop of one event, in case a strategy keeps reacting to its own fills.
+const MAX_FILL_ROUNDS: usize = 16;
+
+impl<S: EventStrategy, E: ExecutionHandler> Engine<S, E> {
+    /// Creates an engine with a flat portfolio and no risk policies.
+    pub fn new(strategy: S, execution: E, starting_equity: f64) -> Self {
+        Self {
+            strategy,
+            execution,
+            portfolio: Portfolio::new(starting_equity),
+            risk_policies: Vec::new(),
+            risk_state: RiskState::default(),
+            fills: Vec::new(),
+            rejections: Vec::new(),
+            funding: HashMap::new(),
+            time_ms: 0,
+        }
+    }
+
+    /// Checks orders that open or increase exposure against these policies.
+    pub fn with_risk_policies(mut self, policies: Vec<Box<dyn RiskPolicy>>) -> Self {
+        self.risk_policies = policies;
+        self
+    }
+
+    /// Records the predicted funding rate of a symbol and its next funding time (milliseconds).
+    pub fn set_funding(&mut self, symbol: Symbol, funding_rate: f64, next_funding_ms: u64) {
+        self.funding.insert(symbol, (funding_rate, next_funding_ms));
+    }
+
+    fn context(&self, symbol: Symbol) -> StrategyContext {
+        let funding = self.funding.get(&symbol);
+        StrategyContext {
+            time_ms: self.time_ms,
+            position: self.portfolio.position(symbol),
+            equity: self.portfolio.equity(),
+            funding_rate: funding.map(|&(rate, _)| rate),
+            next_funding_ms: funding.map(|&(_, next)| next),
+        }
+    }
+
+    /// Processes a market event: resting orders are matched first, then the strategy sees the
+    /// event and its orders are risk-checked and executed. Returns the fills it caused.
+    pub fn on_market_event(&mut self, event: &MarketEvent) -> Vec<FillEvent> {
+        self.time_ms = self.time_ms.max(event.event_time());
+        match event {
+            MarketEvent::MarkPrice(mark) => {
+                self.portfolio.set_mark_price(mark.symbol, mark.mark_price);
+                self.set_funding(mark.symbol, mark.funding_rate, mark.next_funding_time);
+            },
+            _ => if let Some(price) = event_price(event) {
+                self.portfolio.mark(event.symbol(), price);
+            },
+        }
+        self.execution.on_market_event(event);
+        let mut fills = self.poll();
+        let orders = self.strategy.on_market_event(event, &self.context(event.symbol()));
+        self.submit(orders);
+        fills.extend(self.poll());
+        fills
+    }
+
+    /// Applies the fills the execution reported, letting the strategy react to each. Live
+    /// execution reports fills between market events, so the live service also calls this.
+    pub fn poll(&mut self) -> Vec<FillEvent> {
+        let mut applied = Vec::new();
+        for _ in 0..MAX_FILL_ROUNDS {
+            let fills = self.execution.drain_fills();
+            if fills.is_empty() {
+                break;
+            }
+            for fill in fills {
+                self.time_ms = self.time_ms.max(fill.time_ms);
+                let realized = self.portfolio.apply(&fill);
+                self.risk_state.on_fill(realized, fill.fee, fill.time_ms as i64);
+                let orders = self.strategy.on_fill(&fill, &self.context(fill.symbol));
+                self.fills.push(fill.clone());
+                applied.push(fill);
+                self.submit(orders);
+            }
+        }
+        applied
+    }
+
+    /// Risk-checks and forwards orders to the execution.
+    pub fn submit(&mut self, orders: Vec<OrderEvent>) {
+        for order in orders {
+            let result = match order {
+                OrderEvent::Place(request) => match self.check_risk(&request) {
+                    Ok(()) => self.execution.submit(request.clone(), self.time_ms).map_err(|reason| (request, reason)),
+                    Err(reason) => Err((request, reason)),
+                },
+                OrderEvent::Cancel { symbol, client_id } => {
+                    if let Err(e) = self.execution.cancel(symbol, &client_id) {
+                        warn!("Failed to cancel {}: {}", client_id, e);
+                    }
+                    Ok(())
+                },
+            };
+            if let Err((order, reason)) = result {
+                warn!("Order {} rejected: {}", order.client_id, reason);
+                self.rejections.push(Rejection { time_ms: self.time_ms, order, reason });
+            }
+        }
+    }
+
+    /// Runs the risk policies on orders that open or increase exposure.
+    fn check_risk(&self, order: &OrderRequest) -> Result<(), String> {
+        let position = self.portfolio.position(order.symbol);
+        let increases = match order.side {
+            OrderSide::Buy => position >= 0.0,
+            OrderSide::Sell => position <= 0.0,
+        };
+        if order.reduce_only || !increases || self.risk_policies.is_empty() {
+            return Ok(());
+        }
+        let price = match order.kind {
+            OrderKind::Limit(price) | OrderKind::StopMarket(price) => price,
+            OrderKind::Market => self.portfolio.marks.get(&order.symbol).or(self.portfolio.mark_prices.get(&order.symbol)).copied().unwrap_or(0.0),
+        };
+        let now_ms = self.time_ms as i64;
+        let ctx = RiskContext {
+            symbol: order.symbol.to_string(),
+            now_ms,
+            order_notional: order.quantity * price,
+            total_exposure: self.portfolio.total_exposure(),
+            symbol_exposure: self.portfolio.exposure(order.symbol),
+            quote_exposure: self.portfolio.positions.keys()
+                .filter(|symbol| risk::quote_asset(symbol.as_str()) == risk::quote_asset(order.symbol.as_str()))
+                .map(|&symbol| self.portfolio.exposure(symbol))
+                .sum(),
+            open_positions: self.portfolio.positions.values().filter(|p| p.quantity != 0.0).count(),
+            equity: self.portfolio.equity(),
+            realized_pnl_today: self.risk_state.realized_pnl_on(now_ms),
+            last_loss_ms: self.risk_state.last_loss_ms,
+        };
+        risk::check_all(&self.risk_policies, &ctx)
+    }
+}
+
+/// Result of running an engine over historical events.
+#[derive(Debug, Clone, Default)]
+pub struct EngineResult {
+    pub fills: Vec<FillEvent>,
+    pub rejections: Vec<Rejection>,
+    pub equity_curve: Vec<f64>, // Mark-to-market equity at every closed candle
+    pub final_equity: f64,
+    pub risk_metrics: RiskMetrics,
+}
+
+/// Replays historical events through the engine, sampling equity at every closed candle.
+pub fn backtest<S: EventStrategy, E: ExecutionHandler>(engine: &mut Engine<S, E>, events: impl Iterator<Item = MarketEvent>, metrics_config: &MetricsConfig) -> EngineResult {
+    let mut equity_curve = Vec::new();
+    let mut closes = Vec::new();
+    for event in events {
+        engine.on_market_event(&event);
+        if let MarketEvent::Kline(candle @ Candle { is_closed: true, .. }) = &event {
+            equity_curve.push(engine.portfolio.equity());
+            closes.push(candle.close);
+        }
+    }
+    EngineResult {
+        fills: engine.fills.clone(),
+        rejections: engine.rejections.clone(),
+        final_equity: engine.portfolio.equity(),
+        risk_metrics: metrics::compute_risk_metrics(&equity_curve, Some(&closes), metrics_config),
+        equity_curve,
+    }
+}
@@ -0,0 +1,185 @@
+// src/engine/simulated.rs
+
+//! This module simulates order execution against market events, for backtests (historical
+//! candles) and paper trading (live events). Market orders fill at the last price, adjusted by
+//! the slippage. Resting orders are matched against each event's price range (a candle's low and
+//! high, or the price of other events): stops trigger before limits, so a candle that touches
+//! both the stop and the target is counted as a loss. Limits fill at their order price, stops at
+//! their trigger price, or at the best price of the event when it gapped through the trigger (e.g.
+//! an aggregated trade below a sell stop). Reduce-only orders are trimmed to the open position and
+//! dropped once a fill has closed it.
+
+use std::collections::HashMap;
+
+use tracing::info;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use super::{event_price, Engine, EventStrategy, ExecutionHandler, FillEvent, OrderKind, OrderRequest};
+use crate::market_event::{MarketEvent, Symbol};
+use crate::order::OrderSide;
+
+/// Matches orders against market events.
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedExecution {
+    pub fee_rate: f64, // Commission per fill as a fraction of the notional
+    pub slippage: f64, // Market order price penalty as a fraction of the price, e.g. 0.0005
+    resting: Vec<OrderRequest>, // Limit and stop orders, in submission order
+    positions: HashMap<Symbol, f64>, // Signed positions, to enforce reduce-only
+    last_prices: HashMap<Symbol, f64>,
+    time_ms: u64,
+    fills: Vec<FillEvent>,
+}
+
+impl SimulatedExecution {
+    /// Creates a simulator charging `fee_rate` per fill, without slippage.
+    pub fn new(fee_rate: f64) -> Self {
+        Self { fee_rate, ..Default::default() }
+    }
+
+    /// Returns the working limit and stop orders.
+    pub fn resting_orders(&self) -> &[OrderRequest] {
+        &self.resting
+    }
+
+    /// Returns the quantity an order can fill: reduce-only orders are capped by the position.
+    fn fillable(&self, order: &OrderRequest) -> f64 {
+        if !order.reduce_only {
+            return order.quantity;
+        }
+        let position = self.positions.get(&order.symbol).copied().unwrap_or(0.0);
+        let reduces = match order.side {
+            OrderSide::Buy => position < 0.0,
+            OrderSide::Sell => position > 0.0,
+        };
+        if reduces { order.quantity.min(position.abs()) } else { 0.0 }
+    }
+
+    fn fill(&mut self, order: &OrderRequest, price: f64, quantity: f64) {
+        let signed = match order.side {
+            OrderSide::Buy => quantity,
+            OrderSide::Sell => -quantity,
+        };
+        *self.positions.entry(order.symbol).or_default() += signed;
+        self.fills.push(FillEvent {
+            client_id: order.client_id.clone(),
+            symbol: order.symbol,
+            time_ms: self.time_ms,
+            side: order.side,
+            price,
+            quantity,
+            fee: price * quantity * self.fee_rate,
+        });
+    }
+
+    /// Fills the resting orders of `symbol` whose price lies within `[low, high]`.
+    fn match_resting(&mut self, symbol: Symbol, low: f64, high: f64) {
+        let triggered = |order: &OrderRequest| match (order.kind, order.side) {
+            (OrderKind::StopMarket(stop), OrderSide::Sell) => low <= stop,
+            (OrderKind::StopMarket(stop), OrderSide::Buy) => high >= stop,
+            (OrderKind::Limit(limit), OrderSide::Sell) => high >= limit,
+            (OrderKind::Limit(limit), OrderSide::Buy) => low <= limit,
+            (OrderKind::Market, _) => true,
+        };
+        let is_stop = |order: &OrderRequest| matches!(order.kind, OrderKind::StopMarket(_));
+        // Stops first, then limits, each in submission order
+        let mut candidates: Vec<OrderRequest> = self.resting.iter().filter(|o| o.symbol == symbol && triggered(o) && is_stop(o)).cloned().collect();
+        candidates.extend(self.resting.iter().filter(|o| o.symbol == symbol && triggered(o) && !is_stop(o)).cloned());
+        let mut filled = false;
+        for order in candidates {
+            let quantity = self.fillable(&order);
+            if quantity > 0.0 {
+                let price = match (order.kind, order.side) {
+                    (OrderKind::Limit(price), _) => price,
+                    (OrderKind::StopMarket(stop), OrderSide::Sell) => stop.min(high),
+                    (OrderKind::StopMarket(stop), OrderSide::Buy) => stop.max(low),
+                    (OrderKind::Market, _) => self.last_prices.get(&symbol).copied().unwrap_or(low),
+                };
+                self.fill(&order, price, quantity);
+                filled = true;
+            }
+            self.resting.retain(|o| o.client_id != order.client_id);
+        }
+        // Reduce-only orders left without a position to reduce once it has been closed are dropped
+        if filled && self.positions.get(&symbol).copied().unwrap_or(0.0) == 0.0 {
+            self.resting.retain(|o| !(o.reduce_only && o.symbol == symbol));
+        }
+    }
+}
+
+impl ExecutionHandler for SimulatedExecution {
+    fn submit(&mut self, order: OrderRequest, time_ms: u64) -> Result<(), String> {
+        if order.quantity <= 0.0 || !order.quantity.is_finite() {
+            return Err(format!("Invalid quantity {}", order.quantity));
+        }
+        if self.resting.iter().any(|o| o.client_id == order.client_id) {
+            return Err(format!("Duplicate client order ID {}", order.client_id));
+        }
+        self.time_ms = self.time_ms.max(time_ms);
+        match order.kind {
+            OrderKind::Market => {
+                let last = self.last_prices.get(&order.symbol).copied()
+                    .ok_or_else(|| format!("No price for {} yet", order.symbol))?;
+                let quantity = self.fillable(&order);
+                if quantity <= 0.0 {
+                    return Err("Reduce-only order without a position to reduce".to_string());
+                }
+                let price = match order.side {
+                    OrderSide::Buy => last * (1.0 + self.slippage),
+                    OrderSide::Sell => last * (1.0 - self.slippage),
+                };
+                self.fill(&order, price, quantity);
+            },
+            OrderKind::Limit(price) | OrderKind::StopMarket(price) => {
+                if price <= 0.0 {
+                    return Err(format!("Invalid price {}", price));
+                }
+                self.resting.push(order);
+            },
+        }
+        Ok(())
+    }
+
+    fn cancel(&mut self, symbol: Symbol, client_id: &str) -> Result<(), String> {
+        // Orders that already filled or were dropped are no longer working: nothing to cancel
+        self.resting.retain(|o| !(o.symbol == symbol && o.client_id == client_id));
+        Ok(())
+    }
+
+    fn on_market_event(&mut self, event: &MarketEvent) {
+        self.time_ms = self.time_ms.max(event.event_time());
+        let symbol = event.symbol();
+        let range = match event {
+            MarketEvent::Kline(candle) => Some((candle.low, candle.high)),
+            _ => event_price(event).map(|price| (price, price)),
+        };
+        if let Some((low, high)) = range {
+            self.match_resting(symbol, low, high);
+        }
+        if let Some(price) = event_price(event) {
+            self.last_prices.insert(symbol, price);
+        }
+    }
+
+    fn drain_fills(&mut self) -> Vec<FillEvent> {
+        std::mem::take(&mut self.fills)
+    }
+}
+
+/// Spawns a paper trading task: the engine runs on live market events with simulated fills. The
+/// task ends when the channel closes and returns the engine, with its fills and portfolio.
+pub fn spawn_paper_engine<S: EventStrategy + Send + 'static>(
+    mut engine: Engine<S, SimulatedExecution>,
+    mut market_events: mpsc::Receiver<MarketEvent>,
+) -> JoinHandle<Engine<S, SimulatedExecution>> {
+    tokio::spawn(async move {
+        info!("Paper engine started for strategy {}", engine.strategy.name());
+        while let Some(event) = market_events.recv().await {
+            for fill in engine.on_market_event(&event) {
+                info!("Paper fill: {} {:?} {} @ {} (equity {:.2})", fill.client_id, fill.side, fill.quantity, fill.price, engine.portfolio.equity());
+            }
+        }
+        info!("Paper engine stopped for strategy {}", engine.strategy.name());
+        engine
+    })
+}
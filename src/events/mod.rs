@@ -0,0 +1,302 @@
+// src/events/mod.rs
+
+//! This module records the bot's inputs and decisions as an append-only event log (signals, order
+//! decisions, fills and configuration changes) and rebuilds the decision state from it.
+//!
+//! The state the executor acts on (positions, realized PnL for the risk policies, configuration)
+//! is a pure fold over the log (`BotState::apply`), so the exact state at any past moment can be
+//! reconstructed with `replay` and the state in which a decision was taken with `state_before`.
+//! "Why did the bot do X at 03:12?" then becomes a query: see the `replay` binary.
+//!
+//! The log is stored as JSON lines (one `EventRecord` per line) in the state directory. Appended
+//! records are also broadcast to subscribers (`EventLog::subscribe`), e.g. dashboard clients.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use crate::notify::{Notification, Notifications};
+use crate::order::ExecutionType;
+use crate::risk::RiskState;
+use crate::streams::FuturesOrderUpdate;
+
+/// Default file name of the event log inside the state directory.
+pub const EVENT_LOG_FILE: &str = "events.jsonl";
+/// Records kept for a subscriber that falls behind; older ones are skipped.
+const EVENT_BROADCAST_CAPACITY: usize = 1024;
+
+/// Something that happened to the bot, or that the bot decided.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BotEvent {
+    /// A trading signal was received, e.g. from a TradingView webhook.
+    #[serde(rename_all = "camelCase")]
+    Signal {
+        symbol: String,
+        signal: String,
+        #[serde(default)]
+        position_side: Option<String>,
+        #[serde(default)]
+        quote_quantity: Option<f64>,
+        #[serde(default)]
+        variant: Option<String>,
+    },
+    /// The executor placed an order for a signal.
+    #[serde(rename_all = "camelCase")]
+    OrderPlaced {
+        symbol: String,
+        signal: String,
+        client_order_id: String,
+        side: String, // BUY or SELL
+        quantity: f64,
+        price: f64, // Market price the decision was based on
+    },
+    /// The executor did not act on a signal.
+    #[serde(rename_all = "camelCase")]
+    SignalRejected {
+        symbol: String,
+        signal: String,
+        reason: String,
+    },
+    /// An order (partially) filled, from `ORDER_TRADE_UPDATE`.
+    #[serde(rename_all = "camelCase")]
+    Fill {
+        symbol: String,
+        client_order_id: String,
+        side: String,
+        #[serde(default)]
+        position_side: Option<String>, // LONG/SHORT in hedge mode
+        price: f64,
+        quantity: f64,
+        realized_pnl: f64,
+        commission: f64,
+    },
+    /// An open order the bot did not place (or lost track of) was found on the exchange at startup
+    /// and is tracked from now on (see `reconcile`).
+    #[serde(rename_all = "camelCase")]
+    OrderAdopted {
+        symbol: String,
+        client_order_id: String,
+        order_id: u64,
+        order_type: String,
+        side: String,
+        quantity: f64,
+        price: f64, // Limit price, or the trigger price of stop orders
+    },
+    /// A runtime setting changed (or was loaded at startup).
+    #[serde(rename_all = "camelCase")]
+    ConfigChange {
+        key: String,
+        value: Value,
+    },
+}
+
+impl BotEvent {
+    /// Builds a `Fill` from a trade execution on the user data stream; `None` for other updates.
+    /// Commissions are assumed to be charged in the quote asset.
+    pub fn from_order_update(update: &FuturesOrderUpdate) -> Option<Self> {
+        if update.execution_type != ExecutionType::Trade {
+            return None;
+        }
+        let number = |s: &str| s.parse::<f64>().unwrap_or(0.0);
+        Some(BotEvent::Fill {
+            symbol: update.symbol.clone(),
+            client_order_id: update.client_order_id.clone(),
+            side: update.side.clone(),
+            position_side: update.position_side.is_hedge_leg().then(|| update.position_side.to_string()),
+            price: number(&update.last_filled_price),
+            quantity: number(&update.last_filled_quantity),
+            realized_pnl: number(&update.realized_profit),
+            commission: update.commission.as_deref().map(number).unwrap_or(0.0),
+        })
+    }
+}
+
+/// An event with its position in the log.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventRecord {
+    pub seq: u64, // 1-based, strictly increasing
+    pub time_ms: i64,
+    pub event: BotEvent,
+}
+
+/// A position as seen by the bot (not reconciled with the exchange).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionState {
+    pub quantity: f64, // Signed: positive for long, negative for short
+    pub entry_price: f64, // Average entry price of the open quantity
+}
+
+impl PositionState {
+    fn apply_fill(&mut self, signed_quantity: f64, price: f64) {
+        let new_quantity = self.quantity + signed_quantity;
+        if self.quantity == 0.0 || self.quantity.signum() == signed_quantity.signum() {
+            // Opening or increasing: average the entry price
+            self.entry_price = (self.entry_price * self.quantity.abs() + price * signed_quantity.abs()) / new_quantity.abs();
+        } else if new_quantity != 0.0 && new_quantity.signum() != self.quantity.signum() {
+            self.entry_price = price; // Flipped: the remainder was opened at this fill
+        }
+        self.quantity = new_quantity;
+        if self.quantity.abs() < 1e-12 {
+            *self = Self::default();
+        }
+    }
+}
+
+/// The decision state of the bot, rebuilt by applying events in order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BotState {
+    pub last_seq: u64,
+    pub time_ms: i64, // Time of the last applied event
+    pub positions: BTreeMap<String, PositionState>, // Keyed by symbol, or "SYMBOL:LONG"/"SYMBOL:SHORT" in hedge mode
+    #[serde(skip)]
+    pub risk: RiskState,
+    pub realized_pnl: f64, // Net of commissions, since the start of the log
+    pub config: BTreeMap<String, Value>,
+    pub signals: u64,
+    pub orders_placed: u64,
+    pub signals_rejected: u64,
+    pub orders_adopted: u64,
+}
+
+impl BotState {
+    /// Applies one event. Deterministic: the same records always produce the same state.
+    pub fn apply(&mut self, record: &EventRecord) {
+        self.last_seq = record.seq;
+        self.time_ms = record.time_ms;
+        match &record.event {
+            BotEvent::Signal { .. } => self.signals += 1,
+            BotEvent::OrderPlaced { .. } => self.orders_placed += 1,
+            BotEvent::SignalRejected { .. } => self.signals_rejected += 1,
+            BotEvent::OrderAdopted { .. } => self.orders_adopted += 1,
+            BotEvent::Fill { symbol, side, position_side, price, quantity, realized_pnl, commission, .. } => {
+                let key = match position_side {
+                    Some(ps) => format!("{}:{}", symbol, ps),
+                    None => symbol.clone(),
+                };
+                let signed = if side == "BUY" { *quantity } else { -*quantity };
+                self.positions.entry(key.clone()).or_default().apply_fill(signed, *price);
+                if self.positions.get(&key).is_some_and(|p| p.quantity == 0.0) {
+                    self.positions.remove(&key);
+                }
+                self.risk.on_fill(*realized_pnl, *commission, record.time_ms);
+                self.realized_pnl += realized_pnl - commission;
+            },
+            BotEvent::ConfigChange { key, value } => {
+                self.config.insert(key.clone(), value.clone());
+            },
+        }
+    }
+}
+
+/// Rebuilds the state from the records with `time_ms <= until_ms` (all records when `None`).
+/// Records must be in log order.
+pub fn replay(records: &[EventRecord], until_ms: Option<i64>) -> BotState {
+    let mut state = BotState::default();
+    for record in records.iter().take_while(|r| until_ms.is_none_or(|until| r.time_ms <= until)) {
+        state.apply(record);
+    }
+    state
+}
+
+/// Returns the state right before the record `seq` was applied, together with that record:
+/// the exact inputs the bot had when it took the decision.
+pub fn state_before(records: &[EventRecord], seq: u64) -> Option<(BotState, &EventRecord)> {
+    let index = records.iter().position(|r| r.seq == seq)?;
+    let mut state = BotState::default();
+    for record in &records[..index] {
+        state.apply(record);
+    }
+    Some((state, &records[index]))
+}
+
+/// Returns the records with `from_ms <= time_ms <= to_ms`.
+pub fn records_between(records: &[EventRecord], from_ms: i64, to_ms: i64) -> Vec<&EventRecord> {
+    records.iter().filter(|r| r.time_ms >= from_ms && r.time_ms <= to_ms).collect()
+}
+
+/// Parses a JSON-lines event log. Blank lines are skipped; a malformed line is an error.
+pub fn parse_events(reader: impl BufRead) -> Result<Vec<EventRecord>, String> {
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read event log: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: EventRecord = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid event on line {}: {}", index + 1, e))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Loads an event log file; a missing file is an empty log.
+pub fn load_events(path: impl AsRef<Path>) -> Result<Vec<EventRecord>, String> {
+    let path = path.as_ref();
+    match File::open(path) {
+        Ok(file) => parse_events(BufReader::new(file)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to open event log {}: {}", path.display(), e)),
+    }
+}
+
+/// Append-only event log file, shared by the tasks that record events.
+pub struct EventLog {
+    path: PathBuf,
+    inner: Mutex<(File, u64)>, // The file and the last sequence number written
+    notifications: Option<Notifications>,
+    broadcast: broadcast::Sender<EventRecord>,
+}
+
+impl EventLog {
+    /// Opens (or creates) the log at `path`, continuing its sequence numbers.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let last_seq = load_events(&path)?.last().map(|r| r.seq).unwrap_or(0);
+        let file = OpenOptions::new().create(true).append(true).open(&path)
+            .map_err(|e| format!("Failed to open event log {}: {}", path.display(), e))?;
+        Ok(Self { path, inner: Mutex::new((file, last_seq)), notifications: None, broadcast: broadcast::channel(EVENT_BROADCAST_CAPACITY).0 })
+    }
+
+    /// Also sends notifications for appended orders, fills and rejected signals.
+    pub fn with_notifications(mut self, notifications: Notifications) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Returns a receiver of the records appended from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<EventRecord> {
+        self.broadcast.subscribe()
+    }
+
+    /// Returns the path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends an event and returns its record.
+    pub fn append(&self, time_ms: i64, event: BotEvent) -> Result<EventRecord, String> {
+        let mut inner = self.inner.lock().map_err(|_| "Event log lock poisoned".to_string())?;
+        let record = EventRecord { seq: inner.1 + 1, time_ms, event };
+        let mut line = serde_json::to_string(&record).map_err(|e| format!("Failed to serialize event: {}", e))?;
+        line.push('\n');
+        inner.0.write_all(line.as_bytes())
+            .and_then(|_| inner.0.flush())
+            .map_err(|e| format!("Failed to write to event log {}: {}", self.path.display(), e))?;
+        inner.1 = record.seq;
+        let _ = self.broadcast.send(record.clone()); // Nobody may be listening
+        if let (Some(notifications), Some(notification)) = (&self.notifications, Notification::from_event(&record.event)) {
+            notifications.notify(notification);
+        }
+        Ok(record)
+    }
+}
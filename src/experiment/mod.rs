@@ -0,0 +1,199 @@
+// src/experiment/mod.rs
+
+//! This module runs A/B tests of strategy parameters on live capital: two (or more)
+//! parameterizations of the same strategy trade side by side, each receiving a configured share
+//! of the sizing budget.
+//!
+//! Orders of a variant carry its tag in the client order ID (`{base}-v{tag}`), so fills and closed
+//! trades can be attributed to the variant that produced them (`ClosedTrade::variant`).
+//! `compare_variants` then reports per-variant results and a Welch's t-test on per-trade returns,
+//! so a parameter change is only adopted when the difference is statistically meaningful.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::currency::ReportingCurrency;
+use crate::performance::ClosedTrade;
+
+/// Variant tags must fit into a client order ID next to the base ID (36 characters in total).
+const MAX_VARIANT_TAG_LEN: usize = 8;
+
+/// Two-sided 5% critical value of the normal distribution, used once both samples are large.
+const CRITICAL_T_95: f64 = 1.96;
+
+/// Minimum number of trades per variant before a comparison is considered significant.
+pub const MIN_TRADES_FOR_SIGNIFICANCE: usize = 30;
+
+/// One parameterization of the strategy under test.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variant {
+    pub tag: String, // Short alphanumeric tag, e.g. "a" or "ema21"
+    pub budget_share: f64, // Share of the sizing budget, e.g. 0.5
+    #[serde(default)]
+    pub params: BTreeMap<String, f64>, // Strategy parameters of this variant, e.g. {"fastEma": 21}
+}
+
+/// An A/B test of a strategy's parameters.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Experiment {
+    pub name: String,
+    pub strategy: String,
+    pub variants: Vec<Variant>,
+}
+
+impl Experiment {
+    /// Validates tags and budget shares: at least two variants, unique alphanumeric tags, and
+    /// positive shares that do not add up to more than the whole budget.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.variants.len() < 2 {
+            return Err(format!("Experiment '{}' needs at least two variants.", self.name));
+        }
+        for (i, variant) in self.variants.iter().enumerate() {
+            if variant.tag.is_empty() || variant.tag.len() > MAX_VARIANT_TAG_LEN || !variant.tag.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(format!("Variant tag '{}' must be 1-{} alphanumeric characters.", variant.tag, MAX_VARIANT_TAG_LEN));
+            }
+            if self.variants[..i].iter().any(|v| v.tag == variant.tag) {
+                return Err(format!("Duplicate variant tag '{}'.", variant.tag));
+            }
+            if variant.budget_share <= 0.0 {
+                return Err(format!("Budget share of variant '{}' must be positive.", variant.tag));
+            }
+        }
+        let total: f64 = self.variants.iter().map(|v| v.budget_share).sum();
+        if total > 1.0 + 1e-9 {
+            return Err(format!("Budget shares of experiment '{}' add up to {:.2}, more than 1.", self.name, total));
+        }
+        Ok(())
+    }
+
+    /// Returns a variant by tag.
+    pub fn variant(&self, tag: &str) -> Option<&Variant> {
+        self.variants.iter().find(|v| v.tag == tag)
+    }
+
+    /// Returns the part of `total_budget` (e.g. a quote quantity) allotted to a variant.
+    pub fn budget_for(&self, tag: &str, total_budget: f64) -> Result<f64, String> {
+        self.variant(tag)
+            .map(|v| total_budget * v.budget_share)
+            .ok_or_else(|| format!("Unknown variant '{}' in experiment '{}'.", tag, self.name))
+    }
+}
+
+/// Parses an experiment from JSON and validates it.
+pub fn parse_experiment(json: &str) -> Result<Experiment, String> {
+    let experiment: Experiment = serde_json::from_str(json).map_err(|e| format!("Invalid experiment: {}", e))?;
+    experiment.validate()?;
+    Ok(experiment)
+}
+
+/// Appends a variant tag to a client order ID.
+pub fn tag_client_order_id(base: &str, tag: &str) -> String {
+    format!("{}-v{}", base, tag)
+}
+
+/// Returns the variant tag carried by a client order ID, if any.
+pub fn variant_of(client_order_id: &str) -> Option<&str> {
+    client_order_id.rsplit_once("-v").map(|(_, tag)| tag).filter(|tag| !tag.is_empty())
+}
+
+/// Live results of one variant.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VariantStats {
+    pub tag: String,
+    pub trades: usize,
+    pub wins: usize,
+    pub net_pnl: f64,
+    pub mean_return: f64, // Mean net PnL per trade as a fraction of the variant's budget
+    pub std_return: f64,
+}
+
+/// Comparison of the variants of an experiment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ExperimentReport {
+    pub variants: Vec<VariantStats>,
+    pub t_statistic: Option<f64>, // Welch's t of the first two variants' per-trade returns
+    pub significant: bool, // |t| above the 5% critical value with enough trades on both sides
+}
+
+fn mean_std(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    if values.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    (mean, variance.sqrt())
+}
+
+/// Welch's t-statistic of two samples with unequal variances.
+pub fn welch_t_statistic(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (mean_a, std_a) = mean_std(a);
+    let (mean_b, std_b) = mean_std(b);
+    let standard_error = (std_a.powi(2) / a.len() as f64 + std_b.powi(2) / b.len() as f64).sqrt();
+    if standard_error <= 0.0 { None } else { Some((mean_a - mean_b) / standard_error) }
+}
+
+/// Attributes closed trades to the experiment's variants and compares their results.
+///
+/// # Arguments
+/// * `experiment` - The experiment; trades of other variants (or without one) are ignored.
+/// * `trades` - Closed trades, attributed through `ClosedTrade::variant`.
+/// * `total_budget` - The sizing budget split between the variants, used to normalize returns.
+pub fn compare_variants(experiment: &Experiment, trades: &[ClosedTrade], total_budget: f64) -> ExperimentReport {
+    let returns: Vec<Vec<f64>> = experiment.variants.iter().map(|variant| {
+        let budget = total_budget * variant.budget_share;
+        trades.iter()
+            .filter(|t| t.variant.as_deref() == Some(variant.tag.as_str()))
+            .map(|t| if budget > 0.0 { t.net_pnl() / budget } else { 0.0 })
+            .collect()
+    }).collect();
+
+    let variants = experiment.variants.iter().zip(&returns).map(|(variant, r)| {
+        let (mean_return, std_return) = mean_std(r);
+        let attributed = trades.iter().filter(|t| t.variant.as_deref() == Some(variant.tag.as_str()));
+        VariantStats {
+            tag: variant.tag.clone(),
+            trades: r.len(),
+            wins: r.iter().filter(|&&x| x > 0.0).count(),
+            net_pnl: attributed.map(ClosedTrade::net_pnl).sum(),
+            mean_return,
+            std_return,
+        }
+    }).collect();
+
+    let (t_statistic, enough_trades) = match returns.as_slice() {
+        [a, b, ..] => (welch_t_statistic(a, b), a.len().min(b.len()) >= MIN_TRADES_FOR_SIGNIFICANCE),
+        _ => (None, false),
+    };
+    ExperimentReport {
+        variants,
+        t_statistic,
+        significant: enough_trades && t_statistic.is_some_and(|t| t.abs() > CRITICAL_T_95),
+    }
+}
+
+/// Prints an experiment report to the console.
+pub fn print_experiment_report(experiment: &Experiment, report: &ExperimentReport, currency: ReportingCurrency) {
+    println!("\n--- A/B Test '{}' ({}) ---", experiment.name, experiment.strategy);
+    println!("{:<8} | {:>6} | {:>6} | {:>12} | {:>12} | {:>10}", "Variant", "Trades", "Wins", "Net P/L", "Mean Ret.", "Std Ret.");
+    println!("{:-<69}", "");
+    for s in &report.variants {
+        println!(
+            "{:<8} | {:>6} | {:>6} | {:>12} | {:>11.3}% | {:>9.3}%",
+            s.tag, s.trades, s.wins, currency.format(s.net_pnl), s.mean_return * 100.0, s.std_return * 100.0
+        );
+    }
+    println!("{:-<69}", "");
+    match report.t_statistic {
+        Some(t) => println!("Welch's t = {:.2} ({})", t, if report.significant { "significant at 5%" } else { "not significant" }),
+        None => println!("Welch's t = n/a (not enough trades)"),
+    }
+}
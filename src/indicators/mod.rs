@@ -0,0 +1,477 @@
+// src/indicators/mod.rs
+
+//! This module provides incremental technical indicators: each is updated with one closed bar at a
+//! time and keeps only the state it needs, so the same code serves backtests and live streams.
+//! Every update is O(1) (amortized for `Stochastic`), and indicators return `None` until they have
+//! seen enough bars.
+//!
+//! * Moving averages: `Sma`, `Ema` (seeded with the SMA of its first `period` values).
+//! * Oscillators: `Rsi`, `Stochastic` (%K and %D), `Macd` (line, signal and histogram).
+//! * Volatility: `Atr`, `BollingerBands` (SMA middle band, population standard deviation).
+//! * Volume: `Vwap`, anchored to a session (UTC day, funding period, ...) with deviation bands.
+
+use std::collections::VecDeque;
+
+/// Incremental Average True Range using Wilder's smoothing.
+#[derive(Debug, Clone)]
+pub struct Atr {
+    period: usize,
+    previous_close: Option<f64>,
+    seed: Vec<f64>, // True ranges collected until the first average is available
+    value: Option<f64>,
+}
+
+impl Atr {
+    /// Creates an ATR over `period` bars.
+    pub fn new(period: usize) -> Self {
+        Self { period: period.max(1), previous_close: None, seed: Vec::new(), value: None }
+    }
+
+    /// Adds a closed bar and returns the current ATR, once `period` bars have been seen.
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> Option<f64> {
+        let true_range = match self.previous_close {
+            Some(pc) => (high - low).max((high - pc).abs()).max((low - pc).abs()),
+            None => high - low,
+        };
+        self.previous_close = Some(close);
+        self.value = match self.value {
+            Some(atr) => Some((atr * (self.period - 1) as f64 + true_range) / self.period as f64),
+            None => {
+                self.seed.push(true_range);
+                if self.seed.len() == self.period {
+                    Some(self.seed.drain(..).sum::<f64>() / self.period as f64)
+                } else {
+                    None
+                }
+            }
+        };
+        self.value
+    }
+
+    /// Returns the current ATR, if available.
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+/// Incremental Relative Strength Index using Wilder's smoothing.
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    period: usize,
+    previous_close: Option<f64>,
+    seed: Vec<(f64, f64)>, // (gain, loss) collected until the first averages are available
+    averages: Option<(f64, f64)>, // Smoothed (gain, loss)
+}
+
+impl Rsi {
+    /// Creates an RSI over `period` bars.
+    pub fn new(period: usize) -> Self {
+        Self { period: period.max(1), previous_close: None, seed: Vec::new(), averages: None }
+    }
+
+    /// Adds a closing price and returns the current RSI (0-100), once `period` changes have been seen.
+    pub fn update(&mut self, close: f64) -> Option<f64> {
+        let previous = self.previous_close.replace(close)?;
+        let change = close - previous;
+        let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+        let n = self.period as f64;
+        self.averages = match self.averages {
+            Some((avg_gain, avg_loss)) => Some(((avg_gain * (n - 1.0) + gain) / n, (avg_loss * (n - 1.0) + loss) / n)),
+            None => {
+                self.seed.push((gain, loss));
+                if self.seed.len() == self.period {
+                    let (gains, losses) = self.seed.drain(..).fold((0.0, 0.0), |(g, l), (gain, loss)| (g + gain, l + loss));
+                    Some((gains / n, losses / n))
+                } else {
+                    None
+                }
+            }
+        };
+        self.value()
+    }
+
+    /// Returns the RSI the indicator would have if the current (unclosed) bar closed at `close`,
+    /// without updating it. Used for intrabar evaluation.
+    pub fn peek(&self, close: f64) -> Option<f64> {
+        self.clone().update(close)
+    }
+
+    /// Returns the current RSI, if available.
+    pub fn value(&self) -> Option<f64> {
+        self.averages.map(|(avg_gain, avg_loss)| {
+            if avg_loss == 0.0 {
+                if avg_gain == 0.0 { 50.0 } else { 100.0 }
+            } else {
+                100.0 - 100.0 / (1.0 + avg_gain / avg_loss)
+            }
+        })
+    }
+}
+
+/// Incremental Exponential Moving Average, seeded with the simple average of the first `period`
+/// values.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    period: usize,
+    seed: Vec<f64>, // Values collected until the first average is available
+    value: Option<f64>,
+}
+
+impl Ema {
+    /// Creates an EMA over `period` values.
+    pub fn new(period: usize) -> Self {
+        Self { period: period.max(1), seed: Vec::new(), value: None }
+    }
+
+    /// Adds a value and returns the current EMA, once `period` values have been seen.
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        let multiplier = 2.0 / (self.period as f64 + 1.0);
+        self.value = match self.value {
+            Some(ema) => Some((value - ema) * multiplier + ema),
+            None => {
+                self.seed.push(value);
+                if self.seed.len() == self.period {
+                    Some(self.seed.drain(..).sum::<f64>() / self.period as f64)
+                } else {
+                    None
+                }
+            }
+        };
+        self.value
+    }
+
+    /// Returns the current EMA, if available.
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+/// Incremental Simple Moving Average over a sliding window.
+#[derive(Debug, Clone)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Sma {
+    /// Creates an SMA over `period` values.
+    pub fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Self { period, window: VecDeque::with_capacity(period + 1), sum: 0.0 }
+    }
+
+    /// Adds a value and returns the current SMA, once `period` values have been seen.
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        self.window.push_back(value);
+        self.sum += value;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        self.value()
+    }
+
+    /// Returns the current SMA, if available.
+    pub fn value(&self) -> Option<f64> {
+        (self.window.len() == self.period).then(|| self.sum / self.period as f64)
+    }
+}
+
+/// Values of the MACD indicator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdValue {
+    pub macd: f64, // Fast EMA minus slow EMA
+    pub signal: f64, // EMA of the MACD line
+    pub histogram: f64, // MACD line minus signal
+}
+
+/// Incremental Moving Average Convergence Divergence, e.g. `Macd::new(12, 26, 9)`.
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+    value: Option<MacdValue>,
+}
+
+impl Macd {
+    /// Creates a MACD with the given fast, slow and signal periods.
+    pub fn new(fast_period: usize, slow_period: usize, signal_period: usize) -> Self {
+        Self { fast: Ema::new(fast_period), slow: Ema::new(slow_period), signal: Ema::new(signal_period), value: None }
+    }
+
+    /// Adds a closing price and returns the current values, once the signal line is available.
+    pub fn update(&mut self, close: f64) -> Option<MacdValue> {
+        let fast = self.fast.update(close);
+        let slow = self.slow.update(close);
+        let (Some(fast), Some(slow)) = (fast, slow) else { return None };
+        let macd = fast - slow;
+        self.value = self.signal.update(macd).map(|signal| MacdValue { macd, signal, histogram: macd - signal });
+        self.value
+    }
+
+    /// Returns the current values, if available.
+    pub fn value(&self) -> Option<MacdValue> {
+        self.value
+    }
+}
+
+/// Values of the Bollinger Bands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BollingerValue {
+    pub upper: f64,
+    pub middle: f64,
+    pub lower: f64,
+}
+
+/// Incremental Bollinger Bands: an SMA middle band with bands `multiplier` standard deviations
+/// away, e.g. `BollingerBands::new(20, 2.0)`.
+#[derive(Debug, Clone)]
+pub struct BollingerBands {
+    period: usize,
+    multiplier: f64,
+    window: VecDeque<f64>,
+    sum: f64,
+    sum_of_squares: f64,
+}
+
+impl BollingerBands {
+    /// Creates bands over `period` closes, `multiplier` standard deviations wide.
+    pub fn new(period: usize, multiplier: f64) -> Self {
+        let period = period.max(1);
+        Self { period, multiplier, window: VecDeque::with_capacity(period + 1), sum: 0.0, sum_of_squares: 0.0 }
+    }
+
+    /// Adds a closing price and returns the current bands, once `period` closes have been seen.
+    pub fn update(&mut self, close: f64) -> Option<BollingerValue> {
+        self.window.push_back(close);
+        self.sum += close;
+        self.sum_of_squares += close * close;
+        if self.window.len() > self.period {
+            let removed = self.window.pop_front().unwrap_or_default();
+            self.sum -= removed;
+            self.sum_of_squares -= removed * removed;
+        }
+        self.value()
+    }
+
+    /// Returns the current bands, if available.
+    pub fn value(&self) -> Option<BollingerValue> {
+        if self.window.len() < self.period {
+            return None;
+        }
+        let n = self.period as f64;
+        let middle = self.sum / n;
+        // Rounding in the running sums can leave a tiny negative variance for a flat window
+        let deviation = (self.sum_of_squares / n - middle * middle).max(0.0).sqrt();
+        Some(BollingerValue { upper: middle + self.multiplier * deviation, middle, lower: middle - self.multiplier * deviation })
+    }
+}
+
+/// Values of the Stochastic oscillator (0-100).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StochasticValue {
+    pub k: f64, // Position of the close within the `k_period` high-low range
+    pub d: f64, // SMA of %K over `d_period` bars
+}
+
+/// Incremental Stochastic oscillator, e.g. `Stochastic::new(14, 3)`. The highest high and lowest
+/// low of the window are kept in monotonic queues, so updates are O(1) amortized.
+#[derive(Debug, Clone)]
+pub struct Stochastic {
+    k_period: usize,
+    bars: usize,
+    highs: VecDeque<(usize, f64)>, // (bar index, high), decreasing highs
+    lows: VecDeque<(usize, f64)>, // (bar index, low), increasing lows
+    d: Sma,
+    value: Option<StochasticValue>,
+}
+
+impl Stochastic {
+    /// Creates a Stochastic with a `k_period` range and a `d_period` signal.
+    pub fn new(k_period: usize, d_period: usize) -> Self {
+        Self { k_period: k_period.max(1), bars: 0, highs: VecDeque::new(), lows: VecDeque::new(), d: Sma::new(d_period), value: None }
+    }
+
+    /// Adds a closed bar and returns the current values, once %D is available.
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> Option<StochasticValue> {
+        let index = self.bars;
+        self.bars += 1;
+        while self.highs.back().is_some_and(|&(_, h)| h <= high) {
+            self.highs.pop_back();
+        }
+        self.highs.push_back((index, high));
+        while self.lows.back().is_some_and(|&(_, l)| l >= low) {
+            self.lows.pop_back();
+        }
+        self.lows.push_back((index, low));
+        // Drop the bars that left the window
+        let oldest = (index + 1).saturating_sub(self.k_period);
+        while self.highs.front().is_some_and(|&(i, _)| i < oldest) {
+            self.highs.pop_front();
+        }
+        while self.lows.front().is_some_and(|&(i, _)| i < oldest) {
+            self.lows.pop_front();
+        }
+        if self.bars < self.k_period {
+            return None;
+        }
+        let highest = self.highs.front().map(|&(_, h)| h).unwrap_or(high);
+        let lowest = self.lows.front().map(|&(_, l)| l).unwrap_or(low);
+        let k = if highest > lowest { 100.0 * (close - lowest) / (highest - lowest) } else { 50.0 };
+        self.value = self.d.update(k).map(|d| StochasticValue { k, d });
+        self.value
+    }
+
+    /// Returns the current values, if available.
+    pub fn value(&self) -> Option<StochasticValue> {
+        self.value
+    }
+}
+
+/// Session boundaries at which a `Vwap` resets: every `period_ms`, shifted by `offset_ms` from the
+/// epoch (UTC midnight for daily sessions).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VwapSession {
+    pub period_ms: u64,
+    pub offset_ms: u64,
+}
+
+impl VwapSession {
+    /// Sessions starting at UTC midnight.
+    pub fn daily() -> Self {
+        Self { period_ms: 86_400_000, offset_ms: 0 }
+    }
+
+    /// Sessions starting at the funding times of USDⓈ-M perpetuals (00:00, 08:00 and 16:00 UTC).
+    pub fn funding() -> Self {
+        Self { period_ms: 8 * 3_600_000, offset_ms: 0 }
+    }
+
+    /// Parses "daily", "funding" or a period such as "4h" or "1w", optionally followed by a UTC
+    /// offset, e.g. "daily+13h30m" for sessions starting at 13:30 UTC.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (period, offset) = value.trim().split_once('+').unwrap_or((value.trim(), ""));
+        let mut session = match period.to_lowercase().as_str() {
+            "daily" | "day" | "1d" => Self::daily(),
+            "funding" => Self::funding(),
+            _ => Self { period_ms: parse_duration_ms(period)?, offset_ms: 0 },
+        };
+        if !offset.is_empty() {
+            session.offset_ms = parse_duration_ms(offset)? % session.period_ms;
+        }
+        Ok(session)
+    }
+
+    /// Returns the start of the session containing `time_ms`.
+    pub fn start(&self, time_ms: u64) -> u64 {
+        let offset = self.offset_ms % self.period_ms;
+        let shifted = time_ms + self.period_ms - offset;
+        (shifted - shifted % self.period_ms + offset).saturating_sub(self.period_ms)
+    }
+}
+
+/// Parses a duration made of `<count><unit>` parts with units s, m, h, d or w, e.g. "13h30m".
+fn parse_duration_ms(value: &str) -> Result<u64, String> {
+    let mut total = 0;
+    let mut count = String::new();
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            count.push(c);
+            continue;
+        }
+        let unit_ms = match c {
+            's' => 1_000,
+            'm' => 60_000,
+            'h' => 3_600_000,
+            'd' => 86_400_000,
+            'w' => 7 * 86_400_000,
+            _ => return Err(format!("Invalid duration '{}'", value)),
+        };
+        let count_value: u64 = count.parse().map_err(|_| format!("Invalid duration '{}'", value))?;
+        total += count_value * unit_ms;
+        count.clear();
+    }
+    if !count.is_empty() || total == 0 {
+        return Err(format!("Invalid duration '{}': expected e.g. 4h or 13h30m", value));
+    }
+    Ok(total)
+}
+
+/// Values of the VWAP.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VwapValue {
+    pub vwap: f64,
+    pub deviation: f64, // Volume-weighted standard deviation of the prices around the VWAP
+    pub session_start_ms: u64,
+}
+
+impl VwapValue {
+    /// Returns the (upper, lower) band `multiplier` deviations away from the VWAP.
+    pub fn band(&self, multiplier: f64) -> (f64, f64) {
+        (self.vwap + multiplier * self.deviation, self.vwap - multiplier * self.deviation)
+    }
+
+    /// Returns how many deviations `price` lies above (positive) or below the VWAP, e.g. to grade a
+    /// fill against the session benchmark or to fade stretched prices. Zero without dispersion.
+    pub fn z_score(&self, price: f64) -> f64 {
+        if self.deviation > 0.0 { (price - self.vwap) / self.deviation } else { 0.0 }
+    }
+}
+
+/// Incremental session-anchored Volume-Weighted Average Price, fed with trades (price and
+/// quantity) or bars (typical price and volume). It resets at every session boundary.
+#[derive(Debug, Clone)]
+pub struct Vwap {
+    session: VwapSession,
+    session_start_ms: Option<u64>,
+    volume: f64,
+    price_volume: f64,
+    price_squared_volume: f64,
+}
+
+impl Vwap {
+    /// Creates a VWAP resetting at `session` boundaries.
+    pub fn new(session: VwapSession) -> Self {
+        let session = VwapSession { period_ms: session.period_ms.max(1), ..session };
+        Self { session, session_start_ms: None, volume: 0.0, price_volume: 0.0, price_squared_volume: 0.0 }
+    }
+
+    /// Adds volume traded at `price` at `time_ms` and returns the current values, once the
+    /// session has volume. Updates from before the current session are ignored.
+    pub fn update(&mut self, time_ms: u64, price: f64, volume: f64) -> Option<VwapValue> {
+        let start = self.session.start(time_ms);
+        match self.session_start_ms {
+            Some(current) if start < current => return self.value(),
+            Some(current) if start == current => {},
+            _ => {
+                self.session_start_ms = Some(start);
+                self.volume = 0.0;
+                self.price_volume = 0.0;
+                self.price_squared_volume = 0.0;
+            },
+        }
+        if volume > 0.0 {
+            self.volume += volume;
+            self.price_volume += price * volume;
+            self.price_squared_volume += price * price * volume;
+        }
+        self.value()
+    }
+
+    /// Adds a closed bar opening at `open_time_ms`, weighted at its typical price (high + low + close) / 3.
+    pub fn update_bar(&mut self, open_time_ms: u64, high: f64, low: f64, close: f64, volume: f64) -> Option<VwapValue> {
+        self.update(open_time_ms, (high + low + close) / 3.0, volume)
+    }
+
+    /// Returns the current values, if the session has volume.
+    pub fn value(&self) -> Option<VwapValue> {
+        let session_start_ms = self.session_start_ms?;
+        if self.volume <= 0.0 {
+            return None;
+        }
+        let vwap = self.price_volume / self.volume;
+        let deviation = (self.price_squared_volume / self.volume - vwap * vwap).max(0.0).sqrt();
+        Some(VwapValue { vwap, deviation, session_start_ms })
+    }
+}
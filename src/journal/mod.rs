@@ -0,0 +1,189 @@
+// src/journal/mod.rs
+
+//! This module builds the trade journal from the stored history (see `storage`): fills are grouped
+//! into round trips, from the fill opening a position leg to the one bringing it back to flat, with
+//! the average entry and exit prices, holding time, realized PnL, fees, and the signal (and its
+//! strategy) whose order opened the trip. Operators can annotate trips with notes, and the journal
+//! is exported as CSV or JSON for spreadsheet review (see the `journal` binary and `admin`).
+//!
+//! Legs are tracked per symbol and position side (`LONG`/`SHORT` in hedge mode, one leg in one-way
+//! mode); a fill reversing a one-way position closes the trip and opens the next one with the rest.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::storage::{FillRecord, SignalRecord, Storage};
+
+/// Positions smaller than this count as flat.
+const FLAT_EPSILON: f64 = 1e-9;
+
+/// A round-trip trade.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundTrip {
+    pub id: String, // `<symbol>-<side>-<entry time ms>`, stable across exports; notes refer to it
+    pub symbol: String,
+    pub side: String, // LONG or SHORT
+    pub entry_time_ms: i64,
+    pub exit_time_ms: Option<i64>, // `None` while the trip is open
+    pub holding_secs: Option<f64>,
+    pub quantity: f64, // Largest position size of the trip
+    pub entry_price: f64, // Average price of the fills adding to the position
+    pub exit_price: Option<f64>, // Average price of the fills reducing it
+    pub realized_pnl: f64, // As reported by the exchange, before fees
+    pub fees: f64,
+    pub net_pnl: f64, // Realized PnL minus fees
+    pub fills: usize,
+    pub entry_client_order_id: String,
+    pub tracking_id: Option<String>, // Of the webhook signal that placed the entry order
+    pub signal: Option<String>,
+    pub strategy: Option<String>,
+    pub note: Option<String>,
+}
+
+/// A trip being built.
+struct OpenTrip {
+    trip: RoundTrip,
+    position: f64, // Signed: positive for long trips
+    entry_quantity: f64,
+    entry_value: f64,
+    exit_quantity: f64,
+    exit_value: f64,
+}
+
+impl OpenTrip {
+    fn open(fill: &FillRecord, signed_quantity: f64, fees: f64) -> Self {
+        let side = if signed_quantity > 0.0 { "LONG" } else { "SHORT" };
+        let quantity = signed_quantity.abs();
+        Self {
+            trip: RoundTrip {
+                id: format!("{}-{}-{}", fill.symbol, side, fill.time_ms),
+                symbol: fill.symbol.clone(),
+                side: side.to_string(),
+                entry_time_ms: fill.time_ms,
+                exit_time_ms: None,
+                holding_secs: None,
+                quantity,
+                entry_price: fill.price,
+                exit_price: None,
+                realized_pnl: 0.0,
+                fees,
+                net_pnl: -fees,
+                fills: 1,
+                entry_client_order_id: fill.client_order_id.clone(),
+                tracking_id: None,
+                signal: None,
+                strategy: None,
+                note: None,
+            },
+            position: signed_quantity,
+            entry_quantity: quantity,
+            entry_value: quantity * fill.price,
+            exit_quantity: 0.0,
+            exit_value: 0.0,
+        }
+    }
+
+    fn finish(mut self) -> RoundTrip {
+        let trip = &mut self.trip;
+        trip.entry_price = if self.entry_quantity > 0.0 { self.entry_value / self.entry_quantity } else { 0.0 };
+        trip.exit_price = (self.exit_quantity > 0.0).then(|| self.exit_value / self.exit_quantity);
+        trip.net_pnl = trip.realized_pnl - trip.fees;
+        self.trip
+    }
+}
+
+/// Groups fills (oldest first) into round trips, the closed ones first by entry time, then the
+/// open ones. Signals link each trip to the signal whose order opened it.
+pub fn round_trips(fills: &[FillRecord], signals: &[SignalRecord]) -> Vec<RoundTrip> {
+    let mut open: HashMap<(String, String), OpenTrip> = HashMap::new();
+    let mut closed = Vec::new();
+    for fill in fills.iter().filter(|fill| fill.quantity > 0.0) {
+        let leg = (fill.symbol.clone(), fill.position_side.clone().unwrap_or_else(|| "BOTH".to_string()));
+        let signed_quantity = if fill.side.eq_ignore_ascii_case("BUY") { fill.quantity } else { -fill.quantity };
+        let Some(mut current) = open.remove(&leg) else {
+            open.insert(leg, OpenTrip::open(fill, signed_quantity, fill.commission));
+            continue;
+        };
+        current.trip.fills += 1;
+        if current.position.signum() == signed_quantity.signum() {
+            // Adds to the position
+            current.position += signed_quantity;
+            current.entry_quantity += fill.quantity;
+            current.entry_value += fill.quantity * fill.price;
+            current.trip.quantity = current.trip.quantity.max(current.position.abs());
+            current.trip.fees += fill.commission;
+            open.insert(leg, current);
+            continue;
+        }
+        // Reduces the position; the rest of a reversing fill opens the next trip
+        let closing = fill.quantity.min(current.position.abs());
+        let rest = fill.quantity - closing;
+        current.position += signed_quantity.signum() * closing;
+        current.exit_quantity += closing;
+        current.exit_value += closing * fill.price;
+        current.trip.realized_pnl += fill.realized_pnl;
+        current.trip.fees += fill.commission * closing / fill.quantity;
+        if current.position.abs() > FLAT_EPSILON {
+            open.insert(leg, current);
+            continue;
+        }
+        current.trip.exit_time_ms = Some(fill.time_ms);
+        current.trip.holding_secs = Some((fill.time_ms - current.trip.entry_time_ms) as f64 / 1000.0);
+        closed.push(current.finish());
+        if rest > FLAT_EPSILON {
+            open.insert(leg, OpenTrip::open(fill, signed_quantity.signum() * rest, fill.commission * rest / fill.quantity));
+        }
+    }
+    let mut still_open: Vec<RoundTrip> = open.into_values().map(OpenTrip::finish).collect();
+    still_open.sort_by(|a, b| a.entry_time_ms.cmp(&b.entry_time_ms).then_with(|| a.id.cmp(&b.id)));
+    closed.sort_by_key(|trip| trip.entry_time_ms);
+    closed.extend(still_open);
+
+    let by_order: HashMap<&str, &SignalRecord> = signals.iter()
+        .filter_map(|signal| signal.client_order_id.as_deref().map(|id| (id, signal)))
+        .collect();
+    for trip in &mut closed {
+        if let Some(signal) = by_order.get(trip.entry_client_order_id.as_str()) {
+            trip.tracking_id = signal.tracking_id.clone();
+            trip.signal = Some(signal.signal.clone());
+            trip.strategy = signal.payload.get("strategy").and_then(|s| s.as_str()).map(str::to_string);
+        }
+    }
+    closed
+}
+
+/// Reads a `since` time (RFC 3339, e.g. `2024-05-01T00:00:00Z`) as milliseconds.
+pub fn parse_time(s: &str) -> Result<i64, String> {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.timestamp_millis())
+        .map_err(|e| format!("Invalid time '{}': {}", s, e))
+}
+
+/// Builds the journal of the trips entered at or after `since_ms`, with their notes.
+pub async fn load_journal(storage: &dyn Storage, since_ms: i64) -> Result<Vec<RoundTrip>, String> {
+    // Built from the whole history, so trips entered before `since_ms` do not look like new ones
+    let mut trips = round_trips(&storage.fills(0).await?, &storage.signals(0).await?);
+    trips.retain(|trip| trip.entry_time_ms >= since_ms);
+    let mut notes = storage.notes().await?;
+    for trip in &mut trips {
+        trip.note = notes.remove(&trip.id);
+    }
+    Ok(trips)
+}
+
+/// Renders the journal as CSV, one trip per row.
+pub fn to_csv(trips: &[RoundTrip]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for trip in trips {
+        writer.serialize(trip).map_err(|e| format!("Failed to write the journal as CSV: {}", e))?;
+    }
+    let bytes = writer.into_inner().map_err(|e| format!("Failed to write the journal as CSV: {}", e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Failed to write the journal as CSV: {}", e))
+}
+
+/// Renders the journal as a JSON array.
+pub fn to_json(trips: &[RoundTrip]) -> Result<String, String> {
+    serde_json::to_string_pretty(trips).map_err(|e| format!("Failed to write the journal as JSON: {}", e))
+}
@@ -7,4 +7,33 @@ pub mod tui;
 pub mod websocket;
 pub mod websocket_stream;
 pub mod account_info;
-pub mod webhook;
\ No newline at end of file
+pub mod webhook;
+pub mod session;
+pub mod order_book;
+pub mod maker_pnl;
+pub mod config;
+pub mod market_event;
+pub mod metrics;
+pub mod performance;
+pub mod experiment;
+pub mod risk;
+pub mod indicators;
+pub mod events;
+pub mod lifecycle;
+pub mod patterns;
+pub mod currency;
+pub mod data;
+pub mod arming;
+pub mod engine;
+pub mod candles;
+pub mod tunnel;
+pub mod notify;
+pub mod telemetry;
+pub mod admin;
+pub mod dashboard;
+pub mod storage;
+pub mod journal;
+pub mod reconcile;
+pub mod recovery;
+#[cfg(feature = "testnet-tools")]
+pub mod testnet;
@@ -0,0 +1,194 @@
+// src/lifecycle/mod.rs
+
+//! This module orchestrates the startup, shutdown and restart of the bot's subsystems in a fixed
+//! dependency order:
+//!
+//! persistence → exchange clients → user data stream → strategies → webhook
+//!
+//! Each subsystem is a task that signals readiness (`Readiness::ready`) once it can serve its
+//! dependents, e.g. the exchange session after `session.logon`, or the webhook after its listener
+//! is bound. The `Supervisor` only starts the next subsystem once the previous one is ready, so the
+//! webhook cannot accept signals before the WebSocket session is logged on. Subsystems are stopped
+//! in reverse order, and restarting one also restarts everything that depends on it.
+
+use std::future::Future;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use tracing::{error, info, warn};
+use tokio::sync::{oneshot, watch};
+use tokio::task::JoinHandle;
+
+/// Startup stages, in dependency order. A subsystem may only depend on earlier stages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    Persistence,
+    ExchangeClients,
+    UserDataStream,
+    Strategies,
+    Webhook,
+}
+
+/// Handed to a subsystem to signal that it is ready to serve its dependents.
+/// Dropping it without calling `ready` fails the startup.
+pub struct Readiness(Option<oneshot::Sender<()>>);
+
+impl Readiness {
+    /// Signals readiness.
+    pub fn ready(mut self) {
+        if let Some(tx) = self.0.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+/// Resolves when the supervisor asks the subsystem to stop.
+#[derive(Debug, Clone)]
+pub struct ShutdownSignal(watch::Receiver<bool>);
+
+impl ShutdownSignal {
+    /// Waits until shutdown is requested.
+    pub async fn wait(&mut self) {
+        let _ = self.0.wait_for(|stop| *stop).await;
+    }
+
+    /// Returns true once shutdown was requested.
+    pub fn is_requested(&self) -> bool {
+        *self.0.borrow()
+    }
+}
+
+type SubsystemFn = Box<dyn FnMut(Readiness, ShutdownSignal) -> BoxFuture<'static, Result<(), String>> + Send>;
+
+struct Running {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<Result<(), String>>,
+}
+
+struct Subsystem {
+    name: String,
+    stage: Stage,
+    start_timeout: Duration,
+    factory: SubsystemFn, // Called again on restart
+    running: Option<Running>,
+}
+
+/// Status of a registered subsystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubsystemStatus {
+    pub name: String,
+    pub stage: Stage,
+    pub running: bool, // Started and its task has not finished
+}
+
+/// Starts subsystems in stage order, waiting for each to be ready, and stops them in reverse order.
+pub struct Supervisor {
+    subsystems: Vec<Subsystem>,
+    stop_timeout: Duration,
+}
+
+impl Supervisor {
+    /// Creates a supervisor; a subsystem that does not stop within `stop_timeout` is aborted.
+    pub fn new(stop_timeout: Duration) -> Self {
+        Self { subsystems: Vec::new(), stop_timeout }
+    }
+
+    /// Starts a subsystem and waits until it is ready (or fails, or `start_timeout` elapses).
+    /// Subsystems must be started in stage order. `factory` is called again on every restart.
+    pub async fn start<F, Fut>(&mut self, stage: Stage, name: &str, start_timeout: Duration, mut factory: F) -> Result<(), String>
+    where
+        F: FnMut(Readiness, ShutdownSignal) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        if self.subsystems.iter().any(|s| s.name == name) {
+            return Err(format!("Subsystem '{}' is already registered", name));
+        }
+        if let Some(last) = self.subsystems.last().filter(|s| s.stage > stage) {
+            return Err(format!("Cannot start '{}' ({:?}) after '{}' ({:?}) depends on it", name, stage, last.name, last.stage));
+        }
+        self.subsystems.push(Subsystem {
+            name: name.to_string(),
+            stage,
+            start_timeout,
+            factory: Box::new(move |ready, shutdown| factory(ready, shutdown).boxed()),
+            running: None,
+        });
+        let result = self.launch(self.subsystems.len() - 1).await;
+        if result.is_err() {
+            self.subsystems.pop();
+        }
+        result
+    }
+
+    async fn launch(&mut self, index: usize) -> Result<(), String> {
+        let subsystem = &mut self.subsystems[index];
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let mut task = tokio::spawn((subsystem.factory)(Readiness(Some(ready_tx)), ShutdownSignal(shutdown_rx)));
+
+        match tokio::time::timeout(subsystem.start_timeout, ready_rx).await {
+            Ok(Ok(())) => {
+                info!("Subsystem '{}' ({:?}) is ready", subsystem.name, subsystem.stage);
+                subsystem.running = Some(Running { shutdown: shutdown_tx, task });
+                Ok(())
+            },
+            Ok(Err(_)) => {
+                // Readiness was dropped: the task ended (or gave up) before becoming ready
+                let reason = match (&mut task).await {
+                    Ok(Ok(())) => "it exited".to_string(),
+                    Ok(Err(e)) => e,
+                    Err(e) => e.to_string(),
+                };
+                Err(format!("Subsystem '{}' failed to start: {}", subsystem.name, reason))
+            },
+            Err(_) => {
+                task.abort();
+                Err(format!("Subsystem '{}' was not ready within {:?}", subsystem.name, subsystem.start_timeout))
+            },
+        }
+    }
+
+    /// Stops the running subsystems from `index` onwards, last started first.
+    async fn stop_from(&mut self, index: usize) {
+        for subsystem in self.subsystems[index..].iter_mut().rev() {
+            let Some(mut running) = subsystem.running.take() else { continue };
+            let _ = running.shutdown.send(true);
+            match tokio::time::timeout(self.stop_timeout, &mut running.task).await {
+                Ok(Ok(Ok(()))) => info!("Subsystem '{}' stopped", subsystem.name),
+                Ok(Ok(Err(e))) => error!("Subsystem '{}' stopped with an error: {}", subsystem.name, e),
+                Ok(Err(e)) => error!("Subsystem '{}' panicked or was cancelled: {}", subsystem.name, e),
+                Err(_) => {
+                    warn!("Subsystem '{}' did not stop within {:?}; aborting it", subsystem.name, self.stop_timeout);
+                    running.task.abort();
+                },
+            }
+        }
+    }
+
+    /// Stops every subsystem in reverse startup order.
+    pub async fn shutdown(&mut self) {
+        self.stop_from(0).await;
+    }
+
+    /// Restarts a subsystem together with every subsystem started after it (its dependents):
+    /// they are stopped in reverse order, then started again in order.
+    pub async fn restart(&mut self, name: &str) -> Result<(), String> {
+        let index = self.subsystems.iter().position(|s| s.name == name)
+            .ok_or_else(|| format!("Unknown subsystem '{}'", name))?;
+        self.stop_from(index).await;
+        for i in index..self.subsystems.len() {
+            self.launch(i).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the status of every subsystem, in startup order.
+    pub fn status(&self) -> Vec<SubsystemStatus> {
+        self.subsystems.iter().map(|s| SubsystemStatus {
+            name: s.name.clone(),
+            stage: s.stage,
+            running: s.running.as_ref().is_some_and(|r| !r.task.is_finished()),
+        }).collect()
+    }
+}
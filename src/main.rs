@@ -1,12 +1,43 @@
 use trading_bot::websocket::WebSocketClient;
 use trading_bot::rest_api::RestClient; // Add REST client import
+use trading_bot::rest_api::ratelimit::LimitKind;
 use trading_bot::webhook; // Import the webhook listener module
-use log::{info, error, warn};
-use std::env;
+use trading_bot::admin; // Admin API on its own port
+use trading_bot::dashboard; // Event stream for browser dashboards
+use trading_bot::config::{self, RuntimeConfig}; // Runtime configuration (env vars or mounted secret files)
+use tracing::{info, info_span, error, warn, Instrument};
 use dotenv::dotenv;
 use tokio::signal; // For graceful shutdown
-use ngrok::{config::ForwarderBuilder, tunnel::EndpointInfo}; // Import ngrok crates
-use url::Url; // For Url::parse
+use trading_bot::account_info::AccountDiagnostics;
+use trading_bot::strategy::BacktestConfig;
+use trading_bot::strategy::genetic::{self, GeneticConfig};
+use trading_bot::strategy::optimizer::{self, GridSearchConfig};
+use trading_bot::strategy::walk_forward::{self, WalkForwardConfig};
+use trading_bot::events::{self, BotEvent, EventLog};
+use trading_bot::storage::{self, EquitySnapshot, FillRecord};
+use trading_bot::reconcile;
+use trading_bot::recovery::{StateStore, STATE_FILE};
+use trading_bot::order::bracket::order_update_from_message;
+use trading_bot::lifecycle::{Stage, Supervisor};
+use trading_bot::risk::{ExecutionPolicies, RiskPolicy};
+use trading_bot::arming::{Interlock, ARMING_FILE};
+use trading_bot::notify::{self, Notification, Notifications};
+use trading_bot::notify::telegram::{self, TelegramNotifier};
+use trading_bot::websocket::user_data::run_user_data_stream;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often the account configuration and order rate limits are re-fetched.
+const ACCOUNT_DIAGNOSTICS_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// How often the daily loss breaker is checked between signals.
+const LOSS_BREAKER_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the trading schedule is checked for the end of a session.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How long a subsystem may take to become ready at startup.
+const SUBSYSTEM_START_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a subsystem may take to stop before it is aborted.
+const SUBSYSTEM_STOP_TIMEOUT: Duration = Duration::from_secs(5);
 
 // Main application entry point
 #[tokio::main]
@@ -14,88 +45,164 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Load environment variables
     dotenv().ok();
     // Initialize logging
-    env_logger::init();
+    trading_bot::telemetry::init();
+
+    // `trading_bot backtest [--config backtest.toml] [--fast-ema 21 ...] [--symbol BTCUSDT --from 2021-01-01] [--output report.json]`
+    // runs the EMA crossover backtest instead
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("backtest") {
+        let backtest_config = BacktestConfig::from_args(&args[2..])?;
+        return trading_bot::strategy::run(&backtest_config).await.map_err(|e| e.to_string().into());
+    }
+    // `trading_bot walk-forward [backtest flags] --in-sample 1000 --out-of-sample 250 --fast-range 8:34:2 --slow-range 34:89:5`
+    // optimizes the EMA periods on rolling in-sample windows and reports the out-of-sample performance
+    if args.get(1).map(String::as_str) == Some("walk-forward") {
+        let (walk_forward, rest) = WalkForwardConfig::from_args(&args[2..])?;
+        let backtest_config = BacktestConfig::from_args(&rest)?;
+        return walk_forward::run(&backtest_config, &walk_forward).await.map(|_| ()).map_err(|e| e.to_string().into());
+    }
+    // `trading_bot optimize [backtest flags] --fast-range 8:34:2 --slow-range 34:89:5 --rr-values 1,2,3 --risk-values 0.005,0.01`
+    // backtests every combination of the grid in parallel and ranks them
+    if args.get(1).map(String::as_str) == Some("optimize") {
+        let (grid, rest) = GridSearchConfig::from_args(&args[2..])?;
+        let backtest_config = BacktestConfig::from_args(&rest)?;
+        return optimizer::run(&backtest_config, &grid).await.map(|_| ()).map_err(|e| e.to_string().into());
+    }
+    // `trading_bot genetic [backtest flags] --fast-range 5:50 --slow-range 20:200 --rr-bounds 1:5 --risk-bounds 0.005:0.02`
+    // evolves the settings with a genetic algorithm instead of sweeping every combination
+    if args.get(1).map(String::as_str) == Some("genetic") {
+        let (genetic_config, rest) = GeneticConfig::from_args(&args[2..])?;
+        let backtest_config = BacktestConfig::from_args(&rest)?;
+        return genetic::run(&backtest_config, &genetic_config).await.map(|_| ()).map_err(|e| e.to_string().into());
+    }
 
     info!("--- Starting Trading Bot Application ---");
 
-    // Load API keys and URLs from environment variables
-    let api_key = env::var("BINANCE_API_KEY").expect("BINANCE_API_KEY not set in .env");
-    let secret_key = env::var("BINANCE_SECRET_KEY").expect("BINANCE_SECRET_KEY not set in .env");
-    let ws_api_base_url = env::var("BINANCE_WS_API_BASE_URL").expect("BINANCE_WS_API_BASE_URL not set in .env");
-    let rest_api_base_url = env::var("BINANCE_REST_API_BASE_URL").expect("BINANCE_REST_API_BASE_URL not set in .env");
-    let webhook_local_listen_addr = env::var("WEBHOOK_LOCAL_LISTEN_ADDR").expect("WEBHOOK_LOCAL_LISTEN_ADDR not set in .env");
+    // Load API keys, URLs and runtime settings from environment variables or mounted secret files
+    let runtime_config = RuntimeConfig::from_env()?;
+    // The webhook places orders, so it fails closed without a secret
+    runtime_config.check_webhook_auth()?;
+    if runtime_config.container_mode {
+        info!("Running in container mode (state dir: {})", runtime_config.state_dir.display());
+    }
+    runtime_config.prepare_state_dir()?;
+
+    // --- Notifications (orders, fills, rejections, breaker trips, disconnects) to Telegram, critical ones by email ---
+    let mut channels = Vec::new();
+    if let Some(telegram) = runtime_config.telegram.clone() {
+        info!("Sending {:?}+ notifications to Telegram chat {}", telegram.min_severity, telegram.chat_id);
+        channels.push(telegram.into_channel());
+    }
+    if let Some(email) = runtime_config.email.clone() {
+        info!("Sending {:?}+ notifications by email to {}", email.min_severity, email.to.join(", "));
+        channels.push(email.into_channel());
+    }
+    let notifications = (!channels.is_empty()).then(|| Notifications::start(channels));
+    let run_marker = runtime_config.state_path(notify::RUN_MARKER_FILE);
+    let restarted = notify::mark_running(&run_marker)?;
+    if let Some(notifications) = notifications.clone() {
+        if let Some(restarted) = restarted {
+            notifications.notify(restarted);
+        }
+        notify::notify_on_panic(notifications);
+    }
+
+    // --- Safety interlock: a fresh install or a new deployment starts disarmed until an operator arms it ---
+    let mut interlock = Interlock::load(runtime_config.state_path(ARMING_FILE), &runtime_config.deployment_id);
+    if let Some(notifications) = notifications.clone() {
+        interlock = interlock.with_notifications(notifications);
+    }
+    let interlock = Arc::new(interlock);
+    if let Some(telegram) = runtime_config.telegram.clone().filter(|t| !t.operators.is_empty()) {
+        tokio::spawn(telegram::serve_arming_commands(TelegramNotifier::new(telegram), interlock.clone(), telegram::COMMAND_POLL_TIMEOUT));
+    }
+    let arming = interlock.state();
+    if arming.armed {
+        info!("Live trading is ARMED (by {})", arming.armed_by.as_deref().unwrap_or("unknown"));
+    } else {
+        warn!("Live trading is DISARMED ({}); signals are logged but not executed until an operator arms the bot", arming.reason.as_deref().unwrap_or("not armed"));
+        if runtime_config.admin.is_none() && runtime_config.telegram.as_ref().is_none_or(|t| t.operators.is_empty()) {
+            warn!("Neither the admin API nor Telegram arming is enabled: arm the bot with the `arming` binary and restart, or set ADMIN_PORT/ADMIN_TOKEN to use POST /admin/arming/arm");
+        }
+    }
+
+    // --- Start the health/metrics server (enabled by default in container mode) ---
+    let extra_metrics = config::ExtraMetrics::default();
+    if let Some(health_listen_addr) = runtime_config.health_listen_addr.clone() {
+        let extra_metrics = extra_metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = config::run_health_server(&health_listen_addr, extra_metrics).await {
+                error!("Health/metrics server failed: {}", e);
+            }
+        });
+    }
+    // --- Export the same metrics to an OpenTelemetry collector (spans are exported by the log subscriber) ---
+    #[cfg(feature = "otlp")]
+    if let Some(otlp) = trading_bot::telemetry::otlp::OtlpConfig::from_env()? {
+        info!("Exporting spans and metrics over OTLP to {}", otlp.endpoint);
+        tokio::spawn(trading_bot::telemetry::otlp::export_metrics(otlp, extra_metrics.clone()));
+    }
 
     // --- Initialize WebSocketClient (needed for webhook order dispatch) ---
-    let ws_client = WebSocketClient::new(
-        api_key.clone(), // Clone for ws_client
-        secret_key.clone(), // Clone for ws_client
-        ws_api_base_url.clone(),
-    ).await;
+    let ws_client = Arc::new(WebSocketClient::new(
+        runtime_config.api_key.clone(), // Clone for ws_client
+        runtime_config.secret_key.clone(), // Clone for ws_client
+        runtime_config.ws_api_base_url.clone(),
+    ).await.with_recv_window(runtime_config.recv_window_ms));
+    if let Some(notifications) = notifications.clone() {
+        tokio::spawn(notify::watch_connection(ws_client.clone(), notifications, notify::CONNECTION_CHECK_INTERVAL));
+    }
 
     // --- Initialize RestClient (needed for fetching current prices) ---
-    let rest_client = RestClient::new(
-        api_key.clone(), // Clone for rest_client
-        secret_key.clone(), // Clone for rest_client
-        rest_api_base_url,
-    );
-
-    // Perform WebSocket session logon (important for authenticated WS API calls)
-    info!("Attempting WebSocket Session Logon...");
-    match ws_client.session_logon().await {
-        Ok(logon_result) => info!("WebSocket Session Logon Result: {:?}", logon_result),
-        Err(e) => error!("Error during WebSocket session logon: {}", e),
-    }
-
-    // --- Set up ngrok tunnel ---
-    info!("Setting up ngrok tunnel...");
-    let session = ngrok::Session::builder()
-        .authtoken_from_env() // Reads NGROK_AUTHTOKEN from environment
-        .connect()
-        .await
-        .map_err(|e| format!("Failed to connect to ngrok session: {}", e))?;
-
-    println!("{}",webhook_local_listen_addr);
-
-    // Forward HTTP traffic from ngrok to the local webhook listener address
-    // The `webhook_local_listen_addr` should be the address Axum binds to.
-    let listener = session
-        .http_endpoint()
-        // .traffic_policy(r#"{"on_http_request": [{"actions": [{"type": "oauth","config": {"provider": "google"}}]}]}"#) // Uncomment for OAuth
-        .listen_and_forward(Url::parse(&format!("http://{}/", webhook_local_listen_addr)).unwrap()) // Forward to local Axum server
-        .await
-        .map_err(|e| format!("Failed to create ngrok tunnel: {}", e))?;
-
-    let public_ngrok_url = listener.url().to_string();
-    println!("\n--- TradingView Webhook URL ---");
-    println!("Configure your TradingView alert to POST to: {}/webhook", public_ngrok_url);
-    println!("-------------------------------\n");
-    info!("ngrok tunnel established at: {}", public_ngrok_url);
-
-
-    // --- Spawn the webhook listener in a separate Tokio task ---
-    // The webhook listener (Axum server) binds to the local address.
-    let webhook_handle = tokio::spawn(async move {
-        if let Err(e) = webhook::run_webhook_listener(
-            ws_client,
-            rest_client, // Pass the REST client to the webhook listener
-            &webhook_local_listen_addr // Axum binds to this local address
-        ).await {
-            error!("Webhook listener failed: {}", e);
+    let rest_client = Arc::new(RestClient::new(
+        runtime_config.api_key.clone(), // Clone for rest_client
+        runtime_config.secret_key.clone(), // Clone for rest_client
+        runtime_config.rest_api_base_url.clone(),
+    ).with_recv_window(runtime_config.recv_window_ms).with_http_client(runtime_config.http.build_client()?));
+    if let Some(notifications) = notifications.clone() {
+        tokio::spawn(notify::watch_rest_ban(rest_client.clone(), notifications, notify::CONNECTION_CHECK_INTERVAL));
+    }
+
+    // Subsystems start in dependency order, each once the previous one is ready, and stop in reverse:
+    // persistence → exchange clients → user data stream → strategies → webhook
+    let mut supervisor = Supervisor::new(SUBSYSTEM_STOP_TIMEOUT);
+    if let Err(e) = start_subsystems(&mut supervisor, &runtime_config, ws_client, rest_client, extra_metrics, interlock, notifications).await {
+        error!("Startup failed: {}", e);
+        supervisor.shutdown().await;
+        return Err(e.into());
+    }
+
+    // --- Expose the webhook through a tunnel ---
+    // Skipped when none is configured, e.g. in a container whose webhook port is published directly.
+    // The tunnel is kept alive until shutdown.
+    let _tunnel = match &runtime_config.tunnel {
+        Some(config) => {
+            let mut tunnel = config.build()?;
+            info!("Setting up {} tunnel...", tunnel.name());
+            let public_url = tunnel.open(&runtime_config.webhook_listen_addr).await?;
+            println!("\n--- TradingView Webhook URL ---");
+            println!("Configure your TradingView alert to POST to: {}/webhook", public_url);
+            println!("-------------------------------\n");
+            info!("{} tunnel established at: {}", tunnel.name(), public_url);
+            Some(tunnel)
+        },
+        None => {
+            let scheme = if runtime_config.webhook_tls.is_some() { "https" } else { "http" };
+            info!("No tunnel configured. Webhook listens on {}://{}/webhook", scheme, runtime_config.webhook_listen_addr);
+            None
         }
-    });
+    };
 
     info!("Application running. Press Ctrl+C to shut down gracefully.");
 
-    // Wait for Ctrl+C signal to gracefully shut down
-    signal::ctrl_c().await?;
-    info!("Ctrl+C received, shutting down...");
+    // Wait for Ctrl+C (or SIGTERM from `docker stop`) to gracefully shut down
+    wait_for_shutdown_signal().await?;
+    info!("Shutdown signal received, shutting down...");
 
-    // Give some time for tasks to shut down, then forcefully abort if necessary
-    tokio::select! {
-        _ = webhook_handle => { info!("Webhook listener task finished."); },
-        _ = tokio::time::sleep(tokio::time::Duration::from_secs(5)) => {
-            warn!("Webhook listener task did not shut down gracefully in time.");
-        }
+    // Stop the webhook first so no new signals arrive, then its dependencies
+    supervisor.shutdown().await;
+    if let Err(e) = notify::clear_run_marker(&run_marker) {
+        warn!("{}", e);
     }
 
     info!("Application shut down complete.");
@@ -103,3 +210,325 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     Ok(())
 }
 
+/// Starts the bot's subsystems in dependency order, each once the previous one is ready.
+async fn start_subsystems(
+    supervisor: &mut Supervisor,
+    runtime_config: &RuntimeConfig,
+    ws_client: Arc<WebSocketClient>,
+    rest_client: Arc<RestClient>,
+    extra_metrics: config::ExtraMetrics,
+    interlock: Arc<Interlock>,
+    notifications: Option<Notifications>,
+) -> Result<(), String> {
+    // --- Persistence: the event log (signals, decisions, fills, config) used to replay past decisions ---
+    let mut event_log = EventLog::open(runtime_config.state_path(events::EVENT_LOG_FILE))?;
+    if let Some(notifications) = notifications.clone() {
+        event_log = event_log.with_notifications(notifications);
+    }
+    let event_log = Arc::new(event_log);
+    let (webhook_notifications, stream_notifications) = (notifications.clone(), notifications);
+    info!("Recording events to {}", event_log.path().display());
+    let experiment_value = serde_json::to_value(&runtime_config.experiment).unwrap_or_default();
+    let log = event_log.clone();
+    supervisor.start(Stage::Persistence, "event_log", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+        let (log, experiment_value) = (log.clone(), experiment_value.clone());
+        async move {
+            log.append(chrono::Utc::now().timestamp_millis(), BotEvent::ConfigChange { key: "experiment".to_string(), value: experiment_value })?;
+            ready.ready();
+            shutdown.wait().await;
+            Ok(())
+        }
+    }).await?;
+
+    // Risk counters (the day's realized PnL, the last loss, the loss breaker's day) carry over restarts:
+    // restored from the recovery snapshot, or replayed from the event log when it has none
+    let state_store = Arc::new(StateStore::load(runtime_config.state_path(STATE_FILE))?);
+    state_store.log_recovered();
+    let risk = match state_store.state().risk {
+        Some(risk) => risk,
+        None => events::replay(&events::load_events(event_log.path())?, None).risk,
+    };
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    if risk.realized_pnl_on(now_ms) != 0.0 {
+        info!("Recovered realized PnL today: {:.2}", risk.realized_pnl_on(now_ms));
+    }
+    let today = chrono::Utc::now().date_naive();
+    if let Some(reason) = risk.loss_session.as_ref().filter(|session| session.day == today).and_then(|session| session.tripped.as_ref()) {
+        warn!("Daily loss breaker tripped earlier today: {}", reason);
+    }
+    let policies = Arc::new(ExecutionPolicies {
+        sizing: Some(Box::new(runtime_config.sizing)),
+        risk: [
+            runtime_config.exposure_limits.clone().map(|limits| Arc::new(limits) as Arc<dyn RiskPolicy>),
+            runtime_config.trading_schedule.clone().map(|schedule| Arc::new(schedule) as Arc<dyn RiskPolicy>),
+            runtime_config.funding_blackout.map(|blackout| Arc::new(blackout) as Arc<dyn RiskPolicy>),
+        ].into_iter().flatten().collect(),
+        loss_breaker: runtime_config.daily_loss_breaker,
+        state: Arc::new(std::sync::Mutex::new(risk)),
+        store: Some(state_store),
+    });
+
+    // --- Persistence: the history database (signals, orders, fills, equity snapshots) for offline analysis ---
+    let storage = match &runtime_config.storage {
+        Some(storage_config) => {
+            let storage = storage::open_storage(storage_config).await?;
+            info!("Storing history in {}", storage.location());
+            Some(storage)
+        },
+        None => None,
+    };
+
+    // --- Exchange clients: WebSocket session logon, then account diagnostics (fee tier, canTrade, rate limits) for /metrics ---
+    let (session_client, diagnostics_client) = (ws_client.clone(), rest_client.clone());
+    supervisor.start(Stage::ExchangeClients, "exchange_session", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+        let (ws_client, rest_client, extra_metrics) = (session_client.clone(), diagnostics_client.clone(), extra_metrics.clone());
+        async move {
+            info!("Attempting WebSocket Session Logon...");
+            let logon_result = ws_client.session_logon().await.map_err(|e| format!("WebSocket session logon failed: {}", e))?;
+            info!("WebSocket Session Logon Result: {:?}", logon_result);
+            ready.ready();
+
+            let mut interval = tokio::time::interval(ACCOUNT_DIAGNOSTICS_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = shutdown.wait() => return Ok(()),
+                    _ = interval.tick() => {},
+                }
+                match AccountDiagnostics::fetch(&rest_client).await {
+                    Ok(diagnostics) => {
+                        if diagnostics.account_config.as_ref().is_some_and(|c| !c.can_trade) {
+                            warn!("Account config reports canTrade=false; orders will be rejected.");
+                        }
+                        // Pace orders by the limits the exchange reports for the account
+                        for limit in &diagnostics.order_rate_limits {
+                            rest_client.rate_limiter().set_limit(LimitKind::Orders, Duration::from_secs(limit.window_secs()), limit.limit);
+                        }
+                        *extra_metrics.write().await = diagnostics.render_metrics();
+                    },
+                    Err(e) => warn!("Failed to refresh account diagnostics: {}", e),
+                }
+            }
+        }
+    }).await?;
+
+    // Startup reconciliation: adopt unknown orders, flag orphaned stops and unrecognized positions
+    if let Err(e) = reconcile::reconcile_on_startup(&rest_client, &event_log, storage.as_deref(), stream_notifications.as_ref()).await {
+        error!("Startup reconciliation failed, starting without it: {}", e);
+        if let Some(notifications) = &stream_notifications {
+            notifications.notify(Notification::Reconciliation { issues: vec![e] });
+        }
+    }
+
+    // Equity snapshots of the account, every `equity_snapshot_interval`
+    if let (Some(storage), Some(interval)) = (storage.clone(), runtime_config.equity_snapshot_interval) {
+        let snapshot_client = rest_client.clone();
+        supervisor.start(Stage::ExchangeClients, "equity_snapshots", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+            let (rest_client, storage) = (snapshot_client.clone(), storage.clone());
+            async move {
+                ready.ready();
+                let mut interval = tokio::time::interval(interval);
+                loop {
+                    tokio::select! {
+                        _ = shutdown.wait() => return Ok(()),
+                        _ = interval.tick() => {},
+                    }
+                    match rest_client.get_account_info().await {
+                        Ok(account) => {
+                            if let Err(e) = storage.record_equity(&EquitySnapshot::from_account(chrono::Utc::now().timestamp_millis(), &account)).await {
+                                error!("{}", e);
+                            }
+                        },
+                        Err(e) => warn!("Failed to take an equity snapshot: {}", e),
+                    }
+                }
+            }
+        }).await?;
+    }
+
+    // --- User data stream and the fill recorder, when the stream base URL is configured ---
+    if let Some(stream_base_url) = runtime_config.ws_stream_base_url.clone() {
+        let (tx, rx) = tokio::sync::mpsc::channel(256);
+        let stream_client = ws_client.clone();
+        supervisor.start(Stage::UserDataStream, "user_data_stream", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+            let (ws_client, stream_base_url, tx) = (stream_client.clone(), stream_base_url.clone(), tx.clone());
+            async move {
+                ws_client.start_user_data_stream().await?; // Fails early on a missing permission
+                ready.ready();
+                tokio::select! {
+                    _ = shutdown.wait() => Ok(()),
+                    result = run_user_data_stream(ws_client, stream_base_url, tx) => result,
+                }
+            }
+        }).await?;
+
+        // Strategies: consumers of the user data stream
+        let rx = Arc::new(tokio::sync::Mutex::new(rx));
+        let (log, fill_storage, fill_policies) = (event_log.clone(), storage.clone(), policies.clone());
+        supervisor.start(Stage::Strategies, "fill_recorder", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+            let (rx, log, notifications, storage, policies) = (rx.clone(), log.clone(), stream_notifications.clone(), fill_storage.clone(), fill_policies.clone());
+            async move {
+                let mut rx = rx.lock().await;
+                ready.ready();
+                loop {
+                    let message = tokio::select! {
+                        _ = shutdown.wait() => return Ok(()),
+                        message = rx.recv() => message.ok_or("User data channel closed")?,
+                    };
+                    // Margin calls and liquidations
+                    if let Some(notifications) = &notifications {
+                        Notification::from_user_data(&message).into_iter().for_each(|notification| notifications.notify(notification));
+                    }
+                    let Some(update) = order_update_from_message(&message) else { continue };
+                    let Some(fill) = BotEvent::from_order_update(&update) else { continue };
+                    // Same client order ID as the `order` span of the signal that placed the order
+                    let _span = info_span!("fill", client_order_id = %update.client_order_id).entered();
+                    info!("{} {} filled {} at {}", update.side, update.symbol, update.last_filled_quantity, update.last_filled_price);
+                    policies.on_order_update(&update); // Feeds the daily loss limit and cooldown, and saves them
+                    let time_ms = chrono::Utc::now().timestamp_millis();
+                    if let (Some(storage), Some(record)) = (storage.clone(), FillRecord::from_event(time_ms, &fill)) {
+                        // In the background, like the webhook's writes: the next update never waits for the database
+                        tokio::spawn(async move { storage.record_fill(&record).await.unwrap_or_else(|e| error!("{}", e)) }.in_current_span());
+                    }
+                    if let Err(e) = log.append(time_ms, fill) {
+                        error!("{}", e);
+                    }
+                }
+            }
+        }).await?;
+    }
+
+    // --- Webhook: only accepts signals once everything it depends on is ready ---
+    let experiment = runtime_config.experiment.clone();
+    if let Some(experiment) = &experiment {
+        info!("A/B test '{}' running with variants: {:?}", experiment.name, experiment.variants.iter().map(|v| &v.tag).collect::<Vec<_>>());
+    }
+    let mut strategies = HashMap::new();
+    for (name, strategy) in &runtime_config.webhook_strategies.strategies {
+        // Strategies trading on their own account get their own exchange clients
+        let clients = match &strategy.credentials {
+            Some(credentials) => {
+                let ws_client = Arc::new(WebSocketClient::new(credentials.api_key.clone(), credentials.secret_key.clone(), runtime_config.ws_api_base_url.clone()).await.with_recv_window(runtime_config.recv_window_ms));
+                ws_client.session_logon().await.map_err(|e| format!("WebSocket session logon for strategy {} failed: {}", name, e))?;
+                let rest_client = Arc::new(RestClient::new(credentials.api_key.clone(), credentials.secret_key.clone(), runtime_config.rest_api_base_url.clone())
+                    .with_recv_window(runtime_config.recv_window_ms)
+                    .with_http_client(runtime_config.http.build_client()?));
+                Some((ws_client, rest_client))
+            },
+            None => None,
+        };
+        info!("Webhook strategy '{}' configured{}", name, if clients.is_some() { " with its own account" } else { "" });
+        strategies.insert(name.clone(), webhook::strategies::StrategyRoute::new(strategy, clients, &policies));
+    }
+    let app_state = webhook::AppState {
+        ws_client,
+        rest_client,
+        experiment: experiment.map(Arc::new),
+        policies,
+        event_log: Some(event_log.clone()),
+        storage,
+        interlock: Some(interlock),
+        webhook_secret: runtime_config.webhook_secret.clone(),
+        ip_allowlist: runtime_config.webhook_ip_allowlist.clone().map(Arc::new),
+        admin_token: runtime_config.admin.as_ref().map(|admin| admin.token.clone()),
+        admin_allowlist: runtime_config.admin.as_ref().and_then(|admin| admin.allowlist.clone()).map(Arc::new),
+        symbol_configs: Arc::new(runtime_config.webhook_symbols.clone()),
+        strategies: Arc::new(strategies),
+        deduplicator: runtime_config.webhook_dedup_window.map(|window| Arc::new(webhook::dedup::AlertDeduplicator::new(window))),
+        rate_limiter: Some(Arc::new(webhook::ratelimit::RateLimiter::new(runtime_config.webhook_rate_limits))),
+        notifications: webhook_notifications,
+        signals: Arc::new(webhook::queue::SignalTracker::new()),
+        signal_queue: None,
+        started: Instant::now(),
+    }.with_signal_queue(webhook::queue::DEFAULT_QUEUE_CAPACITY);
+    let webhook_listen_addr = runtime_config.webhook_listen_addr.clone();
+    let tls = runtime_config.webhook_tls.as_ref().map(|files| files.load()).transpose()?;
+    let (webhook_state, dashboard_log) = (app_state.clone(), event_log);
+    supervisor.start(Stage::Webhook, "webhook", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+        let (app_state, listen_addr, tls) = (webhook_state.clone(), webhook_listen_addr.clone(), tls.clone());
+        async move {
+            webhook::serve_webhook_until(app_state, &listen_addr, tls, || ready.ready(), async move { shutdown.wait().await }).await
+        }
+    }).await?;
+
+    // --- Daily loss breaker: also checked between signals, so open positions' losses trip it ---
+    if let Some(breaker) = runtime_config.daily_loss_breaker {
+        info!("Daily loss breaker: entries stop for the day after a loss of {:.2}{}", breaker.max_loss, if breaker.flatten { ", positions are flattened" } else { "" });
+        let breaker_state = app_state.clone();
+        supervisor.start(Stage::Webhook, "loss_breaker", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+            let app_state = breaker_state.clone();
+            async move {
+                ready.ready();
+                let mut interval = tokio::time::interval(LOSS_BREAKER_INTERVAL);
+                loop {
+                    tokio::select! {
+                        _ = shutdown.wait() => return Ok(()),
+                        _ = interval.tick() => {},
+                    }
+                    if let Err(e) = webhook::control::watch_loss_breaker(&app_state).await {
+                        warn!("{}", e);
+                    }
+                }
+            }
+        }).await?;
+    }
+
+    // --- Trading schedule: entries only during the sessions, positions optionally closed when one ends ---
+    if let Some(schedule) = runtime_config.trading_schedule.clone() {
+        info!("Trading sessions: {}{}", schedule.spec, if schedule.flatten_outside { ", positions are flattened outside them" } else { "" });
+        if schedule.flatten_outside {
+            let schedule_state = app_state.clone();
+            supervisor.start(Stage::Webhook, "trading_schedule", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+                let (app_state, schedule) = (schedule_state.clone(), schedule.clone());
+                async move {
+                    ready.ready();
+                    let mut interval = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+                    let mut was_open = None;
+                    loop {
+                        tokio::select! {
+                            _ = shutdown.wait() => return Ok(()),
+                            _ = interval.tick() => {},
+                        }
+                        webhook::control::watch_schedule(&app_state, &schedule, &mut was_open).await;
+                    }
+                }
+            }).await?;
+        }
+    }
+
+    // --- Admin API: introspection and control for operators, on its own port with its own token ---
+    if let Some(admin_config) = runtime_config.admin.clone() {
+        supervisor.start(Stage::Webhook, "admin_api", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+            let (app_state, admin_config) = (app_state.clone(), admin_config.clone());
+            async move {
+                admin::serve_admin_until(app_state, &admin_config, || ready.ready(), async move { shutdown.wait().await }).await
+            }
+        }).await?;
+    }
+
+    // --- Dashboard stream: pushes signals, orders, fills and PnL to browser dashboards ---
+    if let Some(dashboard_config) = runtime_config.dashboard.clone() {
+        supervisor.start(Stage::Webhook, "dashboard", SUBSYSTEM_START_TIMEOUT, move |ready, mut shutdown| {
+            let (event_log, dashboard_config) = (dashboard_log.clone(), dashboard_config.clone());
+            async move {
+                dashboard::serve_dashboard_until(event_log, &dashboard_config, || ready.ready(), async move { shutdown.wait().await }).await
+            }
+        }).await?;
+    }
+    Ok(())
+}
+
+/// Resolves on Ctrl+C, or on SIGTERM (sent by `docker stop`) on Unix.
+async fn wait_for_shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut sigterm = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = signal::ctrl_c() => result,
+            _ = sigterm.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        signal::ctrl_c().await
+    }
+}
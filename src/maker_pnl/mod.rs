@@ -0,0 +1,175 @@
+// src/maker_pnl/mod.rs
+
+//! This module decomposes the PnL of maker/grid strategies into its sources, per UTC day:
+//! - spread capture: the edge earned against the mid price at the moment of each fill,
+//! - inventory PnL: mark-to-market gains/losses of the inventory held while the mid moves,
+//! - fees: commissions paid (or rebates received, as negative fees).
+//!
+//! A strategy that earns mostly from spread capture is quoting profitably; one whose PnL is
+//! dominated by inventory PnL is making (or losing) money from directional exposure.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::currency::ReportingCurrency;
+use crate::order::OrderSide;
+use crate::order_book::OrderBookFeatures;
+use crate::streams::OrderUpdateEvent;
+
+/// A single fill of a maker strategy order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub timestamp_ms: i64,
+    pub side: OrderSide,
+    pub price: f64,
+    pub quantity: f64,
+    pub fee: f64, // Commission in the quote asset; negative for rebates
+    pub is_maker: bool,
+}
+
+impl Fill {
+    /// Builds a fill from a user data stream order update, if the update is a trade execution.
+    /// The commission is assumed to be charged in the quote asset.
+    pub fn from_order_update(event: &OrderUpdateEvent) -> Option<Self> {
+        if event.current_execution_type != "TRADE" {
+            return None;
+        }
+        let side = match event.side.as_str() {
+            "BUY" => OrderSide::Buy,
+            "SELL" => OrderSide::Sell,
+            _ => return None,
+        };
+        Some(Self {
+            timestamp_ms: event.trade_time as i64,
+            side,
+            price: event.last_executed_price.parse().ok()?,
+            quantity: event.last_executed_quantity.parse().ok()?,
+            fee: event.commission_amount.parse().unwrap_or(0.0),
+            is_maker: event.is_maker_side,
+        })
+    }
+}
+
+/// A mid price observation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidPrice {
+    pub timestamp_ms: i64,
+    pub price: f64,
+}
+
+impl From<&OrderBookFeatures> for MidPrice {
+    fn from(features: &OrderBookFeatures) -> Self {
+        Self {
+            timestamp_ms: features.event_time as i64,
+            price: features.mid_price,
+        }
+    }
+}
+
+/// PnL decomposition for a single UTC day.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DailyPnlBreakdown {
+    pub date: Option<NaiveDate>,
+    pub spread_capture: f64,
+    pub inventory_pnl: f64,
+    pub fees: f64,
+    pub net_pnl: f64, // spread_capture + inventory_pnl - fees
+    pub fills: usize,
+    pub maker_fills: usize,
+    pub traded_volume: f64, // Quote volume
+    pub ending_inventory: f64, // Base asset inventory at the end of the day
+}
+
+/// Returns the UTC date of a millisecond timestamp.
+fn utc_date(timestamp_ms: i64) -> Option<NaiveDate> {
+    DateTime::<Utc>::from_timestamp_millis(timestamp_ms).map(|dt| dt.date_naive())
+}
+
+/// Events processed in time order by `decompose_pnl`.
+enum PnlEvent<'a> {
+    Mid(&'a MidPrice),
+    Fill(&'a Fill),
+}
+
+impl PnlEvent<'_> {
+    fn timestamp_ms(&self) -> i64 {
+        match self {
+            PnlEvent::Mid(mid) => mid.timestamp_ms,
+            PnlEvent::Fill(fill) => fill.timestamp_ms,
+        }
+    }
+}
+
+/// Decomposes maker strategy PnL into spread capture, inventory PnL and fees per UTC day.
+///
+/// # Arguments
+/// * `fills` - The strategy's fills, in any order.
+/// * `mids` - Mid price history covering the period, in any order.
+/// * `starting_inventory` - Base asset inventory held before the first event.
+///
+/// # Returns
+/// One `DailyPnlBreakdown` per UTC day with activity, sorted by date.
+pub fn decompose_pnl(fills: &[Fill], mids: &[MidPrice], starting_inventory: f64) -> Vec<DailyPnlBreakdown> {
+    let mut events: Vec<PnlEvent> = mids.iter().map(PnlEvent::Mid)
+        .chain(fills.iter().map(PnlEvent::Fill))
+        .collect();
+    // Mids at the same timestamp as a fill are applied first so the fill is measured against them
+    events.sort_by_key(|e| (e.timestamp_ms(), matches!(e, PnlEvent::Fill(_))));
+
+    let mut days: BTreeMap<Option<NaiveDate>, DailyPnlBreakdown> = BTreeMap::new();
+    let mut inventory = starting_inventory;
+    let mut last_mid: Option<f64> = None;
+
+    for event in events {
+        let date = utc_date(event.timestamp_ms());
+        let day = days.entry(date).or_insert_with(|| DailyPnlBreakdown { date, ..Default::default() });
+        match event {
+            PnlEvent::Mid(mid) => {
+                if let Some(previous) = last_mid {
+                    day.inventory_pnl += inventory * (mid.price - previous);
+                }
+                last_mid = Some(mid.price);
+            },
+            PnlEvent::Fill(fill) => {
+                // Without a prior mid, the fill price is the best reference and no edge is attributed
+                let reference = last_mid.unwrap_or(fill.price);
+                let (edge, signed_quantity) = match fill.side {
+                    OrderSide::Buy => (reference - fill.price, fill.quantity),
+                    OrderSide::Sell => (fill.price - reference, -fill.quantity),
+                };
+                day.spread_capture += edge * fill.quantity;
+                day.fees += fill.fee;
+                day.fills += 1;
+                if fill.is_maker {
+                    day.maker_fills += 1;
+                }
+                day.traded_volume += fill.price * fill.quantity;
+                inventory += signed_quantity;
+                last_mid = Some(reference);
+            },
+        }
+        day.ending_inventory = inventory;
+    }
+
+    days.into_values().map(|mut day| {
+        day.net_pnl = day.spread_capture + day.inventory_pnl - day.fees;
+        day
+    }).collect()
+}
+
+/// Prints a per-day PnL decomposition table to the console.
+pub fn print_pnl_breakdown(breakdown: &[DailyPnlBreakdown], currency: ReportingCurrency) {
+    println!("\n--- Maker PnL Decomposition ({}) ---", currency.code());
+    println!("{:<12} | {:>12} | {:>12} | {:>10} | {:>12} | {:>6} | {:>12}", "Date", "Spread", "Inventory", "Fees", "Net P/L", "Fills", "End Inv.");
+    println!("{:-<95}", "");
+    for day in breakdown {
+        let date = day.date.map(|d| d.to_string()).unwrap_or_else(|| "unknown".to_string());
+        println!(
+            "{:<12} | {:>12} | {:>12} | {:>10} | {:>12} | {:>6} | {:>12.6}",
+            date, currency.format(day.spread_capture), currency.format(day.inventory_pnl), currency.format(day.fees),
+            currency.format(day.net_pnl), day.fills, day.ending_inventory
+        );
+    }
+    println!("{:-<95}", "");
+}
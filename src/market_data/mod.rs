@@ -2,10 +2,12 @@
 
 //! This module provides functionalities for retrieving various types of market data
 //! from the Binance API using REST endpoints, including current prices,
-//! 24-hour ticker statistics, and historical candlestick data.
+//! 24-hour ticker statistics, the premium index (mark price and predicted funding rate), funding
+//! rate history, and historical candlestick data.
 
 use serde::Deserialize;
 use crate::{rest_api::RestClient, websocket::WebSocketClient}; // Import the core RestClient
+use crate::streams::DepthLevel;
 use serde_json::{json, Value}; // Import Value for deserialization from generic JSON
 
 /// Represents a single ticker price for a symbol.
@@ -61,6 +63,60 @@ pub struct Ticker24hr {
 }
 
 
+/// Represents the premium index of a perpetual: its mark price and predicted funding rate.
+/// Maps to the response from `/fapi/v1/premiumIndex`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PremiumIndex {
+    pub symbol: String,
+    pub mark_price: String,
+    pub index_price: String,
+    pub estimated_settle_price: String, // Only meaningful in the last hour before a settlement
+    pub last_funding_rate: String, // Predicted rate of the next settlement
+    pub interest_rate: String,
+    pub next_funding_time: u64, // Milliseconds
+    pub time: u64,
+}
+
+impl PremiumIndex {
+    /// Returns the predicted funding rate, e.g. 0.0001 for 0.01%.
+    pub fn funding_rate(&self) -> f64 {
+        self.last_funding_rate.parse().unwrap_or(0.0)
+    }
+}
+
+/// Represents a past funding settlement of a perpetual.
+/// Maps to the array elements returned by `/fapi/v1/fundingRate`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingRate {
+    pub symbol: String,
+    pub funding_rate: String,
+    pub funding_time: u64, // Milliseconds
+    #[serde(default)]
+    pub mark_price: String, // Empty for old settlements
+}
+
+impl FundingRate {
+    /// Returns the settled funding rate, e.g. 0.0001 for 0.01%.
+    pub fn rate(&self) -> f64 {
+        self.funding_rate.parse().unwrap_or(0.0)
+    }
+}
+
+/// Maximum number of settlements `/fapi/v1/fundingRate` returns per request.
+pub const MAX_FUNDING_RATES_PER_REQUEST: u16 = 1000;
+
+/// Returns the start time of the next page of a paginated funding history download, or `None`
+/// when `page` was the last one (a short page, or one that reached `end_time`).
+pub fn next_funding_page_start(page: &[FundingRate], limit: usize, end_time: u64) -> Option<u64> {
+    let last_funding_time = page.last()?.funding_time;
+    if page.len() < limit || last_funding_time >= end_time {
+        return None;
+    }
+    Some(last_funding_time + 1)
+}
+
 /// Represents a single candlestick (K-line) data point.
 /// Maps to the array elements returned by `/fapi/v1/klines`.
 #[derive(Debug, Deserialize)]
@@ -82,8 +138,111 @@ pub enum Candlestick {
     ),
 }
 
+impl Candlestick {
+    /// Returns the open time of the candlestick in milliseconds.
+    pub fn open_time(&self) -> u64 {
+        match self {
+            Candlestick::Array(open_time, ..) => *open_time,
+        }
+    }
+}
+
+/// Represents a single aggregated trade.
+/// Maps to the array elements returned by `/fapi/v1/aggTrades`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AggTrade {
+    #[serde(rename = "a")]
+    pub agg_trade_id: u64,
+    #[serde(rename = "p")]
+    pub price: String,
+    #[serde(rename = "q")]
+    pub quantity: String,
+    #[serde(rename = "f")]
+    pub first_trade_id: u64,
+    #[serde(rename = "l")]
+    pub last_trade_id: u64,
+    #[serde(rename = "T")]
+    pub transact_time: u64,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+}
+
+/// Maximum number of candlesticks `/fapi/v1/klines` returns per request.
+pub const MAX_KLINES_PER_REQUEST: u16 = 1500;
+
+/// Returns the start time of the next page of a paginated klines download, or `None` when `page`
+/// was the last one (a short page, or one that reached `end_time`).
+pub fn next_klines_page_start(page: &[Candlestick], limit: usize, end_time: u64) -> Option<u64> {
+    let last_open_time = page.last()?.open_time();
+    if page.len() < limit || last_open_time >= end_time {
+        return None;
+    }
+    Some(last_open_time + 1)
+}
+
+/// Represents an order book snapshot.
+/// Maps to the response from `/fapi/v1/depth`.
+#[derive(Debug, Deserialize)]
+pub struct OrderBookSnapshot {
+    #[serde(rename = "lastUpdateId")]
+    pub last_update_id: u64,
+    #[serde(rename = "E")]
+    pub event_time: u64, // Message output time
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    pub bids: Vec<DepthLevel>, // [price, quantity], best bid first
+    pub asks: Vec<DepthLevel>, // [price, quantity], best ask first
+}
+
+/// Represents the trading rules (filters) of a single symbol.
+/// Extracted from the `symbols[].filters` array of `/fapi/v1/exchangeInfo`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolFilters {
+    pub symbol: String,
+    pub tick_size: f64, // PRICE_FILTER: price increment
+    pub step_size: f64, // LOT_SIZE: quantity increment for limit orders
+    pub min_qty: f64, // LOT_SIZE: minimum quantity
+    pub max_qty: f64, // LOT_SIZE: maximum quantity
+    pub market_step_size: f64, // MARKET_LOT_SIZE: quantity increment for market orders
+    pub market_max_qty: f64, // MARKET_LOT_SIZE: maximum quantity for market orders
+    pub min_notional: f64, // MIN_NOTIONAL: minimum order value in the quote asset
+}
+
+impl SymbolFilters {
+    /// Builds the filters from a symbol entry of the exchange info response.
+    pub fn from_exchange_info_symbol(symbol_info: &Value) -> Result<Self, String> {
+        let symbol = symbol_info.get("symbol").and_then(|s| s.as_str())
+            .ok_or("Exchange info symbol entry is missing 'symbol'")?
+            .to_string();
+        let filters = symbol_info.get("filters").and_then(|f| f.as_array())
+            .ok_or_else(|| format!("Exchange info for {} is missing 'filters'", symbol))?;
+
+        let find = |filter_type: &str, field: &str| -> Option<f64> {
+            filters.iter()
+                .find(|f| f.get("filterType").and_then(|t| t.as_str()) == Some(filter_type))
+                .and_then(|f| f.get(field))
+                .and_then(|v| v.as_str())
+                .and_then(|v| v.parse::<f64>().ok())
+        };
+
+        let step_size = find("LOT_SIZE", "stepSize")
+            .ok_or_else(|| format!("Exchange info for {} is missing LOT_SIZE stepSize", symbol))?;
+        let max_qty = find("LOT_SIZE", "maxQty").unwrap_or(f64::MAX);
+        Ok(Self {
+            tick_size: find("PRICE_FILTER", "tickSize").unwrap_or(0.0),
+            step_size,
+            min_qty: find("LOT_SIZE", "minQty").unwrap_or(0.0),
+            max_qty,
+            market_step_size: find("MARKET_LOT_SIZE", "stepSize").unwrap_or(step_size),
+            market_max_qty: find("MARKET_LOT_SIZE", "maxQty").unwrap_or(max_qty),
+            min_notional: find("MIN_NOTIONAL", "notional").unwrap_or(0.0),
+            symbol,
+        })
+    }
+}
+
 /// Enum for Candlestick intervals.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KlineInterval {
     #[allow(dead_code)] M1,
     #[allow(dead_code)] M3,
@@ -102,6 +261,56 @@ pub enum KlineInterval {
     #[allow(dead_code)] MN1,
 }
 
+impl KlineInterval {
+    /// Returns the duration of one candlestick in milliseconds (a month counts as 30 days).
+    pub fn duration_ms(&self) -> u64 {
+        const MINUTE: u64 = 60_000;
+        match self {
+            KlineInterval::M1 => MINUTE,
+            KlineInterval::M3 => 3 * MINUTE,
+            KlineInterval::M5 => 5 * MINUTE,
+            KlineInterval::M15 => 15 * MINUTE,
+            KlineInterval::M30 => 30 * MINUTE,
+            KlineInterval::H1 => 60 * MINUTE,
+            KlineInterval::H2 => 120 * MINUTE,
+            KlineInterval::H4 => 240 * MINUTE,
+            KlineInterval::H6 => 360 * MINUTE,
+            KlineInterval::H8 => 480 * MINUTE,
+            KlineInterval::H12 => 720 * MINUTE,
+            KlineInterval::D1 => 1_440 * MINUTE,
+            KlineInterval::D3 => 3 * 1_440 * MINUTE,
+            KlineInterval::W1 => 7 * 1_440 * MINUTE,
+            KlineInterval::MN1 => 30 * 1_440 * MINUTE,
+        }
+    }
+}
+
+impl std::str::FromStr for KlineInterval {
+    type Err = String;
+
+    /// Parses a Binance interval string such as "1m", "4h" or "1M".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1m" => Ok(KlineInterval::M1),
+            "3m" => Ok(KlineInterval::M3),
+            "5m" => Ok(KlineInterval::M5),
+            "15m" => Ok(KlineInterval::M15),
+            "30m" => Ok(KlineInterval::M30),
+            "1h" => Ok(KlineInterval::H1),
+            "2h" => Ok(KlineInterval::H2),
+            "4h" => Ok(KlineInterval::H4),
+            "6h" => Ok(KlineInterval::H6),
+            "8h" => Ok(KlineInterval::H8),
+            "12h" => Ok(KlineInterval::H12),
+            "1d" => Ok(KlineInterval::D1),
+            "3d" => Ok(KlineInterval::D3),
+            "1w" => Ok(KlineInterval::W1),
+            "1M" => Ok(KlineInterval::MN1),
+            _ => Err(format!("Invalid kline interval '{}'", s)),
+        }
+    }
+}
+
 impl ToString for KlineInterval {
     fn to_string(&self) -> String {
         match self {
@@ -167,6 +376,110 @@ impl RestClient {
             .map_err(|e| format!("Failed to parse 24hr ticker stats JSON: {}", e))
     }
 
+    /// Fetches the premium index of a perpetual: mark price, predicted funding rate and next
+    /// funding time.
+    ///
+    /// This method calls the `/fapi/v1/premiumIndex` endpoint.
+    ///
+    /// # Arguments
+    /// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
+    ///
+    /// # Returns
+    /// A `Result` containing `PremiumIndex` on success, or a `String` error
+    /// if the request fails or JSON deserialization fails.
+    pub async fn get_premium_index(&self, symbol: &str) -> Result<PremiumIndex, String> {
+        let endpoint = "/fapi/v1/premiumIndex";
+        let symbol_uppercase = symbol.to_uppercase();
+        let params = vec![("symbol", symbol_uppercase.as_str())];
+        let response_value: Value = self.get_unsigned_rest_request(endpoint, params).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse premium index JSON: {}", e))
+    }
+
+    /// Fetches the premium index of every perpetual.
+    ///
+    /// This method calls the `/fapi/v1/premiumIndex` endpoint without a symbol.
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<PremiumIndex>` on success, or a `String` error
+    /// if the request fails or JSON deserialization fails.
+    pub async fn get_premium_indexes(&self) -> Result<Vec<PremiumIndex>, String> {
+        let endpoint = "/fapi/v1/premiumIndex";
+        let response_value: Value = self.get_unsigned_rest_request(endpoint, vec![]).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse premium indexes JSON: {}", e))
+    }
+
+    /// Fetches past funding settlements, oldest first.
+    ///
+    /// This method calls the `/fapi/v1/fundingRate` endpoint.
+    ///
+    /// # Arguments
+    /// * `symbol` - Optional. The trading pair symbol (e.g., "BTCUSDT"); every perpetual if `None`.
+    /// * `start_time` - Optional. Start time in milliseconds (inclusive).
+    /// * `end_time` - Optional. End time in milliseconds (inclusive).
+    /// * `limit` - Optional. The number of settlements to retrieve (default 100, max 1000).
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<FundingRate>` on success, or a `String` error
+    /// if the request fails or JSON deserialization fails.
+    pub async fn get_funding_rate_history(
+        &self,
+        symbol: Option<&str>,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        limit: Option<u16>,
+    ) -> Result<Vec<FundingRate>, String> {
+        let endpoint = "/fapi/v1/fundingRate";
+        let mut params = vec![];
+
+        let symbol_uppercase = symbol.map(|s| s.to_uppercase());
+        if let Some(ref s) = symbol_uppercase {
+            params.push(("symbol", s.as_str()));
+        }
+        let start_time_str = start_time.map(|st| st.to_string());
+        if let Some(ref st_str) = start_time_str {
+            params.push(("startTime", st_str.as_str()));
+        }
+        let end_time_str = end_time.map(|et| et.to_string());
+        if let Some(ref et_str) = end_time_str {
+            params.push(("endTime", et_str.as_str()));
+        }
+        let limit_str = limit.map(|l| l.to_string());
+        if let Some(ref l_str) = limit_str {
+            params.push(("limit", l_str.as_str()));
+        }
+
+        let response_value: Value = self.get_unsigned_rest_request(endpoint, params).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse funding rate history JSON: {}", e))
+    }
+
+    /// Fetches all funding settlements of a symbol between two times, paginating over
+    /// `/fapi/v1/fundingRate` with the maximum page size.
+    ///
+    /// # Arguments
+    /// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
+    /// * `start_time` - Start time in milliseconds (inclusive).
+    /// * `end_time` - End time in milliseconds (inclusive).
+    ///
+    /// # Returns
+    /// A `Result` containing the settlements in chronological order, or a `String` error
+    /// if any page request fails.
+    pub async fn get_funding_rate_range(&self, symbol: &str, start_time: u64, end_time: u64) -> Result<Vec<FundingRate>, String> {
+        let mut settlements: Vec<FundingRate> = Vec::new();
+        let mut next_start = Some(start_time);
+        while let Some(page_start) = next_start.filter(|start| *start <= end_time) {
+            let page = self.get_funding_rate_history(Some(symbol), Some(page_start), Some(end_time), Some(MAX_FUNDING_RATES_PER_REQUEST)).await?;
+            next_start = next_funding_page_start(&page, MAX_FUNDING_RATES_PER_REQUEST as usize, end_time);
+            settlements.extend(page);
+        }
+        Ok(settlements)
+    }
+
     /// Fetches candlestick (K-line) data for a given symbol and interval using REST API.
     ///
     /// This method calls the `/fapi/v1/klines` endpoint.
@@ -217,11 +530,143 @@ impl RestClient {
             .map_err(|e| format!("Failed to parse klines JSON: {}", e))
     }
 
+    /// Fetches all candlesticks of a symbol between two times, paginating over `/fapi/v1/klines`
+    /// with the maximum page size.
+    ///
+    /// # Arguments
+    /// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
+    /// * `interval` - The candlestick interval.
+    /// * `start_time` - Start time in milliseconds (inclusive).
+    /// * `end_time` - End time in milliseconds (inclusive).
+    ///
+    /// # Returns
+    /// A `Result` containing the candlesticks in chronological order, or a `String` error
+    /// if any page request fails.
+    pub async fn get_klines_range(
+        &self,
+        symbol: &str,
+        interval: KlineInterval,
+        start_time: u64,
+        end_time: u64,
+    ) -> Result<Vec<Candlestick>, String> {
+        let mut candlesticks: Vec<Candlestick> = Vec::new();
+        let mut next_start = Some(start_time);
+        while let Some(page_start) = next_start.filter(|start| *start <= end_time) {
+            let page = self.get_klines(symbol, interval, Some(MAX_KLINES_PER_REQUEST), Some(page_start), Some(end_time)).await?;
+            next_start = next_klines_page_start(&page, MAX_KLINES_PER_REQUEST as usize, end_time);
+            candlesticks.extend(page);
+        }
+        Ok(candlesticks)
+    }
+
+    /// Fetches aggregated trades for a given symbol using REST API.
+    ///
+    /// This method calls the `/fapi/v1/aggTrades` endpoint. Either `from_id` or a time window of at
+    /// most one hour (`start_time`/`end_time`) selects the trades.
+    ///
+    /// # Arguments
+    /// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
+    /// * `from_id` - Optional. Aggregate trade ID to start from (inclusive).
+    /// * `start_time` - Optional. Start time in milliseconds.
+    /// * `end_time` - Optional. End time in milliseconds.
+    /// * `limit` - Optional. The number of trades to retrieve (default 500, max 1000).
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<AggTrade>` on success, or a `String` error
+    /// if the request fails or JSON deserialization fails.
+    pub async fn get_agg_trades(
+        &self,
+        symbol: &str,
+        from_id: Option<u64>,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        limit: Option<u16>,
+    ) -> Result<Vec<AggTrade>, String> {
+        let endpoint = "/fapi/v1/aggTrades";
+        let symbol_uppercase = symbol.to_uppercase();
+        let mut params = vec![("symbol", symbol_uppercase.as_str())];
+
+        let from_id_str = from_id.map(|id| id.to_string());
+        if let Some(ref id_str) = from_id_str {
+            params.push(("fromId", id_str.as_str()));
+        }
+        let start_time_str = start_time.map(|st| st.to_string());
+        if let Some(ref st_str) = start_time_str {
+            params.push(("startTime", st_str.as_str()));
+        }
+        let end_time_str = end_time.map(|et| et.to_string());
+        if let Some(ref et_str) = end_time_str {
+            params.push(("endTime", et_str.as_str()));
+        }
+        let limit_str = limit.map(|l| l.to_string());
+        if let Some(ref l_str) = limit_str {
+            params.push(("limit", l_str.as_str()));
+        }
+
+        let response_value: Value = self.get_unsigned_rest_request(endpoint, params).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse aggregated trades JSON: {}", e))
+    }
+
+    /// Fetches an order book snapshot for a given symbol using REST API.
+    ///
+    /// This method calls the `/fapi/v1/depth` endpoint.
+    ///
+    /// # Arguments
+    /// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
+    /// * `limit` - Optional. Number of levels per side (5, 10, 20, 50, 100, 500, 1000; default 500).
+    ///
+    /// # Returns
+    /// A `Result` containing `OrderBookSnapshot` on success, or a `String` error
+    /// if the request fails or JSON deserialization fails.
+    pub async fn get_order_book(&self, symbol: &str, limit: Option<u16>) -> Result<OrderBookSnapshot, String> {
+        let endpoint = "/fapi/v1/depth";
+        let symbol_uppercase = symbol.to_uppercase();
+        let mut params = vec![("symbol", symbol_uppercase.as_str())];
+
+        let limit_str = limit.map(|l| l.to_string());
+        if let Some(ref l_str) = limit_str {
+            params.push(("limit", l_str.as_str()));
+        }
+
+        let response_value: Value = self.get_unsigned_rest_request(endpoint, params).await?;
+
+        serde_json::from_value(response_value)
+            .map_err(|e| format!("Failed to parse order book JSON: {}", e))
+    }
+
+    /// Fetches the exchange trading rules and symbol information using REST API.
+    ///
+    /// This method calls the `/fapi/v1/exchangeInfo` endpoint.
+    ///
+    /// # Returns
+    /// A `Result` containing the raw exchange info `Value` on success, or a `String` error.
+    pub async fn get_exchange_info(&self) -> Result<Value, String> {
+        let endpoint = "/fapi/v1/exchangeInfo";
+        self.get_unsigned_rest_request(endpoint, vec![]).await
+    }
+
+    /// Fetches the trading filters (tick size, step size, min notional, ...) for a single symbol.
+    ///
+    /// # Arguments
+    /// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
+    ///
+    /// # Returns
+    /// A `Result` containing `SymbolFilters` on success, or a `String` error
+    /// if the request fails or the symbol is not listed.
+    pub async fn get_symbol_filters(&self, symbol: &str) -> Result<SymbolFilters, String> {
+        let symbol_uppercase = symbol.to_uppercase();
+        let exchange_info = self.get_exchange_info().await?;
+        let symbol_info = exchange_info.get("symbols").and_then(|s| s.as_array())
+            .and_then(|symbols| symbols.iter().find(|s| s.get("symbol").and_then(|v| v.as_str()) == Some(symbol_uppercase.as_str())))
+            .ok_or_else(|| format!("Symbol {} not found in exchange info", symbol_uppercase))?;
+        SymbolFilters::from_exchange_info_symbol(symbol_info)
+    }
+
     // You can add other market data functions here, such as:
-    // - get_order_book(symbol: &str, limit: Option<u16>)
     // - get_recent_trades(symbol: &str, limit: Option<u16>)
     // - get_historical_trades(symbol: &str, limit: Option<u16>, from_id: Option<u64>)
-    // - get_exchange_info()
 }
 
 impl WebSocketClient{
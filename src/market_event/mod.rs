@@ -0,0 +1,587 @@
+// src/market_event/mod.rs
+
+//! This module defines the normalized, internal representation of market data.
+//!
+//! Stream messages are decoded once, directly from the frame text, into compact typed structs
+//! (prices and quantities as `f64`, symbols stored inline) instead of `serde_json::Value` trees.
+//! Downstream components (order book, candle store, strategies) consume `MarketEvent`s and no
+//! longer clone and re-parse JSON values on every tick. All events except depth updates are `Copy`.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+/// Maximum symbol length stored inline (Binance symbols are well below this).
+pub const MAX_SYMBOL_LEN: usize = 23;
+
+/// A trading pair symbol stored inline, so events carrying it are `Copy` and allocation-free.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol {
+    len: u8,
+    bytes: [u8; MAX_SYMBOL_LEN],
+}
+
+impl Symbol {
+    /// Creates a symbol, upper-casing it. Fails if it is empty, longer than `MAX_SYMBOL_LEN`, or not ASCII.
+    pub fn new(symbol: &str) -> Result<Self, String> {
+        if symbol.is_empty() || symbol.len() > MAX_SYMBOL_LEN || !symbol.is_ascii() {
+            return Err(format!("Invalid symbol '{}'", symbol));
+        }
+        let mut bytes = [0u8; MAX_SYMBOL_LEN];
+        bytes[..symbol.len()].copy_from_slice(symbol.to_ascii_uppercase().as_bytes());
+        Ok(Self { len: symbol.len() as u8, bytes })
+    }
+
+    /// Returns the symbol as a string slice.
+    pub fn as_str(&self) -> &str {
+        // Only ASCII is ever stored
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Symbol({})", self.as_str())
+    }
+}
+
+/// A single `[price, quantity]` order book level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Level {
+    pub price: f64,
+    pub quantity: f64,
+}
+
+/// A trade (`<symbol>@trade`) or aggregated trade (`<symbol>@aggTrade`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trade {
+    pub symbol: Symbol,
+    pub event_time: u64,
+    pub trade_time: u64,
+    pub trade_id: u64, // Aggregate trade ID for aggTrade events
+    pub price: f64,
+    pub quantity: f64,
+    pub is_buyer_maker: bool,
+}
+
+/// Best bid/ask update (`<symbol>@bookTicker`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookTicker {
+    pub symbol: Symbol,
+    pub event_time: u64,
+    pub update_id: u64,
+    pub bid_price: f64,
+    pub bid_quantity: f64,
+    pub ask_price: f64,
+    pub ask_quantity: f64,
+}
+
+/// Diff depth update (`<symbol>@depth`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthUpdate {
+    pub symbol: Symbol,
+    pub event_time: u64,
+    pub first_update_id: u64,
+    pub final_update_id: u64,
+    pub prev_final_update_id: Option<u64>, // Futures only
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+/// Kline update (`<symbol>@kline_<interval>`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candle {
+    pub symbol: Symbol,
+    pub event_time: u64,
+    pub interval_ms: u64,
+    pub open_time: u64,
+    pub close_time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub quote_volume: f64,
+    pub trades: u64,
+    pub is_closed: bool,
+}
+
+/// Mark price and funding update (`<symbol>@markPrice`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarkPrice {
+    pub symbol: Symbol,
+    pub event_time: u64,
+    pub mark_price: f64,
+    pub index_price: f64,
+    pub funding_rate: f64,
+    pub next_funding_time: u64,
+}
+
+/// Delivery type of a futures contract, as in continuous kline streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContractType {
+    Perpetual,
+    CurrentQuarter,
+    NextQuarter,
+}
+
+impl ContractType {
+    /// Returns the Binance name, e.g. `PERPETUAL`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContractType::Perpetual => "PERPETUAL",
+            ContractType::CurrentQuarter => "CURRENT_QUARTER",
+            ContractType::NextQuarter => "NEXT_QUARTER",
+        }
+    }
+}
+
+impl std::str::FromStr for ContractType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "PERPETUAL" => Ok(ContractType::Perpetual),
+            "CURRENT_QUARTER" => Ok(ContractType::CurrentQuarter),
+            "NEXT_QUARTER" => Ok(ContractType::NextQuarter),
+            _ => Err(format!("Unknown contract type '{}'", s)),
+        }
+    }
+}
+
+/// What the candles of a pair kline stream are built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KlineSource {
+    Continuous(ContractType), // Trades of the pair's contract of that type, across rollovers
+    IndexPrice, // The index (spot) price; no volume
+    MarkPrice, // The mark price; no volume
+}
+
+/// Kline update of a continuous contract (`<pair>_<contractType>@continuousKline_<interval>`), an
+/// index price (`<pair>@indexPriceKline_<interval>`) or a mark price
+/// (`<symbol>@markPriceKline_<interval>`). The candle's symbol is the pair.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceKline {
+    pub source: KlineSource,
+    pub candle: Candle,
+}
+
+/// A forced liquidation order (`<symbol>@forceOrder`, or `!forceOrder@arr` for every symbol).
+/// Binance sends at most the latest liquidation of a symbol per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Liquidation {
+    pub symbol: Symbol,
+    pub event_time: u64,
+    pub trade_time: u64,
+    pub is_sell: bool, // A long position was liquidated
+    pub price: f64,
+    pub average_price: f64,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+}
+
+impl Liquidation {
+    /// Returns the quote notional liquidated so far.
+    pub fn notional(&self) -> f64 {
+        self.average_price * self.filled_quantity
+    }
+}
+
+/// A normalized market data event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketEvent {
+    Trade(Trade),
+    AggTrade(Trade),
+    BookTicker(BookTicker),
+    Depth(DepthUpdate),
+    Kline(Candle),
+    MarkPrice(MarkPrice),
+    Liquidation(Liquidation),
+    PriceKline(PriceKline),
+}
+
+impl MarketEvent {
+    /// Returns the symbol of the event.
+    pub fn symbol(&self) -> Symbol {
+        match self {
+            MarketEvent::Trade(t) | MarketEvent::AggTrade(t) => t.symbol,
+            MarketEvent::BookTicker(b) => b.symbol,
+            MarketEvent::Depth(d) => d.symbol,
+            MarketEvent::Kline(c) => c.symbol,
+            MarketEvent::MarkPrice(m) => m.symbol,
+            MarketEvent::Liquidation(l) => l.symbol,
+            MarketEvent::PriceKline(k) => k.candle.symbol,
+        }
+    }
+
+    /// Returns the exchange event time in milliseconds.
+    pub fn event_time(&self) -> u64 {
+        match self {
+            MarketEvent::Trade(t) | MarketEvent::AggTrade(t) => t.event_time,
+            MarketEvent::BookTicker(b) => b.event_time,
+            MarketEvent::Depth(d) => d.event_time,
+            MarketEvent::Kline(c) => c.event_time,
+            MarketEvent::MarkPrice(m) => m.event_time,
+            MarketEvent::Liquidation(l) => l.event_time,
+            MarketEvent::PriceKline(k) => k.candle.event_time,
+        }
+    }
+}
+
+/// Converts a Binance kline interval (e.g. "1m", "4h", "1w") to milliseconds.
+pub fn interval_to_ms(interval: &str) -> Option<u64> {
+    let split = interval.len().checked_sub(1)?;
+    let (count, unit) = interval.split_at(split);
+    let count = count.parse::<u64>().ok()?;
+    let unit_ms = match unit {
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        "w" => 7 * 86_400_000,
+        "M" => 30 * 86_400_000, // Approximation; monthly klines are calendar aligned
+        _ => return None,
+    };
+    Some(count * unit_ms)
+}
+
+fn num(field: &str, value: &str) -> Result<f64, String> {
+    value.parse::<f64>().map_err(|e| format!("Failed to parse {} '{}': {}", field, value, e))
+}
+
+fn levels(field: &str, raw: &[(&str, &str)]) -> Result<Vec<Level>, String> {
+    raw.iter().map(|(price, quantity)| Ok(Level { price: num(field, price)?, quantity: num(field, quantity)? })).collect()
+}
+
+// Borrowed wire formats: strings point into the frame text, nothing is allocated until conversion.
+
+#[derive(Deserialize)]
+struct Envelope<'a> {
+    #[serde(rename = "e", borrow, default)]
+    event_type: Option<&'a str>,
+    #[serde(borrow, default)]
+    data: Option<&'a RawValue>, // Combined stream payload
+}
+
+#[derive(Deserialize)]
+struct RawTrade<'a> {
+    #[serde(rename = "E")]
+    event_time: u64,
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "t", alias = "a")]
+    trade_id: u64,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    quantity: &'a str,
+    #[serde(rename = "T")]
+    trade_time: u64,
+    #[serde(rename = "m")]
+    is_buyer_maker: bool,
+}
+
+#[derive(Deserialize)]
+struct RawBookTicker<'a> {
+    #[serde(rename = "E", default)]
+    event_time: u64,
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "u")]
+    update_id: u64,
+    #[serde(rename = "b")]
+    bid_price: &'a str,
+    #[serde(rename = "B")]
+    bid_quantity: &'a str,
+    #[serde(rename = "a")]
+    ask_price: &'a str,
+    #[serde(rename = "A")]
+    ask_quantity: &'a str,
+}
+
+#[derive(Deserialize)]
+struct RawDepth<'a> {
+    #[serde(rename = "E")]
+    event_time: u64,
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "U")]
+    first_update_id: u64,
+    #[serde(rename = "u")]
+    final_update_id: u64,
+    #[serde(rename = "pu", default)]
+    prev_final_update_id: Option<u64>,
+    #[serde(rename = "b", borrow)]
+    bids: Vec<(&'a str, &'a str)>,
+    #[serde(rename = "a", borrow)]
+    asks: Vec<(&'a str, &'a str)>,
+}
+
+#[derive(Deserialize)]
+struct RawKline<'a> {
+    #[serde(rename = "E")]
+    event_time: u64,
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "k", borrow)]
+    kline: RawKlineData<'a>,
+}
+
+#[derive(Deserialize)]
+struct RawPairKline<'a> {
+    #[serde(rename = "E")]
+    event_time: u64,
+    #[serde(rename = "ps")]
+    pair: &'a str,
+    #[serde(rename = "ct", default)]
+    contract_type: Option<&'a str>, // Continuous klines only
+    #[serde(rename = "k", borrow)]
+    kline: RawKlineData<'a>,
+}
+
+#[derive(Deserialize)]
+struct RawKlineData<'a> {
+    #[serde(rename = "t")]
+    open_time: u64,
+    #[serde(rename = "T")]
+    close_time: u64,
+    #[serde(rename = "i")]
+    interval: &'a str,
+    #[serde(rename = "o")]
+    open: &'a str,
+    #[serde(rename = "h")]
+    high: &'a str,
+    #[serde(rename = "l")]
+    low: &'a str,
+    #[serde(rename = "c")]
+    close: &'a str,
+    #[serde(rename = "v")]
+    volume: &'a str,
+    #[serde(rename = "q")]
+    quote_volume: &'a str,
+    #[serde(rename = "n")]
+    trades: u64,
+    #[serde(rename = "x")]
+    is_closed: bool,
+}
+
+#[derive(Deserialize)]
+struct RawMarkPrice<'a> {
+    #[serde(rename = "E")]
+    event_time: u64,
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "p")]
+    mark_price: &'a str,
+    #[serde(rename = "i", default)]
+    index_price: &'a str,
+    #[serde(rename = "r", default)]
+    funding_rate: &'a str,
+    #[serde(rename = "T", default)]
+    next_funding_time: u64,
+}
+
+#[derive(Deserialize)]
+struct RawForceOrder<'a> {
+    #[serde(rename = "E")]
+    event_time: u64,
+    #[serde(rename = "o", borrow)]
+    order: RawForceOrderData<'a>,
+}
+
+#[derive(Deserialize)]
+struct RawForceOrderData<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "S")]
+    side: &'a str,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "ap")]
+    average_price: &'a str,
+    #[serde(rename = "q")]
+    quantity: &'a str,
+    #[serde(rename = "z")]
+    filled_quantity: &'a str,
+    #[serde(rename = "T")]
+    trade_time: u64,
+}
+
+fn decode<'a, T: Deserialize<'a>>(text: &'a str, event_type: &str) -> Result<T, String> {
+    serde_json::from_str(text).map_err(|e| format!("Failed to decode {} event: {}", event_type, e))
+}
+
+fn candle(symbol: &str, event_time: u64, k: RawKlineData) -> Result<Candle, String> {
+    Ok(Candle {
+        symbol: Symbol::new(symbol)?,
+        event_time,
+        interval_ms: interval_to_ms(k.interval).ok_or_else(|| format!("Unknown kline interval '{}'", k.interval))?,
+        open_time: k.open_time,
+        close_time: k.close_time,
+        open: num("open", k.open)?,
+        high: num("high", k.high)?,
+        low: num("low", k.low)?,
+        close: num("close", k.close)?,
+        volume: num("volume", k.volume)?,
+        quote_volume: num("quote volume", k.quote_volume)?,
+        trades: k.trades,
+        is_closed: k.is_closed,
+    })
+}
+
+fn optional_num(field: &str, value: &str) -> Result<f64, String> {
+    if value.is_empty() { Ok(0.0) } else { num(field, value) }
+}
+
+/// Decodes a market stream frame into a normalized event.
+///
+/// Both raw (`/ws`) and combined (`/stream`, `{"stream": ..., "data": ...}`) payloads are accepted.
+///
+/// # Returns
+/// `Ok(Some(event))` for supported market events, `Ok(None)` for anything else (subscription
+/// responses, unsupported event types), or an `Err` if a supported event is malformed.
+pub fn parse_market_event(text: &str) -> Result<Option<MarketEvent>, String> {
+    let envelope: Envelope = match serde_json::from_str(text) {
+        Ok(envelope) => envelope,
+        Err(_) => return Ok(None), // Not an object (e.g. a bare array); not a market event
+    };
+    if let Some(data) = envelope.data {
+        return parse_market_event(data.get());
+    }
+    let Some(event_type) = envelope.event_type else {
+        return Ok(None);
+    };
+
+    let event = match event_type {
+        "trade" | "aggTrade" => {
+            let raw: RawTrade = decode(text, event_type)?;
+            let trade = Trade {
+                symbol: Symbol::new(raw.symbol)?,
+                event_time: raw.event_time,
+                trade_time: raw.trade_time,
+                trade_id: raw.trade_id,
+                price: num("price", raw.price)?,
+                quantity: num("quantity", raw.quantity)?,
+                is_buyer_maker: raw.is_buyer_maker,
+            };
+            if event_type == "trade" { MarketEvent::Trade(trade) } else { MarketEvent::AggTrade(trade) }
+        },
+        "bookTicker" => {
+            let raw: RawBookTicker = decode(text, event_type)?;
+            MarketEvent::BookTicker(BookTicker {
+                symbol: Symbol::new(raw.symbol)?,
+                event_time: raw.event_time,
+                update_id: raw.update_id,
+                bid_price: num("bid price", raw.bid_price)?,
+                bid_quantity: num("bid quantity", raw.bid_quantity)?,
+                ask_price: num("ask price", raw.ask_price)?,
+                ask_quantity: num("ask quantity", raw.ask_quantity)?,
+            })
+        },
+        "depthUpdate" => {
+            let raw: RawDepth = decode(text, event_type)?;
+            MarketEvent::Depth(DepthUpdate {
+                symbol: Symbol::new(raw.symbol)?,
+                event_time: raw.event_time,
+                first_update_id: raw.first_update_id,
+                final_update_id: raw.final_update_id,
+                prev_final_update_id: raw.prev_final_update_id,
+                bids: levels("bid level", &raw.bids)?,
+                asks: levels("ask level", &raw.asks)?,
+            })
+        },
+        "kline" => {
+            let raw: RawKline = decode(text, event_type)?;
+            MarketEvent::Kline(candle(raw.symbol, raw.event_time, raw.kline)?)
+        },
+        "continuous_kline" | "indexPrice_kline" | "markPrice_kline" => {
+            let raw: RawPairKline = decode(text, event_type)?;
+            let source = match (event_type, raw.contract_type) {
+                ("indexPrice_kline", _) => KlineSource::IndexPrice,
+                ("markPrice_kline", _) => KlineSource::MarkPrice,
+                (_, contract_type) => KlineSource::Continuous(contract_type.unwrap_or_default().parse()?),
+            };
+            MarketEvent::PriceKline(PriceKline { source, candle: candle(raw.pair, raw.event_time, raw.kline)? })
+        },
+        "markPriceUpdate" => {
+            let raw: RawMarkPrice = decode(text, event_type)?;
+            MarketEvent::MarkPrice(MarkPrice {
+                symbol: Symbol::new(raw.symbol)?,
+                event_time: raw.event_time,
+                mark_price: num("mark price", raw.mark_price)?,
+                index_price: optional_num("index price", raw.index_price)?,
+                funding_rate: optional_num("funding rate", raw.funding_rate)?,
+                next_funding_time: raw.next_funding_time,
+            })
+        },
+        "forceOrder" => {
+            let raw: RawForceOrder = decode(text, event_type)?;
+            let o = raw.order;
+            MarketEvent::Liquidation(Liquidation {
+                symbol: Symbol::new(o.symbol)?,
+                event_time: raw.event_time,
+                trade_time: o.trade_time,
+                is_sell: o.side == "SELL",
+                price: num("price", o.price)?,
+                average_price: num("average price", o.average_price)?,
+                quantity: num("quantity", o.quantity)?,
+                filled_quantity: num("filled quantity", o.filled_quantity)?,
+            })
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
+
+/// The latest market-wide liquidations, e.g. as a volatility or exhaustion signal: a burst of long
+/// liquidations often marks a capitulation low. Shared as `Arc<Mutex<LiquidationFeed>>`, it can
+/// be shown with `tui::display_struct_in_tui`, which redraws it as it fills.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidationFeed {
+    pub window_ms: u64, // Liquidations older than this are dropped
+    pub max_events: usize, // At most this many are kept
+    pub recent: VecDeque<Liquidation>, // Oldest first
+}
+
+impl LiquidationFeed {
+    /// Creates an empty feed keeping up to `max_events` liquidations of the last `window_ms`.
+    pub fn new(window_ms: u64, max_events: usize) -> Self {
+        Self { window_ms, max_events, recent: VecDeque::new() }
+    }
+
+    /// Records a liquidation event and drops expired ones. Returns false for other events.
+    pub fn on_event(&mut self, event: &MarketEvent) -> bool {
+        let MarketEvent::Liquidation(liquidation) = event else { return false };
+        self.recent.push_back(*liquidation);
+        while self.recent.len() > self.max_events {
+            self.recent.pop_front();
+        }
+        self.expire(liquidation.event_time);
+        true
+    }
+
+    /// Drops the liquidations older than the window at `now_ms`.
+    pub fn expire(&mut self, now_ms: u64) {
+        while self.recent.front().is_some_and(|l| l.event_time + self.window_ms < now_ms) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Returns the notional of the long positions liquidated within the window.
+    pub fn long_notional(&self) -> f64 {
+        self.recent.iter().filter(|l| l.is_sell).map(Liquidation::notional).sum()
+    }
+
+    /// Returns the notional of the short positions liquidated within the window.
+    pub fn short_notional(&self) -> f64 {
+        self.recent.iter().filter(|l| !l.is_sell).map(Liquidation::notional).sum()
+    }
+}
@@ -0,0 +1,213 @@
+// src/metrics/mod.rs
+
+//! This module computes risk-adjusted performance metrics (CAGR, Sharpe, Sortino, Calmar,
+//! alpha/beta) from an equity curve, against a configurable risk-free rate and benchmark series (e.g. BTC buy-and-hold).
+//! The same `MetricsConfig` is used by backtest reports and live performance summaries so both
+//! report comparable numbers.
+
+use serde::{Deserialize, Serialize};
+
+/// Bars per year for common sampling intervals.
+pub const DAILY_PERIODS_PER_YEAR: f64 = 365.0; // Crypto trades every day
+pub const FOUR_HOUR_PERIODS_PER_YEAR: f64 = 365.0 * 6.0;
+
+/// Milliseconds in a (365-day) year, to derive the periods per year of any candle interval.
+pub const MS_PER_YEAR: f64 = 365.0 * 24.0 * 3_600_000.0;
+
+/// The benchmark performance is compared against.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Benchmark {
+    None,
+    BuyAndHold, // Buy-and-hold of the traded instrument; the caller supplies its price series
+    Series(Vec<f64>), // An explicit benchmark price series, aligned with the equity curve
+}
+
+/// Settings for risk-adjusted metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsConfig {
+    pub risk_free_rate: f64, // Annual risk-free rate, e.g. 0.04 for 4%
+    pub periods_per_year: f64, // Sampling frequency of the equity curve
+    pub benchmark: Benchmark,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            risk_free_rate: 0.0,
+            periods_per_year: DAILY_PERIODS_PER_YEAR,
+            benchmark: Benchmark::BuyAndHold,
+        }
+    }
+}
+
+impl MetricsConfig {
+    /// Reads `RISK_FREE_RATE` (annual, e.g. "0.04") and `BENCHMARK` ("buy_and_hold" or "none")
+    /// from the environment, keeping `periods_per_year` as given.
+    pub fn from_env(periods_per_year: f64) -> Result<Self, String> {
+        let risk_free_rate = match std::env::var("RISK_FREE_RATE") {
+            Ok(rate) => rate.trim().parse::<f64>().map_err(|e| format!("Invalid RISK_FREE_RATE '{}': {}", rate, e))?,
+            Err(_) => 0.0,
+        };
+        let benchmark = match std::env::var("BENCHMARK").as_deref().map(str::trim) {
+            Ok("none") | Ok("") => Benchmark::None,
+            Ok("buy_and_hold") | Err(_) => Benchmark::BuyAndHold,
+            Ok(other) => return Err(format!("Invalid BENCHMARK '{}': expected 'buy_and_hold' or 'none'", other)),
+        };
+        Ok(Self { risk_free_rate, periods_per_year, benchmark })
+    }
+
+    /// Returns the risk-free rate per period.
+    pub fn risk_free_per_period(&self) -> f64 {
+        (1.0 + self.risk_free_rate).powf(1.0 / self.periods_per_year) - 1.0
+    }
+}
+
+/// Risk-adjusted performance metrics.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct RiskMetrics {
+    pub total_return: f64,
+    pub annualized_return: f64, // Compound annual growth rate (CAGR)
+    pub annualized_volatility: f64,
+    pub sharpe_ratio: Option<f64>,
+    pub sortino_ratio: Option<f64>,
+    #[serde(default)]
+    pub max_drawdown: f64, // Largest peak-to-trough decline of the equity curve, as a fraction of the peak
+    #[serde(default)]
+    pub calmar_ratio: Option<f64>, // CAGR over the maximum drawdown
+    pub benchmark_return: Option<f64>,
+    pub alpha: Option<f64>, // Annualized Jensen's alpha
+    pub beta: Option<f64>,
+}
+
+/// Converts a value series (equity or prices) into simple period returns.
+pub fn simple_returns(values: &[f64]) -> Vec<f64> {
+    values.windows(2)
+        .map(|w| if w[0] != 0.0 { w[1] / w[0] - 1.0 } else { 0.0 })
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 }
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(values);
+    (values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (values.len() - 1) as f64).sqrt()
+}
+
+/// Annualized Sharpe ratio of period `returns` in excess of the risk-free rate.
+pub fn sharpe_ratio(returns: &[f64], config: &MetricsConfig) -> Option<f64> {
+    let rf = config.risk_free_per_period();
+    let excess: Vec<f64> = returns.iter().map(|r| r - rf).collect();
+    let sd = std_dev(&excess);
+    if sd <= 0.0 { None } else { Some(mean(&excess) / sd * config.periods_per_year.sqrt()) }
+}
+
+/// Annualized Sortino ratio: excess return over the downside deviation below the risk-free rate.
+pub fn sortino_ratio(returns: &[f64], config: &MetricsConfig) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let rf = config.risk_free_per_period();
+    let excess: Vec<f64> = returns.iter().map(|r| r - rf).collect();
+    let downside = (excess.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / excess.len() as f64).sqrt();
+    if downside <= 0.0 { None } else { Some(mean(&excess) / downside * config.periods_per_year.sqrt()) }
+}
+
+/// CAPM alpha (annualized) and beta of `returns` against `benchmark_returns` (same length and sampling).
+pub fn alpha_beta(returns: &[f64], benchmark_returns: &[f64], config: &MetricsConfig) -> Option<(f64, f64)> {
+    let n = returns.len().min(benchmark_returns.len());
+    if n < 2 {
+        return None;
+    }
+    let rf = config.risk_free_per_period();
+    let strategy: Vec<f64> = returns[..n].iter().map(|r| r - rf).collect();
+    let benchmark: Vec<f64> = benchmark_returns[..n].iter().map(|r| r - rf).collect();
+    let (ms, mb) = (mean(&strategy), mean(&benchmark));
+    let covariance = strategy.iter().zip(&benchmark).map(|(s, b)| (s - ms) * (b - mb)).sum::<f64>() / (n - 1) as f64;
+    let variance = std_dev(&benchmark).powi(2);
+    if variance <= 0.0 {
+        return None;
+    }
+    let beta = covariance / variance;
+    Some(((ms - beta * mb) * config.periods_per_year, beta))
+}
+
+/// Largest peak-to-trough decline of an equity curve, as a fraction of the peak.
+pub fn max_drawdown(equity: &[f64]) -> f64 {
+    let mut peak = f64::MIN;
+    equity.iter().fold(0.0, |worst: f64, &value| {
+        peak = peak.max(value);
+        if peak > 0.0 { worst.max((peak - value) / peak) } else { worst }
+    })
+}
+
+/// Calmar ratio: annualized return over the maximum drawdown.
+pub fn calmar_ratio(annualized_return: f64, max_drawdown: f64) -> Option<f64> {
+    if max_drawdown > 0.0 { Some(annualized_return / max_drawdown) } else { None }
+}
+
+/// Computes risk-adjusted metrics for an equity curve.
+///
+/// # Arguments
+/// * `equity` - Equity sampled once per period (e.g. per bar or per day).
+/// * `instrument_prices` - Prices of the traded instrument at the same samples, used for `Benchmark::BuyAndHold`.
+/// * `config` - Risk-free rate, sampling frequency and benchmark.
+pub fn compute_risk_metrics(equity: &[f64], instrument_prices: Option<&[f64]>, config: &MetricsConfig) -> RiskMetrics {
+    let returns = simple_returns(equity);
+    let total_return = match (equity.first(), equity.last()) {
+        (Some(&first), Some(&last)) if first > 0.0 => last / first - 1.0,
+        _ => 0.0,
+    };
+    let years = returns.len() as f64 / config.periods_per_year;
+    let annualized_return = if years > 0.0 && total_return > -1.0 { (1.0 + total_return).powf(1.0 / years) - 1.0 } else { 0.0 };
+
+    let benchmark_prices: Option<&[f64]> = match &config.benchmark {
+        Benchmark::None => None,
+        Benchmark::BuyAndHold => instrument_prices,
+        Benchmark::Series(series) => Some(series),
+    };
+    let benchmark_returns = benchmark_prices.map(simple_returns);
+    let benchmark_return = benchmark_prices.and_then(|p| match (p.first(), p.last()) {
+        (Some(&first), Some(&last)) if first > 0.0 => Some(last / first - 1.0),
+        _ => None,
+    });
+    let alpha_beta = benchmark_returns.as_deref().and_then(|b| alpha_beta(&returns, b, config));
+    let max_drawdown = max_drawdown(equity);
+
+    RiskMetrics {
+        total_return,
+        annualized_return,
+        annualized_volatility: std_dev(&returns) * config.periods_per_year.sqrt(),
+        sharpe_ratio: sharpe_ratio(&returns, config),
+        sortino_ratio: sortino_ratio(&returns, config),
+        max_drawdown,
+        calmar_ratio: calmar_ratio(annualized_return, max_drawdown),
+        benchmark_return,
+        alpha: alpha_beta.map(|(alpha, _)| alpha),
+        beta: alpha_beta.map(|(_, beta)| beta),
+    }
+}
+
+/// Prints the risk-adjusted metrics section of a performance report.
+pub fn print_risk_metrics(metrics: &RiskMetrics, config: &MetricsConfig) {
+    let fmt = |value: Option<f64>| value.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "n/a".to_string());
+    let pct = |value: Option<f64>| value.map(|v| format!("{:.2}%", v * 100.0)).unwrap_or_else(|| "n/a".to_string());
+    println!("\n--- Risk-Adjusted Metrics (risk-free rate {:.2}%) ---", config.risk_free_rate * 100.0);
+    println!("{:<25} | {:>15}", "Metric", "Value");
+    println!("{:-<43}", "");
+    println!("{:<25} | {:>15}", "Total Return", pct(Some(metrics.total_return)));
+    println!("{:<25} | {:>15}", "CAGR", pct(Some(metrics.annualized_return)));
+    println!("{:<25} | {:>15}", "Annualized Volatility", pct(Some(metrics.annualized_volatility)));
+    println!("{:<25} | {:>15}", "Sharpe Ratio", fmt(metrics.sharpe_ratio));
+    println!("{:<25} | {:>15}", "Sortino Ratio", fmt(metrics.sortino_ratio));
+    println!("{:<25} | {:>15}", "Max Drawdown (equity)", pct(Some(metrics.max_drawdown)));
+    println!("{:<25} | {:>15}", "Calmar Ratio", fmt(metrics.calmar_ratio));
+    println!("{:<25} | {:>15}", "Benchmark Return", pct(metrics.benchmark_return));
+    println!("{:<25} | {:>15}", "Alpha (annualized)", pct(metrics.alpha));
+    println!("{:<25} | {:>15}", "Beta", fmt(metrics.beta));
+    println!("{:-<43}", "");
+}
@@ -0,0 +1,153 @@
+// src/notify/email.rs
+
+//! This module sends notifications as email alerts over SMTP, by default only critical ones. The
+//! channel batches: the first alert goes out right away, and the ones following within the batch
+//! window are collected into a single mail, so a burst of events (e.g. a margin call on every
+//! position) cannot flood the inbox.
+//!
+//! Mails are built and sent with `lettre`: `STARTTLS` (or TLS from the start on port 465),
+//! `AUTH PLAIN`, and a plain-text message to each recipient. Its message builder encodes the
+//! headers, so text from notifications (e.g. a symbol or an exchange error in the subject) cannot
+//! inject headers.
+
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::{Credentials, Mechanism};
+use lettre::transport::smtp::extension::ClientId;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::{Channel, Notification, Notifier, Severity};
+
+/// Default submission port (`STARTTLS`).
+pub const DEFAULT_SMTP_PORT: u16 = 587;
+/// Port on which SMTP servers expect TLS from the start.
+pub const IMPLICIT_TLS_PORT: u16 = 465;
+/// Default time notifications are collected into one mail.
+pub const DEFAULT_EMAIL_BATCH_WINDOW: Duration = Duration::from_secs(5 * 60);
+/// How long one SMTP exchange may take.
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the connection to the SMTP server is encrypted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    Implicit, // TLS from the start, port 465
+    StartTls, // Upgraded with `STARTTLS`, port 587
+    None, // Plain text, only for a relay on a trusted network
+}
+
+/// SMTP server, sender and recipients of the email alerts.
+#[derive(Clone, PartialEq)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub tls: SmtpTls,
+    pub username: Option<String>, // Authenticates with `AUTH PLAIN` when set
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    pub batch_window: Duration, // Notifications within the window are sent in one mail
+    pub min_severity: Severity, // Only critical notifications by default
+}
+
+impl std::fmt::Debug for EmailConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmailConfig") // Never prints the password
+            .field("smtp_host", &self.smtp_host)
+            .field("smtp_port", &self.smtp_port)
+            .field("tls", &self.tls)
+            .field("username", &self.username)
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("batch_window", &self.batch_window)
+            .field("min_severity", &self.min_severity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EmailConfig {
+    /// Returns the notification channel mailing batches of notifications.
+    pub fn into_channel(self) -> Channel {
+        let (min_severity, batch_window) = (self.min_severity, self.batch_window);
+        Channel { notifier: Box::new(EmailNotifier::new(self)), min_severity, batch_window: Some(batch_window) }
+    }
+}
+
+/// Returns the subject and body of the mail for a batch of notifications.
+pub fn batch_mail(batch: &[Notification]) -> (String, String) {
+    let subject = match batch {
+        [single] => format!("[trading bot] {}", single.title()),
+        _ => format!("[trading bot] {} events", batch.len()),
+    };
+    let body = batch.iter().map(Notification::text).collect::<Vec<_>>().join("\n\n");
+    (subject, body)
+}
+
+/// Parses an address such as `bot@example.com` or `Trading bot <bot@example.com>`.
+fn mailbox(address: &str) -> Result<Mailbox, String> {
+    address.trim().parse().map_err(|e| format!("Invalid email address '{}': {}", address, e))
+}
+
+/// Builds the mail from `from` to every address of `to`.
+pub fn build_message(from: &str, to: &[String], subject: &str, body: &str) -> Result<Message, String> {
+    let mut builder = Message::builder()
+        .from(mailbox(from)?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN);
+    for to in to {
+        builder = builder.to(mailbox(to)?);
+    }
+    builder.body(body.to_string()).map_err(|e| format!("Failed to build the mail: {}", e))
+}
+
+/// Sends notifications as email.
+pub struct EmailNotifier {
+    config: EmailConfig,
+}
+
+impl EmailNotifier {
+    /// Creates a notifier for `config`.
+    pub fn new(config: EmailConfig) -> Self {
+        Self { config }
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+        let config = &self.config;
+        let builder = match config.tls {
+            SmtpTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host),
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host),
+            SmtpTls::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)),
+        }.map_err(|e| format!("Failed to set up TLS for {}: {}", config.smtp_host, e))?;
+        let mut builder = builder
+            .port(config.smtp_port)
+            .hello_name(ClientId::Domain("trading-bot".to_string()))
+            .timeout(Some(SMTP_TIMEOUT));
+        if let Some(username) = &config.username {
+            builder = builder
+                .credentials(Credentials::new(username.clone(), config.password.clone().unwrap_or_default()))
+                .authentication(vec![Mechanism::Plain]);
+        }
+        Ok(builder.build())
+    }
+
+    /// Sends one mail to every recipient.
+    pub async fn send_mail(&self, subject: &str, body: &str) -> Result<(), String> {
+        let config = &self.config;
+        let message = build_message(&config.from, &config.to, subject, body)?;
+        self.transport()?.send(message).await
+            .map(drop)
+            .map_err(|e| format!("Failed to send the mail through {}:{}: {}", config.smtp_host, config.smtp_port, e))
+    }
+}
+
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &'static str {
+        "Email"
+    }
+
+    fn send<'a>(&'a self, batch: &'a [Notification]) -> BoxFuture<'a, Result<(), String>> {
+        let (subject, body) = batch_mail(batch);
+        Box::pin(async move { self.send_mail(&subject, &body).await })
+    }
+}
@@ -0,0 +1,427 @@
+// src/notify/mod.rs
+
+//! This module pushes notifications about what the bot is doing (orders placed, fills, rejected
+//! signals, circuit breaker trips, lost exchange connections) to the operator, so nobody has to
+//! tail the logs to know what happens with the account.
+//!
+//! Notifications are handed to a `Notifications` handle, which never blocks the trading path: it
+//! fans them out to one channel per backend (a `Notifier`, see `telegram` and `email`), each with
+//! its own queue, delivery task and severity threshold, e.g. Telegram gets everything while email
+//! only gets critical notifications. When a queue is full, new notifications are dropped with a
+//! warning. A channel may batch: the first notification is delivered right away, the ones
+//! following within the batch window together once it passed.
+//!
+//! Critical notifications (margin calls, liquidations, repeated order rejections, panics,
+//! restarts after a crash, kill switch triggers and REST bans) need attention right away.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::future::BoxFuture;
+use tracing::{error, info, warn};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::events::BotEvent;
+use crate::order::bracket::order_update_from_message;
+use crate::order::ExecutionType;
+use crate::rest_api::ratelimit::{self, Backoff};
+use crate::rest_api::RestClient;
+use crate::websocket::WebSocketClient;
+use crate::websocket_stream::{BinanceWsMessage, MarketStreamClient};
+
+pub mod email;
+pub mod telegram;
+
+/// Number of notifications waiting for delivery before new ones are dropped.
+pub const NOTIFICATION_QUEUE_CAPACITY: usize = 256;
+/// How often the exchange connection is checked by `watch_connection`.
+pub const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Number of orders the exchange must reject within `REPEATED_REJECTIONS_WINDOW` to raise a
+/// `RepeatedRejections` notification.
+pub const REPEATED_REJECTIONS_THRESHOLD: usize = 3;
+/// Window in which rejected orders are counted.
+pub const REPEATED_REJECTIONS_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// How long a subscribed market data stream may stay silent before a `StreamStale` notification.
+pub const DEFAULT_STREAM_STALE_AFTER: Duration = Duration::from_secs(30);
+/// File in the state directory marking a running bot; left behind when it crashes.
+pub const RUN_MARKER_FILE: &str = "running";
+
+/// How urgent a notification is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical, // Needs attention right away
+}
+
+impl Severity {
+    /// Parses `info`, `warning` or `critical`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "critical" => Ok(Severity::Critical),
+            other => Err(format!("Unknown severity '{}' (expected info, warning or critical)", other)),
+        }
+    }
+}
+
+/// Something the operator should know about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    OrderPlaced { symbol: String, signal: String, side: String, quantity: f64, price: f64 },
+    Fill { symbol: String, side: String, price: f64, quantity: f64, realized_pnl: f64 },
+    SignalRejected { symbol: String, signal: String, reason: String },
+    BreakerTripped { breaker: String, reason: String }, // Trading was disarmed by a risk limit
+    Disconnected { connection: String },
+    Reconnected { connection: String },
+    StreamStale { streams: Vec<String>, silent_secs: u64 }, // Subscribed market data streams stopped delivering messages
+    MarginCall { symbol: String, position_side: String, unrealized_pnl: f64, maintenance_margin: f64 },
+    Liquidation { symbol: String, side: String, quantity: f64, price: f64 },
+    RepeatedRejections { count: usize, last_symbol: String, last_reason: String }, // The exchange keeps rejecting orders
+    Panicked { message: String },
+    Restarted { reason: String },
+    Reconciliation { issues: Vec<String> }, // The exchange's state did not match the bot's at startup
+    KillSwitch { source: String, cancelled_orders: usize, closed_positions: usize, problems: Vec<String> },
+    RestBanned { banned_secs: u64 }, // Binance banned the IP (418); REST requests are halted until the ban is over
+}
+
+impl Notification {
+    /// Returns how urgent the notification is.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Notification::OrderPlaced { .. } | Notification::Fill { .. } | Notification::Reconnected { .. } => Severity::Info,
+            Notification::SignalRejected { .. }
+            | Notification::BreakerTripped { .. }
+            | Notification::Disconnected { .. }
+            | Notification::StreamStale { .. }
+            | Notification::Reconciliation { .. } => Severity::Warning,
+            Notification::MarginCall { .. }
+            | Notification::Liquidation { .. }
+            | Notification::RepeatedRejections { .. }
+            | Notification::Panicked { .. }
+            | Notification::Restarted { .. }
+            | Notification::KillSwitch { .. }
+            | Notification::RestBanned { .. } => Severity::Critical,
+        }
+    }
+
+    /// Returns the notifications for a user data stream message: margin calls (one per position)
+    /// and liquidation fills.
+    pub fn from_user_data(message: &BinanceWsMessage) -> Vec<Self> {
+        if let Some(update) = order_update_from_message(message) {
+            let is_liquidation = update.order_type == "LIQUIDATION" || update.client_order_id.starts_with("autoclose-");
+            if !is_liquidation || update.execution_type != ExecutionType::Trade {
+                return Vec::new();
+            }
+            return vec![Notification::Liquidation {
+                symbol: update.symbol,
+                side: update.side,
+                quantity: update.last_filled_quantity.parse().unwrap_or(0.0),
+                price: update.last_filled_price.parse().unwrap_or(0.0),
+            }];
+        }
+        let Some(data) = message.payload() else {
+            return Vec::new();
+        };
+        if data.get("e").and_then(Value::as_str) != Some("MARGIN_CALL") {
+            return Vec::new();
+        }
+        let text = |position: &Value, key: &str| position.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+        let number = |position: &Value, key: &str| text(position, key).parse::<f64>().unwrap_or(0.0);
+        data.get("p").and_then(Value::as_array).into_iter().flatten()
+            .map(|position| Notification::MarginCall {
+                symbol: text(position, "s"),
+                position_side: text(position, "ps"),
+                unrealized_pnl: number(position, "up"),
+                maintenance_margin: number(position, "mm"),
+            })
+            .collect()
+    }
+
+    /// Returns the notification for an event of the event log, if it is one worth sending.
+    pub fn from_event(event: &BotEvent) -> Option<Self> {
+        match event {
+            BotEvent::OrderPlaced { symbol, signal, side, quantity, price, .. } => Some(Notification::OrderPlaced {
+                symbol: symbol.clone(),
+                signal: signal.clone(),
+                side: side.clone(),
+                quantity: *quantity,
+                price: *price,
+            }),
+            BotEvent::Fill { symbol, side, price, quantity, realized_pnl, .. } => Some(Notification::Fill {
+                symbol: symbol.clone(),
+                side: side.clone(),
+                price: *price,
+                quantity: *quantity,
+                realized_pnl: *realized_pnl,
+            }),
+            BotEvent::SignalRejected { symbol, signal, reason } => Some(Notification::SignalRejected {
+                symbol: symbol.clone(),
+                signal: signal.clone(),
+                reason: reason.clone(),
+            }),
+            BotEvent::Signal { .. } | BotEvent::OrderAdopted { .. } | BotEvent::ConfigChange { .. } => None,
+        }
+    }
+
+    /// Returns the one-line summary of the notification.
+    pub fn title(&self) -> String {
+        match self {
+            Notification::OrderPlaced { symbol, side, .. } => format!("Order placed: {} {}", side, symbol),
+            Notification::Fill { symbol, side, .. } => format!("Fill: {} {}", side, symbol),
+            Notification::SignalRejected { symbol, signal, .. } => format!("Signal rejected: {} {}", signal, symbol),
+            Notification::BreakerTripped { breaker, .. } => format!("Trading disarmed: {}", breaker),
+            Notification::Disconnected { connection } => format!("Disconnected: {}", connection),
+            Notification::Reconnected { connection } => format!("Reconnected: {}", connection),
+            Notification::StreamStale { streams, .. } => format!("Stale market data: {}", streams.join(", ")),
+            Notification::MarginCall { symbol, position_side, .. } => format!("Margin call: {} {}", symbol, position_side),
+            Notification::Liquidation { symbol, side, .. } => format!("Liquidation: {} {}", side, symbol),
+            Notification::RepeatedRejections { count, .. } => format!("{} orders rejected", count),
+            Notification::Panicked { .. } => "Bot panicked".to_string(),
+            Notification::Restarted { .. } => "Bot restarted".to_string(),
+            Notification::Reconciliation { issues } => format!("Startup reconciliation: {} issue(s)", issues.len()),
+            Notification::KillSwitch { source, .. } => format!("Kill switch triggered by the {}", source),
+            Notification::RestBanned { .. } => "REST API banned".to_string(),
+        }
+    }
+
+    /// Returns the full text of the notification.
+    pub fn text(&self) -> String {
+        let details = match self {
+            Notification::OrderPlaced { signal, quantity, price, .. } => format!("{} signal, quantity {} at {}", signal, quantity, price),
+            Notification::Fill { price, quantity, realized_pnl, .. } => format!("{} at {}, realized PnL {:.2}", quantity, price, realized_pnl),
+            Notification::SignalRejected { reason, .. } => reason.clone(),
+            Notification::BreakerTripped { reason, .. } => format!("{}; arm the bot again once resolved", reason),
+            Notification::Disconnected { .. } => "Orders cannot be placed until the connection is back".to_string(),
+            Notification::Reconnected { .. } => "The connection is back".to_string(),
+            Notification::StreamStale { silent_secs, .. } => format!("No message for {}s; prices and signals based on these streams are outdated", silent_secs),
+            Notification::MarginCall { unrealized_pnl, maintenance_margin, .. } => format!("Unrealized PnL {:.2}, maintenance margin {:.2}; add margin or reduce the position", unrealized_pnl, maintenance_margin),
+            Notification::Liquidation { quantity, price, .. } => format!("{} liquidated at {}", quantity, price),
+            Notification::RepeatedRejections { last_symbol, last_reason, .. } => format!("The exchange keeps rejecting orders, last for {}: {}", last_symbol, last_reason),
+            Notification::Panicked { message } => message.clone(),
+            Notification::Restarted { reason } => reason.clone(),
+            Notification::Reconciliation { issues } => issues.join("\n"),
+            Notification::KillSwitch { cancelled_orders, closed_positions, problems, .. } => {
+                let summary = format!("Bot disarmed; cancelled {} open order(s) and closed {} position(s)", cancelled_orders, closed_positions);
+                std::iter::once(summary).chain(problems.iter().cloned()).collect::<Vec<_>>().join("\n")
+            },
+            Notification::RestBanned { banned_secs } => format!("Binance banned the IP for too many requests; REST requests (prices, balances, reconciliation) are halted for {}s", banned_secs),
+        };
+        format!("{}\n{}", self.title(), details)
+    }
+}
+
+/// A notification backend.
+pub trait Notifier: Send + Sync {
+    /// Name of the backend, for logs.
+    fn name(&self) -> &'static str;
+
+    /// Delivers a batch of notifications; a single one unless the channel batches.
+    fn send<'a>(&'a self, batch: &'a [Notification]) -> BoxFuture<'a, Result<(), String>>;
+}
+
+/// A backend with the notifications it gets.
+pub struct Channel {
+    pub notifier: Box<dyn Notifier>,
+    pub min_severity: Severity, // Less urgent notifications are not sent to the backend
+    pub batch_window: Option<Duration>, // Notifications within the window are sent together; `None` sends each on its own
+}
+
+impl Channel {
+    /// Creates a channel sending each notification of at least `min_severity` on its own.
+    pub fn new(notifier: impl Notifier + 'static, min_severity: Severity) -> Self {
+        Self { notifier: Box::new(notifier), min_severity, batch_window: None }
+    }
+
+    async fn deliver(&self, batch: &mut Vec<Notification>) {
+        if batch.is_empty() {
+            return;
+        }
+        if let Err(e) = self.notifier.send(batch).await {
+            error!("Failed to send {} notification(s) to {}: {}", batch.len(), self.notifier.name(), e);
+        }
+        batch.clear();
+    }
+
+    /// Delivers queued notifications until every `Notifications` handle is dropped: without a
+    /// batch window each right away, with one the first right away and then at most one batch
+    /// per window.
+    pub async fn run(self, mut receiver: mpsc::Receiver<Notification>) {
+        let window = self.batch_window.unwrap_or_default();
+        let mut batch = Vec::new();
+        let mut next_send = tokio::time::Instant::now();
+        loop {
+            let received = if batch.is_empty() {
+                receiver.recv().await
+            } else {
+                match tokio::time::timeout_at(next_send, receiver.recv()).await {
+                    Ok(received) => received,
+                    Err(_) => {
+                        self.deliver(&mut batch).await;
+                        next_send = tokio::time::Instant::now() + window;
+                        continue;
+                    },
+                }
+            };
+            let Some(notification) = received else { break };
+            batch.push(notification);
+            if tokio::time::Instant::now() >= next_send {
+                self.deliver(&mut batch).await;
+                next_send = tokio::time::Instant::now() + window;
+            }
+        }
+        self.deliver(&mut batch).await;
+        info!("{} notifications stopped", self.notifier.name());
+    }
+}
+
+/// Queues notifications for delivery to every channel they are urgent enough for. Cheap to clone.
+#[derive(Debug, Clone)]
+pub struct Notifications {
+    senders: Vec<(Severity, mpsc::Sender<Notification>)>,
+    rejections: Arc<Mutex<VecDeque<Instant>>>, // Recent orders rejected by the exchange
+}
+
+impl Notifications {
+    /// Starts a delivery task per channel and returns the handle notifications are queued on.
+    /// Must be called within a Tokio runtime.
+    pub fn start(channels: Vec<Channel>) -> Self {
+        let senders = channels.into_iter()
+            .map(|channel| {
+                let (sender, receiver) = mpsc::channel(NOTIFICATION_QUEUE_CAPACITY);
+                let min_severity = channel.min_severity;
+                tokio::spawn(channel.run(receiver));
+                (min_severity, sender)
+            })
+            .collect();
+        Self { senders, rejections: Arc::default() }
+    }
+
+    /// Creates a handle with a single queue of every notification, and its receiving end.
+    pub fn channel(capacity: usize) -> (Self, mpsc::Receiver<Notification>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        (Self { senders: vec![(Severity::Info, sender)], rejections: Arc::default() }, receiver)
+    }
+
+    /// Returns true once every delivery task stopped.
+    pub fn is_closed(&self) -> bool {
+        self.senders.iter().all(|(_, sender)| sender.is_closed())
+    }
+
+    /// Queues a notification without waiting; it is dropped for channels whose queue is full.
+    pub fn notify(&self, notification: Notification) {
+        let severity = notification.severity();
+        for (_, sender) in self.senders.iter().filter(|(min_severity, _)| severity >= *min_severity) {
+            if let Err(e) = sender.try_send(notification.clone()) {
+                warn!("Dropping notification: {}", e);
+            }
+        }
+    }
+
+    /// Records an order the exchange rejected, and raises a `RepeatedRejections` notification once
+    /// `REPEATED_REJECTIONS_THRESHOLD` were rejected within `REPEATED_REJECTIONS_WINDOW`.
+    pub fn order_rejected(&self, symbol: &str, reason: &str) {
+        let now = Instant::now();
+        let Ok(mut rejections) = self.rejections.lock() else { return };
+        rejections.retain(|at| now.duration_since(*at) < REPEATED_REJECTIONS_WINDOW);
+        rejections.push_back(now);
+        if rejections.len() >= REPEATED_REJECTIONS_THRESHOLD {
+            let count = rejections.len();
+            rejections.clear(); // The next alert needs another full series
+            self.notify(Notification::RepeatedRejections { count, last_symbol: symbol.to_string(), last_reason: reason.to_string() });
+        }
+    }
+}
+
+/// Sends a `Panicked` notification when a thread or task panics, then runs the previous panic hook.
+pub fn notify_on_panic(notifications: Notifications) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        notifications.notify(Notification::Panicked { message: info.to_string() });
+        previous(info);
+    }));
+}
+
+/// Marks the bot as running with a file at `path`. Returns a `Restarted` notification when the
+/// file is still there, i.e. the previous run did not shut down cleanly (a crash, a kill, a lost
+/// host); `clear_run_marker` removes it on a clean shutdown.
+pub fn mark_running(path: &Path) -> Result<Option<Notification>, String> {
+    let restarted = path.exists().then(|| Notification::Restarted {
+        reason: "The previous run did not shut down cleanly; check the positions and open orders".to_string(),
+    });
+    fs::write(path, std::process::id().to_string()).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(restarted)
+}
+
+/// Removes the run marker on a clean shutdown.
+pub fn clear_run_marker(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to remove {}: {}", path.display(), e)),
+        _ => Ok(()),
+    }
+}
+
+/// Notifies when the WebSocket API connection (orders) drops and when it is back. Runs until the
+/// notification worker stops.
+pub async fn watch_connection(ws_client: Arc<WebSocketClient>, notifications: Notifications, interval: Duration) {
+    let connection = "WebSocket API (orders)".to_string();
+    let mut was_connected = ws_client.is_connected();
+    let mut ticker = tokio::time::interval(interval);
+    while !notifications.is_closed() {
+        ticker.tick().await;
+        let connected = ws_client.is_connected();
+        match (was_connected, connected) {
+            (true, false) => notifications.notify(Notification::Disconnected { connection: connection.clone() }),
+            (false, true) => notifications.notify(Notification::Reconnected { connection: connection.clone() }),
+            _ => {},
+        }
+        was_connected = connected;
+    }
+}
+
+/// Notifies when Binance bans the IP of the REST client (418) and when the ban is over. Runs until
+/// the notification worker stops.
+pub async fn watch_rest_ban(rest_client: Arc<RestClient>, notifications: Notifications, interval: Duration) {
+    let connection = "REST API".to_string();
+    let mut was_banned = false;
+    let mut ticker = tokio::time::interval(interval);
+    while !notifications.is_closed() {
+        ticker.tick().await;
+        let now = ratelimit::now_ms();
+        let ban = rest_client.rate_limiter().backoff(now).filter(|state| state.kind == Backoff::Banned);
+        match (was_banned, ban) {
+            (false, Some(ban)) => {
+                error!("REST API banned by Binance for {}s", (ban.until_ms - now) / 1000);
+                notifications.notify(Notification::RestBanned { banned_secs: (ban.until_ms - now).div_ceil(1000) });
+            },
+            (true, None) => notifications.notify(Notification::Reconnected { connection: connection.clone() }),
+            _ => {},
+        }
+        was_banned = ban.is_some();
+    }
+}
+
+/// Notifies when subscribed market data streams stay silent for `stale_after`, once per stream
+/// until it delivers messages again. Runs until the notification worker stops.
+pub async fn watch_stream_staleness(client: Arc<MarketStreamClient>, notifications: Notifications, stale_after: Duration, interval: Duration) {
+    let mut reported: Vec<String> = Vec::new();
+    let mut ticker = tokio::time::interval(interval);
+    while !notifications.is_closed() {
+        ticker.tick().await;
+        let stale = client.stale_streams(stale_after);
+        let fresh: Vec<&(String, u64)> = stale.iter().filter(|(stream, _)| !reported.contains(stream)).collect();
+        if let Some(silent_ms) = fresh.iter().map(|(_, silent_ms)| *silent_ms).min() {
+            warn!("Market data streams stale: {:?}", fresh);
+            notifications.notify(Notification::StreamStale {
+                streams: fresh.iter().map(|(stream, _)| stream.clone()).collect(),
+                silent_secs: silent_ms / 1000,
+            });
+        }
+        reported = stale.into_iter().map(|(stream, _)| stream).collect();
+    }
+}
@@ -0,0 +1,241 @@
+// src/notify/telegram.rs
+
+//! This module delivers notifications as Telegram messages through a bot: create one with
+//! @BotFather, send it a message from the chat that should receive the notifications, and read the
+//! chat ID from `https://api.telegram.org/bot<token>/getUpdates`. The token and chat ID are
+//! configured with `TELEGRAM_BOT_TOKEN` and `TELEGRAM_CHAT_ID`.
+//!
+//! The same bot takes arming commands (`serve_arming_commands`) when `TELEGRAM_OPERATOR_IDS` lists
+//! the Telegram user IDs allowed to send them: `/arm` arms the bot, `/disarm <reason>` disarms it
+//! and `/arming` shows the state. Commands are only read from the configured chat, commands from
+//! other users are refused, and commands sent while the bot was not running are ignored.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::{Channel, Notification, Notifier, Severity};
+use crate::arming::{ArmingState, Interlock};
+
+/// Base URL of the Telegram Bot API.
+pub const TELEGRAM_API_BASE_URL: &str = "https://api.telegram.org";
+
+/// Bot token and destination chat.
+#[derive(Clone, PartialEq)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String, // Numeric chat ID, or `@channelname` for public channels
+    pub min_severity: Severity, // Every notification by default
+    pub operators: Vec<i64>, // User IDs allowed to send arming commands; empty disables the commands
+}
+
+impl std::fmt::Debug for TelegramConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TelegramConfig") // Never prints the token
+            .field("chat_id", &self.chat_id)
+            .field("min_severity", &self.min_severity)
+            .field("operators", &self.operators)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TelegramConfig {
+    /// Returns the notification channel sending each notification as a message.
+    pub fn into_channel(self) -> Channel {
+        let min_severity = self.min_severity;
+        Channel::new(TelegramNotifier::new(self), min_severity)
+    }
+}
+
+/// Sends messages to one Telegram chat.
+pub struct TelegramNotifier {
+    config: TelegramConfig,
+    api_base_url: String,
+    http_client: Client,
+}
+
+impl TelegramNotifier {
+    /// Creates a notifier using the public Bot API.
+    pub fn new(config: TelegramConfig) -> Self {
+        Self::with_api_base_url(config, TELEGRAM_API_BASE_URL)
+    }
+
+    /// Creates a notifier using another Bot API server, e.g. a local one.
+    pub fn with_api_base_url(config: TelegramConfig, api_base_url: &str) -> Self {
+        Self { config, api_base_url: api_base_url.trim_end_matches('/').to_string(), http_client: Client::new() }
+    }
+
+    /// Sends one message.
+    pub async fn send_message(&self, text: &str) -> Result<(), String> {
+        let url = format!("{}/bot{}/sendMessage", self.api_base_url, self.config.bot_token);
+        let response = self.http_client.post(&url)
+            .json(&json!({ "chat_id": self.config.chat_id, "text": text, "disable_web_page_preview": true }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Telegram: {}", e.without_url()))?; // The URL holds the token
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Telegram rejected the message ({}): {}", status, body));
+        }
+        Ok(())
+    }
+}
+
+/// How long a `getUpdates` request waits for a message (long polling).
+pub const COMMAND_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+/// Pause after a failed `getUpdates` request.
+const COMMAND_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+struct Updates {
+    #[serde(default)]
+    result: Vec<Update>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    #[serde(default)]
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    chat: Chat,
+    #[serde(default)]
+    from: Option<User>,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+    #[serde(default)]
+    username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: i64,
+    #[serde(default)]
+    username: Option<String>,
+}
+
+impl TelegramNotifier {
+    /// Fetches the updates from `offset` on, waiting up to `timeout` for one to arrive.
+    async fn get_updates(&self, offset: i64, timeout: Duration) -> Result<Vec<Update>, String> {
+        let url = format!("{}/bot{}/getUpdates", self.api_base_url, self.config.bot_token);
+        let response = self.http_client.post(&url)
+            .json(&json!({ "offset": offset, "timeout": timeout.as_secs(), "allowed_updates": ["message"] }))
+            .timeout(timeout + Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Telegram: {}", e.without_url()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Telegram rejected getUpdates ({}): {}", status, body));
+        }
+        let updates: Updates = response.json().await.map_err(|e| format!("Invalid getUpdates response: {}", e.without_url()))?;
+        Ok(updates.result)
+    }
+
+    fn is_configured_chat(&self, chat: &Chat) -> bool {
+        chat.id.to_string() == self.config.chat_id
+            || chat.username.as_deref().is_some_and(|name| self.config.chat_id.strip_prefix('@') == Some(name))
+    }
+}
+
+fn describe(state: &ArmingState) -> String {
+    match (state.armed, &state.armed_by, &state.reason) {
+        (true, Some(by), _) => format!("Live trading is ARMED (by {}).", by),
+        (true, None, _) => "Live trading is ARMED.".to_string(),
+        (false, _, Some(reason)) => format!("Live trading is DISARMED: {}.", reason),
+        (false, _, None) => "Live trading is DISARMED.".to_string(),
+    }
+}
+
+/// Runs an arming command sent by the Telegram user `user_id` and returns the reply; `None` when
+/// `text` is not an arming command. Users outside `operators` are refused. `username` names the
+/// operator in the arming state, e.g. `telegram:@alice`.
+pub fn handle_arming_command(text: &str, user_id: i64, username: Option<&str>, operators: &[i64], interlock: &Interlock) -> Option<String> {
+    let text = text.trim();
+    let (command, argument) = text.split_once(char::is_whitespace).map_or((text, ""), |(c, a)| (c, a.trim()));
+    let command = command.split('@').next().unwrap_or_default(); // `/arm@MyBot` in group chats
+    if !matches!(command, "/arm" | "/disarm" | "/arming") {
+        return None;
+    }
+    if !operators.contains(&user_id) {
+        warn!("Refused Telegram command {} from user {}: not an operator", command, user_id);
+        return Some("You are not allowed to arm or disarm the bot.".to_string());
+    }
+    let operator = match username {
+        Some(name) => format!("telegram:@{}", name),
+        None => format!("telegram:{}", user_id),
+    };
+    let result = match command {
+        "/arm" => interlock.arm(&operator),
+        "/disarm" if argument.is_empty() => interlock.disarm(&format!("disarmed by {}", operator)),
+        "/disarm" => interlock.disarm(&format!("{} (by {})", argument, operator)),
+        _ => Ok(interlock.state()),
+    };
+    Some(match result {
+        Ok(state) => describe(&state),
+        Err(e) => format!("Failed: {}", e),
+    })
+}
+
+/// Answers arming commands (see `handle_arming_command`) sent to the bot in the configured chat,
+/// polling for them with `getUpdates` every `poll_timeout` at most. Runs until the task is dropped.
+/// Commands sent before it started are skipped, so a stale `/arm` never arms a restarted bot.
+pub async fn serve_arming_commands(notifier: TelegramNotifier, interlock: Arc<Interlock>, poll_timeout: Duration) {
+    let mut offset = None;
+    loop {
+        let updates = match offset {
+            Some(offset) => notifier.get_updates(offset, poll_timeout).await,
+            None => notifier.get_updates(-1, Duration::ZERO).await, // Only the latest update, to skip the backlog
+        };
+        let updates = match updates {
+            Ok(updates) => updates,
+            Err(e) => {
+                warn!("Telegram arming commands: {}", e);
+                tokio::time::sleep(COMMAND_RETRY_DELAY).await;
+                continue;
+            },
+        };
+        let skip_backlog = offset.is_none();
+        offset = Some(updates.iter().map(|u| u.update_id + 1).max().or(offset).unwrap_or(0));
+        if skip_backlog {
+            info!("Listening for arming commands from {} Telegram operator(s)", notifier.config.operators.len());
+            continue;
+        }
+        for message in updates.into_iter().filter_map(|u| u.message) {
+            let (Some(text), Some(from)) = (message.text.as_deref(), message.from.as_ref()) else { continue };
+            if !notifier.is_configured_chat(&message.chat) {
+                continue;
+            }
+            let Some(reply) = handle_arming_command(text, from.id, from.username.as_deref(), &notifier.config.operators, &interlock) else { continue };
+            if let Err(e) = notifier.send_message(&reply).await {
+                warn!("Failed to answer a Telegram arming command: {}", e);
+            }
+        }
+    }
+}
+
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "Telegram"
+    }
+
+    fn send<'a>(&'a self, batch: &'a [Notification]) -> BoxFuture<'a, Result<(), String>> {
+        let text = batch.iter().map(Notification::text).collect::<Vec<_>>().join("\n\n");
+        Box::pin(async move { self.send_message(&text).await })
+    }
+}
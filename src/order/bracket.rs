@@ -0,0 +1,432 @@
+// src/order/bracket.rs
+
+//! This module manages bracket orders: an entry order plus a linked stop-loss and take-profit.
+//! When the entry fills (detected via `ORDER_TRADE_UPDATE` events on the user data stream),
+//! reduce-only protective orders are submitted automatically; when one of them fills, its
+//! sibling is cancelled.
+//!
+//! The bookkeeping lives in the synchronous `BracketManager`, which turns order updates into
+//! `BracketAction`s. `spawn_bracket_service` runs the manager in a task that executes those
+//! actions through the `WebSocketClient`, mirroring how the WebSocket clients run their listeners.
+//! Given a `StateStore`, the service resumes the brackets of the previous run and saves the working
+//! ones whenever they change (see `recovery`).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tracing::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use super::{NewOrderRequest, OrderSide, OrderStatus, OrderType, PositionSide, TimeInForce};
+use crate::recovery::StateStore;
+use crate::rest_api::BinanceApiError;
+use crate::streams::{FuturesOrderTradeUpdateEvent, FuturesOrderUpdate};
+use crate::websocket::WebSocketClient;
+use crate::websocket_stream::BinanceWsMessage;
+
+/// Client order ID suffixes used to link the orders of a bracket.
+const ENTRY_SUFFIX: &str = "-en";
+const STOP_LOSS_SUFFIX: &str = "-sl";
+const TAKE_PROFIT_SUFFIX: &str = "-tp";
+
+/// Binance limits client order IDs to 36 characters.
+const MAX_BRACKET_ID_LEN: usize = 36 - 3;
+
+/// Describes a bracket: an entry with a linked stop-loss and take-profit.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketOrder {
+    pub id: String, // Unique bracket ID, used as the prefix of the linked client order IDs
+    pub symbol: String,
+    pub side: OrderSide, // Side of the entry; protective orders use the opposite side
+    pub quantity: f64,
+    pub entry_price: Option<f64>, // `None` enters with a market order, `Some` with a GTC limit order
+    pub stop_loss: f64,
+    pub take_profit: f64,
+    pub position_side: Option<PositionSide>, // `LONG`/`SHORT` in hedge mode
+}
+
+impl BracketOrder {
+    /// Checks that the stop-loss and take-profit sit on the correct sides of the entry.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() || self.id.len() > MAX_BRACKET_ID_LEN {
+            return Err(format!("Bracket ID must be 1-{} characters long.", MAX_BRACKET_ID_LEN));
+        }
+        if self.quantity <= 0.0 {
+            return Err("Bracket quantity must be positive.".to_string());
+        }
+        let valid = match self.side {
+            OrderSide::Buy => self.stop_loss < self.take_profit,
+            OrderSide::Sell => self.stop_loss > self.take_profit,
+        };
+        if !valid {
+            return Err(format!(
+                "Invalid bracket levels for a {:?} entry: stop loss {} and take profit {}",
+                self.side, self.stop_loss, self.take_profit
+            ));
+        }
+        if let Some(entry) = self.entry_price {
+            let between = (self.stop_loss.min(self.take_profit)..=self.stop_loss.max(self.take_profit)).contains(&entry);
+            if !between {
+                return Err(format!("Entry price {} must lie between the stop loss and take profit.", entry));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the entry order request.
+    pub fn entry_request(&self) -> NewOrderRequest {
+        let mut request = match self.entry_price {
+            Some(price) => NewOrderRequest::new(&self.symbol, self.side, OrderType::Limit)
+                .price(price)
+                .time_in_force(TimeInForce::Gtc),
+            None => NewOrderRequest::new(&self.symbol, self.side, OrderType::Market),
+        };
+        request = request.quantity(self.quantity).new_client_order_id(&format!("{}{}", self.id, ENTRY_SUFFIX));
+        if let Some(ps) = &self.position_side {
+            request = request.position_side(ps.clone());
+        }
+        request
+    }
+
+    /// Builds a protective (stop-loss or take-profit) order for `quantity`.
+    fn protective_request(&self, order_type: OrderType, trigger: f64, quantity: f64, suffix: &str) -> NewOrderRequest {
+        let exit_side = match self.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let request = NewOrderRequest::new(&self.symbol, exit_side, order_type)
+            .quantity(quantity)
+            .stop_price(trigger)
+            .new_client_order_id(&format!("{}{}", self.id, suffix));
+        // In hedge mode the position side makes the order closing; reduceOnly is rejected there
+        match &self.position_side {
+            Some(ps @ (PositionSide::Long | PositionSide::Short)) => request.position_side(ps.clone()),
+            _ => request.reduce_only(true),
+        }
+    }
+}
+
+/// Lifecycle state of a bracket.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum BracketState {
+    PendingEntry, // Entry order submitted, not (fully) filled yet
+    Protected, // Entry filled, stop-loss and take-profit working
+    StoppedOut, // Stop-loss filled, take-profit cancelled
+    TookProfit, // Take-profit filled, stop-loss cancelled
+    Cancelled, // Entry cancelled/expired/rejected without any fill, or cancelled by the user
+}
+
+/// An action the bracket service must execute on the exchange.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BracketAction {
+    PlaceOrder(NewOrderRequest),
+    CancelOrder {
+        symbol: String,
+        client_order_id: String,
+    },
+}
+
+/// A bracket tracked by the manager.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackedBracket {
+    pub bracket: BracketOrder,
+    pub state: BracketState,
+    pub filled_quantity: f64, // Filled quantity of the entry
+}
+
+/// Synchronous bookkeeping for bracket orders.
+#[derive(Debug, Default)]
+pub struct BracketManager {
+    brackets: HashMap<String, TrackedBracket>,
+}
+
+impl BracketManager {
+    /// Creates an empty manager.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a manager tracking the brackets of a previous run (see `recovery`); finished ones
+    /// are dropped.
+    pub fn restore(brackets: impl IntoIterator<Item = TrackedBracket>) -> Self {
+        let brackets = brackets.into_iter()
+            .filter(|tracked| matches!(tracked.state, BracketState::PendingEntry | BracketState::Protected))
+            .map(|tracked| (tracked.bracket.id.clone(), tracked))
+            .collect();
+        Self { brackets }
+    }
+
+    /// Returns the brackets still working (pending entry or protected), by ID, to be persisted.
+    pub fn snapshot(&self) -> Vec<TrackedBracket> {
+        let mut working: Vec<TrackedBracket> = self.brackets.values()
+            .filter(|tracked| matches!(tracked.state, BracketState::PendingEntry | BracketState::Protected))
+            .cloned()
+            .collect();
+        working.sort_by(|a, b| a.bracket.id.cmp(&b.bracket.id));
+        working
+    }
+
+    /// Registers a new bracket and returns the action placing its entry order.
+    pub fn register(&mut self, bracket: BracketOrder) -> Result<Vec<BracketAction>, String> {
+        bracket.validate()?;
+        if self.brackets.contains_key(&bracket.id) {
+            return Err(format!("Bracket {} already exists.", bracket.id));
+        }
+        let entry = bracket.entry_request();
+        self.brackets.insert(bracket.id.clone(), TrackedBracket {
+            bracket,
+            state: BracketState::PendingEntry,
+            filled_quantity: 0.0,
+        });
+        Ok(vec![BracketAction::PlaceOrder(entry)])
+    }
+
+    /// Cancels a bracket: a pending entry is cancelled, an active bracket has both protective orders cancelled.
+    /// The position itself (if any) is left open.
+    pub fn cancel(&mut self, id: &str) -> Result<Vec<BracketAction>, String> {
+        let tracked = self.brackets.get_mut(id).ok_or_else(|| format!("Bracket {} not found.", id))?;
+        let symbol = tracked.bracket.symbol.clone();
+        let suffixes: &[&str] = match tracked.state {
+            BracketState::PendingEntry => &[ENTRY_SUFFIX],
+            BracketState::Protected => &[STOP_LOSS_SUFFIX, TAKE_PROFIT_SUFFIX],
+            _ => return Ok(vec![]),
+        };
+        tracked.state = BracketState::Cancelled;
+        Ok(suffixes.iter().map(|suffix| BracketAction::CancelOrder {
+            symbol: symbol.clone(),
+            client_order_id: format!("{}{}", id, suffix),
+        }).collect())
+    }
+
+    /// Returns a tracked bracket by ID.
+    pub fn get(&self, id: &str) -> Option<&TrackedBracket> {
+        self.brackets.get(id)
+    }
+
+    /// Returns all tracked brackets.
+    pub fn brackets(&self) -> impl Iterator<Item = &TrackedBracket> {
+        self.brackets.values()
+    }
+
+    /// Processes an order update from the user data stream.
+    ///
+    /// # Returns
+    /// The actions required in response (placing protective orders or cancelling a sibling).
+    pub fn on_order_update(&mut self, update: &FuturesOrderUpdate) -> Vec<BracketAction> {
+        let client_id = update.client_order_id.as_str();
+        let (id, suffix) = match [ENTRY_SUFFIX, STOP_LOSS_SUFFIX, TAKE_PROFIT_SUFFIX].iter()
+            .find_map(|suffix| client_id.strip_suffix(suffix).map(|id| (id, *suffix)))
+        {
+            Some(found) => found,
+            None => return vec![],
+        };
+        let Some(tracked) = self.brackets.get_mut(id) else {
+            return vec![];
+        };
+        let filled = update.cumulative_filled_quantity.parse::<f64>().unwrap_or(0.0);
+
+        match (suffix, tracked.state, &update.order_status) {
+            (ENTRY_SUFFIX, BracketState::PendingEntry, OrderStatus::PartiallyFilled) => {
+                tracked.filled_quantity = filled;
+                vec![]
+            },
+            (ENTRY_SUFFIX, BracketState::PendingEntry, OrderStatus::Filled) => {
+                tracked.filled_quantity = filled;
+                Self::protect(tracked)
+            },
+            (ENTRY_SUFFIX, BracketState::PendingEntry, OrderStatus::Canceled | OrderStatus::Expired | OrderStatus::ExpiredInMatch | OrderStatus::Rejected) => {
+                tracked.filled_quantity = filled;
+                if filled > 0.0 {
+                    // Protect whatever was filled before the entry stopped working
+                    Self::protect(tracked)
+                } else {
+                    tracked.state = BracketState::Cancelled;
+                    vec![]
+                }
+            },
+            (STOP_LOSS_SUFFIX, BracketState::Protected, OrderStatus::Filled) => {
+                tracked.state = BracketState::StoppedOut;
+                vec![BracketAction::CancelOrder {
+                    symbol: tracked.bracket.symbol.clone(),
+                    client_order_id: format!("{}{}", id, TAKE_PROFIT_SUFFIX),
+                }]
+            },
+            (TAKE_PROFIT_SUFFIX, BracketState::Protected, OrderStatus::Filled) => {
+                tracked.state = BracketState::TookProfit;
+                vec![BracketAction::CancelOrder {
+                    symbol: tracked.bracket.symbol.clone(),
+                    client_order_id: format!("{}{}", id, STOP_LOSS_SUFFIX),
+                }]
+            },
+            _ => vec![],
+        }
+    }
+
+    /// Moves a bracket to `Protected` and returns the stop-loss and take-profit orders for its filled quantity.
+    fn protect(tracked: &mut TrackedBracket) -> Vec<BracketAction> {
+        tracked.state = BracketState::Protected;
+        let bracket = &tracked.bracket;
+        vec![
+            BracketAction::PlaceOrder(bracket.protective_request(OrderType::StopMarket, bracket.stop_loss, tracked.filled_quantity, STOP_LOSS_SUFFIX)),
+            BracketAction::PlaceOrder(bracket.protective_request(OrderType::TakeProfitMarket, bracket.take_profit, tracked.filled_quantity, TAKE_PROFIT_SUFFIX)),
+        ]
+    }
+}
+
+/// Extracts a Futures order update from a user data stream message, if it is one.
+pub fn order_update_from_message(message: &BinanceWsMessage) -> Option<FuturesOrderUpdate> {
+    let data = message.payload()?;
+    if data.get("e").and_then(|e| e.as_str()) != Some("ORDER_TRADE_UPDATE") {
+        return None;
+    }
+    serde_json::from_value::<FuturesOrderTradeUpdateEvent>(data.clone()).ok().map(|event| event.order)
+}
+
+/// Commands accepted by the bracket service task.
+enum BracketCommand {
+    Submit {
+        bracket: BracketOrder,
+        response_tx: oneshot::Sender<Result<(), String>>,
+    },
+    Cancel {
+        id: String,
+        response_tx: oneshot::Sender<Result<(), String>>,
+    },
+}
+
+/// Handle to a running bracket service.
+pub struct BracketService {
+    command_sender: mpsc::Sender<BracketCommand>,
+    _service_handle: JoinHandle<()>,
+}
+
+impl BracketService {
+    /// Submits a new bracket. Resolves once the entry order has been accepted by the exchange.
+    pub async fn submit(&self, bracket: BracketOrder) -> Result<(), String> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.command_sender.send(BracketCommand::Submit { bracket, response_tx }).await
+            .map_err(|e| format!("Failed to send bracket command: {}", e))?;
+        response_rx.await.map_err(|e| format!("Failed to receive bracket response: {}", e))?
+    }
+
+    /// Cancels a bracket's working orders.
+    pub async fn cancel(&self, id: &str) -> Result<(), String> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.command_sender.send(BracketCommand::Cancel { id: id.to_string(), response_tx }).await
+            .map_err(|e| format!("Failed to send bracket command: {}", e))?;
+        response_rx.await.map_err(|e| format!("Failed to receive bracket response: {}", e))?
+    }
+}
+
+/// Executes bracket actions through the WebSocket API, stopping at the first failure. Cancelling
+/// an order that no longer exists is not a failure.
+/// Also used to execute the actions of other order state machines (e.g. take-profit ladders).
+pub async fn execute_actions(ws_client: &WebSocketClient, actions: Vec<BracketAction>) -> Result<(), String> {
+    for action in actions {
+        match action {
+            BracketAction::PlaceOrder(request) => {
+                let response = ws_client.place_order(&request).await?;
+                info!("Bracket order {} placed (order ID {})", response.client_order_id, response.order_id);
+            },
+            BracketAction::CancelOrder { symbol, client_order_id } => {
+                match ws_client.cancel_order(&symbol, None, Some(&client_order_id)).await {
+                    Ok(_) => info!("Bracket order {} cancelled", client_order_id),
+                    // Already filled or cancelled, e.g. both legs triggered at once
+                    Err(e) if BinanceApiError::from_error(&e).is_some_and(|e| e.code.is_unknown_order()) => {
+                        info!("Bracket order {} was already gone: {}", client_order_id, e);
+                    },
+                    Err(e) => return Err(e),
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Spawns the bracket service.
+///
+/// # Arguments
+/// * `ws_client` - Used to place and cancel orders.
+/// * `user_stream_receiver` - Messages from the USDⓈ-M user data stream (must carry `ORDER_TRADE_UPDATE` events).
+/// * `store` - Where the working brackets are persisted across restarts; `None` keeps them in memory.
+///
+/// # Returns
+/// A `BracketService` handle used to submit and cancel brackets.
+pub fn spawn_bracket_service(
+    ws_client: Arc<WebSocketClient>,
+    mut user_stream_receiver: mpsc::Receiver<BinanceWsMessage>,
+    store: Option<Arc<StateStore>>,
+) -> BracketService {
+    let (command_sender, mut command_receiver) = mpsc::channel::<BracketCommand>(100);
+
+    let service_handle = tokio::spawn(async move {
+        let mut manager = match &store {
+            Some(store) => BracketManager::restore(store.state().brackets),
+            None => BracketManager::new(),
+        };
+        let mut saved = manager.snapshot();
+        loop {
+            tokio::select! {
+                command = command_receiver.recv() => {
+                    match command {
+                        Some(BracketCommand::Submit { bracket, response_tx }) => {
+                            let id = bracket.id.clone();
+                            let result = match manager.register(bracket) {
+                                Ok(actions) => {
+                                    let result = execute_actions(&ws_client, actions).await;
+                                    if result.is_err() {
+                                        // The entry never reached the exchange
+                                        let _ = manager.cancel(&id);
+                                    }
+                                    result
+                                },
+                                Err(e) => Err(e),
+                            };
+                            let _ = response_tx.send(result);
+                        },
+                        Some(BracketCommand::Cancel { id, response_tx }) => {
+                            let result = match manager.cancel(&id) {
+                                Ok(actions) => execute_actions(&ws_client, actions).await,
+                                Err(e) => Err(e),
+                            };
+                            let _ = response_tx.send(result);
+                        },
+                        None => {
+                            info!("Bracket command channel closed. Stopping bracket service.");
+                            return;
+                        }
+                    }
+                },
+                message = user_stream_receiver.recv() => {
+                    let Some(message) = message else {
+                        warn!("User data stream channel closed. Stopping bracket service.");
+                        return;
+                    };
+                    if let Some(update) = order_update_from_message(&message) {
+                        debug!("Bracket service received order update: {} {}", update.client_order_id, update.order_status);
+                        let actions = manager.on_order_update(&update);
+                        if let Err(e) = execute_actions(&ws_client, actions).await {
+                            error!("Failed to execute bracket actions for {}: {}", update.client_order_id, e);
+                        }
+                    }
+                }
+            }
+            if let Some(store) = &store {
+                let working = manager.snapshot();
+                if working != saved {
+                    store.update(|state| state.brackets = working.clone()).unwrap_or_else(|e| error!("{}", e));
+                    saved = working;
+                }
+            }
+        }
+    });
+
+    BracketService {
+        command_sender,
+        _service_handle: service_handle,
+    }
+}
@@ -0,0 +1,217 @@
+// src/order/group.rs
+
+//! This module tracks orders by client order ID and lets them be tagged with a named group (a
+//! grid's level set, the slices of a TWAP or iceberg, the legs of a bracket), so a multi-order
+//! structure can be queried and cancelled as a unit.
+//!
+//! `OrderTracker` is synchronous bookkeeping like `BracketManager`: orders are registered when
+//! submitted (directly or from the `BracketAction`s of the other order state machines), kept up
+//! to date from `ORDER_TRADE_UPDATE` events, and `cancel_group` returns the cancel actions for the
+//! group's working orders. `WebSocketClient::cancel_order_group` executes them.
+
+use std::collections::HashMap;
+
+use tokio::sync::Mutex;
+
+use super::bracket::{execute_actions, BracketAction};
+use super::{NewOrderRequest, OrderStatus};
+use crate::streams::FuturesOrderUpdate;
+use crate::websocket::WebSocketClient;
+
+/// State of a tracked order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackedOrderState {
+    Submitted, // Sent, not acknowledged by the exchange yet
+    Open,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Expired,
+    Rejected,
+}
+
+impl TrackedOrderState {
+    /// Maps an order status of the user data stream (`X`).
+    pub fn from_status(status: &OrderStatus) -> Option<Self> {
+        match status {
+            OrderStatus::New => Some(TrackedOrderState::Open),
+            OrderStatus::PartiallyFilled => Some(TrackedOrderState::PartiallyFilled),
+            OrderStatus::Filled => Some(TrackedOrderState::Filled),
+            OrderStatus::Canceled => Some(TrackedOrderState::Cancelled),
+            OrderStatus::Expired | OrderStatus::ExpiredInMatch => Some(TrackedOrderState::Expired),
+            OrderStatus::Rejected => Some(TrackedOrderState::Rejected),
+            _ => None,
+        }
+    }
+
+    /// Returns true while the order can still fill (and so must be cancelled with its group).
+    pub fn is_working(&self) -> bool {
+        matches!(self, TrackedOrderState::Submitted | TrackedOrderState::Open | TrackedOrderState::PartiallyFilled)
+    }
+}
+
+/// An order known to the tracker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedOrder {
+    pub symbol: String,
+    pub client_order_id: String,
+    pub group: Option<String>,
+    pub quantity: Option<f64>,
+    pub filled_quantity: f64,
+    pub state: TrackedOrderState,
+}
+
+/// Summary of a group's orders.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GroupStatus {
+    pub name: String,
+    pub orders: usize,
+    pub working: usize, // Submitted, open or partially filled
+    pub filled: usize,
+    pub cancelled: usize, // Cancelled, expired or rejected
+    pub filled_quantity: f64,
+}
+
+impl GroupStatus {
+    /// Returns true once none of the group's orders can fill any more.
+    pub fn is_done(&self) -> bool {
+        self.working == 0
+    }
+}
+
+/// Bookkeeping of submitted orders and their groups.
+#[derive(Debug, Default)]
+pub struct OrderTracker {
+    orders: HashMap<String, TrackedOrder>,
+    groups: HashMap<String, Vec<String>>, // Group name -> client order IDs, in submission order
+}
+
+impl OrderTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a submitted order, optionally as part of `group`. The order needs a client order ID.
+    pub fn track(&mut self, request: &NewOrderRequest, group: Option<&str>) -> Result<(), String> {
+        let client_order_id = request.new_client_order_id.clone()
+            .ok_or("Grouped orders need a client order ID.")?;
+        if self.orders.contains_key(&client_order_id) {
+            return Err(format!("Order {} is already tracked.", client_order_id));
+        }
+        if let Some(name) = group {
+            if name.is_empty() {
+                return Err("Group names must not be empty.".to_string());
+            }
+            self.groups.entry(name.to_string()).or_default().push(client_order_id.clone());
+        }
+        self.orders.insert(client_order_id.clone(), TrackedOrder {
+            symbol: request.symbol.clone(),
+            client_order_id,
+            group: group.map(str::to_string),
+            quantity: request.quantity,
+            filled_quantity: 0.0,
+            state: TrackedOrderState::Submitted,
+        });
+        Ok(())
+    }
+
+    /// Registers the orders placed by `actions` (e.g. a ladder's or bracket's) as part of `group`.
+    pub fn track_actions(&mut self, actions: &[BracketAction], group: &str) -> Result<(), String> {
+        for action in actions {
+            if let BracketAction::PlaceOrder(request) = action {
+                self.track(request, Some(group))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes an order update from the user data stream. Updates of untracked orders are ignored.
+    pub fn on_order_update(&mut self, update: &FuturesOrderUpdate) {
+        let Some(order) = self.orders.get_mut(&update.client_order_id) else { return };
+        if let Some(state) = TrackedOrderState::from_status(&update.order_status) {
+            order.state = state;
+        }
+        order.filled_quantity = update.cumulative_filled_quantity.parse::<f64>().unwrap_or(order.filled_quantity);
+    }
+
+    /// Returns a tracked order by client order ID.
+    pub fn get(&self, client_order_id: &str) -> Option<&TrackedOrder> {
+        self.orders.get(client_order_id)
+    }
+
+    /// Returns the names of all groups.
+    pub fn groups(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.groups.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the orders of a group, in submission order.
+    pub fn group_orders(&self, name: &str) -> Vec<&TrackedOrder> {
+        self.groups.get(name)
+            .map(|ids| ids.iter().filter_map(|id| self.orders.get(id)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the status of a group, or `None` if it does not exist.
+    pub fn group_status(&self, name: &str) -> Option<GroupStatus> {
+        self.groups.get(name)?;
+        let mut status = GroupStatus { name: name.to_string(), ..Default::default() };
+        for order in self.group_orders(name) {
+            status.orders += 1;
+            status.filled_quantity += order.filled_quantity;
+            match order.state {
+                state if state.is_working() => status.working += 1,
+                TrackedOrderState::Filled => status.filled += 1,
+                _ => status.cancelled += 1,
+            }
+        }
+        Some(status)
+    }
+
+    /// Returns the actions cancelling every working order of a group. The orders keep their state
+    /// until the exchange confirms the cancellation on the user data stream.
+    pub fn cancel_group(&self, name: &str) -> Result<Vec<BracketAction>, String> {
+        if !self.groups.contains_key(name) {
+            return Err(format!("Order group {} not found.", name));
+        }
+        Ok(self.group_orders(name).into_iter()
+            .filter(|order| order.state.is_working())
+            .map(|order| BracketAction::CancelOrder {
+                symbol: order.symbol.clone(),
+                client_order_id: order.client_order_id.clone(),
+            })
+            .collect())
+    }
+
+    /// Forgets a group and its orders once none of them is working.
+    pub fn remove_group(&mut self, name: &str) -> Result<(), String> {
+        let status = self.group_status(name).ok_or_else(|| format!("Order group {} not found.", name))?;
+        if !status.is_done() {
+            return Err(format!("Order group {} still has {} working orders.", name, status.working));
+        }
+        for id in self.groups.remove(name).unwrap_or_default() {
+            self.orders.remove(&id);
+        }
+        Ok(())
+    }
+}
+
+impl WebSocketClient { // Group-level order management
+    /// Cancels every working order of a group.
+    ///
+    /// # Arguments
+    /// * `tracker` - The tracker the group's orders were registered with.
+    /// * `name` - The group name.
+    ///
+    /// # Returns
+    /// A `Result` containing the number of cancel requests sent, or a `String` error if the group
+    /// does not exist or a cancellation fails.
+    pub async fn cancel_order_group(&self, tracker: &Mutex<OrderTracker>, name: &str) -> Result<usize, String> {
+        let actions = tracker.lock().await.cancel_group(name)?;
+        let count = actions.len();
+        execute_actions(self, actions).await?;
+        Ok(count)
+    }
+}
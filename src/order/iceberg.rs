@@ -0,0 +1,199 @@
+// src/order/iceberg.rs
+
+//! This module works a large limit order as an iceberg: only a small visible clip rests on the
+//! book at a time, and the next clip is posted as soon as the previous one fills, so the full
+//! size is never shown. Fills are detected from `ORDER_TRADE_UPDATE` events.
+//!
+//! `IcebergManager` returns `BracketAction`s, which are executed with `bracket::execute_actions`.
+
+use std::collections::HashMap;
+
+use tracing::info;
+
+use super::bracket::BracketAction;
+use super::{round_down_to_step, NewOrderRequest, OrderSide, OrderStatus, OrderType, PositionSide, TimeInForce};
+use crate::streams::FuturesOrderUpdate;
+
+/// Binance limits client order IDs to 36 characters; leave room for the `-cN` suffix.
+const MAX_ICEBERG_ID_LEN: usize = 28;
+
+/// A large limit order to be worked in clips.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcebergOrder {
+    pub id: String, // Unique ID, used as the prefix of the clips' client order IDs
+    pub symbol: String,
+    pub side: OrderSide,
+    pub price: f64,
+    pub total_quantity: f64,
+    pub clip_quantity: f64, // Visible size of each clip
+    pub step_size: f64, // LOT_SIZE step used to round clip quantities (0.0 disables rounding)
+    pub time_in_force: TimeInForce, // e.g. GTC, or GTX to only ever add liquidity
+    pub reduce_only: bool,
+    pub position_side: Option<PositionSide>, // `LONG`/`SHORT` in hedge mode
+}
+
+impl IcebergOrder {
+    /// Validates sizes and the ID length.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() || self.id.len() > MAX_ICEBERG_ID_LEN {
+            return Err(format!("Iceberg ID must be 1-{} characters long.", MAX_ICEBERG_ID_LEN));
+        }
+        if self.price <= 0.0 || self.total_quantity <= 0.0 || self.clip_quantity <= 0.0 {
+            return Err("Iceberg price and quantities must be positive.".to_string());
+        }
+        if self.clip_quantity > self.total_quantity {
+            return Err("Iceberg clip quantity cannot exceed the total quantity.".to_string());
+        }
+        if round_down_to_step(self.clip_quantity, self.step_size) <= 0.0 {
+            return Err(format!("Iceberg clip quantity {} is smaller than the step size {}.", self.clip_quantity, self.step_size));
+        }
+        Ok(())
+    }
+}
+
+/// Lifecycle state of an iceberg.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IcebergState {
+    Working,
+    Filled,
+    Cancelled, // By the user, or a clip was cancelled/expired/rejected by the exchange
+}
+
+/// An iceberg tracked by the manager.
+#[derive(Debug, Clone)]
+pub struct TrackedIceberg {
+    pub order: IcebergOrder,
+    pub state: IcebergState,
+    pub filled_quantity: f64, // Across completed clips
+    pub clip_filled_quantity: f64, // Of the working clip
+    pub clips_posted: u32,
+    working_clip: Option<String>,
+}
+
+impl TrackedIceberg {
+    /// Returns the quantity not filled yet.
+    pub fn remaining_quantity(&self) -> f64 {
+        (self.order.total_quantity - self.filled_quantity - self.clip_filled_quantity).max(0.0)
+    }
+
+    /// Returns the client order ID of the clip resting on the book, if any.
+    pub fn working_clip(&self) -> Option<&str> {
+        self.working_clip.as_deref()
+    }
+
+    /// Posts the next clip for the remaining quantity, if any is left.
+    fn post_next_clip(&mut self) -> Vec<BracketAction> {
+        let remaining = round_down_to_step(self.remaining_quantity(), self.order.step_size);
+        if remaining <= 0.0 {
+            self.state = IcebergState::Filled;
+            self.working_clip = None;
+            return vec![];
+        }
+        let quantity = round_down_to_step(self.order.clip_quantity.min(remaining), self.order.step_size);
+        self.clips_posted += 1;
+        let client_order_id = format!("{}-c{}", self.order.id, self.clips_posted);
+        let mut request = NewOrderRequest::new(&self.order.symbol, self.order.side, OrderType::Limit)
+            .quantity(quantity)
+            .price(self.order.price)
+            .time_in_force(self.order.time_in_force)
+            .new_client_order_id(&client_order_id);
+        if let Some(ps) = &self.order.position_side {
+            request = request.position_side(ps.clone());
+        }
+        if self.order.reduce_only {
+            request = request.reduce_only(true);
+        }
+        self.working_clip = Some(client_order_id);
+        vec![BracketAction::PlaceOrder(request)]
+    }
+}
+
+/// Synchronous bookkeeping for iceberg orders.
+#[derive(Debug, Default)]
+pub struct IcebergManager {
+    icebergs: HashMap<String, TrackedIceberg>,
+}
+
+impl IcebergManager {
+    /// Creates an empty manager.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts working an iceberg and returns the action posting its first clip.
+    pub fn start(&mut self, order: IcebergOrder) -> Result<Vec<BracketAction>, String> {
+        order.validate()?;
+        if self.icebergs.contains_key(&order.id) {
+            return Err(format!("Iceberg {} already exists.", order.id));
+        }
+        let mut tracked = TrackedIceberg {
+            order,
+            state: IcebergState::Working,
+            filled_quantity: 0.0,
+            clip_filled_quantity: 0.0,
+            clips_posted: 0,
+            working_clip: None,
+        };
+        let actions = tracked.post_next_clip();
+        self.icebergs.insert(tracked.order.id.clone(), tracked);
+        Ok(actions)
+    }
+
+    /// Stops working an iceberg and cancels its resting clip.
+    pub fn cancel(&mut self, id: &str) -> Result<Vec<BracketAction>, String> {
+        let tracked = self.icebergs.get_mut(id).ok_or_else(|| format!("Iceberg {} not found.", id))?;
+        if tracked.state != IcebergState::Working {
+            return Ok(vec![]);
+        }
+        tracked.state = IcebergState::Cancelled;
+        Ok(tracked.working_clip.take().map(|client_order_id| BracketAction::CancelOrder {
+            symbol: tracked.order.symbol.clone(),
+            client_order_id,
+        }).into_iter().collect())
+    }
+
+    /// Returns a tracked iceberg by ID.
+    pub fn get(&self, id: &str) -> Option<&TrackedIceberg> {
+        self.icebergs.get(id)
+    }
+
+    /// Processes an order update from the user data stream.
+    ///
+    /// # Returns
+    /// The action posting the next clip when the working clip filled, or nothing.
+    pub fn on_order_update(&mut self, update: &FuturesOrderUpdate) -> Vec<BracketAction> {
+        let client_id = update.client_order_id.as_str();
+        let Some(tracked) = self.icebergs.values_mut()
+            .find(|t| t.state == IcebergState::Working && t.working_clip.as_deref() == Some(client_id))
+        else {
+            return vec![];
+        };
+        let clip_filled = update.cumulative_filled_quantity.parse::<f64>().unwrap_or(0.0);
+
+        match &update.order_status {
+            OrderStatus::PartiallyFilled => {
+                tracked.clip_filled_quantity = clip_filled;
+                vec![]
+            },
+            OrderStatus::Filled => {
+                tracked.filled_quantity += clip_filled;
+                tracked.clip_filled_quantity = 0.0;
+                let actions = tracked.post_next_clip();
+                if tracked.state == IcebergState::Filled {
+                    info!("Iceberg {} fully filled ({} in {} clips)", tracked.order.id, tracked.filled_quantity, tracked.clips_posted);
+                }
+                actions
+            },
+            OrderStatus::Canceled | OrderStatus::Expired | OrderStatus::ExpiredInMatch | OrderStatus::Rejected => {
+                // e.g. a GTX clip that would have crossed the book, or a manual cancel on the exchange
+                tracked.filled_quantity += clip_filled;
+                tracked.clip_filled_quantity = 0.0;
+                tracked.working_clip = None;
+                tracked.state = IcebergState::Cancelled;
+                info!("Iceberg {} stopped: clip {} {}", tracked.order.id, client_id, update.order_status);
+                vec![]
+            },
+            _ => vec![],
+        }
+    }
+}
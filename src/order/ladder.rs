@@ -0,0 +1,345 @@
+// src/order/ladder.rs
+
+//! This module splits the exit of a position into a ladder of reduce-only take-profit orders
+//! (e.g. 50% at 1R, 30% at 2R, 20% at 3R, where R is the distance between entry and stop),
+//! protected by a stop for the remaining quantity.
+//!
+//! `LadderManager` is driven by `ORDER_TRADE_UPDATE` events: whenever a tranche fills, the stop
+//! is replaced to cover only the remaining quantity (and optionally moved to breakeven or to the
+//! previous target); when the stop fills, the unfilled tranches are cancelled. The returned
+//! `BracketAction`s are executed with `bracket::execute_actions`.
+
+use std::collections::HashMap;
+
+use tracing::info;
+
+use super::bracket::BracketAction;
+use super::{round_down_to_step, NewOrderRequest, OrderSide, OrderStatus, OrderType, PositionSide, TimeInForce};
+use crate::streams::FuturesOrderUpdate;
+
+/// Binance limits client order IDs to 36 characters; leave room for the `-tpN`/`-slN` suffixes.
+const MAX_LADDER_ID_LEN: usize = 28;
+
+/// One rung of the ladder: close `fraction` of the position at `r_multiple` R.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LadderTranche {
+    pub fraction: f64, // Fraction of the initial quantity, e.g. 0.5
+    pub r_multiple: f64, // Target distance from entry in multiples of the initial risk, e.g. 1.0
+}
+
+/// How the stop is moved as tranches fill.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopAdjustment {
+    KeepOriginal, // Only the stop quantity shrinks
+    Breakeven, // Move the stop to the entry price after the first tranche fills
+    PreviousTarget, // Move the stop to the previous tranche's target (breakeven after the first)
+}
+
+/// Take-profit ladder settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LadderConfig {
+    pub tranches: Vec<LadderTranche>,
+    pub stop_adjustment: StopAdjustment,
+}
+
+impl Default for LadderConfig {
+    /// 50% at 1R, 30% at 2R, 20% at 3R, moving the stop to breakeven after the first fill.
+    fn default() -> Self {
+        Self {
+            tranches: vec![
+                LadderTranche { fraction: 0.5, r_multiple: 1.0 },
+                LadderTranche { fraction: 0.3, r_multiple: 2.0 },
+                LadderTranche { fraction: 0.2, r_multiple: 3.0 },
+            ],
+            stop_adjustment: StopAdjustment::Breakeven,
+        }
+    }
+}
+
+impl LadderConfig {
+    /// Checks that the tranches are non-empty, positive, ascending, and do not exceed the position.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tranches.is_empty() {
+            return Err("A take-profit ladder needs at least one tranche.".to_string());
+        }
+        if self.tranches.iter().any(|t| t.fraction <= 0.0 || t.r_multiple <= 0.0) {
+            return Err("Ladder tranche fractions and R multiples must be positive.".to_string());
+        }
+        if self.tranches.windows(2).any(|w| w[1].r_multiple <= w[0].r_multiple) {
+            return Err("Ladder tranches must have strictly increasing R multiples.".to_string());
+        }
+        let total: f64 = self.tranches.iter().map(|t| t.fraction).sum();
+        if total > 1.0 + 1e-9 {
+            return Err(format!("Ladder tranche fractions sum to {}, more than the whole position.", total));
+        }
+        Ok(())
+    }
+}
+
+/// Ladder settings per strategy and/or symbol, with a fallback default.
+#[derive(Debug, Clone, Default)]
+pub struct LadderConfigSet {
+    pub default: LadderConfig,
+    overrides: HashMap<(Option<String>, Option<String>), LadderConfig>,
+}
+
+impl LadderConfigSet {
+    /// Creates a set with `default` used when no override matches.
+    pub fn new(default: LadderConfig) -> Self {
+        Self { default, overrides: HashMap::new() }
+    }
+
+    /// Sets the ladder for a strategy, a symbol, or a strategy/symbol pair.
+    pub fn set(&mut self, strategy: Option<&str>, symbol: Option<&str>, config: LadderConfig) -> Result<(), String> {
+        config.validate()?;
+        self.overrides.insert((strategy.map(str::to_string), symbol.map(str::to_uppercase)), config);
+        Ok(())
+    }
+
+    /// Resolves the ladder for a position: strategy+symbol, then symbol, then strategy, then the default.
+    pub fn resolve(&self, strategy: &str, symbol: &str) -> &LadderConfig {
+        let strategy = Some(strategy.to_string());
+        let symbol = Some(symbol.to_uppercase());
+        [(strategy.clone(), symbol.clone()), (None, symbol), (strategy, None)].iter()
+            .find_map(|key| self.overrides.get(key))
+            .unwrap_or(&self.default)
+    }
+}
+
+/// An open position to be exited through a take-profit ladder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LadderPosition {
+    pub id: String, // Unique ID, used as the prefix of the ladder's client order IDs
+    pub symbol: String,
+    pub side: OrderSide, // `Buy` for a long position, `Sell` for a short one
+    pub entry_price: f64,
+    pub stop_price: f64, // Initial stop; defines 1R
+    pub quantity: f64,
+    pub step_size: f64, // LOT_SIZE step used to round tranche quantities (0.0 disables rounding)
+    pub position_side: Option<PositionSide>, // `LONG`/`SHORT` in hedge mode
+}
+
+/// Lifecycle state of a ladder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LadderState {
+    Active,
+    Completed, // All tranches filled
+    StoppedOut, // The stop filled; unfilled tranches were cancelled
+    Cancelled, // Cancelled by the user
+}
+
+/// A take-profit order of the ladder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LadderRung {
+    pub client_order_id: String,
+    pub price: f64,
+    pub quantity: f64,
+    pub filled: bool,
+}
+
+/// A ladder tracked by the manager.
+#[derive(Debug, Clone)]
+pub struct TrackedLadder {
+    pub position: LadderPosition,
+    pub stop_adjustment: StopAdjustment,
+    pub state: LadderState,
+    pub rungs: Vec<LadderRung>,
+    pub stop_price: f64, // Current stop trigger price
+    pub stop_client_order_id: Option<String>,
+    stop_sequence: u32,
+}
+
+impl TrackedLadder {
+    /// Returns the position quantity not yet closed by filled tranches.
+    pub fn remaining_quantity(&self) -> f64 {
+        let filled: f64 = self.rungs.iter().filter(|r| r.filled).map(|r| r.quantity).sum();
+        (self.position.quantity - filled).max(0.0)
+    }
+
+    fn exit_side(&self) -> OrderSide {
+        match self.position.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        }
+    }
+
+    /// Makes an exit order closing: reduceOnly in one-way mode, the position side in hedge mode.
+    fn closing(&self, request: NewOrderRequest) -> NewOrderRequest {
+        match &self.position.position_side {
+            Some(ps @ (PositionSide::Long | PositionSide::Short)) => request.position_side(ps.clone()),
+            _ => request.reduce_only(true),
+        }
+    }
+
+    /// Returns actions replacing the stop with one for `quantity` at `self.stop_price`.
+    fn replace_stop(&mut self, quantity: f64) -> Vec<BracketAction> {
+        let mut actions = self.cancel_stop();
+        self.stop_sequence += 1;
+        let client_order_id = format!("{}-sl{}", self.position.id, self.stop_sequence);
+        let request = NewOrderRequest::new(&self.position.symbol, self.exit_side(), OrderType::StopMarket)
+            .quantity(quantity)
+            .stop_price(self.stop_price)
+            .new_client_order_id(&client_order_id);
+        actions.push(BracketAction::PlaceOrder(self.closing(request)));
+        self.stop_client_order_id = Some(client_order_id);
+        actions
+    }
+
+    fn cancel_stop(&mut self) -> Vec<BracketAction> {
+        self.stop_client_order_id.take().map(|client_order_id| BracketAction::CancelOrder {
+            symbol: self.position.symbol.clone(),
+            client_order_id,
+        }).into_iter().collect()
+    }
+
+    fn cancel_open_rungs(&self) -> Vec<BracketAction> {
+        self.rungs.iter().filter(|r| !r.filled).map(|r| BracketAction::CancelOrder {
+            symbol: self.position.symbol.clone(),
+            client_order_id: r.client_order_id.clone(),
+        }).collect()
+    }
+}
+
+/// Synchronous bookkeeping for take-profit ladders.
+#[derive(Debug, Default)]
+pub struct LadderManager {
+    ladders: HashMap<String, TrackedLadder>,
+}
+
+impl LadderManager {
+    /// Creates an empty manager.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a ladder for an open position.
+    ///
+    /// # Returns
+    /// The actions placing the take-profit tranches (reduce-only GTC limit orders) and the stop.
+    pub fn open(&mut self, position: LadderPosition, config: &LadderConfig) -> Result<Vec<BracketAction>, String> {
+        config.validate()?;
+        if position.id.is_empty() || position.id.len() > MAX_LADDER_ID_LEN {
+            return Err(format!("Ladder ID must be 1-{} characters long.", MAX_LADDER_ID_LEN));
+        }
+        if self.ladders.contains_key(&position.id) {
+            return Err(format!("Ladder {} already exists.", position.id));
+        }
+        let risk = match position.side {
+            OrderSide::Buy => position.entry_price - position.stop_price,
+            OrderSide::Sell => position.stop_price - position.entry_price,
+        };
+        if risk <= 0.0 || position.quantity <= 0.0 {
+            return Err(format!(
+                "Invalid ladder position: stop {} must be on the losing side of entry {} and quantity positive",
+                position.stop_price, position.entry_price
+            ));
+        }
+
+        let direction = if position.side == OrderSide::Buy { 1.0 } else { -1.0 };
+        let mut rungs = Vec::new();
+        let mut allocated = 0.0;
+        let full_ladder = config.tranches.iter().map(|t| t.fraction).sum::<f64>() > 1.0 - 1e-9;
+        for (i, tranche) in config.tranches.iter().enumerate() {
+            let is_last = i == config.tranches.len() - 1;
+            // A ladder covering the whole position gives the rounding remainder to the last tranche
+            let quantity = if is_last && full_ladder {
+                round_down_to_step(position.quantity - allocated, position.step_size)
+            } else {
+                round_down_to_step(position.quantity * tranche.fraction, position.step_size)
+            };
+            if quantity <= 0.0 {
+                return Err(format!("Ladder tranche {} rounds to a zero quantity.", i + 1));
+            }
+            allocated += quantity;
+            rungs.push(LadderRung {
+                client_order_id: format!("{}-tp{}", position.id, i + 1),
+                price: position.entry_price + direction * risk * tranche.r_multiple,
+                quantity,
+                filled: false,
+            });
+        }
+
+        let mut ladder = TrackedLadder {
+            stop_price: position.stop_price,
+            position,
+            stop_adjustment: config.stop_adjustment,
+            state: LadderState::Active,
+            rungs,
+            stop_client_order_id: None,
+            stop_sequence: 0,
+        };
+        let mut actions: Vec<BracketAction> = ladder.rungs.iter().map(|rung| {
+            let request = NewOrderRequest::new(&ladder.position.symbol, ladder.exit_side(), OrderType::Limit)
+                .quantity(rung.quantity)
+                .price(rung.price)
+                .time_in_force(TimeInForce::Gtc)
+                .new_client_order_id(&rung.client_order_id);
+            BracketAction::PlaceOrder(ladder.closing(request))
+        }).collect();
+        let quantity = ladder.position.quantity;
+        actions.extend(ladder.replace_stop(quantity));
+        self.ladders.insert(ladder.position.id.clone(), ladder);
+        Ok(actions)
+    }
+
+    /// Cancels all working orders of a ladder (the position is left open).
+    pub fn cancel(&mut self, id: &str) -> Result<Vec<BracketAction>, String> {
+        let ladder = self.ladders.get_mut(id).ok_or_else(|| format!("Ladder {} not found.", id))?;
+        if ladder.state != LadderState::Active {
+            return Ok(vec![]);
+        }
+        ladder.state = LadderState::Cancelled;
+        let mut actions = ladder.cancel_open_rungs();
+        actions.extend(ladder.cancel_stop());
+        Ok(actions)
+    }
+
+    /// Returns a tracked ladder by ID.
+    pub fn get(&self, id: &str) -> Option<&TrackedLadder> {
+        self.ladders.get(id)
+    }
+
+    /// Processes an order update from the user data stream.
+    ///
+    /// # Returns
+    /// The actions required in response (replacing the stop, or cancelling the remaining tranches).
+    pub fn on_order_update(&mut self, update: &FuturesOrderUpdate) -> Vec<BracketAction> {
+        if update.order_status != OrderStatus::Filled {
+            return vec![];
+        }
+        let client_id = update.client_order_id.as_str();
+        let Some(ladder) = self.ladders.values_mut()
+            .find(|l| l.state == LadderState::Active && client_id.starts_with(&format!("{}-", l.position.id)))
+        else {
+            return vec![];
+        };
+
+        if ladder.stop_client_order_id.as_deref() == Some(client_id) {
+            info!("Ladder {} stopped out at {}", ladder.position.id, update.average_price);
+            ladder.stop_client_order_id = None;
+            ladder.state = LadderState::StoppedOut;
+            return ladder.cancel_open_rungs();
+        }
+
+        let Some(index) = ladder.rungs.iter().position(|r| !r.filled && r.client_order_id == client_id) else {
+            return vec![];
+        };
+        ladder.rungs[index].filled = true;
+        info!("Ladder {} tranche {} filled at {}", ladder.position.id, index + 1, update.average_price);
+
+        // A ladder covering less than the whole position keeps protecting the runner with the stop
+        let remaining = ladder.remaining_quantity();
+        if remaining <= ladder.position.step_size.max(1e-12) / 2.0 {
+            ladder.state = LadderState::Completed;
+            return ladder.cancel_stop();
+        }
+
+        ladder.stop_price = match ladder.stop_adjustment {
+            StopAdjustment::KeepOriginal => ladder.stop_price,
+            StopAdjustment::Breakeven => ladder.position.entry_price,
+            StopAdjustment::PreviousTarget if index == 0 => ladder.position.entry_price,
+            StopAdjustment::PreviousTarget => ladder.rungs[index - 1].price,
+        };
+        ladder.replace_stop(remaining)
+    }
+}
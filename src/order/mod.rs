@@ -7,9 +7,16 @@
 use serde::{Deserialize, Serialize};
 use crate::rest_api::*; // Import the RestClient for queries
 use serde_json::{json, Value};  // Import Value for deserialization from generic JSON
+use tracing::debug;
  // Import std::io for io::Error and io::ErrorKind (for custom error messages)
 use crate::websocket::WebSocketClient; // Import the WebSocketClient for order placement and cancellation
 
+pub mod bracket;
+pub mod trailing;
+pub mod ladder;
+pub mod iceberg;
+pub mod group;
+
 /// Enum representing the type of order.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -21,10 +28,13 @@ pub enum OrderType {
     TakeProfit,
     TakeProfitLimit,
     LimitMaker,
+    StopMarket, // Futures: market order triggered at `stopPrice`
+    TakeProfitMarket, // Futures: market order triggered at `stopPrice`
+    TrailingStopMarket, // Futures: market order trailing the price by `callbackRate`
 }
 
 /// Enum representing the side of the order (BUY or SELL).
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderSide {
     Buy,
@@ -38,12 +48,407 @@ pub enum TimeInForce {
     Gtc, // Good Till Cancel
     Ioc, // Immediate Or Cancel
     Fok, // Fill Or Kill
+    Gtx, // Good Till Crossing (post-only)
+}
+
+/// Enum representing the position side of an order.
+/// `Both` is used in one-way mode; `Long` and `Short` are used in hedge (dual position side) mode.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PositionSide {
+    #[default]
+    Both,
+    Long,
+    Short,
+    #[serde(untagged)]
+    Other(String), // A value this version does not know
+}
+
+impl PositionSide {
+    /// Parses a position side from a string such as "LONG", "short" or "both".
+    ///
+    /// # Returns
+    /// `Some(PositionSide)` if the string is recognized, `None` otherwise.
+    pub fn from_str_opt(value: &str) -> Option<Self> {
+        match value.to_uppercase().as_str() {
+            "BOTH" => Some(PositionSide::Both),
+            "LONG" => Some(PositionSide::Long),
+            "SHORT" => Some(PositionSide::Short),
+            _ => None,
+        }
+    }
+
+    /// Returns the side as Binance writes it, e.g. `LONG`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            PositionSide::Both => "BOTH",
+            PositionSide::Long => "LONG",
+            PositionSide::Short => "SHORT",
+            PositionSide::Other(other) => other,
+        }
+    }
+
+    /// Returns true for the `Long` and `Short` legs of hedge mode.
+    pub fn is_hedge_leg(&self) -> bool {
+        matches!(self, PositionSide::Long | PositionSide::Short)
+    }
+}
+
+/// Status of an order.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    PendingCancel,
+    Rejected,
+    Expired,
+    ExpiredInMatch, // Expired by self-trade prevention
+    #[serde(untagged)]
+    Other(String), // A value this version does not know
+}
+
+impl OrderStatus {
+    /// Returns the status as Binance writes it, e.g. `PARTIALLY_FILLED`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            OrderStatus::New => "NEW",
+            OrderStatus::PartiallyFilled => "PARTIALLY_FILLED",
+            OrderStatus::Filled => "FILLED",
+            OrderStatus::Canceled => "CANCELED",
+            OrderStatus::PendingCancel => "PENDING_CANCEL",
+            OrderStatus::Rejected => "REJECTED",
+            OrderStatus::Expired => "EXPIRED",
+            OrderStatus::ExpiredInMatch => "EXPIRED_IN_MATCH",
+            OrderStatus::Other(other) => other,
+        }
+    }
+
+    /// Returns true once the order can no longer fill.
+    pub fn is_final(&self) -> bool {
+        matches!(self, OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Rejected | OrderStatus::Expired | OrderStatus::ExpiredInMatch)
+    }
+}
+
+/// What happened to an order in a user data stream update.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ExecutionType {
+    New,
+    Canceled,
+    Calculated, // Liquidation or ADL execution
+    Expired,
+    Trade, // A fill
+    Amendment, // The order was modified
+    #[serde(untagged)]
+    Other(String), // A value this version does not know
+}
+
+impl ExecutionType {
+    /// Returns the execution type as Binance writes it, e.g. `TRADE`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ExecutionType::New => "NEW",
+            ExecutionType::Canceled => "CANCELED",
+            ExecutionType::Calculated => "CALCULATED",
+            ExecutionType::Expired => "EXPIRED",
+            ExecutionType::Trade => "TRADE",
+            ExecutionType::Amendment => "AMENDMENT",
+            ExecutionType::Other(other) => other,
+        }
+    }
+}
+
+/// Price a stop order is triggered by.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WorkingType {
+    MarkPrice,
+    #[default]
+    ContractPrice, // Last traded price
+    #[serde(untagged)]
+    Other(String), // A value this version does not know
+}
+
+impl WorkingType {
+    /// Returns the working type as Binance writes it, e.g. `MARK_PRICE`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            WorkingType::MarkPrice => "MARK_PRICE",
+            WorkingType::ContractPrice => "CONTRACT_PRICE",
+            WorkingType::Other(other) => other,
+        }
+    }
+}
+
+macro_rules! display_as_str {
+    ($($name:ty),*) => {
+        $(impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        })*
+    };
+}
+
+display_as_str!(PositionSide, OrderStatus, ExecutionType, WorkingType);
+
+/// Builder describing a new order to be placed with `WebSocketClient::place_order`.
+///
+/// # Example
+/// ```ignore
+/// let request = NewOrderRequest::new("BTCUSDT", OrderSide::Sell, OrderType::Market)
+///     .quantity(0.01)
+///     .reduce_only(true);
+/// ws_client.place_order(&request).await?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewOrderRequest {
+    pub symbol: String,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub quantity: Option<f64>, // Not sent when `close_position` is true
+    pub quote_quantity: Option<f64>, // Market orders only: size in the quote asset, converted to `quantity` at placement
+    pub step_size: Option<f64>, // Quantity increment used to round the converted `quote_quantity`
+    pub price: Option<f64>,
+    pub time_in_force: Option<TimeInForce>,
+    pub stop_price: Option<f64>, // Trigger price for STOP_MARKET / TAKE_PROFIT_MARKET orders
+    pub activation_price: Option<f64>, // Optional activation price for TRAILING_STOP_MARKET orders
+    pub callback_rate: Option<f64>, // Trailing percentage for TRAILING_STOP_MARKET orders (0.1 - 10)
+    pub position_side: Option<PositionSide>,
+    pub reduce_only: bool, // Only reduce an existing position, never open or flip one
+    pub close_position: bool, // Close the whole position when triggered (stop/take-profit market orders only)
+    pub new_client_order_id: Option<String>,
+}
+
+impl NewOrderRequest {
+    /// Creates a new order request with the required fields.
+    pub fn new(symbol: &str, side: OrderSide, order_type: OrderType) -> Self {
+        Self {
+            symbol: symbol.to_uppercase(),
+            side,
+            order_type,
+            quantity: None,
+            quote_quantity: None,
+            step_size: None,
+            price: None,
+            time_in_force: None,
+            stop_price: None,
+            activation_price: None,
+            callback_rate: None,
+            position_side: None,
+            reduce_only: false,
+            close_position: false,
+            new_client_order_id: None,
+        }
+    }
+
+    /// Sets the amount of the base asset to buy/sell.
+    pub fn quantity(mut self, quantity: f64) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    /// Sizes a market order by quote amount (e.g., 500.0 to buy $500 of BTCUSDT).
+    /// The amount is converted to a base quantity at the current price when the order is placed.
+    pub fn quote_quantity(mut self, quote_quantity: f64) -> Self {
+        self.quote_quantity = Some(quote_quantity);
+        self
+    }
+
+    /// Sets the quantity step size (from `SymbolFilters::market_step_size`) used to round `quote_quantity`.
+    pub fn step_size(mut self, step_size: f64) -> Self {
+        self.step_size = Some(step_size);
+        self
+    }
+
+    /// Sets the limit price.
+    pub fn price(mut self, price: f64) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    /// Sets the time in force.
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    /// Sets the trigger price for stop-market and take-profit-market orders.
+    pub fn stop_price(mut self, stop_price: f64) -> Self {
+        self.stop_price = Some(stop_price);
+        self
+    }
+
+    /// Sets the activation price for trailing stop orders.
+    pub fn activation_price(mut self, activation_price: f64) -> Self {
+        self.activation_price = Some(activation_price);
+        self
+    }
+
+    /// Sets the callback rate (in percent) for trailing stop orders.
+    pub fn callback_rate(mut self, callback_rate: f64) -> Self {
+        self.callback_rate = Some(callback_rate);
+        self
+    }
+
+    /// Sets the position side (hedge mode only).
+    pub fn position_side(mut self, position_side: PositionSide) -> Self {
+        self.position_side = Some(position_side);
+        self
+    }
+
+    /// Marks the order as reduce-only.
+    pub fn reduce_only(mut self, reduce_only: bool) -> Self {
+        self.reduce_only = reduce_only;
+        self
+    }
+
+    /// Marks the order as closing the entire position.
+    pub fn close_position(mut self, close_position: bool) -> Self {
+        self.close_position = close_position;
+        self
+    }
+
+    /// Sets a unique client order ID.
+    pub fn new_client_order_id(mut self, id: &str) -> Self {
+        self.new_client_order_id = Some(id.to_string());
+        self
+    }
+
+    /// Checks the combination of options against Binance's rules.
+    pub fn validate(&self) -> Result<(), String> {
+        let hedge_leg = matches!(self.position_side, Some(PositionSide::Long) | Some(PositionSide::Short));
+        if self.reduce_only && hedge_leg {
+            return Err("reduceOnly cannot be sent in hedge mode; the position side already determines closing orders.".to_string());
+        }
+        if self.quantity.is_some() && self.quote_quantity.is_some() {
+            return Err("quantity and quote_quantity cannot both be set.".to_string());
+        }
+        if self.quote_quantity.is_some() {
+            if self.order_type != OrderType::Market {
+                return Err("quote_quantity is only supported for Market orders.".to_string());
+            }
+            if self.step_size.is_none_or(|step| step <= 0.0) {
+                return Err("quote_quantity requires a positive step_size (see RestClient::get_symbol_filters).".to_string());
+            }
+        }
+        if self.close_position {
+            if self.reduce_only {
+                return Err("closePosition cannot be used together with reduceOnly.".to_string());
+            }
+            if self.quantity.is_some() || self.quote_quantity.is_some() {
+                return Err("closePosition cannot be used together with quantity.".to_string());
+            }
+        } else if self.quantity.is_none() && self.quote_quantity.is_none() {
+            return Err("Missing required quantity for order.".to_string());
+        }
+        match self.order_type {
+            OrderType::StopMarket | OrderType::TakeProfitMarket if self.stop_price.is_none() => {
+                return Err(format!("Missing required stopPrice for {:?} order.", self.order_type));
+            },
+            OrderType::TrailingStopMarket => match self.callback_rate {
+                Some(rate) if (0.1..=10.0).contains(&rate) => {},
+                Some(rate) => return Err(format!("callbackRate must be between 0.1 and 10, got {}.", rate)),
+                None => return Err("Missing required callbackRate for TrailingStopMarket order.".to_string()),
+            },
+            _ => {},
+        }
+        if self.close_position && !matches!(self.order_type, OrderType::StopMarket | OrderType::TakeProfitMarket) {
+            return Err("closePosition is only supported for StopMarket and TakeProfitMarket orders.".to_string());
+        }
+        Ok(())
+    }
+
+    /// Builds the `order.place` parameters for this request.
+    pub fn to_params(&self) -> Value {
+        let mut params = json!({
+            "symbol": self.symbol,
+            "side": serde_json::to_string(&self.side).unwrap().trim_matches('"'),
+            "type": serde_json::to_string(&self.order_type).unwrap().trim_matches('"'),
+        });
+
+        if let Some(qty) = self.quantity {
+            params["quantity"] = json!(qty.to_string()); // Quantity as string
+        }
+        if let Some(p) = self.price {
+            params["price"] = json!(p.to_string()); // Price as string
+        }
+        if let Some(tif) = self.time_in_force {
+            params["timeInForce"] = json!(serde_json::to_string(&tif).unwrap().trim_matches('"'));
+        }
+        if let Some(sp) = self.stop_price {
+            params["stopPrice"] = json!(sp.to_string());
+        }
+        if let Some(ap) = self.activation_price {
+            params["activationPrice"] = json!(ap.to_string());
+        }
+        if let Some(cr) = self.callback_rate {
+            params["callbackRate"] = json!(cr.to_string());
+        }
+        if let Some(ps) = &self.position_side {
+            params["positionSide"] = json!(ps.as_str());
+        }
+        if self.reduce_only {
+            params["reduceOnly"] = json!("true");
+        }
+        if self.close_position {
+            params["closePosition"] = json!("true");
+        }
+        if let Some(ref id) = self.new_client_order_id {
+            params["newClientOrderId"] = json!(id);
+        }
+        params
+    }
+}
+
+/// Converts a quote amount into a base quantity at `price`, rounded down to `step_size`.
+///
+/// # Arguments
+/// * `quote_amount` - The amount of the quote asset to spend (e.g., 500.0 USDT).
+/// * `price` - The price used for the conversion.
+/// * `step_size` - The symbol's quantity increment (e.g., 0.001).
+///
+/// # Returns
+/// A `Result` containing the base quantity on success, or a `String` error if the inputs are invalid
+/// or the amount is smaller than a single step.
+pub fn quote_to_base_quantity(quote_amount: f64, price: f64, step_size: f64) -> Result<f64, String> {
+    if quote_amount <= 0.0 || price <= 0.0 || step_size <= 0.0 {
+        return Err(format!(
+            "Invalid quote conversion inputs: amount {}, price {}, step size {}",
+            quote_amount, price, step_size
+        ));
+    }
+    let quantity = round_down_to_step(quote_amount / price, step_size);
+    if quantity < step_size {
+        return Err(format!(
+            "Quote amount {} at price {} is smaller than the minimum step size {}",
+            quote_amount, price, step_size
+        ));
+    }
+    Ok(quantity)
+}
+
+/// Rounds `quantity` down to a multiple of `step_size` (e.g. the LOT_SIZE step).
+/// Returns `quantity` unchanged if `step_size` is not positive.
+pub fn round_down_to_step(quantity: f64, step_size: f64) -> f64 {
+    if step_size <= 0.0 {
+        return quantity;
+    }
+    // Small epsilon guards against values like 0.30000000000000004 / 0.1 flooring to 2
+    let steps = (quantity / step_size + 1e-9).floor();
+    // Round to the step's number of decimals to strip floating point noise
+    let decimals = (-step_size.log10()).ceil().max(0.0) as i32;
+    let factor = 10f64.powi(decimals);
+    (steps * step_size * factor).round() / factor
 }
 
 /// Represents the response received after placing a new order.
 /// This struct maps to the response from `order.place` WebSocket API call
 /// or `/fapi/v1/order` REST API call.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NewOrderResponse {
     pub symbol: String,
@@ -58,19 +463,19 @@ pub struct NewOrderResponse {
     pub cum_qty: String, // Added this field
     #[serde(rename = "cumQuote")] // Cumulative filled quote quantity
     pub cum_quote: String,
-    pub status: String, // e.g., "NEW", "FILLED", "PARTIALLY_FILLED"
+    pub status: OrderStatus,
     pub time_in_force: String,
     #[serde(rename = "type")]
     pub order_type: String,
     pub side: String,
     pub stop_price: String,
     pub reduce_only: bool,
-    pub position_side: String,
+    pub position_side: PositionSide,
     pub close_position: bool,
     pub update_time: u64, // Changed from 'time' to 'update_time' to match actual response
     pub avg_price: String,
     pub orig_type: String,
-    pub working_type: String,
+    pub working_type: WorkingType,
     pub price_protect: bool,
     pub price_match: String,
     pub self_trade_prevention_mode: String,
@@ -100,8 +505,8 @@ pub struct CancelOrderResponse {
     pub price: String,
     pub reduce_only: bool,
     pub side: String,
-    pub position_side: String,
-    pub status: String,
+    pub position_side: PositionSide,
+    pub status: OrderStatus,
     pub stop_price: String,
     pub close_position: bool,
     pub time_in_force: String,
@@ -110,7 +515,7 @@ pub struct CancelOrderResponse {
     pub activate_price: Option<String>, // Optional for TRAILING_STOP_MARKET
     pub price_rate: Option<String>, // Optional for TRAILING_STOP_MARKET
     pub update_time: u64,
-    pub working_type: String,
+    pub working_type: WorkingType,
     pub price_protect: bool,
     pub price_match: String,
     pub self_trade_prevention_mode: String,
@@ -131,7 +536,7 @@ pub struct Order {
     pub executed_qty: String,
     #[serde(rename = "cumQuote")] // Corrected field name based on schema
     pub cum_quote: String,
-    pub status: String,
+    pub status: OrderStatus,
     pub time_in_force: String,
     #[serde(rename = "type")]
     pub order_type: String,
@@ -143,12 +548,12 @@ pub struct Order {
     pub close_position: bool, // New field from schema
     pub good_till_date: u64, // New field from schema
     pub orig_type: String, // New field from schema
-    pub position_side: String, // New field from schema
+    pub position_side: PositionSide, // New field from schema
     pub price_match: String, // New field from schema
     pub price_protect: bool, // New field from schema
     pub reduce_only: bool, // New field from schema
     pub self_trade_prevention_mode: String, // New field from schema
-    pub working_type: String, // New field from schema
+    pub working_type: WorkingType, // New field from schema
 
     // Fields that are optional/conditionally present in the /fapi/v1/allOrders response
     pub iceberg_qty: Option<String>, // Made optional
@@ -176,19 +581,19 @@ pub struct ModifyOrderResponse {
     pub cum_qty: String,
     #[serde(rename = "cumQuote")]
     pub cum_quote: String,
-    pub status: String,
+    pub status: OrderStatus,
     pub time_in_force: String,
     #[serde(rename = "type")]
     pub order_type: String,
     pub side: String,
     pub stop_price: String,
     pub reduce_only: bool,
-    pub position_side: String,
+    pub position_side: PositionSide,
     pub close_position: bool,
     pub update_time: u64,
     pub avg_price: String,
     pub orig_type: String,
-    pub working_type: String,
+    pub working_type: WorkingType,
     pub price_protect: bool,
     pub price_match: String,
     pub self_trade_prevention_mode: String,
@@ -224,7 +629,6 @@ impl RestClient { // Order querying and historical data via REST API
         let symbol_uppercase = symbol.to_uppercase(); // Store the owned String
         let mut params = vec![
             ("symbol", symbol_uppercase.as_str()), // Use as_str() on the owned String
-            ("recvWindow", "5000"),
         ];
 
         let order_id_str = order_id.map(|id| id.to_string()); // Store the owned String
@@ -255,7 +659,7 @@ impl RestClient { // Order querying and historical data via REST API
     /// if the request fails or JSON deserialization fails.
     pub async fn get_open_orders(&self, symbol: Option<&str>) -> Result<Vec<Order>, String> {
         let endpoint = "/fapi/v1/openOrders"; // Correct endpoint for Futures open orders
-        let mut params = vec![("recvWindow", "5000")];
+        let mut params = vec![];
 
         let symbol_uppercase_opt = symbol.map(|s| s.to_uppercase()); // Store the owned String
         if let Some(ref s_uppercase) = symbol_uppercase_opt { // Use ref to borrow the String
@@ -290,7 +694,6 @@ impl RestClient { // Order querying and historical data via REST API
         let symbol_uppercase = symbol.to_uppercase(); // Store the owned String
         let mut params = vec![
             ("symbol", symbol_uppercase.as_str()), // Use as_str() on the owned String
-            ("recvWindow", "5000"),
         ];
 
         let order_id_str = order_id.map(|id| id.to_string()); // Store the owned String
@@ -318,7 +721,9 @@ impl RestClient { // Order querying and historical data via REST API
 impl WebSocketClient { // Order placement and cancellation via WebSocket API
     /// Places a new order on Binance Futures using WebSocket API.
     ///
-    /// This method calls the `order.place` WebSocket API method.
+    /// This is a convenience wrapper around `place_order` for the common parameters.
+    /// Use `NewOrderRequest` with `place_order` for reduce-only, close-position, the position side
+    /// of accounts in hedge mode and other options.
     ///
     /// # Arguments
     /// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
@@ -342,72 +747,99 @@ impl WebSocketClient { // Order placement and cancellation via WebSocket API
         time_in_force: Option<TimeInForce>,
         new_client_order_id: Option<&str>,
     ) -> Result<NewOrderResponse, String> {
+        let mut request = NewOrderRequest::new(symbol, side, order_type).quantity(quantity);
+        if let Some(p) = price {
+            request = request.price(p);
+        }
+        if let Some(tif) = time_in_force {
+            request = request.time_in_force(tif);
+        }
+        if let Some(id) = new_client_order_id {
+            request = request.new_client_order_id(id);
+        }
+        self.place_order(&request).await
+    }
 
-        // --- 1. Balance Check ---
-        let quote_asset = if symbol.ends_with("USDT") {
-            "USDT"
-        } else if symbol.ends_with("BUSD") {
-            "BUSD"
+    /// Places a new order described by a `NewOrderRequest` on Binance Futures using WebSocket API.
+    ///
+    /// This method calls the `order.place` WebSocket API method. Orders that open or increase
+    /// a position are checked against the available quote balance first; reduce-only and
+    /// close-position orders skip the balance check since they only release margin.
+    ///
+    /// # Arguments
+    /// * `request` - The order to place, built with `NewOrderRequest`.
+    ///
+    /// # Returns
+    /// A `Result` containing `NewOrderResponse` on success, or a `String` error
+    /// if validation, the balance check, the request, or JSON deserialization fails.
+    pub async fn place_order(&self, request: &NewOrderRequest) -> Result<NewOrderResponse, String> {
+        request.validate()?;
+
+        // --- 0. Quote quantity conversion ---
+        let resolved_request;
+        let request = if let (Some(quote_amount), Some(step_size)) = (request.quote_quantity, request.step_size) {
+            let current_price = self.get_current_price(&request.symbol).await
+                .map_err(|e| format!("Failed to get current price for {}: {}", request.symbol, e))?
+                .price.parse::<f64>()
+                .map_err(|e| format!("Failed to parse current price: {}", e))?;
+            let mut converted = request.clone();
+            converted.quantity = Some(quote_to_base_quantity(quote_amount, current_price, step_size)?);
+            converted.quote_quantity = None;
+            resolved_request = converted;
+            &resolved_request
         } else {
-            // Add other quote assets as needed or handle unknown
-            return Err(format!("Unsupported quote asset for symbol: {}", symbol));
+            request
         };
+        let symbol = request.symbol.as_str();
 
-        // Call the new helper function in account_info to get available balance
-        let available_balance_quote = match self.get_asset_balance(quote_asset).await? {
-            Some(asset_balance) => asset_balance.available_balance.parse::<f64>()
-                .map_err(|e| format!("Failed to parse available balance: {}", e))?,
-            None => return Err(format!("Asset {} not found in account balance", quote_asset)),
-        };
+        // --- 1. Balance Check ---
+        if let (Some(quantity), false) = (request.quantity, request.reduce_only || request.close_position) {
+            let quote_asset = if symbol.ends_with("USDT") {
+                "USDT"
+            } else if symbol.ends_with("BUSD") {
+                "BUSD"
+            } else {
+                // Add other quote assets as needed or handle unknown
+                return Err(format!("Unsupported quote asset for symbol: {}", symbol));
+            };
 
-        let order_price = if let Some(price)  = price {
-            price
-        }else{
-            // For market orders, we need to fetch the current price
-            match self.get_current_price(symbol).await {
-                Ok(ticker_price) => ticker_price.price.parse::<f64>()
-                    .map_err(|e| format!("Failed to parse current price: {}", e))?,
-                Err(e) => return Err(format!("Failed to get current price for {}: {}", symbol, e)),
-            }
-        };
+            // Call the new helper function in account_info to get available balance
+            let available_balance_quote = match self.get_asset_balance(quote_asset).await? {
+                Some(asset_balance) => asset_balance.available_balance.parse::<f64>()
+                    .map_err(|e| format!("Failed to parse available balance: {}", e))?,
+                None => return Err(format!("Asset {} not found in account balance", quote_asset)),
+            };
 
+            let order_price = if let Some(price) = request.price {
+                price
+            } else {
+                // For market orders, we need to fetch the current price
+                match self.get_current_price(symbol).await {
+                    Ok(ticker_price) => ticker_price.price.parse::<f64>()
+                        .map_err(|e| format!("Failed to parse current price: {}", e))?,
+                    Err(e) => return Err(format!("Failed to get current price for {}: {}", symbol, e)),
+                }
+            };
 
-        let estimated_cost = quantity * order_price;
-        // Assuming a fixed commission rate for simplicity. In a real bot, fetch from exchange info.
-        const COMMISSION_RATE: f64 = 0.0004; // 0.04%
-        let total_cost_with_commission = estimated_cost * (1.0 + COMMISSION_RATE);
+            let estimated_cost = quantity * order_price;
+            // Assuming a fixed commission rate for simplicity. In a real bot, fetch from exchange info.
+            const COMMISSION_RATE: f64 = 0.0004; // 0.04%
+            let total_cost_with_commission = estimated_cost * (1.0 + COMMISSION_RATE);
 
-        // Debug prints for balance check
-        println!("[DEBUG] Symbol: {} | Side: {:?} | Order Type: {:?}", symbol, side, order_type);
-        println!("[DEBUG] Available balance for {}: {:.8}", quote_asset, available_balance_quote);
-        println!("[DEBUG] Order quantity: {:.8} | Order price: {:.8}", quantity, order_price);
-        println!("[DEBUG] Estimated cost: {:.8} | Total with commission: {:.8}", estimated_cost, total_cost_with_commission);
+            debug!("Balance check for {} {:?} {:?}: available {:.8} {}, quantity {:.8} at {:.8}, cost {:.8} ({:.8} with commission)",
+                   symbol, request.side, request.order_type, available_balance_quote, quote_asset, quantity, order_price, estimated_cost, total_cost_with_commission);
 
-        if available_balance_quote < total_cost_with_commission {
-            println!("[DEBUG] Insufficient funds: required {:.8}, available {:.8}", total_cost_with_commission, available_balance_quote);
-            return Err(format!(
-                "Insufficient funds for order. Required: {:.4} {} (including commission). Available: {:.4} {}",
-                total_cost_with_commission, quote_asset, available_balance_quote, quote_asset
-            ));
+            if available_balance_quote < total_cost_with_commission {
+                debug!("Insufficient funds: required {:.8}, available {:.8}", total_cost_with_commission, available_balance_quote);
+                return Err(format!(
+                    "Insufficient funds for order. Required: {:.4} {} (including commission). Available: {:.4} {}",
+                    total_cost_with_commission, quote_asset, available_balance_quote, quote_asset
+                ));
+            }
         }
 
         let method = "order.place";
-        let mut params = json!({
-            "symbol": symbol.to_uppercase(),
-            "side": serde_json::to_string(&side).unwrap().trim_matches('"'),
-            "type": serde_json::to_string(&order_type).unwrap().trim_matches('"'),
-            "quantity": quantity.to_string(), // Quantity as string
-        });
-
-        if let Some(p) = price {
-            params["price"] = json!(p.to_string()); // Price as string
-        }
-        if let Some(tif) = time_in_force {
-            params["timeInForce"] = json!(serde_json::to_string(&tif).unwrap().trim_matches('"'));
-        }
-        if let Some(id) = new_client_order_id {
-            params["newClientOrderId"] = json!(id);
-        }
+        let params = request.to_params();
 
         let response_value: Value = self.request_websocket_api(method, params).await?;
 
@@ -0,0 +1,436 @@
+// src/order/trailing.rs
+
+//! This module implements client-side trailing stops.
+//! For each open position it tracks the high (long) or low (short) water mark from the price feed
+//! (mark price, trades, book ticker) and moves a resting reduce-only STOP_MARKET order behind it.
+//! The trail distance is either a percentage of the water mark or a multiple of the ATR, which is
+//! computed from closed klines of the same feed. This allows finer trails than Binance's native
+//! TRAILING_STOP_MARKET orders (0.1% callback granularity, no ATR-based trails).
+//!
+//! As with bracket orders, the bookkeeping lives in the synchronous `TrailingStopManager`, and
+//! `spawn_trailing_service` executes the resulting actions through the `WebSocketClient`. Given a
+//! `StateStore`, the service resumes the trailing stops of the previous run and saves the active
+//! ones whenever a stop starts, moves or ends (see `recovery`); water marks are saved with them.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tracing::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use super::bracket::order_update_from_message;
+use super::{NewOrderRequest, OrderSide, OrderStatus, OrderType, PositionSide};
+use crate::market_event::MarketEvent;
+use crate::recovery::StateStore;
+use crate::streams::FuturesOrderUpdate;
+pub use crate::indicators::Atr; // Re-exported for existing users of `trailing::Atr`
+use crate::websocket::WebSocketClient;
+use crate::websocket_stream::BinanceWsMessage;
+
+/// Default ATR period (Wilder).
+pub const DEFAULT_ATR_PERIOD: usize = 14;
+
+/// Distance between the water mark and the stop.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrailDistance {
+    Percent(f64), // e.g. 0.5 = 0.5% of the water mark
+    AtrMultiple(f64), // e.g. 2.0 = 2 x ATR
+}
+
+/// Describes a trailing stop for an open position.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrailingStopConfig {
+    pub id: String, // Unique ID, used as the prefix of the stop orders' client order IDs
+    pub symbol: String,
+    pub position: OrderSide, // `Buy` for a long position, `Sell` for a short one
+    pub quantity: f64,
+    pub distance: TrailDistance,
+    pub min_step: f64, // Minimum stop improvement (in price) before the order is moved, e.g. the tick size
+    pub initial_stop: Option<f64>, // Optional stop placed immediately, before any price is seen
+    pub position_side: Option<PositionSide>, // `LONG`/`SHORT` in hedge mode
+}
+
+impl TrailingStopConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() || self.id.len() > 28 {
+            return Err("Trailing stop ID must be 1-28 characters long.".to_string());
+        }
+        if self.quantity <= 0.0 {
+            return Err("Trailing stop quantity must be positive.".to_string());
+        }
+        match self.distance {
+            TrailDistance::Percent(p) if p <= 0.0 || p >= 100.0 => Err(format!("Invalid trail percent {}", p)),
+            TrailDistance::AtrMultiple(m) if m <= 0.0 => Err(format!("Invalid ATR multiple {}", m)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Lifecycle state of a trailing stop.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum TrailingState {
+    Active,
+    Triggered, // The stop order filled
+    Stopped, // Removed by the user
+}
+
+/// An action the trailing service must execute on the exchange.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrailingAction {
+    PlaceOrder(NewOrderRequest),
+    CancelOrder {
+        symbol: String,
+        client_order_id: String,
+    },
+}
+
+/// A trailing stop tracked by the manager.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrailingStop {
+    pub config: TrailingStopConfig,
+    pub state: TrailingState,
+    pub water_mark: Option<f64>, // Highest (long) / lowest (short) price seen
+    pub stop_price: Option<f64>, // Trigger price of the resting stop order
+    stop_client_order_id: Option<String>,
+    sequence: u32, // Incremented for every replacement order
+}
+
+impl TrailingStop {
+    /// Returns the client order ID of the resting stop order, if any.
+    pub fn stop_client_order_id(&self) -> Option<&str> {
+        self.stop_client_order_id.as_deref()
+    }
+
+    fn is_long(&self) -> bool {
+        self.config.position == OrderSide::Buy
+    }
+
+    /// Returns the stop level implied by `water_mark`, or `None` if the ATR is not available yet.
+    fn target_stop(&self, water_mark: f64, atr: Option<f64>) -> Option<f64> {
+        let distance = match self.config.distance {
+            TrailDistance::Percent(p) => water_mark * p / 100.0,
+            TrailDistance::AtrMultiple(m) => atr? * m,
+        };
+        Some(if self.is_long() { water_mark - distance } else { water_mark + distance })
+    }
+
+    /// Returns actions replacing the resting stop with one at `stop_price`.
+    fn move_stop(&mut self, stop_price: f64) -> Vec<TrailingAction> {
+        let mut actions = Vec::new();
+        if let Some(previous) = self.stop_client_order_id.take() {
+            actions.push(TrailingAction::CancelOrder { symbol: self.config.symbol.clone(), client_order_id: previous });
+        }
+        self.sequence += 1;
+        let client_order_id = format!("{}-ts{}", self.config.id, self.sequence);
+        let exit_side = if self.is_long() { OrderSide::Sell } else { OrderSide::Buy };
+        let request = NewOrderRequest::new(&self.config.symbol, exit_side, OrderType::StopMarket)
+            .quantity(self.config.quantity)
+            .stop_price(stop_price)
+            .new_client_order_id(&client_order_id);
+        // In hedge mode the position side makes the order closing; reduceOnly is rejected there
+        let request = match &self.config.position_side {
+            Some(ps @ (PositionSide::Long | PositionSide::Short)) => request.position_side(ps.clone()),
+            _ => request.reduce_only(true),
+        };
+        actions.push(TrailingAction::PlaceOrder(request));
+        self.stop_price = Some(stop_price);
+        self.stop_client_order_id = Some(client_order_id);
+        actions
+    }
+}
+
+/// Synchronous bookkeeping for client-side trailing stops.
+#[derive(Debug)]
+pub struct TrailingStopManager {
+    stops: HashMap<String, TrailingStop>,
+    atr: HashMap<String, Atr>, // Per symbol
+    atr_period: usize,
+}
+
+impl Default for TrailingStopManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_ATR_PERIOD)
+    }
+}
+
+impl TrailingStopManager {
+    /// Creates an empty manager computing the ATR over `atr_period` closed klines.
+    pub fn new(atr_period: usize) -> Self {
+        Self { stops: HashMap::new(), atr: HashMap::new(), atr_period }
+    }
+
+    /// Creates a manager trailing the stops of a previous run (see `recovery`), with their water
+    /// marks and resting stop orders; inactive ones are dropped. ATR trails wait for `atr_period`
+    /// closed klines again, since the ATR is not persisted.
+    pub fn restore(atr_period: usize, stops: impl IntoIterator<Item = TrailingStop>) -> Self {
+        let mut manager = Self::new(atr_period);
+        manager.stops = stops.into_iter()
+            .filter(|stop| stop.state == TrailingState::Active)
+            .map(|stop| (stop.config.id.clone(), stop))
+            .collect();
+        manager
+    }
+
+    /// Returns the active trailing stops, by ID, to be persisted.
+    pub fn snapshot(&self) -> Vec<TrailingStop> {
+        let mut active: Vec<TrailingStop> = self.stops.values().filter(|stop| stop.state == TrailingState::Active).cloned().collect();
+        active.sort_by(|a, b| a.config.id.cmp(&b.config.id));
+        active
+    }
+
+    /// Starts trailing a position. Returns the initial stop order, if `initial_stop` is set.
+    pub fn start(&mut self, config: TrailingStopConfig) -> Result<Vec<TrailingAction>, String> {
+        config.validate()?;
+        if self.stops.get(&config.id).is_some_and(|s| s.state == TrailingState::Active) {
+            return Err(format!("Trailing stop {} already active.", config.id));
+        }
+        let initial_stop = config.initial_stop;
+        let mut stop = TrailingStop {
+            config,
+            state: TrailingState::Active,
+            water_mark: None,
+            stop_price: None,
+            stop_client_order_id: None,
+            sequence: 0,
+        };
+        let actions = initial_stop.map(|price| stop.move_stop(price)).unwrap_or_default();
+        self.stops.insert(stop.config.id.clone(), stop);
+        Ok(actions)
+    }
+
+    /// Stops trailing and cancels the resting stop order (the position is left open).
+    pub fn stop(&mut self, id: &str) -> Result<Vec<TrailingAction>, String> {
+        let stop = self.stops.get_mut(id).ok_or_else(|| format!("Trailing stop {} not found.", id))?;
+        if stop.state != TrailingState::Active {
+            return Ok(vec![]);
+        }
+        stop.state = TrailingState::Stopped;
+        Ok(stop.stop_client_order_id.take().map(|client_order_id| TrailingAction::CancelOrder {
+            symbol: stop.config.symbol.clone(),
+            client_order_id,
+        }).into_iter().collect())
+    }
+
+    /// Returns a tracked trailing stop by ID.
+    pub fn get(&self, id: &str) -> Option<&TrailingStop> {
+        self.stops.get(id)
+    }
+
+    /// Returns the current ATR of `symbol`, if enough klines have been seen.
+    pub fn atr(&self, symbol: &str) -> Option<f64> {
+        self.atr.get(&symbol.to_uppercase()).and_then(|atr| atr.value())
+    }
+
+    /// Feeds a closed bar of `symbol` into its ATR.
+    pub fn on_closed_bar(&mut self, symbol: &str, high: f64, low: f64, close: f64) {
+        let period = self.atr_period;
+        self.atr.entry(symbol.to_uppercase()).or_insert_with(|| Atr::new(period)).update(high, low, close);
+    }
+
+    /// Processes a new price for `symbol` and returns the actions moving any stop that should trail it.
+    pub fn on_price(&mut self, symbol: &str, price: f64) -> Vec<TrailingAction> {
+        let atr = self.atr(symbol);
+        let mut actions = Vec::new();
+        for stop in self.stops.values_mut() {
+            if stop.state != TrailingState::Active || !stop.config.symbol.eq_ignore_ascii_case(symbol) {
+                continue;
+            }
+            let water_mark = match stop.water_mark {
+                Some(wm) if stop.is_long() => wm.max(price),
+                Some(wm) => wm.min(price),
+                None => price,
+            };
+            stop.water_mark = Some(water_mark);
+
+            let Some(target) = stop.target_stop(water_mark, atr) else {
+                continue;
+            };
+            // Stops only ever move in the position's favour, and by at least `min_step`
+            let improves = match stop.stop_price {
+                Some(current) if stop.is_long() => target - current >= stop.config.min_step.max(f64::EPSILON),
+                Some(current) => current - target >= stop.config.min_step.max(f64::EPSILON),
+                None => true,
+            };
+            if improves {
+                actions.extend(stop.move_stop(target));
+            }
+        }
+        actions
+    }
+
+    /// Processes a normalized market event: mark price, trades and book ticker mids move the stops,
+    /// closed klines update the ATR.
+    pub fn on_market_event(&mut self, event: &MarketEvent) -> Vec<TrailingAction> {
+        let symbol = event.symbol();
+        let price = match event {
+            MarketEvent::MarkPrice(mp) => mp.mark_price,
+            MarketEvent::Trade(t) | MarketEvent::AggTrade(t) => t.price,
+            MarketEvent::BookTicker(b) => (b.bid_price + b.ask_price) / 2.0,
+            MarketEvent::Kline(candle) => {
+                if candle.is_closed {
+                    self.on_closed_bar(symbol.as_str(), candle.high, candle.low, candle.close);
+                }
+                return vec![];
+            },
+            MarketEvent::Depth(_) | MarketEvent::Liquidation(_) | MarketEvent::PriceKline(_) => return vec![],
+        };
+        self.on_price(symbol.as_str(), price)
+    }
+
+    /// Processes an order update from the user data stream; a filled stop ends its trailing stop.
+    /// Returns true when it did.
+    pub fn on_order_update(&mut self, update: &FuturesOrderUpdate) -> bool {
+        if update.order_status != OrderStatus::Filled {
+            return false;
+        }
+        let Some(stop) = self.stops.values_mut()
+            .find(|s| s.stop_client_order_id.as_deref() == Some(update.client_order_id.as_str()))
+        else {
+            return false;
+        };
+        info!("Trailing stop {} triggered at {}", stop.config.id, update.average_price);
+        stop.state = TrailingState::Triggered;
+        stop.stop_client_order_id = None;
+        true
+    }
+}
+
+/// Commands accepted by the trailing service task.
+enum TrailingCommand {
+    Start {
+        config: TrailingStopConfig,
+        response_tx: oneshot::Sender<Result<(), String>>,
+    },
+    Stop {
+        id: String,
+        response_tx: oneshot::Sender<Result<(), String>>,
+    },
+}
+
+/// Handle to a running trailing stop service.
+pub struct TrailingService {
+    command_sender: mpsc::Sender<TrailingCommand>,
+    _service_handle: JoinHandle<()>,
+}
+
+impl TrailingService {
+    /// Starts trailing a position.
+    pub async fn start(&self, config: TrailingStopConfig) -> Result<(), String> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.command_sender.send(TrailingCommand::Start { config, response_tx }).await
+            .map_err(|e| format!("Failed to send trailing command: {}", e))?;
+        response_rx.await.map_err(|e| format!("Failed to receive trailing response: {}", e))?
+    }
+
+    /// Stops trailing a position and cancels its stop order.
+    pub async fn stop(&self, id: &str) -> Result<(), String> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.command_sender.send(TrailingCommand::Stop { id: id.to_string(), response_tx }).await
+            .map_err(|e| format!("Failed to send trailing command: {}", e))?;
+        response_rx.await.map_err(|e| format!("Failed to receive trailing response: {}", e))?
+    }
+}
+
+/// Executes trailing actions through the WebSocket API, stopping at the first failure.
+async fn execute_actions(ws_client: &WebSocketClient, actions: Vec<TrailingAction>) -> Result<(), String> {
+    for action in actions {
+        match action {
+            TrailingAction::PlaceOrder(request) => {
+                let response = ws_client.place_order(&request).await?;
+                info!("Trailing stop order {} placed at {:?}", response.client_order_id, request.stop_price);
+            },
+            TrailingAction::CancelOrder { symbol, client_order_id } => {
+                // The order may already be gone (e.g. triggered); keep going so the replacement is placed
+                if let Err(e) = ws_client.cancel_order(&symbol, None, Some(&client_order_id)).await {
+                    warn!("Failed to cancel trailing stop order {}: {}", client_order_id, e);
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Spawns the trailing stop service.
+///
+/// # Arguments
+/// * `ws_client` - Used to place and cancel the stop orders.
+/// * `market_events` - Normalized market data (subscribe to `<symbol>@markPrice`, and to
+///   `<symbol>@kline_<interval>` when trailing by ATR).
+/// * `user_stream_receiver` - Messages from the USDⓈ-M user data stream, used to detect triggered stops.
+/// * `store` - Where the active trailing stops are persisted across restarts; `None` keeps them in memory.
+///
+/// # Returns
+/// A `TrailingService` handle used to start and stop trailing stops.
+pub fn spawn_trailing_service(
+    ws_client: Arc<WebSocketClient>,
+    atr_period: usize,
+    mut market_events: mpsc::Receiver<MarketEvent>,
+    mut user_stream_receiver: mpsc::Receiver<BinanceWsMessage>,
+    store: Option<Arc<StateStore>>,
+) -> TrailingService {
+    let (command_sender, mut command_receiver) = mpsc::channel::<TrailingCommand>(100);
+
+    let service_handle = tokio::spawn(async move {
+        let mut manager = match &store {
+            Some(store) => TrailingStopManager::restore(atr_period, store.state().trailing_stops),
+            None => TrailingStopManager::new(atr_period),
+        };
+        // Saved when a stop starts, moves or ends rather than on every price
+        let save = |manager: &TrailingStopManager| if let Some(store) = &store {
+            store.update(|state| state.trailing_stops = manager.snapshot()).unwrap_or_else(|e| error!("{}", e));
+        };
+        loop {
+            tokio::select! {
+                command = command_receiver.recv() => {
+                    let Some(command) = command else {
+                        info!("Trailing command channel closed. Stopping trailing service.");
+                        return;
+                    };
+                    let (result, response_tx) = match command {
+                        TrailingCommand::Start { config, response_tx } => (manager.start(config), response_tx),
+                        TrailingCommand::Stop { id, response_tx } => (manager.stop(&id), response_tx),
+                    };
+                    let result = match result {
+                        Ok(actions) => execute_actions(&ws_client, actions).await,
+                        Err(e) => Err(e),
+                    };
+                    save(&manager);
+                    let _ = response_tx.send(result);
+                },
+                event = market_events.recv() => {
+                    let Some(event) = event else {
+                        warn!("Market event channel closed. Stopping trailing service.");
+                        return;
+                    };
+                    let actions = manager.on_market_event(&event);
+                    if !actions.is_empty() {
+                        save(&manager);
+                    }
+                    if let Err(e) = execute_actions(&ws_client, actions).await {
+                        error!("Failed to move trailing stop for {}: {}", event.symbol(), e);
+                    }
+                },
+                message = user_stream_receiver.recv() => {
+                    let Some(message) = message else {
+                        warn!("User data stream channel closed. Stopping trailing service.");
+                        return;
+                    };
+                    if order_update_from_message(&message).is_some_and(|update| manager.on_order_update(&update)) {
+                        save(&manager);
+                    }
+                }
+            }
+        }
+    });
+
+    TrailingService {
+        command_sender,
+        _service_handle: service_handle,
+    }
+}
@@ -0,0 +1,411 @@
+// src/order_book/mod.rs
+
+//! This module maintains a local copy of a symbol's order book from a REST depth snapshot
+//! and the diff depth stream (`<symbol>@depth`), and derives real-time microstructure
+//! features from it: bid/ask imbalance over N levels, microprice, and book slope.
+//! Computed features are published on a channel so strategies can consume them as a feed.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::market_data::OrderBookSnapshot;
+use crate::market_event::{DepthUpdate, Level, MarketEvent, Symbol};
+use crate::rest_api::RestClient;
+use crate::streams::{DepthLevel, DepthStream};
+use crate::websocket_stream::BinanceWsMessage;
+
+/// A price used as an order book key, ordered by `f64::total_cmp`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PriceKey(f64);
+
+impl Eq for PriceKey {}
+
+impl PartialOrd for PriceKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PriceKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Parses a `[price, quantity]` depth level into floats.
+fn parse_level(level: &DepthLevel) -> Result<(f64, f64), String> {
+    let DepthLevel::Array(price, quantity) = level;
+    let price = price.parse::<f64>().map_err(|e| format!("Failed to parse depth price '{}': {}", price, e))?;
+    let quantity = quantity.parse::<f64>().map_err(|e| format!("Failed to parse depth quantity '{}': {}", quantity, e))?;
+    Ok((price, quantity))
+}
+
+/// Converts a JSON depth stream event into the normalized representation.
+fn depth_update_from_stream(event: &DepthStream) -> Result<DepthUpdate, String> {
+    let to_levels = |levels: &[DepthLevel]| -> Result<Vec<Level>, String> {
+        levels.iter().map(|l| parse_level(l).map(|(price, quantity)| Level { price, quantity })).collect()
+    };
+    Ok(DepthUpdate {
+        symbol: Symbol::new(&event.symbol)?,
+        event_time: event.event_time,
+        first_update_id: event.first_update_id,
+        final_update_id: event.final_update_id,
+        prev_final_update_id: event.prev_final_update_id,
+        bids: to_levels(&event.bids)?,
+        asks: to_levels(&event.asks)?,
+    })
+}
+
+/// A locally maintained order book for a single symbol.
+#[derive(Debug, Clone)]
+pub struct LocalOrderBook {
+    pub symbol: String,
+    pub last_update_id: u64,
+    bids: BTreeMap<PriceKey, f64>,
+    asks: BTreeMap<PriceKey, f64>,
+    synced: bool, // True once the first diff event bridging the snapshot has been applied
+}
+
+impl LocalOrderBook {
+    /// Creates an empty order book for `symbol`. It must be seeded with `apply_snapshot` before diffs apply.
+    pub fn new(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_uppercase(),
+            last_update_id: 0,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            synced: false,
+        }
+    }
+
+    /// Replaces the book's contents with a REST depth snapshot.
+    pub fn apply_snapshot(&mut self, snapshot: &OrderBookSnapshot) -> Result<(), String> {
+        self.bids.clear();
+        self.asks.clear();
+        for level in &snapshot.bids {
+            let (price, quantity) = parse_level(level)?;
+            if quantity > 0.0 {
+                self.bids.insert(PriceKey(price), quantity);
+            }
+        }
+        for level in &snapshot.asks {
+            let (price, quantity) = parse_level(level)?;
+            if quantity > 0.0 {
+                self.asks.insert(PriceKey(price), quantity);
+            }
+        }
+        self.last_update_id = snapshot.last_update_id;
+        self.synced = false;
+        Ok(())
+    }
+
+    /// Applies a diff depth event from the stream, see `apply_depth_update`.
+    pub fn apply_diff(&mut self, event: &DepthStream) -> Result<bool, String> {
+        self.apply_depth_update(&depth_update_from_stream(event)?)
+    }
+
+    /// Applies a normalized diff depth update following Binance's local order book rules.
+    ///
+    /// # Returns
+    /// `Ok(true)` if the event was applied, `Ok(false)` if it was older than the book and dropped,
+    /// or an `Err` if a gap in the update sequence was detected (the book must be re-synced from a snapshot).
+    pub fn apply_depth_update(&mut self, event: &DepthUpdate) -> Result<bool, String> {
+        if event.final_update_id < self.last_update_id {
+            return Ok(false); // Older than the snapshot/book, drop it
+        }
+        if !self.synced {
+            // The first event must straddle the snapshot's lastUpdateId
+            if event.first_update_id > self.last_update_id {
+                return Err(format!(
+                    "Order book gap for {}: first event U={} is after snapshot lastUpdateId={}",
+                    self.symbol, event.first_update_id, self.last_update_id
+                ));
+            }
+        } else if event.prev_final_update_id.is_some_and(|prev| prev != self.last_update_id) {
+            return Err(format!(
+                "Order book gap for {}: event pu={} does not match last update id {}",
+                self.symbol, event.prev_final_update_id.unwrap_or_default(), self.last_update_id
+            ));
+        }
+
+        for level in &event.bids {
+            if level.quantity == 0.0 {
+                self.bids.remove(&PriceKey(level.price));
+            } else {
+                self.bids.insert(PriceKey(level.price), level.quantity);
+            }
+        }
+        for level in &event.asks {
+            if level.quantity == 0.0 {
+                self.asks.remove(&PriceKey(level.price));
+            } else {
+                self.asks.insert(PriceKey(level.price), level.quantity);
+            }
+        }
+        self.last_update_id = event.final_update_id;
+        self.synced = true;
+        Ok(true)
+    }
+
+    /// Returns the best (highest) bid as `(price, quantity)`.
+    pub fn best_bid(&self) -> Option<(f64, f64)> {
+        self.bids.iter().next_back().map(|(p, q)| (p.0, *q))
+    }
+
+    /// Returns the best (lowest) ask as `(price, quantity)`.
+    pub fn best_ask(&self) -> Option<(f64, f64)> {
+        self.asks.iter().next().map(|(p, q)| (p.0, *q))
+    }
+
+    /// Returns the mid price between the best bid and ask.
+    pub fn mid_price(&self) -> Option<f64> {
+        Some((self.best_bid()?.0 + self.best_ask()?.0) / 2.0)
+    }
+
+    /// Returns up to `n` bid levels, best first.
+    pub fn top_bids(&self, n: usize) -> Vec<(f64, f64)> {
+        self.bids.iter().rev().take(n).map(|(p, q)| (p.0, *q)).collect()
+    }
+
+    /// Returns up to `n` ask levels, best first.
+    pub fn top_asks(&self, n: usize) -> Vec<(f64, f64)> {
+        self.asks.iter().take(n).map(|(p, q)| (p.0, *q)).collect()
+    }
+}
+
+/// Bid/ask volume imbalance over the top `levels` of each side, in the range [-1, 1].
+/// Positive values indicate more resting bid volume than ask volume.
+pub fn imbalance(book: &LocalOrderBook, levels: usize) -> Option<f64> {
+    let bid_volume: f64 = book.top_bids(levels).iter().map(|(_, q)| q).sum();
+    let ask_volume: f64 = book.top_asks(levels).iter().map(|(_, q)| q).sum();
+    let total = bid_volume + ask_volume;
+    if total > 0.0 { Some((bid_volume - ask_volume) / total) } else { None }
+}
+
+/// Size-weighted microprice of the top of book: the mid price skewed towards the side with less resting size.
+pub fn microprice(book: &LocalOrderBook) -> Option<f64> {
+    let (bid, bid_qty) = book.best_bid()?;
+    let (ask, ask_qty) = book.best_ask()?;
+    let total = bid_qty + ask_qty;
+    if total > 0.0 { Some((bid * ask_qty + ask * bid_qty) / total) } else { None }
+}
+
+/// Book slope of one side: the least-squares slope of cumulative quantity against distance
+/// from the mid price over the given levels. Steeper slopes indicate deeper liquidity near the touch.
+pub fn book_slope(levels: &[(f64, f64)], mid_price: f64) -> Option<f64> {
+    if levels.len() < 2 {
+        return None;
+    }
+    let mut cumulative = 0.0;
+    let points: Vec<(f64, f64)> = levels.iter().map(|(price, quantity)| {
+        cumulative += quantity;
+        ((price - mid_price).abs(), cumulative)
+    }).collect();
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance > 0.0 { Some(covariance / variance) } else { None }
+}
+
+/// A snapshot of order book features computed after a depth update.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderBookFeatures {
+    pub symbol: String,
+    pub event_time: u64,
+    pub best_bid: f64,
+    pub best_ask: f64,
+    pub spread: f64,
+    pub mid_price: f64,
+    pub microprice: f64,
+    pub imbalance: f64, // Imbalance over the configured number of levels
+    pub rolling_imbalance: f64, // Mean imbalance over the rolling window
+    pub bid_slope: Option<f64>,
+    pub ask_slope: Option<f64>,
+}
+
+/// Configuration for the order book feature engine.
+#[derive(Debug, Clone)]
+pub struct FeatureConfig {
+    pub levels: usize, // Number of levels per side used for imbalance and slope
+    pub rolling_window: usize, // Number of updates averaged for the rolling imbalance
+    pub snapshot_limit: u16, // Depth of the REST snapshot used to seed the book
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        Self {
+            levels: 10,
+            rolling_window: 50,
+            snapshot_limit: 1000,
+        }
+    }
+}
+
+/// Maintains a `LocalOrderBook` and computes rolling features from it on each update.
+pub struct OrderBookFeatureEngine {
+    pub book: LocalOrderBook,
+    config: FeatureConfig,
+    imbalance_window: VecDeque<f64>,
+}
+
+impl OrderBookFeatureEngine {
+    /// Creates a new engine for `symbol` with an empty book.
+    pub fn new(symbol: &str, config: FeatureConfig) -> Self {
+        Self {
+            book: LocalOrderBook::new(symbol),
+            imbalance_window: VecDeque::with_capacity(config.rolling_window),
+            config,
+        }
+    }
+
+    /// Seeds the book from a snapshot and clears the rolling window.
+    pub fn apply_snapshot(&mut self, snapshot: &OrderBookSnapshot) -> Result<(), String> {
+        self.imbalance_window.clear();
+        self.book.apply_snapshot(snapshot)
+    }
+
+    /// Applies a depth event and returns the resulting features.
+    ///
+    /// # Returns
+    /// `Ok(Some(features))` if the event was applied and the book has both sides,
+    /// `Ok(None)` if the event was dropped or the book is one-sided,
+    /// or an `Err` if the book lost sync and must be re-seeded.
+    pub fn on_depth_update(&mut self, event: &DepthStream) -> Result<Option<OrderBookFeatures>, String> {
+        if !self.book.apply_diff(event)? {
+            return Ok(None);
+        }
+        Ok(self.compute(event.event_time))
+    }
+
+    /// Applies a normalized depth update and returns the resulting features, see `on_depth_update`.
+    pub fn on_normalized_depth(&mut self, event: &DepthUpdate) -> Result<Option<OrderBookFeatures>, String> {
+        if !self.book.apply_depth_update(event)? {
+            return Ok(None);
+        }
+        Ok(self.compute(event.event_time))
+    }
+
+    /// Computes the current features from the book.
+    pub fn compute(&mut self, event_time: u64) -> Option<OrderBookFeatures> {
+        let (best_bid, _) = self.book.best_bid()?;
+        let (best_ask, _) = self.book.best_ask()?;
+        let mid_price = (best_bid + best_ask) / 2.0;
+        let imbalance = imbalance(&self.book, self.config.levels)?;
+
+        if self.imbalance_window.len() == self.config.rolling_window.max(1) {
+            self.imbalance_window.pop_front();
+        }
+        self.imbalance_window.push_back(imbalance);
+        let rolling_imbalance = self.imbalance_window.iter().sum::<f64>() / self.imbalance_window.len() as f64;
+
+        Some(OrderBookFeatures {
+            symbol: self.book.symbol.clone(),
+            event_time,
+            best_bid,
+            best_ask,
+            spread: best_ask - best_bid,
+            mid_price,
+            microprice: microprice(&self.book)?,
+            imbalance,
+            rolling_imbalance,
+            bid_slope: book_slope(&self.book.top_bids(self.config.levels), mid_price),
+            ask_slope: book_slope(&self.book.top_asks(self.config.levels), mid_price),
+        })
+    }
+}
+
+/// Messages the feature feed can extract depth updates from: raw stream messages
+/// (`BinanceWsMessage`) or normalized `MarketEvent`s.
+pub trait DepthSource {
+    /// Returns the depth update for `symbol` carried by this message, if any.
+    fn into_depth_update(self, symbol: &str) -> Option<DepthUpdate>;
+}
+
+impl DepthSource for BinanceWsMessage {
+    fn into_depth_update(self, symbol: &str) -> Option<DepthUpdate> {
+        let data = self.payload()?;
+        if data.get("e").and_then(|e| e.as_str()) != Some("depthUpdate") {
+            return None;
+        }
+        let event: DepthStream = serde_json::from_value(data.clone()).ok()?;
+        if event.symbol.eq_ignore_ascii_case(symbol) { depth_update_from_stream(&event).ok() } else { None }
+    }
+}
+
+impl DepthSource for MarketEvent {
+    fn into_depth_update(self, symbol: &str) -> Option<DepthUpdate> {
+        match self {
+            MarketEvent::Depth(update) if update.symbol.as_str().eq_ignore_ascii_case(symbol) => Some(update),
+            _ => None,
+        }
+    }
+}
+
+/// Runs the real-time order book feature feed for a single symbol.
+///
+/// The market stream feeding `stream_receiver` must be subscribed to `<symbol>@depth` (or `@depth@100ms`).
+/// The book is seeded from a REST snapshot and re-seeded whenever a sequence gap is detected.
+///
+/// # Arguments
+/// * `rest_client` - Used to fetch depth snapshots.
+/// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
+/// * `config` - Feature computation settings.
+/// * `stream_receiver` - Market stream messages (raw or normalized) containing the diff depth events.
+/// * `feature_sender` - Receives an `OrderBookFeatures` after every applied update.
+pub async fn run_feature_feed<M: DepthSource>(
+    rest_client: Arc<RestClient>,
+    symbol: String,
+    config: FeatureConfig,
+    mut stream_receiver: mpsc::Receiver<M>,
+    feature_sender: mpsc::Sender<OrderBookFeatures>,
+) {
+    let snapshot_limit = config.snapshot_limit;
+    let mut engine = OrderBookFeatureEngine::new(&symbol, config);
+    let mut needs_snapshot = true;
+
+    while let Some(message) = stream_receiver.recv().await {
+        let Some(event) = message.into_depth_update(&symbol) else {
+            continue;
+        };
+
+        if needs_snapshot {
+            match rest_client.get_order_book(&symbol, Some(snapshot_limit)).await {
+                Ok(snapshot) => {
+                    if let Err(e) = engine.apply_snapshot(&snapshot) {
+                        error!("Failed to seed order book for {}: {}", symbol, e);
+                        continue;
+                    }
+                    info!("Order book for {} seeded at update id {}", symbol, snapshot.last_update_id);
+                    needs_snapshot = false;
+                },
+                Err(e) => {
+                    error!("Failed to fetch order book snapshot for {}: {}", symbol, e);
+                    continue;
+                }
+            }
+        }
+
+        match engine.on_normalized_depth(&event) {
+            Ok(Some(features)) => {
+                if feature_sender.send(features).await.is_err() {
+                    info!("Order book feature consumer dropped for {}. Stopping feed.", symbol);
+                    return;
+                }
+            },
+            Ok(None) => debug!("Depth event {} for {} dropped", event.final_update_id, symbol),
+            Err(e) => {
+                warn!("{}. Re-syncing from snapshot.", e);
+                needs_snapshot = true;
+            }
+        }
+    }
+    info!("Market stream channel closed. Order book feature feed for {} stopped.", symbol);
+}
@@ -0,0 +1,178 @@
+// src/patterns/mod.rs
+
+//! This module detects common candlestick patterns: engulfing, pin bars (hammer / shooting star),
+//! inside bars and doji variants. Patterns are evaluated on a slice of closed candles, oldest
+//! first, and refer to the last candle of the slice (engulfing and inside bars also look at the
+//! one before it).
+//!
+//! Patterns can be used directly as strategy building blocks (`Pattern::matches`, `detect`) or as
+//! an optional entry filter configured per strategy (`PatternFilter`), e.g. an RSI entry that also
+//! requires a bullish engulfing or hammer within the last two candles.
+
+use serde::{Deserialize, Serialize};
+
+use crate::market_event::Candle;
+
+/// A candlestick pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    BullishEngulfing, // Green body engulfing the previous red body
+    BearishEngulfing, // Red body engulfing the previous green body
+    BullishPinBar, // Hammer: long lower wick, small body near the high
+    BearishPinBar, // Shooting star: long upper wick, small body near the low
+    InsideBar, // High and low within the previous candle's range
+    Doji, // Tiny body
+    DragonflyDoji, // Doji with the open/close at the high
+    GravestoneDoji, // Doji with the open/close at the low
+    LongLeggedDoji, // Doji with long wicks on both sides
+}
+
+/// The directional bias of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bias {
+    Bullish,
+    Bearish,
+    Neutral,
+}
+
+/// Every pattern, in detection order.
+pub const ALL_PATTERNS: [Pattern; 9] = [
+    Pattern::BullishEngulfing,
+    Pattern::BearishEngulfing,
+    Pattern::BullishPinBar,
+    Pattern::BearishPinBar,
+    Pattern::InsideBar,
+    Pattern::Doji,
+    Pattern::DragonflyDoji,
+    Pattern::GravestoneDoji,
+    Pattern::LongLeggedDoji,
+];
+
+/// Thresholds of the pattern definitions, as fractions of the candle's range.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PatternConfig {
+    pub doji_max_body: f64, // A doji's body is at most this fraction of its range
+    pub pin_min_wick: f64, // A pin bar's long wick is at least this fraction of its range
+    pub pin_max_body: f64, // A pin bar's body is at most this fraction of its range
+    pub shadow_max: f64, // "No wick" means a wick of at most this fraction (dragonfly/gravestone)
+    pub long_leg_min: f64, // Each wick of a long-legged doji is at least this fraction
+}
+
+impl Default for PatternConfig {
+    fn default() -> Self {
+        Self { doji_max_body: 0.1, pin_min_wick: 0.6, pin_max_body: 0.3, shadow_max: 0.1, long_leg_min: 0.3 }
+    }
+}
+
+fn range(c: &Candle) -> f64 {
+    c.high - c.low
+}
+
+fn body(c: &Candle) -> f64 {
+    (c.close - c.open).abs()
+}
+
+fn upper_wick(c: &Candle) -> f64 {
+    c.high - c.open.max(c.close)
+}
+
+fn lower_wick(c: &Candle) -> f64 {
+    c.open.min(c.close) - c.low
+}
+
+fn is_green(c: &Candle) -> bool {
+    c.close > c.open
+}
+
+fn is_red(c: &Candle) -> bool {
+    c.close < c.open
+}
+
+impl Pattern {
+    /// Returns the directional bias of the pattern.
+    pub fn bias(&self) -> Bias {
+        match self {
+            Pattern::BullishEngulfing | Pattern::BullishPinBar | Pattern::DragonflyDoji => Bias::Bullish,
+            Pattern::BearishEngulfing | Pattern::BearishPinBar | Pattern::GravestoneDoji => Bias::Bearish,
+            Pattern::InsideBar | Pattern::Doji | Pattern::LongLeggedDoji => Bias::Neutral,
+        }
+    }
+
+    /// Returns true when the last candle of `candles` completes the pattern.
+    pub fn matches(&self, candles: &[Candle], config: &PatternConfig) -> bool {
+        let Some(last) = candles.last() else { return false };
+        let previous = candles.len().checked_sub(2).map(|i| &candles[i]);
+        let r = range(last);
+        if r <= 0.0 {
+            return false; // A flat candle has no shape
+        }
+        let is_doji = body(last) <= config.doji_max_body * r;
+        match self {
+            Pattern::BullishEngulfing => previous.is_some_and(|p| {
+                is_red(p) && is_green(last) && last.open <= p.close && last.close >= p.open && body(last) > body(p)
+            }),
+            Pattern::BearishEngulfing => previous.is_some_and(|p| {
+                is_green(p) && is_red(last) && last.open >= p.close && last.close <= p.open && body(last) > body(p)
+            }),
+            Pattern::BullishPinBar => {
+                body(last) <= config.pin_max_body * r && lower_wick(last) >= config.pin_min_wick * r
+            },
+            Pattern::BearishPinBar => {
+                body(last) <= config.pin_max_body * r && upper_wick(last) >= config.pin_min_wick * r
+            },
+            Pattern::InsideBar => previous.is_some_and(|p| last.high < p.high && last.low > p.low),
+            Pattern::Doji => is_doji,
+            Pattern::DragonflyDoji => is_doji && upper_wick(last) <= config.shadow_max * r,
+            Pattern::GravestoneDoji => is_doji && lower_wick(last) <= config.shadow_max * r,
+            Pattern::LongLeggedDoji => {
+                is_doji && upper_wick(last) >= config.long_leg_min * r && lower_wick(last) >= config.long_leg_min * r
+            },
+        }
+    }
+}
+
+/// Returns every pattern completed by the last candle of `candles`.
+pub fn detect(candles: &[Candle], config: &PatternConfig) -> Vec<Pattern> {
+    ALL_PATTERNS.iter().copied().filter(|p| p.matches(candles, config)).collect()
+}
+
+/// An entry filter: the entry is allowed when any of `patterns` completed on one of the last
+/// `lookback` candles. An empty pattern list allows every entry.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternFilter {
+    pub patterns: Vec<Pattern>,
+    #[serde(default = "default_lookback")]
+    pub lookback: usize, // 1 = the current candle only
+    #[serde(default)]
+    pub config: PatternConfig,
+}
+
+fn default_lookback() -> usize {
+    1
+}
+
+impl PatternFilter {
+    /// Creates a filter over the current candle with the default thresholds.
+    pub fn new(patterns: Vec<Pattern>) -> Self {
+        Self { patterns, lookback: 1, config: PatternConfig::default() }
+    }
+
+    /// Returns the number of candles `allows` needs to see (lookback plus the engulfing/inside bar context).
+    pub fn history_len(&self) -> usize {
+        self.lookback.max(1) + 1
+    }
+
+    /// Returns true when an entry on the last candle of `candles` is allowed.
+    pub fn allows(&self, candles: &[Candle]) -> bool {
+        if self.patterns.is_empty() {
+            return true;
+        }
+        (0..self.lookback.max(1)).any(|offset| {
+            let end = candles.len().saturating_sub(offset);
+            end > 0 && self.patterns.iter().any(|p| p.matches(&candles[..end], &self.config))
+        })
+    }
+}
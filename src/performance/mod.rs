@@ -0,0 +1,266 @@
+// src/performance/mod.rs
+
+//! This module aggregates live trading performance by calendar week (ISO, Monday start) or month:
+//! returns, max drawdown, trade counts, fees and risk-adjusted metrics, matching what the backtest
+//! reports provide for historical data.
+//!
+//! Closed trades are the input; they can come from a CSV export (`load_closed_trades_csv`, used by
+//! the `performance_report` binary) or be kept in memory by the running bot. Summaries are exposed
+//! through `performance_router` (an Axum router to merge into an admin server) and
+//! `run_periodic_summaries`, which emits a summary message whenever a period ends.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::{extract::{Query, State}, routing::get, Json, Router};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use tracing::info;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, RwLock};
+
+use crate::currency::{CurrencyConverter, ReportingCurrency};
+use crate::metrics::{self, MetricsConfig};
+
+/// A closed (realized) trade.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ClosedTrade {
+    pub closed_at_ms: i64,
+    pub symbol: String,
+    pub pnl: f64, // Realized PnL before fees, in the quote asset
+    #[serde(default)]
+    pub fees: f64,
+    #[serde(default)]
+    pub variant: Option<String>, // A/B test variant that opened the trade, see `experiment`
+}
+
+impl ClosedTrade {
+    /// Net PnL after fees.
+    pub fn net_pnl(&self) -> f64 {
+        self.pnl - self.fees
+    }
+}
+
+/// Aggregation period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Week,
+    Month,
+}
+
+impl Period {
+    /// Parses "week"/"weekly" or "month"/"monthly".
+    pub fn from_str_opt(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "week" | "weekly" => Some(Period::Week),
+            "month" | "monthly" => Some(Period::Month),
+            _ => None,
+        }
+    }
+
+    /// Returns the first day of the period containing `date`.
+    pub fn start_of(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Period::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// Returns the first day of the period following the one starting at `start`.
+    pub fn next_start(&self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Week => start + Duration::days(7),
+            Period::Month => start.checked_add_months(chrono::Months::new(1)).unwrap_or(start),
+        }
+    }
+
+    fn label(&self, start: NaiveDate) -> String {
+        match self {
+            Period::Week => format!("{}-W{:02}", start.iso_week().year(), start.iso_week().week()),
+            Period::Month => start.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// Performance of a single week or month.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PeriodSummary {
+    pub label: String, // e.g. "2024-W05" or "2024-02"
+    pub start: Option<NaiveDate>,
+    pub starting_equity: f64,
+    pub ending_equity: f64,
+    pub net_pnl: f64,
+    pub fees: f64,
+    pub return_pct: f64,
+    pub max_drawdown_pct: f64, // Within the period, on closed-trade equity
+    pub trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub sharpe_ratio: Option<f64>, // From daily returns within the period
+}
+
+fn utc_date(timestamp_ms: i64) -> Option<NaiveDate> {
+    DateTime::<Utc>::from_timestamp_millis(timestamp_ms).map(|dt| dt.date_naive())
+}
+
+/// Aggregates closed trades by week or month.
+///
+/// # Arguments
+/// * `trades` - Closed trades, in any order.
+/// * `period` - Week or month.
+/// * `starting_equity` - Account equity before the first trade, used for returns and drawdowns.
+/// * `config` - Risk-free rate for the per-period Sharpe ratio (computed from daily returns).
+///
+/// # Returns
+/// One summary per period with at least one trade, oldest first.
+pub fn aggregate(trades: &[ClosedTrade], period: Period, starting_equity: f64, config: &MetricsConfig) -> Vec<PeriodSummary> {
+    let mut sorted: Vec<&ClosedTrade> = trades.iter().collect();
+    sorted.sort_by_key(|t| t.closed_at_ms);
+
+    // Group by period, then by day within the period (for daily returns)
+    let mut periods: BTreeMap<NaiveDate, BTreeMap<NaiveDate, Vec<&ClosedTrade>>> = BTreeMap::new();
+    for trade in sorted {
+        let Some(date) = utc_date(trade.closed_at_ms) else { continue };
+        periods.entry(period.start_of(date)).or_default().entry(date).or_default().push(trade);
+    }
+
+    let daily_config = MetricsConfig { periods_per_year: metrics::DAILY_PERIODS_PER_YEAR, ..config.clone() };
+    let mut equity = starting_equity;
+    periods.into_iter().map(|(start, days)| {
+        let mut summary = PeriodSummary {
+            label: period.label(start),
+            start: Some(start),
+            starting_equity: equity,
+            ..Default::default()
+        };
+        let mut peak = equity;
+        let mut daily_equity = vec![equity];
+        for trades in days.values() {
+            for trade in trades {
+                equity += trade.net_pnl();
+                summary.net_pnl += trade.net_pnl();
+                summary.fees += trade.fees;
+                summary.trades += 1;
+                if trade.net_pnl() > 0.0 {
+                    summary.wins += 1;
+                } else if trade.net_pnl() < 0.0 {
+                    summary.losses += 1;
+                }
+                peak = peak.max(equity);
+                if peak > 0.0 {
+                    summary.max_drawdown_pct = summary.max_drawdown_pct.max((peak - equity) / peak * 100.0);
+                }
+            }
+            daily_equity.push(equity);
+        }
+        summary.ending_equity = equity;
+        summary.return_pct = if summary.starting_equity > 0.0 { summary.net_pnl / summary.starting_equity * 100.0 } else { 0.0 };
+        summary.sharpe_ratio = metrics::sharpe_ratio(&metrics::simple_returns(&daily_equity), &daily_config);
+        summary
+    }).collect()
+}
+
+/// Aggregates closed trades like `aggregate`, with PnL, fees and equity converted to the
+/// reporting currency at the time of each trade (returns are then in that currency too).
+pub fn aggregate_converted(trades: &[ClosedTrade], period: Period, starting_equity: f64, config: &MetricsConfig, converter: &CurrencyConverter) -> Vec<PeriodSummary> {
+    let first_trade_ms = trades.iter().map(|t| t.closed_at_ms).min().unwrap_or(i64::MAX);
+    aggregate(&converter.convert_trades(trades), period, converter.convert(starting_equity, first_trade_ms), config)
+}
+
+/// Formats a summary as a short message, e.g. for a scheduled notification.
+pub fn format_summary_message(summary: &PeriodSummary, currency: ReportingCurrency) -> String {
+    format!(
+        "Performance {}: net P/L {} ({:+.2}%), max drawdown {:.2}%, {} trades ({} wins / {} losses), fees {}",
+        summary.label, currency.format(summary.net_pnl), summary.return_pct, summary.max_drawdown_pct,
+        summary.trades, summary.wins, summary.losses, currency.format(summary.fees)
+    )
+}
+
+/// Prints a table of period summaries to the console.
+pub fn print_period_summaries(summaries: &[PeriodSummary], currency: ReportingCurrency) {
+    println!("\n--- Performance by Period ({}) ---", currency.code());
+    println!("{:<10} | {:>12} | {:>9} | {:>9} | {:>6} | {:>6} | {:>10} | {:>8}", "Period", "Net P/L", "Return", "Max DD", "Trades", "Wins", "Fees", "Sharpe");
+    println!("{:-<91}", "");
+    for s in summaries {
+        let sharpe = s.sharpe_ratio.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "n/a".to_string());
+        println!(
+            "{:<10} | {:>12} | {:>8.2}% | {:>8.2}% | {:>6} | {:>6} | {:>10} | {:>8}",
+            s.label, currency.format(s.net_pnl), s.return_pct, s.max_drawdown_pct, s.trades, s.wins, currency.format(s.fees), sharpe
+        );
+    }
+    println!("{:-<91}", "");
+}
+
+/// Loads closed trades from a CSV file with the columns `closed_at_ms,symbol,pnl,fees` (and an
+/// optional `variant` column).
+pub fn load_closed_trades_csv(path: &str) -> Result<Vec<ClosedTrade>, String> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    reader.deserialize()
+        .map(|row| row.map_err(|e| format!("Failed to parse trade in {}: {}", path, e)))
+        .collect()
+}
+
+/// Shared state of the performance endpoints.
+#[derive(Clone)]
+pub struct PerformanceState {
+    pub trades: Arc<RwLock<Vec<ClosedTrade>>>,
+    pub starting_equity: f64,
+    pub metrics_config: MetricsConfig,
+    pub converter: CurrencyConverter, // Reporting currency of the summaries
+}
+
+#[derive(Debug, Deserialize)]
+struct PerformanceQuery {
+    period: Option<String>,
+}
+
+async fn handle_performance(
+    State(state): State<PerformanceState>,
+    Query(query): Query<PerformanceQuery>,
+) -> Result<Json<Vec<PeriodSummary>>, String> {
+    let period = match query.period.as_deref() {
+        Some(p) => Period::from_str_opt(p).ok_or_else(|| format!("Invalid period: {}", p))?,
+        None => Period::Month,
+    };
+    let trades = state.trades.read().await;
+    Ok(Json(aggregate_converted(&trades, period, state.starting_equity, &state.metrics_config, &state.converter)))
+}
+
+/// Builds the admin router serving `GET /performance?period=week|month`, in the reporting currency.
+pub fn performance_router(state: PerformanceState) -> Router {
+    Router::new()
+        .route("/performance", get(handle_performance))
+        .with_state(state)
+}
+
+/// Sends a summary message for each week/month as it ends, until `message_sender` is closed.
+/// Checks for completed periods once per `check_interval`.
+pub async fn run_periodic_summaries(
+    state: PerformanceState,
+    period: Period,
+    check_interval: std::time::Duration,
+    message_sender: mpsc::Sender<String>,
+) {
+    let mut current_start = period.start_of(Utc::now().date_naive());
+    let mut interval = tokio::time::interval(check_interval);
+    loop {
+        interval.tick().await;
+        let now_start = period.start_of(Utc::now().date_naive());
+        if now_start == current_start {
+            continue;
+        }
+        let trades = state.trades.read().await;
+        let summaries = aggregate_converted(&trades, period, state.starting_equity, &state.metrics_config, &state.converter);
+        drop(trades);
+        let message = match summaries.iter().find(|s| s.start == Some(current_start)) {
+            Some(summary) => format_summary_message(summary, state.converter.currency),
+            None => format!("Performance {}: no trades", period.label(current_start)),
+        };
+        info!("{}", message);
+        if message_sender.send(message).await.is_err() {
+            info!("Performance summary consumer dropped. Stopping periodic summaries.");
+            return;
+        }
+        current_start = now_start;
+    }
+}
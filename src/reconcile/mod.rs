@@ -0,0 +1,221 @@
+// src/reconcile/mod.rs
+
+//! This module reconciles the exchange's state with the bot's local state at startup, so the bot
+//! does not start blind after a restart, a crash, or orders placed by hand while it was down.
+//!
+//! The local state is the event log (see `events`): the positions it replays to and the orders the
+//! bot placed. Against it, the open orders, positions and balances fetched from the exchange are
+//! checked:
+//! - Open orders the bot does not know are adopted: an `OrderAdopted` event is appended, so their
+//!   fills are attributed and later restarts know them.
+//! - Stop-loss and take-profit orders (reduce-only or `closePosition`) without a position to
+//!   protect are flagged as orphaned; they are left alone, since they may guard a pending entry.
+//! - Positions that differ from the replayed ones (opened by hand, closed while the bot was down,
+//!   or fills the bot missed) are warned about.
+//! - The wallet balance is compared with the last equity snapshot, when the history is stored.
+//!
+//! Issues are logged and sent as one `Reconciliation` notification.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::account_info::{AccountInfo, PositionRisk};
+use crate::admin::{summarize_orders, OrderSummary};
+use crate::events::{load_events, replay, BotEvent, BotState, EventLog, EventRecord};
+use crate::experiment;
+use crate::notify::{Notification, Notifications};
+use crate::order::{Order, PositionSide};
+use crate::rest_api::RestClient;
+use crate::storage::{EquitySnapshot, Storage};
+
+/// Quantities closer than this count as equal.
+const QUANTITY_EPSILON: f64 = 1e-9;
+/// Order types that trigger at a stop price.
+const STOP_ORDER_TYPES: &[&str] = &["STOP", "STOP_MARKET", "TAKE_PROFIT", "TAKE_PROFIT_MARKET", "TRAILING_STOP_MARKET"];
+
+/// The exchange's state of the account.
+pub struct ExchangeState {
+    pub orders: Vec<Order>,
+    pub positions: Vec<PositionRisk>,
+    pub account: AccountInfo,
+}
+
+impl ExchangeState {
+    /// Fetches the open orders, positions and balances.
+    pub async fn fetch(rest_client: &RestClient) -> Result<Self, String> {
+        let (orders, positions, account) = tokio::try_join!(
+            rest_client.get_open_orders(None),
+            rest_client.get_position_risk(None),
+            rest_client.get_account_info(),
+        )?;
+        Ok(Self { orders, positions, account })
+    }
+}
+
+/// A position leg whose size on the exchange differs from the bot's.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionMismatch {
+    pub key: String, // Symbol, or "SYMBOL:LONG"/"SYMBOL:SHORT" in hedge mode, as in `BotState`
+    pub local_quantity: f64, // Signed, as replayed from the event log
+    pub exchange_quantity: f64,
+    pub entry_price: f64, // On the exchange
+}
+
+/// What startup reconciliation found.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciliationReport {
+    pub known_orders: usize, // Open orders the bot placed or adopted earlier
+    pub adopted_orders: Vec<OrderSummary>,
+    pub orphaned_stops: Vec<OrderSummary>,
+    pub position_mismatches: Vec<PositionMismatch>,
+    pub balances: EquitySnapshot,
+    pub last_snapshot: Option<EquitySnapshot>, // Last equity snapshot stored before the restart
+}
+
+impl ReconciliationReport {
+    /// Returns the events recording the adopted orders.
+    pub fn adoption_events(&self) -> Vec<BotEvent> {
+        self.adopted_orders.iter().map(|order| BotEvent::OrderAdopted {
+            symbol: order.symbol.clone(),
+            client_order_id: order.client_order_id.clone(),
+            order_id: order.order_id,
+            order_type: order.order_type.clone(),
+            side: order.side.clone(),
+            quantity: order.quantity,
+            price: if order.price != 0.0 { order.price } else { order.stop_price },
+        }).collect()
+    }
+
+    /// Describes each discrepancy in one line; empty when the states match.
+    pub fn issues(&self) -> Vec<String> {
+        let adopted = self.adopted_orders.iter().map(|o| format!(
+            "Adopted unknown order {} ({} {} {} {})", o.client_order_id, o.order_type, o.side, o.quantity, o.symbol,
+        ));
+        let orphaned = self.orphaned_stops.iter().map(|o| format!(
+            "Orphaned {} {} for {} at {}: no {} position to protect", o.order_type, o.client_order_id, o.symbol, o.stop_price,
+            if o.position_side == "BOTH" { "open".to_string() } else { o.position_side.clone() },
+        ));
+        let positions = self.position_mismatches.iter().map(|p| match (p.local_quantity == 0.0, p.exchange_quantity == 0.0) {
+            (true, _) => format!("Unrecognized position {} of {} at {} on the exchange", p.key, p.exchange_quantity, p.entry_price),
+            (_, true) => format!("Position {} of {} is closed on the exchange", p.key, p.local_quantity),
+            _ => format!("Position {} is {} on the exchange, {} in the event log", p.key, p.exchange_quantity, p.local_quantity),
+        });
+        adopted.chain(orphaned).chain(positions).collect()
+    }
+}
+
+/// Strips the variant tag and the protective order suffix from a client order ID, so the
+/// stop-loss and take-profit of an entry share the entry's ID.
+fn base_order_id(client_order_id: &str) -> &str {
+    let id = match experiment::variant_of(client_order_id) {
+        Some(tag) => &client_order_id[..client_order_id.len() - tag.len() - 2],
+        None => client_order_id,
+    };
+    id.strip_suffix("sl").or_else(|| id.strip_suffix("tp")).unwrap_or(id)
+}
+
+/// Returns the base IDs of the orders the event log knows: placed for a signal, or adopted.
+pub fn known_order_ids(records: &[EventRecord]) -> HashSet<String> {
+    records.iter()
+        .filter_map(|record| match &record.event {
+            BotEvent::OrderPlaced { client_order_id, .. } | BotEvent::OrderAdopted { client_order_id, .. } => Some(base_order_id(client_order_id).to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Compares the exchange's state with the local one.
+pub fn reconcile(state: &BotState, known_orders: &HashSet<String>, exchange: &ExchangeState, last_snapshot: Option<EquitySnapshot>) -> ReconciliationReport {
+    let orders = summarize_orders(&exchange.orders);
+    let (known, adopted): (Vec<_>, Vec<_>) = orders.iter().cloned()
+        .partition(|order| known_orders.contains(base_order_id(&order.client_order_id)));
+
+    let open_legs: Vec<&PositionRisk> = exchange.positions.iter().filter(|p| p.amount().abs() > QUANTITY_EPSILON).collect();
+    let orphaned_stops = exchange.orders.iter().zip(&orders)
+        .filter(|(order, _)| STOP_ORDER_TYPES.contains(&order.order_type.as_str()) && (order.close_position || order.reduce_only))
+        .filter(|(order, _)| !open_legs.iter().any(|p| p.symbol == order.symbol && (order.position_side == PositionSide::Both || p.position_side == order.position_side.as_str())))
+        .map(|(_, summary)| summary.clone())
+        .collect();
+
+    let mut exchange_positions: BTreeMap<String, (f64, f64)> = BTreeMap::new();
+    for position in open_legs {
+        let key = match position.position_side.as_str() {
+            "LONG" | "SHORT" => format!("{}:{}", position.symbol, position.position_side),
+            _ => position.symbol.clone(),
+        };
+        exchange_positions.insert(key, (position.amount(), position.entry_price.parse().unwrap_or_default()));
+    }
+    let keys: BTreeSet<&String> = exchange_positions.keys().chain(state.positions.keys()).collect();
+    let position_mismatches = keys.into_iter()
+        .filter_map(|key| {
+            let local_quantity = state.positions.get(key).map(|p| p.quantity).unwrap_or_default();
+            let (exchange_quantity, entry_price) = exchange_positions.get(key).copied().unwrap_or_default();
+            ((local_quantity - exchange_quantity).abs() > QUANTITY_EPSILON)
+                .then(|| PositionMismatch { key: key.clone(), local_quantity, exchange_quantity, entry_price })
+        })
+        .collect();
+
+    ReconciliationReport {
+        known_orders: known.len(),
+        adopted_orders: adopted,
+        orphaned_stops,
+        position_mismatches,
+        balances: EquitySnapshot::from_account(chrono::Utc::now().timestamp_millis(), &exchange.account),
+        last_snapshot,
+    }
+}
+
+/// Reconciles the exchange's state with the event log at startup: adopts the unknown orders into
+/// the log, and logs and notifies the discrepancies.
+pub async fn reconcile_on_startup(
+    rest_client: &RestClient,
+    event_log: &EventLog,
+    storage: Option<&dyn Storage>,
+    notifications: Option<&Notifications>,
+) -> Result<ReconciliationReport, String> {
+    let records = load_events(event_log.path())?;
+    let exchange = ExchangeState::fetch(rest_client).await.map_err(|e| format!("Failed to fetch the exchange state: {}", e))?;
+    let snapshots = match storage {
+        Some(storage) => storage.equity_snapshots(0).await.map(Some),
+        None => Ok(None),
+    };
+    let last_snapshot = match snapshots {
+        Ok(snapshots) => snapshots.and_then(|snapshots| snapshots.into_iter().last()),
+        Err(e) => {
+            warn!("Failed to read the last equity snapshot: {}", e);
+            None
+        },
+    };
+    let report = reconcile(&replay(&records, None), &known_order_ids(&records), &exchange, last_snapshot);
+
+    let time_ms = chrono::Utc::now().timestamp_millis();
+    for event in report.adoption_events() {
+        event_log.append(time_ms, event)?;
+    }
+    info!(
+        "Reconciled with the exchange: {} open order(s) ({} adopted), {} open position leg(s), wallet balance {:.2}, available {:.2}",
+        report.known_orders + report.adopted_orders.len(),
+        report.adopted_orders.len(),
+        exchange.positions.iter().filter(|p| p.amount() != 0.0).count(),
+        report.balances.wallet_balance,
+        report.balances.available_balance,
+    );
+    if let Some(last) = &report.last_snapshot {
+        let change = report.balances.wallet_balance - last.wallet_balance;
+        if change.abs() > QUANTITY_EPSILON {
+            info!("Wallet balance changed by {:.2} since the last snapshot", change);
+        }
+    }
+    let issues = report.issues();
+    for issue in &issues {
+        warn!("{}", issue);
+    }
+    if let (Some(notifications), false) = (notifications, issues.is_empty()) {
+        notifications.notify(Notification::Reconciliation { issues });
+    }
+    Ok(report)
+}
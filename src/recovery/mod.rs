@@ -0,0 +1,111 @@
+// src/recovery/mod.rs
+
+//! This module persists the bot's in-flight state, so that after a crash or a restart it resumes
+//! managing the positions it left open instead of abandoning their stop-loss management mid-trade:
+//! the working brackets (pending entries and protected positions, see `order::bracket`), the
+//! active trailing stops with their water marks and resting stop orders (see `order::trailing`)
+//! and the risk counters (the day's realized PnL, the last loss and the day of the daily loss
+//! breaker, see `risk::RiskState`), so the daily limits and a tripped breaker hold after a restart.
+//!
+//! The state is kept in the state directory (`bot_state.json`) by a `StateStore`, rewritten
+//! atomically (a temporary file renamed over the old one) whenever a service's state changes, so a
+//! crash leaves either the old or the new state, never half of it. The bracket and trailing
+//! services restore their managers from the store when they are spawned with one; the bot restores
+//! the risk counters at startup, and replays them from the event log (see `events`) only when the
+//! state has none, e.g. the first start after an upgrade.
+//!
+//! Risk counters (the day's realized PnL and the last loss, see `risk::RiskState`) are not stored
+//! here: they are part of the decision state replayed from the event log (see `events`).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::order::bracket::TrackedBracket;
+use crate::order::trailing::TrailingStop;
+use crate::risk::RiskState;
+
+/// Default file name of the persisted state inside the state directory.
+pub const STATE_FILE: &str = "bot_state.json";
+
+/// The in-flight state that survives restarts.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedState {
+    pub saved_at_ms: i64,
+    #[serde(default)]
+    pub brackets: Vec<TrackedBracket>, // Pending entries and protected positions
+    #[serde(default)]
+    pub trailing_stops: Vec<TrailingStop>, // Active ones
+    #[serde(default)]
+    pub risk: Option<RiskState>, // Daily PnL, last loss and the loss breaker's day; saved on every change
+}
+
+/// The persisted state, shared by the services that update it.
+#[derive(Debug)]
+pub struct StateStore {
+    path: Option<PathBuf>, // `None` keeps the state in memory only
+    state: Mutex<PersistedState>,
+}
+
+impl StateStore {
+    /// Loads the state stored at `path`; a missing file is an empty state. An unreadable one is an
+    /// error rather than a fresh start, which would leave its positions unmanaged.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let state = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| format!("Invalid bot state in {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => PersistedState::default(),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        Ok(Self { path: Some(path), state: Mutex::new(state) })
+    }
+
+    /// Creates a store that is not persisted, e.g. when the crate is embedded or in tests.
+    pub fn in_memory() -> Self {
+        Self { path: None, state: Mutex::new(PersistedState::default()) }
+    }
+
+    /// Returns the path of the state file, if the state is persisted.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns a copy of the current state.
+    pub fn state(&self) -> PersistedState {
+        self.state.lock().map(|state| state.clone()).unwrap_or_default()
+    }
+
+    /// Changes the state and writes it to the file.
+    pub fn update(&self, change: impl FnOnce(&mut PersistedState)) -> Result<(), String> {
+        let mut state = self.state.lock().map_err(|_| "Bot state lock poisoned".to_string())?;
+        change(&mut state);
+        state.saved_at_ms = chrono::Utc::now().timestamp_millis();
+        match &self.path {
+            Some(path) => write_state(path, &state),
+            None => Ok(()),
+        }
+    }
+
+    /// Logs what a previous run left to manage.
+    pub fn log_recovered(&self) {
+        let state = self.state();
+        if !state.brackets.is_empty() || !state.trailing_stops.is_empty() {
+            info!(
+                "Recovered {} working bracket(s) and {} trailing stop(s) saved at {}",
+                state.brackets.len(), state.trailing_stops.len(), state.saved_at_ms,
+            );
+        }
+    }
+}
+
+/// Writes the state to `path` atomically: to a temporary file first, then renamed over `path`.
+pub fn write_state(path: &Path, state: &PersistedState) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(state).map_err(|e| format!("Failed to serialize the bot state: {}", e))?;
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, json).map_err(|e| format!("Failed to write {}: {}", temporary.display(), e))?;
+    fs::rename(&temporary, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
@@ -0,0 +1,191 @@
+// src/rest_api/error.rs
+
+//! This module types the errors Binance returns, e.g. `{"code":-2019,"msg":"Margin is insufficient."}`,
+//! so callers can tell insufficient margin from an invalid symbol or a rate limit without matching
+//! on message text.
+//!
+//! Requests keep failing with `String` errors; the REST and WebSocket API clients format exchange
+//! errors through `BinanceApiError`'s `Display` (`Binance API error -2019: Margin is insufficient.`)
+//! and `BinanceApiError::from_error` recovers them from such a string.
+
+use std::fmt;
+
+use serde::Deserialize;
+
+/// Error codes of the Binance Futures API callers branch on. Other codes are kept as `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinanceErrorCode {
+    Unknown,                   // -1000: unknown error while processing the request
+    Disconnected,              // -1001: internal error; the request may be retried
+    Unauthorized,              // -1002: not authorized to execute the request
+    TooManyRequests,           // -1003: request weight exceeded; banned if it goes on
+    UnexpectedResponse,        // -1006: unexpected response from the message bus
+    Timeout,                   // -1007: timeout waiting for the backend; execution status unknown
+    ServerBusy,                // -1008: server overloaded
+    TooManyOrders,             // -1015: order rate exceeded
+    InvalidTimestamp,          // -1021: timestamp outside the recvWindow
+    InvalidSignature,          // -1022: signature not valid
+    IllegalParameter,          // -1100: illegal characters in a parameter
+    MandatoryParamMissing,     // -1102: a mandatory parameter was not sent
+    BadPrecision,              // -1111: precision over the maximum of the asset
+    InvalidSymbol,             // -1121: invalid symbol
+    NewOrderRejected,          // -2010: new order rejected
+    CancelRejected,            // -2011: unknown order sent to cancel
+    NoSuchOrder,               // -2013: order does not exist
+    BadApiKeyFormat,           // -2014: API key format invalid
+    RejectedApiKey,            // -2015: invalid API key, IP or permissions
+    BalanceInsufficient,       // -2018: balance is insufficient
+    MarginInsufficient,        // -2019: margin is insufficient
+    WouldImmediatelyTrigger,   // -2021: the stop order would trigger immediately
+    ReduceOnlyRejected,        // -2022: reduce-only order rejected
+    QuantityNotPositive,       // -4003: quantity less than or equal to zero
+    PriceNotOnTickSize,        // -4014: price not increased by the tick size
+    MinNotional,               // -4164: notional below the minimum
+    PostOnlyRejected,          // -5022: post-only order would immediately match
+    Other(i64),
+}
+
+impl BinanceErrorCode {
+    /// Returns the well-known code for `code`, or `Other`.
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            -1000 => Self::Unknown,
+            -1001 => Self::Disconnected,
+            -1002 => Self::Unauthorized,
+            -1003 => Self::TooManyRequests,
+            -1006 => Self::UnexpectedResponse,
+            -1007 => Self::Timeout,
+            -1008 => Self::ServerBusy,
+            -1015 => Self::TooManyOrders,
+            -1021 => Self::InvalidTimestamp,
+            -1022 => Self::InvalidSignature,
+            -1100 => Self::IllegalParameter,
+            -1102 => Self::MandatoryParamMissing,
+            -1111 => Self::BadPrecision,
+            -1121 => Self::InvalidSymbol,
+            -2010 => Self::NewOrderRejected,
+            -2011 => Self::CancelRejected,
+            -2013 => Self::NoSuchOrder,
+            -2014 => Self::BadApiKeyFormat,
+            -2015 => Self::RejectedApiKey,
+            -2018 => Self::BalanceInsufficient,
+            -2019 => Self::MarginInsufficient,
+            -2021 => Self::WouldImmediatelyTrigger,
+            -2022 => Self::ReduceOnlyRejected,
+            -4003 => Self::QuantityNotPositive,
+            -4014 => Self::PriceNotOnTickSize,
+            -4164 => Self::MinNotional,
+            -5022 => Self::PostOnlyRejected,
+            other => Self::Other(other),
+        }
+    }
+
+    /// Returns the numeric code.
+    pub fn code(&self) -> i64 {
+        match self {
+            Self::Unknown => -1000,
+            Self::Disconnected => -1001,
+            Self::Unauthorized => -1002,
+            Self::TooManyRequests => -1003,
+            Self::UnexpectedResponse => -1006,
+            Self::Timeout => -1007,
+            Self::ServerBusy => -1008,
+            Self::TooManyOrders => -1015,
+            Self::InvalidTimestamp => -1021,
+            Self::InvalidSignature => -1022,
+            Self::IllegalParameter => -1100,
+            Self::MandatoryParamMissing => -1102,
+            Self::BadPrecision => -1111,
+            Self::InvalidSymbol => -1121,
+            Self::NewOrderRejected => -2010,
+            Self::CancelRejected => -2011,
+            Self::NoSuchOrder => -2013,
+            Self::BadApiKeyFormat => -2014,
+            Self::RejectedApiKey => -2015,
+            Self::BalanceInsufficient => -2018,
+            Self::MarginInsufficient => -2019,
+            Self::WouldImmediatelyTrigger => -2021,
+            Self::ReduceOnlyRejected => -2022,
+            Self::QuantityNotPositive => -4003,
+            Self::PriceNotOnTickSize => -4014,
+            Self::MinNotional => -4164,
+            Self::PostOnlyRejected => -5022,
+            Self::Other(code) => *code,
+        }
+    }
+
+    /// Returns true for rate limit errors, which go away by sending fewer requests.
+    pub fn is_rate_limit(&self) -> bool {
+        matches!(self, Self::TooManyRequests | Self::TooManyOrders)
+    }
+
+    /// Returns true for errors of the API key or signature, which no retry fixes.
+    pub fn is_auth(&self) -> bool {
+        matches!(self, Self::Unauthorized | Self::InvalidSignature | Self::BadApiKeyFormat | Self::RejectedApiKey)
+    }
+
+    /// Returns true when the account lacks the balance or margin for an order.
+    pub fn is_insufficient_funds(&self) -> bool {
+        matches!(self, Self::BalanceInsufficient | Self::MarginInsufficient)
+    }
+
+    /// Returns true when the order to cancel or query no longer exists (e.g. it filled).
+    pub fn is_unknown_order(&self) -> bool {
+        matches!(self, Self::CancelRejected | Self::NoSuchOrder)
+    }
+}
+
+/// An error returned by the Binance API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinanceApiError {
+    pub code: BinanceErrorCode,
+    pub msg: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: i64,
+    msg: String,
+}
+
+/// Prefix of formatted API errors, see `Display`.
+const ERROR_PREFIX: &str = "Binance API error ";
+
+impl BinanceApiError {
+    /// Parses an error body, `{"code":-2019,"msg":"Margin is insufficient."}`. `None` for other
+    /// bodies.
+    pub fn parse(body: &str) -> Option<Self> {
+        serde_json::from_str::<ErrorBody>(body).ok().map(Self::from_body)
+    }
+
+    /// Reads the `error` object of a WebSocket API response.
+    pub fn from_value(error: &serde_json::Value) -> Option<Self> {
+        serde_json::from_value::<ErrorBody>(error.clone()).ok().map(Self::from_body)
+    }
+
+    fn from_body(body: ErrorBody) -> Self {
+        Self { code: BinanceErrorCode::from_code(body.code), msg: body.msg }
+    }
+
+    /// Recovers the API error from the message of a failed request, which contains it formatted
+    /// by `Display` (or, in older messages, as the raw error body). `None` when the request failed
+    /// for another reason (e.g. the connection dropped).
+    pub fn from_error(message: &str) -> Option<Self> {
+        if let Some(start) = message.find(ERROR_PREFIX) {
+            let rest = &message[start + ERROR_PREFIX.len()..];
+            let (code, msg) = rest.split_once(": ")?;
+            return Some(Self { code: BinanceErrorCode::from_code(code.parse().ok()?), msg: msg.to_string() });
+        }
+        let start = message.find("{\"code\"")?;
+        let body = serde_json::Deserializer::from_str(&message[start..]).into_iter::<ErrorBody>().next()?.ok()?;
+        Some(Self::from_body(body))
+    }
+}
+
+impl fmt::Display for BinanceApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}: {}", ERROR_PREFIX, self.code.code(), self.msg)
+    }
+}
+
+impl std::error::Error for BinanceApiError {}
@@ -0,0 +1,68 @@
+// src/rest_api/http.rs
+
+//! This module configures the HTTP client behind `RestClient`: connect and request timeouts, so a
+//! flaky network fails a request instead of hanging it; connection pool settings; an optional
+//! proxy (`http://`, `https://`, `socks5://` or `socks5h://` URL, with credentials in the URL if
+//! needed); and the user agent.
+
+use std::time::Duration;
+
+use reqwest::{Client, Proxy};
+
+/// Time allowed to establish a connection.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time allowed for a whole request, from connecting to reading the response body.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long an idle pooled connection is kept open.
+pub const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Settings of the HTTP client of the REST API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpClientConfig {
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    pub pool_idle_timeout: Duration,
+    pub pool_max_idle_per_host: Option<usize>, // `None` keeps any number of idle connections
+    pub proxy: Option<String>, // Proxy URL every request goes through; `None` connects directly
+    pub user_agent: String,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            pool_idle_timeout: DEFAULT_POOL_IDLE_TIMEOUT,
+            pool_max_idle_per_host: None,
+            proxy: None,
+            user_agent: concat!("trading_bot/", env!("CARGO_PKG_VERSION")).to_string(),
+        }
+    }
+}
+
+/// Parses a proxy URL, accepting the `http`, `https`, `socks5` and `socks5h` schemes.
+pub fn parse_proxy(url: &str) -> Result<Proxy, String> {
+    let scheme = url.split("://").next().unwrap_or_default().to_lowercase();
+    if !matches!(scheme.as_str(), "http" | "https" | "socks5" | "socks5h") || !url.contains("://") {
+        return Err(format!("Unsupported proxy '{}' (expected an http://, https://, socks5:// or socks5h:// URL)", url));
+    }
+    Proxy::all(url).map_err(|e| format!("Invalid proxy '{}': {}", url, e))
+}
+
+impl HttpClientConfig {
+    /// Builds an HTTP client with these settings.
+    pub fn build_client(&self) -> Result<Client, String> {
+        let mut builder = Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.request_timeout)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .user_agent(self.user_agent.clone());
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(parse_proxy(proxy)?);
+        }
+        builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))
+    }
+}
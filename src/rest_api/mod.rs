@@ -4,14 +4,37 @@
 //! handling generic HTTP REST API requests. It provides low-level
 //! functionalities for signed and unsigned GET and POST requests,
 //! managing connections, authentication (signing), and basic request/response dispatch.
+//! Requests are paced by the client's `ratelimit::RateLimiter`; timeouts, pooling and proxy of the
+//! underlying HTTP client are set with `http::HttpClientConfig`.
 
-use reqwest::{Client, Url};
+pub mod error;
+pub mod http;
+pub mod ratelimit;
+
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, Response, StatusCode, Url};
 use serde_json::Value;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
 use hex::encode;
-use std::time::{SystemTime, UNIX_EPOCH};
-use log::debug; // For logging
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn}; // For logging
+
+pub use error::{BinanceApiError, BinanceErrorCode};
+use ratelimit::{order_count, request_weight, RateLimiter};
+
+/// Default `recvWindow` of signed requests: how long after its timestamp a request stays valid.
+pub const DEFAULT_RECV_WINDOW_MS: u64 = 5000;
+/// Largest `recvWindow` Binance accepts.
+pub const MAX_RECV_WINDOW_MS: u64 = 60_000;
+
+/// Adds `recvWindow` to the parameters of a signed request unless the caller set its own.
+pub fn with_recv_window(params: &mut Vec<(String, String)>, recv_window_ms: u64) {
+    if !params.iter().any(|(k, _)| k == "recvWindow") {
+        params.push(("recvWindow".to_string(), recv_window_ms.to_string()));
+    }
+}
 
 /// Represents the Binance REST API Client.
 /// This client handles REST API calls.
@@ -20,6 +43,8 @@ pub struct RestClient {
     secret_key: String,
     http_client: Client,
     rest_base_url: String,
+    recv_window_ms: u64, // Added to every signed request that does not set its own `recvWindow`
+    rate_limiter: Arc<RateLimiter>, // Request weight and order budgets; may be shared with other clients on the same IP
 }
 
 impl RestClient {
@@ -42,9 +67,91 @@ impl RestClient {
             secret_key,
             http_client: Client::new(),
             rest_base_url,
+            recv_window_ms: DEFAULT_RECV_WINDOW_MS,
+            rate_limiter: Arc::new(RateLimiter::default()),
+        }
+    }
+
+    /// Replaces the HTTP client, e.g. with one built from an `http::HttpClientConfig`.
+    pub fn with_http_client(mut self, http_client: Client) -> Self {
+        self.http_client = http_client;
+        self
+    }
+
+    /// Replaces the client's rate limiter, e.g. with one shared by every client using the same IP.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Returns the rate limiter pacing the client's requests.
+    pub fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    /// Records the rate limit usage reported by a response, backs off after a 429 or 418, and
+    /// returns the JSON body of a successful response. Binance error bodies are formatted as a
+    /// `BinanceApiError`.
+    async fn read_response(&self, response: Response, method: &str) -> Result<Value, String> {
+        let now = ratelimit::now_ms();
+        let headers = response.headers().iter()
+            .filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?)));
+        self.rate_limiter.update_from_headers(headers, now);
+
+        if response.status().is_success() {
+            response.json::<Value>()
+                .await
+                .map_err(|e| format!("Failed to parse JSON REST response: {}", e))
+        } else {
+            let status = response.status();
+            let retry_after = response.headers().get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok()?.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            let text = response.text().await.unwrap_or_else(|_| "No response body".to_string());
+            if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::IM_A_TEAPOT {
+                warn!("REST API {} request rejected with status {}, backing off: {}", method, status, text);
+                self.rate_limiter.record_rejection(status.as_u16(), retry_after, ratelimit::parse_ban_until(&text), now);
+            }
+            match BinanceApiError::parse(&text) {
+                Some(api_error) => Err(format!("REST API {} request failed with status {}: {}", method, status, api_error)),
+                None => Err(format!("REST API {} request failed with status {}: {}", method, status, text)),
+            }
         }
     }
 
+    /// Sets the `recvWindow` of signed requests (`DEFAULT_RECV_WINDOW_MS` by default). A request
+    /// passing its own `recvWindow` parameter keeps it.
+    pub fn with_recv_window(mut self, recv_window_ms: u64) -> Self {
+        self.recv_window_ms = recv_window_ms;
+        self
+    }
+
+    /// Returns the `recvWindow` of signed requests, in milliseconds.
+    pub fn recv_window_ms(&self) -> u64 {
+        self.recv_window_ms
+    }
+
+    /// Builds the signed query string of a request: its parameters, `recvWindow` and `timestamp`,
+    /// followed by the signature.
+    fn signed_query(&self, params: &[(&str, &str)]) -> Result<String, String> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| format!("Failed to get timestamp: {}", e))?
+            .as_millis()
+            .to_string();
+
+        let mut pairs: Vec<(String, String)> = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        with_recv_window(&mut pairs, self.recv_window_ms);
+        pairs.push(("timestamp".to_string(), timestamp));
+
+        let query_string = pairs.iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<String>>()
+            .join("&");
+        let signature = self.sign_payload(&query_string);
+        Ok(format!("{}&signature={}", query_string, signature))
+    }
+
     /// Generates a Binance API signature using HMAC SHA256.
     ///
     /// # Arguments
@@ -62,7 +169,8 @@ impl RestClient {
     ///
     /// # Arguments
     /// * `endpoint` - The API endpoint (e.g., "/fapi/v2/account"). This should include the API version.
-    /// * `params` - Query parameters as a vector of (key, value) tuples.
+    /// * `params` - Query parameters as a vector of (key, value) tuples. A `recvWindow` parameter
+    ///   overrides the client's.
     ///
     /// # Returns
     /// A `Result` containing the parsed JSON `Value` on success, or a `String` error.
@@ -70,39 +178,17 @@ impl RestClient {
         let mut url = Url::parse(&format!("{}{}", self.rest_base_url, endpoint))
             .map_err(|e| format!("Failed to parse URL: {}", e))?;
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| format!("Failed to get timestamp: {}", e))?
-            .as_millis()
-            .to_string();
-
-        let mut query_pairs: Vec<String> = params.iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect();
-        query_pairs.push(format!("timestamp={}", timestamp));
-
-        let query_string = query_pairs.join("&");
-        let signature = self.sign_payload(&query_string);
-
-        url.set_query(Some(&format!("{}&signature={}", query_string, signature)));
+        url.set_query(Some(&self.signed_query(&params)?));
 
         debug!("Signed REST GET request URL: {}", url);
 
+        self.rate_limiter.acquire(request_weight(endpoint, &params), 0).await?;
         let response = self.http_client.get(url)
             .header("X-MBX-APIKEY", &self.api_key)
             .send()
             .await
             .map_err(|e| format!("Failed to send REST GET request: {}", e))?;
-
-        if response.status().is_success() {
-            response.json::<Value>()
-                .await
-                .map_err(|e| format!("Failed to parse JSON REST response: {}", e))
-        } else {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_else(|_| "No response body".to_string());
-            Err(format!("REST API GET request failed with status {}: {}", status, text))
-        }
+        self.read_response(response, "GET").await
     }
 
     /// Makes an unsigned GET request to the Binance REST API.
@@ -128,20 +214,12 @@ impl RestClient {
 
         debug!("Unsigned REST GET request URL: {}", url);
 
+        self.rate_limiter.acquire(request_weight(endpoint, &params), 0).await?;
         let response = self.http_client.get(url)
             .send()
             .await
             .map_err(|e| format!("Failed to send REST GET request: {}", e))?;
-
-        if response.status().is_success() {
-            response.json::<Value>()
-                .await
-                .map_err(|e| format!("Failed to parse JSON REST response: {}", e))
-        } else {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_else(|_| "No response body".to_string());
-            Err(format!("REST API GET request failed with status {}: {}", status, text))
-        }
+        self.read_response(response, "GET").await
     }
 
     /// Makes a signed POST request to the Binance REST API.
@@ -150,46 +228,25 @@ impl RestClient {
     /// # Arguments
     /// * `endpoint` - The API endpoint (e.g., "/fapi/v1/order"). This should include the API version.
     /// * `params` - Form parameters as a vector of (key, value) tuples. These will be sent as query parameters for signing.
+    ///   A `recvWindow` parameter overrides the client's.
     ///
     /// # Returns
     /// A `Result` containing the parsed JSON `Value` on success, or a `String` error.
     pub async fn post_signed_rest_request(&self, endpoint: &str, params: Vec<(&str, &str)>) -> Result<Value, String> {
         let url = format!("{}{}", self.rest_base_url, endpoint);
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| format!("Failed to get timestamp: {}", e))?
-            .as_millis()
-            .to_string();
-
-        let mut query_pairs: Vec<String> = params.iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect();
-        query_pairs.push(format!("timestamp={}", timestamp));
-
-        let query_string = query_pairs.join("&");
-        let signature = self.sign_payload(&query_string);
-
         // For POST requests, parameters (including timestamp and signature) are typically sent as query parameters
-        let final_url = format!("{}?{}&signature={}", url, query_string, signature);
+        let final_url = format!("{}?{}", url, self.signed_query(&params)?);
 
         debug!("Signed REST POST request URL: {}", final_url);
 
+        self.rate_limiter.acquire(request_weight(endpoint, &params), order_count(endpoint, &params)).await?;
         let response = self.http_client.post(&final_url)
             .header("X-MBX-APIKEY", &self.api_key)
             .send()
             .await
             .map_err(|e| format!("Failed to send REST POST request: {}", e))?;
-
-        if response.status().is_success() {
-            response.json::<Value>()
-                .await
-                .map_err(|e| format!("Failed to parse JSON REST response: {}", e))
-        } else {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_else(|_| "No response body".to_string());
-            Err(format!("REST API POST request failed with status {}: {}", status, text))
-        }
+        self.read_response(response, "POST").await
     }
 
     /// Makes an unsigned POST request to the Binance REST API.
@@ -217,19 +274,11 @@ impl RestClient {
 
         debug!("Unsigned REST POST request URL: {}", final_url);
 
+        self.rate_limiter.acquire(request_weight(endpoint, &params), 0).await?;
         let response = self.http_client.post(&final_url)
             .send()
             .await
             .map_err(|e| format!("Failed to send REST POST request: {}", e))?;
-
-        if response.status().is_success() {
-            response.json::<Value>()
-                .await
-                .map_err(|e| format!("Failed to parse JSON REST response: {}", e))
-        } else {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_else(|_| "No response body".to_string());
-            Err(format!("REST API POST request failed with status {}: {}", status, text))
-        }
+        self.read_response(response, "POST").await
     }
 }
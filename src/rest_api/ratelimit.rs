@@ -0,0 +1,338 @@
+// src/rest_api/ratelimit.rs
+
+//! This module keeps REST requests within Binance's rate limits on the client side, before the
+//! exchange starts rejecting them (and eventually bans the IP).
+//!
+//! Every response reports the weight used by the IP (`X-MBX-USED-WEIGHT-1M`) and, on order
+//! placement, the orders sent by the account (`X-MBX-ORDER-COUNT-10S`, `X-MBX-ORDER-COUNT-1M`).
+//! The limiter keeps a budget per limit and window, aligned to the exchange's clock windows, adds
+//! the estimated weight of each request as it is sent and catches up with the reported usage as
+//! responses arrive, which also accounts for other processes sharing the IP or the account.
+//! A request that does not fit in a budget waits, in order with the other waiting requests, until
+//! the window resets.
+//!
+//! When the exchange rejects a request anyway, the limiter backs off: after a 429 (too many
+//! requests) every request waits for the `Retry-After` the exchange sent; after a 418 (the IP is
+//! banned for repeating 429s) every request fails right away until the ban is over, since more
+//! requests would only extend it. `notify::watch_rest_ban` raises a critical notification.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+/// Request weight per minute of Binance Futures.
+pub const DEFAULT_WEIGHT_PER_MINUTE: u32 = 2400;
+/// Orders per 10 seconds of Binance Futures.
+pub const DEFAULT_ORDERS_PER_10S: u32 = 300;
+/// Orders per minute of Binance Futures.
+pub const DEFAULT_ORDERS_PER_MINUTE: u32 = 1200;
+/// Back-off after a 429 response without a `Retry-After` header.
+pub const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+/// Cooldown after a 418 response without a ban expiry; Binance bans for 2 minutes at least.
+pub const DEFAULT_BAN_COOLDOWN: Duration = Duration::from_secs(120);
+
+/// What a rate limit counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    RequestWeight, // Weight of every request, per IP
+    Orders,        // Orders placed, per account
+}
+
+/// A limit on the usage of one kind within a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitBudget {
+    pub kind: LimitKind,
+    pub window: Duration,
+    pub limit: u32,
+}
+
+/// Returns the rate limits of Binance Futures: 2400 weight per minute, 300 orders per 10 seconds
+/// and 1200 orders per minute.
+pub fn default_rate_limits() -> Vec<RateLimitBudget> {
+    vec![
+        RateLimitBudget { kind: LimitKind::RequestWeight, window: Duration::from_secs(60), limit: DEFAULT_WEIGHT_PER_MINUTE },
+        RateLimitBudget { kind: LimitKind::Orders, window: Duration::from_secs(10), limit: DEFAULT_ORDERS_PER_10S },
+        RateLimitBudget { kind: LimitKind::Orders, window: Duration::from_secs(60), limit: DEFAULT_ORDERS_PER_MINUTE },
+    ]
+}
+
+/// Parses the interval suffix of a usage header (`1M`, `10S`, `1H`, `1D`).
+pub fn parse_interval(interval: &str) -> Option<Duration> {
+    let interval = interval.to_uppercase();
+    let (num, unit) = interval.split_at(interval.len().checked_sub(1)?);
+    let unit_secs = match unit {
+        "S" => 1,
+        "M" => 60,
+        "H" => 3_600,
+        "D" => 86_400,
+        _ => return None,
+    };
+    Some(Duration::from_secs(num.parse::<u64>().ok()? * unit_secs))
+}
+
+/// Parses a usage header (`X-MBX-USED-WEIGHT-1M: 120`, `X-MBX-ORDER-COUNT-10S: 3`) into the
+/// kind, window and usage it reports. `None` for other headers.
+pub fn parse_usage_header(name: &str, value: &str) -> Option<(LimitKind, Duration, u32)> {
+    let name = name.to_lowercase();
+    let (kind, interval) = if let Some(interval) = name.strip_prefix("x-mbx-used-weight-") {
+        (LimitKind::RequestWeight, interval)
+    } else if let Some(interval) = name.strip_prefix("x-mbx-order-count-") {
+        (LimitKind::Orders, interval)
+    } else {
+        return None;
+    };
+    Some((kind, parse_interval(interval)?, value.trim().parse().ok()?))
+}
+
+/// Returns the estimated weight of a REST request, from Binance Futures' documented weights.
+/// Endpoints not listed weigh 1.
+pub fn request_weight(endpoint: &str, params: &[(&str, &str)]) -> u32 {
+    let param = |name: &str| params.iter().find(|(k, _)| *k == name).map(|(_, v)| *v);
+    let has_symbol = param("symbol").is_some() || param("pair").is_some();
+    let limit = |default: u32| param("limit").and_then(|limit| limit.parse::<u32>().ok()).unwrap_or(default);
+    let path = endpoint.trim_end_matches('/');
+    let name = path.rsplit('/').next().unwrap_or_default();
+    match name {
+        "klines" | "continuousKlines" | "indexPriceKlines" | "markPriceKlines" | "premiumIndexKlines" => match limit(500) {
+            0..=99 => 1,
+            100..=499 => 2,
+            500..=1000 => 5,
+            _ => 10,
+        },
+        "depth" => match limit(500) {
+            0..=50 => 2,
+            51..=100 => 5,
+            101..=500 => 10,
+            _ => 20,
+        },
+        "aggTrades" | "historicalTrades" | "commissionRate" => 20,
+        "trades" | "allOrders" | "userTrades" | "account" | "balance" | "positionRisk" | "batchOrders" | "accountConfig" => 5,
+        "income" => 30,
+        "24hr" | "openOrders" if !has_symbol => 40,
+        "bookTicker" if !has_symbol => 5,
+        "price" if !has_symbol => 2,
+        "premiumIndex" if !has_symbol => 10,
+        _ => 1,
+    }
+}
+
+/// Returns the number of orders a REST request places: one for a new order, one per order of a
+/// batch.
+pub fn order_count(endpoint: &str, params: &[(&str, &str)]) -> u32 {
+    match endpoint.trim_end_matches('/').rsplit('/').next().unwrap_or_default() {
+        "order" => 1,
+        "batchOrders" => params.iter()
+            .find(|(k, _)| *k == "batchOrders")
+            .and_then(|(_, orders)| serde_json::from_str::<Vec<serde_json::Value>>(orders).ok())
+            .map_or(1, |orders| orders.len() as u32),
+        _ => 0,
+    }
+}
+
+/// Parses the expiry of an IP ban from the message of a 418 response, e.g.
+/// `{"code":-1003,"msg":"Way too many requests; IP(1.2.3.4) banned until 1700000000000. ..."}`.
+pub fn parse_ban_until(body: &str) -> Option<u64> {
+    let rest = &body[body.find("banned until ")? + "banned until ".len()..];
+    let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// Why REST requests are held back after a rejection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    RateLimited, // 429: requests wait until `until_ms`
+    Banned,      // 418: requests fail until `until_ms`
+}
+
+/// A back-off in force.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackoffState {
+    pub kind: Backoff,
+    pub until_ms: u64,
+}
+
+/// Usage of one budget in its current window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitUsage {
+    pub kind: LimitKind,
+    pub window: Duration,
+    pub limit: u32,
+    pub used: u32,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    budget: RateLimitBudget,
+    window_index: u64, // Start of the window the usage belongs to, in windows since the epoch
+    used: u32,
+}
+
+impl BudgetState {
+    fn window_ms(&self) -> u64 {
+        (self.budget.window.as_millis() as u64).max(1)
+    }
+
+    /// Starts a new window when `now_ms` is past the current one.
+    fn roll(&mut self, now_ms: u64) {
+        let index = now_ms / self.window_ms();
+        if index != self.window_index {
+            self.window_index = index;
+            self.used = 0;
+        }
+    }
+
+    /// Returns how long after `now_ms` the current window ends.
+    fn until_reset(&self, now_ms: u64) -> Duration {
+        Duration::from_millis((self.window_index + 1) * self.window_ms() - now_ms)
+    }
+}
+
+/// Client-side rate limiter shared by the requests of a `RestClient`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    budgets: Mutex<Vec<BudgetState>>,
+    backoff: Mutex<Option<BackoffState>>, // Set by a 429 or 418 response
+    queue: tokio::sync::Mutex<()>, // Held while a request waits for its budget, so requests are sent in order
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(default_rate_limits())
+    }
+}
+
+impl RateLimiter {
+    /// Creates a limiter enforcing `budgets`.
+    pub fn new(budgets: Vec<RateLimitBudget>) -> Self {
+        let budgets = budgets.into_iter()
+            .map(|budget| BudgetState { budget, window_index: 0, used: 0 })
+            .collect();
+        Self { budgets: Mutex::new(budgets), backoff: Mutex::new(None), queue: tokio::sync::Mutex::new(()) }
+    }
+
+    /// Records a 429 or 418 response received at `now_ms`, backing off for `retry_after` (the
+    /// `Retry-After` header) or until `banned_until_ms` (from the response body). A back-off never
+    /// shortens one already in force, and a ban is never downgraded by a 429. Other statuses are
+    /// ignored.
+    pub fn record_rejection(&self, status: u16, retry_after: Option<Duration>, banned_until_ms: Option<u64>, now_ms: u64) {
+        let (kind, until_ms) = match status {
+            429 => (Backoff::RateLimited, now_ms + retry_after.unwrap_or(DEFAULT_RETRY_AFTER).as_millis() as u64),
+            418 => {
+                let until_ms = banned_until_ms
+                    .or_else(|| retry_after.map(|wait| now_ms + wait.as_millis() as u64))
+                    .unwrap_or(now_ms + DEFAULT_BAN_COOLDOWN.as_millis() as u64);
+                (Backoff::Banned, until_ms)
+            },
+            _ => return,
+        };
+        let mut backoff = self.backoff.lock().unwrap();
+        let current = backoff.filter(|state| state.until_ms > now_ms);
+        *backoff = Some(match current {
+            Some(state) if state.kind == Backoff::Banned && kind == Backoff::RateLimited => state,
+            Some(state) if state.kind == kind && state.until_ms >= until_ms => state,
+            _ => BackoffState { kind, until_ms },
+        });
+    }
+
+    /// Returns the back-off in force at `now_ms`, if any.
+    pub fn backoff(&self, now_ms: u64) -> Option<BackoffState> {
+        self.backoff.lock().unwrap().filter(|state| state.until_ms > now_ms)
+    }
+
+    /// Sets the limit of the budget of `kind` over `window`, adding the budget if there is none,
+    /// e.g. from the limits the exchange reports for the account.
+    pub fn set_limit(&self, kind: LimitKind, window: Duration, limit: u32) {
+        let mut budgets = self.budgets.lock().unwrap();
+        match budgets.iter_mut().find(|state| state.budget.kind == kind && state.budget.window == window) {
+            Some(state) => state.budget.limit = limit,
+            None => budgets.push(BudgetState { budget: RateLimitBudget { kind, window, limit }, window_index: 0, used: 0 }),
+        }
+    }
+
+    /// Reserves `weight` and `orders` for a request sent at `now_ms` if every budget has room for
+    /// them, and returns `Duration::ZERO`. Otherwise reserves nothing and returns how long to wait
+    /// for the full budgets to reset. A request larger than a whole budget is let through once
+    /// its window is empty.
+    pub fn reserve(&self, weight: u32, orders: u32, now_ms: u64) -> Duration {
+        let mut budgets = self.budgets.lock().unwrap();
+        let mut wait = Duration::ZERO;
+        for state in budgets.iter_mut() {
+            state.roll(now_ms);
+            let cost = match state.budget.kind {
+                LimitKind::RequestWeight => weight,
+                LimitKind::Orders => orders,
+            };
+            if cost > 0 && state.used > 0 && state.used + cost > state.budget.limit {
+                wait = wait.max(state.until_reset(now_ms));
+            }
+        }
+        if wait.is_zero() {
+            for state in budgets.iter_mut() {
+                state.used += match state.budget.kind {
+                    LimitKind::RequestWeight => weight,
+                    LimitKind::Orders => orders,
+                };
+            }
+        }
+        wait
+    }
+
+    /// Waits until a request of `weight` placing `orders` fits in every budget, behind the
+    /// requests already waiting, and until a 429 back-off is over. Fails while the IP is banned.
+    pub async fn acquire(&self, weight: u32, orders: u32) -> Result<(), String> {
+        let _turn = self.queue.lock().await;
+        loop {
+            let now = now_ms();
+            match self.backoff(now) {
+                Some(BackoffState { kind: Backoff::Banned, until_ms }) => {
+                    return Err(format!("REST requests halted: the IP is banned by Binance for another {}s", (until_ms - now).div_ceil(1000)));
+                },
+                Some(BackoffState { kind: Backoff::RateLimited, until_ms }) => {
+                    warn!("REST requests rate limited by Binance, waiting {:.1}s", (until_ms - now) as f64 / 1000.0);
+                    tokio::time::sleep(Duration::from_millis(until_ms - now)).await;
+                    continue;
+                },
+                None => {},
+            }
+            let wait = self.reserve(weight, orders, now);
+            if wait.is_zero() {
+                return Ok(());
+            }
+            info!("REST rate limit budget used up, waiting {:.1}s", wait.as_secs_f64());
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Catches up with the usage the exchange reported in the headers of a response received at
+    /// `now_ms`. Reported usage only ever raises the local count, which already includes requests
+    /// still in flight.
+    pub fn update_from_headers<'a>(&self, headers: impl IntoIterator<Item = (&'a str, &'a str)>, now_ms: u64) {
+        let mut budgets = self.budgets.lock().unwrap();
+        for (kind, window, used) in headers.into_iter().filter_map(|(name, value)| parse_usage_header(name, value)) {
+            for state in budgets.iter_mut().filter(|state| state.budget.kind == kind && state.budget.window == window) {
+                state.roll(now_ms);
+                state.used = state.used.max(used);
+            }
+        }
+    }
+
+    /// Returns the usage of every budget at `now_ms`.
+    pub fn usage(&self, now_ms: u64) -> Vec<RateLimitUsage> {
+        let mut budgets = self.budgets.lock().unwrap();
+        budgets.iter_mut()
+            .map(|state| {
+                state.roll(now_ms);
+                RateLimitUsage { kind: state.budget.kind, window: state.budget.window, limit: state.budget.limit, used: state.used }
+            })
+            .collect()
+    }
+}
+
+/// Returns the current time in milliseconds since the epoch.
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,627 @@
+// src/risk/mod.rs
+
+//! This module defines the pluggable position sizing and pre-trade risk policies used by the
+//! order executor (the webhook handler), so custom policies can be supplied when embedding the
+//! crate without changing the executor.
+//!
+//! A `SizingPolicy` turns the account equity, price and (optionally) stop distance or ATR into an
+//! order quantity. Built-ins: `FixedQuantity`, `FixedNotional`, `RiskPercent`, `AtrRisk`, `Kelly`,
+//! `VolatilityTarget`. `PositionSizer` selects one of them from configuration (`SIZING` for the
+//! webhook, the `sizing` setting of the backtest, which also sizes `ema_pullback` live), written as
+//! the policy name followed by its parameters, e.g. `risk_percent:0.01` (see `PositionSizer::parse`).
+//!
+//! A `RiskPolicy` accepts or rejects an order that opens or increases exposure. Built-ins:
+//! `MaxExposure`, `ExposureLimits` (the number of open positions, the total notional and the
+//! notional per quote asset, configured for the webhook), `DailyLossLimit`, `Cooldown`, and
+//! `schedule::TradingSchedule` (entries only during configured sessions), `FundingBlackout` (no
+//! entries around the 8-hourly funding settlements). Realized PnL feeding the daily loss limit and the
+//! cooldown is tracked in `RiskState`, updated from `ORDER_TRADE_UPDATE` events.
+//!
+//! The `DailyLossBreaker` is the session-wide counterpart of `DailyLossLimit`: it counts the
+//! unrealized PnL of the open positions too (its change since the first check of the UTC day), and
+//! once the loss reaches its limit it keeps rejecting entries for the rest of the day, even if the
+//! positions recover, optionally flattening them. It resets at 00:00 UTC.
+//!
+//! The `RiskState` (the day's PnL, the last loss and the breaker's day) is saved to the
+//! `recovery::StateStore` of the policies whenever a fill or a breaker check changes it, so a
+//! restart neither forgets the day's losses nor re-arms a tripped breaker.
+
+pub mod schedule;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::order::{ExecutionType, OrderSide};
+use crate::recovery::StateStore;
+use crate::session::FUNDING_HOURS_UTC;
+use crate::streams::FuturesOrderUpdate;
+
+/// Inputs to a sizing decision.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SizingContext {
+    pub symbol: String,
+    pub price: f64, // Expected entry price
+    pub equity: f64, // Account equity in the quote asset
+    pub stop_price: Option<f64>, // Protective stop, when the signal carries one
+    pub atr: Option<f64>, // Current ATR of the instrument, when available
+}
+
+impl SizingContext {
+    fn stop_distance(&self) -> Option<f64> {
+        self.stop_price.map(|stop| (self.price - stop).abs()).filter(|d| *d > 0.0)
+    }
+}
+
+/// Decides the quantity (in the base asset) of an order that opens a position.
+/// The executor rounds the result to the symbol's step size and checks the minimum quantity.
+pub trait SizingPolicy: Send + Sync {
+    /// Short name used in logs.
+    fn name(&self) -> &str;
+    /// Returns the base asset quantity to trade.
+    fn quantity(&self, ctx: &SizingContext) -> Result<f64, String>;
+}
+
+/// A fixed quantity in the base asset.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FixedQuantity(pub f64);
+
+impl SizingPolicy for FixedQuantity {
+    fn name(&self) -> &str {
+        "fixed_quantity"
+    }
+
+    fn quantity(&self, _ctx: &SizingContext) -> Result<f64, String> {
+        Ok(self.0)
+    }
+}
+
+/// A fixed notional in the quote asset, e.g. $500 per trade.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FixedNotional(pub f64);
+
+impl SizingPolicy for FixedNotional {
+    fn name(&self) -> &str {
+        "fixed_notional"
+    }
+
+    fn quantity(&self, ctx: &SizingContext) -> Result<f64, String> {
+        if ctx.price <= 0.0 {
+            return Err(format!("Invalid price {} for fixed notional sizing.", ctx.price));
+        }
+        Ok(self.0 / ctx.price)
+    }
+}
+
+/// Risks a fraction of equity between the entry and the stop: quantity = equity * risk / |entry - stop|.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RiskPercent {
+    pub risk_fraction: f64, // e.g. 0.01 to risk 1% of equity per trade
+}
+
+impl SizingPolicy for RiskPercent {
+    fn name(&self) -> &str {
+        "risk_percent"
+    }
+
+    fn quantity(&self, ctx: &SizingContext) -> Result<f64, String> {
+        let distance = ctx.stop_distance().ok_or("Risk-percent sizing needs a stop price different from the entry.")?;
+        Ok(ctx.equity * self.risk_fraction / distance)
+    }
+}
+
+/// Risks a fraction of equity over a stop placed a multiple of ATR away:
+/// quantity = equity * risk / (atr_multiple * ATR).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AtrRisk {
+    pub risk_fraction: f64,
+    pub atr_multiple: f64, // Stop distance in ATRs, e.g. 2.0
+}
+
+/// ATR sizing used by the webhook handler when no sizing policy is configured: 1% of equity over a 2 ATR stop.
+pub const DEFAULT_ATR_RISK: AtrRisk = AtrRisk { risk_fraction: 0.01, atr_multiple: 2.0 };
+
+impl AtrRisk {
+    /// Returns the stop of an entry at `entry`: `atr_multiple` ATRs below it for a buy, above it for a sell.
+    pub fn stop_price(&self, side: OrderSide, entry: f64, atr: f64) -> f64 {
+        match side {
+            OrderSide::Buy => entry - self.atr_multiple * atr,
+            OrderSide::Sell => entry + self.atr_multiple * atr,
+        }
+    }
+}
+
+impl SizingPolicy for AtrRisk {
+    fn name(&self) -> &str {
+        "atr_risk"
+    }
+
+    fn quantity(&self, ctx: &SizingContext) -> Result<f64, String> {
+        let atr = ctx.atr.filter(|a| *a > 0.0).ok_or("ATR sizing needs a positive ATR.")?;
+        Ok(ctx.equity * self.risk_fraction / (atr * self.atr_multiple))
+    }
+}
+
+/// Fractional Kelly sizing from the strategy's historical win rate and payoff ratio.
+/// The Kelly fraction of equity is committed as notional, capped at `max_fraction`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Kelly {
+    pub win_rate: f64, // e.g. 0.55
+    pub payoff_ratio: f64, // Average win / average loss
+    pub kelly_multiplier: f64, // e.g. 0.5 for half-Kelly
+    pub max_fraction: f64, // Cap on the fraction of equity, e.g. 0.25
+}
+
+impl Kelly {
+    /// Returns the (scaled, capped) fraction of equity to commit; zero when the edge is negative.
+    pub fn fraction(&self) -> f64 {
+        if self.payoff_ratio <= 0.0 {
+            return 0.0;
+        }
+        let full = self.win_rate - (1.0 - self.win_rate) / self.payoff_ratio;
+        (full * self.kelly_multiplier).clamp(0.0, self.max_fraction)
+    }
+}
+
+impl SizingPolicy for Kelly {
+    fn name(&self) -> &str {
+        "kelly"
+    }
+
+    fn quantity(&self, ctx: &SizingContext) -> Result<f64, String> {
+        if ctx.price <= 0.0 {
+            return Err(format!("Invalid price {} for Kelly sizing.", ctx.price));
+        }
+        let fraction = self.fraction();
+        if fraction <= 0.0 {
+            return Err("Kelly sizing: no positive edge, not trading.".to_string());
+        }
+        Ok(ctx.equity * fraction / ctx.price)
+    }
+}
+
+/// Sizes positions so that a move of one ATR changes the equity by `target_fraction`, i.e. the
+/// position's volatility is a constant share of equity: quantity = equity * target / ATR. The
+/// notional is capped at `max_leverage` times the equity for very quiet markets.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VolatilityTarget {
+    pub target_fraction: f64, // e.g. 0.005 for 0.5% of equity per ATR
+    pub max_leverage: f64,
+}
+
+impl SizingPolicy for VolatilityTarget {
+    fn name(&self) -> &str {
+        "volatility_target"
+    }
+
+    fn quantity(&self, ctx: &SizingContext) -> Result<f64, String> {
+        let atr = ctx.atr.filter(|a| *a > 0.0).ok_or("Volatility-target sizing needs a positive ATR.")?;
+        if ctx.price <= 0.0 {
+            return Err(format!("Invalid price {} for volatility-target sizing.", ctx.price));
+        }
+        Ok((ctx.equity * self.target_fraction / atr).min(ctx.equity * self.max_leverage / ctx.price))
+    }
+}
+
+/// A built-in sizing policy selected by configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionSizer {
+    FixedQuantity(FixedQuantity),
+    FixedNotional(FixedNotional),
+    RiskPercent(RiskPercent),
+    AtrRisk(AtrRisk),
+    Kelly(Kelly),
+    VolatilityTarget(VolatilityTarget),
+}
+
+impl PositionSizer {
+    /// Parses a policy name followed by its parameters, separated by colons:
+    ///
+    /// * `fixed_quantity:<base quantity>`, e.g. `fixed_quantity:0.01`
+    /// * `fixed_notional:<quote amount>`, e.g. `fixed_notional:500`
+    /// * `risk_percent:<risk fraction>`, e.g. `risk_percent:0.01`
+    /// * `atr_risk:<risk fraction>:<ATR multiple>`, e.g. `atr_risk:0.01:2`
+    /// * `kelly:<win rate>:<payoff ratio>:<multiplier>:<max fraction>`, e.g. `kelly:0.55:1.5:0.5:0.25`
+    /// * `volatility_target:<fraction per ATR>:<max leverage>`, e.g. `volatility_target:0.005:3`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.trim().split(':');
+        let name = parts.next().unwrap_or_default().to_lowercase();
+        let values = parts.map(|p| p.trim().parse::<f64>().ok().filter(|v| v.is_finite() && *v > 0.0))
+            .collect::<Option<Vec<f64>>>()
+            .ok_or_else(|| format!("Invalid sizing '{}': parameters must be positive numbers", spec))?;
+        let sizer = match (name.as_str(), values.as_slice()) {
+            ("fixed_quantity", &[quantity]) => Self::FixedQuantity(FixedQuantity(quantity)),
+            ("fixed_notional", &[notional]) => Self::FixedNotional(FixedNotional(notional)),
+            ("risk_percent", &[risk_fraction]) => Self::RiskPercent(RiskPercent { risk_fraction }),
+            ("atr_risk", &[risk_fraction, atr_multiple]) => Self::AtrRisk(AtrRisk { risk_fraction, atr_multiple }),
+            ("kelly", &[win_rate, payoff_ratio, kelly_multiplier, max_fraction]) => Self::Kelly(Kelly { win_rate, payoff_ratio, kelly_multiplier, max_fraction }),
+            ("volatility_target", &[target_fraction, max_leverage]) => Self::VolatilityTarget(VolatilityTarget { target_fraction, max_leverage }),
+            ("fixed_quantity" | "fixed_notional" | "risk_percent" | "atr_risk" | "kelly" | "volatility_target", _) => {
+                return Err(format!("Invalid sizing '{}': wrong number of parameters", spec));
+            },
+            _ => return Err(format!("Unknown sizing policy '{}'", name)),
+        };
+        let risk_fraction = match sizer {
+            Self::RiskPercent(policy) => policy.risk_fraction,
+            Self::AtrRisk(policy) => policy.risk_fraction,
+            _ => 0.0,
+        };
+        if risk_fraction > 1.0 {
+            return Err(format!("Invalid sizing '{}': the risk fraction must be at most 1", spec));
+        }
+        Ok(sizer)
+    }
+
+    fn policy(&self) -> &dyn SizingPolicy {
+        match self {
+            Self::FixedQuantity(policy) => policy,
+            Self::FixedNotional(policy) => policy,
+            Self::RiskPercent(policy) => policy,
+            Self::AtrRisk(policy) => policy,
+            Self::Kelly(policy) => policy,
+            Self::VolatilityTarget(policy) => policy,
+        }
+    }
+}
+
+impl SizingPolicy for PositionSizer {
+    fn name(&self) -> &str {
+        self.policy().name()
+    }
+
+    fn quantity(&self, ctx: &SizingContext) -> Result<f64, String> {
+        self.policy().quantity(ctx)
+    }
+}
+
+/// Inputs to a pre-trade risk check.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RiskContext {
+    pub symbol: String,
+    pub now_ms: i64,
+    pub order_notional: f64, // Notional of the order being checked
+    pub total_exposure: f64, // Absolute notional of all open positions
+    pub symbol_exposure: f64, // Absolute notional of the open position in `symbol`
+    pub quote_exposure: f64, // Absolute notional of the open positions quoted in the same asset as `symbol`
+    pub open_positions: usize, // Symbols with an open position
+    pub equity: f64,
+    pub realized_pnl_today: f64, // Net realized PnL since 00:00 UTC
+    pub last_loss_ms: Option<i64>, // When the last losing trade closed
+}
+
+/// Accepts or rejects an order that opens or increases exposure.
+pub trait RiskPolicy: Send + Sync {
+    /// Short name used in logs.
+    fn name(&self) -> &str;
+    /// Returns `Err` with the reason when the order must not be placed.
+    fn check(&self, ctx: &RiskContext) -> Result<(), String>;
+}
+
+/// Caps the total and per-symbol notional exposure.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaxExposure {
+    pub max_total_notional: f64,
+    pub max_symbol_notional: Option<f64>,
+}
+
+impl RiskPolicy for MaxExposure {
+    fn name(&self) -> &str {
+        "max_exposure"
+    }
+
+    fn check(&self, ctx: &RiskContext) -> Result<(), String> {
+        let total = ctx.total_exposure + ctx.order_notional;
+        if total > self.max_total_notional {
+            return Err(format!("Total exposure {:.2} would exceed the limit {:.2}", total, self.max_total_notional));
+        }
+        if let Some(max_symbol) = self.max_symbol_notional {
+            let symbol_total = ctx.symbol_exposure + ctx.order_notional;
+            if symbol_total > max_symbol {
+                return Err(format!("{} exposure {:.2} would exceed the limit {:.2}", ctx.symbol, symbol_total, max_symbol));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Quote assets symbols can be quoted in, longest first where one is a suffix of another.
+const QUOTE_ASSETS: &[&str] = &["FDUSD", "USDT", "USDC", "BUSD", "BTC", "ETH", "BNB"];
+
+/// Returns the quote asset of a symbol, e.g. `USDT` for `BTCUSDT`.
+pub fn quote_asset(symbol: &str) -> Option<&'static str> {
+    let symbol = symbol.to_uppercase();
+    QUOTE_ASSETS.iter().copied().find(|quote| symbol.len() > quote.len() && symbol.ends_with(quote))
+}
+
+/// Caps the number of simultaneously open positions and the notional exposure, in total and per
+/// quote asset. Adding to an open position does not count as opening one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExposureLimits {
+    pub max_open_positions: Option<usize>,
+    pub max_total_notional: Option<f64>,
+    pub max_quote_notional: BTreeMap<String, f64>, // By quote asset, e.g. "USDT"
+}
+
+impl ExposureLimits {
+    /// Returns true when no limit is set.
+    pub fn is_empty(&self) -> bool {
+        self.max_open_positions.is_none() && self.max_total_notional.is_none() && self.max_quote_notional.is_empty()
+    }
+
+    /// Parses per-quote-asset limits written as `ASSET:notional` pairs, e.g. `USDT:5000,USDC:2000`.
+    pub fn parse_quote_limits(spec: &str) -> Result<BTreeMap<String, f64>, String> {
+        spec.split(',').map(str::trim).filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (asset, limit) = pair.split_once(':').ok_or_else(|| format!("Expected ASSET:notional, got '{}'", pair))?;
+                match limit.trim().parse::<f64>() {
+                    Ok(limit) if limit > 0.0 => Ok((asset.trim().to_uppercase(), limit)),
+                    _ => Err(format!("Invalid notional limit '{}' for {}", limit.trim(), asset.trim())),
+                }
+            })
+            .collect()
+    }
+}
+
+impl RiskPolicy for ExposureLimits {
+    fn name(&self) -> &str {
+        "exposure_limits"
+    }
+
+    fn check(&self, ctx: &RiskContext) -> Result<(), String> {
+        if let Some(max) = self.max_open_positions.filter(|&max| ctx.symbol_exposure == 0.0 && ctx.open_positions >= max) {
+            return Err(format!("{} positions are open, the limit is {}", ctx.open_positions, max));
+        }
+        let total = ctx.total_exposure + ctx.order_notional;
+        if let Some(max) = self.max_total_notional.filter(|&max| total > max) {
+            return Err(format!("Total exposure {:.2} would exceed the limit {:.2}", total, max));
+        }
+        let quote_limit = quote_asset(&ctx.symbol).and_then(|quote| self.max_quote_notional.get(quote).map(|&max| (quote, max)));
+        let quote_total = ctx.quote_exposure + ctx.order_notional;
+        if let Some((quote, max)) = quote_limit.filter(|&(_, max)| quote_total > max) {
+            return Err(format!("{} exposure {:.2} would exceed the limit {:.2}", quote, quote_total, max));
+        }
+        Ok(())
+    }
+}
+
+/// Blocks entries within `minutes` before or after a funding settlement (00:00, 08:00 and 16:00
+/// UTC), so a position is not opened just to pay funding or into the volatility around it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FundingBlackout {
+    pub minutes: u32,
+}
+
+/// Returns the signed distance in minutes from `now_ms` to the nearest funding settlement:
+/// negative before it, positive after it.
+pub fn minutes_from_funding(now_ms: i64) -> i64 {
+    let minute_of_day = now_ms.div_euclid(60_000).rem_euclid(24 * 60);
+    FUNDING_HOURS_UTC.iter()
+        .flat_map(|&hour| [hour as i64 * 60 - 24 * 60, hour as i64 * 60, hour as i64 * 60 + 24 * 60])
+        .map(|settlement| minute_of_day - settlement)
+        .min_by_key(|distance| distance.abs())
+        .unwrap_or_default()
+}
+
+impl RiskPolicy for FundingBlackout {
+    fn name(&self) -> &str {
+        "funding_blackout"
+    }
+
+    fn check(&self, ctx: &RiskContext) -> Result<(), String> {
+        let distance = minutes_from_funding(ctx.now_ms);
+        let limit = self.minutes as i64;
+        match distance {
+            d if (-limit..0).contains(&d) => Err(format!("Funding settles in {} minute(s)", -d)),
+            d if (0..limit).contains(&d) => Err(format!("Funding settled {} minute(s) ago", d)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Stops opening positions once the day's realized loss reaches a limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DailyLossLimit {
+    pub max_loss: f64, // Positive amount in the quote asset, e.g. 200.0
+}
+
+impl RiskPolicy for DailyLossLimit {
+    fn name(&self) -> &str {
+        "daily_loss_limit"
+    }
+
+    fn check(&self, ctx: &RiskContext) -> Result<(), String> {
+        if -ctx.realized_pnl_today >= self.max_loss {
+            return Err(format!("Daily loss {:.2} reached the limit {:.2}", -ctx.realized_pnl_today, self.max_loss));
+        }
+        Ok(())
+    }
+}
+
+/// Pauses new entries for a while after a losing trade.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cooldown {
+    pub after_loss_ms: i64,
+}
+
+impl RiskPolicy for Cooldown {
+    fn name(&self) -> &str {
+        "cooldown"
+    }
+
+    fn check(&self, ctx: &RiskContext) -> Result<(), String> {
+        match ctx.last_loss_ms {
+            Some(last) if ctx.now_ms - last < self.after_loss_ms => Err(format!(
+                "Cooling down after a loss for another {}s", (self.after_loss_ms - (ctx.now_ms - last)) / 1000
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Runs every policy and returns the first rejection, prefixed with the policy's name.
+pub fn check_all<P: AsRef<dyn RiskPolicy>>(policies: &[P], ctx: &RiskContext) -> Result<(), String> {
+    for policy in policies.iter().map(AsRef::as_ref) {
+        policy.check(ctx).map_err(|reason| format!("{}: {}", policy.name(), reason))?;
+    }
+    Ok(())
+}
+
+/// Stops new entries for the rest of the UTC day once the day's PnL, realized plus unrealized,
+/// loses `max_loss`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DailyLossBreaker {
+    pub max_loss: f64, // Positive amount in the quote asset, e.g. 500.0
+    pub flatten: bool, // Also close every position when it trips
+}
+
+/// What a `DailyLossBreaker` check found.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BreakerCheck {
+    Clear,
+    Tripped(String), // Tripped by this check
+    Holding(String), // Tripped earlier in the day
+}
+
+impl DailyLossBreaker {
+    /// Checks the day's PnL at `now_ms` given the open positions' current unrealized PnL. The first
+    /// check of a UTC day takes the unrealized PnL as the day's starting point.
+    pub fn check(&self, state: &mut RiskState, unrealized_pnl: f64, now_ms: i64) -> BreakerCheck {
+        let today = DateTime::<Utc>::from_timestamp_millis(now_ms).map(|dt| dt.date_naive());
+        if state.loss_session.as_ref().map(|session| session.day) != today {
+            state.loss_session = today.map(|day| LossSession { day, unrealized_at_start: unrealized_pnl, tripped: None });
+        }
+        let realized = state.realized_pnl_on(now_ms);
+        let Some(session) = state.loss_session.as_mut() else { return BreakerCheck::Clear };
+        if let Some(reason) = &session.tripped {
+            return BreakerCheck::Holding(reason.clone());
+        }
+        let pnl = realized + unrealized_pnl - session.unrealized_at_start;
+        if -pnl < self.max_loss {
+            return BreakerCheck::Clear;
+        }
+        let reason = format!("Daily loss {:.2} (realized and unrealized) reached the limit {:.2}; entries resume at 00:00 UTC", -pnl, self.max_loss);
+        session.tripped = Some(reason.clone());
+        BreakerCheck::Tripped(reason)
+    }
+}
+
+/// The UTC day a `DailyLossBreaker` is checking.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LossSession {
+    pub day: NaiveDate,
+    pub unrealized_at_start: f64, // Unrealized PnL of the open positions at the first check of the day
+    pub tripped: Option<String>, // Why the breaker tripped, once it has
+}
+
+/// Realized PnL of the current UTC day and the time of the last loss, fed from fills.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RiskState {
+    pub day: Option<NaiveDate>,
+    pub realized_pnl_today: f64,
+    pub last_loss_ms: Option<i64>,
+    pub loss_session: Option<LossSession>, // Kept by the `DailyLossBreaker`
+}
+
+impl RiskState {
+    fn add_to_day(&mut self, amount: f64, time_ms: i64) {
+        let day = DateTime::<Utc>::from_timestamp_millis(time_ms).map(|dt| dt.date_naive());
+        if day != self.day {
+            self.day = day;
+            self.realized_pnl_today = 0.0;
+        }
+        self.realized_pnl_today += amount;
+    }
+
+    /// Records the realized PnL (net of fees) of a closing trade at `time_ms`, rolling the day over
+    /// at 00:00 UTC. A negative PnL starts the cooldown.
+    pub fn record_realized(&mut self, pnl: f64, time_ms: i64) {
+        self.add_to_day(pnl, time_ms);
+        if pnl < 0.0 {
+            self.last_loss_ms = Some(time_ms);
+        }
+    }
+
+    /// Returns the realized PnL of the day containing `now_ms` (zero once the day has rolled over).
+    pub fn realized_pnl_on(&self, now_ms: i64) -> f64 {
+        let today = DateTime::<Utc>::from_timestamp_millis(now_ms).map(|dt| dt.date_naive());
+        if today == self.day { self.realized_pnl_today } else { 0.0 }
+    }
+
+    /// Records the realized profit and commission of a fill from the user data stream.
+    /// Commissions are assumed to be charged in the quote asset.
+    pub fn on_order_update(&mut self, update: &FuturesOrderUpdate) {
+        if update.execution_type != ExecutionType::Trade {
+            return;
+        }
+        let realized = update.realized_profit.parse::<f64>().unwrap_or(0.0);
+        let commission = update.commission.as_deref().and_then(|c| c.parse::<f64>().ok()).unwrap_or(0.0);
+        self.on_fill(realized, commission, update.trade_time as i64);
+    }
+
+    /// Records a fill's realized profit (before fees) and commission at `time_ms`.
+    pub fn on_fill(&mut self, realized: f64, commission: f64, time_ms: i64) {
+        if realized != 0.0 {
+            self.record_realized(realized - commission, time_ms);
+        } else if commission != 0.0 {
+            self.add_to_day(-commission, time_ms); // Entry fees count towards the day, not the cooldown
+        }
+    }
+}
+
+/// The sizing and risk policies applied by the order executor. The default applies none, which
+/// keeps the executor's built-in sizing (payload `quoteQuantity`, or `DEFAULT_ATR_RISK`).
+#[derive(Default)]
+pub struct ExecutionPolicies {
+    pub sizing: Option<Box<dyn SizingPolicy>>,
+    pub risk: Vec<Arc<dyn RiskPolicy>>,
+    pub loss_breaker: Option<DailyLossBreaker>,
+    pub state: Arc<Mutex<RiskState>>, // Fed with fills via `RiskState::on_order_update`
+    pub store: Option<Arc<StateStore>>, // Where `state` is persisted; `None` keeps it in memory
+}
+
+impl ExecutionPolicies {
+    /// Returns true when no policy needs account data before an order.
+    pub fn is_empty(&self) -> bool {
+        self.sizing.is_none() && self.risk.is_empty() && self.loss_breaker.is_none()
+    }
+
+    /// Returns the same risk policies and state with another sizing policy, e.g. for a strategy
+    /// that sizes its orders differently but stays within the global limits.
+    pub fn with_sizing(&self, sizing: Box<dyn SizingPolicy>) -> Self {
+        Self { sizing: Some(sizing), risk: self.risk.clone(), loss_breaker: self.loss_breaker, state: self.state.clone(), store: self.store.clone() }
+    }
+
+    /// Checks the daily loss breaker, if one is configured (see `DailyLossBreaker::check`), and
+    /// saves the risk state when the check started a new day or tripped the breaker.
+    pub fn check_loss_breaker(&self, unrealized_pnl: f64, now_ms: i64) -> BreakerCheck {
+        let Some(breaker) = self.loss_breaker else { return BreakerCheck::Clear };
+        self.update_state(|risk| breaker.check(risk, unrealized_pnl, now_ms))
+            .unwrap_or_else(|| BreakerCheck::Holding("Risk state lock poisoned".to_string()))
+    }
+
+    /// Records a fill from the user data stream (see `RiskState::on_order_update`) and saves the
+    /// risk state when it changed.
+    pub fn on_order_update(&self, update: &FuturesOrderUpdate) {
+        self.update_state(|risk| risk.on_order_update(update));
+    }
+
+    /// Changes the risk state and saves it to the store if it changed; `None` if the lock is poisoned.
+    fn update_state<T>(&self, change: impl FnOnce(&mut RiskState) -> T) -> Option<T> {
+        let mut risk = self.state.lock().ok()?;
+        let before = risk.clone();
+        let result = change(&mut risk);
+        if let Some(store) = self.store.as_deref().filter(|_| *risk != before) {
+            let saved = risk.clone();
+            if let Err(e) = store.update(|state| state.risk = Some(saved)) {
+                error!("Failed to save the risk state: {}", e);
+            }
+        }
+        Some(result)
+    }
+}
@@ -0,0 +1,123 @@
+// src/risk/schedule.rs
+
+//! This module restricts entries to configured trading sessions, so strategies that only work
+//! during liquid hours are not triggered at 4am on a Sunday.
+//!
+//! A schedule is a comma-separated list of windows, each a day or day range followed by a UTC time
+//! range, e.g. `mon-fri 07:00-21:00, sat 10-14`. Day ranges may wrap (`sun-thu`), `daily` (or `*`)
+//! means every day, and a window ending before it starts runs past midnight into the next day
+//! (`sun-thu 22:00-02:00`). `TradingSchedule` is a `RiskPolicy`: orders that open or increase
+//! exposure are rejected outside every window, closing orders are not. With `flatten_outside`,
+//! positions are also closed when a session ends (see `webhook::control::watch_schedule`).
+
+use chrono::{DateTime, Datelike, Timelike, Utc, Weekday};
+
+use super::{RiskContext, RiskPolicy};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// A time range on some days of the week, in UTC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionWindow {
+    pub days: [bool; 7], // From Monday
+    pub start_minute: u32, // Minutes after 00:00
+    pub end_minute: u32, // Exclusive; not after `start_minute` when the window runs past midnight
+}
+
+impl SessionWindow {
+    /// Returns true when `time` falls within the window.
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        let day = time.weekday().num_days_from_monday() as usize;
+        let minute = time.hour() * 60 + time.minute();
+        if self.start_minute < self.end_minute {
+            return self.days[day] && (self.start_minute..self.end_minute).contains(&minute);
+        }
+        // Runs past midnight: the evening of a session day, or the morning after one
+        (self.days[day] && minute >= self.start_minute) || (self.days[(day + 6) % 7] && minute < self.end_minute)
+    }
+
+    /// Parses one window, e.g. `mon-fri 07:00-21:00`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (days, hours) = spec.trim().split_once(char::is_whitespace)
+            .ok_or_else(|| format!("Expected '<days> <start>-<end>', got '{}'", spec.trim()))?;
+        let (start, end) = hours.trim().split_once('-').ok_or_else(|| format!("Expected a time range, got '{}'", hours.trim()))?;
+        let (start_minute, end_minute) = (parse_minute(start)?, parse_minute(end)?);
+        if start_minute == end_minute || start_minute == MINUTES_PER_DAY {
+            return Err(format!("Empty time range '{}'", hours.trim()));
+        }
+        Ok(Self { days: parse_days(days)?, start_minute, end_minute })
+    }
+}
+
+fn parse_day(name: &str) -> Result<usize, String> {
+    // `mon` or `monday`
+    name.trim().parse::<Weekday>()
+        .map(|day| day.num_days_from_monday() as usize)
+        .map_err(|_| format!("Unknown day '{}'", name.trim()))
+}
+
+fn parse_days(spec: &str) -> Result<[bool; 7], String> {
+    let spec = spec.trim().to_lowercase();
+    if spec == "daily" || spec == "*" {
+        return Ok([true; 7]);
+    }
+    let (first, last) = match spec.split_once('-') {
+        Some((first, last)) => (parse_day(first)?, parse_day(last)?),
+        None => (parse_day(&spec)?, parse_day(&spec)?),
+    };
+    let mut days = [false; 7];
+    let mut day = first;
+    loop {
+        days[day] = true;
+        if day == last {
+            return Ok(days);
+        }
+        day = (day + 1) % 7;
+    }
+}
+
+fn parse_minute(time: &str) -> Result<u32, String> {
+    let time = time.trim();
+    let (hour, minute) = time.split_once(':').unwrap_or((time, "0"));
+    match (hour.parse::<u32>(), minute.parse::<u32>()) {
+        (Ok(hour), Ok(minute)) if minute < 60 && hour * 60 + minute <= MINUTES_PER_DAY => Ok(hour * 60 + minute),
+        _ => Err(format!("Invalid time '{}' (expected HH or HH:MM, up to 24:00)", time)),
+    }
+}
+
+/// The sessions entries are allowed in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradingSchedule {
+    pub spec: String, // As configured, for messages
+    pub windows: Vec<SessionWindow>,
+    pub flatten_outside: bool, // Close every position when a session ends
+}
+
+impl TradingSchedule {
+    /// Parses a comma-separated list of windows, e.g. `mon-fri 07:00-21:00, sat 10-14`.
+    pub fn parse(spec: &str, flatten_outside: bool) -> Result<Self, String> {
+        let windows = spec.split(',').filter(|window| !window.trim().is_empty()).map(SessionWindow::parse).collect::<Result<Vec<_>, _>>()?;
+        if windows.is_empty() {
+            return Err("The schedule has no window".to_string());
+        }
+        Ok(Self { spec: spec.trim().to_string(), windows, flatten_outside })
+    }
+
+    /// Returns true when `now_ms` falls within a session.
+    pub fn is_open(&self, now_ms: i64) -> bool {
+        DateTime::<Utc>::from_timestamp_millis(now_ms).is_some_and(|time| self.windows.iter().any(|window| window.contains(time)))
+    }
+}
+
+impl RiskPolicy for TradingSchedule {
+    fn name(&self) -> &str {
+        "trading_schedule"
+    }
+
+    fn check(&self, ctx: &RiskContext) -> Result<(), String> {
+        if self.is_open(ctx.now_ms) {
+            return Ok(());
+        }
+        Err(format!("Outside the trading sessions ({})", self.spec))
+    }
+}
@@ -0,0 +1,222 @@
+// src/session/mod.rs
+
+//! This module tags trades with session metadata: the market hours they were opened in
+//! (Asia/Europe/US), their position relative to the 8-hourly funding settlements, and a
+//! volatility bucket. Tagged trades can then be broken down per tag to find out when a
+//! strategy actually performs.
+
+use chrono::{DateTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::currency::ReportingCurrency;
+
+/// Hours (UTC) at which Binance Futures settles funding.
+pub const FUNDING_HOURS_UTC: [u32; 3] = [0, 8, 16];
+
+/// Enum representing the market session a trade was opened in, based on UTC hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketSession {
+    Asia,      // 00:00 - 08:00 UTC
+    Europe,    // 08:00 - 13:00 UTC
+    Us,        // 13:00 - 21:00 UTC
+    Overnight, // 21:00 - 24:00 UTC
+}
+
+impl MarketSession {
+    /// Returns the session for a given UTC hour (0-23).
+    pub fn from_utc_hour(hour: u32) -> Self {
+        match hour {
+            0..=7 => MarketSession::Asia,
+            8..=12 => MarketSession::Europe,
+            13..=20 => MarketSession::Us,
+            _ => MarketSession::Overnight,
+        }
+    }
+}
+
+/// Enum representing where a trade falls relative to the nearest funding settlement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingWindow {
+    PreFunding,  // Within the window before a settlement
+    PostFunding, // Within the window after a settlement
+    Clear,       // Away from any settlement
+}
+
+/// Enum representing the volatility regime at the time of the trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VolatilityBucket {
+    Low,
+    Normal,
+    High,
+    Unknown, // No volatility measurement was available
+}
+
+/// The full set of session tags attached to a single trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionTags {
+    pub session: MarketSession,
+    pub funding: FundingWindow,
+    pub volatility: VolatilityBucket,
+}
+
+impl SessionTags {
+    /// Returns the tags as flat `category:value` labels (e.g., "session:asia").
+    pub fn labels(&self) -> Vec<String> {
+        vec![
+            format!("session:{}", enum_label(&self.session)),
+            format!("funding:{}", enum_label(&self.funding)),
+            format!("volatility:{}", enum_label(&self.volatility)),
+        ]
+    }
+}
+
+/// Serializes a unit enum variant to its snake_case label.
+fn enum_label<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default().trim_matches('"').to_string()
+}
+
+/// Assigns `SessionTags` to trades based on their timestamp and the prevailing volatility.
+#[derive(Debug, Clone)]
+pub struct SessionTagger {
+    pub funding_window_minutes: u32, // Minutes before/after a settlement counted as pre/post funding
+    pub low_volatility_threshold: f64, // Volatility below this is `Low` (e.g., 0.0075 = 0.75% per bar)
+    pub high_volatility_threshold: f64, // Volatility above this is `High`
+}
+
+impl Default for SessionTagger {
+    fn default() -> Self {
+        Self {
+            funding_window_minutes: 60,
+            low_volatility_threshold: 0.0075,
+            high_volatility_threshold: 0.015,
+        }
+    }
+}
+
+impl SessionTagger {
+    /// Tags a trade opened at `timestamp_ms`.
+    ///
+    /// # Arguments
+    /// * `timestamp_ms` - The trade's entry time in milliseconds since the Unix epoch.
+    /// * `volatility` - Optional. The volatility at entry (e.g., from `realized_volatility`).
+    ///
+    /// # Returns
+    /// The `SessionTags` for the trade, or `None` if the timestamp is out of range.
+    pub fn tag(&self, timestamp_ms: i64, volatility: Option<f64>) -> Option<SessionTags> {
+        let time = DateTime::<Utc>::from_timestamp_millis(timestamp_ms)?;
+        Some(SessionTags {
+            session: MarketSession::from_utc_hour(time.hour()),
+            funding: self.funding_window(time.hour() * 60 + time.minute()),
+            volatility: self.volatility_bucket(volatility),
+        })
+    }
+
+    /// Classifies a minute-of-day (UTC) relative to the funding settlements.
+    fn funding_window(&self, minute_of_day: u32) -> FundingWindow {
+        const MINUTES_PER_DAY: u32 = 24 * 60;
+        for hour in FUNDING_HOURS_UTC {
+            let settlement = hour * 60;
+            let after = (minute_of_day + MINUTES_PER_DAY - settlement) % MINUTES_PER_DAY;
+            let before = (settlement + MINUTES_PER_DAY - minute_of_day) % MINUTES_PER_DAY;
+            if after < self.funding_window_minutes {
+                return FundingWindow::PostFunding;
+            }
+            if before > 0 && before <= self.funding_window_minutes {
+                return FundingWindow::PreFunding;
+            }
+        }
+        FundingWindow::Clear
+    }
+
+    /// Buckets a volatility reading using the configured thresholds.
+    fn volatility_bucket(&self, volatility: Option<f64>) -> VolatilityBucket {
+        match volatility {
+            Some(v) if v < self.low_volatility_threshold => VolatilityBucket::Low,
+            Some(v) if v > self.high_volatility_threshold => VolatilityBucket::High,
+            Some(_) => VolatilityBucket::Normal,
+            None => VolatilityBucket::Unknown,
+        }
+    }
+}
+
+/// Computes the realized volatility (standard deviation of log returns) of a series of closes.
+///
+/// # Returns
+/// `Some(volatility)` per bar, or `None` if fewer than two valid closes are provided.
+pub fn realized_volatility(closes: &[f64]) -> Option<f64> {
+    let returns: Vec<f64> = closes.windows(2)
+        .filter(|w| w[0] > 0.0 && w[1] > 0.0)
+        .map(|w| (w[1] / w[0]).ln())
+        .collect();
+    if returns.is_empty() {
+        return None;
+    }
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64;
+    Some(variance.sqrt())
+}
+
+/// Performance statistics for all trades sharing a single tag.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagPerformance {
+    pub tag: String,
+    pub trades: usize,
+    pub wins: usize,
+    pub win_rate: f64, // Percentage (0-100)
+    pub net_pnl: f64,
+    pub avg_pnl: f64,
+    pub profit_factor: f64,
+}
+
+/// Breaks down the performance of tagged trades per tag label.
+///
+/// # Arguments
+/// * `trades` - Pairs of session tags and realized PnL for each closed trade.
+///
+/// # Returns
+/// One `TagPerformance` per tag label that appears in `trades`, sorted by label.
+pub fn performance_by_tag(trades: &[(SessionTags, f64)]) -> Vec<TagPerformance> {
+    let mut grouped: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for (tags, pnl) in trades {
+        for label in tags.labels() {
+            grouped.entry(label).or_default().push(*pnl);
+        }
+    }
+
+    grouped.into_iter().map(|(tag, pnls)| {
+        let wins = pnls.iter().filter(|&&p| p > 0.0).count();
+        let gross_profit: f64 = pnls.iter().filter(|&&p| p > 0.0).sum();
+        let gross_loss: f64 = pnls.iter().filter(|&&p| p < 0.0).sum::<f64>().abs();
+        let net_pnl: f64 = pnls.iter().sum();
+        TagPerformance {
+            tag,
+            trades: pnls.len(),
+            wins,
+            win_rate: wins as f64 / pnls.len() as f64 * 100.0,
+            net_pnl,
+            avg_pnl: net_pnl / pnls.len() as f64,
+            profit_factor: if gross_loss > 0.0 { gross_profit / gross_loss } else { f64::INFINITY },
+        }
+    }).collect()
+}
+
+/// Prints a per-tag performance breakdown table to the console.
+pub fn print_tag_breakdown(breakdown: &[TagPerformance], currency: ReportingCurrency) {
+    if breakdown.is_empty() {
+        return;
+    }
+    println!("\n--- Performance by Session Tag ({}) ---", currency.code());
+    println!("{:<25} | {:>6} | {:>8} | {:>12} | {:>10} | {:>8}", "Tag", "Trades", "Win %", "Net P/L", "Avg P/L", "PF");
+    println!("{:-<83}", "");
+    for row in breakdown {
+        println!(
+            "{:<25} | {:>6} | {:>7.2}% | {:>12} | {:>10} | {:>8.2}",
+            row.tag, row.trades, row.win_rate, currency.format(row.net_pnl), currency.format(row.avg_pnl), row.profit_factor
+        );
+    }
+    println!("{:-<83}", "");
+}
@@ -0,0 +1,304 @@
+// src/storage/sqlite.rs
+
+//! This module stores the bot's history in a SQLite database file. The schema is versioned: each
+//! migration in `MIGRATIONS` runs once, in order, in its own transaction, and is recorded in the
+//! `schema_migrations` table. New migrations are appended; applied ones are never edited.
+//!
+//! SQLite calls block, so each one runs on Tokio's blocking pool (see `with_connection`).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde_json::Value;
+
+use super::{EquitySnapshot, FillRecord, OrderRecord, SignalRecord, Storage};
+
+/// Schema migrations: version, name and SQL.
+const MIGRATIONS: &[(i64, &str, &str)] = &[
+    (1, "create history tables", "
+        CREATE TABLE signals (
+            id INTEGER PRIMARY KEY,
+            time_ms INTEGER NOT NULL,
+            tracking_id TEXT,
+            symbol TEXT NOT NULL,
+            signal TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            outcome TEXT NOT NULL,
+            reason TEXT,
+            client_order_id TEXT
+        );
+        CREATE INDEX signals_time ON signals (time_ms);
+        CREATE TABLE orders (
+            id INTEGER PRIMARY KEY,
+            time_ms INTEGER NOT NULL,
+            symbol TEXT NOT NULL,
+            client_order_id TEXT,
+            request TEXT NOT NULL,
+            response TEXT,
+            order_id INTEGER,
+            status TEXT,
+            error TEXT
+        );
+        CREATE INDEX orders_time ON orders (time_ms);
+        CREATE INDEX orders_client_order_id ON orders (client_order_id);
+        CREATE TABLE fills (
+            id INTEGER PRIMARY KEY,
+            time_ms INTEGER NOT NULL,
+            symbol TEXT NOT NULL,
+            client_order_id TEXT NOT NULL,
+            side TEXT NOT NULL,
+            position_side TEXT,
+            price REAL NOT NULL,
+            quantity REAL NOT NULL,
+            realized_pnl REAL NOT NULL,
+            commission REAL NOT NULL
+        );
+        CREATE INDEX fills_time ON fills (time_ms);
+        CREATE INDEX fills_client_order_id ON fills (client_order_id);
+        CREATE TABLE equity_snapshots (
+            id INTEGER PRIMARY KEY,
+            time_ms INTEGER NOT NULL,
+            wallet_balance REAL NOT NULL,
+            unrealized_pnl REAL NOT NULL,
+            margin_balance REAL NOT NULL,
+            available_balance REAL NOT NULL
+        );
+        CREATE INDEX equity_snapshots_time ON equity_snapshots (time_ms);
+    "),
+    (2, "create trade notes", "
+        CREATE TABLE trade_notes (
+            trade_id TEXT PRIMARY KEY,
+            note TEXT NOT NULL,
+            updated_ms INTEGER NOT NULL
+        );
+    "),
+];
+
+/// Latest schema version.
+pub const SCHEMA_VERSION: i64 = MIGRATIONS[MIGRATIONS.len() - 1].0;
+/// How long a statement waits for a lock held by another connection.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn to_json(value: &Value) -> String {
+    value.to_string()
+}
+
+fn from_json(text: Option<String>) -> Option<Value> {
+    text.and_then(|text| serde_json::from_str(&text).ok())
+}
+
+/// The bot's history in a SQLite database.
+pub struct SqliteStorage {
+    path: PathBuf,
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    /// Opens (or creates) the database at `path` and applies the pending migrations.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let connection = Connection::open(&path).map_err(|e| format!("Failed to open database {}: {}", path.display(), e))?;
+        connection.busy_timeout(BUSY_TIMEOUT).map_err(|e| e.to_string())?;
+        connection.pragma_update(None, "journal_mode", "WAL").map_err(|e| e.to_string())?;
+        migrate(&connection).map_err(|e| format!("Failed to migrate database {}: {}", path.display(), e))?;
+        Ok(Self { path, connection: Arc::new(Mutex::new(connection)) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Runs `f` on the connection on the blocking pool, so a slow disk never stalls the async workers.
+    async fn with_connection<T: Send + 'static>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static) -> Result<T, String> {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || {
+            let connection = connection.lock().map_err(|_| "Database lock poisoned".to_string())?;
+            f(&connection).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Database task failed: {}", e))?
+    }
+
+    /// Reads the rows of `sql` at or after `since_ms`.
+    async fn rows<T: Send + 'static>(&self, sql: &'static str, since_ms: i64, map: fn(&Row) -> rusqlite::Result<T>) -> Result<Vec<T>, String> {
+        self.with_connection(move |c| c.prepare_cached(sql)?.query_map([since_ms], map)?.collect()).await
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    async fn schema_version(&self) -> Result<i64, String> {
+        self.with_connection(current_version).await
+    }
+
+    async fn record_signal(&self, record: &SignalRecord) -> Result<(), String> {
+        let record = record.clone();
+        self.with_connection(move |c| c.execute(
+            "INSERT INTO signals (time_ms, tracking_id, symbol, signal, payload, outcome, reason, client_order_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.time_ms,
+                record.tracking_id,
+                record.symbol,
+                record.signal,
+                to_json(&record.payload),
+                record.outcome,
+                record.reason,
+                record.client_order_id,
+            ],
+        ).map(drop)).await.map_err(|e| format!("Failed to store signal: {}", e))
+    }
+
+    async fn record_order(&self, record: &OrderRecord) -> Result<(), String> {
+        let record = record.clone();
+        self.with_connection(move |c| c.execute(
+            "INSERT INTO orders (time_ms, symbol, client_order_id, request, response, order_id, status, error) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.time_ms,
+                record.symbol,
+                record.client_order_id,
+                to_json(&record.request),
+                record.response.as_ref().map(to_json),
+                record.order_id.map(|id| id as i64),
+                record.status,
+                record.error,
+            ],
+        ).map(drop)).await.map_err(|e| format!("Failed to store order: {}", e))
+    }
+
+    async fn record_fill(&self, record: &FillRecord) -> Result<(), String> {
+        let record = record.clone();
+        self.with_connection(move |c| c.execute(
+            "INSERT INTO fills (time_ms, symbol, client_order_id, side, position_side, price, quantity, realized_pnl, commission) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                record.time_ms,
+                record.symbol,
+                record.client_order_id,
+                record.side,
+                record.position_side,
+                record.price,
+                record.quantity,
+                record.realized_pnl,
+                record.commission,
+            ],
+        ).map(drop)).await.map_err(|e| format!("Failed to store fill: {}", e))
+    }
+
+    async fn record_equity(&self, snapshot: &EquitySnapshot) -> Result<(), String> {
+        let snapshot = snapshot.clone();
+        self.with_connection(move |c| c.execute(
+            "INSERT INTO equity_snapshots (time_ms, wallet_balance, unrealized_pnl, margin_balance, available_balance) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                snapshot.time_ms,
+                snapshot.wallet_balance,
+                snapshot.unrealized_pnl,
+                snapshot.margin_balance,
+                snapshot.available_balance,
+            ],
+        ).map(drop)).await.map_err(|e| format!("Failed to store equity snapshot: {}", e))
+    }
+
+    async fn signals(&self, since_ms: i64) -> Result<Vec<SignalRecord>, String> {
+        self.rows("SELECT time_ms, tracking_id, symbol, signal, payload, outcome, reason, client_order_id FROM signals WHERE time_ms >= ?1 ORDER BY id", since_ms, |row| Ok(SignalRecord {
+            time_ms: row.get(0)?,
+            tracking_id: row.get(1)?,
+            symbol: row.get(2)?,
+            signal: row.get(3)?,
+            payload: from_json(row.get(4)?).unwrap_or_default(),
+            outcome: row.get(5)?,
+            reason: row.get(6)?,
+            client_order_id: row.get(7)?,
+        })).await
+    }
+
+    async fn orders(&self, since_ms: i64) -> Result<Vec<OrderRecord>, String> {
+        self.rows("SELECT time_ms, symbol, client_order_id, request, response, order_id, status, error FROM orders WHERE time_ms >= ?1 ORDER BY id", since_ms, |row| Ok(OrderRecord {
+            time_ms: row.get(0)?,
+            symbol: row.get(1)?,
+            client_order_id: row.get(2)?,
+            request: from_json(row.get(3)?).unwrap_or_default(),
+            response: from_json(row.get(4)?),
+            order_id: row.get::<_, Option<i64>>(5)?.map(|id| id as u64),
+            status: row.get(6)?,
+            error: row.get(7)?,
+        })).await
+    }
+
+    async fn fills(&self, since_ms: i64) -> Result<Vec<FillRecord>, String> {
+        self.rows("SELECT time_ms, symbol, client_order_id, side, position_side, price, quantity, realized_pnl, commission FROM fills WHERE time_ms >= ?1 ORDER BY id", since_ms, |row| Ok(FillRecord {
+            time_ms: row.get(0)?,
+            symbol: row.get(1)?,
+            client_order_id: row.get(2)?,
+            side: row.get(3)?,
+            position_side: row.get(4)?,
+            price: row.get(5)?,
+            quantity: row.get(6)?,
+            realized_pnl: row.get(7)?,
+            commission: row.get(8)?,
+        })).await
+    }
+
+    async fn equity_snapshots(&self, since_ms: i64) -> Result<Vec<EquitySnapshot>, String> {
+        self.rows("SELECT time_ms, wallet_balance, unrealized_pnl, margin_balance, available_balance FROM equity_snapshots WHERE time_ms >= ?1 ORDER BY id", since_ms, |row| Ok(EquitySnapshot {
+            time_ms: row.get(0)?,
+            wallet_balance: row.get(1)?,
+            unrealized_pnl: row.get(2)?,
+            margin_balance: row.get(3)?,
+            available_balance: row.get(4)?,
+        })).await
+    }
+
+    async fn set_note(&self, trade_id: &str, note: &str, time_ms: i64) -> Result<(), String> {
+        let (trade_id, note) = (trade_id.to_string(), note.to_string());
+        self.with_connection(move |c| if note.is_empty() {
+            c.execute("DELETE FROM trade_notes WHERE trade_id = ?1", [&trade_id]).map(drop)
+        } else {
+            c.execute(
+                "INSERT INTO trade_notes (trade_id, note, updated_ms) VALUES (?1, ?2, ?3) ON CONFLICT (trade_id) DO UPDATE SET note = excluded.note, updated_ms = excluded.updated_ms",
+                params![trade_id, note, time_ms],
+            ).map(drop)
+        }).await.map_err(|e| format!("Failed to store note: {}", e))
+    }
+
+    async fn notes(&self) -> Result<HashMap<String, String>, String> {
+        self.with_connection(|c| c.prepare("SELECT trade_id, note FROM trade_notes")?.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect()).await
+    }
+}
+
+fn current_version(connection: &Connection) -> rusqlite::Result<i64> {
+    connection
+        .query_row("SELECT MAX(version) FROM schema_migrations", [], |row| row.get::<_, Option<i64>>(0))
+        .optional()
+        .map(|version| version.flatten().unwrap_or_default())
+}
+
+/// Applies the migrations newer than the database's schema.
+fn migrate(connection: &Connection) -> Result<(), String> {
+    connection
+        .execute_batch("CREATE TABLE IF NOT EXISTS schema_migrations (version INTEGER PRIMARY KEY, name TEXT NOT NULL, applied_at_ms INTEGER NOT NULL)")
+        .map_err(|e| e.to_string())?;
+    let current = current_version(connection).map_err(|e| e.to_string())?;
+    if current > SCHEMA_VERSION {
+        return Err(format!("schema version {} is newer than this build ({})", current, SCHEMA_VERSION));
+    }
+    for (version, name, sql) in MIGRATIONS.iter().filter(|(version, _, _)| *version > current) {
+        // Rolled back when dropped without a commit
+        let applied = connection.unchecked_transaction().and_then(|transaction| {
+            transaction.execute_batch(sql)?;
+            transaction.execute(
+                "INSERT INTO schema_migrations (version, name, applied_at_ms) VALUES (?1, ?2, ?3)",
+                params![version, name, chrono::Utc::now().timestamp_millis()],
+            )?;
+            transaction.commit()
+        });
+        applied.map_err(|e| format!("migration {} ({}) failed: {}", version, name, e))?;
+    }
+    Ok(())
+}
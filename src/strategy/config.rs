@@ -0,0 +1,304 @@
+// src/strategy/config.rs
+
+//! This module defines the settings of the EMA crossover backtest, so different settings can be
+//! tested without recompiling. Settings are read from a TOML file and/or command line flags
+//! (flags override the file):
+//!
+//! ```toml
+//! # backtest.toml
+//! data_path = "./btc_4h_data_2018_to_2025.csv"
+//! fast_ema_period = 21
+//! slow_ema_period = 55
+//! risk_reward_ratio = 3.0
+//! account_balance = 5000.0
+//! risk_percentage = 0.01
+//! leverage = 20.0
+//! maintenance_margin_rate = 0.004
+//! reporting_currency = "EUR" # USD, EUR or BTC
+//! ```
+//!
+//! `trading_bot backtest --config backtest.toml --fast-ema 13 --risk 0.005`
+//!
+//! Setting `symbol` pulls the candles from Binance's klines endpoint instead of the CSV file:
+//! `trading_bot backtest --symbol BTCUSDT --from 2021-01-01 [--to 2022-01-01] [--interval 1h]`
+//! `--agg-trades trades.csv` replays the aggregated trades of each bar to find which exit was hit
+//! first (see `ticks`).
+//!
+//! `--atr-period 14 [--atr-stop 2]` places the stop `atr_stop_multiple` ATRs below the entry instead
+//! of at the signal candle's low; the position still risks `risk_percentage` of the balance, i.e.
+//! its size is the risked amount divided by `atr_stop_multiple * ATR`.
+//!
+//! `--sizing volatility_target:0.005:3` sizes positions with another built-in policy (see
+//! `risk::PositionSizer::parse`); policies needing the ATR are given the `atr_period` ATR (14 bars
+//! without ATR stops).
+//!
+//! `--output report.json` also writes the settings, metrics and trades as JSON (see `report`), and
+//! `--html report.html` a self-contained page with the equity, drawdown and price charts (see `html`).
+//! `--trades trades.csv` exports every trade (times, prices, exit reason, size, fee, PnL, MAE/MFE).
+//! `--monte-carlo 5000 [--mc-method bootstrap] [--mc-seed 42]` resamples the trades to report the
+//! spread of final equity and drawdown (see `monte_carlo`). `trading_bot walk-forward` takes the same
+//! settings and re-optimizes the EMA periods on rolling windows (see `walk_forward`), and
+//! `trading_bot optimize` ranks a grid of settings (see `optimizer`) and `trading_bot genetic` evolves
+//! them (see `genetic`).
+//!
+//! Only flat `key = value` files are supported (strings, numbers, comments), which is all the
+//! backtest settings need.
+
+use std::fs;
+
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::margin::MarginConfig;
+use super::monte_carlo::Resampling;
+use crate::currency::ReportingCurrency;
+use crate::market_data::KlineInterval;
+use crate::market_event::Symbol;
+use crate::risk::{AtrRisk, PositionSizer, RiskPercent};
+
+/// REST endpoint klines are downloaded from; historical data is only complete on mainnet.
+pub const DEFAULT_KLINES_BASE_URL: &str = "https://fapi.binance.com";
+
+/// ATR period used for sizing when stops are not ATR-based.
+pub const DEFAULT_ATR_PERIOD: usize = 14;
+/// Symbol the candles of the data file are traded as when no symbol is set.
+pub const DEFAULT_SYMBOL: &str = "BTCUSDT";
+
+/// Settings of the EMA crossover backtest.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BacktestConfig {
+    pub data_path: String, // Binance CSV export (or Parquet `.parquet` file) of the candles, used when no symbol is set
+    pub symbol: Option<String>, // Download the candles of this symbol from Binance instead
+    pub interval: String, // Kline interval, e.g. "4h"
+    pub from: Option<String>, // Start of the download, e.g. "2021-01-01" or "2021-01-01 08:00:00" (UTC)
+    pub to: Option<String>, // End of the download; defaults to now
+    pub agg_trades_path: Option<String>, // Aggregated trades of the period, to resolve exits within bars (see `ticks`)
+    pub rest_base_url: String,
+    pub fast_ema_period: usize,
+    pub slow_ema_period: usize,
+    pub risk_reward_ratio: f64, // Take profit distance as a multiple of the stop distance
+    pub account_balance: f64, // Starting account balance for the simulation
+    pub risk_percentage: f64, // Fraction of the balance risked per trade, e.g. 0.01 for 1%
+    pub atr_period: usize, // ATR period of ATR-based stops; 0 places the stop at the signal candle's low
+    pub atr_stop_multiple: f64, // Stop distance in ATRs when `atr_period` is set
+    pub sizing: Option<PositionSizer>, // Position sizing; defaults to risking `risk_percentage` over the stop
+    pub volatility_lookback: usize, // Number of closes used to measure volatility for session tagging
+    pub leverage: f64, // Caps the position notional at `leverage` x balance; sets the liquidation price
+    pub maintenance_margin_rate: f64,
+    pub reporting_currency: ReportingCurrency, // PnL and balances are converted at historical prices
+    pub fee_rate: f64, // Commission per side as a fraction of the notional, e.g. 0.0005 for the 0.05% taker fee
+    pub output: Option<String>, // Path of the JSON report, if one should be written
+    pub html_output: Option<String>, // Path of the HTML report, if one should be written
+    pub trades_output: Option<String>, // Path of the CSV trade log, if one should be written
+    pub monte_carlo_runs: usize, // Resampled paths of the Monte Carlo analysis; 0 disables it
+    pub monte_carlo_method: Resampling,
+    pub monte_carlo_seed: Option<u64>, // Makes the Monte Carlo analysis reproducible
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            data_path: "./btc_4h_data_2018_to_2025.csv".to_string(),
+            symbol: None,
+            interval: "4h".to_string(),
+            from: None,
+            to: None,
+            agg_trades_path: None,
+            rest_base_url: DEFAULT_KLINES_BASE_URL.to_string(),
+            fast_ema_period: 21,
+            slow_ema_period: 55,
+            risk_reward_ratio: 3.0,
+            account_balance: 5000.0,
+            risk_percentage: 0.01,
+            atr_period: 0,
+            atr_stop_multiple: 2.0,
+            sizing: None,
+            volatility_lookback: 20,
+            leverage: MarginConfig::default().leverage,
+            maintenance_margin_rate: MarginConfig::default().maintenance_margin_rate,
+            reporting_currency: ReportingCurrency::Usd,
+            fee_rate: 0.0,
+            output: None,
+            html_output: None,
+            trades_output: None,
+            monte_carlo_runs: 0,
+            monte_carlo_method: Resampling::Shuffle,
+            monte_carlo_seed: None,
+        }
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse::<T>().map_err(|e| format!("Invalid value '{}' for {}: {}", value, key, e))
+}
+
+impl BacktestConfig {
+    /// Sets one setting by name. Accepts the TOML keys and the command line flag names.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "data_path" | "data" => self.data_path = value.to_string(),
+            "symbol" => self.symbol = Some(value.to_uppercase()),
+            "interval" => self.interval = value.to_string(),
+            "from" => self.from = Some(value.to_string()),
+            "to" => self.to = Some(value.to_string()),
+            "agg_trades_path" | "agg-trades" => self.agg_trades_path = Some(value.to_string()),
+            "rest_base_url" | "rest-url" => self.rest_base_url = value.to_string(),
+            "fast_ema_period" | "fast-ema" => self.fast_ema_period = parse_value(key, value)?,
+            "slow_ema_period" | "slow-ema" => self.slow_ema_period = parse_value(key, value)?,
+            "risk_reward_ratio" | "rr" => self.risk_reward_ratio = parse_value(key, value)?,
+            "account_balance" | "balance" => self.account_balance = parse_value(key, value)?,
+            "risk_percentage" | "risk" => self.risk_percentage = parse_value(key, value)?,
+            "atr_period" | "atr-period" => self.atr_period = parse_value(key, value)?,
+            "atr_stop_multiple" | "atr-stop" => self.atr_stop_multiple = parse_value(key, value)?,
+            "sizing" => self.sizing = Some(PositionSizer::parse(value)?),
+            "volatility_lookback" => self.volatility_lookback = parse_value(key, value)?,
+            "leverage" => self.leverage = parse_value(key, value)?,
+            "maintenance_margin_rate" | "mmr" => self.maintenance_margin_rate = parse_value(key, value)?,
+            "reporting_currency" | "currency" => self.reporting_currency = ReportingCurrency::parse(value)?,
+            "output" => self.output = Some(value.to_string()),
+            "html_output" | "html" => self.html_output = Some(value.to_string()),
+            "trades_output" | "trades" => self.trades_output = Some(value.to_string()),
+            "fee_rate" | "fee-rate" => self.fee_rate = parse_value(key, value)?,
+            "monte_carlo_runs" | "monte-carlo" => self.monte_carlo_runs = parse_value(key, value)?,
+            "monte_carlo_method" | "mc-method" => self.monte_carlo_method = Resampling::parse(value)?,
+            "monte_carlo_seed" | "mc-seed" => self.monte_carlo_seed = Some(parse_value(key, value)?),
+            _ => return Err(format!("Unknown backtest setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Applies the settings of a flat TOML document on top of `self`.
+    pub fn apply_toml(&mut self, toml: &str) -> Result<(), String> {
+        for (index, line) in toml.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("Line {}: expected `key = value`", index + 1))?;
+            let value = value.trim();
+            let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+            self.set(key.trim(), value).map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        }
+        Ok(())
+    }
+
+    /// Loads the settings from a TOML file; settings missing from the file keep their defaults.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let toml = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let mut config = Self::default();
+        config.apply_toml(&toml)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Builds the settings from command line flags (`--config <file>` first, then `--<flag> <value>`
+    /// overrides), e.g. `--fast-ema 13 --slow-ema 34 --rr 2 --balance 10000 --risk 0.005 --data file.csv`.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut pairs = Vec::new();
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let key = flag.strip_prefix("--").ok_or_else(|| format!("Unexpected argument '{}'", flag))?;
+            let value = iter.next().ok_or_else(|| format!("Missing value for --{}", key))?;
+            pairs.push((key, value.as_str()));
+        }
+        let mut config = match pairs.iter().find(|(key, _)| *key == "config") {
+            Some((_, path)) => Self::load(path)?,
+            None => Self::default(),
+        };
+        for (key, value) in pairs.into_iter().filter(|(key, _)| *key != "config") {
+            config.set(key, value)?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the settings.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.fast_ema_period == 0 || self.fast_ema_period >= self.slow_ema_period {
+            return Err(format!("The fast EMA period ({}) must be positive and below the slow one ({}).", self.fast_ema_period, self.slow_ema_period));
+        }
+        if self.risk_reward_ratio <= 0.0 || self.account_balance <= 0.0 {
+            return Err("The reward/risk ratio and the account balance must be positive.".to_string());
+        }
+        if !(0.0..0.1).contains(&self.fee_rate) {
+            return Err(format!("The fee rate must be in [0, 0.1), got {}.", self.fee_rate));
+        }
+        if !(self.risk_percentage > 0.0 && self.risk_percentage <= 1.0) {
+            return Err(format!("The risk percentage must be in (0, 1], got {}.", self.risk_percentage));
+        }
+        if self.atr_period > 0 && self.atr_stop_multiple <= 0.0 {
+            return Err(format!("The ATR stop multiple must be positive, got {}.", self.atr_stop_multiple));
+        }
+        self.kline_interval()?;
+        self.market_symbol()?;
+        if self.symbol.is_some() {
+            self.date_range_ms()?;
+        }
+        self.margin().validate()
+    }
+
+    /// Returns the kline interval.
+    pub fn kline_interval(&self) -> Result<KlineInterval, String> {
+        self.interval.parse()
+    }
+
+    /// Returns the download range `(from, to)` in epoch milliseconds.
+    pub fn date_range_ms(&self) -> Result<(u64, u64), String> {
+        let from = self.from.as_deref().ok_or("Downloading klines requires a start date (--from).")?;
+        let from = parse_date_ms(from)?;
+        let to = match self.to.as_deref() {
+            Some(to) => parse_date_ms(to)?,
+            None => Utc::now().timestamp_millis() as u64,
+        };
+        if from >= to {
+            return Err(format!("The start date ({}) must be before the end date ({}).", from, to));
+        }
+        Ok((from, to))
+    }
+
+    /// Returns the ATR sizing of ATR-based stops, or `None` when stops are placed at the signal candle's low.
+    pub fn atr_risk(&self) -> Option<AtrRisk> {
+        (self.atr_period > 0).then_some(AtrRisk { risk_fraction: self.risk_percentage, atr_multiple: self.atr_stop_multiple })
+    }
+
+    /// Returns the position sizing: the `sizing` setting, or risking `risk_percentage` of the
+    /// balance over the stop (over the ATR stop with `atr_period`).
+    pub fn position_sizer(&self) -> PositionSizer {
+        match (self.sizing, self.atr_risk()) {
+            (Some(sizer), _) => sizer,
+            (None, Some(atr_risk)) => PositionSizer::AtrRisk(atr_risk),
+            (None, None) => PositionSizer::RiskPercent(RiskPercent { risk_fraction: self.risk_percentage }),
+        }
+    }
+
+    /// Returns the period of the ATR given to sizing policies: `atr_period`, or `DEFAULT_ATR_PERIOD`.
+    pub fn sizing_atr_period(&self) -> usize {
+        if self.atr_period > 0 { self.atr_period } else { DEFAULT_ATR_PERIOD }
+    }
+
+    /// Returns the margin settings of simulated positions.
+    pub fn margin(&self) -> MarginConfig {
+        MarginConfig { leverage: self.leverage, maintenance_margin_rate: self.maintenance_margin_rate }
+    }
+
+    /// Returns the symbol traded in the simulation: `symbol`, else `DEFAULT_SYMBOL`.
+    pub fn market_symbol(&self) -> Result<Symbol, String> {
+        Symbol::new(self.symbol.as_deref().unwrap_or(DEFAULT_SYMBOL))
+    }
+}
+
+/// Parses a UTC date ("2021-01-01"), date and time ("2021-01-01 08:00:00") or epoch milliseconds.
+pub fn parse_date_ms(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    if let Ok(ms) = value.parse::<u64>() {
+        return Ok(ms);
+    }
+    let datetime = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default()))
+        .map_err(|_| format!("Invalid date '{}': expected YYYY-MM-DD, YYYY-MM-DD HH:MM:SS or epoch milliseconds", value))?;
+    u64::try_from(datetime.and_utc().timestamp_millis()).map_err(|_| format!("Date '{}' is before 1970", value))
+}
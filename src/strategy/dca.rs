@@ -0,0 +1,483 @@
+// src/strategy/dca.rs
+
+//! This module implements a DCA (dollar-cost averaging) scale-in strategy, in the style of
+//! 3Commas DCA bots: a base order opens the position, and safety orders resting at increasing
+//! distances against it scale in on adverse moves, each larger than the previous one. A single
+//! take profit sits a configured distance beyond the average entry and is moved as safety
+//! orders fill; when it fills the cycle completes and a new one can start.
+//!
+//! `DcaEngine` holds the cycle and its PnL. It is driven by `DcaStrategy` in backtests (as a
+//! `Strategy`) and by `DcaManager` in live trading, which reacts to `ORDER_TRADE_UPDATE` events.
+
+use tracing::info;
+
+use super::{SimulatedFill, Strategy};
+use crate::market_event::Candle;
+use crate::order::bracket::BracketAction;
+use crate::order::{round_down_to_step, NewOrderRequest, OrderSide, OrderStatus, OrderType, PositionSide, TimeInForce};
+use crate::streams::FuturesOrderUpdate;
+
+/// Binance limits client order IDs to 36 characters; leave room for suffixes like `-c123s10`.
+const MAX_DCA_ID_LEN: usize = 20;
+
+/// Settings of a DCA bot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DcaConfig {
+    pub symbol: String,
+    pub side: OrderSide, // Buy for a long DCA bot, Sell for a short one
+    pub base_order_quantity: f64,
+    pub safety_order_quantity: f64, // Quantity of the first safety order
+    pub max_safety_orders: usize,
+    pub price_deviation: f64, // Distance of the first safety order from the base price, e.g. 0.01 for 1%
+    pub step_scale: f64, // Each further safety order's distance step is multiplied by this, e.g. 1.5
+    pub volume_scale: f64, // Each further safety order's quantity is multiplied by this, e.g. 2.0
+    pub take_profit: f64, // Take profit beyond the average entry, e.g. 0.015 for 1.5%
+    pub step_size: f64, // LOT_SIZE step used to round quantities (0.0 disables rounding)
+    pub tick_size: f64, // Prices are rounded down to this tick (0.0 disables rounding)
+    pub fee_rate: f64, // Fee per fill as a fraction of notional
+}
+
+impl Default for DcaConfig {
+    fn default() -> Self {
+        Self {
+            symbol: "BTCUSDT".to_string(),
+            side: OrderSide::Buy,
+            base_order_quantity: 0.001,
+            safety_order_quantity: 0.001,
+            max_safety_orders: 5,
+            price_deviation: 0.01,
+            step_scale: 1.5,
+            volume_scale: 2.0,
+            take_profit: 0.015,
+            step_size: 0.001,
+            tick_size: 0.1,
+            fee_rate: 0.0004,
+        }
+    }
+}
+
+impl DcaConfig {
+    /// Validates quantities and distances.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.base_order_quantity <= 0.0 || (self.max_safety_orders > 0 && self.safety_order_quantity <= 0.0) {
+            return Err("DCA order quantities must be positive.".to_string());
+        }
+        if self.price_deviation <= 0.0 || self.step_scale <= 0.0 || self.volume_scale <= 0.0 || self.take_profit <= 0.0 {
+            return Err("DCA price deviation, scales and take profit must be positive.".to_string());
+        }
+        if self.side == OrderSide::Buy && self.total_deviation() >= 1.0 {
+            return Err(format!("Last safety order is {:.1}% below the base price; reduce the deviation or scales.", self.total_deviation() * 100.0));
+        }
+        Ok(())
+    }
+
+    fn total_deviation(&self) -> f64 {
+        (0..self.max_safety_orders).map(|k| self.price_deviation * self.step_scale.powi(k as i32)).sum()
+    }
+
+    /// Returns the safety orders (price, quantity) for a cycle whose base order filled at `base_price`,
+    /// nearest first.
+    pub fn safety_orders(&self, base_price: f64) -> Vec<(f64, f64)> {
+        let mut deviation = 0.0;
+        (0..self.max_safety_orders).map(|k| {
+            deviation += self.price_deviation * self.step_scale.powi(k as i32);
+            let price = match self.side {
+                OrderSide::Buy => base_price * (1.0 - deviation),
+                OrderSide::Sell => base_price * (1.0 + deviation),
+            };
+            let quantity = self.safety_order_quantity * self.volume_scale.powi(k as i32);
+            (round_down_to_step(price, self.tick_size), round_down_to_step(quantity, self.step_size))
+        }).collect()
+    }
+
+    fn exit_side(&self) -> OrderSide {
+        match self.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        }
+    }
+
+    fn direction(&self) -> f64 {
+        match self.side {
+            OrderSide::Buy => 1.0,
+            OrderSide::Sell => -1.0,
+        }
+    }
+}
+
+/// An open DCA cycle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DcaCycle {
+    pub number: u32,
+    pub base_price: f64,
+    pub quantity: f64, // Total filled quantity (unsigned)
+    pub cost: f64, // Sum of price * quantity of all entry fills
+    pub safety_orders: Vec<(f64, f64)>, // (price, quantity), nearest first
+    pub safety_orders_filled: Vec<bool>,
+}
+
+impl DcaCycle {
+    /// Volume-weighted average entry price.
+    pub fn average_entry(&self) -> f64 {
+        if self.quantity > 0.0 { self.cost / self.quantity } else { 0.0 }
+    }
+}
+
+/// PnL of a DCA bot across cycles.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DcaStats {
+    pub realized_pnl: f64, // Net of fees
+    pub fees: f64,
+    pub cycles_completed: u32,
+    pub safety_orders_filled: u32,
+}
+
+/// A DCA bot's cycle and bookkeeping, independent of how orders are executed.
+#[derive(Debug, Clone)]
+pub struct DcaEngine {
+    config: DcaConfig,
+    cycle: Option<DcaCycle>,
+    cycles_started: u32,
+    stats: DcaStats,
+}
+
+impl DcaEngine {
+    /// Creates an idle engine.
+    pub fn new(config: DcaConfig) -> Result<Self, String> {
+        config.validate()?;
+        Ok(Self { config, cycle: None, cycles_started: 0, stats: DcaStats::default() })
+    }
+
+    /// Returns the settings.
+    pub fn config(&self) -> &DcaConfig {
+        &self.config
+    }
+
+    /// Returns the open cycle, if any.
+    pub fn cycle(&self) -> Option<&DcaCycle> {
+        self.cycle.as_ref()
+    }
+
+    /// Returns PnL across cycles.
+    pub fn stats(&self) -> &DcaStats {
+        &self.stats
+    }
+
+    fn charge_fee(&mut self, price: f64, quantity: f64) -> f64 {
+        let fee = price * quantity * self.config.fee_rate;
+        self.stats.fees += fee;
+        self.stats.realized_pnl -= fee;
+        fee
+    }
+
+    /// Opens a new cycle with the base order filled at `price`.
+    pub fn on_base_fill(&mut self, price: f64, quantity: f64) -> Result<&DcaCycle, String> {
+        if self.cycle.is_some() {
+            return Err("A DCA cycle is already open.".to_string());
+        }
+        self.cycles_started += 1;
+        let safety_orders = self.config.safety_orders(price);
+        self.cycle = Some(DcaCycle {
+            number: self.cycles_started,
+            base_price: price,
+            quantity,
+            cost: price * quantity,
+            safety_orders_filled: vec![false; safety_orders.len()],
+            safety_orders,
+        });
+        self.charge_fee(price, quantity);
+        Ok(self.cycle.as_ref().expect("cycle was just opened"))
+    }
+
+    /// Records a fill of safety order `index` and returns the new take profit price.
+    pub fn on_safety_fill(&mut self, index: usize, price: f64, quantity: f64) -> Option<f64> {
+        let cycle = self.cycle.as_mut()?;
+        if *cycle.safety_orders_filled.get(index)? {
+            return None;
+        }
+        cycle.safety_orders_filled[index] = true;
+        cycle.quantity += quantity;
+        cycle.cost += price * quantity;
+        self.stats.safety_orders_filled += 1;
+        self.charge_fee(price, quantity);
+        self.take_profit_price()
+    }
+
+    /// Returns the take profit price of the open cycle.
+    pub fn take_profit_price(&self) -> Option<f64> {
+        let cycle = self.cycle.as_ref()?;
+        let price = cycle.average_entry() * (1.0 + self.config.direction() * self.config.take_profit);
+        Some(round_down_to_step(price, self.config.tick_size))
+    }
+
+    /// Closes the open cycle at `price` and returns the PnL it realized, net of the exit fee.
+    pub fn on_take_profit_fill(&mut self, price: f64) -> Option<f64> {
+        let cycle = self.cycle.take()?;
+        let gross = (price - cycle.average_entry()) * cycle.quantity * self.config.direction();
+        self.stats.realized_pnl += gross;
+        let fee = self.charge_fee(price, cycle.quantity);
+        self.stats.cycles_completed += 1;
+        Some(gross - fee)
+    }
+
+    /// Unrealized PnL of the open cycle at `mark_price`.
+    pub fn unrealized_pnl(&self, mark_price: f64) -> f64 {
+        self.cycle.as_ref()
+            .map(|c| (mark_price - c.average_entry()) * c.quantity * self.config.direction())
+            .unwrap_or(0.0)
+    }
+}
+
+/// Backtestable DCA strategy.
+///
+/// A cycle starts with a market base order at the close of the first candle without an open cycle.
+/// Safety orders fill when a candle trades through their price, and the take profit when a candle
+/// reaches it. A green candle is assumed to trade to its low before its high, a red one the reverse.
+#[derive(Debug, Clone)]
+pub struct DcaStrategy {
+    engine: DcaEngine,
+}
+
+impl DcaStrategy {
+    /// Creates the strategy from a DCA configuration.
+    pub fn new(config: DcaConfig) -> Result<Self, String> {
+        Ok(Self { engine: DcaEngine::new(config)? })
+    }
+
+    /// Returns the underlying engine.
+    pub fn engine(&self) -> &DcaEngine {
+        &self.engine
+    }
+
+    fn fill_safety_orders(&mut self, candle: &Candle, fills: &mut Vec<SimulatedFill>) {
+        let Some(cycle) = self.engine.cycle() else { return };
+        let touched: Vec<(usize, f64, f64)> = cycle.safety_orders.iter().enumerate()
+            .filter(|(i, _)| !cycle.safety_orders_filled[*i])
+            .filter(|(_, (price, _))| match self.engine.config().side {
+                OrderSide::Buy => candle.low <= *price,
+                OrderSide::Sell => candle.high >= *price,
+            })
+            .map(|(i, (price, quantity))| (i, *price, *quantity))
+            .collect();
+        for (index, price, quantity) in touched {
+            let before = self.engine.stats().realized_pnl;
+            self.engine.on_safety_fill(index, price, quantity);
+            fills.push(SimulatedFill {
+                time_ms: candle.close_time,
+                side: self.engine.config().side,
+                price,
+                quantity,
+                realized_pnl: self.engine.stats().realized_pnl - before,
+            });
+        }
+    }
+
+    fn fill_take_profit(&mut self, candle: &Candle, fills: &mut Vec<SimulatedFill>) {
+        let (Some(price), Some(quantity)) = (self.engine.take_profit_price(), self.engine.cycle().map(|c| c.quantity)) else { return };
+        let reached = match self.engine.config().side {
+            OrderSide::Buy => candle.high >= price,
+            OrderSide::Sell => candle.low <= price,
+        };
+        if reached {
+            let realized_pnl = self.engine.on_take_profit_fill(price).unwrap_or(0.0);
+            fills.push(SimulatedFill { time_ms: candle.close_time, side: self.engine.config().exit_side(), price, quantity, realized_pnl });
+        }
+    }
+}
+
+impl Strategy for DcaStrategy {
+    fn name(&self) -> &str {
+        "dca"
+    }
+
+    fn on_candle(&mut self, candle: &Candle) -> Vec<SimulatedFill> {
+        let mut fills = Vec::new();
+        if self.engine.cycle().is_none() {
+            let quantity = self.engine.config().base_order_quantity;
+            let before = self.engine.stats().realized_pnl;
+            if self.engine.on_base_fill(candle.close, quantity).is_ok() {
+                let realized_pnl = self.engine.stats().realized_pnl - before;
+                fills.push(SimulatedFill { time_ms: candle.close_time, side: self.engine.config().side, price: candle.close, quantity, realized_pnl });
+            }
+            return fills;
+        }
+        let adverse_first = match self.engine.config().side {
+            OrderSide::Buy => candle.close >= candle.open,
+            OrderSide::Sell => candle.close < candle.open,
+        };
+        if adverse_first {
+            self.fill_safety_orders(candle, &mut fills);
+            self.fill_take_profit(candle, &mut fills);
+        } else {
+            self.fill_take_profit(candle, &mut fills);
+            self.fill_safety_orders(candle, &mut fills);
+        }
+        fills
+    }
+
+    fn pnl(&self, mark_price: f64) -> f64 {
+        self.engine.stats().realized_pnl + self.engine.unrealized_pnl(mark_price)
+    }
+}
+
+/// Runs a DCA bot live. Returns `BracketAction`s, which are executed with `bracket::execute_actions`.
+///
+/// Client order IDs are `{id}-c{cycle}b` for the base order, `{id}-c{cycle}s{n}` for safety orders and
+/// `{id}-c{cycle}t{n}` for the take profit, which is replaced after every safety order fill.
+#[derive(Debug, Clone)]
+pub struct DcaManager {
+    id: String,
+    engine: DcaEngine,
+    position_side: Option<PositionSide>, // `LONG`/`SHORT` in hedge mode
+    restart: bool, // Start a new cycle when the take profit fills
+    working_take_profit: Option<String>,
+    take_profit_sequence: u32,
+    stopped: bool,
+}
+
+impl DcaManager {
+    /// Creates a manager; `id` prefixes the client order IDs of the bot's orders.
+    pub fn new(id: &str, config: DcaConfig, position_side: Option<PositionSide>, restart: bool) -> Result<Self, String> {
+        if id.is_empty() || id.len() > MAX_DCA_ID_LEN {
+            return Err(format!("DCA bot ID must be 1-{} characters long.", MAX_DCA_ID_LEN));
+        }
+        Ok(Self {
+            id: id.to_string(),
+            engine: DcaEngine::new(config)?,
+            position_side,
+            restart,
+            working_take_profit: None,
+            take_profit_sequence: 0,
+            stopped: false,
+        })
+    }
+
+    /// Returns the underlying engine.
+    pub fn engine(&self) -> &DcaEngine {
+        &self.engine
+    }
+
+    fn cycle_prefix(&self, cycle: u32) -> String {
+        format!("{}-c{}", self.id, cycle)
+    }
+
+    fn with_position_side(&self, request: NewOrderRequest) -> NewOrderRequest {
+        match &self.position_side {
+            Some(ps) => request.position_side(ps.clone()),
+            None => request,
+        }
+    }
+
+    /// Starts a cycle with a market base order.
+    pub fn start(&mut self) -> Vec<BracketAction> {
+        if self.engine.cycle().is_some() {
+            return vec![];
+        }
+        self.stopped = false;
+        let config = self.engine.config();
+        let request = NewOrderRequest::new(&config.symbol, config.side, OrderType::Market)
+            .quantity(config.base_order_quantity)
+            .new_client_order_id(&format!("{}b", self.cycle_prefix(self.engine.cycles_started + 1)));
+        vec![BracketAction::PlaceOrder(self.with_position_side(request))]
+    }
+
+    fn take_profit_order(&mut self) -> Option<BracketAction> {
+        let price = self.engine.take_profit_price()?;
+        let cycle = self.engine.cycle()?;
+        self.take_profit_sequence += 1;
+        let client_order_id = format!("{}t{}", self.cycle_prefix(cycle.number), self.take_profit_sequence);
+        let config = self.engine.config();
+        let request = NewOrderRequest::new(&config.symbol, config.exit_side(), OrderType::Limit)
+            .quantity(round_down_to_step(cycle.quantity, config.step_size))
+            .price(price)
+            .time_in_force(TimeInForce::Gtc)
+            .new_client_order_id(&client_order_id);
+        // Exit orders must only close: reduceOnly in one-way mode, the position side in hedge mode
+        let request = match &self.position_side {
+            Some(ps @ (PositionSide::Long | PositionSide::Short)) => request.position_side(ps.clone()),
+            _ => request.reduce_only(true),
+        };
+        self.working_take_profit = Some(client_order_id);
+        Some(BracketAction::PlaceOrder(request))
+    }
+
+    fn cancel(&self, client_order_id: String) -> BracketAction {
+        BracketAction::CancelOrder { symbol: self.engine.config().symbol.clone(), client_order_id }
+    }
+
+    fn open_safety_order_ids(&self) -> Vec<String> {
+        let Some(cycle) = self.engine.cycle() else { return vec![] };
+        cycle.safety_orders_filled.iter().enumerate()
+            .filter(|(_, filled)| !**filled)
+            .map(|(i, _)| format!("{}s{}", self.cycle_prefix(cycle.number), i + 1))
+            .collect()
+    }
+
+    /// Stops the bot: cancels the open safety orders and the take profit. The position is left as is.
+    pub fn stop(&mut self) -> Vec<BracketAction> {
+        self.stopped = true;
+        let mut ids = self.open_safety_order_ids();
+        ids.extend(self.working_take_profit.take());
+        ids.into_iter().map(|id| self.cancel(id)).collect()
+    }
+
+    /// Processes an order update from the user data stream.
+    ///
+    /// # Returns
+    /// The actions to execute: safety orders and take profit after the base fill, a replaced take
+    /// profit after a safety fill, and cleanup (plus the next base order) after the take profit fill.
+    pub fn on_order_update(&mut self, update: &FuturesOrderUpdate) -> Vec<BracketAction> {
+        if self.stopped || update.order_status != OrderStatus::Filled {
+            return vec![];
+        }
+        let Some(suffix) = update.client_order_id.strip_prefix(&format!("{}-c", self.id)) else { return vec![] };
+        let price = update.average_price.parse::<f64>().unwrap_or(0.0);
+        let quantity = update.cumulative_filled_quantity.parse::<f64>().unwrap_or(0.0);
+
+        if suffix.ends_with('b') {
+            if self.engine.on_base_fill(price, quantity).is_err() {
+                return vec![];
+            }
+            let Some(cycle) = self.engine.cycle().cloned() else { return vec![] };
+            info!("DCA {} cycle {} opened at {}", self.id, cycle.number, price);
+            let config = self.engine.config().clone();
+            let mut actions: Vec<BracketAction> = cycle.safety_orders.iter().enumerate().map(|(i, (so_price, so_quantity))| {
+                let request = NewOrderRequest::new(&config.symbol, config.side, OrderType::Limit)
+                    .quantity(*so_quantity)
+                    .price(*so_price)
+                    .time_in_force(TimeInForce::Gtc)
+                    .new_client_order_id(&format!("{}s{}", self.cycle_prefix(cycle.number), i + 1));
+                BracketAction::PlaceOrder(self.with_position_side(request))
+            }).collect();
+            actions.extend(self.take_profit_order());
+            return actions;
+        }
+
+        let Some(cycle_number) = self.engine.cycle().map(|c| c.number) else { return vec![] };
+        let prefix = format!("{}", cycle_number);
+        let Some(kind) = suffix.strip_prefix(&prefix) else { return vec![] };
+
+        if let Some(index) = kind.strip_prefix('s').and_then(|n| n.parse::<usize>().ok()) {
+            if self.engine.on_safety_fill(index.saturating_sub(1), price, quantity).is_none() {
+                return vec![];
+            }
+            info!("DCA {} safety order {} filled at {}; new average entry {:.4}", self.id, index, price, self.engine.cycle().map(|c| c.average_entry()).unwrap_or(0.0));
+            let mut actions: Vec<BracketAction> = self.working_take_profit.take().map(|id| self.cancel(id)).into_iter().collect();
+            actions.extend(self.take_profit_order());
+            return actions;
+        }
+
+        if kind.starts_with('t') && self.working_take_profit.as_deref() == Some(update.client_order_id.as_str()) {
+            let remaining = self.open_safety_order_ids();
+            self.working_take_profit = None;
+            let realized = self.engine.on_take_profit_fill(price).unwrap_or(0.0);
+            info!("DCA {} cycle {} closed at {} (PnL {:.4})", self.id, cycle_number, price, realized);
+            let mut actions: Vec<BracketAction> = remaining.into_iter().map(|id| self.cancel(id)).collect();
+            if self.restart {
+                actions.extend(self.start());
+            }
+            return actions;
+        }
+        vec![]
+    }
+}
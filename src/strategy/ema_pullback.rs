@@ -0,0 +1,217 @@
+// src/strategy/ema_pullback.rs
+
+//! This module implements the EMA crossover pullback strategy of the candle backtest as an
+//! `engine::EventStrategy`, so the same code can be backtested, paper traded and traded live.
+//! On a closed candle in an uptrend (fast EMA above the slow one) that recovers above the fast EMA
+//! after closing below it, it buys at market with a reduce-only stop at the candle's low and a
+//! reduce-only take-profit at `risk_reward_ratio` times the stop distance. When one exit fills
+//! the others are cancelled. The quantity comes from a `risk::SizingPolicy` (the backtest's `sizing`
+//! with `from_config`), capped by the notional the leverage allows.
+//!
+//! `with_atr_stop` places the stop a multiple of ATR below the entry instead of at the candle's
+//! low, as the backtest does with `--atr-period`. The sizing policy is given the ATR (of
+//! `DEFAULT_ATR_PERIOD` candles without ATR stops), see `risk::AtrRisk` and `risk::VolatilityTarget`.
+//!
+//! When the liquidation price of the position (at the margin settings' leverage) lies above the
+//! stop, a reduce-only stop at the liquidation price closes the position there first, as the
+//! exchange would.
+//!
+//! The candle backtest (`strategy::run`) runs this strategy on the engine; `exit_reason` tells its
+//! report which exit closed a trade.
+
+use tracing::debug;
+
+use super::margin::MarginConfig;
+use super::config::DEFAULT_ATR_PERIOD;
+use super::report::ExitReason;
+use super::BacktestConfig;
+use crate::engine::{EventStrategy, FillEvent, OrderEvent, OrderKind, OrderRequest, StrategyContext};
+use crate::indicators::{Atr, Ema};
+use crate::market_event::{MarketEvent, Symbol};
+use crate::order::OrderSide;
+use crate::risk::{SizingContext, SizingPolicy};
+
+/// Client order IDs of the working bracket.
+#[derive(Debug, Clone, PartialEq)]
+struct Bracket {
+    stop_loss: String,
+    take_profit: String,
+    liquidation: Option<String>, // Stop at the liquidation price, when it lies above the stop loss
+}
+
+impl Bracket {
+    fn exits(&self) -> impl Iterator<Item = &String> {
+        [&self.stop_loss, &self.take_profit].into_iter().chain(&self.liquidation)
+    }
+}
+
+/// Returns the exit a fill of one of the strategy's orders closed the position with, or `None`
+/// for an entry.
+pub fn exit_reason(client_id: &str) -> Option<ExitReason> {
+    match client_id.rsplit('-').next() {
+        Some("sl") => Some(ExitReason::StopLoss),
+        Some("tp") => Some(ExitReason::TakeProfit),
+        Some("lq") => Some(ExitReason::Liquidation),
+        _ => None,
+    }
+}
+
+/// The EMA crossover pullback strategy for the event-driven engine.
+pub struct EmaPullback {
+    name: String,
+    symbol: Symbol,
+    slow_period: usize,
+    risk_reward_ratio: f64,
+    sizing: Box<dyn SizingPolicy>,
+    margin: MarginConfig,
+    fast: Ema,
+    slow: Ema,
+    atr: Atr,
+    atr_stop: Option<f64>, // Stop distance in ATRs, when stops are ATR-based
+    bars: usize,
+    previous: Option<(f64, Option<f64>)>, // Close and fast EMA of the previous candle
+    bracket: Option<Bracket>,
+    next_id: u64,
+}
+
+impl EmaPullback {
+    /// Creates the strategy for `symbol`'s closed candles with the given sizing policy.
+    pub fn new(symbol: Symbol, fast_period: usize, slow_period: usize, risk_reward_ratio: f64, sizing: Box<dyn SizingPolicy>) -> Self {
+        Self {
+            name: format!("{}/{} EMA Crossover", fast_period, slow_period),
+            symbol,
+            slow_period,
+            risk_reward_ratio,
+            sizing,
+            margin: MarginConfig::default(),
+            fast: Ema::new(fast_period),
+            slow: Ema::new(slow_period),
+            atr: Atr::new(DEFAULT_ATR_PERIOD),
+            atr_stop: None,
+            bars: 0,
+            previous: None,
+            bracket: None,
+            next_id: 0,
+        }
+    }
+
+    /// Creates the strategy with the periods, reward/risk ratio, position sizing, stops and leverage
+    /// of the candle backtest's settings.
+    pub fn from_config(symbol: Symbol, config: &BacktestConfig) -> Self {
+        let sizing = Box::new(config.position_sizer());
+        let strategy = Self::new(symbol, config.fast_ema_period, config.slow_ema_period, config.risk_reward_ratio, sizing).with_margin(config.margin());
+        match config.atr_risk() {
+            Some(atr_risk) => strategy.with_atr_stop(config.atr_period, atr_risk.atr_multiple),
+            None => strategy,
+        }
+    }
+
+    /// Places the stop `atr_multiple` ATRs (over `atr_period` candles) below the entry instead of
+    /// at the signal candle's low. No entry is made until the ATR is available.
+    pub fn with_atr_stop(mut self, atr_period: usize, atr_multiple: f64) -> Self {
+        self.atr = Atr::new(atr_period);
+        self.atr_stop = Some(atr_multiple);
+        self
+    }
+
+    /// Caps position sizes at the notional the margin settings allow.
+    pub fn with_margin(mut self, margin: MarginConfig) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    fn entry_orders(&mut self, close: f64, low: f64, ctx: &StrategyContext) -> Vec<OrderEvent> {
+        let atr = self.atr.value();
+        let stop = match (self.atr_stop, atr) {
+            (Some(multiple), Some(atr)) => close - multiple * atr,
+            (Some(_), None) => return vec![],
+            (None, _) => low,
+        };
+        let risk_per_unit = close - stop;
+        if risk_per_unit <= 0.0 {
+            return vec![];
+        }
+        let sizing = SizingContext { symbol: self.symbol.to_string(), price: close, equity: ctx.equity, stop_price: Some(stop), atr };
+        let quantity = match self.sizing.quantity(&sizing) {
+            Ok(quantity) => quantity.min(self.margin.max_quantity(ctx.equity, close)),
+            Err(e) => {
+                debug!("{}: no entry: {}", self.name, e);
+                return vec![];
+            }
+        };
+        if quantity <= 0.0 {
+            return vec![];
+        }
+        self.next_id += 1;
+        let id = format!("ema-{}-{}", ctx.time_ms, self.next_id);
+        let order = |suffix: &str, side: OrderSide, kind: OrderKind, reduce_only: bool| OrderEvent::Place(OrderRequest {
+            client_id: format!("{}-{}", id, suffix),
+            symbol: self.symbol,
+            side,
+            kind,
+            quantity,
+            reduce_only,
+        });
+        let mut orders = vec![order("en", OrderSide::Buy, OrderKind::Market, false)];
+        // Stops are matched in submission order, so the liquidation comes first
+        let liquidation_price = self.margin.open(OrderSide::Buy, close, quantity).liquidation_price();
+        let liquidation = (liquidation_price > stop).then(|| {
+            orders.push(order("lq", OrderSide::Sell, OrderKind::StopMarket(liquidation_price), true));
+            format!("{}-lq", id)
+        });
+        orders.push(order("sl", OrderSide::Sell, OrderKind::StopMarket(stop), true));
+        orders.push(order("tp", OrderSide::Sell, OrderKind::Limit(close + risk_per_unit * self.risk_reward_ratio), true));
+        self.bracket = Some(Bracket { stop_loss: format!("{}-sl", id), take_profit: format!("{}-tp", id), liquidation });
+        orders
+    }
+}
+
+impl EventStrategy for EmaPullback {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_market_event(&mut self, event: &MarketEvent, ctx: &StrategyContext) -> Vec<OrderEvent> {
+        let MarketEvent::Kline(candle) = event else { return vec![] };
+        if !candle.is_closed || candle.symbol != self.symbol {
+            return vec![];
+        }
+        let mut orders = Vec::new();
+        // A bracket without a position never got its entry filled (e.g. a risk policy rejected it)
+        if let Some(bracket) = self.bracket.take_if(|_| ctx.position == 0.0) {
+            orders.extend(bracket.exits().map(|client_id| OrderEvent::Cancel { symbol: self.symbol, client_id: client_id.clone() }));
+        }
+
+        self.atr.update(candle.high, candle.low, candle.close);
+        let fast = self.fast.update(candle.close);
+        let slow = self.slow.update(candle.close);
+        let previous = self.previous.replace((candle.close, fast));
+        self.bars += 1;
+        if self.bars <= self.slow_period || ctx.position != 0.0 {
+            return orders;
+        }
+        let (Some(fast), Some(slow), Some((previous_close, Some(previous_fast)))) = (fast, slow, previous) else { return orders };
+        let is_uptrend = fast > slow;
+        let pulled_back = previous_close < previous_fast;
+        let recovered = candle.close > fast;
+        if is_uptrend && pulled_back && recovered {
+            orders.extend(self.entry_orders(candle.close, candle.low, ctx));
+        }
+        orders
+    }
+
+    fn on_fill(&mut self, fill: &FillEvent, ctx: &StrategyContext) -> Vec<OrderEvent> {
+        let Some(bracket) = &self.bracket else { return vec![] };
+        if !bracket.exits().any(|client_id| *client_id == fill.client_id) {
+            return vec![];
+        }
+        if ctx.position != 0.0 {
+            return vec![]; // Partially closed: the exits keep working
+        }
+        let Some(bracket) = self.bracket.take() else { return vec![] };
+        bracket.exits()
+            .filter(|client_id| **client_id != fill.client_id)
+            .map(|client_id| OrderEvent::Cancel { symbol: self.symbol, client_id: client_id.clone() })
+            .collect()
+    }
+}
@@ -0,0 +1,333 @@
+// src/strategy/genetic.rs
+
+//! This module searches the settings of the EMA crossover backtest with a genetic algorithm, for
+//! parameter spaces too large to sweep as a grid (see `optimizer`). A population of settings is
+//! evolved over generations: parents are picked by tournament on the fitness (the chosen
+//! objective), their genes are mixed by uniform crossover and randomly mutated, and the fittest
+//! settings are carried over unchanged (elitism). Each generation is backtested in parallel and
+//! settings already backtested are not run again.
+//!
+//! `trading_bot genetic [backtest flags] --fast-range 5:50 --slow-range 20:200 --rr-bounds 1:5 --risk-bounds 0.005:0.02 [--population 40] [--generations 25] [--mutation-rate 0.2] [--elite 2] [--objective sharpe] [--seed 42]`
+//!
+//! `--output results.json` writes the best settings and the progress of every generation. Amounts
+//! are in the quote asset.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::optimizer::{self, GridResult};
+use super::walk_forward::{Objective, ParamRange};
+use super::{backtest_metrics_config, load_backtest_data, BacktestConfig};
+use crate::currency::ReportingCurrency;
+use crate::metrics::MetricsConfig;
+use crate::market_event::Candle;
+
+/// Parses inclusive bounds written "min:max".
+pub fn parse_bounds(value: &str) -> Result<(f64, f64), String> {
+    let (min, max) = value.split_once(':').ok_or_else(|| format!("Invalid bounds '{}': expected min:max", value))?;
+    let number = |part: &str| part.trim().parse::<f64>().map_err(|e| format!("Invalid bounds '{}': {}", value, e));
+    let (min, max) = (number(min)?, number(max)?);
+    if !(min > 0.0 && min <= max) {
+        return Err(format!("Invalid bounds '{}': the minimum must be positive and at most the maximum", value));
+    }
+    Ok((min, max))
+}
+
+/// Settings of the genetic optimizer and the bounds of the searched parameters.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GeneticConfig {
+    pub fast_range: ParamRange,
+    pub slow_range: ParamRange,
+    pub rr_bounds: (f64, f64),
+    pub risk_bounds: (f64, f64),
+    pub population: usize,
+    pub generations: usize,
+    pub mutation_rate: f64, // Probability of mutating each gene of a child
+    pub elite: usize, // Fittest settings carried over unchanged to the next generation
+    pub tournament_size: usize,
+    pub objective: Objective,
+    pub threads: usize, // 0 uses one thread per CPU core
+    pub top: usize,
+    pub seed: Option<u64>, // Makes the search reproducible
+}
+
+impl Default for GeneticConfig {
+    fn default() -> Self {
+        Self {
+            fast_range: ParamRange { start: 5, end: 50, step: 1 },
+            slow_range: ParamRange { start: 20, end: 200, step: 1 },
+            rr_bounds: (1.0, 5.0),
+            risk_bounds: (0.005, 0.02),
+            population: 40,
+            generations: 25,
+            mutation_rate: 0.2,
+            elite: 2,
+            tournament_size: 3,
+            objective: Objective::Sharpe,
+            threads: 0,
+            top: 10,
+            seed: None,
+        }
+    }
+}
+
+impl GeneticConfig {
+    /// Takes the genetic optimizer flags out of the command line and returns them with the remaining
+    /// (backtest) arguments.
+    pub fn from_args(args: &[String]) -> Result<(Self, Vec<String>), String> {
+        let mut config = Self::default();
+        let mut rest = Vec::new();
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let key = flag.strip_prefix("--").unwrap_or(flag);
+            let value = iter.next().ok_or_else(|| format!("Missing value for --{}", key))?;
+            let count = |value: &str| value.parse::<usize>().map_err(|e| format!("Invalid value '{}' for {}: {}", value, key, e));
+            match key {
+                "fast-range" => config.fast_range = ParamRange::parse(value)?,
+                "slow-range" => config.slow_range = ParamRange::parse(value)?,
+                "rr-bounds" => config.rr_bounds = parse_bounds(value)?,
+                "risk-bounds" => config.risk_bounds = parse_bounds(value)?,
+                "population" => config.population = count(value)?,
+                "generations" => config.generations = count(value)?,
+                "mutation-rate" => config.mutation_rate = value.parse().map_err(|e| format!("Invalid value '{}' for {}: {}", value, key, e))?,
+                "elite" => config.elite = count(value)?,
+                "tournament" => config.tournament_size = count(value)?,
+                "objective" => config.objective = Objective::parse(value)?,
+                "threads" => config.threads = count(value)?,
+                "top" => config.top = count(value)?,
+                "seed" => config.seed = Some(value.parse().map_err(|e| format!("Invalid value '{}' for {}: {}", value, key, e))?),
+                _ => rest.extend([flag.clone(), value.clone()]),
+            }
+        }
+        config.validate()?;
+        Ok((config, rest))
+    }
+
+    /// Validates the settings.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.population < 2 || self.generations == 0 {
+            return Err("The population needs at least two members and one generation.".to_string());
+        }
+        if self.elite >= self.population || self.tournament_size == 0 {
+            return Err(format!("The elite ({}) must be smaller than the population ({}) and the tournament non-empty.", self.elite, self.population));
+        }
+        if !(0.0..=1.0).contains(&self.mutation_rate) {
+            return Err(format!("The mutation rate must be in [0, 1], got {}.", self.mutation_rate));
+        }
+        if self.risk_bounds.1 > 1.0 {
+            return Err(format!("The risk percentage must be at most 1, got {}.", self.risk_bounds.1));
+        }
+        if self.slow_range.values().last().is_none_or(|&slowest| self.fast_range.start >= slowest) {
+            return Err("The fast and slow ranges contain no pair with a fast period below the slow one.".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// One member of the population: a point of the parameter space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Genome {
+    fast: usize,
+    slow: usize,
+    risk_reward_ratio: f64,
+    risk_percentage: f64,
+}
+
+impl Genome {
+    fn key(&self) -> (usize, usize, u64, u64) {
+        (self.fast, self.slow, self.risk_reward_ratio.to_bits(), self.risk_percentage.to_bits())
+    }
+}
+
+/// Draws and recombines genomes within the bounds of a `GeneticConfig`.
+struct Breeder<'a> {
+    config: &'a GeneticConfig,
+    fast_values: Vec<usize>,
+    slow_values: Vec<usize>,
+    rng: StdRng,
+}
+
+impl<'a> Breeder<'a> {
+    fn new(config: &'a GeneticConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+        Self { config, fast_values: config.fast_range.values(), slow_values: config.slow_range.values(), rng }
+    }
+
+    fn pick(&mut self, values: &[usize]) -> usize {
+        values[self.rng.random_range(0..values.len())]
+    }
+
+    fn random(&mut self) -> Genome {
+        let genome = Genome {
+            fast: self.fast_values[self.rng.random_range(0..self.fast_values.len())],
+            slow: self.slow_values[self.rng.random_range(0..self.slow_values.len())],
+            risk_reward_ratio: self.rng.random_range(self.config.rr_bounds.0..=self.config.rr_bounds.1),
+            risk_percentage: self.rng.random_range(self.config.risk_bounds.0..=self.config.risk_bounds.1),
+        };
+        self.repair(genome)
+    }
+
+    /// Redraws the slow period (or, failing that, the fast one) so the fast EMA is the faster one.
+    fn repair(&mut self, mut genome: Genome) -> Genome {
+        if genome.fast < genome.slow {
+            return genome;
+        }
+        let slower: Vec<usize> = self.slow_values.iter().copied().filter(|&slow| slow > genome.fast).collect();
+        if !slower.is_empty() {
+            genome.slow = self.pick(&slower);
+        } else {
+            // `validate` guarantees a fast period below the slowest one
+            genome.slow = self.slow_values[self.slow_values.len() - 1];
+            let faster: Vec<usize> = self.fast_values.iter().copied().filter(|&fast| fast < genome.slow).collect();
+            genome.fast = self.pick(&faster);
+        }
+        genome
+    }
+
+    /// Uniform crossover: every gene comes from either parent.
+    fn crossover(&mut self, a: &Genome, b: &Genome) -> Genome {
+        let rng = &mut self.rng;
+        Genome {
+            fast: if rng.random_bool(0.5) { a.fast } else { b.fast },
+            slow: if rng.random_bool(0.5) { a.slow } else { b.slow },
+            risk_reward_ratio: if rng.random_bool(0.5) { a.risk_reward_ratio } else { b.risk_reward_ratio },
+            risk_percentage: if rng.random_bool(0.5) { a.risk_percentage } else { b.risk_percentage },
+        }
+    }
+
+    /// Redraws each gene with the mutation rate's probability.
+    fn mutate(&mut self, mut genome: Genome) -> Genome {
+        let fresh = self.random();
+        let rate = self.config.mutation_rate;
+        if self.rng.random_bool(rate) { genome.fast = fresh.fast; }
+        if self.rng.random_bool(rate) { genome.slow = fresh.slow; }
+        if self.rng.random_bool(rate) { genome.risk_reward_ratio = fresh.risk_reward_ratio; }
+        if self.rng.random_bool(rate) { genome.risk_percentage = fresh.risk_percentage; }
+        self.repair(genome)
+    }
+
+    /// Picks the fittest of `tournament_size` random members of the ranked population.
+    fn select<'p>(&mut self, ranked: &'p [GridResult]) -> &'p GridResult {
+        let best = (0..self.config.tournament_size).map(|_| self.rng.random_range(0..ranked.len())).min().unwrap_or(0);
+        &ranked[best]
+    }
+}
+
+fn genome_of(result: &GridResult) -> Genome {
+    Genome {
+        fast: result.fast_ema_period,
+        slow: result.slow_ema_period,
+        risk_reward_ratio: result.risk_reward_ratio,
+        risk_percentage: result.risk_percentage,
+    }
+}
+
+/// Best and mean fitness of one generation.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best_score: f64,
+    pub mean_score: f64, // Over members with a finite score
+    pub evaluated: usize, // New settings backtested in this generation
+}
+
+/// The outcome of a genetic search.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GeneticReport {
+    pub genetic: GeneticConfig,
+    pub generations: Vec<GenerationStats>,
+    pub best: Vec<GridResult>, // Every distinct setting backtested, ranked
+}
+
+/// Loads the candles, runs the genetic search, prints the progress and ranking and writes `--output`
+/// if set.
+pub async fn run(config: &BacktestConfig, genetic: &GeneticConfig) -> Result<GeneticReport, Box<dyn Error>> {
+    println!("--- Starting Genetic Parameter Search ---");
+    if config.reporting_currency != ReportingCurrency::Usd {
+        return Err("Genetic search results are in the quote asset; --currency is not supported.".into());
+    }
+    genetic.validate()?;
+    let candles = load_backtest_data(config).await?;
+    if candles.len() <= genetic.slow_range.end {
+        return Err(format!("Not enough historical data for a {}-period EMA ({} candles).", genetic.slow_range.end, candles.len()).into());
+    }
+    optimizer::candidate(config, genetic.fast_range.start, genetic.slow_range.end, genetic.rr_bounds.1, genetic.risk_bounds.1).validate()?;
+
+    let metrics_config = backtest_metrics_config(config)?;
+    let report = evolve(&candles, config, genetic, &metrics_config);
+    let mut best = report.best.clone();
+    best.truncate(genetic.top);
+    optimizer::print_ranking(&best, genetic.top);
+    if let Some(path) = &config.output {
+        let json = serde_json::to_string_pretty(&report).map_err(|e| format!("Failed to serialize the genetic search results: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        println!("\nGenetic search results written to {}", path);
+    }
+    Ok(report)
+}
+
+/// Evolves the population over the configured number of generations.
+fn evolve(candles: &[Candle], config: &BacktestConfig, genetic: &GeneticConfig, metrics_config: &MetricsConfig) -> GeneticReport {
+    let threads = optimizer::GridSearchConfig { threads: genetic.threads, ..Default::default() }.thread_count();
+    let mut breeder = Breeder::new(genetic);
+    let mut cache: HashMap<(usize, usize, u64, u64), GridResult> = HashMap::new();
+    let mut population: Vec<Genome> = (0..genetic.population).map(|_| breeder.random()).collect();
+    let mut generations = Vec::with_capacity(genetic.generations);
+
+    for generation in 1..=genetic.generations {
+        // Backtest the settings not seen before, in parallel
+        let mut fresh: Vec<Genome> = Vec::new();
+        for genome in &population {
+            if !cache.contains_key(&genome.key()) && !fresh.iter().any(|f| f.key() == genome.key()) {
+                fresh.push(*genome);
+            }
+        }
+        let candidates: Vec<BacktestConfig> = fresh.iter()
+            .map(|g| optimizer::candidate(config, g.fast, g.slow, g.risk_reward_ratio, g.risk_percentage))
+            .collect();
+        for (genome, result) in fresh.iter().zip(optimizer::evaluate(candles, &candidates, metrics_config, genetic.objective, threads)) {
+            cache.insert(genome.key(), result);
+        }
+
+        let mut ranked: Vec<GridResult> = population.iter().filter_map(|g| cache.get(&g.key()).cloned()).collect();
+        optimizer::rank(&mut ranked);
+        let finite: Vec<f64> = ranked.iter().map(|r| r.score).filter(|s| s.is_finite()).collect();
+        let stats = GenerationStats {
+            generation,
+            best_score: ranked.first().map(|r| r.score).unwrap_or(f64::NEG_INFINITY),
+            mean_score: if finite.is_empty() { 0.0 } else { finite.iter().sum::<f64>() / finite.len() as f64 },
+            evaluated: fresh.len(),
+        };
+        println!("Generation {:>3}: best {:>10.3}, mean {:>10.3} ({} new settings)", stats.generation, stats.best_score, stats.mean_score, stats.evaluated);
+        generations.push(stats);
+        if generation == genetic.generations {
+            break;
+        }
+
+        // Breed the next generation: the elite carries over, the rest are mutated children
+        let mut next: Vec<Genome> = ranked.iter().take(genetic.elite).map(genome_of).collect();
+        while next.len() < genetic.population {
+            let (a, b) = (genome_of(breeder.select(&ranked)), genome_of(breeder.select(&ranked)));
+            let child = breeder.crossover(&a, &b);
+            next.push(breeder.mutate(child));
+        }
+        population = next;
+    }
+
+    let mut best: Vec<GridResult> = cache.into_values().collect();
+    // Rank by score, breaking ties by the settings so the order does not depend on the hash map
+    best.sort_by(|a, b| b.score.total_cmp(&a.score)
+        .then(a.fast_ema_period.cmp(&b.fast_ema_period))
+        .then(a.slow_ema_period.cmp(&b.slow_ema_period))
+        .then(a.risk_reward_ratio.total_cmp(&b.risk_reward_ratio))
+        .then(a.risk_percentage.total_cmp(&b.risk_percentage)));
+    GeneticReport { genetic: genetic.clone(), generations, best }
+}
@@ -0,0 +1,356 @@
+// src/strategy/grid.rs
+
+//! This module implements a grid trading strategy: buy limit orders are laid below a reference
+//! price and sell limit orders above it, at arithmetic or geometric spacing. When a level fills,
+//! the opposite order is re-armed one level away (a filled buy is followed by a sell one level up,
+//! and vice versa), so every buy/sell round trip captures one grid step.
+//!
+//! `GridEngine` holds the grid and its PnL. It is driven by `GridStrategy` in backtests (as a
+//! `Strategy`) and by `GridManager` in live trading, which maps the engine's orders to client
+//! order IDs and reacts to `ORDER_TRADE_UPDATE` events.
+
+use std::collections::HashMap;
+
+use tracing::info;
+
+use super::{SimulatedFill, Strategy};
+use crate::market_event::Candle;
+use crate::order::bracket::BracketAction;
+use crate::order::{round_down_to_step, NewOrderRequest, OrderSide, OrderStatus, OrderType, PositionSide, TimeInForce};
+use crate::streams::FuturesOrderUpdate;
+
+/// Binance limits client order IDs to 36 characters; leave room for the `-gN` suffix.
+const MAX_GRID_ID_LEN: usize = 24;
+
+/// Distance between neighbouring grid levels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridSpacing {
+    Arithmetic(f64), // Fixed price step, e.g. 100.0
+    Geometric(f64), // Fixed ratio, e.g. 0.01 for 1% between levels
+}
+
+/// Settings of a grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridConfig {
+    pub symbol: String,
+    pub reference_price: f64, // Centre of the grid; no order rests at this level initially
+    pub levels_per_side: usize,
+    pub spacing: GridSpacing,
+    pub quantity_per_level: f64,
+    pub tick_size: f64, // Level prices are rounded down to this tick (0.0 disables rounding)
+    pub fee_rate: f64, // Fee per fill as a fraction of notional, e.g. 0.0002 for the maker fee
+}
+
+impl GridConfig {
+    /// Returns the level prices from the lowest to the highest.
+    pub fn level_prices(&self) -> Result<Vec<f64>, String> {
+        if self.reference_price <= 0.0 || self.quantity_per_level <= 0.0 || self.levels_per_side == 0 {
+            return Err("Grid reference price, quantity and level count must be positive.".to_string());
+        }
+        let n = self.levels_per_side as i32;
+        let prices: Vec<f64> = (-n..=n).map(|offset| {
+            let price = match self.spacing {
+                GridSpacing::Arithmetic(step) => self.reference_price + step * offset as f64,
+                GridSpacing::Geometric(ratio) => self.reference_price * (1.0 + ratio).powi(offset),
+            };
+            round_down_to_step(price, self.tick_size)
+        }).collect();
+        if prices[0] <= 0.0 {
+            return Err(format!("Lowest grid level {} is not positive; reduce the spacing or level count.", prices[0]));
+        }
+        if prices.windows(2).any(|w| w[1] <= w[0]) {
+            return Err("Grid levels must be strictly increasing; increase the spacing.".to_string());
+        }
+        Ok(prices)
+    }
+}
+
+/// An order the grid wants resting at a level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridOrder {
+    pub level: usize, // Index into the level prices, lowest first
+    pub side: OrderSide,
+    pub price: f64,
+    pub quantity: f64,
+}
+
+/// PnL and inventory of a grid.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GridStats {
+    pub position: f64, // Signed base quantity; negative when short
+    pub average_entry: f64,
+    pub realized_pnl: f64, // Net of fees
+    pub fees: f64,
+    pub fills: usize,
+}
+
+impl GridStats {
+    /// Unrealized PnL of the inventory at `mark_price`.
+    pub fn unrealized_pnl(&self, mark_price: f64) -> f64 {
+        (mark_price - self.average_entry) * self.position
+    }
+
+    /// Applies a fill using average-cost accounting and returns the PnL it realized.
+    fn apply_fill(&mut self, side: OrderSide, price: f64, quantity: f64, fee_rate: f64) -> f64 {
+        let signed = match side {
+            OrderSide::Buy => quantity,
+            OrderSide::Sell => -quantity,
+        };
+        let mut realized = 0.0;
+        if self.position == 0.0 || self.position.signum() == signed.signum() {
+            let total = self.position.abs() + quantity;
+            self.average_entry = (self.average_entry * self.position.abs() + price * quantity) / total;
+        } else {
+            let closed = self.position.abs().min(quantity);
+            realized = closed * (price - self.average_entry) * self.position.signum();
+            if quantity > self.position.abs() {
+                self.average_entry = price; // Flipped to the other side
+            }
+        }
+        self.position += signed;
+        if self.position.abs() < 1e-12 {
+            self.position = 0.0;
+            self.average_entry = 0.0;
+        }
+        let fee = price * quantity * fee_rate;
+        self.fees += fee;
+        self.fills += 1;
+        self.realized_pnl += realized - fee;
+        realized - fee
+    }
+}
+
+/// The grid and its bookkeeping, independent of how orders are executed.
+#[derive(Debug, Clone)]
+pub struct GridEngine {
+    config: GridConfig,
+    prices: Vec<f64>,
+    orders: Vec<Option<OrderSide>>, // Order resting at each level, if any
+    stats: GridStats,
+}
+
+impl GridEngine {
+    /// Lays out the grid: buys below the reference price, sells above it.
+    pub fn new(config: GridConfig) -> Result<Self, String> {
+        let prices = config.level_prices()?;
+        let centre = config.levels_per_side;
+        let orders = (0..prices.len()).map(|level| match level.cmp(&centre) {
+            std::cmp::Ordering::Less => Some(OrderSide::Buy),
+            std::cmp::Ordering::Greater => Some(OrderSide::Sell),
+            std::cmp::Ordering::Equal => None,
+        }).collect();
+        Ok(Self { config, prices, orders, stats: GridStats::default() })
+    }
+
+    /// Returns the grid settings.
+    pub fn config(&self) -> &GridConfig {
+        &self.config
+    }
+
+    /// Returns the level prices, lowest first.
+    pub fn prices(&self) -> &[f64] {
+        &self.prices
+    }
+
+    /// Returns PnL and inventory.
+    pub fn stats(&self) -> &GridStats {
+        &self.stats
+    }
+
+    fn order_at(&self, level: usize, side: OrderSide) -> GridOrder {
+        GridOrder { level, side, price: self.prices[level], quantity: self.config.quantity_per_level }
+    }
+
+    /// Returns the orders that should be resting on the book.
+    pub fn working_orders(&self) -> Vec<GridOrder> {
+        self.orders.iter().enumerate()
+            .filter_map(|(level, side)| side.map(|s| self.order_at(level, s)))
+            .collect()
+    }
+
+    /// Records a fill of the order at `level` and returns the opposite order re-armed one level away.
+    pub fn on_fill(&mut self, level: usize, price: f64) -> Option<GridOrder> {
+        let side = self.orders.get_mut(level)?.take()?;
+        self.stats.apply_fill(side, price, self.config.quantity_per_level, self.config.fee_rate);
+        let next_level = match side {
+            OrderSide::Buy => level + 1,
+            OrderSide::Sell => level.checked_sub(1)?,
+        };
+        let opposite = match side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        match self.orders.get_mut(next_level) {
+            Some(slot @ None) => {
+                *slot = Some(opposite);
+                Some(self.order_at(next_level, opposite))
+            },
+            _ => None,
+        }
+    }
+
+    /// Removes the order at `level` without a fill (e.g. cancelled by the exchange).
+    pub fn remove_order(&mut self, level: usize) {
+        if let Some(slot) = self.orders.get_mut(level) {
+            *slot = None;
+        }
+    }
+}
+
+/// Backtestable grid strategy.
+///
+/// A candle fills every resting buy at or above its low and every resting sell at or below its
+/// high, at the level price. Orders re-armed by a fill only become active on the next candle.
+#[derive(Debug, Clone)]
+pub struct GridStrategy {
+    engine: GridEngine,
+}
+
+impl GridStrategy {
+    /// Creates the strategy from a grid configuration.
+    pub fn new(config: GridConfig) -> Result<Self, String> {
+        Ok(Self { engine: GridEngine::new(config)? })
+    }
+
+    /// Returns the underlying grid.
+    pub fn engine(&self) -> &GridEngine {
+        &self.engine
+    }
+}
+
+impl Strategy for GridStrategy {
+    fn name(&self) -> &str {
+        "grid"
+    }
+
+    fn on_candle(&mut self, candle: &Candle) -> Vec<SimulatedFill> {
+        let mut touched: Vec<GridOrder> = self.engine.working_orders().into_iter()
+            .filter(|o| match o.side {
+                OrderSide::Buy => candle.low <= o.price,
+                OrderSide::Sell => candle.high >= o.price,
+            })
+            .collect();
+        // Assume a green candle traded down to its low before its high, and a red candle the reverse
+        let low_first = candle.close >= candle.open;
+        touched.sort_by(|a, b| {
+            let rank = |o: &GridOrder| match (o.side, low_first) {
+                (OrderSide::Buy, true) | (OrderSide::Sell, false) => 0,
+                _ => 1,
+            };
+            rank(a).cmp(&rank(b)).then_with(|| match a.side {
+                OrderSide::Buy => b.price.total_cmp(&a.price), // Highest buy is reached first
+                OrderSide::Sell => a.price.total_cmp(&b.price), // Lowest sell is reached first
+            })
+        });
+
+        touched.into_iter().map(|order| {
+            let before = self.engine.stats().realized_pnl;
+            self.engine.on_fill(order.level, order.price);
+            SimulatedFill {
+                time_ms: candle.close_time,
+                side: order.side,
+                price: order.price,
+                quantity: order.quantity,
+                realized_pnl: self.engine.stats().realized_pnl - before,
+            }
+        }).collect()
+    }
+
+    fn pnl(&self, mark_price: f64) -> f64 {
+        let stats = self.engine.stats();
+        stats.realized_pnl + stats.unrealized_pnl(mark_price)
+    }
+}
+
+/// Runs a grid live: places the grid's orders and re-arms levels as fills arrive.
+/// Returns `BracketAction`s, which are executed with `bracket::execute_actions`.
+#[derive(Debug, Clone)]
+pub struct GridManager {
+    id: String,
+    engine: GridEngine,
+    position_side: Option<PositionSide>, // `LONG`/`SHORT` in hedge mode
+    working: HashMap<String, usize>, // Client order ID -> level
+    sequence: u32,
+    stopped: bool,
+}
+
+impl GridManager {
+    /// Creates a manager; `id` prefixes the client order IDs of the grid's orders.
+    pub fn new(id: &str, config: GridConfig, position_side: Option<PositionSide>) -> Result<Self, String> {
+        if id.is_empty() || id.len() > MAX_GRID_ID_LEN {
+            return Err(format!("Grid ID must be 1-{} characters long.", MAX_GRID_ID_LEN));
+        }
+        Ok(Self {
+            id: id.to_string(),
+            engine: GridEngine::new(config)?,
+            position_side,
+            working: HashMap::new(),
+            sequence: 0,
+            stopped: false,
+        })
+    }
+
+    /// Returns the underlying grid.
+    pub fn engine(&self) -> &GridEngine {
+        &self.engine
+    }
+
+    fn place(&mut self, order: GridOrder) -> BracketAction {
+        self.sequence += 1;
+        let client_order_id = format!("{}-g{}", self.id, self.sequence);
+        let mut request = NewOrderRequest::new(&self.engine.config().symbol, order.side, OrderType::Limit)
+            .quantity(order.quantity)
+            .price(order.price)
+            .time_in_force(TimeInForce::Gtc)
+            .new_client_order_id(&client_order_id);
+        if let Some(ps) = &self.position_side {
+            request = request.position_side(ps.clone());
+        }
+        self.working.insert(client_order_id, order.level);
+        BracketAction::PlaceOrder(request)
+    }
+
+    /// Places all orders of the grid.
+    pub fn start(&mut self) -> Vec<BracketAction> {
+        self.engine.working_orders().into_iter().map(|order| self.place(order)).collect()
+    }
+
+    /// Cancels all resting orders of the grid; the inventory is left as is.
+    pub fn stop(&mut self) -> Vec<BracketAction> {
+        self.stopped = true;
+        let symbol = self.engine.config().symbol.clone();
+        let mut ids: Vec<String> = self.working.drain().map(|(id, _)| id).collect();
+        ids.sort();
+        ids.into_iter().map(|client_order_id| BracketAction::CancelOrder { symbol: symbol.clone(), client_order_id }).collect()
+    }
+
+    /// Processes an order update from the user data stream.
+    ///
+    /// # Returns
+    /// The action re-arming the opposite order when a grid order filled, or nothing.
+    pub fn on_order_update(&mut self, update: &FuturesOrderUpdate) -> Vec<BracketAction> {
+        if self.stopped {
+            return vec![];
+        }
+        match &update.order_status {
+            OrderStatus::Filled => {
+                let Some(level) = self.working.remove(&update.client_order_id) else { return vec![] };
+                let price = update.average_price.parse::<f64>().ok()
+                    .filter(|p| *p > 0.0)
+                    .unwrap_or(self.engine.prices()[level]);
+                let rearmed = self.engine.on_fill(level, price);
+                let stats = self.engine.stats();
+                info!("Grid {} level {} filled at {} (position {}, realized PnL {:.4})", self.id, level, price, stats.position, stats.realized_pnl);
+                rearmed.map(|order| self.place(order)).into_iter().collect()
+            },
+            OrderStatus::Canceled | OrderStatus::Expired | OrderStatus::ExpiredInMatch | OrderStatus::Rejected => {
+                if let Some(level) = self.working.remove(&update.client_order_id) {
+                    info!("Grid {} order {} {}; level {} left empty", self.id, update.client_order_id, update.order_status, level);
+                    self.engine.remove_order(level);
+                }
+                vec![]
+            },
+            _ => vec![],
+        }
+    }
+}
@@ -0,0 +1,205 @@
+// src/strategy/html.rs
+
+//! This module renders a `BacktestReport` as a self-contained HTML page: the metrics table, the
+//! equity curve, the drawdown curve, the price with trade entry/exit markers and the trade list.
+//!
+//! Charts are inline SVG generated here, so the page has no scripts or external assets and can be
+//! archived or mailed as a single file.
+
+use std::fmt::Write;
+use std::fs;
+
+use chrono::DateTime;
+
+use super::report::{drawdown_curve, format_duration, BacktestReport, ExitReason};
+
+const CHART_WIDTH: f64 = 960.0;
+const CHART_HEIGHT: f64 = 260.0;
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_Y: f64 = 20.0;
+
+/// Escapes text for HTML content and attributes.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn format_time(time_ms: i64) -> String {
+    DateTime::from_timestamp_millis(time_ms).map(|dt| dt.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default()
+}
+
+fn format_option(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "n/a".to_string())
+}
+
+/// Maps data coordinates onto the plot area of a chart.
+struct Scale {
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+}
+
+impl Scale {
+    fn new(xs: &[f64], ys: &[f64]) -> Self {
+        let bounds = |values: &[f64]| values.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        let (min_x, max_x) = bounds(xs);
+        let (mut min_y, mut max_y) = bounds(ys);
+        if min_y == max_y {
+            min_y -= 1.0;
+            max_y += 1.0;
+        }
+        Self { min_x, max_x, min_y, max_y }
+    }
+
+    fn x(&self, value: f64) -> f64 {
+        let span = (self.max_x - self.min_x).max(f64::EPSILON);
+        MARGIN_LEFT + (value - self.min_x) / span * (CHART_WIDTH - MARGIN_LEFT - MARGIN_RIGHT)
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        MARGIN_Y + (self.max_y - value) / (self.max_y - self.min_y) * (CHART_HEIGHT - 2.0 * MARGIN_Y)
+    }
+}
+
+/// Renders a line chart of `ys` over the times `xs` as SVG; `extra` is drawn on top (e.g. markers).
+fn line_chart(title: &str, xs: &[f64], ys: &[f64], color: &str, label: impl Fn(f64) -> String, extra: impl Fn(&Scale) -> String) -> String {
+    if xs.is_empty() {
+        return format!("<h2>{}</h2><p>No data.</p>\n", escape(title));
+    }
+    let scale = Scale::new(xs, ys);
+    let points: Vec<String> = xs.iter().zip(ys).map(|(&x, &y)| format!("{:.1},{:.1}", scale.x(x), scale.y(y))).collect();
+    let mut svg = format!(
+        "<h2>{}</h2>\n<svg width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" xmlns=\"http://www.w3.org/2000/svg\">\n",
+        escape(title), w = CHART_WIDTH, h = CHART_HEIGHT,
+    );
+    let _ = writeln!(svg, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#ccc\"/>",
+        MARGIN_LEFT, MARGIN_Y, CHART_WIDTH - MARGIN_LEFT - MARGIN_RIGHT, CHART_HEIGHT - 2.0 * MARGIN_Y);
+    for value in [scale.max_y, scale.min_y] {
+        let _ = writeln!(svg, "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\" font-size=\"11\">{}</text>",
+            MARGIN_LEFT - 6.0, scale.y(value) + 4.0, escape(&label(value)));
+    }
+    let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" font-size=\"11\">{}</text>", MARGIN_LEFT, CHART_HEIGHT - 4.0, format_time(scale.min_x as i64));
+    let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" text-anchor=\"end\" font-size=\"11\">{}</text>",
+        CHART_WIDTH - MARGIN_RIGHT, CHART_HEIGHT - 4.0, format_time(scale.max_x as i64));
+    let _ = writeln!(svg, "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>", color, points.join(" "));
+    svg.push_str(&extra(&scale));
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn metrics_table(report: &BacktestReport) -> String {
+    let (s, r, currency) = (&report.summary, &report.risk_metrics, report.currency);
+    let rows = [
+        ("Total Trades", s.total_trades.to_string()),
+        ("Winning / Losing Trades", format!("{} / {}", s.winning_trades, s.losing_trades)),
+        ("Win Rate", format!("{:.2}%", s.win_rate * 100.0)),
+        ("Net Profit/Loss", currency.format(s.net_pnl)),
+        ("Profit Factor", format_option(s.profit_factor)),
+        ("Avg. R/R Ratio", format_option(s.realized_rr_ratio)),
+        ("Expectancy per Trade", currency.format(s.expectancy)),
+        ("Avg. Holding Time", s.avg_holding_ms.map(format_duration).unwrap_or_else(|| "n/a".to_string())),
+        ("Max Drawdown", format!("{:.2}%", s.max_drawdown * 100.0)),
+        ("Longest Losing Streak", s.max_consecutive_losses.to_string()),
+        ("Starting Balance", currency.format(s.starting_balance)),
+        ("Final Balance", currency.format(s.final_balance)),
+        ("Total Return", format!("{:.2}%", r.total_return * 100.0)),
+        ("CAGR", format!("{:.2}%", r.annualized_return * 100.0)),
+        ("Annualized Volatility", format!("{:.2}%", r.annualized_volatility * 100.0)),
+        ("Sharpe Ratio", format_option(r.sharpe_ratio)),
+        ("Sortino Ratio", format_option(r.sortino_ratio)),
+        ("Max Drawdown (equity)", format!("{:.2}%", r.max_drawdown * 100.0)),
+        ("Calmar Ratio", format_option(r.calmar_ratio)),
+        ("Benchmark Return", r.benchmark_return.map(|v| format!("{:.2}%", v * 100.0)).unwrap_or_else(|| "n/a".to_string())),
+        ("Alpha / Beta", format!("{} / {}", format_option(r.alpha), format_option(r.beta))),
+    ];
+    let mut html = String::from("<h2>Metrics</h2>\n<table>\n");
+    for (name, value) in rows {
+        let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, escape(&value));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn monte_carlo_table(report: &BacktestReport) -> String {
+    let Some(mc) = &report.monte_carlo else { return String::new() };
+    let currency = report.currency;
+    let mut html = format!("<h2>Monte Carlo ({} paths)</h2>\n<table>\n<tr><th></th><th>5th pct.</th><th>Median</th><th>95th pct.</th></tr>\n", mc.runs);
+    let (e, d) = (&mc.final_equity, &mc.max_drawdown);
+    let _ = writeln!(html, "<tr><th>Final Equity</th><td>{}</td><td>{}</td><td>{}</td></tr>",
+        escape(&currency.format(e.p5)), escape(&currency.format(e.p50)), escape(&currency.format(e.p95)));
+    let _ = writeln!(html, "<tr><th>Max Drawdown</th><td>{:.2}%</td><td>{:.2}%</td><td>{:.2}%</td></tr>", d.p5 * 100.0, d.p50 * 100.0, d.p95 * 100.0);
+    let _ = writeln!(html, "<tr><th>Probability of Loss</th><td colspan=\"3\">{:.1}%</td></tr>", mc.probability_of_loss * 100.0);
+    html.push_str("</table>\n");
+    html
+}
+
+fn trades_table(report: &BacktestReport) -> String {
+    let mut html = String::from("<h2>Trades</h2>\n<table>\n<tr><th>Entry</th><th>Exit</th><th>Entry Price</th><th>Exit Price</th><th>Quantity</th><th>Fee</th><th>P/L</th><th>MAE</th><th>MFE</th><th>Exit Reason</th></tr>\n");
+    for t in &report.trades {
+        let reason = match t.exit_reason {
+            ExitReason::StopLoss => "stop loss",
+            ExitReason::TakeProfit => "take profit",
+            ExitReason::Liquidation => "liquidation",
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{:.2}</td><td>{:.6}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            format_time(t.entry_time_ms), format_time(t.exit_time_ms), t.entry_price, t.exit_price, t.quantity,
+            escape(&report.currency.format(t.fee)), if t.pnl >= 0.0 { "win" } else { "loss" }, escape(&report.currency.format(t.pnl)),
+            escape(&report.currency.format(t.mae)), escape(&report.currency.format(t.mfe)), reason,
+        );
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// Renders the report as a self-contained HTML page.
+pub fn render_html(report: &BacktestReport) -> String {
+    let times: Vec<f64> = report.equity_curve.iter().map(|p| p.time_ms as f64).collect();
+    let equity: Vec<f64> = report.equity_curve.iter().map(|p| p.equity).collect();
+    let prices: Vec<f64> = report.equity_curve.iter().map(|p| p.price).collect();
+    let drawdown: Vec<f64> = drawdown_curve(&report.equity_curve).iter().map(|d| -d * 100.0).collect();
+    let no_markers = |_: &Scale| String::new();
+
+    let trade_markers = |scale: &Scale| {
+        let mut svg = String::new();
+        for t in &report.trades {
+            let (x, y) = (scale.x(t.entry_time_ms as f64), scale.y(t.entry_price));
+            let _ = writeln!(svg, "<path d=\"M{:.1},{:.1} l-5,9 h10 z\" fill=\"#2a7\"><title>Entry {} @ {:.2}</title></path>",
+                x, y, format_time(t.entry_time_ms), t.entry_price);
+            let color = if t.pnl >= 0.0 { "#27c" } else { "#d33" };
+            let _ = writeln!(svg, "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"4\" fill=\"{}\"><title>Exit {} @ {:.2}</title></circle>",
+                scale.x(t.exit_time_ms as f64), scale.y(t.exit_price), color, format_time(t.exit_time_ms), t.exit_price);
+        }
+        svg
+    };
+
+    let mut html = String::new();
+    let title = format!("Backtest: {}", report.strategy);
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: sans-serif; margin: 24px; }}\n\
+         table {{ border-collapse: collapse; margin-bottom: 16px; }}\n\
+         th, td {{ border: 1px solid #ddd; padding: 4px 10px; text-align: right; }}\n\
+         th {{ background: #f4f4f4; text-align: left; }}\n\
+         .win {{ color: #27c; }} .loss {{ color: #d33; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>\n<p>Data: {} &middot; Amounts in {}</p>\n",
+        escape(&title), escape(&title),
+        escape(report.config.symbol.as_deref().unwrap_or(&report.config.data_path)), report.currency.code(),
+    );
+    html.push_str(&metrics_table(report));
+    html.push_str(&line_chart("Equity Curve", &times, &equity, "#27c", |v| format!("{:.2}", v), no_markers));
+    html.push_str(&line_chart("Drawdown", &times, &drawdown, "#d33", |v| format!("{:.1}%", v), no_markers));
+    html.push_str(&line_chart("Price and Trades", &times, &prices, "#555", |v| format!("{:.2}", v), trade_markers));
+    html.push_str(&monte_carlo_table(report));
+    html.push_str(&trades_table(report));
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Writes the report as an HTML page to `path`.
+pub fn write_html(report: &BacktestReport, path: &str) -> Result<(), String> {
+    fs::write(path, render_html(report)).map_err(|e| format!("Failed to write {}: {}", path, e))
+}
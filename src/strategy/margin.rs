@@ -0,0 +1,97 @@
+// src/strategy/margin.rs
+
+//! This module provides the margin math of USDⓈ-M futures used by the backtester: initial margin
+//! from the leverage, the maximum position size the equity allows, and the liquidation price of an
+//! isolated-margin position. A backtest that ignores it treats a leveraged futures position like
+//! cash-settled spot and never gets liquidated.
+//!
+//! The liquidation price follows Binance's isolated-margin formula for a single position:
+//! long: `(entry * qty - margin) / (qty * (1 - mmr))`, short: `(entry * qty + margin) / (qty * (1 + mmr))`,
+//! where `margin` is the isolated margin and `mmr` the maintenance margin rate (the maintenance
+//! amount of the first bracket is zero).
+
+use crate::order::OrderSide;
+
+/// Leverage and maintenance margin settings of a simulated position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginConfig {
+    pub leverage: f64, // e.g. 20.0
+    pub maintenance_margin_rate: f64, // e.g. 0.004 for 0.4% (BTCUSDT, first bracket)
+}
+
+impl Default for MarginConfig {
+    fn default() -> Self {
+        Self { leverage: 20.0, maintenance_margin_rate: 0.004 }
+    }
+}
+
+impl MarginConfig {
+    /// Validates the settings.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1.0..=125.0).contains(&self.leverage) {
+            return Err(format!("Leverage must be between 1 and 125, got {}.", self.leverage));
+        }
+        if !(0.0..1.0 / self.leverage).contains(&self.maintenance_margin_rate) {
+            return Err(format!("The maintenance margin rate {} must be below the initial margin rate {}.", self.maintenance_margin_rate, 1.0 / self.leverage));
+        }
+        Ok(())
+    }
+
+    /// Initial margin of a position with the given notional.
+    pub fn initial_margin(&self, notional: f64) -> f64 {
+        notional.abs() / self.leverage
+    }
+
+    /// Largest quantity whose initial margin fits in `equity` at `price`.
+    pub fn max_quantity(&self, equity: f64, price: f64) -> f64 {
+        if price <= 0.0 { 0.0 } else { (equity * self.leverage / price).max(0.0) }
+    }
+
+    /// Opens an isolated-margin position funded with its initial margin.
+    pub fn open(&self, side: OrderSide, entry_price: f64, quantity: f64) -> IsolatedPosition {
+        IsolatedPosition {
+            side,
+            entry_price,
+            quantity,
+            margin: self.initial_margin(entry_price * quantity),
+            maintenance_margin_rate: self.maintenance_margin_rate,
+        }
+    }
+}
+
+/// A simulated isolated-margin position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IsolatedPosition {
+    pub side: OrderSide, // Buy for long, Sell for short
+    pub entry_price: f64,
+    pub quantity: f64,
+    pub margin: f64, // Isolated margin; all of it is lost on liquidation
+    pub maintenance_margin_rate: f64,
+}
+
+impl IsolatedPosition {
+    /// Price at which the position is liquidated.
+    pub fn liquidation_price(&self) -> f64 {
+        let (qty, mmr) = (self.quantity, self.maintenance_margin_rate);
+        match self.side {
+            OrderSide::Buy => ((self.entry_price * qty - self.margin) / (qty * (1.0 - mmr))).max(0.0),
+            OrderSide::Sell => (self.entry_price * qty + self.margin) / (qty * (1.0 + mmr)),
+        }
+    }
+
+    /// Returns true when a candle with this low and high reaches the liquidation price.
+    pub fn is_liquidated(&self, low: f64, high: f64) -> bool {
+        match self.side {
+            OrderSide::Buy => low <= self.liquidation_price(),
+            OrderSide::Sell => high >= self.liquidation_price(),
+        }
+    }
+
+    /// Unrealized PnL at `price`.
+    pub fn unrealized_pnl(&self, price: f64) -> f64 {
+        match self.side {
+            OrderSide::Buy => (price - self.entry_price) * self.quantity,
+            OrderSide::Sell => (self.entry_price - price) * self.quantity,
+        }
+    }
+}
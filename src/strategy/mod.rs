@@ -1,14 +1,41 @@
 use serde::Deserialize;
 use std::error::Error;
+use std::collections::VecDeque;
 use std::fs::File;
+use std::path::Path;
 use std::cmp::max;
+use chrono::NaiveDateTime;
+use crate::session::{self, SessionTagger, SessionTags};
+use crate::metrics::{self, MetricsConfig, RiskMetrics};
+use crate::market_event;
+use crate::order::OrderSide;
+use crate::currency::{CurrencyConverter, ReportingCurrency};
+use crate::market_data::Candlestick;
+use crate::rest_api::RestClient;
+use crate::data::columnar;
+use crate::engine::{Engine, FillEvent, OrderKind, SimulatedExecution};
+use crate::market_event::MarketEvent;
+use ema_pullback::EmaPullback;
+use report::{EquityPoint, ExitReason, PerformanceSummary, TradeRecord};
 
-// --- Configuration ---
-const FAST_EMA_PERIOD: usize = 21;
-const SLOW_EMA_PERIOD: usize = 55;
-const RISK_REWARD_RATIO: f64 = 3.0; // Target a profit of 3x our risk.
-const ACCOUNT_BALANCE: f64 = 5000.0; // Starting account balance for simulation.
-const RISK_PERCENTAGE: f64 = 0.01; // We risk 1% of our account on each trade.
+pub mod grid;
+pub mod dca;
+pub mod rsi;
+pub mod timeframe;
+pub mod config;
+pub mod margin;
+pub mod timing;
+pub mod report;
+pub mod html;
+pub mod monte_carlo;
+pub mod walk_forward;
+pub mod optimizer;
+pub mod genetic;
+pub mod ema_pullback;
+pub mod ticks;
+
+pub use config::BacktestConfig;
+pub use report::BacktestReport;
 
 /// Represents a single candlestick data point from the official Binance CSV.
 #[derive(Debug, Deserialize)]
@@ -40,154 +67,370 @@ struct Candle {
 }
 
 
-/// Represents an active trade, holding all necessary information.
+/// A trade the engine has opened, tracked for the report.
 #[derive(Debug)]
-struct Trade {
+struct OpenTrade {
     entry_price: f64,
-    stop_loss: f64,
-    take_profit: f64,
-    position_size_btc: f64,
-    risk_amount_usd: f64,
+    quantity: f64,
+    entry_fee: f64,
+    tags: Option<SessionTags>, // Session metadata captured at entry
+    entry_time_ms: i64,
+    lowest_price: f64, // Lowest and highest price seen while open, for MAE/MFE
+    highest_price: f64,
+}
+
+/// A fill simulated by a backtested strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedFill {
+    pub time_ms: u64,
+    pub side: OrderSide,
+    pub price: f64,
+    pub quantity: f64,
+    pub realized_pnl: f64, // PnL realized by this fill, net of fees
+}
+
+/// A strategy that can be backtested on closed candles.
+pub trait Strategy {
+    /// Short name used in reports.
+    fn name(&self) -> &str;
+    /// Processes a closed candle and returns the fills it caused.
+    fn on_candle(&mut self, candle: &market_event::Candle) -> Vec<SimulatedFill>;
+    /// Evaluates a still-forming candle, for strategies that act intrabar (see `timing`).
+    /// Indicators must not be advanced here; the candle is delivered again to `on_candle` once closed.
+    fn on_forming_candle(&mut self, _candle: &market_event::Candle) -> Vec<SimulatedFill> {
+        vec![]
+    }
+    /// Total PnL (realized and unrealized) when marked at `mark_price`.
+    fn pnl(&self, mark_price: f64) -> f64;
+}
+
+/// Result of backtesting a `Strategy`.
+#[derive(Debug, Clone, Default)]
+pub struct BacktestResult {
+    pub fills: Vec<SimulatedFill>,
+    pub equity_curve: Vec<f64>, // Mark-to-market equity at every candle close
+    pub final_equity: f64,
+    pub risk_metrics: RiskMetrics,
+}
+
+/// Backtests a strategy on closed candles, marking equity to market at every close.
+pub fn backtest(strategy: &mut impl Strategy, candles: &[market_event::Candle], starting_equity: f64, metrics_config: &MetricsConfig) -> BacktestResult {
+    backtest_stream(strategy, candles.iter().copied(), starting_equity, metrics_config)
+}
+
+/// Backtests a strategy on a stream of candles, e.g. read chunk by chunk from a columnar file
+/// (`data::columnar::ChunkReader::candles`), without holding all candles in memory.
+pub fn backtest_stream(strategy: &mut impl Strategy, candles: impl Iterator<Item = market_event::Candle>, starting_equity: f64, metrics_config: &MetricsConfig) -> BacktestResult {
+    let mut result = BacktestResult { final_equity: starting_equity, ..Default::default() };
+    let mut closes = Vec::new();
+    for candle in candles.filter(|c| c.is_closed) {
+        result.fills.extend(strategy.on_candle(&candle));
+        result.equity_curve.push(starting_equity + strategy.pnl(candle.close));
+        closes.push(candle.close);
+    }
+    if let Some(&equity) = result.equity_curve.last() {
+        result.final_equity = equity;
+    }
+    result.risk_metrics = metrics::compute_risk_metrics(&result.equity_curve, Some(&closes), metrics_config);
+    result
 }
 
 /// Main function to orchestrate the backtest.
-pub fn run() -> Result<(), Box<dyn Error>> {
+pub async fn run(config: &BacktestConfig) -> Result<(), Box<dyn Error>> {
+    config.validate()?;
     println!("--- Starting Backtest (Full Metrics) ---");
-    println!("Strategy: {}/{} EMA Crossover, {} a:1 Reward/Risk", FAST_EMA_PERIOD, SLOW_EMA_PERIOD, RISK_REWARD_RATIO);
-    println!("Risk per trade: {}%", RISK_PERCENTAGE * 100.0);
+    println!("Strategy: {}/{} EMA Crossover, {} a:1 Reward/Risk", config.fast_ema_period, config.slow_ema_period, config.risk_reward_ratio);
+    println!("Risk per trade: {}%", config.risk_percentage * 100.0);
     println!("------------------------------------------------");
 
-    // 1. Load historical data from a CSV file.
-    let candles = load_data("./btc_4h_data_2018_to_2025.csv")?;
-    if candles.len() <= SLOW_EMA_PERIOD {
-        panic!("Not enough historical data to perform the backtest.");
+    // 1. Load historical data from Binance when a symbol is set, otherwise from the data file.
+    // Parquet files are streamed into the simulation chunk by chunk instead of loaded whole.
+    let (candles, reader) = match &config.symbol {
+        None if columnar::is_columnar(&config.data_path) => (Vec::new(), Some(columnar::ChunkReader::open(Path::new(&config.data_path))?)),
+        _ => (load_backtest_data(config).await?, None),
+    };
+    let bars = reader.as_ref().map_or(candles.len(), |reader| reader.rows());
+    if bars <= config.slow_ema_period {
+        return Err(format!("Not enough historical data to perform the backtest ({} candles).", bars).into());
     }
+    let (start_ms, end_ms) = match &reader {
+        Some(reader) => reader.time_range()?.unwrap_or_default(),
+        None => candle_time_range(&candles),
+    };
 
-    // 2. Calculate the EMAs for the entire dataset.
-    let closes: Vec<f64> = candles.iter().map(|c| c.close).collect();
-    let fast_emas = calculate_ema(&closes, FAST_EMA_PERIOD);
-    let slow_emas = calculate_ema(&closes, SLOW_EMA_PERIOD);
+    let ticks = match &config.agg_trades_path {
+        Some(path) => {
+            let ticks = ticks::load_agg_trades(path, config.symbol.as_deref().unwrap_or(config::DEFAULT_SYMBOL))?;
+            println!("Resolving exits from {} aggregated trades", ticks.len());
+            ticks
+        },
+        None => Vec::new(),
+    };
 
-    // 3. Run the backtesting simulation.
-    run_simulation(&candles, &fast_emas, &slow_emas);
+    // 2. Run the backtesting simulation.
+    let metrics_config = backtest_metrics_config(config)?;
+    let converter = load_converter(config, start_ms, end_ms).await?;
+    let mut read_error = None;
+    let report = match reader {
+        Some(reader) => {
+            let candles = reader.candles().map_while(|candle| candle.map_err(|e| read_error = Some(e)).ok());
+            run_simulation(candles, &ticks, &metrics_config, config, &converter, true)
+        },
+        None => run_simulation(candles, &ticks, &metrics_config, config, &converter, true),
+    };
+    if let Some(e) = read_error {
+        return Err(e.into());
+    }
+    if let Some(path) = &config.output {
+        report.write_json(path)?;
+        println!("Backtest report written to {}", path);
+    }
+    if let Some(path) = &config.trades_output {
+        report::write_trades_csv(&report.trades, path)?;
+        println!("Trade log written to {}", path);
+    }
+    if let Some(path) = &config.html_output {
+        html::write_html(&report, path)?;
+        println!("HTML report written to {}", path);
+    }
 
     Ok(())
 }
 
-/// Executes the main trading simulation loop.
-fn run_simulation(candles: &[Candle], fast_emas: &[f64], slow_emas: &[f64]) {
-    let mut current_trade: Option<Trade> = None;
-    let mut balance = ACCOUNT_BALANCE;
+/// Loads the backtest's candles from Binance when a symbol is set, otherwise from the data file.
+async fn load_backtest_data(config: &BacktestConfig) -> Result<Vec<market_event::Candle>, Box<dyn Error>> {
+    let symbol = config.market_symbol()?;
+    match &config.symbol {
+        Some(name) => Ok(fetch_data(config, name).await?.iter().map(|c| market_candle(c, symbol)).collect()),
+        None => load_candles(&config.data_path, symbol.as_str()),
+    }
+}
+
+/// Returns the open time of the first candle and the close time of the last one.
+fn candle_time_range(candles: &[market_event::Candle]) -> (u64, u64) {
+    (candles.first().map_or(0, |c| c.open_time), candles.last().map_or(0, |c| c.close_time))
+}
+
+/// Risk-free rate and benchmark come from RISK_FREE_RATE / BENCHMARK; the data is sampled every `interval`.
+fn backtest_metrics_config(config: &BacktestConfig) -> Result<MetricsConfig, String> {
+    let periods_per_year = metrics::MS_PER_YEAR / config.kline_interval()?.duration_ms() as f64;
+    MetricsConfig::from_env(periods_per_year)
+}
+
+/// Runs the EMA crossover strategy (`ema_pullback::EmaPullback`) on the event-driven engine with
+/// simulated execution, exactly as it would be paper traded, and returns the results as a report.
+/// While a position is open, the bar's trades in `ticks` (aggregated trades sorted by time) are
+/// replayed before the bar, so the exit that was actually reached first closes it, see `ticks`.
+/// The candles are consumed one at a time, so they can be streamed from a file.
+/// With `verbose`, every trade and the report sections are printed as the simulation runs.
+fn run_simulation(candles: impl IntoIterator<Item = market_event::Candle>, ticks: &[market_event::Trade], metrics_config: &MetricsConfig, config: &BacktestConfig, converter: &CurrencyConverter, verbose: bool) -> BacktestReport {
+    let symbol = config.market_symbol()
+        .unwrap_or_else(|_| market_event::Symbol::new(config::DEFAULT_SYMBOL).expect("valid symbol"));
+    let mut engine = Engine::new(EmaPullback::from_config(symbol, config), SimulatedExecution::new(config.fee_rate), config.account_balance);
+    let candles = candles.into_iter();
+    let bars = candles.size_hint().0;
+    // Closes of the volatility lookback, ending with the current bar
+    let mut closes: VecDeque<f64> = VecDeque::with_capacity(config.volatility_lookback + 1);
+    let (mut first_time_ms, mut last_time_ms) = (None, None);
+    let margin_config = config.margin();
+
+    let mut current_trade: Option<OpenTrade> = None;
+    let mut balance = config.account_balance;
     
     // Performance metrics
     let mut trade_history: Vec<f64> = Vec::new();
-    let mut peak_balance = ACCOUNT_BALANCE;
+    let mut trades: Vec<TradeRecord> = Vec::new();
+    let mut tagged_trades: Vec<(SessionTags, f64)> = Vec::new();
+    let tagger = SessionTagger::default();
+    let mut peak_balance = config.account_balance;
     let mut max_drawdown = 0.0;
+    // Mark-to-market equity and instrument price at every bar, for risk-adjusted metrics
+    let mut equity_curve: Vec<f64> = Vec::with_capacity(bars);
+    let mut benchmark_prices: Vec<f64> = Vec::with_capacity(bars);
+    let mut equity_points: Vec<EquityPoint> = Vec::with_capacity(bars);
     
     // NEW: Metrics for losing streak calculation
     let mut consecutive_losses = 0;
     let mut max_consecutive_losses = 0;
 
-    // We start the loop after the initial EMA calculation period.
-    for i in SLOW_EMA_PERIOD..candles.len() {
-        let current_candle = &candles[i];
-        let previous_candle = &candles[i-1];
-        
-        // --- Trade Management ---
-        if let Some(trade) = &current_trade {
-            let mut trade_closed = false;
-            let mut pnl = 0.0;
-
-            // Check for Stop Loss
-            if current_candle.low <= trade.stop_loss {
-                pnl = (trade.stop_loss - trade.entry_price) * trade.position_size_btc;
-                println!("[{}] STOP LOSS triggered at ${:.2}. P/L: ${:.2}", current_candle.timestamp, trade.stop_loss, pnl);
-                trade_closed = true;
-            } 
-            // Check for Take Profit
-            else if current_candle.high >= trade.take_profit {
-                pnl = (trade.take_profit - trade.entry_price) * trade.position_size_btc;
-                 println!("[{}] TAKE PROFIT hit at ${:.2}. P/L: ${:.2}", current_candle.timestamp, trade.take_profit, pnl);
-                trade_closed = true;
-            }
+    for (i, candle) in candles.enumerate() {
+        // The engine trades the configured symbol, whatever the data file's
+        let candle = market_event::Candle { symbol, ..candle };
+        let bar_time_ms = candle.open_time as i64;
+        let bar_time = format_time(bar_time_ms);
+        first_time_ms.get_or_insert(bar_time_ms);
+        last_time_ms = Some(bar_time_ms);
+        closes.push_back(candle.close);
+        if closes.len() > config.volatility_lookback {
+            closes.pop_front();
+        }
 
-            if trade_closed {
-                balance += pnl;
-                trade_history.push(pnl);
-                current_trade = None;
-                
-                // NEW: Update losing streak logic
-                if pnl < 0.0 {
-                    consecutive_losses += 1;
-                } else {
-                    max_consecutive_losses = max(max_consecutive_losses, consecutive_losses);
-                    consecutive_losses = 0;
-                }
-                
-                // Update drawdown metrics
-                if balance > peak_balance {
-                    peak_balance = balance;
-                }
-                let drawdown = (peak_balance - balance) / peak_balance;
-                if drawdown > max_drawdown {
-                    max_drawdown = drawdown;
-                }
+        // --- Market events: the bar's trades while a position is open, then the bar itself ---
+        // Fills come with the time of the trade that caused them, if any
+        let mut fills: Vec<(FillEvent, Option<i64>)> = Vec::new();
+        let bar_ticks = if current_trade.is_some() { ticks::trades_between(ticks, candle.open_time, candle.close_time) } else { &[] };
+        for tick in bar_ticks {
+            let tick_fills = engine.on_market_event(&MarketEvent::AggTrade(*tick));
+            if let Some(trade) = current_trade.as_mut().filter(|_| tick_fills.is_empty()) {
+                trade.lowest_price = trade.lowest_price.min(tick.price);
+                trade.highest_price = trade.highest_price.max(tick.price);
             }
+            fills.extend(tick_fills.into_iter().map(|fill| (fill, Some(tick.trade_time as i64))));
         }
+        fills.extend(engine.on_market_event(&MarketEvent::Kline(candle)).into_iter().map(|fill| (fill, None)));
 
-        // --- Entry Logic ---
-        if current_trade.is_none() {
-            let is_uptrend = fast_emas[i] > slow_emas[i];
-            let pulled_back = previous_candle.close < fast_emas[i-1];
-            let recovered = current_candle.close > fast_emas[i];
-
-            if is_uptrend && pulled_back && recovered {
-                let entry_price = current_candle.close;
-                let stop_loss = current_candle.low;
-                let risk_per_btc = entry_price - stop_loss;
-
-                if risk_per_btc > 0.0 {
-                    let risk_amount_usd = balance * RISK_PERCENTAGE;
-                    let position_size_btc = risk_amount_usd / risk_per_btc;
-                    let take_profit = entry_price + (risk_per_btc * RISK_REWARD_RATIO);
-                    
-                    let new_trade = Trade {
-                        entry_price,
-                        stop_loss,
-                        take_profit,
-                        position_size_btc,
-                        risk_amount_usd,
+        // --- Trade Management ---
+        let held = current_trade.is_some();
+        let mut exited = false;
+        for (fill, tick_time_ms) in fills {
+            let Some(exit_reason) = ema_pullback::exit_reason(&fill.client_id) else {
+                // An entry, at the close of the signal candle
+                let tags = tagger.tag(bar_time_ms, session::realized_volatility(closes.make_contiguous()));
+                let new_trade = OpenTrade {
+                    entry_price: fill.price,
+                    quantity: fill.quantity,
+                    entry_fee: fill.fee,
+                    tags,
+                    entry_time_ms: bar_time_ms,
+                    lowest_price: fill.price,
+                    highest_price: fill.price,
+                };
+                if verbose {
+                    let exit_price = |suffix: &str| {
+                        let client_id = format!("{}-{}", fill.client_id.trim_end_matches("-en"), suffix);
+                        engine.execution.resting_orders().iter().find(|o| o.client_id == client_id).and_then(|o| match o.kind {
+                            OrderKind::Limit(price) | OrderKind::StopMarket(price) => Some(price),
+                            OrderKind::Market => None,
+                        }).unwrap_or(f64::NAN)
                     };
-
-                    println!("\n[{}] ==> ENTRY SIGNAL. Price: ${:.2}", current_candle.timestamp, new_trade.entry_price);
-                    println!("    Stop: ${:.2}, Target: ${:.2}, Risking: ${:.2}\n", new_trade.stop_loss, new_trade.take_profit, new_trade.risk_amount_usd);
-                    
-                    current_trade = Some(new_trade);
+                    let stop_loss = exit_price("sl");
+                    let liquidation_price = margin_config.open(OrderSide::Buy, fill.price, fill.quantity).liquidation_price();
+                    println!("\n[{}] ==> ENTRY SIGNAL. Price: ${:.2}", bar_time, new_trade.entry_price);
+                    println!("    Stop: ${:.2}, Target: ${:.2}, Risking: ${:.2}, Liquidation: ${:.2}\n", stop_loss, exit_price("tp"), (fill.price - stop_loss) * fill.quantity, liquidation_price);
                 }
+                current_trade = Some(new_trade);
+                continue;
+            };
+            let Some(mut trade) = current_trade.take() else { continue };
+            exited = true;
+            let exit_price = fill.price;
+            let fee = trade.entry_fee + fill.fee;
+            let pnl = (exit_price - trade.entry_price) * fill.quantity - fee;
+            if verbose {
+                let event = match exit_reason {
+                    ExitReason::Liquidation => "LIQUIDATED",
+                    ExitReason::StopLoss => "STOP LOSS triggered",
+                    ExitReason::TakeProfit => "TAKE PROFIT hit",
+                };
+                println!("[{}] {} at ${:.2}. P/L: ${:.2}", bar_time, event, exit_price, pnl + fee);
+            }
+            // The exit bar only counts up to the exit price: whatever happened after it is not part of the trade
+            trade.lowest_price = trade.lowest_price.min(exit_price);
+            trade.highest_price = trade.highest_price.max(exit_price);
+            balance += pnl;
+            // Reported PnL is converted at the price of the closing bar
+            let exit_time_ms = tick_time_ms.unwrap_or(bar_time_ms);
+            let convert = |amount: f64| converter.convert(amount, exit_time_ms);
+            let reported_pnl = convert(pnl);
+            trade_history.push(reported_pnl);
+            trades.push(TradeRecord {
+                entry_time_ms: trade.entry_time_ms,
+                exit_time_ms,
+                side: OrderSide::Buy,
+                entry_price: trade.entry_price,
+                exit_price,
+                exit_reason,
+                quantity: trade.quantity,
+                fee: convert(fee),
+                pnl: reported_pnl,
+                mae: convert(((trade.entry_price - trade.lowest_price) * trade.quantity).max(0.0)),
+                mfe: convert(((trade.highest_price - trade.entry_price) * trade.quantity).max(0.0)),
+            });
+            if let Some(tags) = trade.tags {
+                tagged_trades.push((tags, reported_pnl));
+            }
+
+            // NEW: Update losing streak logic
+            if pnl < 0.0 {
+                consecutive_losses += 1;
+            } else {
+                max_consecutive_losses = max(max_consecutive_losses, consecutive_losses);
+                consecutive_losses = 0;
+            }
+            
+            // Update drawdown metrics
+            if balance > peak_balance {
+                peak_balance = balance;
+            }
+            let drawdown = (peak_balance - balance) / peak_balance;
+            if drawdown > max_drawdown {
+                max_drawdown = drawdown;
             }
         }
+        // A trade held through the whole bar saw its range
+        if let Some(trade) = current_trade.as_mut().filter(|_| held && !exited) {
+            trade.lowest_price = trade.lowest_price.min(candle.low);
+            trade.highest_price = trade.highest_price.max(candle.high);
+        }
+
+        // Equity is sampled once the slow EMA is warmed up, when the strategy can first trade
+        if i >= config.slow_ema_period {
+            let unrealized = current_trade.as_ref()
+                .map(|trade| (candle.close - trade.entry_price) * trade.quantity)
+                .unwrap_or(0.0);
+            equity_curve.push(balance + unrealized);
+            benchmark_prices.push(candle.close);
+            equity_points.push(EquityPoint { time_ms: bar_time_ms, equity: balance + unrealized, price: candle.close });
+        }
     }
     
     // Final check for losing streak in case the simulation ends on one.
     max_consecutive_losses = max(max_consecutive_losses, consecutive_losses);
     
     // --- Final Performance Report ---
-    print_performance_report(&trade_history, balance, max_drawdown, max_consecutive_losses);
+    let starting_balance = converter.convert(config.account_balance, first_time_ms.unwrap_or(i64::MAX));
+    let final_balance = converter.convert(balance, last_time_ms.unwrap_or(i64::MAX));
+    let summary = PerformanceSummary::from_trades(&trades, starting_balance, final_balance, max_drawdown, max_consecutive_losses);
+    let risk_metrics = metrics::compute_risk_metrics(&equity_curve, Some(&benchmark_prices), metrics_config);
+    let monte_carlo = monte_carlo::simulate(&trade_history, starting_balance, config.monte_carlo_runs, config.monte_carlo_method, config.monte_carlo_seed);
+    if verbose {
+        report::print_performance_report(&summary, converter.currency);
+        metrics::print_risk_metrics(&risk_metrics, metrics_config);
+        session::print_tag_breakdown(&session::performance_by_tag(&tagged_trades), converter.currency);
+        if let Some(result) = &monte_carlo {
+            monte_carlo::print_monte_carlo(result, converter.currency);
+        }
+    }
+
+    BacktestReport {
+        strategy: format!("{}/{} EMA Crossover", config.fast_ema_period, config.slow_ema_period),
+        config: config.clone(),
+        currency: converter.currency,
+        summary,
+        risk_metrics,
+        trades,
+        equity_curve: equity_points,
+        monte_carlo,
+    }
 }
 
+/// Formats epoch milliseconds as a UTC date and time for the simulation log.
+fn format_time(time_ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(time_ms).map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default()
+}
 
-/// Calculates the Exponential Moving Average (EMA) for a series of values.
-fn calculate_ema(data: &[f64], period: usize) -> Vec<f64> {
-    let mut emas = vec![0.0; data.len()];
-    let multiplier = 2.0 / (period as f64 + 1.0);
-    let sum: f64 = data[0..period].iter().sum();
-    emas[period - 1] = sum / period as f64;
-    for i in period..data.len() {
-        emas[i] = (data[i] - emas[i - 1]) * multiplier + emas[i - 1];
+/// Parses a CSV timestamp (either epoch milliseconds or "YYYY-MM-DD HH:MM:SS[.f]") into epoch milliseconds.
+fn parse_timestamp_ms(timestamp: &str) -> Option<i64> {
+    let trimmed = timestamp.trim().trim_end_matches(" UTC");
+    if let Ok(ms) = trimmed.parse::<i64>() {
+        return Some(ms);
     }
-    emas
+    NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .map(|dt| dt.and_utc().timestamp_millis())
 }
 
+
 /// Loads and parses historical price data from a CSV file.
 fn load_data(file_path: &str) -> Result<Vec<Candle>, Box<dyn Error>> {
     let file = File::open(file_path)
@@ -203,43 +446,88 @@ fn load_data(file_path: &str) -> Result<Vec<Candle>, Box<dyn Error>> {
     Ok(candles)
 }
 
-/// Prints a summary of the backtest's performance.
-fn print_performance_report(history: &[f64], final_balance: f64, max_drawdown: f64, max_consecutive_losses: u32) {
-    let total_trades = history.len();
-    if total_trades == 0 {
-        println!("\n--- No Trades Executed ---");
-        return;
+/// Loads the prices of the reporting currency from `start` to `end` (epoch ms; none are needed for USD).
+async fn load_converter(config: &BacktestConfig, start: u64, end: u64) -> Result<CurrencyConverter, Box<dyn Error>> {
+    if config.reporting_currency == ReportingCurrency::Usd {
+        return Ok(CurrencyConverter::default());
+    }
+    println!("Downloading {} conversion prices from {}...", config.reporting_currency.code(), config.rest_base_url);
+    let rest_client = RestClient::new(String::new(), String::new(), config.rest_base_url.clone());
+    Ok(rest_client.get_historical_currency_converter(config.reporting_currency, start, end).await?)
+}
+
+/// Downloads the configured date range of klines from Binance, in the same shape as the CSV rows.
+async fn fetch_data(config: &BacktestConfig, symbol: &str) -> Result<Vec<Candle>, Box<dyn Error>> {
+    let interval = config.kline_interval()?;
+    let (from, to) = config.date_range_ms()?;
+    println!("Downloading {} {} klines from {}...", symbol, interval.to_string(), config.rest_base_url);
+    let rest_client = RestClient::new(String::new(), String::new(), config.rest_base_url.clone());
+    let klines = rest_client.get_klines_range(symbol, interval, from, to).await?;
+    let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+    let mut candles = Vec::with_capacity(klines.len());
+    // The last kline of a range ending now is still forming
+    for kline in klines.iter().filter(|k| match k { Candlestick::Array(_, _, _, _, _, _, close_time, ..) => *close_time < now_ms }) {
+        candles.push(Candle::try_from(kline)?);
+    }
+    println!("Downloaded {} candles.", candles.len());
+    Ok(candles)
+}
+
+impl TryFrom<&Candlestick> for Candle {
+    type Error = String;
+
+    fn try_from(kline: &Candlestick) -> Result<Self, Self::Error> {
+        let Candlestick::Array(open_time, open, high, low, close, volume, close_time, quote_volume, trades, taker_base, taker_quote, ignore) = kline;
+        let number = |value: &str| value.parse::<f64>().map_err(|e| format!("Invalid kline value '{}': {}", value, e));
+        Ok(Candle {
+            timestamp: open_time.to_string(),
+            open: number(open)?,
+            high: number(high)?,
+            low: number(low)?,
+            close: number(close)?,
+            volume: number(volume)?,
+            close_time: close_time.to_string(),
+            quote_asset_volume: number(quote_volume)?,
+            number_of_trades: *trades as u32,
+            taker_buy_base_asset_volume: number(taker_base)?,
+            taker_buy_quote_asset_volume: number(taker_quote)?,
+            ignore: number(ignore).unwrap_or(0.0),
+        })
+    }
+}
+
+/// Loads a Binance CSV export as closed market candles, e.g. a second, higher timeframe for
+/// `timeframe::Aligned`. Rows whose timestamps cannot be parsed are skipped. Parquet `.parquet`
+/// files are read as they are (they record their own symbol).
+pub fn load_candles(file_path: &str, symbol: &str) -> Result<Vec<market_event::Candle>, Box<dyn Error>> {
+    if columnar::is_columnar(file_path) {
+        return Ok(columnar::read_candles(Path::new(file_path))?);
+    }
+    let symbol = market_event::Symbol::new(symbol)?;
+    let candles = load_data(file_path)?.iter()
+        .filter(|c| parse_timestamp_ms(&c.timestamp).is_some() && parse_timestamp_ms(&c.close_time).is_some())
+        .map(|c| market_candle(c, symbol))
+        .collect();
+    Ok(candles)
+}
+
+/// Converts a CSV row to a closed market candle; unparseable timestamps are taken as 0.
+fn market_candle(candle: &Candle, symbol: market_event::Symbol) -> market_event::Candle {
+    let open_time = parse_timestamp_ms(&candle.timestamp).unwrap_or_default().max(0) as u64;
+    let close_time = parse_timestamp_ms(&candle.close_time).unwrap_or_default().max(0) as u64;
+    market_event::Candle {
+        symbol,
+        event_time: close_time,
+        interval_ms: close_time.saturating_sub(open_time) + 1,
+        open_time,
+        close_time,
+        open: candle.open,
+        high: candle.high,
+        low: candle.low,
+        close: candle.close,
+        volume: candle.volume,
+        quote_volume: candle.quote_asset_volume,
+        trades: candle.number_of_trades as u64,
+        is_closed: true,
     }
-    
-    let winning_trades: Vec<f64> = history.iter().filter(|&&pnl| pnl > 0.0).cloned().collect();
-    let losing_trades: Vec<f64> = history.iter().filter(|&&pnl| pnl < 0.0).cloned().collect();
-    
-    let win_rate = (winning_trades.len() as f64 / total_trades as f64) * 100.0;
-    let total_pnl = history.iter().sum::<f64>();
-    
-    let gross_profit: f64 = winning_trades.iter().sum();
-    let gross_loss: f64 = losing_trades.iter().sum::<f64>().abs();
-    
-    let profit_factor = if gross_loss > 0.0 { gross_profit / gross_loss } else { f64::INFINITY };
-
-    // NEW: Calculate Average R/R Ratio
-    let avg_win = if !winning_trades.is_empty() { gross_profit / winning_trades.len() as f64 } else { 0.0 };
-    let avg_loss = if !losing_trades.is_empty() { gross_loss / losing_trades.len() as f64 } else { 0.0 };
-    let realized_rr_ratio = if avg_loss > 0.0 { avg_win / avg_loss } else { f64::INFINITY };
-
-    println!("\n--- Backtest Performance Report ---");
-    println!("{:<25} | {:>15}", "Metric", "Value");
-    println!("{:-<43}", "");
-    println!("{:<25} | {:>15}", "Total Trades", total_trades);
-    println!("{:<25} | {:>15}", "Winning Trades", winning_trades.len());
-    println!("{:<25} | {:>15}", "Losing Trades", losing_trades.len());
-    println!("{:<25} | {:>14.2}%", "Win Rate", win_rate);
-    println!("{:<25} | ${:>14.2}", "Net Profit/Loss", total_pnl);
-    println!("{:<25} | {:>15.2}", "Profit Factor", profit_factor);
-    println!("{:<25} | {:>15.2}:1", "Avg. R/R Ratio", realized_rr_ratio); // NEW
-    println!("{:<25} | {:>14.2}%", "Max Drawdown", max_drawdown * 100.0);
-    println!("{:<25} | {:>15}", "Longest Losing Streak", max_consecutive_losses); // NEW
-    println!("{:<25} | ${:>14.2}", "Starting Balance", ACCOUNT_BALANCE);
-    println!("{:<25} | ${:>14.2}", "Final Balance", final_balance);
-    println!("{:-<43}", "");
 }
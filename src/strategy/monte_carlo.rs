@@ -0,0 +1,141 @@
+// src/strategy/monte_carlo.rs
+
+//! This module resamples the trades of a backtest to show the variance behind its single equity
+//! path: the order of the trades (shuffle) or the trades themselves (bootstrap, drawn with
+//! replacement) are randomized thousands of times, and the distribution of the final equity and
+//! maximum drawdown is reported as percentiles.
+//!
+//! Trades are resampled as returns on the equity before each trade rather than as absolute PnL,
+//! since the backtest sizes positions as a fraction of the balance.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::currency::ReportingCurrency;
+use crate::metrics;
+
+/// Default number of resampled paths.
+pub const DEFAULT_RUNS: usize = 5_000;
+
+/// How trade sequences are resampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resampling {
+    #[default]
+    Shuffle, // Same trades in a random order: final equity is unchanged, drawdowns vary
+    Bootstrap, // Trades drawn with replacement: both final equity and drawdowns vary
+}
+
+impl Resampling {
+    /// Parses "shuffle" or "bootstrap".
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "shuffle" => Ok(Resampling::Shuffle),
+            "bootstrap" => Ok(Resampling::Bootstrap),
+            _ => Err(format!("Invalid resampling method '{}': expected shuffle or bootstrap", value)),
+        }
+    }
+}
+
+/// The 5th, 50th and 95th percentiles of a distribution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct Percentiles {
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+}
+
+impl Percentiles {
+    /// Computes the percentiles of `values` (sorted in place).
+    pub fn of(values: &mut [f64]) -> Self {
+        values.sort_by(|a, b| a.total_cmp(b));
+        Self { p5: percentile(values, 0.05), p50: percentile(values, 0.50), p95: percentile(values, 0.95) }
+    }
+}
+
+/// Returns the `p` quantile (0..=1) of sorted values, interpolating linearly between ranks.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Distributions over the resampled paths. Equity is in the reporting currency.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MonteCarloResult {
+    pub runs: usize,
+    pub method: Resampling,
+    pub final_equity: Percentiles,
+    pub max_drawdown: Percentiles, // Fraction of the peak
+    pub probability_of_loss: f64, // Share of paths ending below the starting equity
+}
+
+/// Converts trade PnL into returns on the equity before each trade.
+pub fn trade_returns(trade_pnl: &[f64], starting_equity: f64) -> Vec<f64> {
+    let mut equity = starting_equity;
+    trade_pnl.iter().map(|pnl| {
+        let r = if equity > 0.0 { pnl / equity } else { 0.0 };
+        equity += pnl;
+        r
+    }).collect()
+}
+
+/// Resamples the trades `runs` times. Returns `None` without trades. A `seed` makes the result
+/// reproducible.
+pub fn simulate(trade_pnl: &[f64], starting_equity: f64, runs: usize, method: Resampling, seed: Option<u64>) -> Option<MonteCarloResult> {
+    if trade_pnl.is_empty() || runs == 0 {
+        return None;
+    }
+    let returns = trade_returns(trade_pnl, starting_equity);
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    let mut path = returns.clone();
+    let mut equity_curve = Vec::with_capacity(returns.len() + 1);
+    let (mut finals, mut drawdowns) = (Vec::with_capacity(runs), Vec::with_capacity(runs));
+    for _ in 0..runs {
+        match method {
+            Resampling::Shuffle => path.shuffle(&mut rng),
+            Resampling::Bootstrap => path.iter_mut().for_each(|r| *r = returns[rng.random_range(0..returns.len())]),
+        }
+        equity_curve.clear();
+        equity_curve.push(starting_equity);
+        for r in &path {
+            let last = equity_curve[equity_curve.len() - 1];
+            equity_curve.push((last * (1.0 + r)).max(0.0));
+        }
+        finals.push(equity_curve[equity_curve.len() - 1]);
+        drawdowns.push(metrics::max_drawdown(&equity_curve));
+    }
+    let losing = finals.iter().filter(|&&equity| equity < starting_equity).count();
+    Some(MonteCarloResult {
+        runs,
+        method,
+        final_equity: Percentiles::of(&mut finals),
+        max_drawdown: Percentiles::of(&mut drawdowns),
+        probability_of_loss: losing as f64 / runs as f64,
+    })
+}
+
+/// Prints the Monte Carlo section of a backtest report.
+pub fn print_monte_carlo(result: &MonteCarloResult, currency: ReportingCurrency) {
+    let method = match result.method {
+        Resampling::Shuffle => "shuffled",
+        Resampling::Bootstrap => "bootstrapped",
+    };
+    println!("\n--- Monte Carlo ({} {} paths) ---", result.runs, method);
+    println!("{:<25} | {:>15} | {:>15} | {:>15}", "Metric", "5th pct.", "Median", "95th pct.");
+    println!("{:-<79}", "");
+    let equity = &result.final_equity;
+    println!("{:<25} | {:>15} | {:>15} | {:>15}", "Final Equity", currency.format(equity.p5), currency.format(equity.p50), currency.format(equity.p95));
+    let dd = &result.max_drawdown;
+    println!("{:<25} | {:>14.2}% | {:>14.2}% | {:>14.2}%", "Max Drawdown", dd.p5 * 100.0, dd.p50 * 100.0, dd.p95 * 100.0);
+    println!("{:-<79}", "");
+    println!("Probability of ending below the starting equity: {:.1}%", result.probability_of_loss * 100.0);
+}
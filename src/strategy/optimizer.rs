@@ -0,0 +1,258 @@
+// src/strategy/optimizer.rs
+
+//! This module sweeps the EMA crossover backtest over a cartesian grid of settings (fast and slow
+//! EMA periods, reward/risk ratio and risk per trade) and ranks the combinations by an objective.
+//! The combinations are spread over a rayon thread pool, one thread per CPU core by default, as a
+//! sequential sweep over years of candles is slow.
+//!
+//! `trading_bot optimize [backtest flags] --fast-range 8:34:2 --slow-range 34:89:5 --rr-values 1,2,3 --risk-values 0.005,0.01 [--objective sharpe] [--threads 8] [--top 20] [--heatmap heatmap.csv]`
+//!
+//! Values are lists ("1,2,3") or ranges ("1:3:0.5"). `--output results.json` writes every ranked
+//! result, and `--heatmap` the best score of each fast/slow pair as CSV, ready for a heatmap plot.
+//! Amounts are in the quote asset.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::thread;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::report::PerformanceSummary;
+use super::walk_forward::{Objective, ParamRange};
+use super::{backtest_metrics_config, load_backtest_data, run_simulation, BacktestConfig};
+use crate::currency::{CurrencyConverter, ReportingCurrency};
+use crate::metrics::{MetricsConfig, RiskMetrics};
+use crate::market_event::Candle;
+
+/// Parses a comma separated list ("1,2,3") or an inclusive range with a step ("1:3:0.5").
+pub fn parse_values(value: &str) -> Result<Vec<f64>, String> {
+    let number = |part: &str| part.trim().parse::<f64>().map_err(|e| format!("Invalid value '{}': {}", value, e));
+    let parts: Vec<&str> = value.split(':').collect();
+    let values = match parts.as_slice() {
+        [start, end, step] => {
+            let (start, end, step) = (number(start)?, number(end)?, number(step)?);
+            if !(step > 0.0 && start <= end) {
+                return Err(format!("Invalid range '{}': the step must be positive and the start at most the end", value));
+            }
+            // Rounding the count keeps the end of ranges like 0.1:0.3:0.1 despite floating point error
+            let steps = ((end - start) / step + 1e-9).floor() as usize;
+            (0..=steps).map(|i| start + i as f64 * step).collect()
+        }
+        [_] => value.split(',').map(number).collect::<Result<_, _>>()?,
+        _ => return Err(format!("Invalid values '{}': expected a list (1,2,3) or a range (start:end:step)", value)),
+    };
+    Ok(values)
+}
+
+/// The grid of settings searched by the optimizer.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GridSearchConfig {
+    pub fast_range: ParamRange,
+    pub slow_range: ParamRange,
+    pub risk_reward_ratios: Vec<f64>, // Empty keeps the backtest setting
+    pub risk_percentages: Vec<f64>, // Empty keeps the backtest setting
+    pub objective: Objective,
+    pub threads: usize, // 0 uses one thread per CPU core
+    pub top: usize, // Rows of the printed ranking
+    pub heatmap_output: Option<String>,
+}
+
+impl Default for GridSearchConfig {
+    fn default() -> Self {
+        Self {
+            fast_range: ParamRange { start: 8, end: 34, step: 2 },
+            slow_range: ParamRange { start: 34, end: 89, step: 5 },
+            risk_reward_ratios: Vec::new(),
+            risk_percentages: Vec::new(),
+            objective: Objective::Sharpe,
+            threads: 0,
+            top: 20,
+            heatmap_output: None,
+        }
+    }
+}
+
+impl GridSearchConfig {
+    /// Takes the optimizer flags out of the command line and returns them with the remaining
+    /// (backtest) arguments.
+    pub fn from_args(args: &[String]) -> Result<(Self, Vec<String>), String> {
+        let mut config = Self::default();
+        let mut rest = Vec::new();
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let key = flag.strip_prefix("--").unwrap_or(flag);
+            let value = iter.next().ok_or_else(|| format!("Missing value for --{}", key))?;
+            let count = |value: &str| value.parse::<usize>().map_err(|e| format!("Invalid value '{}' for {}: {}", value, key, e));
+            match key {
+                "fast-range" => config.fast_range = ParamRange::parse(value)?,
+                "slow-range" => config.slow_range = ParamRange::parse(value)?,
+                "rr-values" => config.risk_reward_ratios = parse_values(value)?,
+                "risk-values" => config.risk_percentages = parse_values(value)?,
+                "objective" => config.objective = Objective::parse(value)?,
+                "threads" => config.threads = count(value)?,
+                "top" => config.top = count(value)?,
+                "heatmap" => config.heatmap_output = Some(value.clone()),
+                _ => rest.extend([flag.clone(), value.clone()]),
+            }
+        }
+        Ok((config, rest))
+    }
+
+    /// Returns the backtest settings of every combination of the grid, skipping EMA pairs with the
+    /// fast period at or above the slow one.
+    pub fn candidates(&self, base: &BacktestConfig) -> Vec<BacktestConfig> {
+        let or_base = |values: &[f64], base: f64| if values.is_empty() { vec![base] } else { values.to_vec() };
+        let ratios = or_base(&self.risk_reward_ratios, base.risk_reward_ratio);
+        let risks = or_base(&self.risk_percentages, base.risk_percentage);
+        let mut candidates = Vec::new();
+        for fast in self.fast_range.values() {
+            for slow in self.slow_range.values().into_iter().filter(|&slow| fast < slow) {
+                for &risk_reward_ratio in &ratios {
+                    for &risk_percentage in &risks {
+                        candidates.push(candidate(base, fast, slow, risk_reward_ratio, risk_percentage));
+                    }
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Returns the number of worker threads to use.
+    pub fn thread_count(&self) -> usize {
+        if self.threads > 0 {
+            return self.threads;
+        }
+        thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    }
+}
+
+/// Returns the backtest settings of one combination, without the report outputs and Monte Carlo
+/// analysis of the base settings.
+pub fn candidate(base: &BacktestConfig, fast: usize, slow: usize, risk_reward_ratio: f64, risk_percentage: f64) -> BacktestConfig {
+    BacktestConfig {
+        fast_ema_period: fast,
+        slow_ema_period: slow,
+        risk_reward_ratio,
+        risk_percentage,
+        monte_carlo_runs: 0,
+        output: None,
+        html_output: None,
+        trades_output: None,
+        ..base.clone()
+    }
+}
+
+/// The backtest result of one combination of the grid.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GridResult {
+    pub fast_ema_period: usize,
+    pub slow_ema_period: usize,
+    pub risk_reward_ratio: f64,
+    pub risk_percentage: f64,
+    pub score: f64,
+    pub summary: PerformanceSummary,
+    pub risk_metrics: RiskMetrics,
+}
+
+/// Backtests every candidate on a pool of `threads` worker threads and returns the results in the
+/// order of the candidates. Should the pool fail to start, rayon's global pool is used.
+pub(super) fn evaluate(candles: &[Candle], candidates: &[BacktestConfig], metrics_config: &MetricsConfig, objective: Objective, threads: usize) -> Vec<GridResult> {
+    let converter = CurrencyConverter::default();
+    let backtest = |candidate: &BacktestConfig| {
+        let report = run_simulation(candles.iter().copied(), &[], metrics_config, candidate, &converter, false);
+        GridResult {
+            fast_ema_period: candidate.fast_ema_period,
+            slow_ema_period: candidate.slow_ema_period,
+            risk_reward_ratio: candidate.risk_reward_ratio,
+            risk_percentage: candidate.risk_percentage,
+            score: objective.score(&report),
+            summary: report.summary,
+            risk_metrics: report.risk_metrics,
+        }
+    };
+    let sweep = || candidates.par_iter().map(backtest).collect();
+    match rayon::ThreadPoolBuilder::new().num_threads(threads.max(1)).build() {
+        Ok(pool) => pool.install(sweep),
+        Err(_) => sweep(),
+    }
+}
+
+/// Sorts results by descending score; ties keep the grid order.
+pub fn rank(results: &mut [GridResult]) {
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+/// Returns the best score of each fast/slow EMA pair over the other settings, by fast then slow period.
+pub fn heatmap(results: &[GridResult]) -> Vec<(usize, usize, f64)> {
+    let mut best: BTreeMap<(usize, usize), f64> = BTreeMap::new();
+    for result in results {
+        let cell = best.entry((result.fast_ema_period, result.slow_ema_period)).or_insert(f64::NEG_INFINITY);
+        *cell = cell.max(result.score);
+    }
+    best.into_iter().map(|((fast, slow), score)| (fast, slow, score)).collect()
+}
+
+/// Writes the heatmap as `fast_ema_period,slow_ema_period,score` rows.
+pub fn write_heatmap_csv(results: &[GridResult], path: &str) -> Result<(), String> {
+    let mut csv = String::from("fast_ema_period,slow_ema_period,score\n");
+    for (fast, slow, score) in heatmap(results) {
+        csv.push_str(&format!("{},{},{}\n", fast, slow, score));
+    }
+    fs::write(path, csv).map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Loads the candles, runs the grid search, prints the ranking and writes the requested outputs.
+/// Returns the ranked results.
+pub async fn run(config: &BacktestConfig, grid: &GridSearchConfig) -> Result<Vec<GridResult>, Box<dyn Error>> {
+    println!("--- Starting Parameter Grid Search ---");
+    if config.reporting_currency != ReportingCurrency::Usd {
+        return Err("Grid search results are in the quote asset; --currency is not supported.".into());
+    }
+    let candidates = grid.candidates(config);
+    if candidates.is_empty() {
+        return Err("The fast and slow ranges contain no pair with a fast period below the slow one.".into());
+    }
+    let candles = load_backtest_data(config).await?;
+    let slowest = candidates.iter().map(|c| c.slow_ema_period).max().unwrap_or_default();
+    if candles.len() <= slowest {
+        return Err(format!("Not enough historical data for a {}-period EMA ({} candles).", slowest, candles.len()).into());
+    }
+    for candidate in &candidates {
+        candidate.validate()?;
+    }
+
+    let threads = grid.thread_count();
+    println!("Backtesting {} combinations on {} threads...", candidates.len(), threads);
+    let metrics_config = backtest_metrics_config(config)?;
+    let mut results = evaluate(&candles, &candidates, &metrics_config, grid.objective, threads);
+    rank(&mut results);
+    print_ranking(&results, grid.top);
+
+    if let Some(path) = &config.output {
+        let json = serde_json::to_string_pretty(&results).map_err(|e| format!("Failed to serialize the grid search results: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        println!("\nGrid search results written to {}", path);
+    }
+    if let Some(path) = &grid.heatmap_output {
+        write_heatmap_csv(&results, path)?;
+        println!("Heatmap data written to {}", path);
+    }
+    Ok(results)
+}
+
+/// Prints the `top` best results.
+pub fn print_ranking(results: &[GridResult], top: usize) {
+    let ratio = |value: Option<f64>| value.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "n/a".to_string());
+    println!("\n--- Top {} of {} Combinations ---", top.min(results.len()), results.len());
+    println!("{:>4} | {:>7} | {:>5} | {:>6} | {:>10} | {:>12} | {:>6} | {:>6} | {:>7} | {:>7}", "Rank", "EMAs", "RR", "Risk", "Score", "Net PnL", "Trades", "Win %", "Sharpe", "Max DD");
+    println!("{:-<97}", "");
+    for (rank, result) in results.iter().take(top).enumerate() {
+        println!("{:>4} | {:>7} | {:>5.2} | {:>5.2}% | {:>10.3} | {:>12.2} | {:>6} | {:>5.1}% | {:>7} | {:>6.2}%",
+            rank + 1, format!("{}/{}", result.fast_ema_period, result.slow_ema_period), result.risk_reward_ratio,
+            result.risk_percentage * 100.0, result.score, result.summary.net_pnl, result.summary.total_trades,
+            result.summary.win_rate * 100.0, ratio(result.risk_metrics.sharpe_ratio), result.risk_metrics.max_drawdown * 100.0);
+    }
+    println!("{:-<97}", "");
+}
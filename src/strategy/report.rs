@@ -0,0 +1,210 @@
+// src/strategy/report.rs
+
+//! This module collects the results of the EMA crossover backtest into a `BacktestReport` (the
+//! settings, all metrics, the trade list and the equity curve), which is printed as a table and can
+//! be written as JSON with `--output report.json`, so runs can be compared and archived
+//! programmatically, or rendered as an HTML page (see `html`). The trade list can also be exported
+//! as CSV with `--trades trades.csv` to audit individual trades.
+
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use super::BacktestConfig;
+use crate::currency::ReportingCurrency;
+use crate::metrics::RiskMetrics;
+use crate::order::OrderSide;
+use super::monte_carlo::MonteCarloResult;
+
+/// Why a backtested trade was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitReason {
+    StopLoss,
+    TakeProfit,
+    Liquidation,
+}
+
+/// A closed trade of the backtest. Amounts are in the reporting currency, prices in the quote asset.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TradeRecord {
+    pub entry_time_ms: i64,
+    pub exit_time_ms: i64,
+    pub side: OrderSide, // Side of the entry order
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub exit_reason: ExitReason,
+    pub quantity: f64,
+    pub fee: f64, // Entry and exit commission
+    pub pnl: f64, // Net of fees
+    pub mae: f64, // Maximum adverse excursion: the largest unrealized loss while open, as a positive amount
+    pub mfe: f64, // Maximum favorable excursion: the largest unrealized profit while open
+}
+
+/// Writes the trades as CSV, one row per trade with the `TradeRecord` fields as columns.
+pub fn write_trades_csv(trades: &[TradeRecord], path: &str) -> Result<(), String> {
+    let error = |e: csv::Error| format!("Failed to write {}: {}", path, e);
+    let mut writer = csv::Writer::from_path(path).map_err(error)?;
+    for trade in trades {
+        writer.serialize(trade).map_err(error)?;
+    }
+    writer.flush().map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Reads trades written by `write_trades_csv`.
+pub fn read_trades_csv(path: &str) -> Result<Vec<TradeRecord>, String> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    reader.deserialize().collect::<Result<_, _>>().map_err(|e| format!("Invalid trade log {}: {}", path, e))
+}
+
+/// Equity and instrument price at a bar's close.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct EquityPoint {
+    pub time_ms: i64,
+    pub equity: f64, // Mark-to-market, in the quote asset
+    pub price: f64,
+}
+
+/// Returns the drawdown from the running peak at every point, as a fraction of the peak.
+pub fn drawdown_curve(points: &[EquityPoint]) -> Vec<f64> {
+    let mut peak = f64::MIN;
+    points.iter().map(|p| {
+        peak = peak.max(p.equity);
+        if peak > 0.0 { (peak - p.equity) / peak } else { 0.0 }
+    }).collect()
+}
+
+/// Trade statistics of a backtest. Ratios without losing trades are `None` (infinite).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PerformanceSummary {
+    pub total_trades: usize,
+    pub winning_trades: usize,
+    pub losing_trades: usize,
+    pub win_rate: f64, // Fraction of winning trades, e.g. 0.45
+    pub net_pnl: f64,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+    pub profit_factor: Option<f64>,
+    pub avg_win: f64,
+    pub avg_loss: f64,
+    pub realized_rr_ratio: Option<f64>, // Average win over average loss
+    #[serde(default)]
+    pub expectancy: f64, // Average PnL per trade
+    #[serde(default)]
+    pub avg_holding_ms: Option<i64>, // Average time between entry and exit
+    pub max_drawdown: f64, // Fraction of the peak balance
+    pub max_consecutive_losses: u32,
+    pub starting_balance: f64,
+    pub final_balance: f64,
+}
+
+impl PerformanceSummary {
+    /// Computes the statistics of closed trades, including their average holding time.
+    pub fn from_trades(trades: &[TradeRecord], starting_balance: f64, final_balance: f64, max_drawdown: f64, max_consecutive_losses: u32) -> Self {
+        let pnl: Vec<f64> = trades.iter().map(|t| t.pnl).collect();
+        let holding: i64 = trades.iter().map(|t| t.exit_time_ms - t.entry_time_ms).sum();
+        Self {
+            avg_holding_ms: (!trades.is_empty()).then(|| holding / trades.len() as i64),
+            ..Self::from_pnl(&pnl, starting_balance, final_balance, max_drawdown, max_consecutive_losses)
+        }
+    }
+
+    /// Computes the statistics of the trades' PnL.
+    pub fn from_pnl(history: &[f64], starting_balance: f64, final_balance: f64, max_drawdown: f64, max_consecutive_losses: u32) -> Self {
+        let wins: Vec<f64> = history.iter().copied().filter(|&pnl| pnl > 0.0).collect();
+        let losses: Vec<f64> = history.iter().copied().filter(|&pnl| pnl < 0.0).collect();
+        let gross_profit: f64 = wins.iter().sum();
+        let gross_loss = losses.iter().sum::<f64>().abs();
+        let avg_win = if wins.is_empty() { 0.0 } else { gross_profit / wins.len() as f64 };
+        let avg_loss = if losses.is_empty() { 0.0 } else { gross_loss / losses.len() as f64 };
+        Self {
+            total_trades: history.len(),
+            winning_trades: wins.len(),
+            losing_trades: losses.len(),
+            win_rate: if history.is_empty() { 0.0 } else { wins.len() as f64 / history.len() as f64 },
+            net_pnl: history.iter().sum(),
+            gross_profit,
+            gross_loss,
+            profit_factor: (gross_loss > 0.0).then(|| gross_profit / gross_loss),
+            avg_win,
+            avg_loss,
+            realized_rr_ratio: (avg_loss > 0.0).then(|| avg_win / avg_loss),
+            expectancy: if history.is_empty() { 0.0 } else { history.iter().sum::<f64>() / history.len() as f64 },
+            avg_holding_ms: None,
+            max_drawdown,
+            max_consecutive_losses,
+            starting_balance,
+            final_balance,
+        }
+    }
+}
+
+/// Everything a backtest run produced.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BacktestReport {
+    pub strategy: String,
+    pub config: BacktestConfig,
+    pub currency: ReportingCurrency,
+    pub summary: PerformanceSummary,
+    pub risk_metrics: RiskMetrics,
+    pub trades: Vec<TradeRecord>,
+    #[serde(default)]
+    pub equity_curve: Vec<EquityPoint>,
+    #[serde(default)]
+    pub monte_carlo: Option<MonteCarloResult>,
+}
+
+impl BacktestReport {
+    /// Serializes the report as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("Failed to serialize the backtest report: {}", e))
+    }
+
+    /// Reads a report written by `write_json`.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid backtest report: {}", e))
+    }
+
+    /// Writes the report as JSON to `path`.
+    pub fn write_json(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_json()?).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+}
+
+/// Formats a duration in milliseconds as e.g. "2d 4h 30m".
+pub fn format_duration(ms: i64) -> String {
+    let minutes = ms / 60_000;
+    let (days, hours, minutes) = (minutes / 1_440, minutes / 60 % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h {}m", days, hours, minutes),
+    }
+}
+
+/// Prints the trade statistics of a backtest (the risk-adjusted metrics are printed by
+/// `metrics::print_risk_metrics`).
+pub fn print_performance_report(summary: &PerformanceSummary, currency: ReportingCurrency) {
+    if summary.total_trades == 0 {
+        println!("\n--- No Trades Executed ---");
+        return;
+    }
+
+    println!("\n--- Backtest Performance Report ({}) ---", currency.code());
+    println!("{:<25} | {:>15}", "Metric", "Value");
+    println!("{:-<43}", "");
+    println!("{:<25} | {:>15}", "Total Trades", summary.total_trades);
+    println!("{:<25} | {:>15}", "Winning Trades", summary.winning_trades);
+    println!("{:<25} | {:>15}", "Losing Trades", summary.losing_trades);
+    println!("{:<25} | {:>14.2}%", "Win Rate", summary.win_rate * 100.0);
+    println!("{:<25} | {:>15}", "Net Profit/Loss", currency.format(summary.net_pnl));
+    println!("{:<25} | {:>15.2}", "Profit Factor", summary.profit_factor.unwrap_or(f64::INFINITY));
+    println!("{:<25} | {:>15.2}:1", "Avg. R/R Ratio", summary.realized_rr_ratio.unwrap_or(f64::INFINITY));
+    println!("{:<25} | {:>15}", "Expectancy per Trade", currency.format(summary.expectancy));
+    println!("{:<25} | {:>15}", "Avg. Holding Time", summary.avg_holding_ms.map(format_duration).unwrap_or_else(|| "n/a".to_string()));
+    println!("{:<25} | {:>14.2}%", "Max Drawdown", summary.max_drawdown * 100.0);
+    println!("{:<25} | {:>15}", "Longest Losing Streak", summary.max_consecutive_losses);
+    println!("{:<25} | {:>15}", "Starting Balance", currency.format(summary.starting_balance));
+    println!("{:<25} | {:>15}", "Final Balance", currency.format(summary.final_balance));
+    println!("{:-<43}", "");
+}
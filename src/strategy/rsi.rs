@@ -0,0 +1,127 @@
+// src/strategy/rsi.rs
+
+//! This module implements an RSI mean-reversion strategy for the backtester: it goes long when the
+//! RSI drops below an oversold level and exits when it rises above an exit level. It is the
+//! non-trend-following counterpart to the EMA crossover backtest.
+
+use super::{SimulatedFill, Strategy};
+use crate::indicators::Rsi;
+use crate::market_event::Candle;
+use crate::order::OrderSide;
+use crate::patterns::PatternFilter;
+
+/// Settings of the RSI mean-reversion strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RsiConfig {
+    pub period: usize,
+    pub entry_below: f64, // Enter long when the RSI closes below this level
+    pub exit_above: f64, // Exit when the RSI closes above this level
+    pub quantity: f64, // Position size in the base asset
+    pub fee_rate: f64, // Fee per fill as a fraction of notional
+    pub entry_filter: Option<PatternFilter>, // Optional candlestick patterns required to enter
+}
+
+impl Default for RsiConfig {
+    fn default() -> Self {
+        Self { period: 14, entry_below: 30.0, exit_above: 70.0, quantity: 0.01, fee_rate: 0.0004, entry_filter: None }
+    }
+}
+
+impl RsiConfig {
+    /// Validates the levels and size.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.period < 2 {
+            return Err("RSI period must be at least 2.".to_string());
+        }
+        if !(0.0..100.0).contains(&self.entry_below) || self.exit_above <= self.entry_below || self.exit_above > 100.0 {
+            return Err(format!("RSI levels must satisfy 0 <= entry ({}) < exit ({}) <= 100.", self.entry_below, self.exit_above));
+        }
+        if self.quantity <= 0.0 {
+            return Err("RSI strategy quantity must be positive.".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Backtestable RSI mean-reversion strategy. Entries and exits fill at the candle close.
+#[derive(Debug, Clone)]
+pub struct RsiStrategy {
+    config: RsiConfig,
+    rsi: Rsi,
+    recent: Vec<Candle>, // Last candles, for the entry filter
+    entry_price: Option<f64>,
+    realized_pnl: f64,
+}
+
+impl RsiStrategy {
+    /// Creates the strategy from its settings.
+    pub fn new(config: RsiConfig) -> Result<Self, String> {
+        config.validate()?;
+        Ok(Self { rsi: Rsi::new(config.period), config, recent: Vec::new(), entry_price: None, realized_pnl: 0.0 })
+    }
+
+    /// Returns the entry price of the open position, if any.
+    pub fn entry_price(&self) -> Option<f64> {
+        self.entry_price
+    }
+
+    /// Returns the current RSI, once enough candles have been seen.
+    pub fn rsi(&self) -> Option<f64> {
+        self.rsi.value()
+    }
+}
+
+impl Strategy for RsiStrategy {
+    fn name(&self) -> &str {
+        "rsi_mean_reversion"
+    }
+
+    fn on_candle(&mut self, candle: &Candle) -> Vec<SimulatedFill> {
+        if let Some(filter) = &self.config.entry_filter {
+            self.recent.push(*candle);
+            let excess = self.recent.len().saturating_sub(filter.history_len());
+            self.recent.drain(..excess);
+        }
+        let Some(rsi) = self.rsi.update(candle.close) else { return vec![] };
+        let entry_allowed = self.config.entry_filter.as_ref().is_none_or(|f| f.allows(&self.recent));
+        let quantity = self.config.quantity;
+        let fee = candle.close * quantity * self.config.fee_rate;
+        match self.entry_price {
+            None if rsi < self.config.entry_below && entry_allowed => {
+                self.entry_price = Some(candle.close);
+                self.realized_pnl -= fee;
+                vec![SimulatedFill { time_ms: candle.close_time, side: OrderSide::Buy, price: candle.close, quantity, realized_pnl: -fee }]
+            },
+            Some(entry) if rsi > self.config.exit_above => {
+                self.entry_price = None;
+                let pnl = (candle.close - entry) * quantity - fee;
+                self.realized_pnl += pnl;
+                vec![SimulatedFill { time_ms: candle.close_time, side: OrderSide::Sell, price: candle.close, quantity, realized_pnl: pnl }]
+            },
+            _ => vec![],
+        }
+    }
+
+    fn on_forming_candle(&mut self, candle: &Candle) -> Vec<SimulatedFill> {
+        // Only entries act intrabar (with the RSI the bar would have if it closed now); exits wait
+        // for the close. The entry filter's patterns need closed candles, so it only applies there.
+        if self.entry_price.is_some() || self.config.entry_filter.is_some() {
+            return vec![];
+        }
+        match self.rsi.peek(candle.close) {
+            Some(rsi) if rsi < self.config.entry_below => {
+                let quantity = self.config.quantity;
+                let fee = candle.close * quantity * self.config.fee_rate;
+                self.entry_price = Some(candle.close);
+                self.realized_pnl -= fee;
+                vec![SimulatedFill { time_ms: candle.event_time, side: OrderSide::Buy, price: candle.close, quantity, realized_pnl: -fee }]
+            },
+            _ => vec![],
+        }
+    }
+
+    fn pnl(&self, mark_price: f64) -> f64 {
+        let unrealized = self.entry_price.map(|entry| (mark_price - entry) * self.config.quantity).unwrap_or(0.0);
+        self.realized_pnl + unrealized
+    }
+}
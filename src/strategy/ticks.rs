@@ -0,0 +1,63 @@
+// src/strategy/ticks.rs
+
+//! This module lets the EMA crossover backtest resolve exits from aggregated trades instead of
+//! candle extremes. With bars alone, a candle whose range contains both the stop and the target
+//! cannot tell which was hit first (the backtest assumes the stop). Given the aggTrades of the
+//! period (`--agg-trades BTCUSDT-aggTrades.csv`, as written by `download_data ... --agg-trades`
+//! or taken from Binance's public data dumps), the trades of every bar with an open position are
+//! replayed in order through the engine's simulated execution (`engine::SimulatedExecution`)
+//! before the bar, so the first one crossing the liquidation price, the stop or the target closes
+//! it:
+//!
+//! * the stop is a stop-market order, so it fills at the price of the trade that crossed it, which
+//!   is below the stop when the price gapped through it;
+//! * the target is a limit order and fills at its price;
+//! * a liquidation closes the position like a stop at the liquidation price.
+//!
+//! Entries still happen at the close of the signal candle. Bars without trades in the file (e.g.
+//! outside the downloaded range) fall back to the bar-based exits.
+
+use std::error::Error;
+use std::fs::File;
+
+use crate::market_event::{Symbol, Trade};
+
+/// Loads aggregated trades from a CSV file with the columns `agg_trade_id, price, quantity,
+/// first_trade_id, last_trade_id, transact_time, is_buyer_maker`, with or without the header row.
+/// The trades are returned sorted by time.
+pub fn load_agg_trades(file_path: &str, symbol: &str) -> Result<Vec<Trade>, Box<dyn Error>> {
+    let symbol = Symbol::new(symbol)?;
+    let file = File::open(file_path).map_err(|e| format!("Failed to open {}: {}", file_path, e))?;
+    let mut reader = csv::ReaderBuilder::new().has_headers(false).from_reader(file);
+    let mut trades = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        // The header row, if any
+        if index == 0 && record.get(0).is_some_and(|id| id.parse::<u64>().is_err()) {
+            continue;
+        }
+        let field = |column: usize| record.get(column).map(str::trim)
+            .ok_or_else(|| format!("{} line {}: missing column {}", file_path, index + 1, column + 1));
+        let invalid = |column: usize| format!("{} line {}: invalid value in column {}", file_path, index + 1, column + 1);
+        let trade_time = field(5)?.parse().map_err(|_| invalid(5))?;
+        trades.push(Trade {
+            symbol,
+            event_time: trade_time,
+            trade_time,
+            trade_id: field(0)?.parse().map_err(|_| invalid(0))?,
+            price: field(1)?.parse().map_err(|_| invalid(1))?,
+            quantity: field(2)?.parse().map_err(|_| invalid(2))?,
+            is_buyer_maker: field(6)?.eq_ignore_ascii_case("true"),
+        });
+    }
+    trades.sort_by_key(|t| (t.trade_time, t.trade_id));
+    Ok(trades)
+}
+
+/// Returns the trades executed between `start_ms` and `end_ms` (inclusive). `trades` must be
+/// sorted by time.
+pub fn trades_between(trades: &[Trade], start_ms: u64, end_ms: u64) -> &[Trade] {
+    let start = trades.partition_point(|t| t.trade_time < start_ms);
+    let end = trades.partition_point(|t| t.trade_time <= end_ms);
+    &trades[start..end.max(start)]
+}
@@ -0,0 +1,179 @@
+// src/strategy/timeframe.rs
+
+//! This module lets a backtested strategy consume two timeframes, e.g. a 4h trend filter with 1h
+//! entries. The higher timeframe is either resampled from the lower one (`Resampled`) or loaded
+//! separately, e.g. from a second CSV, and aligned by timestamp (`Aligned`).
+//!
+//! Both adapters only ever show a strategy the last higher-timeframe candle that had *closed* by
+//! the close of the current lower-timeframe candle, so there is no lookahead bias: at the 1h bar
+//! closing at 03:00 the strategy sees the 4h bar that closed at 00:00, until the 4h bar closing at
+//! 04:00 is complete.
+
+use super::{SimulatedFill, Strategy};
+use crate::market_event::Candle;
+
+/// A strategy driven by lower-timeframe candles with a higher-timeframe context.
+pub trait MultiTimeframeStrategy {
+    /// Short name used in reports.
+    fn name(&self) -> &str;
+    /// Processes a closed lower-timeframe candle. `higher` is the last closed higher-timeframe
+    /// candle (`None` until the first one has closed).
+    fn on_candle(&mut self, candle: &Candle, higher: Option<&Candle>) -> Vec<SimulatedFill>;
+    /// Total PnL (realized and unrealized) when marked at `mark_price`.
+    fn pnl(&self, mark_price: f64) -> f64;
+}
+
+/// Incrementally builds higher-timeframe candles from closed lower-timeframe candles.
+/// Buckets are aligned to multiples of the interval since the epoch (as Binance klines are).
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    interval_ms: u64,
+    current: Option<Candle>,
+}
+
+impl Resampler {
+    /// Creates a resampler for `interval_ms`, e.g. `4 * 60 * 60 * 1000` for 4h candles.
+    pub fn new(interval_ms: u64) -> Result<Self, String> {
+        if interval_ms == 0 {
+            return Err("Resampling interval must be positive.".to_string());
+        }
+        Ok(Self { interval_ms, current: None })
+    }
+
+    /// Returns the resampling interval in milliseconds.
+    pub fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    /// Adds a closed lower-timeframe candle. Returns the higher-timeframe candle when this candle
+    /// completes it. A bucket that is left before it completes (a gap in the data) is discarded.
+    pub fn update(&mut self, candle: &Candle) -> Option<Candle> {
+        if !candle.is_closed {
+            return None;
+        }
+        let open_time = candle.open_time - candle.open_time % self.interval_ms;
+        let close_time = open_time + self.interval_ms - 1;
+        match self.current.as_mut() {
+            Some(current) if current.open_time == open_time => {
+                current.event_time = candle.event_time;
+                current.high = current.high.max(candle.high);
+                current.low = current.low.min(candle.low);
+                current.close = candle.close;
+                current.volume += candle.volume;
+                current.quote_volume += candle.quote_volume;
+                current.trades += candle.trades;
+            },
+            _ => {
+                self.current = Some(Candle { interval_ms: self.interval_ms, open_time, close_time, is_closed: false, ..*candle });
+            },
+        }
+        if candle.close_time >= close_time {
+            return self.current.take().map(|c| Candle { is_closed: true, ..c });
+        }
+        None
+    }
+}
+
+/// Resamples closed candles to a higher interval. Only complete buckets are returned.
+pub fn resample(candles: &[Candle], interval_ms: u64) -> Result<Vec<Candle>, String> {
+    let mut resampler = Resampler::new(interval_ms)?;
+    Ok(candles.iter().filter_map(|c| resampler.update(c)).collect())
+}
+
+/// For every lower-timeframe candle, returns the index of the last closed higher-timeframe candle
+/// whose close time is not after the lower candle's close time. Both series must be sorted by time.
+pub fn align_higher_timeframe(lower: &[Candle], higher: &[Candle]) -> Vec<Option<usize>> {
+    let mut next = 0;
+    let mut last_closed = None;
+    lower.iter().map(|candle| {
+        while next < higher.len() && higher[next].close_time <= candle.close_time {
+            if higher[next].is_closed {
+                last_closed = Some(next);
+            }
+            next += 1;
+        }
+        last_closed
+    }).collect()
+}
+
+/// Runs a multi-timeframe strategy on lower-timeframe candles, building the higher timeframe by
+/// resampling them. Implements `Strategy`, so it can be passed to `backtest`.
+#[derive(Debug, Clone)]
+pub struct Resampled<S> {
+    inner: S,
+    resampler: Resampler,
+    last_higher: Option<Candle>,
+}
+
+impl<S: MultiTimeframeStrategy> Resampled<S> {
+    /// Wraps `inner`, resampling to `interval_ms`.
+    pub fn new(inner: S, interval_ms: u64) -> Result<Self, String> {
+        Ok(Self { inner, resampler: Resampler::new(interval_ms)?, last_higher: None })
+    }
+
+    /// Returns the wrapped strategy.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: MultiTimeframeStrategy> Strategy for Resampled<S> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn on_candle(&mut self, candle: &Candle) -> Vec<SimulatedFill> {
+        // A higher candle completed by this candle closed at the same time, so it is already known
+        if let Some(higher) = self.resampler.update(candle) {
+            self.last_higher = Some(higher);
+        }
+        self.inner.on_candle(candle, self.last_higher.as_ref())
+    }
+
+    fn pnl(&self, mark_price: f64) -> f64 {
+        self.inner.pnl(mark_price)
+    }
+}
+
+/// Runs a multi-timeframe strategy on lower-timeframe candles with a separately loaded
+/// higher-timeframe series (sorted by time), aligned by close time. Implements `Strategy`.
+#[derive(Debug, Clone)]
+pub struct Aligned<'a, S> {
+    inner: S,
+    higher: &'a [Candle],
+    next: usize,
+    last_closed: Option<usize>,
+}
+
+impl<'a, S: MultiTimeframeStrategy> Aligned<'a, S> {
+    /// Wraps `inner` with the higher-timeframe series.
+    pub fn new(inner: S, higher: &'a [Candle]) -> Self {
+        Self { inner, higher, next: 0, last_closed: None }
+    }
+
+    /// Returns the wrapped strategy.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: MultiTimeframeStrategy> Strategy for Aligned<'_, S> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn on_candle(&mut self, candle: &Candle) -> Vec<SimulatedFill> {
+        while self.next < self.higher.len() && self.higher[self.next].close_time <= candle.close_time {
+            if self.higher[self.next].is_closed {
+                self.last_closed = Some(self.next);
+            }
+            self.next += 1;
+        }
+        let higher = self.last_closed.map(|i| &self.higher[i]);
+        self.inner.on_candle(candle, higher)
+    }
+
+    fn pnl(&self, mark_price: f64) -> f64 {
+        self.inner.pnl(mark_price)
+    }
+}
@@ -0,0 +1,156 @@
+// src/strategy/timing.rs
+
+//! This module controls when a strategy fed from a live kline stream evaluates and acts:
+//!
+//! * `OnClose` - only on closed candles (`is_closed == true`), the default;
+//! * `EveryUpdate` - also on every update of the forming candle;
+//! * `Intrabar { throttle_ms }` - also on the forming candle, at most once per `throttle_ms`.
+//!
+//! `CandleStore` keeps the closed candles and the forming one, and classifies each kline update so
+//! that a closed candle is delivered to `Strategy::on_candle` exactly once (repeated or late
+//! updates of a bar are dropped) and a forming candle never advances indicators. In the intrabar
+//! modes `Strategy::on_forming_candle` is called instead, and once it has acted on a bar it is not
+//! called again for that bar, so a strategy cannot act twice on the same unclosed bar.
+
+use std::collections::VecDeque;
+
+use super::{SimulatedFill, Strategy};
+use crate::market_event::Candle;
+
+/// When a strategy evaluates kline updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionTiming {
+    #[default]
+    OnClose,
+    EveryUpdate,
+    Intrabar { throttle_ms: u64 },
+}
+
+impl ExecutionTiming {
+    /// Parses "close", "update" or "intrabar:<milliseconds>".
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "close" | "on_close" => Ok(ExecutionTiming::OnClose),
+            "update" | "every_update" => Ok(ExecutionTiming::EveryUpdate),
+            other => match other.strip_prefix("intrabar:") {
+                Some(ms) => ms.parse::<u64>()
+                    .map(|throttle_ms| ExecutionTiming::Intrabar { throttle_ms })
+                    .map_err(|e| format!("Invalid intrabar throttle '{}': {}", ms, e)),
+                None => Err(format!("Invalid execution timing '{}': expected close, update or intrabar:<ms>", value)),
+            },
+        }
+    }
+}
+
+/// How a kline update relates to the candles already stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandleUpdate {
+    Closed, // A bar closed
+    Forming, // An update of the bar in progress
+    Stale, // A repeated close or a late update of a bar that already closed
+}
+
+/// The most recent closed candles of one symbol/interval, plus the forming candle.
+#[derive(Debug, Clone)]
+pub struct CandleStore {
+    capacity: usize,
+    closed: VecDeque<Candle>,
+    forming: Option<Candle>,
+}
+
+impl CandleStore {
+    /// Creates a store keeping up to `capacity` closed candles.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), closed: VecDeque::new(), forming: None }
+    }
+
+    /// Stores a kline update and classifies it.
+    pub fn update(&mut self, candle: &Candle) -> CandleUpdate {
+        let last_closed = self.closed.back().map(|c| c.open_time);
+        let forming = self.forming.as_ref().map(|c| c.open_time);
+        if last_closed.is_some_and(|t| candle.open_time <= t) || forming.is_some_and(|t| candle.open_time < t) {
+            return CandleUpdate::Stale;
+        }
+        if candle.is_closed {
+            self.closed.push_back(*candle);
+            if self.closed.len() > self.capacity {
+                self.closed.pop_front();
+            }
+            self.forming = None;
+            CandleUpdate::Closed
+        } else {
+            self.forming = Some(*candle);
+            CandleUpdate::Forming
+        }
+    }
+
+    /// Returns the closed candles, oldest first.
+    pub fn closed(&self) -> &VecDeque<Candle> {
+        &self.closed
+    }
+
+    /// Returns the candle in progress, if any.
+    pub fn forming(&self) -> Option<&Candle> {
+        self.forming.as_ref()
+    }
+}
+
+/// Feeds a strategy from kline updates according to its execution timing.
+#[derive(Debug, Clone)]
+pub struct TimedStrategy<S> {
+    strategy: S,
+    timing: ExecutionTiming,
+    store: CandleStore,
+    last_evaluation_ms: Option<u64>, // Event time of the last intrabar evaluation
+    acted_bar: Option<u64>, // Open time of the bar the strategy last acted on intrabar
+}
+
+impl<S: Strategy> TimedStrategy<S> {
+    /// Wraps `strategy`, keeping `history` closed candles in the store.
+    pub fn new(strategy: S, timing: ExecutionTiming, history: usize) -> Self {
+        Self { strategy, timing, store: CandleStore::new(history), last_evaluation_ms: None, acted_bar: None }
+    }
+
+    /// Returns the wrapped strategy.
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// Returns the candle store.
+    pub fn store(&self) -> &CandleStore {
+        &self.store
+    }
+
+    /// Processes a kline update (closed or not) and returns the fills the strategy caused.
+    pub fn on_kline(&mut self, candle: &Candle) -> Vec<SimulatedFill> {
+        match self.store.update(candle) {
+            CandleUpdate::Stale => vec![],
+            CandleUpdate::Closed => {
+                self.last_evaluation_ms = None;
+                self.strategy.on_candle(candle)
+            },
+            CandleUpdate::Forming => match self.timing {
+                ExecutionTiming::OnClose => vec![],
+                ExecutionTiming::EveryUpdate => self.evaluate_forming(candle),
+                ExecutionTiming::Intrabar { throttle_ms } => {
+                    if self.last_evaluation_ms.is_some_and(|t| candle.event_time < t + throttle_ms) {
+                        return vec![];
+                    }
+                    self.evaluate_forming(candle)
+                },
+            },
+        }
+    }
+
+    fn evaluate_forming(&mut self, candle: &Candle) -> Vec<SimulatedFill> {
+        if self.acted_bar == Some(candle.open_time) {
+            return vec![];
+        }
+        self.last_evaluation_ms = Some(candle.event_time);
+        let fills = self.strategy.on_forming_candle(candle);
+        if !fills.is_empty() {
+            self.acted_bar = Some(candle.open_time);
+        }
+        fills
+    }
+}
@@ -0,0 +1,327 @@
+// src/strategy/walk_forward.rs
+
+//! This module runs the EMA crossover backtest as a walk-forward analysis, to check that optimized
+//! EMA settings hold up on data they were not fitted to. The candles are split into rolling windows:
+//! every EMA pair of the search grid is backtested on an in-sample window, the best pair by the
+//! objective is then traded on the following out-of-sample window, and the windows roll forward by
+//! the out-of-sample length. Only the out-of-sample trades are aggregated into the final report.
+//!
+//! `trading_bot walk-forward [backtest flags] --in-sample 1000 --out-of-sample 250 --fast-range 8:34:2 --slow-range 34:89:5 [--objective sharpe]`
+//!
+//! The balance compounds from one out-of-sample window to the next. A trade still open at the end
+//! of a window is dropped, as the next window starts flat. Amounts are in the quote asset.
+
+use std::error::Error;
+use std::fs;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::optimizer::{self, GridResult};
+use super::report::{self, EquityPoint, PerformanceSummary, TradeRecord};
+use super::{backtest_metrics_config, load_backtest_data, run_simulation, BacktestConfig, BacktestReport};
+use crate::currency::{CurrencyConverter, ReportingCurrency};
+use crate::metrics::{self, MetricsConfig, RiskMetrics};
+use crate::market_event::Candle;
+
+/// An inclusive range of EMA periods searched in steps, written "start:end:step" (or "start:end").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ParamRange {
+    pub start: usize,
+    pub end: usize,
+    pub step: usize,
+}
+
+impl ParamRange {
+    /// Parses "start:end:step"; the step defaults to 1.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = value.trim().split(':').collect();
+        let number = |part: &str| part.trim().parse::<usize>().map_err(|e| format!("Invalid range '{}': {}", value, e));
+        let range = match parts.as_slice() {
+            [start, end] => Self { start: number(start)?, end: number(end)?, step: 1 },
+            [start, end, step] => Self { start: number(start)?, end: number(end)?, step: number(step)? },
+            _ => return Err(format!("Invalid range '{}': expected start:end[:step]", value)),
+        };
+        if range.start == 0 || range.step == 0 || range.start > range.end {
+            return Err(format!("Invalid range '{}': the start and step must be positive and the start at most the end", value));
+        }
+        Ok(range)
+    }
+
+    /// Returns the periods of the range.
+    pub fn values(&self) -> Vec<usize> {
+        (self.start..=self.end).step_by(self.step).collect()
+    }
+}
+
+/// What the in-sample optimization maximizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    NetPnl,
+    #[default]
+    Sharpe,
+    Calmar,
+    ProfitFactor,
+}
+
+impl Objective {
+    /// Parses "net_pnl", "sharpe", "calmar" or "profit_factor".
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().replace('-', "_").as_str() {
+            "net_pnl" | "pnl" => Ok(Objective::NetPnl),
+            "sharpe" => Ok(Objective::Sharpe),
+            "calmar" => Ok(Objective::Calmar),
+            "profit_factor" => Ok(Objective::ProfitFactor),
+            _ => Err(format!("Invalid objective '{}': expected net_pnl, sharpe, calmar or profit_factor", value)),
+        }
+    }
+
+    /// Scores a backtest; undefined ratios score lowest, except a profit factor without losses.
+    pub fn score(&self, report: &BacktestReport) -> f64 {
+        match self {
+            Objective::NetPnl => report.summary.net_pnl,
+            Objective::Sharpe => report.risk_metrics.sharpe_ratio.unwrap_or(f64::NEG_INFINITY),
+            Objective::Calmar => report.risk_metrics.calmar_ratio.unwrap_or(f64::NEG_INFINITY),
+            Objective::ProfitFactor => match report.summary.profit_factor {
+                Some(factor) => factor,
+                None if report.summary.gross_profit > 0.0 => f64::INFINITY,
+                None => f64::NEG_INFINITY,
+            },
+        }
+    }
+}
+
+/// Settings of the walk-forward analysis; the strategy settings come from the `BacktestConfig`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WalkForwardConfig {
+    pub in_sample_bars: usize,
+    pub out_of_sample_bars: usize,
+    pub fast_range: ParamRange,
+    pub slow_range: ParamRange,
+    pub objective: Objective,
+}
+
+impl Default for WalkForwardConfig {
+    fn default() -> Self {
+        Self {
+            in_sample_bars: 1000,
+            out_of_sample_bars: 250,
+            fast_range: ParamRange { start: 8, end: 34, step: 2 },
+            slow_range: ParamRange { start: 34, end: 89, step: 5 },
+            objective: Objective::Sharpe,
+        }
+    }
+}
+
+impl WalkForwardConfig {
+    /// Takes the walk-forward flags out of the command line and returns them with the remaining
+    /// (backtest) arguments.
+    pub fn from_args(args: &[String]) -> Result<(Self, Vec<String>), String> {
+        let mut config = Self::default();
+        let mut rest = Vec::new();
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let key = flag.strip_prefix("--").unwrap_or(flag);
+            let value = iter.next().ok_or_else(|| format!("Missing value for --{}", key))?;
+            if !matches!(key, "in-sample" | "out-of-sample" | "fast-range" | "slow-range" | "objective") {
+                rest.extend([flag.clone(), value.clone()]);
+                continue;
+            }
+            let count = |value: &str| value.parse::<usize>().map_err(|e| format!("Invalid value '{}' for {}: {}", value, key, e));
+            match key {
+                "in-sample" => config.in_sample_bars = count(value)?,
+                "out-of-sample" => config.out_of_sample_bars = count(value)?,
+                "fast-range" => config.fast_range = ParamRange::parse(value)?,
+                "slow-range" => config.slow_range = ParamRange::parse(value)?,
+                _ => config.objective = Objective::parse(value)?,
+            }
+        }
+        config.validate()?;
+        Ok((config, rest))
+    }
+
+    /// Validates the settings.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.out_of_sample_bars == 0 {
+            return Err("The out-of-sample window must contain at least one bar.".to_string());
+        }
+        // The slow EMA needs `slow` bars to warm up before the in-sample window can trade
+        if self.in_sample_bars <= self.slow_range.end {
+            return Err(format!("The in-sample window ({} bars) must be longer than the slowest EMA period ({}).", self.in_sample_bars, self.slow_range.end));
+        }
+        if self.parameter_pairs().is_empty() {
+            return Err("The fast and slow ranges contain no pair with a fast period below the slow one.".to_string());
+        }
+        Ok(())
+    }
+
+    /// Returns every `(fast, slow)` pair of the search grid with `fast < slow`.
+    pub fn parameter_pairs(&self) -> Vec<(usize, usize)> {
+        let slow_values = self.slow_range.values();
+        self.fast_range.values().into_iter()
+            .flat_map(|fast| slow_values.iter().filter(move |&&slow| fast < slow).map(move |&slow| (fast, slow)))
+            .collect()
+    }
+
+    /// Splits `bars` candles into `(in_sample, out_of_sample)` index ranges, rolling forward by the
+    /// out-of-sample length. The last out-of-sample window may be shorter.
+    pub fn windows(&self, bars: usize) -> Vec<(Range<usize>, Range<usize>)> {
+        let mut windows = Vec::new();
+        let mut start = 0;
+        while start + self.in_sample_bars < bars {
+            let split = start + self.in_sample_bars;
+            windows.push((start..split, split..(split + self.out_of_sample_bars).min(bars)));
+            start += self.out_of_sample_bars;
+        }
+        windows
+    }
+}
+
+/// The result of one walk-forward step.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WalkForwardWindow {
+    pub in_sample_start_ms: i64,
+    pub out_of_sample_start_ms: i64,
+    pub out_of_sample_end_ms: i64,
+    pub fast_ema_period: usize,
+    pub slow_ema_period: usize,
+    pub in_sample_score: f64,
+    pub in_sample_return: f64, // Total return of the chosen pair on the in-sample window
+    pub out_of_sample_return: f64,
+    pub out_of_sample: PerformanceSummary,
+}
+
+/// Aggregated out-of-sample performance of a walk-forward analysis.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WalkForwardReport {
+    pub config: BacktestConfig,
+    pub walk_forward: WalkForwardConfig,
+    pub windows: Vec<WalkForwardWindow>,
+    pub summary: PerformanceSummary, // Of all out-of-sample trades
+    pub risk_metrics: RiskMetrics, // Of the stitched out-of-sample equity curve
+    pub trades: Vec<TradeRecord>,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+impl WalkForwardReport {
+    /// Writes the report as pretty-printed JSON.
+    pub fn write_json(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| format!("Failed to serialize the walk-forward report: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+}
+
+/// Loads the candles, runs the walk-forward analysis, prints it and writes `--output` if set.
+pub async fn run(config: &BacktestConfig, walk_forward: &WalkForwardConfig) -> Result<WalkForwardReport, Box<dyn Error>> {
+    println!("--- Starting Walk-Forward Analysis ---");
+    if config.reporting_currency != ReportingCurrency::Usd {
+        return Err("Walk-forward reports are in the quote asset; --currency is not supported.".into());
+    }
+    let candles = load_backtest_data(config).await?;
+    let metrics_config = backtest_metrics_config(config)?;
+    let report = analyze(&candles, config, walk_forward, &metrics_config)?;
+    print_walk_forward(&report, &metrics_config);
+    if let Some(path) = &config.output {
+        report.write_json(path)?;
+        println!("\nWalk-forward report written to {}", path);
+    }
+    Ok(report)
+}
+
+/// Runs the walk-forward analysis over the candles.
+fn analyze(candles: &[Candle], config: &BacktestConfig, walk_forward: &WalkForwardConfig, metrics_config: &MetricsConfig) -> Result<WalkForwardReport, String> {
+    walk_forward.validate()?;
+    let windows = walk_forward.windows(candles.len());
+    if windows.is_empty() {
+        return Err(format!("Not enough historical data for a {}-bar in-sample window ({} candles).", walk_forward.in_sample_bars, candles.len()));
+    }
+    let converter = CurrencyConverter::default();
+    let pairs = walk_forward.parameter_pairs();
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let time_of = |index: usize| candles[index].open_time as i64;
+
+    let mut balance = config.account_balance;
+    let mut results = Vec::with_capacity(windows.len());
+    let mut trades = Vec::new();
+    let mut equity_curve = vec![EquityPoint { time_ms: time_of(windows[0].1.start), equity: balance, price: candles[windows[0].1.start - 1].close }];
+    for (in_sample, out_of_sample) in windows {
+        let window_config = |fast: usize, slow: usize| BacktestConfig {
+            fast_ema_period: fast,
+            slow_ema_period: slow,
+            account_balance: balance,
+            monte_carlo_runs: 0,
+            ..config.clone()
+        };
+
+        // Optimize on the in-sample window in parallel; ties keep the first pair of the grid
+        let candidates: Vec<BacktestConfig> = pairs.iter().map(|&(fast, slow)| window_config(fast, slow)).collect();
+        let mut best: Option<(usize, GridResult)> = None;
+        for (index, result) in optimizer::evaluate(&candles[in_sample.clone()], &candidates, metrics_config, walk_forward.objective, threads).into_iter().enumerate() {
+            if best.as_ref().is_none_or(|(_, best)| result.score > best.score) {
+                best = Some((index, result));
+            }
+        }
+        let Some((index, in_sample_result)) = best else { continue };
+        let chosen = &candidates[index];
+
+        // Trade the chosen pair out of sample, with the preceding bars warming up the EMAs
+        let warm_up = out_of_sample.start - chosen.slow_ema_period;
+        let report = run_simulation(candles[warm_up..out_of_sample.end].iter().copied(), &[], metrics_config, chosen, &converter, false);
+        balance += report.trades.iter().map(|t| t.pnl).sum::<f64>();
+        equity_curve.extend(report.equity_curve.iter().copied());
+        trades.extend(report.trades.iter().cloned());
+        results.push(WalkForwardWindow {
+            in_sample_start_ms: time_of(in_sample.start),
+            out_of_sample_start_ms: time_of(out_of_sample.start),
+            out_of_sample_end_ms: time_of(out_of_sample.end - 1),
+            fast_ema_period: chosen.fast_ema_period,
+            slow_ema_period: chosen.slow_ema_period,
+            in_sample_score: in_sample_result.score,
+            in_sample_return: in_sample_result.risk_metrics.total_return,
+            out_of_sample_return: report.risk_metrics.total_return,
+            out_of_sample: report.summary,
+        });
+    }
+
+    let equity: Vec<f64> = equity_curve.iter().map(|p| p.equity).collect();
+    let prices: Vec<f64> = equity_curve.iter().map(|p| p.price).collect();
+    let summary = PerformanceSummary::from_trades(&trades, config.account_balance, balance, metrics::max_drawdown(&equity), max_consecutive_losses(&trades));
+    Ok(WalkForwardReport {
+        config: config.clone(),
+        walk_forward: walk_forward.clone(),
+        windows: results,
+        summary,
+        risk_metrics: metrics::compute_risk_metrics(&equity, Some(&prices), metrics_config),
+        trades,
+        equity_curve,
+    })
+}
+
+/// Returns the longest run of losing trades.
+fn max_consecutive_losses(trades: &[TradeRecord]) -> u32 {
+    let (mut current, mut longest) = (0, 0);
+    for trade in trades {
+        current = if trade.pnl < 0.0 { current + 1 } else { 0 };
+        longest = longest.max(current);
+    }
+    longest
+}
+
+/// Prints the chosen parameters per window and the aggregated out-of-sample performance.
+pub fn print_walk_forward(report: &WalkForwardReport, metrics_config: &MetricsConfig) {
+    let format_date = |ms: i64| chrono::DateTime::from_timestamp_millis(ms).map(|t| t.format("%Y-%m-%d").to_string()).unwrap_or_default();
+    println!("\n--- Walk-Forward Windows ({} bars in sample, {} out of sample) ---", report.walk_forward.in_sample_bars, report.walk_forward.out_of_sample_bars);
+    println!("{:<12} | {:<12} | {:>9} | {:>12} | {:>10} | {:>10} | {:>7}", "In Sample", "Out Sample", "EMAs", "IS Score", "IS Return", "OOS Return", "Trades");
+    println!("{:-<89}", "");
+    for window in &report.windows {
+        println!("{:<12} | {:<12} | {:>9} | {:>12.3} | {:>9.2}% | {:>9.2}% | {:>7}",
+            format_date(window.in_sample_start_ms), format_date(window.out_of_sample_start_ms),
+            format!("{}/{}", window.fast_ema_period, window.slow_ema_period), window.in_sample_score,
+            window.in_sample_return * 100.0, window.out_of_sample_return * 100.0, window.out_of_sample.total_trades);
+    }
+    println!("{:-<89}", "");
+    println!("\nAggregated out-of-sample performance:");
+    report::print_performance_report(&report.summary, ReportingCurrency::Usd);
+    metrics::print_risk_metrics(&report.risk_metrics, metrics_config);
+}
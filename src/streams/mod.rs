@@ -5,6 +5,10 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::market_data::KlineInterval;
+use crate::market_event::ContractType;
+use crate::order::{ExecutionType, OrderStatus, PositionSide, WorkingType};
+
 /// Represents an aggregated trade stream message (`<symbol>@aggTrade`).
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -29,8 +33,8 @@ pub struct AggTradeStream {
     pub trade_time: u64,
     #[serde(rename = "m")]
         pub maker: bool,
-    #[serde(rename = "M")]
-    pub ignore: bool,
+    #[serde(rename = "M", default)]
+    pub ignore: bool, // Spot only; absent on Futures streams
 }
 
 
@@ -52,6 +56,8 @@ pub struct DepthStream {
     pub first_update_id: u64, // First update ID in event
     #[serde(rename = "u")]
     pub final_update_id: u64, // Final update ID in event
+    #[serde(rename = "pu", default)]
+    pub prev_final_update_id: Option<u64>, // Final update ID of the previous event (Futures only)
     #[serde(rename = "b")]
     pub bids: Vec<DepthLevel>, // Bids to be updated/inserted
     #[serde(rename = "a")]
@@ -264,6 +270,75 @@ pub struct BalanceUpdateEvent {
     #[serde(rename = "T")]
     pub clear_time: u64, // The time of the balance clear
 }
+/// Represents a Futures order update event (`ORDER_TRADE_UPDATE`) from the USDⓈ-M user data stream.
+/// This event is pushed when a new order is created or an order status changes.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FuturesOrderTradeUpdateEvent {
+    #[serde(rename = "e")]
+    pub event_type: String, // ORDER_TRADE_UPDATE
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(rename = "o")]
+    pub order: FuturesOrderUpdate,
+}
+
+/// Represents the order details within a `FuturesOrderTradeUpdateEvent`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FuturesOrderUpdate {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "c")]
+    pub client_order_id: String,
+    #[serde(rename = "S")]
+    pub side: String, // BUY or SELL
+    #[serde(rename = "o")]
+    pub order_type: String, // MARKET, LIMIT, STOP_MARKET, ...
+    #[serde(rename = "f")]
+    pub time_in_force: String,
+    #[serde(rename = "q")]
+    pub original_quantity: String,
+    #[serde(rename = "p")]
+    pub original_price: String,
+    #[serde(rename = "ap")]
+    pub average_price: String,
+    #[serde(rename = "sp", default)]
+    pub stop_price: String,
+    #[serde(rename = "x")]
+    pub execution_type: ExecutionType,
+    #[serde(rename = "X")]
+    pub order_status: OrderStatus,
+    #[serde(rename = "i")]
+    pub order_id: u64,
+    #[serde(rename = "l")]
+    pub last_filled_quantity: String,
+    #[serde(rename = "z")]
+    pub cumulative_filled_quantity: String,
+    #[serde(rename = "L")]
+    pub last_filled_price: String,
+    #[serde(rename = "N", default)]
+    pub commission_asset: Option<String>, // Not present if no commission was charged
+    #[serde(rename = "n", default)]
+    pub commission: Option<String>,
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+    #[serde(rename = "t")]
+    pub trade_id: u64,
+    #[serde(rename = "m", default)]
+    pub is_maker: bool,
+    #[serde(rename = "R", default)]
+    pub is_reduce_only: bool,
+    #[serde(rename = "ps", default)]
+    pub position_side: PositionSide,
+    #[serde(rename = "wt", default)]
+    pub working_type: WorkingType,
+    #[serde(rename = "cp", default)]
+    pub close_position: bool,
+    #[serde(rename = "rp", default)]
+    pub realized_profit: String,
+}
+
 // src/websocket/kline.rs
 
 
@@ -320,3 +395,172 @@ pub struct KlineData {
     #[serde(rename = "B")]
     pub ignore: String, // This field is often ignored/unused in Binance kline data
 }
+
+// src/websocket/mark_price.rs
+
+
+/// Represents a mark price and funding rate stream message (`<symbol>@markPrice` or
+/// `<symbol>@markPrice@1s`). Binance margins positions, and liquidates them, at the mark price.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkPriceStream {
+    #[serde(rename = "e")]
+    pub event_type: String,
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p")]
+    pub mark_price: String,
+    #[serde(rename = "i")]
+    pub index_price: String,
+    #[serde(rename = "P")]
+    pub estimated_settle_price: String, // Only meaningful in the last hour before a settlement
+    #[serde(rename = "r")]
+    pub funding_rate: String, // Predicted rate of the next settlement
+    #[serde(rename = "T")]
+    pub next_funding_time: u64,
+}
+
+/// Returns the mark price stream name of a symbol, updated every 3 seconds or, with
+/// `every_second`, every second (e.g. `btcusdt@markPrice@1s`).
+pub fn mark_price_stream(symbol: &str, every_second: bool) -> String {
+    let suffix = if every_second { "@1s" } else { "" };
+    format!("{}@markPrice{}", symbol.to_lowercase(), suffix)
+}
+
+// src/websocket/force_order.rs
+
+
+/// Represents a forced liquidation order stream message (`<symbol>@forceOrder` or `!forceOrder@arr`).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForceOrderStream {
+    #[serde(rename = "e")]
+    pub event_type: String,
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "o")]
+    pub order: ForceOrderData,
+}
+
+/// Represents the liquidation order within a `ForceOrderStream` message.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForceOrderData {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "S")]
+    pub side: String, // SELL when a long position is liquidated
+    #[serde(rename = "o")]
+    pub order_type: String,
+    #[serde(rename = "f")]
+    pub time_in_force: String,
+    #[serde(rename = "q")]
+    pub quantity: String,
+    #[serde(rename = "p")]
+    pub price: String,
+    #[serde(rename = "ap")]
+    pub average_price: String,
+    #[serde(rename = "X")]
+    pub status: String,
+    #[serde(rename = "l")]
+    pub last_filled_quantity: String,
+    #[serde(rename = "z")]
+    pub filled_quantity: String,
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+}
+
+/// Returns the liquidation stream name of a symbol (e.g. `btcusdt@forceOrder`), or of every
+/// symbol (`!forceOrder@arr`) for `None`.
+pub fn force_order_stream(symbol: Option<&str>) -> String {
+    match symbol {
+        Some(symbol) => format!("{}@forceOrder", symbol.to_lowercase()),
+        None => "!forceOrder@arr".to_string(),
+    }
+}
+
+/// Represents a continuous contract kline stream message
+/// (`<pair>_<contractType>@continuousKline_<interval>`).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuousKlineStream {
+    #[serde(rename = "e")]
+    pub event_type: String,
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "ct")]
+    pub contract_type: String, // PERPETUAL, CURRENT_QUARTER or NEXT_QUARTER
+    #[serde(rename = "k")]
+    pub kline: PairKlineData,
+}
+
+/// Represents an index price (`<pair>@indexPriceKline_<interval>`) or mark price
+/// (`<symbol>@markPriceKline_<interval>`) kline stream message.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceKlineStream {
+    #[serde(rename = "e")]
+    pub event_type: String, // indexPrice_kline or markPrice_kline
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "k")]
+    pub kline: PairKlineData,
+}
+
+/// Represents the kline data within pair kline stream messages. Unlike `KlineData` it carries no
+/// symbol, and index and mark price klines have no volume (sent as "0").
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PairKlineData {
+    #[serde(rename = "t")]
+    pub open_time: u64,
+    #[serde(rename = "T")]
+    pub close_time: u64,
+    #[serde(rename = "i")]
+    pub interval: String,
+    #[serde(rename = "f")]
+    pub first_update_id: u64,
+    #[serde(rename = "L")]
+    pub last_update_id: u64,
+    #[serde(rename = "o")]
+    pub open: String,
+    #[serde(rename = "c")]
+    pub close: String,
+    #[serde(rename = "h")]
+    pub high: String,
+    #[serde(rename = "l")]
+    pub low: String,
+    #[serde(rename = "v")]
+    pub volume: String,
+    #[serde(rename = "n")]
+    pub number_of_trades: u64, // Number of price updates for index and mark price klines
+    #[serde(rename = "x")]
+    pub is_closed: bool,
+    #[serde(rename = "q")]
+    pub quote_asset_volume: String,
+    #[serde(rename = "V")]
+    pub taker_buy_base_asset_volume: String,
+    #[serde(rename = "Q")]
+    pub taker_buy_quote_asset_volume: String,
+}
+
+/// Returns the continuous kline stream name of a pair, e.g. `btcusdt_perpetual@continuousKline_1m`.
+pub fn continuous_kline_stream(pair: &str, contract_type: ContractType, interval: KlineInterval) -> String {
+    format!("{}_{}@continuousKline_{}", pair.to_lowercase(), contract_type.as_str().to_lowercase(), interval.to_string())
+}
+
+/// Returns the index price kline stream name of a pair, e.g. `btcusdt@indexPriceKline_1m`.
+pub fn index_price_kline_stream(pair: &str, interval: KlineInterval) -> String {
+    format!("{}@indexPriceKline_{}", pair.to_lowercase(), interval.to_string())
+}
+
+/// Returns the mark price kline stream name of a symbol, e.g. `btcusdt@markPriceKline_1m`.
+pub fn mark_price_kline_stream(symbol: &str, interval: KlineInterval) -> String {
+    format!("{}@markPriceKline_{}", symbol.to_lowercase(), interval.to_string())
+}
@@ -0,0 +1,42 @@
+// src/telemetry/file.rs
+
+//! This module opens the log files: log lines are written as JSON, one object per line, to a file
+//! per day (UTC) in the log directory, e.g. `trading-bot.2024-05-01.jsonl`, so the logs of a live
+//! trading incident survive the terminal. Rotation and retention are `tracing-appender`'s: the
+//! oldest files beyond the retention are deleted when a file is opened.
+//!
+//! A line looks like
+//!
+//! `{"timestamp":"2024-05-01T12:00:00.123456Z","level":"INFO","fields":{"message":"Order placed"},"target":"trading_bot::webhook","spans":[{"id":"sig-1","name":"signal"}]}`
+//!
+//! so e.g. `jq 'select(.spans[0].id == "sig-1")'` follows one signal.
+
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+
+/// Default number of days log files are kept.
+pub const DEFAULT_LOG_RETENTION_DAYS: u32 = 14;
+/// Name of the log files before the date.
+const LOG_FILE_PREFIX: &str = "trading-bot";
+/// Extension of the log files.
+const LOG_FILE_SUFFIX: &str = "jsonl";
+
+/// Returns the path of the log file for `date` in `dir`.
+pub fn log_file_path(dir: &Path, date: NaiveDate) -> PathBuf {
+    dir.join(format!("{}.{}.{}", LOG_FILE_PREFIX, date.format("%Y-%m-%d"), LOG_FILE_SUFFIX))
+}
+
+/// Returns a writer appending to the current day's file in `dir` (created when missing), keeping
+/// the files of the last `retention_days` days. A retention of 0 keeps every file.
+pub fn daily_appender(dir: &Path, retention_days: u32) -> Result<RollingFileAppender, String> {
+    let mut builder = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX);
+    if retention_days > 0 {
+        builder = builder.max_log_files(retention_days as usize);
+    }
+    builder.build(dir).map_err(|e| format!("Failed to open the log files in {}: {}", dir.display(), e))
+}
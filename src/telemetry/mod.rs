@@ -0,0 +1,120 @@
+// src/telemetry/mod.rs
+
+//! This module sets up the bot's logging with `tracing` and `tracing-subscriber`. Spans tie the log
+//! lines of one unit of work together across async tasks: a webhook signal runs in a `signal` span
+//! carrying its tracking ID, with child spans for the price fetch, the risk checks and the order
+//! request (which records the client order ID), and fills from the user data stream run in a
+//! `fill` span with the same client order ID. Every line is prefixed with its spans, e.g.
+//!
+//! `2024-05-01T12:00:00.123456Z  INFO signal{id="sig-1" symbol="BTCUSDT"}:order{client_order_id="whb123456"}: trading_bot::webhook: Order placed`
+//!
+//! so `grep sig-1` (then the client order ID) follows a single trade. Records of dependencies that
+//! log through the `log` crate are printed the same way.
+//!
+//! The filter is an `EnvFilter` read from `RUST_LOG`: a default level and per-target levels, e.g.
+//! `info,trading_bot::webhook=debug,hyper=warn` (`error` when unset); a target matches every target
+//! it prefixes. Spans are filtered like events, so lines only carry the spans of enabled levels
+//! (the bot's spans are at `info`).
+//!
+//! With `LOG_DIR` set, lines are also written as JSON to daily files in that directory, kept for
+//! `LOG_RETENTION_DAYS` (14 by default; 0 keeps them all), see `file`.
+//!
+//! With the `otlp` feature and `OTEL_EXPORTER_OTLP_ENDPOINT` set, spans are also exported to an
+//! OpenTelemetry collector, see `otlp`.
+
+pub mod file;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use tracing::Subscriber;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use self::file::DEFAULT_LOG_RETENTION_DAYS;
+
+/// Target prefix of the bot's own spans and events.
+#[cfg_attr(not(feature = "otlp"), allow(dead_code))]
+const CRATE_TARGET: &str = "trading_bot";
+/// Filter used when `RUST_LOG` is unset.
+const DEFAULT_LOG_FILTER: &str = "error";
+
+/// Keeps the writer of the log files flushing until the process exits.
+static FILE_WRITER_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// Parses `RUST_LOG` style directives, e.g. `warn,trading_bot::webhook=debug`.
+pub fn parse_filter(spec: &str) -> Result<EnvFilter, String> {
+    EnvFilter::builder().parse(spec).map_err(|e| format!("Invalid RUST_LOG '{}': {}", spec, e))
+}
+
+/// Returns the layer printing lines as plain text (no colors, so they stay greppable), spans first,
+/// to `writer`.
+pub fn text_layer<S, W>(writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer().with_ansi(false).with_writer(writer)
+}
+
+/// Returns the layer writing lines as JSON objects, with the list of their spans, to `writer`.
+pub fn json_layer<S, W>(writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer().json().with_current_span(false).with_span_list(true).with_writer(writer)
+}
+
+/// Returns the JSON layer writing to the daily files in `LOG_DIR`, when it is set.
+fn file_layer_from_env<S>() -> Result<Option<impl Layer<S>>, String>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let Some(dir) = std::env::var("LOG_DIR").ok().filter(|dir| !dir.trim().is_empty()) else { return Ok(None) };
+    let retention_days = match std::env::var("LOG_RETENTION_DAYS") {
+        Ok(days) => days.trim().parse::<u32>().map_err(|_| format!("Invalid LOG_RETENTION_DAYS '{}'", days))?,
+        Err(_) => DEFAULT_LOG_RETENTION_DAYS,
+    };
+    let (writer, guard) = tracing_appender::non_blocking(file::daily_appender(Path::new(dir.trim()), retention_days)?);
+    let _ = FILE_WRITER_GUARD.set(guard);
+    Ok(Some(json_layer(writer)))
+}
+
+/// Installs the subscriber and the `log` bridge with the filter from `RUST_LOG`, writing to stderr
+/// and to the JSON files in `LOG_DIR`. Fails when a subscriber or logger is already installed, or
+/// a setting is invalid.
+pub fn try_init() -> Result<(), String> {
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(spec) => parse_filter(&spec)?,
+        Err(_) => parse_filter(DEFAULT_LOG_FILTER)?,
+    };
+    #[cfg(feature = "otlp")]
+    let span_export = otlp::OtlpConfig::from_env()?.map(|otlp| otlp::layer(&otlp)).transpose()?;
+    #[cfg(not(feature = "otlp"))]
+    let span_export: Option<tracing_subscriber::layer::Identity> = None;
+    #[cfg(not(feature = "otlp"))]
+    if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").is_ok() {
+        eprintln!("OTEL_EXPORTER_OTLP_ENDPOINT is ignored: this build has no OTLP export (feature `otlp`)");
+    }
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(text_layer(std::io::stderr))
+        .with(file_layer_from_env()?)
+        .with(span_export)
+        .try_init()
+        .map_err(|e| format!("Failed to install the tracing subscriber: {}", e))
+}
+
+/// Installs logging as `try_init` does; an error is printed and logging stays off.
+pub fn init() {
+    if let Err(e) = try_init() {
+        eprintln!("{}", e);
+    }
+}
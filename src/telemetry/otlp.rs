@@ -0,0 +1,248 @@
+// src/telemetry/otlp.rs
+
+//! This module exports the bot's spans and metrics with OTLP (the OpenTelemetry protocol) over
+//! HTTP, so they land in the same stack as other services: traces in e.g. Tempo or Jaeger, metrics
+//! in Prometheus (through an OpenTelemetry Collector, or Prometheus' own OTLP receiver). Needs the
+//! `otlp` feature.
+//!
+//! Spans reach the OpenTelemetry SDK through `tracing-opentelemetry` and are exported in batches by
+//! `opentelemetry-otlp`. Metrics are those served on `/metrics`: every export interval the
+//! Prometheus text exposition is recorded into OpenTelemetry instruments (counters as counters,
+//! every other type as gauges), which the SDK's periodic reader exports.
+//!
+//! It is configured with the standard OpenTelemetry variables: `OTEL_EXPORTER_OTLP_ENDPOINT` (e.g.
+//! `http://localhost:4318`; `/v1/traces` and `/v1/metrics` are appended), `OTEL_SERVICE_NAME`
+//! (`trading-bot` by default), `OTEL_BSP_SCHEDULE_DELAY` (milliseconds between span exports, 5000)
+//! and `OTEL_METRIC_EXPORT_INTERVAL` (milliseconds between metric exports, 60000).
+//!
+//! Spans of the bot itself are exported (`signal`, `order`, `fill`, ...).
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use opentelemetry::metrics::{Counter, Gauge, Meter, MeterProvider as _};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::trace::{BatchConfigBuilder, BatchSpanProcessor, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing::{warn, Subscriber};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use super::CRATE_TARGET;
+use crate::config::{self, ExtraMetrics};
+
+/// Default service name of the exported spans and metrics.
+pub const DEFAULT_OTLP_SERVICE_NAME: &str = "trading-bot";
+/// Default time between span exports.
+pub const DEFAULT_SPAN_EXPORT_DELAY: Duration = Duration::from_secs(5);
+/// Default time between metric exports.
+pub const DEFAULT_METRIC_EXPORT_INTERVAL: Duration = Duration::from_secs(60);
+/// Spans waiting for export; more are dropped until the next export.
+const SPAN_QUEUE_CAPACITY: usize = 2048;
+/// Spans sent in one request.
+const MAX_SPANS_PER_EXPORT: usize = 512;
+/// How long one export request may take.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Collector endpoint and export schedule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpConfig {
+    pub endpoint: String, // Base URL, without `/v1/traces`
+    pub service_name: String,
+    pub span_export_delay: Duration,
+    pub metric_export_interval: Duration,
+}
+
+fn read_millis(name: &str, default: Duration) -> Result<Duration, String> {
+    match std::env::var(name) {
+        Ok(value) => value.trim().parse::<u64>().ok().filter(|ms| *ms > 0).map(Duration::from_millis)
+            .ok_or_else(|| format!("Invalid {} '{}' (expected milliseconds)", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+impl OtlpConfig {
+    /// Reads the configuration from the environment; `None` when `OTEL_EXPORTER_OTLP_ENDPOINT` is unset.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(endpoint) = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|e| !e.trim().is_empty()) else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            endpoint: endpoint.trim().trim_end_matches('/').to_string(),
+            service_name: std::env::var("OTEL_SERVICE_NAME").ok().filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_OTLP_SERVICE_NAME.to_string()),
+            span_export_delay: read_millis("OTEL_BSP_SCHEDULE_DELAY", DEFAULT_SPAN_EXPORT_DELAY)?,
+            metric_export_interval: read_millis("OTEL_METRIC_EXPORT_INTERVAL", DEFAULT_METRIC_EXPORT_INTERVAL)?,
+        }))
+    }
+}
+
+fn resource(service_name: &str) -> Resource {
+    Resource::builder().with_service_name(service_name.to_string()).build()
+}
+
+/// Returns the tracer provider exporting spans to `/v1/traces` in batches, every `span_export_delay`.
+pub fn tracer_provider(config: &OtlpConfig) -> Result<SdkTracerProvider, String> {
+    let url = format!("{}/v1/traces", config.endpoint);
+    let exporter = SpanExporter::builder().with_http().with_endpoint(&url).with_timeout(EXPORT_TIMEOUT).build()
+        .map_err(|e| format!("Failed to set up the OTLP span export to {}: {}", url, e))?;
+    let batches = BatchConfigBuilder::default()
+        .with_scheduled_delay(config.span_export_delay)
+        .with_max_queue_size(SPAN_QUEUE_CAPACITY)
+        .with_max_export_batch_size(MAX_SPANS_PER_EXPORT)
+        .build();
+    Ok(SdkTracerProvider::builder()
+        .with_span_processor(BatchSpanProcessor::builder(exporter).with_batch_config(batches).build())
+        .with_resource(resource(&config.service_name))
+        .build())
+}
+
+/// Returns the layer exporting the bot's spans (targets under `trading_bot`) over OTLP.
+pub fn layer<S>(config: &OtlpConfig) -> Result<impl Layer<S> + use<S>, String>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let tracer = tracer_provider(config)?.tracer(CRATE_TARGET); // The tracer keeps its provider alive
+    Ok(tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(filter_fn(|metadata| metadata.target().starts_with(CRATE_TARGET))))
+}
+
+/// Parses the labels of a sample, e.g. `symbol="BTCUSDT",side="BUY"`.
+fn parse_labels(labels: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = labels;
+    while let Some((key, after)) = rest.split_once("=\"") {
+        let mut value = String::new();
+        let mut chars = after.char_indices();
+        let mut end = after.len();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, escaped)) => value.push(escaped),
+                    None => {}
+                },
+                '"' => {
+                    end = i + 1;
+                    break;
+                }
+                c => value.push(c),
+            }
+        }
+        pairs.push((key.trim_matches(|c: char| c == ',' || c.is_whitespace()).to_string(), value));
+        rest = &after[end..];
+    }
+    pairs
+}
+
+/// A metric name, its labels and the value.
+type Sample<'a> = (&'a str, Vec<(String, String)>, f64);
+
+/// Parses one sample line, e.g. `trading_bot_positions{symbol="BTCUSDT"} 2`.
+fn parse_sample(line: &str) -> Option<Sample<'_>> {
+    let (series, value) = line.rsplit_once(' ')?;
+    let value = value.parse::<f64>().ok()?;
+    match series.split_once('{') {
+        Some((name, labels)) => Some((name, parse_labels(labels.strip_suffix('}')?), value)),
+        None => Some((series, Vec::new(), value)),
+    }
+}
+
+/// One sample of a Prometheus text exposition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    pub name: String,
+    pub description: String, // From the `# HELP` line
+    pub counter: bool, // `# TYPE ... counter`: the value is a running total
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+/// Parses the samples of a Prometheus text exposition, with the type and help of their metric.
+pub fn parse_exposition(exposition: &str) -> Vec<MetricSample> {
+    let mut types: HashMap<&str, &str> = HashMap::new();
+    let mut help: HashMap<&str, &str> = HashMap::new();
+    let mut samples = Vec::new();
+    for line in exposition.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut parts = comment.trim_start().splitn(3, ' ');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("TYPE"), Some(name), Some(kind)) => { types.insert(name, kind.trim()); }
+                (Some("HELP"), Some(name), Some(text)) => { help.insert(name, text); }
+                _ => {}
+            }
+            continue;
+        }
+        let Some((name, labels, value)) = parse_sample(line) else { continue };
+        samples.push(MetricSample {
+            name: name.to_string(),
+            description: help.get(name).copied().unwrap_or_default().to_string(),
+            counter: types.get(name) == Some(&"counter"),
+            labels,
+            value,
+        });
+    }
+    samples
+}
+
+/// Records expositions into OpenTelemetry instruments, created on first sight of a metric.
+/// Counters are added the increase of their total since the previous exposition.
+#[derive(Default)]
+pub struct ExpositionRecorder {
+    gauges: HashMap<String, Gauge<f64>>,
+    counters: HashMap<String, Counter<f64>>,
+    totals: HashMap<(String, Vec<(String, String)>), f64>, // Last total of each counter series
+}
+
+impl ExpositionRecorder {
+    /// Records the samples of `exposition` with `meter`.
+    pub fn record(&mut self, meter: &Meter, exposition: &str) {
+        for sample in parse_exposition(exposition) {
+            let attributes: Vec<KeyValue> = sample.labels.iter().map(|(key, value)| KeyValue::new(key.clone(), value.clone())).collect();
+            if sample.counter {
+                let previous = self.totals.insert((sample.name.clone(), sample.labels), sample.value).unwrap_or(0.0);
+                let counter = self.counters.entry(sample.name.clone())
+                    .or_insert_with(|| meter.f64_counter(sample.name).with_description(sample.description).build());
+                counter.add((sample.value - previous).max(0.0), &attributes); // A lower total is a reset
+            } else {
+                let gauge = self.gauges.entry(sample.name.clone())
+                    .or_insert_with(|| meter.f64_gauge(sample.name).with_description(sample.description).build());
+                gauge.record(sample.value, &attributes);
+            }
+        }
+    }
+}
+
+/// Returns the meter provider exporting to `/v1/metrics` every `metric_export_interval`.
+pub fn meter_provider(config: &OtlpConfig) -> Result<SdkMeterProvider, String> {
+    let url = format!("{}/v1/metrics", config.endpoint);
+    let exporter = MetricExporter::builder().with_http().with_endpoint(&url).with_timeout(EXPORT_TIMEOUT).build()
+        .map_err(|e| format!("Failed to set up the OTLP metric export to {}: {}", url, e))?;
+    Ok(SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter).with_interval(config.metric_export_interval).build())
+        .with_resource(resource(&config.service_name))
+        .build())
+}
+
+/// Records the `/metrics` exposition (with `extra_metrics`) every `metric_export_interval` for
+/// export to `/v1/metrics`. Runs until the task is dropped.
+pub async fn export_metrics(config: OtlpConfig, extra_metrics: ExtraMetrics) {
+    let provider = match meter_provider(&config) {
+        Ok(provider) => provider,
+        Err(e) => return warn!("{}", e),
+    };
+    let meter = provider.meter(CRATE_TARGET);
+    let mut recorder = ExpositionRecorder::default();
+    let started = Instant::now();
+    loop {
+        let mut exposition = config::render_metrics(started.elapsed().as_secs());
+        exposition.push_str(&extra_metrics.read().await);
+        recorder.record(&meter, &exposition);
+        tokio::time::sleep(config.metric_export_interval).await;
+    }
+}
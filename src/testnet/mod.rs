@@ -0,0 +1,141 @@
+// src/testnet/mod.rs
+
+//! This module provides a development helper that resets the Binance Futures **testnet** state the
+//! bot cares about, so integration tests and manual experiments start from a known clean state:
+//! it cancels all open orders, closes all positions with market orders, clears the local state
+//! directory (event log, runtime record) and verifies the remaining balances.
+//!
+//! Only compiled with the `testnet-tools` feature (`cargo run --features testnet-tools --bin
+//! testnet_reset`), and refuses to run unless both API base URLs point at the testnet.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::{info, warn};
+
+use crate::account_info::PositionInfo;
+use crate::config::RuntimeConfig;
+use crate::order::{NewOrderRequest, OrderSide, OrderType, PositionSide};
+use crate::rest_api::RestClient;
+use crate::websocket::WebSocketClient;
+
+/// Fails unless both the REST and the WebSocket API base URLs are testnet URLs.
+pub fn ensure_testnet(rest_api_base_url: &str, ws_api_base_url: &str) -> Result<(), String> {
+    for url in [rest_api_base_url, ws_api_base_url] {
+        if !url.to_lowercase().contains("testnet") {
+            return Err(format!("Refusing to reset a non-testnet environment ({}).", url));
+        }
+    }
+    Ok(())
+}
+
+/// Builds the market order closing a position, or `None` for an empty position.
+/// Hedge mode positions are closed via their `LONG`/`SHORT` side, one-way positions with `reduceOnly`.
+pub fn close_position_request(position: &PositionInfo, client_order_id: &str) -> Option<NewOrderRequest> {
+    let amount = position.position_amt.parse::<f64>().ok().filter(|a| *a != 0.0)?;
+    let side = if amount > 0.0 { OrderSide::Sell } else { OrderSide::Buy };
+    let request = NewOrderRequest::new(&position.symbol, side, OrderType::Market)
+        .quantity(amount.abs())
+        .new_client_order_id(client_order_id);
+    Some(match PositionSide::from_str_opt(&position.position_side) {
+        Some(ps @ (PositionSide::Long | PositionSide::Short)) => request.position_side(ps),
+        _ => request.reduce_only(true),
+    })
+}
+
+/// Removes the files inside the state directory (the directory itself is kept).
+/// Returns the removed paths; a missing directory is already clean.
+pub fn clear_state_dir(state_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = match fs::read_dir(state_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read state directory {}: {}", state_dir.display(), e)),
+    };
+    let mut removed = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| format!("Failed to read state directory entry: {}", e))?.path();
+        let result = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+        result.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+/// What a testnet reset did.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResetReport {
+    pub cancelled_orders: usize,
+    pub closed_positions: usize,
+    pub removed_files: Vec<PathBuf>,
+    pub balances: Vec<(String, f64)>, // Non-zero wallet balances after the reset
+    pub problems: Vec<String>, // Anything that is still not clean
+}
+
+impl ResetReport {
+    /// Returns true when the account and the local state are clean.
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Resets the testnet account and the local state of `config`.
+///
+/// # Arguments
+/// * `config` - The runtime configuration; its API base URLs must be testnet URLs.
+/// * `clear_state` - Whether to clear the local state directory.
+pub async fn reset_testnet(config: &RuntimeConfig, clear_state: bool) -> Result<ResetReport, String> {
+    ensure_testnet(&config.rest_api_base_url, &config.ws_api_base_url)?;
+    let rest_client = RestClient::new(config.api_key.clone(), config.secret_key.clone(), config.rest_api_base_url.clone())
+        .with_recv_window(config.recv_window_ms)
+        .with_http_client(config.http.build_client()?);
+    let ws_client = WebSocketClient::new(config.api_key.clone(), config.secret_key.clone(), config.ws_api_base_url.clone()).await.with_recv_window(config.recv_window_ms);
+    ws_client.session_logon().await?;
+    let mut report = ResetReport::default();
+
+    // 1. Cancel every open order
+    for order in rest_client.get_open_orders(None).await? {
+        match ws_client.cancel_order(&order.symbol, Some(order.order_id), None).await {
+            Ok(_) => report.cancelled_orders += 1,
+            Err(e) => report.problems.push(format!("Failed to cancel order {} on {}: {}", order.order_id, order.symbol, e)),
+        }
+    }
+    info!("Cancelled {} open order(s)", report.cancelled_orders);
+
+    // 2. Close every position
+    let account = rest_client.get_account_info().await?;
+    for (index, position) in account.positions.iter().enumerate() {
+        let Some(request) = close_position_request(position, &format!("reset{}", index)) else { continue };
+        match ws_client.place_order(&request).await {
+            Ok(_) => report.closed_positions += 1,
+            Err(e) => report.problems.push(format!("Failed to close {} {} position: {}", position.symbol, position.position_side, e)),
+        }
+    }
+    info!("Closed {} position(s)", report.closed_positions);
+
+    // 3. Clear the local state
+    if clear_state {
+        report.removed_files = clear_state_dir(&config.state_dir)?;
+    }
+
+    // 4. Verify the account is clean and report the balances
+    let remaining_orders = rest_client.get_open_orders(None).await?;
+    if !remaining_orders.is_empty() {
+        report.problems.push(format!("{} open order(s) remain", remaining_orders.len()));
+    }
+    let account = rest_client.get_account_info().await?;
+    let open_positions = account.positions.iter()
+        .filter(|p| p.position_amt.parse::<f64>().unwrap_or(0.0) != 0.0)
+        .count();
+    if open_positions > 0 {
+        report.problems.push(format!("{} position(s) remain open", open_positions));
+    }
+    report.balances = account.assets.iter()
+        .map(|a| (a.asset.clone(), a.wallet_balance.parse::<f64>().unwrap_or(0.0)))
+        .filter(|(_, balance)| *balance != 0.0)
+        .collect();
+    if account.available_balance.parse::<f64>().unwrap_or(0.0) <= 0.0 {
+        warn!("No available balance left on the testnet account");
+        report.problems.push("No available balance; top up the testnet account".to_string());
+    }
+    Ok(report)
+}
@@ -2,10 +2,19 @@
 
 //! This module provides a generic function to display any struct that implements
 //! the `Debug` trait within a simple `ratatui` Text User Interface (TUI).
+//! When no interactive terminal is available (systemd, Docker, CI, captured test output),
+//! the same content is printed as plain structured console output instead.
+//! `display_with_kill_switch` additionally binds `K` to an emergency action, typically
+//! `webhook::control::panic_close_all`, run after a `y` confirmation. `display_with_arming` shows
+//! the arming interlock with `A` (arm) and `D` (disarm) bound the same way.
+//! The item is formatted again on every frame, so state shared behind a `Mutex` (e.g. a
+//! `market_event::LiquidationFeed` fed by the `forceOrder` stream) is shown live.
 
 use std::{
-    io::{self, stdout},
-    fmt::Debug,
+    env,
+    io::{self, stdout, IsTerminal},
+    fmt::{self, Debug},
+    future::Future,
     time::Duration,
 };
 use crossterm::{
@@ -13,6 +22,9 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use tracing::warn;
+
+use crate::arming::Interlock;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
@@ -22,6 +34,36 @@ use ratatui::{
     Frame, Terminal,
 };
 
+/// Environment variable that forces the plain console renderer when set to a non-empty value other than "0".
+pub const NO_TUI_ENV: &str = "TRADING_BOT_NO_TUI";
+
+/// Returns `true` if an interactive TUI can be shown: both stdin and stdout are terminals,
+/// `TERM` is not "dumb", and the fallback has not been forced via `TRADING_BOT_NO_TUI`.
+pub fn is_tui_available() -> bool {
+    let forced_off = env::var(NO_TUI_ENV).map(|v| !v.is_empty() && v != "0").unwrap_or(false);
+    let dumb_term = env::var("TERM").map(|t| t == "dumb").unwrap_or(false);
+    !forced_off && !dumb_term && io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Renders any `Debug` struct as a plain-text report with a title header.
+/// This is the headless counterpart of `display_struct_in_tui`.
+///
+/// # Arguments
+/// * `item` - A reference to the struct to be rendered.
+/// * `title` - A title printed above the content.
+///
+/// # Returns
+/// The rendered report as a `String`.
+pub fn render_struct_plain<T: Debug>(item: &T, title: &str) -> String {
+    let rule = "=".repeat(title.chars().count().clamp(20, 100));
+    format!("{}\n{}\n{}\n{:#?}\n{}", rule, title, rule, item, rule)
+}
+
+/// Prints any `Debug` struct to stdout as a plain-text report (see `render_struct_plain`).
+pub fn print_struct_plain<T: Debug>(item: &T, title: &str) {
+    println!("{}", render_struct_plain(item, title));
+}
+
 /// Sets up the terminal for TUI mode.
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>, Box<dyn std::error::Error>> {
     enable_raw_mode()?;
@@ -40,8 +82,34 @@ fn restore_terminal(mut terminal: Terminal<CrosstermBackend<io::Stdout>>) -> Res
     Ok(())
 }
 
+/// Why the viewer loop ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ViewerExit {
+    Quit,
+    Action(char), // A bound key pressed and confirmed with `y`
+}
+
+/// A key bound to an action of the viewer, returned once confirmed with `y`.
+#[derive(Debug, Clone, Copy)]
+struct KeyBinding {
+    key: char,
+    label: &'static str, // Shown in the hint, e.g. "kill switch"
+    prompt: &'static str, // Shown while waiting for the confirmation
+}
+
+const KILL_SWITCH_KEYS: [KeyBinding; 1] = [KeyBinding {
+    key: 'K',
+    label: "kill switch",
+    prompt: "KILL SWITCH: press y to disarm and flatten everything, any other key to cancel",
+}];
+
+const ARMING_KEYS: [KeyBinding; 2] = [
+    KeyBinding { key: 'A', label: "arm", prompt: "ARM: press y to enable live trading, any other key to cancel" },
+    KeyBinding { key: 'D', label: "disarm", prompt: "DISARM: press y to stop executing signals, any other key to cancel" },
+];
+
 /// Draws the UI for displaying the struct's debug output.
-fn ui<T: Debug>(frame: &mut Frame, item: &T, title: &str, scroll: u16) {
+fn ui<T: Debug>(frame: &mut Frame, item: &T, title: &str, hint: &str, scroll: u16) {
     let size = frame.size();
 
     // Create a central block for the content
@@ -62,7 +130,7 @@ fn ui<T: Debug>(frame: &mut Frame, item: &T, title: &str, scroll: u16) {
                     Span::styled(" ", Style::default()),
                     Span::styled(title, Style::default().add_modifier(Modifier::BOLD)),
                     Span::styled(" ", Style::default()),
-                    Span::styled("(q: quit, ↑/↓: scroll)", Style::default().italic()),
+                    Span::styled(hint, Style::default().italic()),
                 ]))
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(ratatui::style::Color::Blue))
@@ -76,22 +144,76 @@ fn ui<T: Debug>(frame: &mut Frame, item: &T, title: &str, scroll: u16) {
 /// Displays any struct that implements `Debug` in a `ratatui` terminal UI.
 ///
 /// The UI will display the pretty-printed debug output of the struct.
-/// Press 'q' to quit the display.
+/// Press 'q' to quit the display. If no interactive terminal is available,
+/// the struct is printed with `print_struct_plain` instead and the function returns immediately.
 ///
 /// # Arguments
 /// * `item` - A reference to the struct to be displayed.
 /// * `title` - A title to display at the top of the TUI window.
 pub async fn display_struct_in_tui<T: Debug>(item: &T, title: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if !is_tui_available() {
+        print_struct_plain(item, title);
+        return Ok(());
+    }
+    let mut terminal = match setup_terminal() {
+        Ok(terminal) => terminal,
+        Err(e) => {
+            // Raw mode or the alternate screen can still fail (e.g., restricted terminals); fall back
+            let _ = disable_raw_mode();
+            warn!("TUI unavailable ({}), falling back to console output.", e);
+            print_struct_plain(item, title);
+            return Ok(());
+        }
+    };
+    let exit = run_viewer(&mut terminal, item, title, &[]);
+    restore_terminal(terminal)?;
+    exit.map(|_| ())
+}
+
+/// Like `display_struct_in_tui`, with `K` bound to `kill_switch`: after a `y` confirmation the TUI
+/// is closed, the action is run and its result printed. Without an interactive terminal the struct
+/// is only printed; the key binding needs the TUI.
+pub async fn display_with_kill_switch<T, F, Fut, R>(item: &T, title: &str, kill_switch: F) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Debug,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = R>,
+    R: Debug,
+{
+    if !is_tui_available() {
+        print_struct_plain(item, title);
+        return Ok(());
+    }
     let mut terminal = setup_terminal()?;
+    let exit = run_viewer(&mut terminal, item, title, &KILL_SWITCH_KEYS);
+    restore_terminal(terminal)?;
+    if exit? == ViewerExit::Action('K') {
+        warn!("Kill switch confirmed in the TUI");
+        print_struct_plain(&kill_switch().await, "Kill switch");
+    }
+    Ok(())
+}
+
+/// Runs the viewer until it is quit or the action of one of `bindings` is confirmed.
+fn run_viewer<T: Debug>(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    item: &T,
+    title: &str,
+    bindings: &[KeyBinding],
+) -> Result<ViewerExit, Box<dyn std::error::Error>> {
     let mut scroll: u16 = 0;
+    let mut confirming: Option<KeyBinding> = None;
     let debug_output = format!("{:#?}", item);
     let total_lines = debug_output.lines().count() as u16;
+    let keys: String = bindings.iter().map(|b| format!(", {}: {}", b.key, b.label)).collect();
+    let idle_hint = format!("(q: quit, ↑/↓: scroll{})", keys);
 
     loop {
+        let hint = confirming.map_or(idle_hint.as_str(), |binding| binding.prompt);
         let mut visible_height = 0;
         terminal.draw(|frame| {
             visible_height = frame.size().height.saturating_sub(2); // Subtract borders
-            ui(frame, item, title, scroll);
+            ui(frame, item, title, hint, scroll);
         })?;
 
         let max_scroll = total_lines.saturating_sub(visible_height);
@@ -101,8 +223,17 @@ pub async fn display_struct_in_tui<T: Debug>(item: &T, title: &str) -> Result<()
 
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
+                if let Some(binding) = confirming.take() {
+                    if key.code == KeyCode::Char('y') {
+                        return Ok(ViewerExit::Action(binding.key));
+                    }
+                    continue;
+                }
                 match key.code {
-                    KeyCode::Char('q') => break,
+                    KeyCode::Char('q') => return Ok(ViewerExit::Quit),
+                    KeyCode::Char(c) if bindings.iter().any(|b| b.key == c) => {
+                        confirming = bindings.iter().find(|b| b.key == c).copied();
+                    }
                     KeyCode::Up => {
                         if scroll > 0 { scroll -= 1; }
                     }
@@ -120,7 +251,38 @@ pub async fn display_struct_in_tui<T: Debug>(item: &T, title: &str) -> Result<()
             }
         }
     }
+}
+
+/// Shows the current arming state of an interlock.
+struct ArmingView<'a>(&'a Interlock);
+
+impl Debug for ArmingView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0.state(), f)
+    }
+}
 
+/// Shows the arming state of `interlock` with `A` bound to arming it on behalf of `operator` and
+/// `D` to disarming it, each after a `y` confirmation; the state is redrawn after every change.
+/// Without an interactive terminal the state is only printed.
+pub fn display_with_arming(interlock: &Interlock, operator: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let title = "Live trading interlock";
+    if !is_tui_available() {
+        print_struct_plain(&ArmingView(interlock), title);
+        return Ok(());
+    }
+    let mut terminal = setup_terminal()?;
+    let result = loop {
+        let changed = match run_viewer(&mut terminal, &ArmingView(interlock), title, &ARMING_KEYS) {
+            Ok(ViewerExit::Quit) => break Ok(()),
+            Ok(ViewerExit::Action('A')) => interlock.arm(operator),
+            Ok(ViewerExit::Action(_)) => interlock.disarm(&format!("disarmed by {} in the TUI", operator)),
+            Err(e) => break Err(e),
+        };
+        if let Err(e) = changed {
+            break Err(e.into());
+        }
+    };
     restore_terminal(terminal)?;
-    Ok(())
+    result
 }
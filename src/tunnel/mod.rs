@@ -0,0 +1,204 @@
+// src/tunnel/mod.rs
+
+//! This module exposes the local webhook listener to the internet for hosts without a public IP,
+//! so TradingView can reach it. The `Tunnel` trait hides the provider:
+//!
+//! - `ngrok` (`NgrokTunnel`, needs the `ngrok` feature and an ngrok account token), an in-process
+//!   tunnel with a random or reserved ngrok URL.
+//! - `cloudflare` (`CloudflareTunnel`) runs the `cloudflared` binary. Without a token it opens a
+//!   quick tunnel on a random `trycloudflare.com` URL, no account needed; with a tunnel token it
+//!   runs a named tunnel whose public hostname and route are configured in the Cloudflare dashboard.
+//!
+//! A tunnel stays open until it is dropped.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use tracing::debug;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+
+/// How long a tunnel may take to come up.
+pub const TUNNEL_OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default name of the `cloudflared` binary, looked up on the `PATH`.
+pub const DEFAULT_CLOUDFLARED_BIN: &str = "cloudflared";
+
+/// A way to make the local webhook listener reachable from the internet.
+pub trait Tunnel: Send {
+    /// Name of the provider, for logs.
+    fn name(&self) -> &'static str;
+
+    /// Opens the tunnel to `local_addr` (`host:port` of the webhook listener) and returns the
+    /// public base URL.
+    fn open<'a>(&'a mut self, local_addr: &'a str) -> BoxFuture<'a, Result<String, String>>;
+}
+
+/// Which tunnel exposes the webhook.
+#[derive(Clone, PartialEq)]
+pub enum TunnelConfig {
+    Ngrok { authtoken: String },
+    Cloudflare {
+        token: Option<String>, // Runs a named tunnel; a quick tunnel otherwise
+        hostname: Option<String>, // Public hostname of the named tunnel; required with a token
+        binary: String,
+    },
+}
+
+impl std::fmt::Debug for TunnelConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never prints the tokens
+        match self {
+            TunnelConfig::Ngrok { .. } => f.write_str("Ngrok"),
+            TunnelConfig::Cloudflare { token, hostname, binary } => f.debug_struct("Cloudflare")
+                .field("named", &token.is_some())
+                .field("hostname", hostname)
+                .field("binary", binary)
+                .finish(),
+        }
+    }
+}
+
+impl TunnelConfig {
+    /// Creates the tunnel.
+    pub fn build(&self) -> Result<Box<dyn Tunnel>, String> {
+        match self {
+            #[cfg(feature = "ngrok")]
+            TunnelConfig::Ngrok { authtoken } => Ok(Box::new(NgrokTunnel::new(authtoken.clone()))),
+            #[cfg(not(feature = "ngrok"))]
+            TunnelConfig::Ngrok { .. } => Err("This build has no ngrok support (feature `ngrok`)".to_string()),
+            TunnelConfig::Cloudflare { token, hostname, binary } => Ok(Box::new(CloudflareTunnel {
+                binary: binary.clone(),
+                token: token.clone(),
+                hostname: hostname.clone(),
+                process: None,
+            })),
+        }
+    }
+}
+
+/// Returns the address a tunnel forwards to: a wildcard bind address is not connectable, so
+/// loopback on the same port is used instead.
+pub fn forward_addr(listen_addr: &str) -> String {
+    listen_addr.replace("0.0.0.0", "127.0.0.1")
+}
+
+/// An ngrok tunnel running in-process.
+#[cfg(feature = "ngrok")]
+pub struct NgrokTunnel {
+    authtoken: String,
+    forwarder: Option<ngrok::forwarder::Forwarder<ngrok::tunnel::HttpTunnel>>, // Kept until drop so the tunnel stays open
+}
+
+#[cfg(feature = "ngrok")]
+impl NgrokTunnel {
+    /// Creates a tunnel authenticated with an ngrok account token.
+    pub fn new(authtoken: String) -> Self {
+        Self { authtoken, forwarder: None }
+    }
+}
+
+#[cfg(feature = "ngrok")]
+impl Tunnel for NgrokTunnel {
+    fn name(&self) -> &'static str {
+        "ngrok"
+    }
+
+    fn open<'a>(&'a mut self, local_addr: &'a str) -> BoxFuture<'a, Result<String, String>> {
+        use ngrok::{config::ForwarderBuilder, tunnel::EndpointInfo};
+        Box::pin(async move {
+            let session = ngrok::Session::builder()
+                .authtoken(self.authtoken.clone())
+                .connect()
+                .await
+                .map_err(|e| format!("Failed to connect to ngrok session: {}", e))?;
+            let url = url::Url::parse(&format!("http://{}/", forward_addr(local_addr)))
+                .map_err(|e| format!("Invalid webhook address {}: {}", local_addr, e))?;
+            let forwarder = session
+                .http_endpoint()
+                // .traffic_policy(r#"{"on_http_request": [{"actions": [{"type": "oauth","config": {"provider": "google"}}]}]}"#) // Uncomment for OAuth
+                .listen_and_forward(url)
+                .await
+                .map_err(|e| format!("Failed to create ngrok tunnel: {}", e))?;
+            let public_url = forwarder.url().to_string();
+            self.forwarder = Some(forwarder);
+            Ok(public_url)
+        })
+    }
+}
+
+/// A Cloudflare Tunnel run by the `cloudflared` binary.
+pub struct CloudflareTunnel {
+    binary: String,
+    token: Option<String>,
+    hostname: Option<String>,
+    process: Option<Child>, // Killed on drop
+}
+
+/// Extracts the public URL of a quick tunnel from a line of `cloudflared` output.
+pub fn quick_tunnel_url(line: &str) -> Option<String> {
+    line.split(|c: char| c.is_whitespace() || c == '|')
+        .find(|word| word.starts_with("https://") && word.trim_end_matches('/').ends_with(".trycloudflare.com"))
+        .map(|url| url.trim_end_matches('/').to_string())
+}
+
+/// Returns true when a line of `cloudflared` output reports a connection to Cloudflare's edge.
+pub fn is_connected_line(line: &str) -> bool {
+    line.contains("Registered tunnel connection")
+}
+
+impl Tunnel for CloudflareTunnel {
+    fn name(&self) -> &'static str {
+        "cloudflare"
+    }
+
+    fn open<'a>(&'a mut self, local_addr: &'a str) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            let mut command = Command::new(&self.binary);
+            command.args(["tunnel", "--no-autoupdate"]);
+            match &self.token {
+                // The token is passed through the environment so it does not show up in `ps`
+                Some(token) => {
+                    command.arg("run").env("TUNNEL_TOKEN", token);
+                },
+                None => {
+                    command.args(["--url", &format!("http://{}", forward_addr(local_addr))]);
+                },
+            }
+            let mut child = command.stdout(Stdio::null()).stderr(Stdio::piped()).kill_on_drop(true).spawn()
+                .map_err(|e| format!("Failed to start {}: {}", self.binary, e))?;
+            let stderr = child.stderr.take().ok_or("cloudflared has no output")?;
+            let mut lines = BufReader::new(stderr).lines();
+
+            // cloudflared logs to stderr: the quick tunnel URL, then the edge connections
+            let named = self.token.is_some();
+            let wait = async {
+                let mut quick_url = None;
+                while let Some(line) = lines.next_line().await.map_err(|e| format!("Failed to read cloudflared output: {}", e))? {
+                    debug!("cloudflared: {}", line);
+                    quick_url = quick_url.or_else(|| quick_tunnel_url(&line));
+                    if is_connected_line(&line) && (named || quick_url.is_some()) {
+                        return Ok(quick_url);
+                    }
+                }
+                Err("cloudflared exited before the tunnel was connected".to_string())
+            };
+            let quick_url = tokio::time::timeout(TUNNEL_OPEN_TIMEOUT, wait).await
+                .map_err(|_| format!("cloudflared did not connect within {:?}", TUNNEL_OPEN_TIMEOUT))??;
+
+            // Keep draining the output so cloudflared never blocks on a full pipe
+            tokio::spawn(async move {
+                while let Ok(Some(line)) = lines.next_line().await {
+                    debug!("cloudflared: {}", line);
+                }
+            });
+            self.process = Some(child);
+            match (quick_url, &self.hostname) {
+                (Some(url), _) => Ok(url),
+                (None, Some(hostname)) => Ok(format!("https://{}", hostname.trim_start_matches("https://").trim_end_matches('/'))),
+                (None, None) => Err("A named Cloudflare tunnel needs its public hostname".to_string()),
+            }
+        })
+    }
+}
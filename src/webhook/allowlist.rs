@@ -0,0 +1,157 @@
+// src/webhook/allowlist.rs
+
+//! This module restricts which source addresses can reach the webhook. TradingView sends alerts
+//! from a few published addresses (`TRADINGVIEW_WEBHOOK_IPS`); anything else, e.g. someone who
+//! found the tunnel URL, is rejected with 403 before the request is parsed.
+//!
+//! Behind a tunnel or reverse proxy the TCP peer is the proxy, not TradingView. The client address
+//! is then taken from `X-Forwarded-For`, but only for connections from a trusted proxy (loopback by
+//! default, which covers the in-process ngrok tunnel): entries are read from the right, skipping
+//! trusted proxies, so addresses a client prepends itself are never believed.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+/// Addresses TradingView sends webhook alerts from, as documented by TradingView.
+pub const TRADINGVIEW_WEBHOOK_IPS: [&str; 4] = ["52.89.214.238", "34.212.75.30", "54.218.53.128", "52.32.178.7"];
+
+/// Header proxies append the client address to.
+pub const FORWARDED_FOR_HEADER: &str = "X-Forwarded-For";
+
+/// An IPv4 or IPv6 network in CIDR notation, e.g. `10.0.0.0/8`; a bare address is a single host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    pub address: IpAddr,
+    pub prefix_len: u8,
+}
+
+impl IpNetwork {
+    /// Parses `address[/prefix]`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let (address, prefix_len) = value.split_once('/').map_or((value, None), |(a, p)| (a, Some(p)));
+        let address: IpAddr = address.parse().map_err(|_| format!("Invalid IP address '{}'", value))?;
+        let max_len = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(len) => len.parse::<u8>().ok().filter(|len| *len <= max_len)
+                .ok_or_else(|| format!("Invalid prefix length in '{}'", value))?,
+            None => max_len,
+        };
+        Ok(Self { address, prefix_len })
+    }
+
+    /// Returns true when `ip` lies in the network. IPv4-mapped IPv6 addresses match IPv4 networks.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            v4 => v4,
+        };
+        match (self.address, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            },
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            },
+            _ => false,
+        }
+    }
+}
+
+fn parse_networks(list: &str) -> Result<Vec<IpNetwork>, String> {
+    list.split(',').map(str::trim).filter(|entry| !entry.is_empty()).map(IpNetwork::parse).collect()
+}
+
+/// The source addresses allowed to reach the webhook (or the admin API).
+#[derive(Debug, Clone, PartialEq)]
+pub struct IpAllowlist {
+    pub allowed: Vec<IpNetwork>,
+    pub trusted_proxies: Vec<IpNetwork>, // Peers whose `X-Forwarded-For` is honored
+}
+
+impl IpAllowlist {
+    /// Allows TradingView's published addresses, with loopback as the only trusted proxy.
+    pub fn tradingview() -> Self {
+        let allowed = TRADINGVIEW_WEBHOOK_IPS.iter().map(|ip| IpNetwork::parse(ip).expect("valid address")).collect();
+        Self { allowed, trusted_proxies: Self::loopback() }
+    }
+
+    fn loopback() -> Vec<IpNetwork> {
+        vec![IpNetwork::parse("127.0.0.0/8").expect("valid network"), IpNetwork::parse("::1").expect("valid address")]
+    }
+
+    /// Parses a comma-separated list of addresses and CIDRs; `tradingview` stands for TradingView's
+    /// published addresses, e.g. `tradingview,203.0.113.0/24`. `trusted_proxies` is parsed the same
+    /// way (without the keyword) and defaults to loopback.
+    pub fn parse(allowed: &str, trusted_proxies: Option<&str>) -> Result<Self, String> {
+        let mut networks = Vec::new();
+        for entry in allowed.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            if entry.eq_ignore_ascii_case("tradingview") {
+                networks.extend(Self::tradingview().allowed);
+            } else {
+                networks.push(IpNetwork::parse(entry)?);
+            }
+        }
+        if networks.is_empty() {
+            return Err("The IP allowlist is empty.".to_string());
+        }
+        let trusted_proxies = match trusted_proxies {
+            Some(list) => parse_networks(list)?,
+            None => Self::loopback(),
+        };
+        Ok(Self { allowed: networks, trusted_proxies })
+    }
+
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|network| network.contains(ip))
+    }
+
+    /// Returns the address of the client: the peer itself, or for a trusted proxy the rightmost
+    /// `X-Forwarded-For` entry that is not a trusted proxy. `None` when that header holds an
+    /// invalid entry.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> Option<IpAddr> {
+        if !self.is_trusted_proxy(peer) {
+            return Some(peer);
+        }
+        let forwarded: Vec<&str> = headers.get_all(FORWARDED_FOR_HEADER).iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+        let mut client = peer;
+        for entry in forwarded.iter().rev() {
+            client = entry.trim().parse().ok()?;
+            if !self.is_trusted_proxy(client) {
+                break;
+            }
+        }
+        Some(client)
+    }
+
+    /// Returns true when requests from `ip` may reach the webhook.
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        self.allowed.iter().any(|network| network.contains(ip))
+    }
+}
+
+/// Middleware rejecting requests from addresses outside the allowlist with 403.
+pub async fn enforce_allowlist(State(allowlist): State<Arc<IpAllowlist>>, request: Request, next: Next) -> Response {
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip());
+    let client = peer.and_then(|peer| allowlist.client_ip(peer, request.headers()));
+    match client {
+        Some(ip) if allowlist.allows(ip) => next.run(request).await,
+        _ => {
+            warn!("Rejected webhook request from {:?} (peer {:?}): not in the IP allowlist", client, peer);
+            (StatusCode::FORBIDDEN, "Forbidden".to_string()).into_response()
+        },
+    }
+}
@@ -0,0 +1,258 @@
+// src/webhook/control.rs
+
+//! This module serves the control endpoints of the webhook server, so an operator can intervene
+//! from a phone without SSH access to the box:
+//!
+//! - `POST /control/pause` disarms the bot (see `arming`); signals are then recorded but not executed.
+//! - `POST /control/resume` arms it again.
+//! - `POST /control/cancel/{symbol}` cancels every open order of a symbol.
+//! - `POST /control/close_all` cancels every open order and closes every position with market orders.
+//! - `POST /control/kill` is the kill switch (`panic_close_all`): it disarms the bot, then flattens
+//!   everything like `close_all`, logging each cancel and close and sending a `KillSwitch`
+//!   notification. The admin API, the TUI (`tui::display_with_kill_switch`) and a webhook signal
+//!   (`"signal": "kill_switch"`) trigger the same operation.
+//!
+//! The daily loss breaker (`risk::DailyLossBreaker`) is enforced here too: `check_loss_breaker` runs
+//! before every entry and periodically (`watch_loss_breaker`); when it trips it disarms the bot
+//! (`arming::Interlock::trip`), so trading only resumes once an operator arms it again, and
+//! flattens through the same path, if configured to. So does the trading schedule (`risk::schedule`) when a session
+//! ends, see `watch_schedule`.
+//!
+//! The routes act on the account, so they are guarded like the admin API rather than the webhook:
+//! they are refused (503) unless the admin API is configured, every request must send the admin
+//! token (`ADMIN_TOKEN`, not the webhook secret TradingView alerts carry) as
+//! `Authorization: Bearer <token>`, and with `ADMIN_ALLOWED_IPS` come from an allowed address.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use tracing::{error, info, warn};
+use serde::Serialize;
+
+use crate::account_info::{AccountInfo, PositionRisk};
+use crate::arming::ArmingState;
+use crate::notify::Notification;
+use crate::order::{NewOrderRequest, OrderSide, OrderType, PositionSide};
+use crate::risk::schedule::TradingSchedule;
+use crate::risk::BreakerCheck;
+use super::response::{ErrorCode, WebhookError, WebhookResponse};
+use super::{authorize_admin, close_order_request, store_order, AppState};
+
+/// Operator who arms the bot through `POST /control/resume`.
+pub const CONTROL_OPERATOR: &str = "control endpoint";
+/// Name of the daily loss breaker in trip notifications and the arming state.
+pub const LOSS_BREAKER: &str = "daily_loss_limit";
+/// Signal that triggers the kill switch from a webhook alert.
+pub const KILL_SWITCH_SIGNAL: &str = "kill_switch";
+
+/// What a cancel or close-all request did.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ControlReport {
+    pub cancelled_orders: usize,
+    pub closed_positions: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>, // Orders or positions that could not be cancelled or closed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub confirmations: Vec<String>, // One line per order cancelled or position closed
+}
+
+/// Builds the market orders closing every position leg that is not flat. Hedge mode legs are
+/// closed via their `LONG`/`SHORT` side, one-way positions with `reduceOnly`.
+pub fn close_all_requests(positions: &[PositionRisk], client_order_id: impl Fn(usize) -> String) -> Vec<NewOrderRequest> {
+    positions.iter()
+        .filter(|p| p.amount() != 0.0)
+        .enumerate()
+        .map(|(index, p)| {
+            let side = if p.amount() > 0.0 { OrderSide::Sell } else { OrderSide::Buy };
+            let request = NewOrderRequest::new(&p.symbol, side, OrderType::Market)
+                .quantity(p.amount().abs())
+                .new_client_order_id(&client_order_id(index));
+            close_order_request(request, PositionSide::from_str_opt(&p.position_side))
+        })
+        .collect()
+}
+
+/// Arms or disarms the bot on behalf of `operator`, e.g. `CONTROL_OPERATOR`.
+pub(crate) fn set_arming(state: &AppState, arm: bool, operator: &str) -> Result<ArmingState, WebhookError> {
+    let Some(interlock) = state.interlock.as_deref() else {
+        return Err(WebhookError::new(ErrorCode::Unavailable, "No arming interlock is configured"));
+    };
+    let result = if arm { interlock.arm(operator) } else { interlock.disarm(&format!("paused through the {}", operator)) };
+    result.map_err(|e| {
+        error!("Failed to change the arming state: {}", e);
+        WebhookError::new(ErrorCode::Unavailable, e)
+    })
+}
+
+/// Disarms the bot.
+pub async fn pause(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<ArmingState>, WebhookResponse> {
+    authorize_admin(&state, &headers)?;
+    Ok(Json(set_arming(&state, false, CONTROL_OPERATOR)?))
+}
+
+/// Arms the bot.
+pub async fn resume(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<ArmingState>, WebhookResponse> {
+    authorize_admin(&state, &headers)?;
+    Ok(Json(set_arming(&state, true, CONTROL_OPERATOR)?))
+}
+
+async fn cancel_open_orders(state: &AppState, symbol: Option<&str>, report: &mut ControlReport) -> Result<(), WebhookError> {
+    let orders = state.rest_client.get_open_orders(symbol).await.map_err(|e| {
+        error!("Failed to get open orders: {}", e);
+        WebhookError::exchange(format!("Could not get open orders: {}", e))
+    })?;
+    for order in orders {
+        match state.ws_client.cancel_order(&order.symbol, Some(order.order_id), None).await {
+            Ok(_) => {
+                report.cancelled_orders += 1;
+                report.confirmations.push(format!("Cancelled {} order {} on {}", order.order_type, order.order_id, order.symbol));
+            },
+            Err(e) => report.problems.push(format!("Failed to cancel order {} on {}: {}", order.order_id, order.symbol, e)),
+        }
+    }
+    Ok(())
+}
+
+/// Cancels every open order of a symbol.
+pub async fn cancel(State(state): State<AppState>, Path(symbol): Path<String>, headers: HeaderMap) -> Result<Json<ControlReport>, WebhookResponse> {
+    authorize_admin(&state, &headers)?;
+    let symbol = symbol.to_uppercase();
+    let mut report = ControlReport::default();
+    cancel_open_orders(&state, Some(&symbol), &mut report).await?;
+    info!("Control: cancelled {} open order(s) on {}", report.cancelled_orders, symbol);
+    Ok(Json(report))
+}
+
+/// Cancels every open order and closes every position with market orders. Failing to list the
+/// open orders is reported as a problem; the positions are closed regardless.
+pub(crate) async fn flatten(state: &AppState) -> Result<ControlReport, WebhookError> {
+    let mut report = ControlReport::default();
+    if let Err(e) = cancel_open_orders(state, None, &mut report).await {
+        report.problems.push(e.message);
+    }
+
+    let positions = state.rest_client.get_position_risk(None).await.map_err(|e| {
+        error!("Failed to get positions: {}", e);
+        WebhookError::exchange(format!("Could not get positions: {}", e))
+    })?;
+    let short_timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default() % 1000000;
+    for request in close_all_requests(&positions, |index| format!("ctl{}{}", short_timestamp, index)) {
+        let result = state.ws_client.place_order(&request).await;
+        store_order(state, &request, result.as_ref());
+        match result {
+            Ok(response) => {
+                report.closed_positions += 1;
+                report.confirmations.push(format!(
+                    "Closed {} position with a {:?} market order of {} ({})",
+                    request.symbol, request.side, request.quantity.unwrap_or_default(), response.order_id,
+                ));
+            },
+            Err(e) => report.problems.push(format!("Failed to close {} position: {}", request.symbol, e)),
+        }
+    }
+    warn!("Flattened: cancelled {} open order(s) and closed {} position(s)", report.cancelled_orders, report.closed_positions);
+    Ok(report)
+}
+
+/// The kill switch: disarms the bot so no signal reopens a position, then cancels every open order
+/// and market-closes every position reduce-only, across all symbols. Each cancel and close is
+/// logged, and the outcome is notified. A failure to disarm (e.g. no interlock) does not stop the
+/// flatten; it is reported as a problem.
+pub async fn panic_close_all(state: &AppState, source: &str) -> Result<ControlReport, WebhookError> {
+    error!("KILL SWITCH triggered by the {}: disarming and flattening every position", source);
+    let disarmed = match state.interlock.as_deref() {
+        Some(interlock) => interlock.disarm(&format!("kill switch triggered by the {}", source)).err(),
+        None => Some("No arming interlock is configured".to_string()),
+    };
+    let result = flatten(state).await;
+    let (cancelled_orders, closed_positions, mut problems) = match &result {
+        Ok(report) => {
+            for confirmation in &report.confirmations {
+                warn!("Kill switch: {}", confirmation);
+            }
+            (report.cancelled_orders, report.closed_positions, report.problems.clone())
+        },
+        Err(e) => (0, 0, vec![e.message.clone()]),
+    };
+    problems.extend(disarmed.map(|e| format!("Failed to disarm: {}", e)));
+    for problem in &problems {
+        error!("Kill switch: {}", problem);
+    }
+    if let Some(notifications) = state.notifications.as_ref() {
+        notifications.notify(Notification::KillSwitch { source: source.to_string(), cancelled_orders, closed_positions, problems: problems.clone() });
+    }
+    result.map(|report| ControlReport { problems, ..report })
+}
+
+/// Checks the daily loss breaker, if one is configured, against the account's unrealized PnL.
+/// When it trips, it is logged, the bot is disarmed (`Interlock::trip`, which notifies the trip)
+/// and, if configured, every position is flattened. Returns the reason while new entries are blocked.
+pub async fn check_loss_breaker(state: &AppState, account: &AccountInfo) -> Result<(), String> {
+    let Some(breaker) = state.policies.loss_breaker else { return Ok(()) };
+    let unrealized_pnl = account.total_unrealized_profit.parse::<f64>().unwrap_or_default();
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default();
+    let reason = match state.policies.check_loss_breaker(unrealized_pnl, now_ms) {
+        BreakerCheck::Clear => return Ok(()),
+        BreakerCheck::Holding(reason) => return Err(reason),
+        BreakerCheck::Tripped(reason) => reason,
+    };
+    error!("Daily loss breaker tripped: {}", reason);
+    match state.interlock.as_deref() {
+        Some(interlock) => interlock.trip(LOSS_BREAKER, &reason),
+        None => if let Some(notifications) = state.notifications.as_ref() {
+            notifications.notify(Notification::BreakerTripped { breaker: LOSS_BREAKER.to_string(), reason: reason.clone() });
+        },
+    }
+    if breaker.flatten {
+        match flatten(state).await {
+            Ok(report) => report.problems.iter().for_each(|problem| error!("Daily loss breaker: {}", problem)),
+            Err(e) => error!("Daily loss breaker failed to flatten: {}", e.message),
+        }
+    }
+    Err(reason)
+}
+
+/// Checks the daily loss breaker with fresh account data, so it trips on open positions' losses
+/// between signals too.
+pub async fn watch_loss_breaker(state: &AppState) -> Result<(), String> {
+    let account = state.rest_client.get_account_info().await.map_err(|e| format!("Failed to get account info for the daily loss breaker: {}", e))?;
+    // A tripped breaker is already logged and notified; only the first check reports it
+    let _ = check_loss_breaker(state, &account).await;
+    Ok(())
+}
+
+/// Flattens every position when a session of `schedule` ends (or when first checked outside one),
+/// if it is configured to. `was_open` carries the previous check's outcome between calls.
+pub async fn watch_schedule(state: &AppState, schedule: &TradingSchedule, was_open: &mut Option<bool>) {
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default();
+    let open = schedule.is_open(now_ms);
+    let closed_now = !open && *was_open != Some(false);
+    *was_open = Some(open);
+    if !closed_now || !schedule.flatten_outside {
+        return;
+    }
+    info!("Trading session ended ({}), flattening", schedule.spec);
+    match flatten(state).await {
+        Ok(report) => report.problems.iter().for_each(|problem| error!("Session end: {}", problem)),
+        Err(e) => {
+            error!("Failed to flatten at the session end: {}", e.message);
+            *was_open = None; // Retried on the next check
+        },
+    }
+}
+
+/// Cancels every open order and closes every position.
+pub async fn close_all(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<ControlReport>, WebhookResponse> {
+    authorize_admin(&state, &headers)?;
+    Ok(Json(flatten(&state).await?))
+}
+
+/// Triggers the kill switch.
+pub async fn kill(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<ControlReport>, WebhookResponse> {
+    authorize_admin(&state, &headers)?;
+    Ok(Json(panic_close_all(&state, CONTROL_OPERATOR).await?))
+}
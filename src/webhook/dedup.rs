@@ -0,0 +1,66 @@
+// src/webhook/dedup.rs
+
+//! This module suppresses duplicate webhook alerts. TradingView retries alerts it could not
+//! deliver, and a retried "buy" must not double the position. An alert is identified by its
+//! `alertId` when the payload carries one, otherwise by a hash of the payload; an alert seen again
+//! within the deduplication window is acknowledged but not executed.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use super::WebhookPayload;
+
+/// Default window in which a repeated alert counts as a duplicate.
+pub const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(60);
+
+/// Remembers recently received alerts.
+#[derive(Debug)]
+pub struct AlertDeduplicator {
+    window_ms: i64,
+    seen: Mutex<HashMap<String, i64>>, // Alert key -> time it was first received
+}
+
+impl AlertDeduplicator {
+    /// Creates a deduplicator treating alerts repeated within `window` as duplicates.
+    pub fn new(window: Duration) -> Self {
+        Self { window_ms: window.as_millis() as i64, seen: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns the key identifying an alert: `id:{alertId}`, or `hash:{sha256}` of the payload
+    /// (without the secret) when it has no ID.
+    pub fn alert_key(payload: &WebhookPayload) -> String {
+        match payload.alert_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+            Some(id) => format!("id:{}", id),
+            None => {
+                let json = serde_json::to_vec(payload).unwrap_or_default();
+                format!("hash:{}", hex::encode(Sha256::digest(&json)))
+            },
+        }
+    }
+
+    /// Registers an alert received at `now_ms`. Returns an error naming the alert when the same
+    /// alert was already received within the window.
+    pub fn register(&self, payload: &WebhookPayload, now_ms: i64) -> Result<(), String> {
+        let key = Self::alert_key(payload);
+        let mut seen = self.seen.lock().map_err(|_| "Deduplication state is poisoned".to_string())?;
+        seen.retain(|_, received_ms| now_ms - *received_ms < self.window_ms);
+        match seen.get(&key) {
+            Some(received_ms) => Err(format!("Duplicate alert {} (first received {} ms ago)", key, now_ms - received_ms)),
+            None => {
+                seen.insert(key, now_ms);
+                Ok(())
+            },
+        }
+    }
+
+    /// Forgets a registered alert that was not executed after all (e.g. rate limited), so a retry
+    /// of it is not taken for a duplicate.
+    pub fn forget(&self, payload: &WebhookPayload) {
+        if let Ok(mut seen) = self.seen.lock() {
+            seen.remove(&Self::alert_key(payload));
+        }
+    }
+}
@@ -3,23 +3,96 @@
 //! This module provides an HTTP server to listen for TradingView webhook alerts.
 //! It parses incoming JSON payloads and dispatches trading signals.
 //! Upon receiving a buy/sell signal, it fetches the current market price and places a market order.
-//! The webhook payload is simplified to only include symbol and signal, and secret validation is removed for now.
+//! Only symbol and signal are required; optional fields choose the size (`quantity` in the base
+//! asset or `quoteQuantity`), a limit `price`, the `leverage` set before the entry, `stopLoss` and
+//! `takeProfit` levels placed as closing orders once the entry is accepted, and `reduceOnly`.
+//! `close_long`/`close_short` look up the actual position (`/fapi/v3/positionRisk`) and close it
+//! with a reduce-only market order (at most `quantity`, when sent); they do nothing when flat.
+//!
+//! When a shared secret is configured (`TRADINGVIEW_WEBHOOK_SECRET`), requests must prove they know
+//! it, otherwise they are rejected with 401 before anything is logged or executed: either with an
+//! `X-Signature` header holding the hex HMAC-SHA256 of the raw body keyed with the secret
+//! (optionally prefixed with `sha256=`), or with a `secret` field in the payload, since TradingView
+//! alerts cannot set headers. Without a secret every request is accepted, so the bot only starts
+//! the webhook without one when `WEBHOOK_ALLOW_UNAUTHENTICATED=true` (see `config`).
+//!
+//! An IP allowlist (`WEBHOOK_ALLOWED_IPS`, see `allowlist`) can additionally restrict `/webhook` to
+//! TradingView's published addresses and user-specified networks.
+//!
+//! Signals are acknowledged with 202 and a tracking ID as soon as they are validated, and executed
+//! by a background worker (see `queue`); `GET /signals/{id}` reports the outcome.
+//!
+//! Responses carry a JSON `WebhookResponse` (see `response`) with a status code telling bad
+//! payloads (400), failed authentication (401), policy rejections (422) and exchange errors (502)
+//! apart.
+//!
+//! `GET /health` and `GET /status` report liveness and the state of the bot, see `status`.
+//! Operators can pause trading, cancel orders and close all positions through the `/control/...`
+//! routes, authenticated with the admin token and restricted by the admin allowlist, see `control`.
+//! A `"signal": "kill_switch"` alert triggers the kill switch (disarm, cancel every order, close
+//! every position) right away, bypassing the queue; it is refused (503) unless a webhook secret
+//! authenticates it.
+//!
+//! With `"dryRun": true` a signal goes through every check (price, sizing, symbol settings, risk
+//! limits) and the response lists the orders it would have placed, without placing them.
+//!
+//! A `strategy` field routes the signal to the sizing, symbol settings and account of that
+//! strategy, see `strategies`.
+//!
+//! Alerts repeated within the deduplication window (same `alertId`, or same payload) are
+//! acknowledged without being executed again, see `dedup`.
+//!
+//! Requests are rate limited globally and per source, and signals per symbol, so a misfiring alert
+//! loop cannot spam orders; see `ratelimit`.
 
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use axum::{
-    routing::post,
-    extract::{State, Json},
-    Router,
+    body::Bytes,
+    http::{header::AUTHORIZATION, HeaderMap},
+    middleware,
+    routing::{get, post},
+    extract::{Path, State},
+    Json, Router,
 };
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
-use log::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
 
-use crate::order::{OrderSide, OrderType, TimeInForce};
+use crate::order::{quote_to_base_quantity, NewOrderRequest, NewOrderResponse, OrderSide, OrderType, PositionSide, TimeInForce};
 use crate::websocket::WebSocketClient; // To send orders to Binance via WS API
 use crate::rest_api::RestClient; // To fetch current market price via REST API
+use crate::experiment::{self, Experiment};
+use crate::risk::{self, ExecutionPolicies, RiskContext, SizingContext};
+use crate::account_info::{AccountInfo, PositionRisk};
+use crate::market_data::SymbolFilters;
+use crate::events::{BotEvent, EventLog};
+use crate::arming::Interlock;
+use crate::notify::Notifications;
+use crate::storage::{OrderRecord, SignalRecord, Storage};
+use allowlist::{enforce_allowlist, IpAllowlist};
+use dedup::AlertDeduplicator;
+use queue::{QueuedSignal, SignalStatus, SignalTracker};
+use ratelimit::{enforce_rate_limit, RateLimitSettings, RateLimiter};
+use response::{ErrorCode, WebhookError, WebhookResponse};
+use strategies::StrategyRoute;
+use symbols::{SymbolConfig, SymbolConfigs};
+
+pub mod allowlist;
+pub mod control;
+pub mod dedup;
+pub mod queue;
+pub mod ratelimit;
+pub mod response;
+pub mod status;
+pub mod strategies;
+pub mod symbols;
+pub mod tls;
 
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -27,6 +100,168 @@ use crate::rest_api::RestClient; // To fetch current market price via REST API
 pub struct WebhookPayload {
     pub symbol: String,
     pub signal: String, // e.g., "buy", "sell", "close_long", "close_short"
+    #[serde(default)]
+    pub position_side: Option<String>, // Optional "LONG"/"SHORT"/"BOTH" for accounts in hedge mode
+    #[serde(default)]
+    pub quote_quantity: Option<f64>, // Optional order size in the quote asset (e.g., 500.0 = $500 of BTCUSDT)
+    #[serde(default)]
+    pub variant: Option<String>, // Optional A/B test variant tag; scales `quoteQuantity` by the variant's budget share
+    #[serde(default)]
+    pub stop_price: Option<f64>, // Optional protective stop, used by risk-based sizing policies
+    #[serde(default)]
+    pub atr: Option<f64>, // Optional current ATR, used by ATR sizing policies
+    #[serde(default)]
+    pub quantity: Option<f64>, // Optional order size in the base asset; exclusive with `quoteQuantity`
+    #[serde(default)]
+    pub order_type: Option<String>, // Optional "market" or "limit"; a `price` alone also makes a limit order
+    #[serde(default)]
+    pub price: Option<f64>, // Optional limit price (GTC)
+    #[serde(default)]
+    pub leverage: Option<u32>, // Optional initial leverage set on the symbol before an entry
+    #[serde(default)]
+    pub stop_loss: Option<f64>, // Optional stop-loss level, placed as a closing stop-market order after a buy/sell
+    #[serde(default)]
+    pub take_profit: Option<f64>, // Optional take-profit level, placed as a closing take-profit-market order
+    #[serde(default)]
+    pub reduce_only: bool, // Optional; makes a buy/sell only reduce the position
+    #[serde(default)]
+    pub strategy: Option<String>, // Optional strategy the signal is routed to, see `strategies`
+    #[serde(default)]
+    pub dry_run: bool, // Optional; validates and sizes the signal and returns its orders without placing them
+    #[serde(default, alias = "alert_id")]
+    pub alert_id: Option<String>, // Optional unique ID of the alert; repeats of it are not executed again
+    #[serde(default, skip_serializing)]
+    pub secret: Option<String>, // Shared secret, for senders that cannot sign requests (TradingView)
+}
+
+impl WebhookPayload {
+    /// Returns the order type of the signal's order: a limit order when `orderType` is "limit" or
+    /// only a `price` is given, a market order otherwise.
+    pub fn order_type(&self) -> Result<OrderType, String> {
+        match (self.order_type.as_deref().map(str::to_lowercase).as_deref(), self.price) {
+            (Some("limit"), Some(price)) | (None, Some(price)) if price > 0.0 => Ok(OrderType::Limit),
+            (Some("limit"), _) | (None, Some(_)) => Err("Limit orders need a positive price".to_string()),
+            (Some("market"), None) | (None, None) => Ok(OrderType::Market),
+            (Some("market"), Some(_)) => Err("Market orders cannot have a price".to_string()),
+            (Some(other), _) => Err(format!("Unsupported orderType: {}", other)),
+        }
+    }
+}
+
+/// Checks an order quantity against the symbol's lot size filter: at least `min_qty` and a whole
+/// number of steps (`step_size` for limit orders, `market_step_size` for market orders).
+pub fn check_quantity(filters: &SymbolFilters, order_type: OrderType, quantity: f64) -> Result<(), String> {
+    let step_size = if order_type == OrderType::Limit { filters.step_size } else { filters.market_step_size };
+    if quantity < filters.min_qty {
+        return Err(format!("Quantity {} is below the minimum {}", quantity, filters.min_qty));
+    }
+    if (crate::order::round_down_to_step(quantity, step_size) - quantity).abs() > step_size * 1e-6 {
+        return Err(format!("Quantity {} is not a multiple of the step size {}", quantity, step_size));
+    }
+    Ok(())
+}
+
+/// Checks that the stop loss and take profit of an entry at `entry_price` sit on the losing and
+/// winning side of it.
+pub fn check_exit_levels(side: OrderSide, entry_price: f64, stop_loss: Option<f64>, take_profit: Option<f64>) -> Result<(), String> {
+    // Positive when a level is in profit for the entry's side
+    let direction = match side {
+        OrderSide::Buy => 1.0,
+        OrderSide::Sell => -1.0,
+    };
+    if let Some(stop_loss) = stop_loss.filter(|sl| (sl - entry_price) * direction >= 0.0) {
+        return Err(format!("Stop loss {} is on the wrong side of the {:?} entry at {}", stop_loss, side, entry_price));
+    }
+    if let Some(take_profit) = take_profit.filter(|tp| (tp - entry_price) * direction <= 0.0) {
+        return Err(format!("Take profit {} is on the wrong side of the {:?} entry at {}", take_profit, side, entry_price));
+    }
+    Ok(())
+}
+
+/// Builds the stop-loss and take-profit orders protecting an entry on `entry_side`. They close the
+/// whole position when triggered (`closePosition`), so they need no quantity, and do nothing if the
+/// position is already gone, e.g. when a limit entry has not filled yet.
+pub fn protective_orders(
+    symbol: &str,
+    entry_side: OrderSide,
+    stop_loss: Option<f64>,
+    take_profit: Option<f64>,
+    position_side: Option<PositionSide>,
+    client_order_id: impl Fn(&str) -> String,
+) -> Vec<NewOrderRequest> {
+    let exit_side = match entry_side {
+        OrderSide::Buy => OrderSide::Sell,
+        OrderSide::Sell => OrderSide::Buy,
+    };
+    let order = |order_type: OrderType, level: f64, suffix: &str| {
+        let request = NewOrderRequest::new(symbol, exit_side, order_type)
+            .stop_price(level)
+            .close_position(true)
+            .new_client_order_id(&client_order_id(suffix));
+        match &position_side {
+            Some(ps) => request.position_side(ps.clone()),
+            None => request,
+        }
+    };
+    stop_loss.map(|level| order(OrderType::StopMarket, level, "sl")).into_iter()
+        .chain(take_profit.map(|level| order(OrderType::TakeProfitMarket, level, "tp")))
+        .collect()
+}
+
+/// Header carrying the hex HMAC-SHA256 of the raw request body, keyed with the shared secret.
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Compares two byte strings in time independent of where they differ.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Authenticates a webhook request against the shared secret, using the `X-Signature` header when
+/// present (a wrong signature is rejected even if the payload carries the right secret), otherwise
+/// the payload's `secret` field.
+///
+/// # Arguments
+/// * `secret` - The configured shared secret.
+/// * `body` - The raw request body, as signed by the sender.
+/// * `signature` - The value of the `X-Signature` header, if any.
+/// * `payload_secret` - The payload's `secret` field, if any.
+pub fn authenticate(secret: &str, body: &[u8], signature: Option<&str>, payload_secret: Option<&str>) -> Result<(), String> {
+    match (signature, payload_secret) {
+        (Some(signature), _) => {
+            let signature = signature.trim();
+            let signature = hex::decode(signature.strip_prefix("sha256=").unwrap_or(signature))
+                .map_err(|_| "Malformed signature".to_string())?;
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                .expect("HMAC can take key of any size");
+            mac.update(body);
+            mac.verify_slice(&signature).map_err(|_| "Invalid signature".to_string())
+        },
+        (None, Some(payload_secret)) if constant_time_eq(payload_secret.as_bytes(), secret.as_bytes()) => Ok(()),
+        (None, Some(_)) => Err("Invalid secret".to_string()),
+        (None, None) => Err("Missing signature or secret".to_string()),
+    }
+}
+
+/// Authorizes an operator request carrying the shared secret as `Authorization: Bearer <secret>`.
+pub fn authorize_bearer(secret: &str, authorization: Option<&str>) -> Result<(), String> {
+    match authorization.map(|value| value.trim().strip_prefix("Bearer ")) {
+        Some(Some(token)) if constant_time_eq(token.trim().as_bytes(), secret.as_bytes()) => Ok(()),
+        Some(Some(_)) => Err("Invalid token".to_string()),
+        _ => Err("Missing bearer token".to_string()),
+    }
+}
+
+/// Authorizes a request to the operator routes (`/control`, `/status`) with the admin token; they are
+/// refused when none is configured.
+pub(crate) fn authorize_admin(state: &AppState, headers: &HeaderMap) -> Result<(), WebhookError> {
+    let Some(token) = state.admin_token.as_deref() else {
+        return Err(WebhookError::new(ErrorCode::Unavailable, "Operator endpoints need the admin token (ADMIN_TOKEN)"));
+    };
+    let authorization = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok());
+    authorize_bearer(token, authorization).map_err(|reason| {
+        warn!("Rejected unauthenticated operator request: {}", reason);
+        WebhookError::new(ErrorCode::Unauthorized, reason)
+    })
 }
 
 /// The shared state for the Axum application.
@@ -34,42 +269,332 @@ pub struct WebhookPayload {
 #[derive(Clone)]
 pub struct AppState {
     pub ws_client: Arc<WebSocketClient>,
-    pub rest_client: Arc<RestClient> // Added RestClient to AppState
-    // pub webhook_secret: String, // Removed webhook_secret for now
+    pub rest_client: Arc<RestClient>, // Added RestClient to AppState
+    pub experiment: Option<Arc<Experiment>>, // Running A/B test, if any
+    pub policies: Arc<ExecutionPolicies>, // Sizing and pre-trade risk policies
+    pub event_log: Option<Arc<EventLog>>, // Records signals and decisions for replay, see `events`
+    pub storage: Option<Arc<dyn Storage>>, // Stores signals and orders for offline analysis, see `storage`
+    pub interlock: Option<Arc<Interlock>>, // Signals are only executed while armed; `None` disables the interlock
+    pub webhook_secret: Option<String>, // Shared secret requests must be authenticated with; `None` accepts any request
+    pub ip_allowlist: Option<Arc<IpAllowlist>>, // Source addresses allowed to reach `/webhook`; `None` allows any
+    pub admin_token: Option<String>, // Token the `/control` and `/status` routes must be authenticated with; `None` refuses them
+    pub admin_allowlist: Option<Arc<IpAllowlist>>, // Source addresses allowed to reach `/control` and `/status`; `None` allows any
+    pub symbol_configs: Arc<SymbolConfigs>, // Per-symbol sizing and restrictions, see `symbols`
+    pub strategies: Arc<HashMap<String, StrategyRoute>>, // Settings of each strategy by lowercase name, see `strategies`
+    pub deduplicator: Option<Arc<AlertDeduplicator>>, // Suppresses retried alerts, see `dedup`; `None` executes every alert
+    pub rate_limiter: Option<Arc<RateLimiter>>, // Request and per-symbol signal limits, see `ratelimit`; `None` is unlimited
+    pub notifications: Option<Notifications>, // Told about orders the exchange rejects, see `notify`
+    pub signals: Arc<SignalTracker>, // Status of recent signals by tracking ID
+    pub signal_queue: Option<mpsc::Sender<QueuedSignal>>, // Set by `with_signal_queue`; `None` executes signals before responding
+    pub started: Instant, // Start of the service, for the uptime reported on `/status`
 }
 
+/// An order placed for a signal.
+#[derive(Debug, Clone, PartialEq)]
+struct PlacedOrder {
+    order_id: u64, // Exchange order ID
+    client_order_id: String,
+    side: &'static str, // BUY or SELL
+    quantity: f64,
+    price: f64, // Limit price, or the market price the order was sized at
+    warnings: Vec<String>, // Problems after the order was placed, e.g. a protective order that failed
+}
 
-async fn handle_webhook(
-    State(state): State<AppState>,
-    Json(payload): Json<WebhookPayload>,
-) -> String {
-    println!("Received webhook payload: {:?}", payload);
+/// Turns an order into one that closes (part of) a position without being able to open the opposite one.
+/// In one-way mode the order is sent as `reduceOnly`; in hedge mode the `LONG`/`SHORT` position side
+/// already makes it a closing order and Binance rejects `reduceOnly`.
+fn close_order_request(request: NewOrderRequest, position_side: Option<PositionSide>) -> NewOrderRequest {
+    match position_side {
+        Some(ps @ (PositionSide::Long | PositionSide::Short)) => request.position_side(ps),
+        _ => request.reduce_only(true),
+    }
+}
+
+/// Finds the position a close signal refers to and returns its size and position side: for
+/// `close_long` a long position (positive `BOTH` leg in one-way mode, `LONG` leg in hedge mode),
+/// otherwise a short one. `position_side` restricts the search to one leg. `None` when flat.
+pub fn closing_position(positions: &[PositionRisk], symbol: &str, close_long: bool, position_side: Option<PositionSide>) -> Option<(f64, PositionSide)> {
+    positions.iter()
+        .filter(|p| p.symbol.eq_ignore_ascii_case(symbol))
+        .filter_map(|p| PositionSide::from_str_opt(&p.position_side).map(|ps| (p.amount(), ps)))
+        .filter(|(_, ps)| position_side.as_ref().is_none_or(|wanted| wanted == ps))
+        .find(|(amount, ps)| match ps {
+            PositionSide::Both if close_long => *amount > 0.0,
+            PositionSide::Both => *amount < 0.0,
+            PositionSide::Long => close_long && *amount != 0.0,
+            PositionSide::Short => !close_long && *amount != 0.0,
+            PositionSide::Other(_) => false,
+        })
+        .map(|(amount, ps)| (amount.abs(), ps))
+}
+
+/// Sizes an order with a sizing policy, scaled by the A/B test budget share and rounded to the
+/// symbol's market step size.
+fn size_with_policy(filters: &SymbolFilters, policy: &dyn risk::SizingPolicy, ctx: &SizingContext, budget_share: f64) -> Result<f64, WebhookError> {
+    let quantity = policy.quantity(ctx).map_err(WebhookError::rejected)?;
+    let quantity = crate::order::round_down_to_step(quantity * budget_share, filters.market_step_size);
+    if quantity < filters.min_qty {
+        return Err(WebhookError::rejected(format!("Quantity {} is below the minimum {}", quantity, filters.min_qty)));
+    }
+    Ok(quantity)
+}
+
+/// Builds the risk check inputs from the account's open positions and the tracked realized PnL.
+fn risk_context(state: &AppState, account: &AccountInfo, symbol: &str, order_notional: f64) -> RiskContext {
+    let notional = |p: &crate::account_info::PositionInfo| p.notional.parse::<f64>().unwrap_or_default().abs();
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default();
+    let risk_state = state.policies.state.lock().map(|s| s.clone()).unwrap_or_default();
+    let quote = risk::quote_asset(symbol);
+    let open: HashSet<&str> = account.positions.iter()
+        .filter(|p| p.position_amt.parse::<f64>().unwrap_or_default() != 0.0)
+        .map(|p| p.symbol.as_str())
+        .collect();
+    RiskContext {
+        symbol: symbol.to_uppercase(),
+        now_ms,
+        order_notional,
+        total_exposure: account.positions.iter().map(notional).sum(),
+        symbol_exposure: account.positions.iter().filter(|p| p.symbol.eq_ignore_ascii_case(symbol)).map(notional).sum(),
+        quote_exposure: account.positions.iter().filter(|p| risk::quote_asset(&p.symbol) == quote).map(notional).sum(),
+        open_positions: open.len(),
+        equity: account.total_margin_balance.parse::<f64>().unwrap_or_default(),
+        realized_pnl_today: risk_state.realized_pnl_on(now_ms),
+        last_loss_ms: risk_state.last_loss_ms,
+    }
+}
+
+/// Determines the quantity of an order that opens or adds to a position. An explicit `quantity` is
+/// used as given (checked against the lot size filter before dispatch). A `quoteQuantity` is converted at the entry price and rounded to the symbol's
+/// market step size; otherwise the sizing policy decides, by default risking 1% of equity over a
+/// stop 2 ATRs away (`risk::DEFAULT_ATR_RISK`, needs the payload's `atr` or a `stopLoss`).
+async fn open_quantity(
+    state: &AppState,
+    payload: &WebhookPayload,
+    filters: &SymbolFilters,
+    entry_price: f64,
+    budget_share: Option<f64>,
+    account: Option<&AccountInfo>,
+) -> Result<f64, WebhookError> {
+    let quantity = match (payload.quantity, payload.quote_quantity.map(|q| q * budget_share.unwrap_or(1.0))) {
+        (Some(quantity), _) if quantity > 0.0 => quantity,
+        (Some(quantity), _) => return Err(WebhookError::invalid(format!("Invalid quantity {}", quantity))),
+        (None, Some(quote_amount)) => {
+            match quote_to_base_quantity(quote_amount, entry_price, filters.market_step_size) {
+                Ok(quantity) if quantity >= filters.min_qty => quantity,
+                Ok(quantity) => {
+                    error!("Converted quantity {} for {} is below the minimum {}", quantity, payload.symbol, filters.min_qty);
+                    return Err(WebhookError::rejected(format!("Quantity {} is below the minimum {}", quantity, filters.min_qty)));
+                },
+                Err(e) => {
+                    error!("Failed to convert quote quantity for {}: {}", payload.symbol, e);
+                    return Err(WebhookError::invalid(e));
+                }
+            }
+        },
+        (None, None) => match account {
+            Some(account) => {
+                let policy = state.policies.sizing.as_deref().unwrap_or(&risk::DEFAULT_ATR_RISK);
+                let ctx = SizingContext {
+                    symbol: payload.symbol.clone(),
+                    price: entry_price,
+                    equity: account.total_margin_balance.parse::<f64>().unwrap_or_default(),
+                    stop_price: payload.stop_loss.or(payload.stop_price),
+                    atr: payload.atr,
+                };
+                match size_with_policy(filters, policy, &ctx, budget_share.unwrap_or(1.0)) {
+                    Ok(quantity) => quantity,
+                    Err(e) => {
+                        error!("Sizing policy '{}' failed for {}: {}", policy.name(), payload.symbol, e.message);
+                        return Err(e);
+                    }
+                }
+            },
+            None => return Err(WebhookError::exchange("Could not get account info for sizing")),
+        },
+    };
+    Ok(quantity)
+}
+
+/// Outcome of a signal that was not refused.
+enum Execution {
+    Placed(PlacedOrder),
+    Skipped(String), // Nothing to do, e.g. a close signal while flat
+    DryRun(Vec<NewOrderRequest>), // The orders a `dryRun` signal would have placed, entry first
+}
+
+/// Decides and places the order for a signal. Returns the placed order, or the reason nothing was placed.
+/// For `dryRun` signals every check runs, but nothing is sent: neither the leverage nor the orders.
+async fn execute_signal(state: &AppState, payload: &WebhookPayload, symbol_config: Option<&SymbolConfig>) -> Result<Execution, WebhookError> {
+    // Resolve the optional position side (only meaningful for accounts in hedge mode)
+    let position_side = match payload.position_side.as_deref() {
+        Some(ps) => match PositionSide::from_str_opt(ps) {
+            Some(side) => Some(side),
+            None => {
+                warn!("Received invalid positionSide: {}", ps);
+                return Err(WebhookError::invalid(format!("Invalid positionSide: {}", ps)));
+            }
+        },
+        None => None,
+    };
+
+    // Resolve the signal to the side of its order; closes only reduce the position
+    let signal = payload.signal.to_lowercase();
+    let (side, closes_position) = match signal.as_str() {
+        "buy" => (OrderSide::Buy, false),
+        "sell" => (OrderSide::Sell, false),
+        "close_long" => (OrderSide::Sell, true), // Sell to close a long position
+        "close_short" => (OrderSide::Buy, true), // Buy to close a short position
+        _ => {
+            warn!("Received unknown signal: {}", payload.signal);
+            return Err(WebhookError::invalid(format!("Unknown signal: {}", payload.signal)));
+        }
+    };
+    let opens_position = !closes_position && !payload.reduce_only;
+    let order_type = payload.order_type().map_err(|e| {
+        warn!("Received invalid order for {}: {}", payload.symbol, e);
+        WebhookError::invalid(e)
+    })?;
+    if payload.quantity.is_some() && payload.quote_quantity.is_some() {
+        return Err(WebhookError::invalid("Only one of quantity and quoteQuantity can be given"));
+    }
+    if !opens_position && (payload.stop_loss.is_some() || payload.take_profit.is_some()) {
+        return Err(WebhookError::invalid(format!("stopLoss/takeProfit need an opening buy/sell signal, not {}", payload.signal)));
+    }
+
+    // Resolve the optional A/B test variant; its budget share applies to the quote quantity
+    let budget_share = match (payload.variant.as_deref(), state.experiment.as_deref()) {
+        (Some(tag), Some(experiment)) => match experiment.variant(tag) {
+            Some(variant) => Some(variant.budget_share),
+            None => {
+                warn!("Received unknown variant '{}' for experiment '{}'", tag, experiment.name);
+                return Err(WebhookError::invalid(format!("Unknown variant: {}", tag)));
+            }
+        },
+        (Some(tag), None) => {
+            warn!("Received variant '{}' but no experiment is running", tag);
+            return Err(WebhookError::rejected(format!("No experiment running for variant: {}", tag)));
+        },
+        (None, _) => None,
+    };
 
-    let current_price_res = state.rest_client.get_current_price(&payload.symbol).await;
+    let current_price_res = state.rest_client.get_current_price(&payload.symbol).instrument(info_span!("price_fetch")).await;
     let current_price = match current_price_res {
         Ok(ticker_price) => ticker_price.price.parse::<f64>().unwrap_or_default(),
         Err(e) => {
             error!("Failed to get current price for {}: {}", payload.symbol, e);
-            return format!("Error: Could not get current price for {}", payload.symbol);
+            return Err(WebhookError::exchange(format!("Could not get current price for {}", payload.symbol)));
         }
     };
     if current_price <= 0.0 {
         error!("Fetched invalid current price for {}: {}", payload.symbol, current_price);
-        return format!("Error: Invalid current price for {}", payload.symbol);
+        return Err(WebhookError::exchange(format!("Invalid current price for {}", payload.symbol)));
     }
-    println!("Current market price for {}: {}", payload.symbol, current_price);
+    debug!("Current market price for {}: {}", payload.symbol, current_price);
 
-    // Determine quantity to trade. Using a fixed default quantity for now.
-    // IMPORTANT: Adjust this default quantity based on your strategy and minimum notional values.
-    let quantity_to_trade = 0.04; // Reduced quantity to fit within available balance (~4,740 USDT)
+    // Limit orders are sized, risk-checked and protected at their limit price
+    let entry_price = payload.price.unwrap_or(current_price);
+    if opens_position {
+        check_exit_levels(side, entry_price, payload.stop_loss, payload.take_profit).map_err(|e| {
+            warn!("Received invalid exit levels for {}: {}", payload.symbol, e);
+            WebhookError::invalid(e)
+        })?;
+    }
+
+    // Account data is only needed when a sizing, risk or position limit applies
+    let sized_by_policy = !closes_position && payload.quantity.is_none() && payload.quote_quantity.is_none();
+    let max_position = symbol_config.filter(|config| config.max_position.is_some());
+    let limits_apply = !state.policies.is_empty() || max_position.is_some();
+    let account = if (opens_position && limits_apply) || sized_by_policy {
+        match state.rest_client.get_account_info().await {
+            Ok(account) => Some(account),
+            Err(e) => {
+                error!("Failed to get account info for sizing/risk checks: {}", e);
+                return Err(WebhookError::exchange("Could not get account info for sizing/risk checks"));
+            }
+        }
+    } else {
+        None
+    };
+
+    // The lot size and notional filters; a close of the whole position needs neither
+    let filters = if closes_position && payload.quantity.is_none() {
+        None
+    } else {
+        match state.rest_client.get_symbol_filters(&payload.symbol).await {
+            Ok(filters) => Some(filters),
+            Err(e) => {
+                error!("Failed to get symbol filters for {}: {}", payload.symbol, e);
+                return Err(WebhookError::exchange(format!("Could not get trading filters for {}", payload.symbol)));
+            }
+        }
+    };
+
+    // Closes trade the actual position: all of it, or at most an explicit `quantity`
+    let (quantity_to_trade, position_side) = if closes_position {
+        let positions = state.rest_client.get_position_risk(Some(&payload.symbol)).await.map_err(|e| {
+            error!("Failed to get positions for {}: {}", payload.symbol, e);
+            WebhookError::exchange(format!("Could not get positions for {}", payload.symbol))
+        })?;
+        let close_long = side == OrderSide::Sell;
+        match closing_position(&positions, &payload.symbol, close_long, position_side) {
+            Some((size, leg)) => (payload.quantity.map_or(size, |q| q.min(size)), Some(leg)),
+            None => {
+                let reason = format!("No open {} position in {} to close", if close_long { "long" } else { "short" }, payload.symbol.to_uppercase());
+                info!("Ignoring {} signal: {}", payload.signal, reason);
+                return Ok(Execution::Skipped(reason));
+            }
+        }
+    } else {
+        let filters = filters.as_ref().expect("filters are fetched for opening orders");
+        (open_quantity(state, payload, filters, entry_price, budget_share, account.as_ref()).await?, position_side)
+    };
+    if let Some(Err(e)) = filters.as_ref().filter(|_| payload.quantity.is_some()).map(|filters| check_quantity(filters, order_type, quantity_to_trade)) {
+        warn!("Received invalid quantity for {}: {}", payload.symbol, e);
+        return Err(WebhookError::rejected(e));
+    }
 
-    // Ensure minimum notional value (e.g., 5 USDT for Binance Futures)
-    let min_notional = 5.0; // This should ideally be fetched from exchange info
-    if (quantity_to_trade * current_price) < min_notional {
+    // After a day's loss limit is hit, no entry until the next UTC day
+    let breaker = match account.as_ref().filter(|_| opens_position) {
+        Some(account) => control::check_loss_breaker(state, account).await,
+        None => Ok(()),
+    };
+    if let Err(reason) = breaker {
+        warn!("Order for {} rejected by the daily loss breaker: {}", payload.symbol, reason);
+        return Err(WebhookError::rejected(format!("Rejected by the daily loss breaker: {}", reason)));
+    }
+
+    // Pre-trade risk checks for orders that open or increase exposure
+    let risk_span = info_span!("risk_check").entered();
+    if let Some(account) = account.as_ref().filter(|_| opens_position && !state.policies.risk.is_empty()) {
+        let ctx = risk_context(state, account, &payload.symbol, quantity_to_trade * entry_price);
+        if let Err(reason) = risk::check_all(&state.policies.risk, &ctx) {
+            warn!("Order for {} rejected by risk policy: {}", payload.symbol, reason);
+            return Err(WebhookError::rejected(format!("Rejected by risk policy: {}", reason)));
+        }
+    }
+
+    // The symbol's position limit applies to orders that open or increase exposure
+    if let (Some(config), Some(account)) = (max_position.filter(|_| opens_position), account.as_ref()) {
+        let position: f64 = account.positions.iter()
+            .filter(|p| p.symbol.eq_ignore_ascii_case(&payload.symbol))
+            .filter(|p| position_side.as_ref().is_none_or(|ps| PositionSide::from_str_opt(&p.position_side).as_ref() == Some(ps)))
+            .map(|p| p.position_amt.parse::<f64>().unwrap_or_default())
+            .sum();
+        let signed_quantity = if side == OrderSide::Buy { quantity_to_trade } else { -quantity_to_trade };
+        if let Err(reason) = config.check_position(&payload.symbol, position, signed_quantity) {
+            warn!("Order for {} rejected: {}", payload.symbol, reason);
+            return Err(WebhookError::rejected(format!("Rejected by symbol limit: {}", reason)));
+        }
+    }
+
+    // Ensure the symbol's minimum notional value (MIN_NOTIONAL); closing orders are exempt
+    let min_notional = filters.as_ref().map_or(0.0, |filters| filters.min_notional);
+    if !closes_position && (quantity_to_trade * entry_price) < min_notional {
         error!("Calculated notional value ({:.4}) for {} is below minimum {}. Order not placed.",
-               quantity_to_trade * current_price, payload.symbol, min_notional);
-        return format!("Error: Notional value too small ({:.4})", quantity_to_trade * current_price);
+               quantity_to_trade * entry_price, payload.symbol, min_notional);
+        return Err(WebhookError::rejected(format!("Notional value too small ({:.4})", quantity_to_trade * entry_price)));
     }
+    risk_span.exit();
 
     // Generate a short, unique client order ID using timestamp
     let timestamp = SystemTime::now()
@@ -78,99 +603,499 @@ async fn handle_webhook(
         .as_millis();
     // Use only last 6 digits of timestamp to keep ID short
     let short_timestamp = timestamp % 1000000;
-    let client_order_id = format!("wh{}{}", payload.signal.chars().next().unwrap_or('x'), short_timestamp);
-
-    // 3. Dispatch the order using WebSocketClient (Market Order)
-    let order_result = match payload.signal.to_lowercase().as_str() {
-        "buy" => {
-            println!("Placing MARKET BUY order for {} quantity {} at price {}", payload.symbol, quantity_to_trade, current_price);
-            state.ws_client.new_order(
-                &payload.symbol,
-                OrderSide::Buy,
-                OrderType::Market, // Always a Market Order for this scenario
-                quantity_to_trade,
-                None, // No specific price for Market Order
-                None, // No TimeInForce for Market Order (FOK/IOC might be implied by exchange for Market)
-                Some(&client_order_id), // Use short client order ID
-            ).await
+    let base_order_id = format!("wh{}{}", payload.signal.chars().next().unwrap_or('x'), short_timestamp);
+    // Tag the orders so fills and closed trades are attributed to the variant
+    let tagged_order_id = |suffix: &str| {
+        let id = format!("{}{}", base_order_id, suffix);
+        match payload.variant.as_deref() {
+            Some(tag) => experiment::tag_client_order_id(&id, tag),
+            None => id,
+        }
+    };
+    let client_order_id = tagged_order_id("");
+
+    // Build the entry order, sent over the WebSocket API
+    let mut request = NewOrderRequest::new(&payload.symbol, side, order_type)
+        .quantity(quantity_to_trade)
+        .new_client_order_id(&client_order_id);
+    if let (OrderType::Limit, Some(price)) = (order_type, payload.price) {
+        request = request.price(price).time_in_force(TimeInForce::Gtc);
+    }
+    request = if closes_position {
+        info!("Received {} signal for {}. Attempting to {:?} {} to close the position.", signal.to_uppercase(), payload.symbol, side, quantity_to_trade);
+        close_order_request(request, position_side.clone())
+    } else {
+        info!("Placing {:?} {:?} order for {} quantity {} at price {}", order_type, side, payload.symbol, quantity_to_trade, entry_price);
+        let request = request.reduce_only(payload.reduce_only);
+        match position_side.clone() {
+            Some(ps) => request.position_side(ps), // From the payload; only set for accounts in hedge mode
+            None => request,
+        }
+    };
+
+    // Dry runs report the orders instead of placing them
+    if payload.dry_run {
+        let mut orders = vec![request];
+        orders.extend(protective_orders(&payload.symbol, side, payload.stop_loss, payload.take_profit, position_side, tagged_order_id));
+        for order in &orders {
+            order.validate().map_err(WebhookError::rejected)?;
+        }
+        info!("Dry run of {} signal for {}: {} order(s) not placed", payload.signal, payload.symbol, orders.len());
+        return Ok(Execution::DryRun(orders));
+    }
+
+    // The order requests run in an `order` span with the client order ID, like the fills of the order
+    let order_span = info_span!("order", client_order_id = %client_order_id);
+    async {
+        // Set the requested leverage before an entry; a failure leaves the position unopened
+        if let Some(leverage) = payload.leverage.filter(|_| opens_position) {
+            if let Err(e) = state.rest_client.set_leverage(&payload.symbol, leverage).await {
+                error!("Failed to set leverage {}x for {}: {}", leverage, payload.symbol, e);
+                return Err(WebhookError::exchange(format!("Error setting leverage: {}", e)));
+            }
+            info!("Set leverage for {} to {}x", payload.symbol, leverage);
+        }
+
+        let order_result = state.ws_client.place_order(&request).await;
+        store_order(state, &request, order_result.as_ref());
+
+        let response = match order_result {
+            Ok(response) => response,
+            Err(e) => {
+                error!("Failed to place order: {}", e);
+                return Err(WebhookError::exchange(format!("Error placing order: {}", e)));
+            }
+        };
+        info!("Order placed successfully: {:?}", response);
+
+        // Protect the entry with the requested stop loss and take profit
+        let mut warnings = Vec::new();
+        for protective in protective_orders(&payload.symbol, side, payload.stop_loss, payload.take_profit, position_side, tagged_order_id) {
+            let protective_result = state.ws_client.place_order(&protective).await;
+            store_order(state, &protective, protective_result.as_ref());
+            if let Err(e) = protective_result {
+                error!("Failed to place {:?} order for {}: {}", protective.order_type, payload.symbol, e);
+                warnings.push(format!("{:?} order failed: {}", protective.order_type, e));
+            }
+        }
+        let side = match side {
+            OrderSide::Buy => "BUY",
+            OrderSide::Sell => "SELL",
+        };
+        Ok(Execution::Placed(PlacedOrder { order_id: response.order_id, client_order_id, side, quantity: quantity_to_trade, price: entry_price, warnings }))
+    }.instrument(order_span).await
+}
+
+/// Appends an event to the event log, if one is configured. Failures are logged, never fatal.
+fn record_event(state: &AppState, event: BotEvent) {
+    if let Some(log) = state.event_log.as_deref() {
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default();
+        if let Err(e) = log.append(now_ms, event) {
+            error!("{}", e);
+        }
+    }
+}
+
+/// Stores a signal and its outcome in the database, if one is configured. The write runs in the
+/// background, so the signal never waits for the database; failures are logged, never fatal.
+fn store_signal(state: &AppState, tracking_id: Option<&str>, payload: &WebhookPayload, status: &SignalStatus) {
+    let Some(storage) = state.storage.clone() else { return };
+    let (outcome, reason, client_order_id) = match status {
+        SignalStatus::Placed { client_order_id, .. } => ("placed", None, Some(client_order_id.clone())),
+        SignalStatus::Skipped { reason } => ("skipped", Some(reason.clone()), None),
+        SignalStatus::Rejected { reason, .. } => ("rejected", Some(reason.clone()), None),
+        SignalStatus::Queued => return,
+    };
+    let record = SignalRecord {
+        time_ms: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default(),
+        tracking_id: tracking_id.map(str::to_string),
+        symbol: payload.symbol.to_uppercase(),
+        signal: payload.signal.clone(),
+        payload: serde_json::to_value(payload).unwrap_or_default(), // The secret is never serialized
+        outcome: outcome.to_string(),
+        reason,
+        client_order_id,
+    };
+    tokio::spawn(async move {
+        if let Err(e) = storage.record_signal(&record).await {
+            error!("{}", e);
+        }
+    }.in_current_span());
+}
+
+/// Stores an order request and the exchange's response in the database, if one is configured. The
+/// write runs in the background, so e.g. the stop loss after an entry is never delayed by the
+/// database; failures are logged, never fatal.
+fn store_order(state: &AppState, request: &NewOrderRequest, result: Result<&NewOrderResponse, &String>) {
+    let Some(storage) = state.storage.clone() else { return };
+    let response = result.ok();
+    let record = OrderRecord {
+        time_ms: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default(),
+        symbol: request.symbol.clone(),
+        client_order_id: request.new_client_order_id.clone(),
+        request: request.to_params(),
+        response: response.and_then(|r| serde_json::to_value(r).ok()),
+        order_id: response.map(|r| r.order_id),
+        status: response.map(|r| r.status.to_string()),
+        error: result.err().cloned(),
+    };
+    tokio::spawn(async move {
+        if let Err(e) = storage.record_order(&record).await {
+            error!("{}", e);
+        }
+    }.in_current_span());
+}
+
+/// Executes a signal with the settings of its strategy and its symbol, which restrict the signal
+/// and fill in the size and leverage it does not send.
+async fn execute_with_symbol_config(state: &AppState, payload: &mut WebhookPayload) -> Result<Execution, WebhookError> {
+    let routed;
+    let state = match payload.strategy.as_deref() {
+        Some(name) => match state.strategies.get(&name.trim().to_lowercase()) {
+            Some(route) => {
+                routed = route.apply(state);
+                &routed
+            },
+            None => {
+                warn!("Received signal for unknown strategy '{}'", name);
+                return Err(WebhookError::invalid(format!("Unknown strategy: {}", name)));
+            }
+        },
+        None => state,
+    };
+    let config = state.symbol_configs.resolve(&payload.symbol).map_err(WebhookError::rejected)?;
+    if let Some(config) = config {
+        config.apply(payload).map_err(WebhookError::rejected)?;
+    }
+    execute_signal(state, payload, config).await
+}
+
+/// Runs a `dryRun` signal through every check and answers with the orders it would have placed.
+/// Dry runs bypass the queue, the deduplicator and the arming interlock, and leave no events.
+async fn dry_run_signal(state: &AppState, mut payload: WebhookPayload) -> WebhookResponse {
+    match execute_with_symbol_config(state, &mut payload).await {
+        Ok(Execution::DryRun(orders)) => WebhookResponse::dry_run(orders.iter().map(NewOrderRequest::to_params).collect()),
+        Ok(Execution::Skipped(reason)) => WebhookResponse::skipped(reason),
+        Ok(Execution::Placed(_)) => WebhookError::new(ErrorCode::Unavailable, "Dry run placed an order").into(),
+        Err(error) => error.into(),
+    }
+}
+
+/// Executes a signal: checks the arming interlock and the symbol's settings, places the order,
+/// and records the outcome in the event log and the signal tracker.
+/// Runs in a `signal` span carrying the tracking ID, and the client order ID once the order is built.
+#[instrument(name = "signal", skip_all, fields(id = %tracking_id, symbol = %payload.symbol, signal = %payload.signal))]
+async fn process_signal(state: &AppState, tracking_id: &str, mut payload: WebhookPayload) -> SignalStatus {
+    // While disarmed, signals are logged and recorded but never executed
+    let disarmed = state.interlock.as_deref().map(Interlock::state).filter(|s| !s.armed);
+    let result = match disarmed {
+        Some(arming) => {
+            let reason = format!("Disarmed: {}", arming.reason.as_deref().unwrap_or("not armed"));
+            warn!("Not executing {} signal for {}: {}", payload.signal, payload.symbol, reason);
+            Err(WebhookError::rejected(reason))
         },
-        "sell" => {
-            println!("Placing MARKET SELL order for {} quantity {} at price {}", payload.symbol, quantity_to_trade, current_price);
-            state.ws_client.new_order(
-                &payload.symbol,
-                OrderSide::Sell,
-                OrderType::Market, // Always a Market Order for this scenario
-                quantity_to_trade,
-                None, // No specific price for Market Order
-                None, // No TimeInForce for Market Order
-                Some(&client_order_id), // Use short client order ID
-            ).await
+        None => execute_with_symbol_config(state, &mut payload).await,
+    };
+
+    let status = match result {
+        Ok(Execution::Placed(order)) => {
+            record_event(state, BotEvent::OrderPlaced {
+                symbol: payload.symbol.to_uppercase(),
+                signal: payload.signal.clone(),
+                client_order_id: order.client_order_id.clone(),
+                side: order.side.to_string(),
+                quantity: order.quantity,
+                price: order.price,
+            });
+            SignalStatus::Placed {
+                order_id: order.order_id,
+                client_order_id: order.client_order_id,
+                side: order.side.to_string(),
+                quantity: order.quantity,
+                price: order.price,
+                warnings: order.warnings,
+            }
         },
-        // You can add more complex signals here, e.g., to close positions
-        "close_long" => {
-            println!("Received CLOSE LONG signal for {}. Attempting to market sell current position.", payload.symbol);
-            // In a real bot, you'd query your current position for 'symbol' and use that quantity
-            // For simplicity, we'll assume a fixed quantity or rely on the webhook to send it.
-            state.ws_client.new_order(
-                &payload.symbol,
-                OrderSide::Sell, // Sell to close a long position
-                OrderType::Market,
-                quantity_to_trade, // Using fixed quantity
-                None,
-                None,
-                Some(&client_order_id), // Use short client order ID
-            ).await
+        Err(error) => {
+            if let (ErrorCode::ExchangeError, Some(notifications)) = (error.code, state.notifications.as_ref()) {
+                notifications.order_rejected(&payload.symbol.to_uppercase(), &error.message);
+            }
+            record_event(state, BotEvent::SignalRejected {
+                symbol: payload.symbol.to_uppercase(),
+                signal: payload.signal.clone(),
+                reason: error.message.clone(),
+            });
+            SignalStatus::Rejected { code: error.code, reason: error.message }
         },
-        "close_short" => {
-            println!("Received CLOSE SHORT signal for {}. Attempting to market buy current position.", payload.symbol);
-            state.ws_client.new_order(
-                &payload.symbol,
-                OrderSide::Buy, // Buy to close a short position
-                OrderType::Market,
-                quantity_to_trade, // Using fixed quantity
-                None,
-                None,
-                Some(&client_order_id), // Use short client order ID
-            ).await
+        Ok(Execution::Skipped(reason)) => {
+            record_event(state, BotEvent::SignalRejected {
+                symbol: payload.symbol.to_uppercase(),
+                signal: payload.signal.clone(),
+                reason: reason.clone(),
+            });
+            SignalStatus::Skipped { reason }
         },
-        _ => {
-            warn!("Received unknown signal: {}", payload.signal);
-            return format!("Unknown signal: {}", payload.signal);
-        }
+        Ok(Execution::DryRun(_)) => SignalStatus::Skipped { reason: "Dry run".to_string() },
     };
+    store_signal(state, Some(tracking_id), &payload, &status);
+    state.signals.set(tracking_id, status.clone());
+    status
+}
 
-    match order_result {
-        Ok(response) => {
-            println!("Order placed successfully: {:?}", response);
-            "Order placed successfully".to_string()
-        },
+/// Drains the signal queue, executing one signal at a time in arrival order.
+async fn run_signal_worker(state: AppState, mut receiver: mpsc::Receiver<QueuedSignal>) {
+    while let Some(queued) = receiver.recv().await {
+        debug!("Executing signal {} ({} for {})", queued.tracking_id, queued.payload.signal, queued.payload.symbol);
+        process_signal(&state, &queued.tracking_id, queued.payload).await;
+    }
+    info!("Signal queue closed, worker stopped");
+}
+
+impl AppState {
+    /// Moves signal execution to a background worker fed by a queue of up to `capacity` signals,
+    /// so requests are acknowledged with 202 before the order is placed. Must be called within a
+    /// Tokio runtime.
+    pub fn with_signal_queue(mut self, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        tokio::spawn(run_signal_worker(self.clone(), receiver));
+        self.signal_queue = Some(sender);
+        self
+    }
+}
+
+async fn handle_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> WebhookResponse {
+    let mut payload: WebhookPayload = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
         Err(e) => {
-            error!("Failed to place order: {}", e);
-            format!("Error placing order: {}", e)
+            warn!("Received invalid webhook payload: {}", e);
+            return WebhookError::invalid(format!("Invalid payload: {}", e)).into();
+        }
+    };
+    if let Some(secret) = state.webhook_secret.as_deref() {
+        let signature = headers.get(SIGNATURE_HEADER).and_then(|v| v.to_str().ok());
+        if let Err(reason) = authenticate(secret, &body, signature, payload.secret.as_deref()) {
+            warn!("Rejected unauthenticated webhook request for {}: {}", payload.symbol, reason);
+            return WebhookError::new(ErrorCode::Unauthorized, reason).into();
+        }
+    }
+    payload.secret = None; // Never logged
+    info!("Received webhook payload: {:?}", payload);
+    if payload.signal.eq_ignore_ascii_case(control::KILL_SWITCH_SIGNAL) {
+        if state.webhook_secret.is_none() {
+            warn!("Rejected kill switch signal: the webhook has no secret to authenticate it with");
+            return WebhookError::new(ErrorCode::Unavailable, "The kill switch signal needs a webhook secret").into();
+        }
+        return kill_switch_signal(&state, payload).await;
+    }
+    if payload.dry_run {
+        return dry_run_signal(&state, payload).await;
+    }
+    record_event(&state, BotEvent::Signal {
+        symbol: payload.symbol.clone(),
+        signal: payload.signal.clone(),
+        position_side: payload.position_side.clone(),
+        quote_quantity: payload.quote_quantity,
+        variant: payload.variant.clone(),
+    });
+
+    // Retried alerts are acknowledged but never executed twice
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default();
+    if let Some(reason) = state.deduplicator.as_deref().and_then(|dedup| dedup.register(&payload, now_ms).err()) {
+        info!("Not executing {} signal for {}: {}", payload.signal, payload.symbol, reason);
+        record_event(&state, BotEvent::SignalRejected {
+            symbol: payload.symbol.to_uppercase(),
+            signal: payload.signal.clone(),
+            reason: reason.clone(),
+        });
+        store_signal(&state, None, &payload, &SignalStatus::Skipped { reason: reason.clone() });
+        return WebhookResponse::duplicate(reason);
+    }
+
+    // Misfiring alert loops are cut off per symbol; a rejected alert is not a duplicate when retried
+    if let Some(reason) = state.rate_limiter.as_deref().and_then(|limiter| limiter.check_symbol(&payload.symbol, now_ms).err()) {
+        warn!("Not executing {} signal for {}: {}", payload.signal, payload.symbol, reason);
+        if let Some(dedup) = state.deduplicator.as_deref() {
+            dedup.forget(&payload);
         }
+        record_event(&state, BotEvent::SignalRejected {
+            symbol: payload.symbol.to_uppercase(),
+            signal: payload.signal.clone(),
+            reason: reason.clone(),
+        });
+        store_signal(&state, None, &payload, &SignalStatus::Rejected { code: ErrorCode::RateLimited, reason: reason.clone() });
+        return WebhookError::new(ErrorCode::RateLimited, reason).into();
+    }
+
+    let tracking_id = state.signals.start(now_ms, &payload.symbol, &payload.signal);
+    let Some(queue) = state.signal_queue.as_ref() else {
+        // Without a queue the signal is executed before responding
+        return match process_signal(&state, &tracking_id, payload).await {
+            SignalStatus::Placed { order_id, client_order_id, warnings, .. } => WebhookResponse::placed(&tracking_id, order_id, &client_order_id, warnings),
+            SignalStatus::Rejected { code, reason } => WebhookResponse { tracking_id: Some(tracking_id), ..WebhookError::new(code, reason).into() },
+            SignalStatus::Skipped { reason } => WebhookResponse { tracking_id: Some(tracking_id), ..WebhookResponse::skipped(reason) },
+            SignalStatus::Queued => WebhookResponse::accepted(&tracking_id),
+        };
+    };
+    let (symbol, signal) = (payload.symbol.to_uppercase(), payload.signal.clone());
+    match queue.try_send(QueuedSignal { tracking_id: tracking_id.clone(), payload }) {
+        Ok(()) => WebhookResponse::accepted(&tracking_id),
+        Err(e) => {
+            let (reason, queued) = match e {
+                mpsc::error::TrySendError::Full(queued) => ("Signal queue is full".to_string(), queued),
+                mpsc::error::TrySendError::Closed(queued) => ("Signal worker is not running".to_string(), queued),
+            };
+            error!("Dropping {} signal for {}: {}", signal, symbol, reason);
+            // The dropped alert was never executed, so its retry must not count as a duplicate
+            if let Some(dedup) = state.deduplicator.as_deref() {
+                dedup.forget(&queued.payload);
+            }
+            record_event(&state, BotEvent::SignalRejected { symbol, signal, reason: reason.clone() });
+            let status = SignalStatus::Rejected { code: ErrorCode::Unavailable, reason: reason.clone() };
+            store_signal(&state, Some(&tracking_id), &queued.payload, &status);
+            state.signals.set(&tracking_id, status);
+            WebhookResponse { tracking_id: Some(tracking_id), ..WebhookError::new(ErrorCode::Unavailable, reason).into() }
+        },
+    }
+}
+
+/// Executes a kill switch signal right away, bypassing the queue, the deduplication and the
+/// arming state: it must work most when everything else is stuck.
+async fn kill_switch_signal(state: &AppState, payload: WebhookPayload) -> WebhookResponse {
+    record_event(state, BotEvent::Signal {
+        symbol: payload.symbol.clone(),
+        signal: payload.signal.clone(),
+        position_side: None,
+        quote_quantity: None,
+        variant: None,
+    });
+    match control::panic_close_all(state, "webhook signal").await {
+        Ok(report) => WebhookResponse::flattened(
+            format!("Disarmed; cancelled {} open order(s) and closed {} position(s)", report.cancelled_orders, report.closed_positions),
+            report.problems,
+        ),
+        Err(error) => error.into(),
     }
 }
 
+/// Returns the status of a signal by the tracking ID it was acknowledged with.
+async fn signal_status(
+    State(state): State<AppState>,
+    Path(tracking_id): Path<String>,
+) -> Result<Json<SignalStatus>, WebhookResponse> {
+    state.signals.get(&tracking_id)
+        .map(Json)
+        .ok_or_else(|| WebhookError::new(ErrorCode::NotFound, format!("Unknown signal {}", tracking_id)).into())
+}
+
 pub async fn run_webhook_listener(
     ws_client: WebSocketClient,
     rest_client: RestClient, // Added RestClient
     listen_addr: &str,
-    // webhook_secret: String, // Removed webhook_secret from arguments
+    experiment: Option<Experiment>, // A/B test whose variants are selected by the payload's `variant`
+    event_log: Option<Arc<EventLog>>, // Event log recording signals and decisions
+    webhook_secret: Option<String>, // Shared secret requests must be authenticated with
+    ip_allowlist: Option<IpAllowlist>, // Source addresses allowed to reach the webhook
 ) -> Result<(), Box<dyn std::error::Error>> {
     let app_state = AppState {
         ws_client: Arc::new(ws_client),
         rest_client: Arc::new(rest_client), // Pass RestClient to state
-        // webhook_secret, // Removed webhook_secret from state initialization
-    };
+        experiment: experiment.map(Arc::new),
+        policies: Arc::new(ExecutionPolicies::default()),
+        event_log,
+        storage: None,
+        interlock: None,
+        webhook_secret,
+        ip_allowlist: ip_allowlist.map(Arc::new),
+        admin_token: None,
+        admin_allowlist: None,
+        symbol_configs: Arc::new(SymbolConfigs::default()),
+        strategies: Arc::new(HashMap::new()),
+        deduplicator: Some(Arc::new(AlertDeduplicator::new(dedup::DEFAULT_DEDUP_WINDOW))),
+        rate_limiter: Some(Arc::new(RateLimiter::new(RateLimitSettings::default()))),
+        notifications: None,
+        signals: Arc::new(SignalTracker::new()),
+        signal_queue: None,
+        started: Instant::now(),
+    }.with_signal_queue(queue::DEFAULT_QUEUE_CAPACITY);
+    serve_webhook(app_state, listen_addr).await
+}
+
+/// Builds the webhook router, behind the IP allowlist when one is configured.
+/// Serve it with `into_make_service_with_connect_info::<SocketAddr>()` so the allowlist sees the peer.
+pub fn router(app_state: AppState) -> Router {
+    if app_state.webhook_secret.is_none() {
+        warn!("No webhook secret configured: the webhook accepts unauthenticated requests");
+    }
+    let mut route = post(handle_webhook).route_layer(middleware::from_fn_with_state(app_state.clone(), enforce_rate_limit));
+    if let Some(allowlist) = app_state.ip_allowlist.clone() {
+        info!("Webhook restricted to {} allowed networks", allowlist.allowed.len());
+        route = route.route_layer(middleware::from_fn_with_state(allowlist, enforce_allowlist));
+    }
+    let mut status_route = get(signal_status);
+    if let Some(allowlist) = app_state.ip_allowlist.clone() {
+        status_route = status_route.route_layer(middleware::from_fn_with_state(allowlist, enforce_allowlist));
+    }
+    let mut operator_routes = Router::new()
+        .route("/status", get(status::status))
+        .route("/control/pause", post(control::pause))
+        .route("/control/resume", post(control::resume))
+        .route("/control/cancel/{symbol}", post(control::cancel))
+        .route("/control/close_all", post(control::close_all))
+        .route("/control/kill", post(control::kill));
+    if let Some(allowlist) = app_state.admin_allowlist.clone() {
+        operator_routes = operator_routes.route_layer(middleware::from_fn_with_state(allowlist, enforce_allowlist));
+    }
+    Router::new()
+        .route("/webhook", route)
+        .route("/signals/{id}", status_route)
+        .route("/health", get(status::health))
+        .merge(operator_routes)
+        .with_state(app_state)
+}
+
+/// Runs the webhook listener until `shutdown` resolves, calling `on_ready` once the listener is
+/// bound. Used by the subsystem supervisor, which only starts the webhook once its dependencies
+/// (exchange session, user data stream) are ready. With a `tls` configuration the webhook is
+/// served over HTTPS, see `tls`.
+pub async fn serve_webhook_until(
+    app_state: AppState,
+    listen_addr: &str,
+    tls: Option<Arc<tls::ServerConfig>>,
+    on_ready: impl FnOnce(),
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<(), String> {
+    let app = router(app_state);
 
-    let app = Router::new()
-        .route("/webhook", post(handle_webhook))
-        .with_state(app_state);
+    let listener = tokio::net::TcpListener::bind(listen_addr).await
+        .map_err(|e| format!("Failed to bind the webhook listener on {}: {}", listen_addr, e))?;
+    if let Some(tls) = tls {
+        info!("TradingView Webhook listener starting on https://{}", listen_addr);
+        on_ready();
+        return tls::serve_tls_until(listener, app, tls, shutdown).await;
+    }
+    info!("TradingView Webhook listener starting on http://{}", listen_addr);
+    on_ready();
+
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).with_graceful_shutdown(shutdown).await
+        .map_err(|e| format!("Webhook listener failed: {}", e))
+}
+
+/// Runs the webhook listener with a prepared `AppState`, e.g. one carrying custom sizing and risk
+/// policies when the crate is embedded.
+pub async fn serve_webhook(
+    app_state: AppState,
+    listen_addr: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let app = router(app_state);
 
     let listener = tokio::net::TcpListener::bind(listen_addr).await?;
     info!("TradingView Webhook listener starting on http://{}", listen_addr);
 
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
@@ -0,0 +1,119 @@
+// src/webhook/queue.rs
+
+//! This module decouples acknowledging a webhook alert from executing it. TradingView gives up on
+//! a request after about 3 seconds, while executing a signal needs a price lookup, an account fetch
+//! and the order round trip. Validated signals are therefore put on a queue drained by a worker,
+//! and the request is answered with 202 and a tracking ID right away. The outcome of a signal is
+//! kept by the `SignalTracker` and served on `GET /signals/{id}`. That route needs no token, so
+//! tracking IDs are random 128-bit UUIDs: only the sender of an alert can look up its outcome.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::response::ErrorCode;
+use super::WebhookPayload;
+
+/// Default number of signals waiting for the worker before new ones are refused.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 64;
+/// Number of signal outcomes kept for lookups.
+pub const TRACKED_SIGNALS: usize = 1000;
+
+/// A validated signal waiting for execution.
+#[derive(Debug, Clone)]
+pub struct QueuedSignal {
+    pub tracking_id: String,
+    pub payload: WebhookPayload,
+}
+
+/// Processing state of a received signal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignalStatus {
+    Queued,
+    Placed {
+        order_id: u64,
+        client_order_id: String,
+        side: String,
+        quantity: f64,
+        price: f64,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        warnings: Vec<String>, // Problems after the order was placed, e.g. a protective order that failed
+    },
+    Skipped {
+        reason: String, // Nothing to do, e.g. a close signal while flat
+    },
+    Rejected {
+        code: ErrorCode,
+        reason: String,
+    },
+}
+
+/// A received signal and its processing state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackedSignal {
+    pub tracking_id: String,
+    pub received_ms: i64,
+    pub symbol: String,
+    pub signal: String,
+    #[serde(flatten)]
+    pub status: SignalStatus,
+}
+
+/// Remembers the status of the most recent signals by tracking ID.
+#[derive(Debug, Default)]
+pub struct SignalTracker {
+    signals: Mutex<VecDeque<TrackedSignal>>, // Oldest first, at most `TRACKED_SIGNALS`
+}
+
+impl SignalTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new signal received at `now_ms` as queued and returns its tracking ID.
+    pub fn start(&self, now_ms: i64, symbol: &str, signal: &str) -> String {
+        let tracking_id = Uuid::new_v4().to_string();
+        if let Ok(mut signals) = self.signals.lock() {
+            if signals.len() >= TRACKED_SIGNALS {
+                signals.pop_front();
+            }
+            signals.push_back(TrackedSignal {
+                tracking_id: tracking_id.clone(),
+                received_ms: now_ms,
+                symbol: symbol.to_uppercase(),
+                signal: signal.to_string(),
+                status: SignalStatus::Queued,
+            });
+        }
+        tracking_id
+    }
+
+    /// Records the outcome of a signal.
+    pub fn set(&self, tracking_id: &str, status: SignalStatus) {
+        let Ok(mut signals) = self.signals.lock() else { return };
+        if let Some(entry) = signals.iter_mut().find(|entry| entry.tracking_id == tracking_id) {
+            entry.status = status;
+        }
+    }
+
+    /// Returns the status of a signal, if it is still tracked.
+    pub fn get(&self, tracking_id: &str) -> Option<SignalStatus> {
+        let signals = self.signals.lock().ok()?;
+        signals.iter().find(|entry| entry.tracking_id == tracking_id).map(|entry| entry.status.clone())
+    }
+
+    /// Returns the tracked signals, most recent first.
+    pub fn recent(&self) -> Vec<TrackedSignal> {
+        self.signals.lock().map(|signals| signals.iter().rev().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Returns the most recently received signal that has been processed.
+    pub fn last_processed(&self) -> Option<TrackedSignal> {
+        let signals = self.signals.lock().ok()?;
+        signals.iter().rev().find(|entry| entry.status != SignalStatus::Queued).cloned()
+    }
+}
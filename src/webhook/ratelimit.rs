@@ -0,0 +1,204 @@
+// src/webhook/ratelimit.rs
+
+//! This module protects the webhook against floods, e.g. a misfiring alert loop spamming orders or
+//! someone hammering a leaked tunnel URL. Requests to `/webhook` pass two token buckets, a global
+//! one and one per source address, and are rejected with 429 once a bucket is empty. Buckets hold
+//! up to `burst` requests and refill at a steady rate per minute. Signals that get through are
+//! additionally limited per symbol: more than `symbol_signals_per_minute` signals for a symbol
+//! within a rolling minute are rejected.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use super::allowlist::IpAllowlist;
+use super::response::{ErrorCode, WebhookError, WebhookResponse};
+use super::AppState;
+
+/// Default global limit: 120 requests per minute, bursts of 30.
+pub const DEFAULT_GLOBAL_LIMIT: RateLimit = RateLimit { per_minute: 120, burst: 30 };
+/// Default limit per source address: 60 requests per minute, bursts of 20.
+pub const DEFAULT_SOURCE_LIMIT: RateLimit = RateLimit { per_minute: 60, burst: 20 };
+/// Default number of signals per symbol within a rolling minute.
+pub const DEFAULT_SYMBOL_SIGNALS_PER_MINUTE: u32 = 10;
+/// Number of tracked sources above which idle sources, then the least recently seen, are forgotten.
+pub const MAX_TRACKED_SOURCES: usize = 1000;
+const MINUTE_MS: i64 = 60_000;
+
+/// Settings of a token bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub per_minute: u32, // Steady rate the bucket refills at
+    pub burst: u32, // Capacity: requests accepted at once after a quiet period
+}
+
+impl RateLimit {
+    /// Parses `per_minute[/burst]`, e.g. `120/30`; the burst defaults to the rate. `off` or `0`
+    /// disables the limit (`None`).
+    pub fn parse(value: &str) -> Result<Option<Self>, String> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("off") || value == "0" {
+            return Ok(None);
+        }
+        let (rate, burst) = value.split_once('/').map_or((value, None), |(r, b)| (r, Some(b)));
+        let per_minute = rate.trim().parse::<u32>().map_err(|e| format!("Invalid rate '{}': {}", rate, e))?;
+        let burst = match burst {
+            Some(burst) => burst.trim().parse::<u32>().map_err(|e| format!("Invalid burst '{}': {}", burst, e))?,
+            None => per_minute,
+        };
+        if per_minute == 0 || burst == 0 {
+            return Err(format!("Rate limit '{}' needs a positive rate and burst", value));
+        }
+        Ok(Some(Self { per_minute, burst }))
+    }
+}
+
+/// A token bucket: starts full, each request takes a token, tokens refill continuously.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated_ms: i64,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    pub fn new(limit: RateLimit, now_ms: i64) -> Self {
+        Self { limit, tokens: limit.burst as f64, updated_ms: now_ms }
+    }
+
+    fn tokens_at(&self, now_ms: i64) -> f64 {
+        let elapsed_ms = (now_ms - self.updated_ms).max(0) as f64;
+        (self.tokens + elapsed_ms * self.limit.per_minute as f64 / MINUTE_MS as f64).min(self.limit.burst as f64)
+    }
+
+    fn refill(&mut self, now_ms: i64) {
+        self.tokens = self.tokens_at(now_ms);
+        self.updated_ms = now_ms;
+    }
+
+    /// Takes a token at `now_ms`; false when the bucket is empty.
+    pub fn try_acquire(&mut self, now_ms: i64) -> bool {
+        self.refill(now_ms);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// Returns true when the bucket has refilled completely by `now_ms`, without touching it, so
+    /// `updated_ms` stays the time of the last request.
+    fn is_full(&self, now_ms: i64) -> bool {
+        self.tokens_at(now_ms) >= self.limit.burst as f64
+    }
+}
+
+/// Which limits apply; `None` disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitSettings {
+    pub global: Option<RateLimit>,
+    pub per_source: Option<RateLimit>,
+    pub symbol_signals_per_minute: Option<u32>,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            global: Some(DEFAULT_GLOBAL_LIMIT),
+            per_source: Some(DEFAULT_SOURCE_LIMIT),
+            symbol_signals_per_minute: Some(DEFAULT_SYMBOL_SIGNALS_PER_MINUTE),
+        }
+    }
+}
+
+/// Tracks the request and signal rates.
+#[derive(Debug)]
+pub struct RateLimiter {
+    settings: RateLimitSettings,
+    global: Mutex<Option<TokenBucket>>,
+    sources: Mutex<HashMap<IpAddr, TokenBucket>>,
+    symbols: Mutex<HashMap<String, VecDeque<i64>>>, // Symbol -> times of its signals within the last minute
+}
+
+impl RateLimiter {
+    /// Creates a limiter enforcing `settings`.
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            settings,
+            global: Mutex::new(None),
+            sources: Mutex::new(HashMap::new()),
+            symbols: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Admits a request from `source` (unknown when `None`) at `now_ms`, or returns why it is refused.
+    pub fn check_request(&self, source: Option<IpAddr>, now_ms: i64) -> Result<(), String> {
+        if let Some(limit) = self.settings.global {
+            let mut global = self.global.lock().map_err(|_| "Rate limiter state is poisoned".to_string())?;
+            if !global.get_or_insert_with(|| TokenBucket::new(limit, now_ms)).try_acquire(now_ms) {
+                return Err(format!("Global rate limit of {} requests per minute exceeded", limit.per_minute));
+            }
+        }
+        if let (Some(limit), Some(source)) = (self.settings.per_source, source) {
+            let mut sources = self.sources.lock().map_err(|_| "Rate limiter state is poisoned".to_string())?;
+            if sources.len() >= MAX_TRACKED_SOURCES && !sources.contains_key(&source) {
+                sources.retain(|_, bucket| !bucket.is_full(now_ms));
+                // Under a flood from many addresses no bucket is idle; the least recently seen source makes room
+                if sources.len() >= MAX_TRACKED_SOURCES {
+                    let least_recent = sources.iter().min_by_key(|(_, bucket)| bucket.updated_ms).map(|(ip, _)| *ip);
+                    least_recent.map(|ip| sources.remove(&ip));
+                }
+            }
+            if !sources.entry(source).or_insert_with(|| TokenBucket::new(limit, now_ms)).try_acquire(now_ms) {
+                return Err(format!("Rate limit of {} requests per minute exceeded for {}", limit.per_minute, source));
+            }
+        }
+        Ok(())
+    }
+
+    /// Admits a signal for `symbol` at `now_ms`, or returns why it is refused.
+    pub fn check_symbol(&self, symbol: &str, now_ms: i64) -> Result<(), String> {
+        let Some(max_signals) = self.settings.symbol_signals_per_minute else { return Ok(()) };
+        let mut symbols = self.symbols.lock().map_err(|_| "Rate limiter state is poisoned".to_string())?;
+        symbols.retain(|_, times| {
+            while times.front().is_some_and(|received_ms| now_ms - received_ms >= MINUTE_MS) {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+        let times = symbols.entry(symbol.to_uppercase()).or_default();
+        if times.len() >= max_signals as usize {
+            return Err(format!("More than {} signals for {} within a minute", max_signals, symbol.to_uppercase()));
+        }
+        times.push_back(now_ms);
+        Ok(())
+    }
+}
+
+/// Middleware rejecting requests over the global or per-source rate limit with 429. The source is
+/// resolved like the allowlist does, honoring `X-Forwarded-For` from trusted proxies only.
+pub async fn enforce_rate_limit(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(limiter) = state.rate_limiter.as_deref() else { return next.run(request).await };
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip());
+    let source = peer.and_then(|peer| match state.ip_allowlist.as_deref() {
+        Some(allowlist) => allowlist.client_ip(peer, request.headers()),
+        None => IpAllowlist::tradingview().client_ip(peer, request.headers()),
+    });
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default();
+    match limiter.check_request(source, now_ms) {
+        Ok(()) => next.run(request).await,
+        Err(reason) => {
+            warn!("Rejected webhook request from {:?}: {}", source, reason);
+            WebhookResponse::from(WebhookError::new(ErrorCode::RateLimited, reason)).into_response()
+        },
+    }
+}
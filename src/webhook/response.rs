@@ -0,0 +1,184 @@
+// src/webhook/response.rs
+
+//! This module defines the JSON body of webhook responses and the HTTP status each outcome maps
+//! to, so senders can tell a bad request (4xx, do not retry) from an exchange failure (502).
+//!
+//! ```json
+//! {"status": "placed", "tracking_id": "1718000000000-0", "order_id": 4075520283, "client_order_id": "whb123456"}
+//! {"status": "error", "error": {"code": "rejected", "message": "Rejected by risk policy: ..."}}
+//! ```
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Why a request or signal failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidPayload, // The body is not a valid payload, or its fields contradict each other
+    Unauthorized, // Missing or wrong signature/secret
+    Rejected, // Refused by a policy: risk checks, sizing, symbol settings, the arming interlock
+    ExchangeError, // Binance could not be reached or refused a request
+    Unavailable, // The bot cannot take signals right now, e.g. the queue is full
+    RateLimited, // Too many requests, or too many signals for a symbol
+    NotFound,
+}
+
+impl ErrorCode {
+    /// Returns the HTTP status responses with this error are sent with.
+    pub fn http_status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidPayload => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Rejected => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::ExchangeError => StatusCode::BAD_GATEWAY,
+            ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+        }
+    }
+}
+
+/// A failed request or signal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl WebhookError {
+    /// Creates an error with `code`.
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    /// An invalid payload (400).
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidPayload, message)
+    }
+
+    /// A signal refused by a policy (422).
+    pub fn rejected(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Rejected, message)
+    }
+
+    /// A failed exchange request (502).
+    pub fn exchange(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ExchangeError, message)
+    }
+}
+
+/// Outcome reported in a webhook response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Placed, // The order was placed before responding
+    Accepted, // The signal was queued; its outcome is served on `/signals/{tracking_id}`
+    Duplicate, // A repeat of an alert already received; acknowledged, not executed
+    Skipped, // Nothing to do, e.g. a close signal while flat
+    DryRun, // A `dryRun` signal passed every check; its orders were not placed
+    Flattened, // The kill switch ran: the bot is disarmed, orders cancelled and positions closed
+    Error,
+}
+
+/// JSON body of webhook responses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookResponse {
+    pub status: ResponseStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracking_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orders: Vec<Value>, // Order parameters a dry run would have sent, entry first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>, // Problems after the order was placed, e.g. a protective order that failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<WebhookError>,
+}
+
+impl WebhookResponse {
+    fn with_status(status: ResponseStatus) -> Self {
+        Self {
+            status,
+            tracking_id: None,
+            order_id: None,
+            client_order_id: None,
+            message: None,
+            orders: Vec::new(),
+            warnings: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// An order placed before responding.
+    pub fn placed(tracking_id: &str, order_id: u64, client_order_id: &str, warnings: Vec<String>) -> Self {
+        Self {
+            tracking_id: Some(tracking_id.to_string()),
+            order_id: Some(order_id),
+            client_order_id: Some(client_order_id.to_string()),
+            warnings,
+            ..Self::with_status(ResponseStatus::Placed)
+        }
+    }
+
+    /// A signal queued for execution.
+    pub fn accepted(tracking_id: &str) -> Self {
+        Self { tracking_id: Some(tracking_id.to_string()), ..Self::with_status(ResponseStatus::Accepted) }
+    }
+
+    /// A repeated alert that was not executed.
+    pub fn duplicate(message: impl Into<String>) -> Self {
+        Self { message: Some(message.into()), ..Self::with_status(ResponseStatus::Duplicate) }
+    }
+
+    /// A signal that needed no order.
+    pub fn skipped(message: impl Into<String>) -> Self {
+        Self { message: Some(message.into()), ..Self::with_status(ResponseStatus::Skipped) }
+    }
+
+    /// The orders a dry run would have placed.
+    pub fn dry_run(orders: Vec<Value>) -> Self {
+        Self { orders, ..Self::with_status(ResponseStatus::DryRun) }
+    }
+
+    /// A kill switch signal that was executed; `problems` lists what could not be cancelled or closed.
+    pub fn flattened(message: impl Into<String>, problems: Vec<String>) -> Self {
+        Self { message: Some(message.into()), warnings: problems, ..Self::with_status(ResponseStatus::Flattened) }
+    }
+
+    /// A failed request or signal.
+    pub fn error(error: WebhookError) -> Self {
+        Self { error: Some(error), ..Self::with_status(ResponseStatus::Error) }
+    }
+
+    /// Returns the HTTP status the response is sent with.
+    pub fn http_status(&self) -> StatusCode {
+        match (self.status, &self.error) {
+            (ResponseStatus::Accepted, _) => StatusCode::ACCEPTED,
+            (_, Some(error)) => error.code.http_status(),
+            _ => StatusCode::OK,
+        }
+    }
+}
+
+impl From<WebhookError> for WebhookResponse {
+    fn from(error: WebhookError) -> Self {
+        Self::error(error)
+    }
+}
+
+impl IntoResponse for WebhookResponse {
+    fn into_response(self) -> Response {
+        (self.http_status(), Json(self)).into_response()
+    }
+}
@@ -0,0 +1,84 @@
+// src/webhook/status.rs
+
+//! This module serves the health and status endpoints of the webhook server, so monitors and
+//! whoever wires up TradingView alerts can check the bot without reading its logs.
+//! `GET /health` is a plain liveness probe. `GET /status` reports the WebSocket API session, the
+//! arming state, the signal queue, the last processed signal and the open positions, so it is
+//! guarded like the control routes: it is refused (503) without the admin token, which must be sent
+//! as `Authorization: Bearer <token>`, and with `ADMIN_ALLOWED_IPS` only answers allowed addresses.
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use serde::Serialize;
+
+use crate::account_info::PositionRisk;
+use crate::arming::Interlock;
+use super::queue::TrackedSignal;
+use super::response::WebhookResponse;
+use super::{authorize_admin, AppState};
+
+/// An open position leg.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PositionSummary {
+    pub symbol: String,
+    pub position_side: String,
+    pub amount: f64, // Negative for shorts in one-way mode
+    pub entry_price: f64,
+    pub mark_price: f64,
+    pub unrealized_pnl: f64,
+}
+
+/// Summarizes the position legs that are not flat.
+pub fn summarize_positions(positions: &[PositionRisk]) -> Vec<PositionSummary> {
+    let number = |value: &str| value.parse::<f64>().unwrap_or_default();
+    positions.iter()
+        .filter(|p| p.amount() != 0.0)
+        .map(|p| PositionSummary {
+            symbol: p.symbol.clone(),
+            position_side: p.position_side.clone(),
+            amount: p.amount(),
+            entry_price: number(&p.entry_price),
+            mark_price: number(&p.mark_price),
+            unrealized_pnl: number(&p.unrealized_profit),
+        })
+        .collect()
+}
+
+/// Body of `GET /status`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceStatus {
+    pub uptime_secs: u64,
+    pub ws_connected: bool, // Whether the WebSocket API session orders are sent over is open
+    pub armed: Option<bool>, // `None` without an arming interlock
+    pub queued_signals: usize, // Signals waiting for the worker
+    pub last_signal: Option<TrackedSignal>, // Most recent signal that has been processed
+    pub positions: Vec<PositionSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub positions_error: Option<String>, // Why the positions could not be fetched
+}
+
+/// Liveness probe.
+pub async fn health() -> &'static str {
+    "ok"
+}
+
+/// Reports the state of the bot.
+pub async fn status(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<ServiceStatus>, WebhookResponse> {
+    authorize_admin(&state, &headers)?;
+    let (positions, positions_error) = match state.rest_client.get_position_risk(None).await {
+        Ok(positions) => (summarize_positions(&positions), None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    Ok(Json(ServiceStatus {
+        uptime_secs: state.started.elapsed().as_secs(),
+        ws_connected: state.ws_client.is_connected(),
+        armed: state.interlock.as_deref().map(Interlock::is_armed),
+        queued_signals: state.signal_queue.as_ref().map_or(0, |queue| queue.max_capacity() - queue.capacity()),
+        last_signal: state.signals.last_processed(),
+        positions,
+        positions_error,
+    }))
+}
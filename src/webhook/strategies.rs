@@ -0,0 +1,138 @@
+// src/webhook/strategies.rs
+
+//! This module routes webhook signals by the `strategy` field of the payload, so several
+//! TradingView strategies can share one endpoint without interfering. Each strategy can bring its
+//! own symbol settings (which also act as its symbol whitelist, see `symbols`), its own sizing
+//! policy and its own API keys, e.g. a sub-account:
+//!
+//! ```json
+//! {
+//!   "trend": { "symbols": { "BTCUSDT": { "leverage": 3 } }, "sizing": "risk_percent:0.01" },
+//!   "scalp": { "symbols": { "ETHUSDT": {}, "SOLUSDT": {} }, "apiKey": "...", "secretKey": "..." }
+//! }
+//! ```
+//!
+//! Settings a strategy leaves out are taken from the global configuration; the risk limits (see
+//! `risk::ExposureLimits` and `risk::DailyLossBreaker`) always are. Signals without a
+//! `strategy` use the global configuration; signals naming an unknown strategy are rejected.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::rest_api::RestClient;
+use crate::risk::{ExecutionPolicies, PositionSizer};
+use crate::websocket::WebSocketClient;
+use super::symbols::{symbol_configs_from_map, SymbolConfig, SymbolConfigs};
+use super::AppState;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RawStrategyConfig {
+    #[serde(default)]
+    symbols: Option<HashMap<String, SymbolConfig>>,
+    #[serde(default)]
+    sizing: Option<String>,
+    #[serde(default)]
+    api_key: Option<String>,
+    #[serde(default)]
+    secret_key: Option<String>,
+}
+
+/// API credentials of a strategy trading on its own account.
+#[derive(Clone, PartialEq)]
+pub struct Credentials {
+    pub api_key: String,
+    pub secret_key: String,
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials").field("api_key", &self.api_key).finish_non_exhaustive() // Never prints the secret
+    }
+}
+
+/// Configuration of one strategy; `None` falls back to the global setting.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StrategyConfig {
+    pub symbols: Option<SymbolConfigs>,
+    pub sizing: Option<PositionSizer>,
+    pub credentials: Option<Credentials>,
+}
+
+/// The configured strategies, keyed by lowercase name.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StrategyConfigs {
+    pub strategies: HashMap<String, StrategyConfig>,
+}
+
+impl StrategyConfigs {
+    /// Returns the configuration of a strategy.
+    pub fn get(&self, name: &str) -> Option<&StrategyConfig> {
+        self.strategies.get(&name.trim().to_lowercase())
+    }
+
+    /// Returns true when no strategy is configured.
+    pub fn is_empty(&self) -> bool {
+        self.strategies.is_empty()
+    }
+}
+
+/// Parses the strategy configuration from its JSON form.
+pub fn parse_strategy_configs(json: &str) -> Result<StrategyConfigs, String> {
+    let raw: HashMap<String, RawStrategyConfig> = serde_json::from_str(json)
+        .map_err(|e| format!("Invalid strategy configuration: {}", e))?;
+    let mut strategies = HashMap::new();
+    for (name, raw) in raw {
+        let symbols = raw.symbols.map(symbol_configs_from_map).transpose()
+            .map_err(|e| format!("Strategy {}: {}", name, e))?;
+        let sizing = raw.sizing.as_deref().map(PositionSizer::parse).transpose()
+            .map_err(|e| format!("Strategy {}: {}", name, e))?;
+        let credentials = match (raw.api_key, raw.secret_key) {
+            (Some(api_key), Some(secret_key)) => Some(Credentials { api_key, secret_key }),
+            (None, None) => None,
+            _ => return Err(format!("Strategy {} needs both apiKey and secretKey", name)),
+        };
+        if strategies.insert(name.trim().to_lowercase(), StrategyConfig { symbols, sizing, credentials }).is_some() {
+            return Err(format!("Strategy {} is configured twice", name));
+        }
+    }
+    Ok(StrategyConfigs { strategies })
+}
+
+/// What a strategy replaces in the application state.
+#[derive(Clone)]
+pub struct StrategyRoute {
+    pub symbol_configs: Option<Arc<SymbolConfigs>>,
+    pub policies: Option<Arc<ExecutionPolicies>>,
+    pub clients: Option<(Arc<WebSocketClient>, Arc<RestClient>)>, // Clients of the strategy's own account
+}
+
+impl StrategyRoute {
+    /// Builds the route of a strategy; `clients` must be given when it has credentials. Its sizing
+    /// replaces the one of `policies`, whose risk limits still apply.
+    pub fn new(config: &StrategyConfig, clients: Option<(Arc<WebSocketClient>, Arc<RestClient>)>, policies: &ExecutionPolicies) -> Self {
+        Self {
+            symbol_configs: config.symbols.clone().map(Arc::new),
+            policies: config.sizing.map(|sizer| Arc::new(policies.with_sizing(Box::new(sizer)))),
+            clients,
+        }
+    }
+
+    /// Returns `state` with the settings of the strategy.
+    pub fn apply(&self, state: &AppState) -> AppState {
+        let mut state = state.clone();
+        if let Some(symbol_configs) = &self.symbol_configs {
+            state.symbol_configs = symbol_configs.clone();
+        }
+        if let Some(policies) = &self.policies {
+            state.policies = policies.clone();
+        }
+        if let Some((ws_client, rest_client)) = &self.clients {
+            state.ws_client = ws_client.clone();
+            state.rest_client = rest_client.clone();
+        }
+        state
+    }
+}
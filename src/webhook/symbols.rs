@@ -0,0 +1,150 @@
+// src/webhook/symbols.rs
+
+//! This module holds the per-symbol webhook configuration, so one bot instance can serve alerts
+//! from several TradingView charts with different sizing per market. It is defined as a JSON
+//! object keyed by symbol; the `*` entry applies to symbols without their own entry:
+//!
+//! ```json
+//! {
+//!   "BTCUSDT": { "quoteQuantity": 200, "maxPosition": 0.05, "leverage": 5 },
+//!   "ETHUSDT": { "quantity": 0.1, "allowedSignals": ["buy", "close_long"] },
+//!   "*": { "enabled": false }
+//! }
+//! ```
+//!
+//! Without any configuration every symbol is traded with the payload as sent. Once symbols are
+//! configured, alerts for other symbols are rejected unless a `*` entry exists.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::WebhookPayload;
+
+/// Key of the entry applying to symbols without their own entry.
+pub const DEFAULT_SYMBOL_KEY: &str = "*";
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Webhook trading configuration of one symbol.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SymbolConfig {
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool, // Alerts for a disabled symbol are rejected
+    #[serde(default)]
+    pub quantity: Option<f64>, // Default order size in the base asset, when the payload sends no size
+    #[serde(default)]
+    pub quote_quantity: Option<f64>, // Default order size in the quote asset, when the payload sends no size
+    #[serde(default)]
+    pub max_position: Option<f64>, // Largest absolute position (base asset) an opening order may lead to
+    #[serde(default)]
+    pub leverage: Option<u32>, // Leverage set before entries, when the payload sends none
+    #[serde(default)]
+    pub allowed_signals: Option<Vec<String>>, // Signals accepted for the symbol; all by default
+}
+
+impl Default for SymbolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            quantity: None,
+            quote_quantity: None,
+            max_position: None,
+            leverage: None,
+            allowed_signals: None,
+        }
+    }
+}
+
+impl SymbolConfig {
+    fn validate(&self, symbol: &str) -> Result<(), String> {
+        if self.quantity.is_some() && self.quote_quantity.is_some() {
+            return Err(format!("{}: only one of quantity and quoteQuantity can be set", symbol));
+        }
+        let positive = [("quantity", self.quantity), ("quoteQuantity", self.quote_quantity), ("maxPosition", self.max_position)];
+        if let Some((name, value)) = positive.iter().find_map(|(name, value)| value.filter(|v| *v <= 0.0).map(|v| (name, v))) {
+            return Err(format!("{}: {} must be positive, got {}", symbol, name, value));
+        }
+        if self.leverage == Some(0) {
+            return Err(format!("{}: leverage must be at least 1", symbol));
+        }
+        Ok(())
+    }
+
+    /// Checks that the symbol accepts the payload's signal and fills in the default size and
+    /// leverage where the payload sends none. Close signals keep closing the whole position.
+    pub fn apply(&self, payload: &mut WebhookPayload) -> Result<(), String> {
+        if !self.enabled {
+            return Err(format!("Trading {} via webhook is disabled", payload.symbol));
+        }
+        let allowed = |signals: &Vec<String>| signals.iter().any(|signal| signal.eq_ignore_ascii_case(&payload.signal));
+        if !self.allowed_signals.as_ref().is_none_or(allowed) {
+            return Err(format!("Signal {} is not allowed for {}", payload.signal, payload.symbol));
+        }
+        let opens = matches!(payload.signal.to_lowercase().as_str(), "buy" | "sell");
+        if opens && payload.quantity.is_none() && payload.quote_quantity.is_none() {
+            payload.quantity = self.quantity;
+            payload.quote_quantity = self.quote_quantity;
+        }
+        payload.leverage = payload.leverage.or(self.leverage);
+        Ok(())
+    }
+
+    /// Checks that an order changing the position by `signed_quantity` (negative for sells) keeps
+    /// it within `max_position`.
+    pub fn check_position(&self, symbol: &str, position: f64, signed_quantity: f64) -> Result<(), String> {
+        match self.max_position {
+            Some(max) if (position + signed_quantity).abs() > max => Err(format!(
+                "Position in {} would grow to {} (max {})", symbol, position + signed_quantity, max
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Webhook trading configuration of all symbols.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolConfigs {
+    symbols: HashMap<String, SymbolConfig>, // Keyed by upper-case symbol, or `*`
+}
+
+impl SymbolConfigs {
+    /// Returns true when no symbol is configured.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns the configuration of `symbol`: its own entry, else the `*` entry. `Ok(None)` when
+    /// nothing is configured; an error when other symbols are configured but not this one.
+    pub fn resolve(&self, symbol: &str) -> Result<Option<&SymbolConfig>, String> {
+        if self.symbols.is_empty() {
+            return Ok(None);
+        }
+        self.symbols.get(&symbol.to_uppercase())
+            .or_else(|| self.symbols.get(DEFAULT_SYMBOL_KEY))
+            .map(Some)
+            .ok_or_else(|| format!("{} is not configured for webhook trading", symbol))
+    }
+}
+
+/// Parses per-symbol configurations from a JSON object keyed by symbol and validates them.
+pub fn parse_symbol_configs(json: &str) -> Result<SymbolConfigs, String> {
+    let raw: HashMap<String, SymbolConfig> = serde_json::from_str(json)
+        .map_err(|e| format!("Invalid symbol configuration: {}", e))?;
+    symbol_configs_from_map(raw)
+}
+
+/// Validates symbol settings keyed by symbol, e.g. when embedded in another configuration.
+pub fn symbol_configs_from_map(raw: HashMap<String, SymbolConfig>) -> Result<SymbolConfigs, String> {
+    let mut symbols = HashMap::new();
+    for (symbol, config) in raw {
+        config.validate(&symbol)?;
+        if symbols.insert(symbol.trim().to_uppercase(), config).is_some() {
+            return Err(format!("Symbol {} is configured twice", symbol));
+        }
+    }
+    Ok(SymbolConfigs { symbols })
+}
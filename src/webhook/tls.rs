@@ -0,0 +1,111 @@
+// src/webhook/tls.rs
+
+//! This module serves the webhook over HTTPS, for hosts with a public IP that expose it directly
+//! instead of through the ngrok tunnel. TradingView only sends alerts to ports 80 and 443, so the
+//! listener is typically bound to `0.0.0.0:443` with a certificate for the host's domain (e.g.
+//! from Let's Encrypt). The certificate chain and private key are read from PEM files
+//! (`WEBHOOK_TLS_CERT`, `WEBHOOK_TLS_KEY`).
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{extract::ConnectInfo, Extension, Router};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use tracing::{debug, info, warn};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls;
+pub use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+/// How long a client may take to complete the TLS handshake.
+pub const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long open connections may take to finish once the listener stops.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Locations of the PEM files of the HTTPS listener.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsFiles {
+    pub cert_path: PathBuf, // Certificate chain, leaf first (e.g. Let's Encrypt's `fullchain.pem`)
+    pub key_path: PathBuf,
+}
+
+impl TlsFiles {
+    /// Loads the server configuration from the files.
+    pub fn load(&self) -> Result<Arc<ServerConfig>, String> {
+        load_server_config(&self.cert_path, &self.key_path)
+    }
+}
+
+/// Loads the certificate chain and private key (PKCS#8, PKCS#1 or SEC1) from PEM files.
+pub fn load_server_config(cert_path: &Path, key_path: &Path) -> Result<Arc<ServerConfig>, String> {
+    let open = |path: &Path| File::open(path).map(BufReader::new)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e));
+    let certs = rustls_pemfile::certs(&mut open(cert_path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid certificate in {}: {}", cert_path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificate found in {}", cert_path.display()));
+    }
+    let key = rustls_pemfile::private_key(&mut open(key_path)?)
+        .map_err(|e| format!("Invalid private key in {}: {}", key_path.display(), e))?
+        .ok_or_else(|| format!("No private key found in {}", key_path.display()))?;
+    let mut config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::aws_lc_rs::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Certificate {} does not match key {}: {}", cert_path.display(), key_path.display(), e))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// Serves `app` over TLS on `listener` until `shutdown` resolves. Requests carry the client
+/// address as `ConnectInfo`, like the plain HTTP listener.
+pub async fn serve_tls_until(
+    listener: TcpListener,
+    app: Router,
+    tls: Arc<ServerConfig>,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<(), String> {
+    let acceptor = TlsAcceptor::from(tls);
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = &mut shutdown => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept webhook connection: {}", e);
+                    continue;
+                }
+            },
+        };
+        let (acceptor, watcher) = (acceptor.clone(), graceful.watcher());
+        let app = app.clone().layer(Extension(ConnectInfo(peer)));
+        // The handshake runs on the connection's task, so a slow client cannot stall the listener
+        tokio::spawn(async move {
+            let stream = match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => return debug!("TLS handshake with {} failed: {}", peer, e),
+                Err(_) => return debug!("TLS handshake with {} timed out", peer),
+            };
+            let builder = auto::Builder::new(TokioExecutor::new());
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), TowerToHyperService::new(app));
+            if let Err(e) = watcher.watch(connection.into_owned()).await {
+                debug!("Webhook connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+    info!("Webhook TLS listener stopped, waiting for open connections");
+    if tokio::time::timeout(SHUTDOWN_GRACE, graceful.shutdown()).await.is_err() {
+        warn!("Open webhook connections did not finish within {:?}", SHUTDOWN_GRACE);
+    }
+    Ok(())
+}
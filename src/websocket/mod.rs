@@ -11,12 +11,17 @@ use serde_json::Value;
 use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
 use std::collections::{HashMap, BTreeMap}; // For managing pending requests and sorted params
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering}; // For the connection state shared with the listener task
 use std::time::{SystemTime, UNIX_EPOCH}; // For timestamps in signed requests
 use hmac::{Hmac, Mac}; // For HMAC signing
 use sha2::Sha256; // For SHA256 hashing
 use hex::encode; // For hex encoding the signature
-use log::{info, error, debug, warn}; // For logging
+use tracing::{info, error, debug, warn}; // For logging
 use uuid::Uuid; // For generating unique request IDs
+use crate::rest_api::{BinanceApiError, DEFAULT_RECV_WINDOW_MS}; // Shared with the REST client
+
+pub mod user_data;
 
 /// Represents a generic WebSocket message received from Binance.
 /// This enum uses `untagged` to allow flexible deserialization based on message structure.
@@ -74,6 +79,9 @@ pub struct WebSocketClient {
     ws_api_request_sender: mpsc::Sender<WsApiRequest>,
     // Handle to the WebSocket API listener task (for signed requests)
     _ws_api_listener_handle: JoinHandle<()>,
+    // Whether the listener task currently holds an open WebSocket API connection
+    connected: Arc<AtomicBool>,
+    recv_window_ms: u64, // Added to every signed request that does not set its own `recvWindow`
 }
 
 impl WebSocketClient {
@@ -97,6 +105,8 @@ impl WebSocketClient {
         let ws_api_base_url_clone = ws_base_url_api.clone();
         let api_key_clone = api_key.clone();
         let secret_key_clone = secret_key.clone();
+        let connected = Arc::new(AtomicBool::new(false));
+        let connected_clone = connected.clone();
 
         // Spawn the WebSocket API listener task
         let ws_api_listener_handle = tokio::spawn(async move {
@@ -105,6 +115,7 @@ impl WebSocketClient {
                 ws_api_base_url_clone,
                 api_key_clone,
                 secret_key_clone,
+                connected_clone,
             ).await;
         });
 
@@ -114,9 +125,23 @@ impl WebSocketClient {
             ws_base_url_api,
             ws_api_request_sender,
             _ws_api_listener_handle: ws_api_listener_handle,
+            connected,
+            recv_window_ms: DEFAULT_RECV_WINDOW_MS,
         }
     }
 
+    /// Sets the `recvWindow` of signed requests (`DEFAULT_RECV_WINDOW_MS` by default). A request
+    /// passing its own `recvWindow` parameter keeps it.
+    pub fn with_recv_window(mut self, recv_window_ms: u64) -> Self {
+        self.recv_window_ms = recv_window_ms;
+        self
+    }
+
+    /// Returns true while the WebSocket API connection is open.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
     /// Generates a Binance API signature using HMAC SHA256.
     ///
     /// # Arguments
@@ -134,7 +159,8 @@ impl WebSocketClient {
     ///
     /// # Arguments
     /// * `method` - The WebSocket API method (e.g., "session.logon", "v2/account.status").
-    /// * `params` - Parameters for the method as a `serde_json::Value` object.
+    /// * `params` - Parameters for the method as a `serde_json::Value` object. On signed methods a
+    ///   `recvWindow` parameter overrides the client's.
     ///
     /// # Returns
     /// A `Result` containing the parsed JSON `Value` of the result on success, or a `String` error.
@@ -150,6 +176,10 @@ impl WebSocketClient {
                 .map_err(|e| format!("Failed to get timestamp: {}", e))?
                 .as_millis();
 
+            if let Some(map) = params.as_object_mut() {
+                map.entry("recvWindow").or_insert(Value::from(self.recv_window_ms));
+            }
+
             // Prepare parameters for signing: sort alphabetically and join
             // The `params` Value might contain numbers, which need to be converted to strings for signing.
             let mut signable_params: BTreeMap<String, String> = BTreeMap::new();
@@ -176,6 +206,12 @@ impl WebSocketClient {
             } else {
                 return Err("Params must be a JSON object for signed requests".to_string());
             }
+        } else if method.starts_with("userDataStream.") {
+            // User data stream methods are not signed but must identify the API key
+            match params.as_object_mut() {
+                Some(map) => { map.insert("apiKey".to_string(), Value::String(self.api_key.clone())); },
+                None => return Err("Params must be a JSON object for userDataStream requests".to_string()),
+            }
         }
 
         let (response_tx, response_rx) = oneshot::channel();
@@ -200,6 +236,7 @@ impl WebSocketClient {
         ws_base_url_api: String,
         api_key: String, // Cloned for use in signing if necessary within listener
         secret_key: String, // Cloned for use in signing if necessary within listener
+        connected: Arc<AtomicBool>, // Updated as the connection opens and drops
     ) {
         let mut pending_requests: HashMap<String, oneshot::Sender<Result<Value, String>>> = HashMap::new();
         let mut ws_stream_opt = None;
@@ -222,6 +259,7 @@ impl WebSocketClient {
                     Ok((ws_stream, _)) => {
                         info!("WebSocket API connection established.");
                         ws_stream_opt = Some(ws_stream);
+                        connected.store(true, Ordering::Relaxed);
                     },
                     Err(e) => {
                         error!("Failed to connect to WebSocket API: {}. Retrying in 5 seconds...", e);
@@ -285,7 +323,10 @@ impl WebSocketClient {
                                                 if json_value.get("status").and_then(|s| s.as_u64()) == Some(200) {
                                                     let _ = response_tx.send(Ok(json_value.get("result").cloned().unwrap_or_default()));
                                                 } else {
-                                                    let error_msg = json_value.get("error").and_then(|e| e.get("msg").and_then(|m| m.as_str())).unwrap_or("Unknown error").to_string();
+                                                    let error_msg = match json_value.get("error").and_then(BinanceApiError::from_value) {
+                                                        Some(api_error) => api_error.to_string(),
+                                                        None => "Unknown error".to_string(),
+                                                    };
                                                     let _ = response_tx.send(Err(format!("WebSocket API error: {}", error_msg)));
                                                 }
                                             } else {
@@ -339,6 +380,7 @@ impl WebSocketClient {
             }
             if need_reconnect {
                 ws_stream_opt = None;
+                connected.store(false, Ordering::Relaxed);
             }
             if timeout_reconnect && ws_stream_opt.is_none() {
                 warn!("WebSocket API connection not established for 60 seconds, attempting reconnect.");
@@ -0,0 +1,158 @@
+// src/websocket/user_data.rs
+
+//! This module manages the USDⓈ-M user data stream over the WebSocket API, using the
+//! `userDataStream.start/ping/stop` methods on the existing `WebSocketClient` connection instead of
+//! the REST listenKey endpoints. Together with order placement over the WebSocket API, the whole
+//! private flow (orders and user data) then runs without any REST dependency.
+//!
+//! `run_user_data_stream` obtains a listenKey, connects to the user data stream, forwards its
+//! events (e.g. `ORDER_TRADE_UPDATE`, consumed by the bracket/trailing/ladder services) as
+//! `BinanceWsMessage`s, keeps the listenKey alive, and starts a new one when it expires.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tracing::{debug, error, info, warn};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+
+use super::WebSocketClient;
+use crate::websocket_stream::BinanceWsMessage;
+
+/// A listenKey stays valid for 60 minutes after its last keepalive; Binance recommends pinging
+/// every 30 to 60 minutes.
+pub const USER_DATA_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(50 * 60);
+
+/// Delay before reconnecting after the user data stream dropped.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+fn listen_key_from(result: &Value) -> Result<String, String> {
+    result.get("listenKey")
+        .and_then(|k| k.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("Missing listenKey in userDataStream response: {}", result))
+}
+
+/// Builds the user data stream URL for a listenKey, e.g. "wss://fstream.binance.com/ws/<listenKey>".
+pub fn user_data_stream_url(ws_base_url_market_stream: &str, listen_key: &str) -> String {
+    format!("{}/{}", ws_base_url_market_stream.trim_end_matches('/'), listen_key)
+}
+
+impl WebSocketClient { // User data stream management via WebSocket API
+    /// Starts a user data stream (`userDataStream.start`).
+    /// If a stream is already active for the API key, Binance returns its listenKey and extends it.
+    ///
+    /// # Returns
+    /// A `Result` containing the listenKey on success, or a `String` error.
+    pub async fn start_user_data_stream(&self) -> Result<String, String> {
+        info!("Starting user data stream via WebSocket API...");
+        let result = self.request_websocket_api("userDataStream.start", json!({})).await?;
+        listen_key_from(&result)
+    }
+
+    /// Extends the validity of the active listenKey by 60 minutes (`userDataStream.ping`).
+    ///
+    /// # Returns
+    /// A `Result` containing the (unchanged) listenKey on success, or a `String` error.
+    pub async fn ping_user_data_stream(&self) -> Result<String, String> {
+        debug!("Sending user data stream keepalive via WebSocket API...");
+        let result = self.request_websocket_api("userDataStream.ping", json!({})).await?;
+        listen_key_from(&result)
+    }
+
+    /// Closes the user data stream (`userDataStream.stop`).
+    pub async fn stop_user_data_stream(&self) -> Result<(), String> {
+        info!("Stopping user data stream via WebSocket API...");
+        self.request_websocket_api("userDataStream.stop", json!({})).await.map(|_| ())
+    }
+}
+
+/// Runs the user data stream until `data_sender` is closed, then stops the listenKey.
+///
+/// # Arguments
+/// * `ws_client` - The WebSocket API client used for `userDataStream.*` requests.
+/// * `ws_base_url_market_stream` - The base URL of the streams (e.g. "wss://fstream.binancefuture.com/ws").
+/// * `data_sender` - Receives the user data events as `BinanceWsMessage`s.
+pub async fn run_user_data_stream(
+    ws_client: Arc<WebSocketClient>,
+    ws_base_url_market_stream: String,
+    data_sender: mpsc::Sender<BinanceWsMessage>,
+) -> Result<(), String> {
+    loop {
+        let listen_key = ws_client.start_user_data_stream().await?;
+        let url = user_data_stream_url(&ws_base_url_market_stream, &listen_key);
+        let mut ws_stream = match connect_async(&url).await {
+            Ok((ws_stream, _)) => {
+                info!("User data stream connected.");
+                ws_stream
+            },
+            Err(e) => {
+                error!("Failed to connect to user data stream: {}. Retrying in {:?}...", e, RECONNECT_DELAY);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+
+        let mut keepalive = tokio::time::interval(USER_DATA_KEEPALIVE_INTERVAL);
+        keepalive.tick().await; // The first tick completes immediately
+        loop {
+            tokio::select! {
+                _ = keepalive.tick() => {
+                    match ws_client.ping_user_data_stream().await {
+                        Ok(key) if key == listen_key => debug!("User data stream keepalive sent."),
+                        Ok(_) => {
+                            warn!("User data stream listenKey changed. Reconnecting...");
+                            break;
+                        },
+                        Err(e) => {
+                            warn!("User data stream keepalive failed: {}. Restarting stream...", e);
+                            break;
+                        }
+                    }
+                },
+                _ = data_sender.closed() => {
+                    info!("User data consumer dropped. Closing user data stream.");
+                    return ws_client.stop_user_data_stream().await;
+                },
+                msg = ws_stream.next() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => {
+                            let message = match serde_json::from_str::<BinanceWsMessage>(&text) {
+                                Ok(message) => message,
+                                Err(e) => {
+                                    error!("Failed to parse user data event: {} - {}", e, text);
+                                    continue;
+                                }
+                            };
+                            let expired = matches!(&message, BinanceWsMessage::Raw(v) if v.get("e").and_then(|e| e.as_str()) == Some("listenKeyExpired"));
+                            if data_sender.send(message).await.is_err() {
+                                info!("User data consumer dropped. Closing user data stream.");
+                                return ws_client.stop_user_data_stream().await;
+                            }
+                            if expired {
+                                warn!("User data stream listenKey expired. Restarting stream...");
+                                break;
+                            }
+                        },
+                        Some(Ok(Message::Close(close_frame))) => {
+                            info!("User data stream closed by server: {:?}", close_frame);
+                            break;
+                        },
+                        Some(Ok(_)) => {}, // Pings are answered by tungstenite
+                        Some(Err(e)) => {
+                            error!("User data stream read error: {}", e);
+                            break;
+                        },
+                        None => {
+                            info!("User data stream ended. Reconnecting...");
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
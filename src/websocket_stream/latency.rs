@@ -0,0 +1,255 @@
+// src/websocket_stream/latency.rs
+
+//! This module monitors the health of market data streams: the delay between each event's
+//! `event_time` (`E`) and its local receive time, summarized per stream over a rolling window of
+//! samples (p50/p90/p99), and the time since each subscribed stream last delivered a message, so
+//! a silent stream is noticed before stale prices cost money.
+//!
+//! Streams are identified by a key derived from the stream name (`btcusdt@depth@100ms` and
+//! `btcusdt@depth20` both become `btcusdt@depth`) or, on raw `/ws` connections where messages
+//! carry no stream name, from the event itself. All-market streams (`!forceOrder@arr`) can only be
+//! told apart on combined connections.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+/// Default number of latency samples kept per stream.
+pub const DEFAULT_LATENCY_WINDOW: usize = 1000;
+
+/// Returns the monitoring key of a stream name: the lowercased symbol and the stream type,
+/// without update speed (`@100ms`, `@1s`) or depth levels.
+pub fn stream_key(stream: &str) -> String {
+    let mut parts = stream.split('@');
+    let symbol = parts.next().unwrap_or_default().to_lowercase();
+    let kind = parts.next().unwrap_or_default();
+    let kind = if kind.starts_with("depth") { "depth" } else { kind };
+    format!("{}@{}", symbol, kind)
+}
+
+#[derive(Deserialize)]
+struct Envelope<'a> {
+    #[serde(borrow, default)]
+    stream: Option<&'a str>, // Combined stream name
+    #[serde(borrow, default)]
+    data: Option<&'a RawValue>, // Combined stream payload
+}
+
+#[derive(Deserialize)]
+struct EventHeader<'a> {
+    #[serde(rename = "e", borrow, default)]
+    event_type: Option<&'a str>,
+    #[serde(rename = "E", default)]
+    event_time: Option<u64>,
+    #[serde(rename = "s", borrow, default)]
+    symbol: Option<&'a str>,
+    #[serde(rename = "ps", borrow, default)]
+    pair: Option<&'a str>,
+    #[serde(rename = "ct", borrow, default)]
+    contract_type: Option<&'a str>,
+    #[serde(rename = "k", borrow, default)]
+    kline: Option<KlineHeader<'a>>,
+    #[serde(rename = "o", borrow, default)]
+    order: Option<OrderHeader<'a>>, // forceOrder payload
+}
+
+#[derive(Deserialize)]
+struct KlineHeader<'a> {
+    #[serde(rename = "i", borrow)]
+    interval: &'a str,
+}
+
+#[derive(Deserialize)]
+struct OrderHeader<'a> {
+    #[serde(rename = "s", borrow)]
+    symbol: &'a str,
+}
+
+impl EventHeader<'_> {
+    /// Returns the key of the stream an event of a raw connection came from.
+    fn stream_key(&self) -> Option<String> {
+        let symbol = || self.symbol.map(str::to_lowercase);
+        let pair = || self.pair.map(str::to_lowercase);
+        let interval = || self.kline.as_ref().map(|k| k.interval);
+        Some(match self.event_type? {
+            "aggTrade" => format!("{}@aggTrade", symbol()?),
+            "trade" => format!("{}@trade", symbol()?),
+            "bookTicker" => format!("{}@bookTicker", symbol()?),
+            "depthUpdate" => format!("{}@depth", symbol()?),
+            "markPriceUpdate" => format!("{}@markPrice", symbol()?),
+            "24hrTicker" => format!("{}@ticker", symbol()?),
+            "24hrMiniTicker" => format!("{}@miniTicker", symbol()?),
+            "kline" => format!("{}@kline_{}", symbol()?, interval()?),
+            "forceOrder" => format!("{}@forceOrder", self.order.as_ref()?.symbol.to_lowercase()),
+            "continuous_kline" => format!("{}_{}@continuousKline_{}", pair()?, self.contract_type?.to_lowercase(), interval()?),
+            "indexPrice_kline" => format!("{}@indexPriceKline_{}", pair()?, interval()?),
+            "markPrice_kline" => format!("{}@markPriceKline_{}", pair()?, interval()?),
+            _ => return None,
+        })
+    }
+}
+
+/// The stream key and event time of a market stream message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameInfo {
+    pub stream: String, // See `stream_key`
+    pub event_time: Option<u64>, // `E`, in milliseconds; absent on some streams (e.g. Spot bookTicker)
+}
+
+/// Reads the stream key and event time of a message, from either a raw or a combined stream,
+/// without decoding the rest of it. `None` for responses and unknown events.
+pub fn frame_info(text: &str) -> Option<FrameInfo> {
+    let envelope: Envelope = serde_json::from_str(text).ok()?;
+    match (envelope.stream, envelope.data) {
+        (Some(stream), Some(data)) => {
+            let header: EventHeader = serde_json::from_str(data.get()).ok()?;
+            Some(FrameInfo { stream: stream_key(stream), event_time: header.event_time })
+        },
+        _ => {
+            let header: EventHeader = serde_json::from_str(text).ok()?;
+            Some(FrameInfo { stream: header.stream_key()?, event_time: header.event_time })
+        },
+    }
+}
+
+/// Latency and activity of one stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamLatency {
+    pub stream: String,
+    pub messages: u64, // Messages received since monitoring started
+    pub samples: usize, // Latency samples in the rolling window
+    pub p50_ms: i64,
+    pub p90_ms: i64,
+    pub p99_ms: i64,
+    pub max_ms: i64,
+    pub last_message_ms: Option<u64>, // Local receive time of the last message
+}
+
+#[derive(Debug, Default)]
+struct StreamState {
+    samples: VecDeque<i64>, // Receive time minus event time, in milliseconds; negative with clock skew
+    messages: u64,
+    last_message_ms: Option<u64>,
+    watched_since_ms: Option<u64>, // Set while subscribed
+}
+
+/// Per-stream latency samples and last message times.
+#[derive(Debug)]
+pub struct StreamMonitor {
+    window: usize,
+    streams: HashMap<String, StreamState>,
+}
+
+impl Default for StreamMonitor {
+    fn default() -> Self {
+        Self::new(DEFAULT_LATENCY_WINDOW)
+    }
+}
+
+/// Returns the nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[i64], p: f64) -> i64 {
+    let rank = ((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
+
+impl StreamMonitor {
+    /// Creates a monitor keeping the last `window` latency samples of each stream.
+    pub fn new(window: usize) -> Self {
+        Self { window: window.max(1), streams: HashMap::new() }
+    }
+
+    /// Records a message of a stream received at `received_ms`.
+    pub fn record(&mut self, frame: &FrameInfo, received_ms: u64) {
+        let state = self.streams.entry(frame.stream.clone()).or_default();
+        state.messages += 1;
+        state.last_message_ms = Some(received_ms);
+        if let Some(event_time) = frame.event_time {
+            if state.samples.len() == self.window {
+                state.samples.pop_front();
+            }
+            state.samples.push_back(received_ms as i64 - event_time as i64);
+        }
+    }
+
+    /// Sets the subscribed streams (by name) checked by `stale_streams`. Newly subscribed streams
+    /// count as silent from `now_ms`; the others stop being checked.
+    pub fn set_watched(&mut self, streams: &[String], now_ms: u64) {
+        let keys: Vec<String> = streams.iter().map(|stream| stream_key(stream)).collect();
+        for (key, state) in self.streams.iter_mut() {
+            if !keys.contains(key) {
+                state.watched_since_ms = None;
+            }
+        }
+        for key in keys {
+            let state = self.streams.entry(key).or_default();
+            state.watched_since_ms.get_or_insert(now_ms);
+        }
+    }
+
+    /// Returns the subscribed streams without a message for at least `stale_after_ms`, with how
+    /// long they have been silent, sorted by stream.
+    pub fn stale_streams(&self, now_ms: u64, stale_after_ms: u64) -> Vec<(String, u64)> {
+        let mut stale: Vec<(String, u64)> = self.streams.iter()
+            .filter_map(|(stream, state)| {
+                let since = state.last_message_ms.unwrap_or_default().max(state.watched_since_ms?);
+                let silent_ms = now_ms.saturating_sub(since);
+                (silent_ms >= stale_after_ms).then(|| (stream.clone(), silent_ms))
+            })
+            .collect();
+        stale.sort();
+        stale
+    }
+
+    /// Returns the latency summary of every stream that delivered a message, sorted by stream.
+    pub fn summaries(&self) -> Vec<StreamLatency> {
+        let mut summaries: Vec<StreamLatency> = self.streams.iter()
+            .filter(|(_, state)| state.messages > 0)
+            .map(|(stream, state)| {
+                let mut sorted: Vec<i64> = state.samples.iter().copied().collect();
+                sorted.sort_unstable();
+                let (p50_ms, p90_ms, p99_ms, max_ms) = match sorted.last() {
+                    Some(max) => (percentile(&sorted, 0.5), percentile(&sorted, 0.9), percentile(&sorted, 0.99), *max),
+                    None => (0, 0, 0, 0),
+                };
+                StreamLatency {
+                    stream: stream.clone(),
+                    messages: state.messages,
+                    samples: sorted.len(),
+                    p50_ms,
+                    p90_ms,
+                    p99_ms,
+                    max_ms,
+                    last_message_ms: state.last_message_ms,
+                }
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.stream.cmp(&b.stream));
+        summaries
+    }
+
+    /// Renders the latency percentiles and the time since the last message of every stream in the
+    /// Prometheus text exposition format.
+    pub fn render_metrics(&self, now_ms: u64) -> String {
+        let summaries = self.summaries();
+        if summaries.is_empty() {
+            return String::new();
+        }
+        let mut out = String::new();
+        out.push_str("# HELP trading_bot_stream_latency_ms Delay between the event time and the local receive time of stream messages.\n");
+        out.push_str("# TYPE trading_bot_stream_latency_ms summary\n");
+        for s in &summaries {
+            for (quantile, value) in [("0.5", s.p50_ms), ("0.9", s.p90_ms), ("0.99", s.p99_ms)] {
+                out.push_str(&format!("trading_bot_stream_latency_ms{{stream=\"{}\",quantile=\"{}\"}} {}\n", s.stream, quantile, value));
+            }
+            out.push_str(&format!("trading_bot_stream_latency_ms_count{{stream=\"{}\"}} {}\n", s.stream, s.messages));
+        }
+        out.push_str("# HELP trading_bot_stream_last_message_age_seconds Seconds since the stream last delivered a message.\n");
+        out.push_str("# TYPE trading_bot_stream_last_message_age_seconds gauge\n");
+        for s in &summaries {
+            let age_ms = s.last_message_ms.map_or(0, |last| now_ms.saturating_sub(last));
+            out.push_str(&format!("trading_bot_stream_last_message_age_seconds{{stream=\"{}\"}} {:.3}\n", s.stream, age_ms as f64 / 1000.0));
+        }
+        out
+    }
+}
@@ -4,15 +4,29 @@
 //! market data streams (e.g., klines, aggregated trades, tickers).
 //! It handles the connection, continuous reception of stream messages,
 //! and dynamic subscription/unsubscription to streams.
+//!
+//! Clients connect either to the raw endpoint (`/ws`), where each message is the event itself, or
+//! to the combined endpoint (`/stream?streams=...`, see `MarketStreamClient::new_combined`), where
+//! each message is wrapped in `{"stream": "...", "data": {...}}`.
 
 use futures_util::{StreamExt, SinkExt};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
 use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
-use std::collections::HashMap;
-use log::{info, error, debug, warn};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{info, error, debug, warn};
+
+use crate::market_event::{parse_market_event, MarketEvent};
+use latency::{frame_info, stream_key, StreamLatency, StreamMonitor};
+use crate::market_data::KlineInterval;
+use crate::market_event::ContractType;
+use crate::streams::{AggTradeStream, KlineStream, continuous_kline_stream, force_order_stream, index_price_kline_stream, mark_price_kline_stream, mark_price_stream};
+
+pub mod latency;
 
 /// Represents a generic WebSocket message received from Binance.
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -34,6 +48,18 @@ pub enum BinanceWsMessage {
     Raw(Value),
 }
 
+impl BinanceWsMessage {
+    /// Returns the event payload of a stream message: the `data` of a combined stream message or
+    /// the message itself on a raw stream. Results and errors carry none.
+    pub fn payload(&self) -> Option<&Value> {
+        match self {
+            BinanceWsMessage::StreamData { data, .. } => Some(data),
+            BinanceWsMessage::Raw(raw) => Some(raw),
+            BinanceWsMessage::Result(_) | BinanceWsMessage::Error(_) => None,
+        }
+    }
+}
+
 /// Represents a successful subscription/unsubscription result.
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct SubscriptionResult {
@@ -41,6 +67,224 @@ pub struct SubscriptionResult {
     pub id: u64,
 }
 
+/// Maximum number of streams sent in a single SUBSCRIBE request when re-applying subscriptions.
+const MAX_STREAMS_PER_REQUEST: usize = 200;
+
+/// Capacity of the receivers returned by the typed subscription helpers.
+const TYPED_CHANNEL_CAPACITY: usize = 100;
+
+/// Decodes a stream payload and forwards it to a typed receiver; returns false once the receiver is gone.
+type TypedRoute = Box<dyn Fn(&Value) -> bool + Send>;
+
+/// Typed subscriptions by stream name (e.g. `btcusdt@aggTrade`).
+type TypedRoutes = Mutex<HashMap<String, Vec<TypedRoute>>>;
+
+/// A named set of streams subscribed for a list of symbols, e.g. "scalping":
+/// `bookTicker` + `aggTrade` for 5 symbols, or "swing": `kline_4h` for 20 symbols.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SubscriptionProfile {
+    #[serde(default)]
+    pub name: String, // Filled from the profile's key when parsed with `parse_subscription_profiles`
+    pub symbols: Vec<String>,
+    pub streams: Vec<String>, // Stream suffixes, e.g. "bookTicker", "aggTrade", "kline_4h", "depth@100ms"
+}
+
+impl SubscriptionProfile {
+    /// Expands the profile into stream names (e.g. `btcusdt@bookTicker`).
+    pub fn stream_names(&self) -> Vec<String> {
+        self.symbols.iter()
+            .flat_map(|symbol| self.streams.iter().map(move |stream| format!("{}@{}", symbol.to_lowercase(), stream)))
+            .collect()
+    }
+}
+
+/// Parses subscription profiles from a JSON object keyed by profile name:
+/// `{"scalping": {"symbols": ["BTCUSDT"], "streams": ["bookTicker", "aggTrade"]}}`.
+///
+/// # Returns
+/// The profiles sorted by name, or a `String` error if the JSON is invalid or a profile is empty.
+pub fn parse_subscription_profiles(json: &str) -> Result<Vec<SubscriptionProfile>, String> {
+    let profiles: HashMap<String, SubscriptionProfile> = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse subscription profiles: {}", e))?;
+    let mut profiles: Vec<SubscriptionProfile> = profiles.into_iter()
+        .map(|(name, profile)| SubscriptionProfile { name, ..profile })
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    if let Some(empty) = profiles.iter().find(|p| p.symbols.is_empty() || p.streams.is_empty()) {
+        return Err(format!("Subscription profile '{}' needs at least one symbol and one stream", empty.name));
+    }
+    Ok(profiles)
+}
+
+/// Builds the combined stream URL (`<root>/stream?streams=a/b/c`) for some streams from a market
+/// stream base URL, with or without its `/ws` or `/stream` path (e.g. `wss://fstream.binance.com/ws`).
+/// Without streams it is the bare `/stream` endpoint, to be filled with `subscribe`.
+pub fn combined_stream_url(ws_base_url: &str, streams: &[String]) -> String {
+    let base = ws_base_url.split('?').next().unwrap_or_default().trim_end_matches('/');
+    let root = base.strip_suffix("/ws").or_else(|| base.strip_suffix("/stream")).unwrap_or(base);
+    if streams.is_empty() {
+        format!("{}/stream", root)
+    } else {
+        format!("{}/stream?streams={}", root, streams.join("/"))
+    }
+}
+
+/// How the listener hands stream messages to a full `data_sender` channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackpressurePolicy {
+    #[default]
+    Block, // Wait for the consumer; the connection stalls (and Binance may drop it) while it lags
+    DropOldest, // Buffer up to another channel's worth of messages, dropping the oldest beyond that
+    DropNewest, // Drop incoming messages while the channel is full
+}
+
+impl BackpressurePolicy {
+    /// Returns the configuration name, e.g. `drop_oldest`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackpressurePolicy::Block => "block",
+            BackpressurePolicy::DropOldest => "drop_oldest",
+            BackpressurePolicy::DropNewest => "drop_newest",
+        }
+    }
+
+    /// Reads `MARKET_DATA_BACKPRESSURE` ("block", "drop_oldest" or "drop_newest"; "block" by default).
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("MARKET_DATA_BACKPRESSURE") {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(BackpressurePolicy::Block),
+        }
+    }
+}
+
+impl std::str::FromStr for BackpressurePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "block" | "" => Ok(BackpressurePolicy::Block),
+            "drop_oldest" => Ok(BackpressurePolicy::DropOldest),
+            "drop_newest" => Ok(BackpressurePolicy::DropNewest),
+            _ => Err(format!("Invalid backpressure policy '{}': expected 'block', 'drop_oldest' or 'drop_newest'", s)),
+        }
+    }
+}
+
+/// State shared between the client and its listener task.
+#[derive(Default)]
+struct SharedState {
+    typed_routes: TypedRoutes, // Served before normalization and `data_sender`
+    data_queue: DataQueue, // Backpressure policy and counters of `data_sender`
+    monitor: Mutex<StreamMonitor>, // Latency and last message time of every stream
+}
+
+/// Backpressure state of the `data_sender` channel.
+#[derive(Default)]
+struct DataQueue {
+    policy: Mutex<BackpressurePolicy>,
+    dropped: AtomicU64,
+    buffered: AtomicUsize, // Messages held back by `DropOldest` while the channel is full
+}
+
+/// Depth of the market data channel and messages dropped by the backpressure policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataQueueStats {
+    pub policy: BackpressurePolicy,
+    pub capacity: usize, // Capacity of the `data_sender` channel
+    pub depth: usize, // Messages waiting for the consumer, in the channel or buffered
+    pub dropped: u64,
+}
+
+impl DataQueueStats {
+    /// Renders the stats in the Prometheus text exposition format.
+    pub fn render_metrics(&self) -> String {
+        format!(
+            "# HELP trading_bot_market_data_queue_depth Market data messages waiting for the consumer.\n\
+             # TYPE trading_bot_market_data_queue_depth gauge\n\
+             trading_bot_market_data_queue_depth{{policy=\"{policy}\"}} {}\n\
+             # HELP trading_bot_market_data_queue_capacity Capacity of the market data channel.\n\
+             # TYPE trading_bot_market_data_queue_capacity gauge\n\
+             trading_bot_market_data_queue_capacity{{policy=\"{policy}\"}} {}\n\
+             # HELP trading_bot_market_data_dropped_total Market data messages dropped because the consumer lagged.\n\
+             # TYPE trading_bot_market_data_dropped_total counter\n\
+             trading_bot_market_data_dropped_total{{policy=\"{policy}\"}} {}\n",
+            self.depth, self.capacity, self.dropped, policy = self.policy.as_str()
+        )
+    }
+}
+
+/// Hands a message to the consumer according to the backpressure policy.
+///
+/// # Returns
+/// An `Err` if the consumer dropped its receiver.
+async fn forward_data(
+    data_sender: &mpsc::Sender<BinanceWsMessage>,
+    queue: &DataQueue,
+    buffer: &mut VecDeque<BinanceWsMessage>,
+    message: BinanceWsMessage,
+) -> Result<(), String> {
+    let policy = *queue.policy.lock().unwrap();
+    let message = match policy {
+        BackpressurePolicy::Block => return data_sender.send(message).await.map_err(|e| e.to_string()),
+        // Buffered messages go first to keep the order
+        BackpressurePolicy::DropOldest if !buffer.is_empty() => message,
+        _ => match data_sender.try_send(message) {
+            Ok(()) => return Ok(()),
+            Err(mpsc::error::TrySendError::Closed(_)) => return Err("channel closed".to_string()),
+            Err(mpsc::error::TrySendError::Full(message)) => message,
+        },
+    };
+    if policy == BackpressurePolicy::DropNewest {
+        queue.dropped.fetch_add(1, Ordering::Relaxed);
+        return Ok(());
+    }
+    if buffer.len() >= data_sender.max_capacity() {
+        buffer.pop_front();
+        queue.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+    buffer.push_back(message);
+    queue.buffered.store(buffer.len(), Ordering::Relaxed);
+    Ok(())
+}
+
+/// Delivers a stream message to the typed subscriptions of its stream (by `latency::stream_key`).
+///
+/// # Returns
+/// `true` if at least one typed receiver took the message, which is then not forwarded elsewhere.
+fn route_typed(routes: &TypedRoutes, stream: &str, text: &str) -> bool {
+    let mut routes = routes.lock().unwrap();
+    let Some(forwards) = routes.get_mut(stream) else {
+        return false;
+    };
+    let Some(payload) = serde_json::from_str::<BinanceWsMessage>(text).ok().and_then(|message| message.payload().cloned()) else {
+        return false;
+    };
+    forwards.retain(|forward| forward(&payload));
+    if forwards.is_empty() {
+        routes.remove(stream);
+        return false;
+    }
+    true
+}
+
+/// Sets the subscribed streams checked for staleness. All-market streams (`!forceOrder@arr`) are
+/// only checked on combined connections, where their messages can be attributed to them.
+fn watch_streams(monitor: &Mutex<StreamMonitor>, active_streams: &[String], combined: bool) {
+    let watched: Vec<String> = active_streams.iter().filter(|stream| combined || !stream.starts_with('!')).cloned().collect();
+    monitor.lock().unwrap().set_watched(&watched, now_ms());
+}
+
+/// Returns the current time in milliseconds, the receive time of stream messages.
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+/// Returns the next request ID for stream management messages.
+fn next_request_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_ID.fetch_add(1, Ordering::SeqCst)
+}
+
 /// Represents an error message from the WebSocket server.
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct WsError {
@@ -96,6 +340,8 @@ pub struct MarketStreamClient {
     _ws_stream_listener_handle: JoinHandle<()>,
     // Sender for parsed stream data to the consumer
     data_sender: mpsc::Sender<BinanceWsMessage>,
+    // Typed subscription receivers and backpressure state, shared with the listener task
+    shared: Arc<SharedState>,
 }
 
 impl MarketStreamClient {
@@ -110,17 +356,102 @@ impl MarketStreamClient {
     pub async fn new(
         ws_base_url_market_stream: String,
         data_sender: mpsc::Sender<BinanceWsMessage>,
+    ) -> Self {
+        Self::spawn(ws_base_url_market_stream, data_sender, None, Vec::new(), false)
+    }
+
+    /// Creates a new `MarketStreamClient` that emits normalized market data.
+    ///
+    /// Supported market events (trades, bookTicker, depth, klines, mark price) are decoded directly
+    /// from the frame text and sent to `event_sender` as `MarketEvent`s, without building a
+    /// `serde_json::Value`. Anything else is still forwarded to `data_sender` as a `BinanceWsMessage`.
+    ///
+    /// # Arguments
+    /// * `ws_base_url_market_stream` - The base URL for public market data WebSocket streams.
+    /// * `data_sender` - Receives messages that are not normalized market events.
+    /// * `event_sender` - Receives normalized `MarketEvent`s.
+    ///
+    /// # Returns
+    /// A new `MarketStreamClient` instance.
+    pub async fn new_normalized(
+        ws_base_url_market_stream: String,
+        data_sender: mpsc::Sender<BinanceWsMessage>,
+        event_sender: mpsc::Sender<MarketEvent>,
+    ) -> Self {
+        Self::spawn(ws_base_url_market_stream, data_sender, Some(event_sender), Vec::new(), false)
+    }
+
+    /// Creates a new `MarketStreamClient` subscribed to the streams of `profiles`.
+    ///
+    /// The profiles are applied as soon as the connection is established, and all active
+    /// subscriptions (including ones added later with `subscribe`) are re-applied on every reconnect.
+    ///
+    /// # Arguments
+    /// * `ws_base_url_market_stream` - The base URL for public market data WebSocket streams.
+    /// * `data_sender` - Receives stream messages (those not normalized, if `event_sender` is set).
+    /// * `event_sender` - Optional. Receives normalized `MarketEvent`s, see `new_normalized`.
+    /// * `profiles` - The subscription profiles to apply.
+    ///
+    /// # Returns
+    /// A new `MarketStreamClient` instance.
+    pub async fn new_with_profiles(
+        ws_base_url_market_stream: String,
+        data_sender: mpsc::Sender<BinanceWsMessage>,
+        event_sender: Option<mpsc::Sender<MarketEvent>>,
+        profiles: &[SubscriptionProfile],
+    ) -> Self {
+        let streams = profiles.iter().flat_map(|p| p.stream_names()).collect();
+        Self::spawn(ws_base_url_market_stream, data_sender, event_sender, streams, false)
+    }
+
+    /// Creates a new `MarketStreamClient` connected to the combined stream endpoint.
+    ///
+    /// The connection URL (`/stream?streams=a/b/c`) is built from `streams` with
+    /// `combined_stream_url`; on reconnect it is rebuilt from the active subscriptions, including
+    /// ones added later with `subscribe`. Messages arrive wrapped in `{"stream": ..., "data": ...}`
+    /// and are forwarded as `BinanceWsMessage::StreamData`, or decoded to `MarketEvent`s when
+    /// `event_sender` is set.
+    ///
+    /// # Arguments
+    /// * `ws_base_url_market_stream` - The base URL for public market data WebSocket streams (e.g., "wss://fstream.binancefuture.com/ws").
+    /// * `data_sender` - Receives stream messages (those not normalized, if `event_sender` is set).
+    /// * `event_sender` - Optional. Receives normalized `MarketEvent`s, see `new_normalized`.
+    /// * `streams` - The initial stream names (e.g., `["btcusdt@aggTrade", "ethusdt@kline_1m"]`).
+    ///
+    /// # Returns
+    /// A new `MarketStreamClient` instance.
+    pub async fn new_combined(
+        ws_base_url_market_stream: String,
+        data_sender: mpsc::Sender<BinanceWsMessage>,
+        event_sender: Option<mpsc::Sender<MarketEvent>>,
+        streams: Vec<String>,
+    ) -> Self {
+        Self::spawn(ws_base_url_market_stream, data_sender, event_sender, streams, true)
+    }
+
+    fn spawn(
+        ws_base_url_market_stream: String,
+        data_sender: mpsc::Sender<BinanceWsMessage>,
+        event_sender: Option<mpsc::Sender<MarketEvent>>,
+        initial_streams: Vec<String>,
+        combined: bool,
     ) -> Self {
         let (ws_stream_request_sender, ws_stream_request_receiver) = mpsc::channel::<WsStreamRequest>(100);
 
         let ws_base_url_clone = ws_base_url_market_stream.clone();
         let data_sender_clone = data_sender.clone();
+        let shared: Arc<SharedState> = Arc::default();
+        let shared_clone = shared.clone();
 
         let ws_stream_listener_handle = tokio::spawn(async move {
             Self::run_market_stream_listener(
                 ws_stream_request_receiver,
                 ws_base_url_clone,
                 data_sender_clone,
+                event_sender,
+                initial_streams,
+                combined,
+                shared_clone,
             ).await;
         });
 
@@ -129,6 +460,7 @@ impl MarketStreamClient {
             ws_stream_request_sender,
             _ws_stream_listener_handle: ws_stream_listener_handle,
             data_sender,
+            shared,
         }
     }
 
@@ -138,22 +470,68 @@ impl MarketStreamClient {
         mut ws_request_receiver: mpsc::Receiver<WsStreamRequest>,
         ws_base_url_market_stream: String,
         data_sender: mpsc::Sender<BinanceWsMessage>, // To send parsed stream data out
+        event_sender: Option<mpsc::Sender<MarketEvent>>, // To send normalized market events out, if enabled
+        initial_streams: Vec<String>, // Streams from subscription profiles, applied on every (re)connect
+        combined: bool, // Connect to `/stream?streams=...` with the active streams instead of subscribing to them
+        shared: Arc<SharedState>, // Typed subscriptions and the backpressure policy of `data_sender`
     ) {
         let mut pending_requests: HashMap<u64, oneshot::Sender<Result<Value, String>>> = HashMap::new();
         let mut ws_stream_opt = None;
+        // Active subscriptions, kept in subscription order and re-applied on reconnect
+        let mut active_streams: Vec<String> = Vec::new();
+        for stream in initial_streams {
+            if !active_streams.contains(&stream) {
+                active_streams.push(stream);
+            }
+        }
+        watch_streams(&shared.monitor, &active_streams, combined);
+        // Subscribe/unsubscribe requests awaiting confirmation: (is_subscribe, streams)
+        let mut pending_stream_changes: HashMap<u64, (bool, Vec<String>)> = HashMap::new();
+        // IDs of the SUBSCRIBE requests sent to re-apply `active_streams`
+        let mut resubscribe_ids: Vec<u64> = Vec::new();
+        // Messages held back by the `DropOldest` policy until the consumer catches up
+        let mut buffer: VecDeque<BinanceWsMessage> = VecDeque::new();
         // `next_request_id` is managed by `get_next_request_id` now, no need for it here.
 
         loop {
             // Reconnect if stream is not established or disconnected
             if ws_stream_opt.is_none() {
-                info!("Attempting to connect to Market Stream at {}", ws_base_url_market_stream);
-                match connect_async(&ws_base_url_market_stream).await {
-                    Ok((ws_stream, _)) => {
+                let url = if combined {
+                    combined_stream_url(&ws_base_url_market_stream, &active_streams)
+                } else {
+                    ws_base_url_market_stream.clone()
+                };
+                info!("Attempting to connect to Market Stream at {}", url);
+                match connect_async(&url).await {
+                    Ok((mut ws_stream, _)) => {
                         info!("Market Stream connection established.");
+                        // Re-apply all active subscriptions (profiles and earlier `subscribe` calls);
+                        // a combined stream URL already carries them
+                        let mut resubscribed = true;
+                        let to_resubscribe: &[String] = if combined { &[] } else { &active_streams };
+                        for chunk in to_resubscribe.chunks(MAX_STREAMS_PER_REQUEST) {
+                            let id = next_request_id();
+                            let payload = json!({
+                                "method": "SUBSCRIBE",
+                                "params": chunk,
+                                "id": id,
+                            }).to_string();
+                            if let Err(e) = ws_stream.send(Message::Text(payload.into())).await {
+                                error!("Failed to re-apply Market Stream subscriptions: {}", e);
+                                resubscribed = false;
+                                break;
+                            }
+                            resubscribe_ids.push(id);
+                        }
+                        if !resubscribed {
+                            resubscribe_ids.clear();
+                            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                            continue;
+                        }
+                        if !to_resubscribe.is_empty() {
+                            info!("Re-applying {} Market Stream subscriptions.", active_streams.len());
+                        }
                         ws_stream_opt = Some(ws_stream);
-                        // On reconnection, resubscribe to all active streams if managing state
-                        // For simplicity, this example doesn't persist active subscriptions across reconnects.
-                        // A more robust solution would store `streams` from `Subscribe` requests.
                     },
                     Err(e) => {
                         error!("Failed to connect to Market Stream: {}. Retrying in 5 seconds...", e);
@@ -174,6 +552,7 @@ impl MarketStreamClient {
                         if let Some(ws_req) = req {
                             let (id, message_text, response_tx_opt) = match ws_req {
                                 WsStreamRequest::Subscribe { id, streams, response_tx } => {
+                                    pending_stream_changes.insert(id, (true, streams.clone()));
                                     let payload = json!({
                                         "method": "SUBSCRIBE",
                                         "params": streams,
@@ -182,6 +561,7 @@ impl MarketStreamClient {
                                     (id, payload, Some(response_tx))
                                 },
                                 WsStreamRequest::Unsubscribe { id, streams, response_tx } => {
+                                    pending_stream_changes.insert(id, (false, streams.clone()));
                                     let payload = json!({
                                         "method": "UNSUBSCRIBE",
                                         "params": streams,
@@ -244,45 +624,85 @@ impl MarketStreamClient {
                         match msg {
                             Some(Ok(Message::Text(text))) => {
                                 debug!("Received Market Stream message: {}", text);
-                                match serde_json::from_str::<BinanceWsMessage>(&text) {
-                                    Ok(parsed_msg) => {
-                                        match parsed_msg {
-                                            BinanceWsMessage::Result(res) => {
-                                                if let Some(response_tx) = pending_requests.remove(&res.id) {
-                                                    let _ = response_tx.send(Ok(res.result.unwrap_or_default()));
-                                                } else {
-                                                    warn!("Received unmatched SubscriptionResult (ID: {}): {:#?}", res.id, res);
-                                                }
-                                            },
-                                            BinanceWsMessage::Error(err) => {
-                                                if let Some(id) = err.id {
-                                                    if let Some(response_tx) = pending_requests.remove(&id) {
-                                                        let _ = response_tx.send(Err(format!("Market Stream Error (ID: {}): {}", id, err.msg)));
+                                let frame = frame_info(&text);
+                                if let Some(frame) = &frame {
+                                    shared.monitor.lock().unwrap().record(frame, now_ms());
+                                }
+                                // Streams of typed subscriptions go to their own receivers only
+                                let typed = frame.is_some_and(|frame| route_typed(&shared.typed_routes, &frame.stream, &text));
+                                // Normalized market events skip the generic JSON path entirely
+                                let normalized = match event_sender.as_ref() {
+                                    Some(event_sender) if !typed => match parse_market_event(&text) {
+                                        Ok(event) => event.map(|event| (event_sender, event)),
+                                        Err(e) => {
+                                            warn!("Failed to normalize Market Stream message: {}", e);
+                                            None
+                                        },
+                                    },
+                                    _ => None,
+                                };
+                                if let Some((event_sender, event)) = normalized {
+                                    if let Err(e) = event_sender.send(event).await {
+                                        error!("Failed to send market event to consumer: {}", e);
+                                        need_reconnect = true;
+                                    }
+                                } else if !typed {
+                                    match serde_json::from_str::<BinanceWsMessage>(&text) {
+                                        Ok(parsed_msg) => {
+                                            match parsed_msg {
+                                                BinanceWsMessage::Result(res) => {
+                                                    // Track confirmed subscription changes so they survive reconnects
+                                                    if let Some((is_subscribe, streams)) = pending_stream_changes.remove(&res.id) {
+                                                        if is_subscribe {
+                                                            for stream in streams {
+                                                                if !active_streams.contains(&stream) {
+                                                                    active_streams.push(stream);
+                                                                }
+                                                            }
+                                                        } else {
+                                                            active_streams.retain(|s| !streams.contains(s));
+                                                        }
+                                                        watch_streams(&shared.monitor, &active_streams, combined);
+                                                    }
+                                                    if let Some(position) = resubscribe_ids.iter().position(|id| *id == res.id) {
+                                                        resubscribe_ids.remove(position);
+                                                        debug!("Market Stream subscriptions re-applied (ID: {})", res.id);
+                                                    } else if let Some(response_tx) = pending_requests.remove(&res.id) {
+                                                        let _ = response_tx.send(Ok(res.result.unwrap_or_default()));
                                                     } else {
-                                                        error!("Received unmatched WsError (ID: {}): {:#?}", id, err);
+                                                        warn!("Received unmatched SubscriptionResult (ID: {}): {:#?}", res.id, res);
+                                                    }
+                                                },
+                                                BinanceWsMessage::Error(err) => {
+                                                    if let Some(id) = err.id {
+                                                        if let Some(response_tx) = pending_requests.remove(&id) {
+                                                            let _ = response_tx.send(Err(format!("Market Stream Error (ID: {}): {}", id, err.msg)));
+                                                        } else {
+                                                            error!("Received unmatched WsError (ID: {}): {:#?}", id, err);
+                                                        }
+                                                    } else {
+                                                        error!("Received WsError without ID: {:#?}", err);
+                                                    }
+                                                },
+                                                // For actual stream data, send it to the consumer
+                                                BinanceWsMessage::StreamData { stream, data } => {
+                                                    if let Err(e) = forward_data(&data_sender, &shared.data_queue, &mut buffer, BinanceWsMessage::StreamData { stream, data }).await {
+                                                        error!("Failed to send stream data to consumer: {}", e);
+                                                        // If consumer channel is closed, we might want to exit or reconnect
+                                                        need_reconnect = true; // Consider consumer drop as a reason to reconnect or stop
+                                                    }
+                                                },
+                                                BinanceWsMessage::Raw(raw_val) => {
+                                                    // Handle raw unparsed messages, potentially send to consumer if generic handling is desired
+                                                    if let Err(e) = forward_data(&data_sender, &shared.data_queue, &mut buffer, BinanceWsMessage::Raw(raw_val)).await {
+                                                        error!("Failed to send raw stream data to consumer: {}", e);
+                                                        need_reconnect = true;
                                                     }
-                                                } else {
-                                                    error!("Received WsError without ID: {:#?}", err);
-                                                }
-                                            },
-                                            // For actual stream data, send it to the consumer
-                                            BinanceWsMessage::StreamData { stream, data } => {
-                                                if let Err(e) = data_sender.send(BinanceWsMessage::StreamData { stream, data }).await {
-                                                    error!("Failed to send stream data to consumer: {}", e);
-                                                    // If consumer channel is closed, we might want to exit or reconnect
-                                                    need_reconnect = true; // Consider consumer drop as a reason to reconnect or stop
-                                                }
-                                            },
-                                            BinanceWsMessage::Raw(raw_val) => {
-                                                // Handle raw unparsed messages, potentially send to consumer if generic handling is desired
-                                                if let Err(e) = data_sender.send(BinanceWsMessage::Raw(raw_val)).await {
-                                                    error!("Failed to send raw stream data to consumer: {}", e);
-                                                    need_reconnect = true;
                                                 }
                                             }
-                                        }
-                                    },
-                                    Err(e) => error!("Failed to parse Market Stream message as BinanceWsMessage: {} from text: {}", e, text),
+                                        },
+                                        Err(e) => error!("Failed to parse Market Stream message as BinanceWsMessage: {} from text: {}", e, text),
+                                    }
                                 }
                             },
                             Some(Ok(Message::Binary(_))) => {
@@ -312,6 +732,22 @@ impl MarketStreamClient {
                             },
                         }
                     },
+                    // Hand messages buffered by the `DropOldest` policy to the consumer as it catches up
+                    permit = data_sender.reserve(), if !buffer.is_empty() => {
+                        match permit {
+                            Ok(permit) => {
+                                if let Some(message) = buffer.pop_front() {
+                                    permit.send(message);
+                                }
+                                shared.data_queue.buffered.store(buffer.len(), Ordering::Relaxed);
+                            },
+                            Err(e) => {
+                                error!("Failed to send buffered stream data to consumer: {}", e);
+                                buffer.clear();
+                                need_reconnect = true;
+                            },
+                        }
+                    },
                     // Add a timeout for connection re-establishment or inactivity
                     _ = tokio::time::sleep(tokio::time::Duration::from_secs(60)) => {
                         warn!("Market Stream connection inactive for 60 seconds, attempting reconnect.");
@@ -325,6 +761,8 @@ impl MarketStreamClient {
                 for (_, tx) in pending_requests.drain() {
                     let _ = tx.send(Err("WebSocket connection lost during request.".to_string()));
                 }
+                pending_stream_changes.clear();
+                resubscribe_ids.clear();
             }
         }
     }
@@ -406,11 +844,176 @@ impl MarketStreamClient {
         self.send_stream_request(WsStreamRequest::GetProperty { id, property: property.to_string(), response_tx: oneshot::channel().0 }).await
     }
 
+    /// Subscribes to the mark price and funding rate streams of some symbols. Their
+    /// `markPriceUpdate` messages decode to `MarketEvent::MarkPrice`.
+    ///
+    /// # Arguments
+    /// * `symbols` - The trading pair symbols (e.g., `["BTCUSDT", "ETHUSDT"]`).
+    /// * `every_second` - Updates every second instead of every 3 seconds.
+    ///
+    /// # Returns
+    /// A `Result` containing the API response `Value` on success, or a `String` error.
+    pub async fn subscribe_mark_price(&self, symbols: &[&str], every_second: bool) -> Result<Value, String> {
+        self.subscribe(symbols.iter().map(|symbol| mark_price_stream(symbol, every_second)).collect()).await
+    }
+
+    /// Subscribes to the forced liquidation streams of some symbols, or of every symbol when
+    /// `symbols` is empty. Their `forceOrder` messages decode to `MarketEvent::Liquidation`.
+    ///
+    /// # Arguments
+    /// * `symbols` - The trading pair symbols (e.g., `["BTCUSDT"]`), or none for the whole market.
+    ///
+    /// # Returns
+    /// A `Result` containing the API response `Value` on success, or a `String` error.
+    pub async fn subscribe_liquidations(&self, symbols: &[&str]) -> Result<Value, String> {
+        let streams = match symbols {
+            [] => vec![force_order_stream(None)],
+            symbols => symbols.iter().map(|symbol| force_order_stream(Some(symbol))).collect(),
+        };
+        self.subscribe(streams).await
+    }
+
+    /// Subscribes to the continuous contract kline streams of some pairs. Their `continuous_kline`
+    /// messages decode to `MarketEvent::PriceKline` with a `KlineSource::Continuous` source.
+    ///
+    /// # Arguments
+    /// * `pairs` - The underlying pairs (e.g., `["BTCUSDT"]`).
+    /// * `contract_type` - The contract delivery type to follow across rollovers.
+    /// * `interval` - The candle interval.
+    ///
+    /// # Returns
+    /// A `Result` containing the API response `Value` on success, or a `String` error.
+    pub async fn subscribe_continuous_klines(&self, pairs: &[&str], contract_type: ContractType, interval: KlineInterval) -> Result<Value, String> {
+        self.subscribe(pairs.iter().map(|pair| continuous_kline_stream(pair, contract_type, interval)).collect()).await
+    }
+
+    /// Subscribes to the index price kline streams of some pairs. Their `indexPrice_kline` messages
+    /// decode to `MarketEvent::PriceKline` with a `KlineSource::IndexPrice` source.
+    ///
+    /// # Arguments
+    /// * `pairs` - The underlying pairs (e.g., `["BTCUSDT"]`).
+    /// * `interval` - The candle interval.
+    ///
+    /// # Returns
+    /// A `Result` containing the API response `Value` on success, or a `String` error.
+    pub async fn subscribe_index_price_klines(&self, pairs: &[&str], interval: KlineInterval) -> Result<Value, String> {
+        self.subscribe(pairs.iter().map(|pair| index_price_kline_stream(pair, interval)).collect()).await
+    }
+
+    /// Subscribes to the mark price kline streams of some symbols. Their `markPrice_kline` messages
+    /// decode to `MarketEvent::PriceKline` with a `KlineSource::MarkPrice` source.
+    ///
+    /// # Arguments
+    /// * `symbols` - The trading pair symbols (e.g., `["BTCUSDT"]`).
+    /// * `interval` - The candle interval.
+    ///
+    /// # Returns
+    /// A `Result` containing the API response `Value` on success, or a `String` error.
+    pub async fn subscribe_mark_price_klines(&self, symbols: &[&str], interval: KlineInterval) -> Result<Value, String> {
+        self.subscribe(symbols.iter().map(|symbol| mark_price_kline_stream(symbol, interval)).collect()).await
+    }
+
+    /// Subscribes to a stream and returns a dedicated receiver of its messages decoded as `T`.
+    ///
+    /// Messages of the stream are delivered only to typed receivers (not to `data_sender` or as
+    /// `MarketEvent`s) while at least one is alive. When a receiver is full, messages are dropped
+    /// with a warning rather than stalling the connection.
+    ///
+    /// # Arguments
+    /// * `stream` - The stream name (e.g., "btcusdt@aggTrade"). Raw `/ws` connections can only
+    ///   route the per-symbol streams known to `latency::frame_info`; combined connections route any stream.
+    ///
+    /// # Returns
+    /// A `Result` containing the receiver on success, or a `String` error.
+    pub async fn subscribe_typed<T: DeserializeOwned + Send + 'static>(&self, stream: String) -> Result<mpsc::Receiver<T>, String> {
+        let (sender, receiver) = mpsc::channel::<T>(TYPED_CHANNEL_CAPACITY);
+        let name = stream.clone();
+        let forward: TypedRoute = Box::new(move |payload: &Value| {
+            match serde_json::from_value::<T>(payload.clone()) {
+                Ok(message) => match sender.try_send(message) {
+                    Ok(()) => true,
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        warn!("Receiver of {} is full, dropping message", name);
+                        true
+                    },
+                    Err(mpsc::error::TrySendError::Closed(_)) => false,
+                },
+                Err(e) => {
+                    warn!("Failed to decode {} message: {}", name, e);
+                    !sender.is_closed()
+                },
+            }
+        });
+        self.shared.typed_routes.lock().unwrap().entry(stream_key(&stream)).or_default().push(forward);
+        // On failure the receiver is dropped, and its route with it on the next message
+        self.subscribe(vec![stream]).await?;
+        Ok(receiver)
+    }
+
+    /// Subscribes to the kline stream of a symbol (`<symbol>@kline_<interval>`).
+    ///
+    /// # Arguments
+    /// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
+    /// * `interval` - The candle interval.
+    ///
+    /// # Returns
+    /// A `Result` containing a receiver of `KlineStream` messages, or a `String` error.
+    pub async fn subscribe_klines(&self, symbol: &str, interval: KlineInterval) -> Result<mpsc::Receiver<KlineStream>, String> {
+        self.subscribe_typed(format!("{}@kline_{}", symbol.to_lowercase(), interval.to_string())).await
+    }
+
+    /// Subscribes to the aggregated trade stream of a symbol (`<symbol>@aggTrade`).
+    ///
+    /// # Arguments
+    /// * `symbol` - The trading pair symbol (e.g., "BTCUSDT").
+    ///
+    /// # Returns
+    /// A `Result` containing a receiver of `AggTradeStream` messages, or a `String` error.
+    pub async fn subscribe_agg_trades(&self, symbol: &str) -> Result<mpsc::Receiver<AggTradeStream>, String> {
+        self.subscribe_typed(format!("{}@aggTrade", symbol.to_lowercase())).await
+    }
+
+    /// Subscribes to all streams of a subscription profile. They are re-applied on reconnect.
+    pub async fn apply_profile(&self, profile: &SubscriptionProfile) -> Result<Value, String> {
+        self.subscribe(profile.stream_names()).await
+    }
+
+    /// Sets how messages are handed to a full `data_sender` channel (see `BackpressurePolicy`).
+    /// Typed subscriptions and normalized events are not affected.
+    pub fn set_backpressure_policy(&self, policy: BackpressurePolicy) {
+        *self.shared.data_queue.policy.lock().unwrap() = policy;
+    }
+
+    /// Returns the depth of the `data_sender` channel and the messages dropped so far.
+    pub fn data_queue_stats(&self) -> DataQueueStats {
+        let capacity = self.data_sender.max_capacity();
+        DataQueueStats {
+            policy: *self.shared.data_queue.policy.lock().unwrap(),
+            capacity,
+            depth: capacity - self.data_sender.capacity() + self.shared.data_queue.buffered.load(Ordering::Relaxed),
+            dropped: self.shared.data_queue.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the latency summary (event time to receive time) of every stream that delivered a message.
+    pub fn stream_latencies(&self) -> Vec<StreamLatency> {
+        self.shared.monitor.lock().unwrap().summaries()
+    }
+
+    /// Returns the subscribed streams without a message for at least `stale_after`, with how long
+    /// (in milliseconds) they have been silent.
+    pub fn stale_streams(&self, stale_after: std::time::Duration) -> Vec<(String, u64)> {
+        self.shared.monitor.lock().unwrap().stale_streams(now_ms(), stale_after.as_millis() as u64)
+    }
+
+    /// Renders the stream latencies and the `data_sender` queue in the Prometheus text exposition format.
+    pub fn render_metrics(&self) -> String {
+        let latencies = self.shared.monitor.lock().unwrap().render_metrics(now_ms());
+        self.data_queue_stats().render_metrics() + &latencies
+    }
+
     // Internal counter for generating unique request IDs for stream management
-    // Note: This is a simplified approach. For production, consider an AtomicU64.
     fn get_next_request_id(&self) -> u64 {
-        use std::sync::atomic::{AtomicU64, Ordering};
-        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
-        NEXT_ID.fetch_add(1, Ordering::SeqCst)
+        next_request_id()
     }
 }
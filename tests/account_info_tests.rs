@@ -0,0 +1,29 @@
+// tests/account_info_tests.rs
+
+//! This file contains offline tests for the account configuration and rate limit types.
+
+use serde_json::json;
+use trading_bot::account_info::*;
+
+#[test]
+fn test_parse_account_config_and_rate_limits() {
+    let config: AccountConfig = serde_json::from_value(json!({
+        "feeTier": 2, "canTrade": true, "canDeposit": true, "canWithdraw": false,
+        "dualSidePosition": true, "updateTime": 0, "multiAssetsMargin": false, "tradeGroupId": -1
+    })).unwrap();
+    assert_eq!(config.fee_tier, 2);
+    assert!(config.dual_side_position);
+
+    let limits: Vec<OrderRateLimit> = serde_json::from_value(json!([
+        {"rateLimitType": "ORDERS", "interval": "SECOND", "intervalNum": 10, "limit": 300},
+        {"rateLimitType": "ORDERS", "interval": "MINUTE", "intervalNum": 1, "limit": 1200}
+    ])).unwrap();
+    assert_eq!(limits[0].window_secs(), 10);
+    assert_eq!(limits[1].window_secs(), 60);
+
+    let diagnostics = AccountDiagnostics { account_config: Some(config), order_rate_limits: limits };
+    let metrics = diagnostics.render_metrics();
+    assert!(metrics.contains("trading_bot_account_fee_tier 2"));
+    assert!(metrics.contains("trading_bot_account_can_trade 1"));
+    assert!(metrics.contains("trading_bot_order_rate_limit{window_seconds=\"10\"} 300"));
+}
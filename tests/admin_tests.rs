@@ -0,0 +1,167 @@
+// tests/admin_tests.rs
+
+//! This file contains tests for the admin API: its token check and the summaries it reports.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::http::StatusCode;
+use axum::{middleware, routing::get, Router};
+use serde_json::json;
+use trading_bot::account_info::AccountInfo;
+use trading_bot::admin::*;
+use trading_bot::arming::Interlock;
+use trading_bot::order::Order;
+use trading_bot::rest_api::RestClient;
+use trading_bot::risk::{DailyLossBreaker, ExecutionPolicies};
+use trading_bot::webhook::allowlist::IpAllowlist;
+use trading_bot::webhook::control::check_loss_breaker;
+use trading_bot::webhook::queue::SignalTracker;
+use trading_bot::webhook::symbols::SymbolConfigs;
+use trading_bot::webhook::AppState;
+use trading_bot::websocket::WebSocketClient;
+
+/// State of a bot that is not connected to an exchange; enough for the arming and breaker paths.
+async fn offline_state(interlock: Arc<Interlock>, policies: ExecutionPolicies) -> AppState {
+    AppState {
+        ws_client: Arc::new(WebSocketClient::new("key".to_string(), "secret".to_string(), "ws://127.0.0.1:9".to_string()).await),
+        rest_client: Arc::new(RestClient::new("key".to_string(), "secret".to_string(), "http://127.0.0.1:9".to_string())),
+        experiment: None,
+        policies: Arc::new(policies),
+        event_log: None,
+        storage: None,
+        interlock: Some(interlock),
+        webhook_secret: None,
+        ip_allowlist: None,
+        admin_token: None,
+        admin_allowlist: None,
+        symbol_configs: Arc::new(SymbolConfigs::default()),
+        strategies: Arc::new(HashMap::new()),
+        deduplicator: None,
+        rate_limiter: None,
+        notifications: None,
+        signals: Arc::new(SignalTracker::new()),
+        signal_queue: None,
+        started: Instant::now(),
+    }
+}
+
+fn account(unrealized_pnl: &str) -> AccountInfo {
+    serde_json::from_value(json!({
+        "totalInitialMargin": "0", "totalMaintMargin": "0", "totalWalletBalance": "1000",
+        "totalUnrealizedProfit": unrealized_pnl, "totalMarginBalance": "1000", "totalPositionInitialMargin": "0",
+        "totalOpenOrderInitialMargin": "0", "totalCrossWalletBalance": "1000", "totalCrossUnPnl": unrealized_pnl,
+        "availableBalance": "1000", "maxWithdrawAmount": "1000", "assets": [], "positions": []
+    })).unwrap()
+}
+
+#[tokio::test]
+async fn test_admin_requests_need_the_admin_token() {
+    let app = Router::new()
+        .route("/admin/positions", get(|| async { "[]" }))
+        .route_layer(middleware::from_fn_with_state(std::sync::Arc::<str>::from("admin-token"), require_token));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let client = reqwest::Client::new();
+    let send = |authorization: Option<&'static str>| {
+        let mut request = client.get(format!("http://{}/admin/positions", address));
+        if let Some(value) = authorization {
+            request = request.header("Authorization", value);
+        }
+        request.send()
+    };
+    assert_eq!(send(Some("Bearer admin-token")).await.unwrap().status(), StatusCode::OK);
+    assert_eq!(send(Some("Bearer tv-shared-secret")).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(send(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn test_open_orders_are_summarized() {
+    let order: Order = serde_json::from_value(json!({
+        "symbol": "BTCUSDT", "orderId": 42, "clientOrderId": "whb1sl", "price": "0", "origQty": "0.010",
+        "executedQty": "0", "cumQuote": "0", "status": "NEW", "timeInForce": "GTC", "type": "STOP_MARKET",
+        "side": "SELL", "stopPrice": "58000", "time": 1714564800000u64, "updateTime": 1714564800000u64,
+        "avgPrice": "0", "closePosition": false, "goodTillDate": 0, "origType": "STOP_MARKET",
+        "positionSide": "BOTH", "priceMatch": "NONE", "priceProtect": false, "reduceOnly": true,
+        "selfTradePreventionMode": "NONE", "workingType": "MARK_PRICE"
+    })).unwrap();
+    let summary = &summarize_orders(&[order])[0];
+    assert_eq!((summary.symbol.as_str(), summary.order_id, summary.order_type.as_str()), ("BTCUSDT", 42, "STOP_MARKET"));
+    assert_eq!((summary.quantity, summary.stop_price, summary.reduce_only), (0.01, 58000.0, true));
+}
+
+#[test]
+fn test_balances_list_only_funded_assets() {
+    let asset = |name: &str, wallet: &str, unrealized: &str| json!({
+        "asset": name, "walletBalance": wallet, "unrealizedProfit": unrealized, "marginBalance": wallet,
+        "maintMargin": "0", "initialMargin": "0", "positionInitialMargin": "0", "openOrderInitialMargin": "0",
+        "crossWalletBalance": wallet, "crossUnPnl": unrealized, "availableBalance": wallet,
+        "maxWithdrawAmount": wallet, "updateTime": 0
+    });
+    let account: AccountInfo = serde_json::from_value(json!({
+        "totalInitialMargin": "0", "totalMaintMargin": "0", "totalWalletBalance": "1000.5",
+        "totalUnrealizedProfit": "-12.5", "totalMarginBalance": "988", "totalPositionInitialMargin": "0",
+        "totalOpenOrderInitialMargin": "0", "totalCrossWalletBalance": "1000.5", "totalCrossUnPnl": "-12.5",
+        "availableBalance": "900", "maxWithdrawAmount": "900",
+        "assets": [asset("USDT", "1000.5", "-12.5"), asset("BNB", "0", "0")],
+        "positions": []
+    })).unwrap();
+    let balances = summarize_balances(&account);
+    assert_eq!((balances.total_wallet_balance, balances.total_unrealized_pnl, balances.available_balance), (1000.5, -12.5, 900.0));
+    assert_eq!(balances.assets.len(), 1);
+    assert_eq!(balances.assets[0].asset, "USDT");
+}
+
+#[tokio::test]
+async fn test_arming_needs_the_admin_token_and_an_allowed_address() {
+    let interlock = Arc::new(Interlock::in_memory(false));
+    let config = AdminConfig {
+        listen_addr: "127.0.0.1:0".to_string(),
+        token: "admin-token".to_string(),
+        allowlist: Some(IpAllowlist::parse("203.0.113.0/24", None).unwrap()),
+    };
+    let app = router(offline_state(interlock.clone(), ExecutionPolicies::default()).await, &config);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await });
+
+    let client = reqwest::Client::new();
+    let arm = |forwarded_for: &'static str, authorization: Option<&'static str>| {
+        // The test connects from loopback, a trusted proxy, so the forwarded address is the client's
+        let mut request = client.post(format!("http://{}/admin/arming/arm", address))
+            .header("X-Forwarded-For", forwarded_for)
+            .json(&json!({"by": "alice"}));
+        if let Some(value) = authorization {
+            request = request.header("Authorization", value);
+        }
+        request.send()
+    };
+    assert_eq!(arm("198.51.100.7", Some("Bearer admin-token")).await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert_eq!(arm("203.0.113.5", None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    assert!(!interlock.is_armed());
+
+    assert_eq!(arm("203.0.113.5", Some("Bearer admin-token")).await.unwrap().status(), StatusCode::OK);
+    assert!(interlock.is_armed());
+    let state: serde_json::Value = client.get(format!("http://{}/admin/arming", address))
+        .header("X-Forwarded-For", "203.0.113.5")
+        .header("Authorization", "Bearer admin-token")
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!(state["armedBy"], "alice");
+}
+
+#[tokio::test]
+async fn test_loss_breaker_trip_disarms_the_bot() {
+    let interlock = Arc::new(Interlock::in_memory(true));
+    let policies = ExecutionPolicies { loss_breaker: Some(DailyLossBreaker { max_loss: 200.0, flatten: false }), ..ExecutionPolicies::default() };
+    let state = offline_state(interlock.clone(), policies).await;
+
+    assert!(check_loss_breaker(&state, &account("0")).await.is_ok());
+    assert!(interlock.is_armed());
+    assert!(check_loss_breaker(&state, &account("-250")).await.is_err());
+    assert!(!interlock.is_armed());
+    assert!(interlock.state().reason.unwrap().starts_with("circuit breaker daily_loss_limit"));
+}
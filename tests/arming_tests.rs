@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use trading_bot::arming::{read_state, router, Interlock};
+
+fn temp_file(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("arming_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("arming.json")
+}
+
+#[test]
+fn test_fresh_install_starts_disarmed() {
+    let path = temp_file("fresh");
+    let state = read_state(&path, "1.0", 1_000);
+    assert!(!state.armed);
+    assert_eq!(state.reason.as_deref(), Some("fresh install"));
+    assert!(!Interlock::load(&path, "1.0").is_armed());
+}
+
+#[test]
+fn test_arming_survives_restart_but_not_redeployment() {
+    let path = temp_file("restart");
+    let interlock = Interlock::load(&path, "1.0");
+    assert!(interlock.arm("  ").is_err());
+    let state = interlock.arm("alice").unwrap();
+    assert!(state.armed);
+    assert_eq!(state.armed_by.as_deref(), Some("alice"));
+
+    // Restart of the same deployment keeps the bot armed
+    assert!(Interlock::load(&path, "1.0").is_armed());
+
+    // A new deployment starts disarmed
+    let redeployed = Interlock::load(&path, "1.1");
+    assert!(!redeployed.is_armed());
+    assert!(redeployed.state().reason.unwrap().contains("new deployment 1.1"));
+}
+
+#[test]
+fn test_circuit_breaker_trip_persists_disarmed_state() {
+    let path = temp_file("trip");
+    let interlock = Interlock::load(&path, "1.0");
+    interlock.arm("alice").unwrap();
+    interlock.trip("daily_loss_limit", "loss 210.00 reached the limit 200.00");
+    assert!(!interlock.is_armed());
+
+    let restarted = Interlock::load(&path, "1.0");
+    assert!(!restarted.is_armed());
+    assert!(restarted.state().reason.unwrap().starts_with("circuit breaker daily_loss_limit"));
+}
+
+#[test]
+fn test_invalid_state_file_disarms() {
+    let path = temp_file("invalid");
+    std::fs::write(&path, "{not json").unwrap();
+    assert!(!Interlock::load(&path, "1.0").is_armed());
+}
+
+#[tokio::test]
+async fn test_admin_endpoints_arm_and_disarm() {
+    let interlock = Arc::new(Interlock::in_memory(false));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = router(interlock.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = reqwest::Client::new();
+
+    let state: serde_json::Value = client.get(format!("http://{}/arming", addr)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(state["armed"], false);
+
+    let response = client.post(format!("http://{}/arming/arm", addr)).json(&serde_json::json!({"by": ""})).send().await.unwrap();
+    assert_eq!(response.status(), 400);
+    assert!(!interlock.is_armed());
+
+    let response = client.post(format!("http://{}/arming/arm", addr)).json(&serde_json::json!({"by": "alice"})).send().await.unwrap();
+    assert!(response.status().is_success());
+    assert!(interlock.is_armed());
+
+    let state: serde_json::Value = client.post(format!("http://{}/arming/disarm", addr))
+        .json(&serde_json::json!({"reason": "maintenance"})).send().await.unwrap().json().await.unwrap();
+    assert_eq!(state["armed"], false);
+    assert_eq!(state["reason"], "maintenance");
+    assert!(!interlock.is_armed());
+}
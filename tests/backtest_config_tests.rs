@@ -0,0 +1,81 @@
+// tests/backtest_config_tests.rs
+
+//! This file contains tests for loading the backtest settings from TOML and command line flags.
+
+use trading_bot::market_data::{next_klines_page_start, Candlestick};
+use trading_bot::strategy::config::parse_date_ms;
+use trading_bot::risk::{FixedNotional, PositionSizer, RiskPercent};
+use trading_bot::strategy::BacktestConfig;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_toml_settings_override_defaults() {
+    let mut config = BacktestConfig::default();
+    config.apply_toml("# EMA settings\nfast_ema_period = 13\nslow_ema_period = 34 # Fibonacci\n\ndata_path = \"eth_1h.csv\"\n").unwrap();
+    assert_eq!(config.fast_ema_period, 13);
+    assert_eq!(config.slow_ema_period, 34);
+    assert_eq!(config.data_path, "eth_1h.csv");
+    assert_eq!(config.risk_reward_ratio, 3.0);
+
+    assert!(config.apply_toml("max_open_trades = 10").unwrap_err().contains("Unknown backtest setting"));
+    assert!(config.apply_toml("fast_ema_period = fast").is_err());
+}
+
+#[test]
+fn test_flags_override_config_file() {
+    let path = std::env::temp_dir().join(format!("backtest_config_{}.toml", std::process::id()));
+    std::fs::write(&path, "risk_percentage = 0.02\naccount_balance = 10000\n").unwrap();
+
+    let config = BacktestConfig::from_args(&args(&["--risk", "0.005", "--config", path.to_str().unwrap(), "--rr", "2"])).unwrap();
+    assert_eq!(config.risk_percentage, 0.005);
+    assert_eq!(config.account_balance, 10_000.0);
+    assert_eq!(config.risk_reward_ratio, 2.0);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(BacktestConfig::from_args(&args(&["--fast-ema", "60"])).is_err()); // Fast must stay below slow
+    assert!(BacktestConfig::from_args(&args(&["--rr"])).is_err());
+    assert_eq!(BacktestConfig::from_args(&[]).unwrap(), BacktestConfig::default());
+
+    let config = BacktestConfig::from_args(&args(&["--atr-period", "14", "--atr-stop", "1.5", "--risk", "0.02"])).unwrap();
+    let atr_risk = config.atr_risk().unwrap();
+    assert_eq!((atr_risk.risk_fraction, atr_risk.atr_multiple), (0.02, 1.5));
+    assert!(BacktestConfig::default().atr_risk().is_none());
+    assert!(BacktestConfig::from_args(&args(&["--atr-period", "14", "--atr-stop", "0"])).is_err());
+
+    // The default sizing follows the risk and stop settings; `--sizing` replaces it
+    assert_eq!(config.position_sizer(), PositionSizer::AtrRisk(atr_risk));
+    assert_eq!(BacktestConfig::default().position_sizer(), PositionSizer::RiskPercent(RiskPercent { risk_fraction: 0.01 }));
+    let config = BacktestConfig::from_args(&args(&["--sizing", "fixed_notional:500"])).unwrap();
+    assert_eq!(config.position_sizer(), PositionSizer::FixedNotional(FixedNotional(500.0)));
+    assert!(BacktestConfig::from_args(&args(&["--sizing", "fixed_notional"])).is_err());
+}
+
+#[test]
+fn test_kline_source_settings() {
+    let config = BacktestConfig::from_args(&args(&["--symbol", "btcusdt", "--from", "2021-01-01", "--to", "2021-01-02 12:00:00", "--interval", "1h"])).unwrap();
+    assert_eq!(config.symbol.as_deref(), Some("BTCUSDT"));
+    assert_eq!(config.kline_interval().unwrap().duration_ms(), 3_600_000);
+    assert_eq!(config.date_range_ms().unwrap(), (1_609_459_200_000, 1_609_588_800_000));
+    assert_eq!(parse_date_ms("1609459200000").unwrap(), 1_609_459_200_000);
+
+    assert!(BacktestConfig::from_args(&args(&["--symbol", "BTCUSDT"])).unwrap_err().contains("--from"));
+    assert!(BacktestConfig::from_args(&args(&["--symbol", "BTCUSDT", "--from", "01/01/2021"])).is_err());
+    assert!(BacktestConfig::from_args(&args(&["--interval", "2d"])).is_err());
+}
+
+fn kline(open_time: u64) -> Candlestick {
+    let s = || "1".to_string();
+    Candlestick::Array(open_time, s(), s(), s(), s(), s(), open_time + 59_999, s(), 1, s(), s(), s())
+}
+
+#[test]
+fn test_klines_pagination() {
+    let page: Vec<Candlestick> = (0..3).map(|i| kline(i * 60_000)).collect();
+    assert_eq!(next_klines_page_start(&page, 3, 1_000_000), Some(120_001));
+    assert_eq!(next_klines_page_start(&page, 4, 1_000_000), None); // Short page: no more data
+    assert_eq!(next_klines_page_start(&page, 3, 120_000), None); // Reached the end time
+    assert_eq!(next_klines_page_start(&[], 3, 1_000_000), None);
+}
@@ -0,0 +1,125 @@
+// tests/backtest_report_tests.rs
+
+//! This file contains tests for the JSON, HTML and CSV trade log backtest reports.
+
+use trading_bot::strategy::html::{escape, render_html};
+use trading_bot::order::OrderSide;
+use trading_bot::strategy::report::{drawdown_curve, format_duration, read_trades_csv, EquityPoint, ExitReason, PerformanceSummary, TradeRecord};
+use trading_bot::strategy::{BacktestConfig, BacktestReport};
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_summary_statistics() {
+    let summary = PerformanceSummary::from_pnl(&[30.0, -10.0, 60.0, -20.0], 1000.0, 1060.0, 0.02, 1);
+    assert_eq!(summary.total_trades, 4);
+    assert_eq!(summary.winning_trades, 2);
+    assert_eq!(summary.win_rate, 0.5);
+    assert_eq!(summary.net_pnl, 60.0);
+    assert_eq!(summary.profit_factor, Some(3.0));
+    assert_eq!(summary.realized_rr_ratio, Some(3.0));
+    assert_eq!(summary.expectancy, 15.0);
+
+    // Without losing trades the ratios are infinite, which JSON cannot represent
+    let summary = PerformanceSummary::from_pnl(&[10.0], 1000.0, 1010.0, 0.0, 0);
+    assert_eq!(summary.profit_factor, None);
+    assert_eq!(summary.realized_rr_ratio, None);
+}
+
+#[test]
+fn test_average_holding_time() {
+    let trade = |entry_time_ms: i64, exit_time_ms: i64, pnl: f64| TradeRecord {
+        entry_time_ms, exit_time_ms, side: OrderSide::Buy, entry_price: 100.0, exit_price: 100.0 + pnl,
+        exit_reason: ExitReason::TakeProfit, quantity: 1.0, fee: 0.0, pnl, mae: 0.0, mfe: pnl,
+    };
+    let summary = PerformanceSummary::from_trades(&[trade(0, 3_600_000, 10.0), trade(0, 10_800_000, 20.0)], 1000.0, 1030.0, 0.0, 0);
+    assert_eq!(summary.avg_holding_ms, Some(7_200_000));
+    assert_eq!(summary.expectancy, 15.0);
+    assert_eq!(PerformanceSummary::from_trades(&[], 1000.0, 1000.0, 0.0, 0).avg_holding_ms, None);
+
+    assert_eq!(format_duration(7_200_000), "2h 0m");
+    assert_eq!(format_duration(90_000), "1m");
+    assert_eq!(format_duration(2 * 86_400_000 + 5_400_000), "2d 1h 30m");
+}
+
+#[test]
+fn test_output_flag() {
+    let config = BacktestConfig::from_args(&args(&["--output", "report.json"])).unwrap();
+    assert_eq!(config.output.as_deref(), Some("report.json"));
+    assert_eq!(BacktestConfig::default().output, None);
+    assert!(BacktestConfig::from_args(&args(&["--fee-rate", "-0.001"])).is_err());
+}
+
+#[tokio::test]
+async fn test_backtest_writes_json_report() {
+    let dir = std::env::temp_dir().join(format!("backtest_report_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("candles.csv");
+    let output = dir.join("report.json");
+    let html_output = dir.join("report.html");
+    let trades_output = dir.join("trades.csv");
+
+    // A rising market with regular pullbacks below the fast EMA
+    let mut csv = String::from("Open time,Open,High,Low,Close,Volume,Close time,Quote asset volume,Number of trades,Taker buy base asset volume,Taker buy quote asset volume,Ignore\n");
+    for i in 0..300u64 {
+        let close = 100.0 + i as f64 * 0.5 + 4.0 * (i as f64 * 0.7).sin();
+        let open_time = 1_609_459_200_000 + i * 14_400_000;
+        csv.push_str(&format!("{},{},{},{},{},1,{},1,1,0,0,0\n", open_time, close, close + 1.5, close - 1.5, close, open_time + 14_399_999));
+    }
+    std::fs::write(&data, csv).unwrap();
+
+    let config = BacktestConfig::from_args(&args(&[
+        "--data", data.to_str().unwrap(), "--fast-ema", "3", "--slow-ema", "8", "--rr", "1", "--output", output.to_str().unwrap(),
+        "--html", html_output.to_str().unwrap(), "--trades", trades_output.to_str().unwrap(), "--fee-rate", "0.0005",
+        "--monte-carlo", "200", "--mc-seed", "1",
+    ])).unwrap();
+    trading_bot::strategy::run(&config).await.unwrap();
+
+    let report = BacktestReport::from_json(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(report.config, config);
+    assert_eq!(report.strategy, "3/8 EMA Crossover");
+    assert!(!report.trades.is_empty());
+    assert_eq!(report.trades.len(), report.summary.total_trades);
+    let net: f64 = report.trades.iter().map(|t| t.pnl).sum();
+    assert!((net - report.summary.net_pnl).abs() < 1e-6);
+    assert_eq!(report.equity_curve.len(), 300 - 8);
+    assert_eq!(report.monte_carlo.as_ref().map(|mc| mc.runs), Some(200));
+    for trade in &report.trades {
+        assert!(trade.exit_time_ms > trade.entry_time_ms);
+        assert_eq!(trade.side, OrderSide::Buy);
+        let expected_fee = (trade.entry_price + trade.exit_price) * trade.quantity * 0.0005;
+        assert!((trade.fee - expected_fee).abs() < 1e-9);
+        // Excursions are bounded by the exit: a stop's MAE is at least its loss, a target's MFE at least its gain
+        assert!(trade.mae >= 0.0 && trade.mfe >= 0.0);
+        let gross = trade.pnl + trade.fee;
+        assert!(trade.mae >= -gross - 1e-9 && trade.mfe >= gross - 1e-9);
+        match trade.exit_reason {
+            ExitReason::TakeProfit => assert!(trade.pnl > 0.0),
+            ExitReason::StopLoss | ExitReason::Liquidation => assert!(trade.pnl <= 0.0),
+        }
+    }
+    let logged = read_trades_csv(trades_output.to_str().unwrap()).unwrap();
+    assert_eq!(logged.len(), report.trades.len());
+    for (row, trade) in logged.iter().zip(&report.trades) {
+        assert_eq!((row.entry_time_ms, row.exit_time_ms, row.exit_reason), (trade.entry_time_ms, trade.exit_time_ms, trade.exit_reason));
+        assert!((row.pnl - trade.pnl).abs() < 1e-9 && (row.mfe - trade.mfe).abs() < 1e-9);
+    }
+
+    let html = std::fs::read_to_string(&html_output).unwrap();
+    assert_eq!(html, render_html(&report));
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(!html.contains("<script"));
+    assert_eq!(html.matches("<svg").count(), 3); // Equity, drawdown, price
+    assert_eq!(html.matches("<circle").count(), report.trades.len()); // One exit marker per trade
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_drawdown_curve_and_escaping() {
+    let point = |equity: f64| EquityPoint { time_ms: 0, equity, price: 1.0 };
+    let drawdown = drawdown_curve(&[point(100.0), point(120.0), point(90.0), point(130.0)]);
+    assert_eq!(drawdown, vec![0.0, 0.0, 0.25, 0.0]);
+    assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+}
@@ -1,6 +1,6 @@
 use trading_bot::rest_api::RestClient;
 use trading_bot::websocket::WebSocketClient;
-use trading_bot::order::{OrderSide, OrderType, TimeInForce};
+use trading_bot::order::{OrderSide, OrderStatus, OrderType, TimeInForce};
 use trading_bot::tui::display_struct_in_tui;
 
 const API_KEY: &str = "ae01d811bd0704d1fe996f9c1ea63ed241a4a7373ad6bbeafd8ac13e9bf5a5ec";
@@ -109,7 +109,7 @@ async fn test_modify_order() {
     
     display_struct_in_tui(&order_status, &format!("Order {} Status Before Modification", order_id)).await.unwrap();
     
-    if order_status.status == "NEW" || order_status.status == "PARTIALLY_FILLED" {
+    if matches!(order_status.status, OrderStatus::New | OrderStatus::PartiallyFilled) {
         println!("Modifying order...");
         let new_price = 305.0;
         let new_quantity = 0.03;
@@ -173,3 +173,21 @@ async fn test_cancel_order() {
     display_struct_in_tui(&cancel_response, &format!("Canceled Order ID: {}", order_id)).await.unwrap();
     println!("Order canceled successfully!");
 }
+
+#[tokio::test]
+async fn test_user_data_stream_over_ws_api() {
+    let ws_client = WebSocketClient::new(
+        API_KEY.to_string(),
+        SECRET_KEY.to_string(),
+        WS_API_BASE_URL.to_string(),
+    ).await;
+
+    let listen_key = ws_client.start_user_data_stream().await.expect("Failed to start user data stream");
+    assert!(!listen_key.is_empty());
+    let pinged = ws_client.ping_user_data_stream().await.expect("Failed to ping user data stream");
+    assert_eq!(pinged, listen_key);
+    ws_client.stop_user_data_stream().await.expect("Failed to stop user data stream");
+
+    let url = trading_bot::websocket::user_data::user_data_stream_url("wss://fstream.binancefuture.com/ws/", &listen_key);
+    assert_eq!(url, format!("wss://fstream.binancefuture.com/ws/{}", listen_key));
+}
@@ -0,0 +1,102 @@
+// tests/bracket_tests.rs
+
+//! This file contains offline tests for the bracket order state machine (no network access needed).
+
+use serde_json::json;
+use trading_bot::order::bracket::*;
+use trading_bot::order::{OrderSide, OrderType};
+use trading_bot::streams::FuturesOrderUpdate;
+use trading_bot::websocket_stream::BinanceWsMessage;
+
+fn long_bracket() -> BracketOrder {
+    BracketOrder {
+        id: "br1".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        quantity: 0.01,
+        entry_price: None,
+        stop_loss: 29000.0,
+        take_profit: 32000.0,
+        position_side: None,
+    }
+}
+
+fn update(client_order_id: &str, status: &str, filled: &str) -> FuturesOrderUpdate {
+    let message = BinanceWsMessage::Raw(json!({
+        "e": "ORDER_TRADE_UPDATE", "E": 1, "T": 1,
+        "o": {
+            "s": "BTCUSDT", "c": client_order_id, "S": "BUY", "o": "MARKET", "f": "GTC",
+            "q": "0.01", "p": "0", "ap": "30000", "x": "TRADE", "X": status, "i": 1,
+            "l": filled, "z": filled, "L": "30000", "T": 1, "t": 1
+        }
+    }));
+    order_update_from_message(&message).expect("valid order update")
+}
+
+#[test]
+fn test_bracket_validation() {
+    assert!(long_bracket().validate().is_ok());
+    let inverted = BracketOrder { stop_loss: 33000.0, ..long_bracket() };
+    assert!(inverted.validate().is_err());
+    let entry_outside = BracketOrder { entry_price: Some(35000.0), ..long_bracket() };
+    assert!(entry_outside.validate().is_err());
+}
+
+#[test]
+fn test_entry_fill_places_protective_orders() {
+    let mut manager = BracketManager::new();
+    let actions = manager.register(long_bracket()).unwrap();
+    assert_eq!(actions.len(), 1);
+    assert!(manager.register(long_bracket()).is_err());
+
+    let actions = manager.on_order_update(&update("br1-en", "FILLED", "0.01"));
+    assert_eq!(manager.get("br1").unwrap().state, BracketState::Protected);
+    assert_eq!(actions.len(), 2);
+    match &actions[0] {
+        BracketAction::PlaceOrder(sl) => {
+            assert_eq!(sl.order_type, OrderType::StopMarket);
+            assert_eq!(sl.side, OrderSide::Sell);
+            assert!(sl.reduce_only);
+            assert_eq!(sl.to_params()["stopPrice"], "29000");
+        },
+        other => panic!("unexpected action {:?}", other),
+    }
+    match &actions[1] {
+        BracketAction::PlaceOrder(tp) => assert_eq!(tp.order_type, OrderType::TakeProfitMarket),
+        other => panic!("unexpected action {:?}", other),
+    }
+}
+
+#[test]
+fn test_protective_fill_cancels_sibling() {
+    let mut manager = BracketManager::new();
+    manager.register(long_bracket()).unwrap();
+    manager.on_order_update(&update("br1-en", "FILLED", "0.01"));
+
+    let actions = manager.on_order_update(&update("br1-tp", "FILLED", "0.01"));
+    assert_eq!(manager.get("br1").unwrap().state, BracketState::TookProfit);
+    assert_eq!(actions, vec![BracketAction::CancelOrder {
+        symbol: "BTCUSDT".to_string(),
+        client_order_id: "br1-sl".to_string(),
+    }]);
+    // A late update for the cancelled sibling is ignored
+    assert!(manager.on_order_update(&update("br1-sl", "CANCELED", "0")).is_empty());
+}
+
+#[test]
+fn test_partially_filled_entry_is_protected_on_cancel() {
+    let mut manager = BracketManager::new();
+    manager.register(BracketOrder { entry_price: Some(30000.0), ..long_bracket() }).unwrap();
+    assert!(manager.on_order_update(&update("br1-en", "PARTIALLY_FILLED", "0.004")).is_empty());
+
+    let actions = manager.on_order_update(&update("br1-en", "CANCELED", "0.004"));
+    assert_eq!(actions.len(), 2);
+    if let BracketAction::PlaceOrder(sl) = &actions[0] {
+        assert_eq!(sl.quantity, Some(0.004));
+    }
+
+    let mut manager = BracketManager::new();
+    manager.register(long_bracket()).unwrap();
+    assert!(manager.on_order_update(&update("br1-en", "EXPIRED", "0")).is_empty());
+    assert_eq!(manager.get("br1").unwrap().state, BracketState::Cancelled);
+}
@@ -0,0 +1,91 @@
+// tests/candle_aggregator_tests.rs
+
+//! This file contains tests for building custom-interval candles from aggregated trades.
+
+use trading_bot::candles::aggregator::*;
+use trading_bot::market_event::{MarketEvent, Symbol, Trade};
+use trading_bot::streams::AggTradeStream;
+
+const MINUTE: u64 = 60_000;
+
+fn trade(time_ms: u64, price: f64, quantity: f64) -> Trade {
+    Trade { symbol: Symbol::new("BTCUSDT").unwrap(), event_time: time_ms, trade_time: time_ms, trade_id: time_ms, price, quantity, is_buyer_maker: false }
+}
+
+fn agg_trade(time_ms: u64, price: &str, quantity: &str) -> AggTradeStream {
+    serde_json::from_value(serde_json::json!({
+        "e": "aggTrade", "E": time_ms, "s": "BTCUSDT", "a": 1, "p": price, "q": quantity,
+        "f": 100, "l": 104, "T": time_ms, "m": true, "M": true,
+    })).unwrap()
+}
+
+#[test]
+fn test_intervals_are_parsed_and_validated() {
+    let aggregator = CandleAggregator::new(&["45m", "2m", "90s", "2m"]).unwrap();
+    assert_eq!(aggregator.intervals_ms(), &[90_000, 2 * MINUTE, 45 * MINUTE]);
+    assert!(CandleAggregator::new(&["0m"]).is_err());
+    assert!(CandleAggregator::new(&["2x"]).is_err());
+    assert!(CandleAggregator::new(&[]).is_err());
+}
+
+#[test]
+fn test_trades_build_ohlcv_candles_per_interval() {
+    let mut aggregator = CandleAggregator::new(&["2m", "4m"]).unwrap();
+    assert!(aggregator.on_trade(&trade(10_000, 100.0, 1.0)).is_empty());
+    assert!(aggregator.on_trade(&trade(70_000, 104.0, 2.0)).is_empty());
+    assert!(aggregator.on_trade(&trade(110_000, 98.0, 1.0)).is_empty());
+
+    let forming = aggregator.forming(Symbol::new("BTCUSDT").unwrap(), 2 * MINUTE).unwrap();
+    assert!(!forming.is_closed);
+    assert_eq!((forming.open, forming.high, forming.low, forming.close, forming.volume), (100.0, 104.0, 98.0, 98.0, 4.0));
+
+    // The first trade of the next 2m bucket closes the 2m candle; the 4m candle keeps forming
+    let closed = aggregator.on_trade(&trade(2 * MINUTE + 5_000, 101.0, 1.0));
+    assert_eq!(closed.len(), 1);
+    let candle = closed[0];
+    assert!(candle.is_closed);
+    assert_eq!((candle.open_time, candle.close_time, candle.interval_ms), (0, 2 * MINUTE - 1, 2 * MINUTE));
+    assert_eq!((candle.quote_volume, candle.trades), (100.0 + 208.0 + 98.0, 3));
+
+    let closed = aggregator.on_trade(&trade(4 * MINUTE, 102.0, 1.0));
+    assert_eq!(closed.iter().map(|c| c.interval_ms).collect::<Vec<_>>(), vec![2 * MINUTE, 4 * MINUTE]);
+    assert_eq!((closed[1].open, closed[1].close, closed[1].volume), (100.0, 101.0, 5.0));
+}
+
+#[test]
+fn test_gaps_produce_flat_candles_and_late_trades_are_dropped() {
+    let mut aggregator = CandleAggregator::new(&["2m"]).unwrap();
+    aggregator.on_trade(&trade(0, 100.0, 1.0));
+    // Nothing trades for two buckets
+    let closed = aggregator.on_trade(&trade(6 * MINUTE + 1, 103.0, 1.0));
+    assert_eq!(closed.len(), 3);
+    assert_eq!(closed.iter().map(|c| c.open_time).collect::<Vec<_>>(), vec![0, 2 * MINUTE, 4 * MINUTE]);
+    assert_eq!((closed[2].open, closed[2].close, closed[2].volume, closed[2].trades), (100.0, 100.0, 0.0, 0));
+
+    // A trade of an already closed bucket is ignored
+    assert!(aggregator.on_trade(&trade(5 * MINUTE, 90.0, 1.0)).is_empty());
+    assert_eq!(aggregator.forming(Symbol::new("BTCUSDT").unwrap(), 2 * MINUTE).unwrap().low, 103.0);
+
+    // Flushing closes the forming candle after its close time, then one flat candle per elapsed bucket
+    assert!(aggregator.flush(8 * MINUTE - 1).is_empty());
+    let closed = aggregator.flush(12 * MINUTE + 1);
+    assert_eq!(closed.iter().map(|c| c.open_time).collect::<Vec<_>>(), vec![6 * MINUTE, 8 * MINUTE, 10 * MINUTE]);
+    assert!(aggregator.on_trade(&trade(9 * MINUTE, 90.0, 1.0)).is_empty());
+}
+
+#[tokio::test]
+async fn test_service_forwards_closed_candles_as_klines() {
+    let (trade_sender, trade_receiver) = tokio::sync::mpsc::channel(16);
+    let (candle_sender, mut candle_receiver) = tokio::sync::mpsc::channel(16);
+    let handle = spawn_candle_aggregator(CandleAggregator::new(&["1m"]).unwrap(), trade_receiver, candle_sender);
+
+    trade_sender.send(agg_trade(1_000, "100.5", "0.5")).await.unwrap();
+    trade_sender.send(agg_trade(MINUTE + 1_000, "101", "1")).await.unwrap();
+    let Some(MarketEvent::Kline(candle)) = candle_receiver.recv().await else { panic!("expected a kline") };
+    assert!(candle.is_closed);
+    assert_eq!((candle.open_time, candle.close, candle.volume, candle.trades), (0, 100.5, 0.5, 5));
+
+    drop(trade_sender);
+    let aggregator = handle.await.unwrap();
+    assert_eq!(aggregator.forming(Symbol::new("BTCUSDT").unwrap(), MINUTE).map(|c| c.open), Some(101.0));
+}
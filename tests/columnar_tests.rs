@@ -0,0 +1,144 @@
+// tests/columnar_tests.rs
+
+//! This file contains tests for the Parquet candle files.
+
+use trading_bot::data::columnar::*;
+use trading_bot::market_event::{Candle, Symbol};
+use trading_bot::metrics::MetricsConfig;
+use trading_bot::strategy::rsi::{RsiConfig, RsiStrategy};
+use trading_bot::strategy::{backtest, backtest_stream, BacktestConfig, BacktestReport};
+
+fn candles(count: u64) -> Vec<Candle> {
+    (0..count).map(|i| {
+        let close = 100.0 + (i as f64 * 0.7).sin() * 10.0;
+        Candle {
+            symbol: Symbol::new("ETHUSDT").unwrap(),
+            event_time: i * 60_000 + 59_999,
+            interval_ms: 60_000,
+            open_time: i * 60_000,
+            close_time: i * 60_000 + 59_999,
+            open: close - 1.0,
+            high: close + 2.0,
+            low: close - 2.0,
+            close,
+            volume: i as f64,
+            quote_volume: i as f64 * close,
+            trades: i,
+            is_closed: true,
+        }
+    }).collect()
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{}_{}.parquet", name, std::process::id()))
+}
+
+#[test]
+fn test_write_and_read_chunks() {
+    let path = temp_path("columnar_roundtrip");
+    let data = candles(25);
+    write_candles(&path, &data, 10).unwrap();
+
+    let reader = ChunkReader::open(&path).unwrap();
+    assert_eq!(reader.symbol().as_str(), "ETHUSDT");
+    let sizes: Vec<usize> = reader.map(|chunk| chunk.unwrap().len()).collect();
+    assert_eq!(sizes, vec![10, 10, 5]);
+    assert_eq!(read_candles(&path).unwrap(), data);
+    assert!(is_columnar(path.to_str().unwrap()) && !is_columnar("btc.csv"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_append_chunks() {
+    let path = temp_path("columnar_append");
+    let _ = std::fs::remove_file(&path);
+    let data = candles(8);
+    let symbol = Symbol::new("ETHUSDT").unwrap();
+    for session in data.chunks(4) {
+        let mut writer = ChunkWriter::append(&path, symbol).unwrap();
+        for part in session.chunks(3) {
+            let mut chunk = CandleChunk::default();
+            part.iter().for_each(|c| chunk.push(c));
+            writer.write(&chunk).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+    let sizes: Vec<usize> = ChunkReader::open(&path).unwrap().map(|chunk| chunk.unwrap().len()).collect();
+    assert_eq!(sizes, vec![3, 1, 3, 1]);
+    assert_eq!(read_candles(&path).unwrap(), data);
+    assert!(ChunkWriter::append(&path, Symbol::new("BTCUSDT").unwrap()).is_err());
+
+    // An unfinished writer leaves the file as it was
+    let mut writer = ChunkWriter::append(&path, symbol).unwrap();
+    let mut chunk = CandleChunk::default();
+    chunk.push(&data[0]);
+    writer.write(&chunk).unwrap();
+    drop(writer);
+    assert_eq!(read_candles(&path).unwrap(), data);
+    std::fs::remove_file(&path).unwrap();
+    let _ = std::fs::remove_file(path.with_extension("parquet.tmp"));
+
+    std::fs::write(&path, b"Open time,Open\n").unwrap();
+    assert!(ChunkReader::open(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_streamed_backtest_matches_in_memory() {
+    let path = temp_path("columnar_backtest");
+    let data = candles(500);
+    write_candles(&path, &data, 64).unwrap();
+    let config = RsiConfig { period: 5, quantity: 1.0, ..RsiConfig::default() };
+
+    let expected = backtest(&mut RsiStrategy::new(config.clone()).unwrap(), &data, 1000.0, &MetricsConfig::default());
+    let stream = ChunkReader::open(&path).unwrap().candles().map(Result::unwrap);
+    let streamed = backtest_stream(&mut RsiStrategy::new(config).unwrap(), stream, 1000.0, &MetricsConfig::default());
+    assert!(!expected.fills.is_empty());
+    assert_eq!(streamed.fills, expected.fills);
+    assert_eq!(streamed.final_equity, expected.final_equity);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_ema_backtest_streams_parquet_like_csv() {
+    let dir = std::env::temp_dir().join(format!("columnar_ema_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let data: Vec<Candle> = (0..300u64).map(|i| {
+        let close = 100.0 + i as f64 * 0.5 + 4.0 * (i as f64 * 0.7).sin();
+        let open_time = 1_609_459_200_000 + i * 14_400_000;
+        Candle {
+            symbol: Symbol::new("BTCUSDT").unwrap(),
+            event_time: open_time + 14_399_999,
+            interval_ms: 14_400_000,
+            open_time,
+            close_time: open_time + 14_399_999,
+            open: close,
+            high: close + 1.5,
+            low: close - 1.5,
+            close,
+            volume: 1.0,
+            quote_volume: 1.0,
+            trades: 1,
+            is_closed: true,
+        }
+    }).collect();
+    let mut csv = String::from("Open time,Open,High,Low,Close,Volume,Close time,Quote asset volume,Number of trades,Taker buy base asset volume,Taker buy quote asset volume,Ignore\n");
+    for c in &data {
+        csv.push_str(&format!("{},{},{},{},{},1,{},1,1,0,0,0\n", c.open_time, c.open, c.high, c.low, c.close, c.close_time));
+    }
+    std::fs::write(dir.join("candles.csv"), csv).unwrap();
+    write_candles(&dir.join("candles.parquet"), &data, 64).unwrap();
+
+    let mut reports = Vec::new();
+    for file in ["candles.csv", "candles.parquet"] {
+        let output = dir.join(format!("{}.json", file));
+        let args: Vec<String> = ["--data", dir.join(file).to_str().unwrap(), "--fast-ema", "3", "--slow-ema", "8", "--rr", "1", "--output", output.to_str().unwrap()]
+            .iter().map(|arg| arg.to_string()).collect();
+        trading_bot::strategy::run(&BacktestConfig::from_args(&args).unwrap()).await.unwrap();
+        reports.push(BacktestReport::from_json(&std::fs::read_to_string(&output).unwrap()).unwrap());
+    }
+    assert!(!reports[0].trades.is_empty());
+    assert_eq!(reports[1].trades, reports[0].trades);
+    assert_eq!(reports[1].equity_curve, reports[0].equity_curve);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
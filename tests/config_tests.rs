@@ -0,0 +1,314 @@
+// tests/config_tests.rs
+
+//! This file contains tests for loading the runtime configuration from env vars and secret files.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use trading_bot::config::*;
+use trading_bot::risk::{DailyLossBreaker, FixedNotional, PositionSizer, DEFAULT_ATR_RISK};
+use trading_bot::notify::email::SmtpTls;
+use trading_bot::notify::Severity;
+use trading_bot::storage::StorageConfig;
+use trading_bot::tunnel::TunnelConfig;
+
+fn base_env() -> HashMap<String, String> {
+    [
+        ("BINANCE_API_KEY", "key"),
+        ("BINANCE_REST_API_BASE_URL", "https://testnet.binancefuture.com"),
+        ("BINANCE_WS_API_BASE_URL", "wss://testnet.binancefuture.com/ws-fapi/v1"),
+    ].into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+fn load(env: &HashMap<String, String>) -> Result<RuntimeConfig, String> {
+    RuntimeConfig::from_lookup(|name| env.get(name).cloned())
+}
+
+#[test]
+fn test_secret_read_from_file() {
+    let path = std::env::temp_dir().join(format!("trading_bot_secret_{}", std::process::id()));
+    std::fs::write(&path, "file-secret\n").unwrap();
+
+    let mut env = base_env();
+    env.insert("BINANCE_SECRET_KEY_FILE".to_string(), path.display().to_string());
+    env.insert("WEBHOOK_LOCAL_LISTEN_ADDR".to_string(), "127.0.0.1:3000".to_string());
+    let config = load(&env).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(config.secret_key, "file-secret");
+    assert!(!config.container_mode);
+    assert_eq!(config.webhook_listen_addr, "127.0.0.1:3000");
+    assert_eq!(config.health_listen_addr, None);
+    assert_eq!(config.state_dir, PathBuf::from(LOCAL_STATE_DIR));
+    assert_eq!(config.sizing, PositionSizer::AtrRisk(DEFAULT_ATR_RISK));
+    assert_eq!(config.webhook_secret, None);
+    assert_eq!(config.recv_window_ms, trading_bot::rest_api::DEFAULT_RECV_WINDOW_MS);
+}
+
+#[test]
+fn test_recv_window() {
+    let mut env = base_env();
+    env.insert("BINANCE_SECRET_KEY".to_string(), "secret".to_string());
+    env.insert("WEBHOOK_LOCAL_LISTEN_ADDR".to_string(), "127.0.0.1:3000".to_string());
+    env.insert("RECV_WINDOW_MS".to_string(), "10000".to_string());
+    assert_eq!(load(&env).unwrap().recv_window_ms, 10_000);
+    for invalid in ["0", "60001", "fast"] {
+        env.insert("RECV_WINDOW_MS".to_string(), invalid.to_string());
+        assert!(load(&env).unwrap_err().contains("RECV_WINDOW_MS"));
+    }
+}
+
+#[test]
+fn test_http_client_settings() {
+    let mut env = base_env();
+    env.insert("BINANCE_SECRET_KEY".to_string(), "secret".to_string());
+    env.insert("WEBHOOK_LOCAL_LISTEN_ADDR".to_string(), "127.0.0.1:3000".to_string());
+    assert_eq!(load(&env).unwrap().http, trading_bot::rest_api::http::HttpClientConfig::default());
+
+    env.insert("HTTP_CONNECT_TIMEOUT_SECS".to_string(), "3".to_string());
+    env.insert("HTTP_REQUEST_TIMEOUT_SECS".to_string(), "15".to_string());
+    env.insert("HTTP_POOL_MAX_IDLE_PER_HOST".to_string(), "4".to_string());
+    env.insert("HTTP_PROXY_URL".to_string(), "socks5://proxy.corp:1080".to_string());
+    env.insert("HTTP_USER_AGENT".to_string(), "desk-7".to_string());
+    let http = load(&env).unwrap().http;
+    assert_eq!(http.connect_timeout, Duration::from_secs(3));
+    assert_eq!(http.request_timeout, Duration::from_secs(15));
+    assert_eq!(http.pool_max_idle_per_host, Some(4));
+    assert_eq!(http.proxy.as_deref(), Some("socks5://proxy.corp:1080"));
+    assert_eq!(http.user_agent, "desk-7");
+    assert!(http.build_client().is_ok());
+
+    env.insert("HTTP_PROXY_URL".to_string(), "proxy.corp:1080".to_string());
+    assert!(load(&env).unwrap_err().contains("HTTP_PROXY_URL"));
+    env.remove("HTTP_PROXY_URL");
+    env.insert("HTTP_REQUEST_TIMEOUT_SECS".to_string(), "0".to_string());
+    assert!(load(&env).unwrap_err().contains("HTTP_REQUEST_TIMEOUT_SECS"));
+}
+
+#[test]
+fn test_missing_secret_is_an_error() {
+    let mut env = base_env();
+    env.insert("WEBHOOK_LOCAL_LISTEN_ADDR".to_string(), "127.0.0.1:3000".to_string());
+    assert!(load(&env).unwrap_err().contains("BINANCE_SECRET_KEY"));
+
+    env.insert("BINANCE_SECRET_KEY_FILE".to_string(), "/nonexistent/secret".to_string());
+    assert!(load(&env).is_err());
+}
+
+#[test]
+fn test_container_mode_defaults() {
+    let mut env = base_env();
+    env.insert("BINANCE_SECRET_KEY".to_string(), "secret".to_string());
+    env.insert("TRADING_BOT_CONTAINER".to_string(), "1".to_string());
+    env.insert("WEBHOOK_PORT".to_string(), "8443".to_string());
+    let config = load(&env).unwrap();
+
+    assert!(config.container_mode);
+    assert_eq!(config.webhook_listen_addr, "0.0.0.0:8443");
+    assert_eq!(config.health_listen_addr.as_deref(), Some("0.0.0.0:9090"));
+    assert_eq!(config.state_dir, PathBuf::from(CONTAINER_STATE_DIR));
+    assert_eq!(config.state_path("runtime.json"), PathBuf::from("/data/runtime.json"));
+
+    env.insert("HEALTH_PORT".to_string(), "0".to_string());
+    assert_eq!(load(&env).unwrap().health_listen_addr, None);
+    // The webhook fails closed without a secret, unless unauthenticated requests are allowed
+    assert!(load(&env).unwrap().check_webhook_auth().unwrap_err().contains("WEBHOOK_ALLOW_UNAUTHENTICATED"));
+    env.insert("WEBHOOK_ALLOW_UNAUTHENTICATED".to_string(), "true".to_string());
+    assert!(load(&env).unwrap().check_webhook_auth().is_ok());
+    env.remove("WEBHOOK_ALLOW_UNAUTHENTICATED");
+    env.insert("TRADINGVIEW_WEBHOOK_SECRET".to_string(), "tv-secret\n".to_string());
+    assert_eq!(load(&env).unwrap().webhook_secret.as_deref(), Some("tv-secret"));
+    assert!(load(&env).unwrap().check_webhook_auth().is_ok());
+    env.insert("WEBHOOK_ALLOWED_IPS".to_string(), "tradingview,10.0.0.0/8".to_string());
+    assert_eq!(load(&env).unwrap().webhook_ip_allowlist.map(|a| a.allowed.len()), Some(5));
+    env.insert("WEBHOOK_TRUSTED_PROXIES".to_string(), "172.16.0.0/40".to_string());
+    assert!(load(&env).is_err());
+    env.remove("WEBHOOK_TRUSTED_PROXIES");
+    env.insert("SIZING".to_string(), "fixed_notional:250".to_string());
+    assert_eq!(load(&env).unwrap().sizing, PositionSizer::FixedNotional(FixedNotional(250.0)));
+    env.insert("SIZING".to_string(), "martingale:2".to_string());
+    assert!(load(&env).unwrap_err().contains("SIZING"));
+    env.remove("SIZING");
+    env.insert("DAILY_LOSS_LIMIT".to_string(), "500".to_string());
+    env.insert("DAILY_LOSS_FLATTEN".to_string(), "true".to_string());
+    assert_eq!(load(&env).unwrap().daily_loss_breaker, Some(DailyLossBreaker { max_loss: 500.0, flatten: true }));
+    env.insert("DAILY_LOSS_LIMIT".to_string(), "0".to_string());
+    assert_eq!(load(&env).unwrap().daily_loss_breaker, None);
+    env.insert("DAILY_LOSS_LIMIT".to_string(), "-100".to_string());
+    assert!(load(&env).unwrap_err().contains("DAILY_LOSS_LIMIT"));
+    env.remove("DAILY_LOSS_LIMIT");
+    env.remove("DAILY_LOSS_FLATTEN");
+    env.insert("MAX_OPEN_POSITIONS".to_string(), "3".to_string());
+    env.insert("MAX_QUOTE_NOTIONAL".to_string(), "USDT:5000".to_string());
+    let limits = load(&env).unwrap().exposure_limits.unwrap();
+    assert_eq!((limits.max_open_positions, limits.max_total_notional, limits.max_quote_notional["USDT"]), (Some(3), None, 5000.0));
+    env.insert("MAX_TOTAL_NOTIONAL".to_string(), "lots".to_string());
+    assert!(load(&env).unwrap_err().contains("MAX_TOTAL_NOTIONAL"));
+    env.remove("MAX_OPEN_POSITIONS");
+    env.remove("MAX_QUOTE_NOTIONAL");
+    env.remove("MAX_TOTAL_NOTIONAL");
+    assert!(load(&env).unwrap().exposure_limits.is_none());
+    env.insert("TRADING_SCHEDULE".to_string(), "mon-fri 7-21".to_string());
+    env.insert("TRADING_SCHEDULE_FLATTEN".to_string(), "yes".to_string());
+    assert!(load(&env).unwrap().trading_schedule.unwrap().flatten_outside);
+    env.insert("TRADING_SCHEDULE".to_string(), "mon-fri".to_string());
+    assert!(load(&env).unwrap_err().contains("TRADING_SCHEDULE"));
+    env.remove("TRADING_SCHEDULE");
+    env.remove("TRADING_SCHEDULE_FLATTEN");
+    env.insert("WEBHOOK_SYMBOLS".to_string(), r#"{"btcusdt": {"quoteQuantity": 200}}"#.to_string());
+    assert!(load(&env).unwrap().webhook_symbols.resolve("BTCUSDT").unwrap().is_some());
+    env.insert("WEBHOOK_SYMBOLS".to_string(), r#"{"BTCUSDT": {"quoteQuantity": -5}}"#.to_string());
+    assert!(load(&env).unwrap_err().contains("WEBHOOK_SYMBOLS"));
+    env.remove("WEBHOOK_SYMBOLS");
+    env.insert("WEBHOOK_STRATEGIES".to_string(), r#"{"trend": {"sizing": "fixed_notional:100"}}"#.to_string());
+    assert!(load(&env).unwrap().webhook_strategies.get("trend").is_some());
+    env.insert("WEBHOOK_STRATEGIES".to_string(), r#"{"trend": {"sizing": "all_in"}}"#.to_string());
+    assert!(load(&env).unwrap_err().contains("WEBHOOK_STRATEGIES"));
+    env.remove("WEBHOOK_STRATEGIES");
+    env.insert("WEBHOOK_TLS_CERT".to_string(), "/etc/letsencrypt/live/bot/fullchain.pem".to_string());
+    assert!(load(&env).unwrap_err().contains("WEBHOOK_TLS_KEY"));
+    env.insert("WEBHOOK_TLS_KEY".to_string(), "/etc/letsencrypt/live/bot/privkey.pem".to_string());
+    assert_eq!(load(&env).unwrap().webhook_tls.unwrap().key_path.to_str(), Some("/etc/letsencrypt/live/bot/privkey.pem"));
+    env.insert("NGROK_AUTHTOKEN".to_string(), "token".to_string());
+    assert!(load(&env).unwrap_err().contains("NGROK_AUTHTOKEN"));
+    env.remove("NGROK_AUTHTOKEN");
+    env.remove("WEBHOOK_TLS_CERT");
+    env.remove("WEBHOOK_TLS_KEY");
+    assert!(load(&env).unwrap().tunnel.is_none());
+    env.insert("NGROK_AUTHTOKEN".to_string(), "token".to_string());
+    assert!(matches!(load(&env).unwrap().tunnel, Some(TunnelConfig::Ngrok { .. })));
+    env.insert("WEBHOOK_TUNNEL".to_string(), "cloudflare".to_string());
+    assert!(matches!(load(&env).unwrap().tunnel, Some(TunnelConfig::Cloudflare { token: None, .. })));
+    env.insert("CLOUDFLARE_TUNNEL_TOKEN".to_string(), "cf-token".to_string());
+    assert!(load(&env).unwrap_err().contains("CLOUDFLARE_TUNNEL_HOSTNAME"));
+    env.insert("WEBHOOK_TUNNEL".to_string(), "localtunnel".to_string());
+    assert!(load(&env).is_err());
+    env.remove("WEBHOOK_TUNNEL");
+    env.remove("NGROK_AUTHTOKEN");
+    env.remove("CLOUDFLARE_TUNNEL_TOKEN");
+    assert!(load(&env).unwrap().telegram.is_none());
+    env.insert("TELEGRAM_BOT_TOKEN".to_string(), "123:abc".to_string());
+    assert!(load(&env).unwrap_err().contains("TELEGRAM_CHAT_ID"));
+    env.insert("TELEGRAM_CHAT_ID".to_string(), "-100200".to_string());
+    let telegram = load(&env).unwrap().telegram.unwrap();
+    assert_eq!(telegram.chat_id, "-100200");
+    assert!(!format!("{:?}", telegram).contains("abc"));
+    assert!(telegram.operators.is_empty());
+    env.insert("TELEGRAM_OPERATOR_IDS".to_string(), "1001, 2002".to_string());
+    assert_eq!(load(&env).unwrap().telegram.unwrap().operators, vec![1001, 2002]);
+    env.insert("TELEGRAM_OPERATOR_IDS".to_string(), "@alice".to_string());
+    assert!(load(&env).unwrap_err().contains("TELEGRAM_OPERATOR_IDS"));
+    env.remove("TELEGRAM_OPERATOR_IDS");
+    env.remove("TELEGRAM_BOT_TOKEN");
+    env.remove("TELEGRAM_CHAT_ID");
+    env.insert("SMTP_HOST".to_string(), "smtp.example.com".to_string());
+    assert!(load(&env).unwrap_err().contains("ALERT_EMAIL_TO"));
+    env.insert("ALERT_EMAIL_TO".to_string(), "ops@example.com, alice@example.com".to_string());
+    env.insert("ALERT_EMAIL_FROM".to_string(), "bot@example.com".to_string());
+    env.insert("SMTP_PORT".to_string(), "465".to_string());
+    let email = load(&env).unwrap().email.unwrap();
+    assert_eq!((email.tls, email.to.len(), email.batch_window), (SmtpTls::Implicit, 2, Duration::from_secs(300)));
+    assert_eq!(email.min_severity, Severity::Critical);
+    env.insert("ALERT_EMAIL_MIN_SEVERITY".to_string(), "warning".to_string());
+    assert_eq!(load(&env).unwrap().email.unwrap().min_severity, Severity::Warning);
+    env.insert("ALERT_EMAIL_MIN_SEVERITY".to_string(), "urgent".to_string());
+    assert!(load(&env).unwrap_err().contains("ALERT_EMAIL_MIN_SEVERITY"));
+    env.remove("ALERT_EMAIL_MIN_SEVERITY");
+    env.insert("SMTP_TLS".to_string(), "ssl".to_string());
+    assert!(load(&env).unwrap_err().contains("SMTP_TLS"));
+    for name in ["SMTP_HOST", "SMTP_PORT", "SMTP_TLS", "ALERT_EMAIL_TO", "ALERT_EMAIL_FROM"] {
+        env.remove(name);
+    }
+    assert_eq!(load(&env).unwrap().webhook_dedup_window, Some(Duration::from_secs(60)));
+    env.insert("WEBHOOK_DEDUP_WINDOW_SECS".to_string(), "0".to_string());
+    assert_eq!(load(&env).unwrap().webhook_dedup_window, None);
+    env.remove("WEBHOOK_DEDUP_WINDOW_SECS");
+    assert_eq!(load(&env).unwrap().webhook_rate_limits.symbol_signals_per_minute, Some(10));
+    env.insert("WEBHOOK_SOURCE_RATE_LIMIT".to_string(), "30/5".to_string());
+    env.insert("WEBHOOK_SYMBOL_SIGNALS_PER_MINUTE".to_string(), "off".to_string());
+    let limits = load(&env).unwrap().webhook_rate_limits;
+    assert_eq!((limits.per_source.map(|l| (l.per_minute, l.burst)), limits.symbol_signals_per_minute), (Some((30, 5)), None));
+    env.insert("WEBHOOK_RATE_LIMIT".to_string(), "fast".to_string());
+    assert!(load(&env).unwrap_err().contains("WEBHOOK_RATE_LIMIT"));
+    env.remove("WEBHOOK_RATE_LIMIT");
+    assert!(load(&env).unwrap().admin.is_none());
+    env.insert("ADMIN_PORT".to_string(), "9091".to_string());
+    assert!(load(&env).unwrap_err().contains("ADMIN_TOKEN"));
+    env.insert("ADMIN_TOKEN".to_string(), "admin-token".to_string());
+    let admin = load(&env).unwrap().admin.unwrap();
+    assert_eq!((admin.listen_addr.as_str(), admin.token.as_str()), ("0.0.0.0:9091", "admin-token"));
+    assert!(!format!("{:?}", admin).contains("admin-token"));
+    env.insert("ADMIN_LISTEN_ADDR".to_string(), "10.0.0.5:9091".to_string());
+    assert_eq!(load(&env).unwrap().admin.unwrap().listen_addr, "10.0.0.5:9091");
+    assert!(load(&env).unwrap().admin.unwrap().allowlist.is_none());
+    env.insert("ADMIN_ALLOWED_IPS".to_string(), "10.0.0.0/8".to_string());
+    assert_eq!(load(&env).unwrap().admin.unwrap().allowlist.unwrap().allowed.len(), 1);
+    env.insert("ADMIN_ALLOWED_IPS".to_string(), "office".to_string());
+    assert!(load(&env).unwrap_err().contains("ADMIN_ALLOWED_IPS"));
+    env.remove("ADMIN_ALLOWED_IPS");
+    env.insert("DASHBOARD_PORT".to_string(), "9092".to_string());
+    let dashboard = load(&env).unwrap().dashboard.unwrap();
+    assert_eq!((dashboard.listen_addr.as_str(), dashboard.token), ("0.0.0.0:9092", None));
+    env.insert("DASHBOARD_PORT".to_string(), "dash".to_string());
+    assert!(load(&env).unwrap_err().contains("DASHBOARD_PORT"));
+    for name in ["ADMIN_PORT", "ADMIN_LISTEN_ADDR", "ADMIN_TOKEN", "DASHBOARD_PORT"] {
+        env.remove(name);
+    }
+    let config = load(&env).unwrap();
+    assert_eq!(config.storage, Some(StorageConfig::Sqlite { path: PathBuf::from("/data/trading_bot.sqlite3") }));
+    assert_eq!(config.equity_snapshot_interval, Some(Duration::from_secs(300)));
+    env.insert("DATABASE_URL".to_string(), "postgres://bot:pw@db:5432/bot".to_string());
+    let storage = load(&env).unwrap().storage.unwrap();
+    assert_eq!(storage, StorageConfig::Postgres { url: "postgres://bot:pw@db:5432/bot".to_string() });
+    assert!(!format!("{:?}", storage).contains("pw"));
+    env.insert("STORAGE_BACKEND".to_string(), "sqlite".to_string());
+    env.insert("SQLITE_PATH".to_string(), "/data/history.db".to_string());
+    assert_eq!(load(&env).unwrap().storage, Some(StorageConfig::Sqlite { path: PathBuf::from("/data/history.db") }));
+    env.insert("STORAGE_BACKEND".to_string(), "none".to_string());
+    env.insert("EQUITY_SNAPSHOT_INTERVAL_SECS".to_string(), "0".to_string());
+    let config = load(&env).unwrap();
+    assert_eq!((config.storage, config.equity_snapshot_interval), (None, None));
+    env.insert("STORAGE_BACKEND".to_string(), "mysql".to_string());
+    assert!(load(&env).unwrap_err().contains("STORAGE_BACKEND"));
+    env.remove("DATABASE_URL");
+    env.insert("STORAGE_BACKEND".to_string(), "postgres".to_string());
+    assert!(load(&env).unwrap_err().contains("DATABASE_URL"));
+    for name in ["STORAGE_BACKEND", "SQLITE_PATH", "EQUITY_SNAPSHOT_INTERVAL_SECS"] {
+        env.remove(name);
+    }
+    env.insert("WEBHOOK_PORT".to_string(), "not-a-port".to_string());
+    assert!(load(&env).is_err());
+}
+
+#[test]
+fn test_metrics_rendering() {
+    let metrics = render_metrics(42);
+    assert!(metrics.contains("trading_bot_up 1"));
+    assert!(metrics.contains("trading_bot_uptime_seconds 42"));
+}
+
+#[test]
+fn test_subscription_profiles() {
+    let mut env = base_env();
+    env.insert("BINANCE_SECRET_KEY".to_string(), "secret".to_string());
+    env.insert("WEBHOOK_LOCAL_LISTEN_ADDR".to_string(), "127.0.0.1:3000".to_string());
+    env.insert("SUBSCRIPTION_PROFILES".to_string(), r#"{
+        "scalping": {"symbols": ["BTCUSDT", "ETHUSDT"], "streams": ["bookTicker", "aggTrade"]},
+        "swing": {"symbols": ["SOLUSDT"], "streams": ["kline_4h"]}
+    }"#.to_string());
+    let config = load(&env).unwrap();
+    assert_eq!(config.subscription_profiles.len(), 2);
+    assert_eq!(config.subscription_profiles[0].name, "scalping");
+    assert_eq!(config.subscription_profiles[0].stream_names(), vec![
+        "btcusdt@bookTicker", "btcusdt@aggTrade", "ethusdt@bookTicker", "ethusdt@aggTrade",
+    ]);
+
+    env.insert("ACTIVE_SUBSCRIPTION_PROFILES".to_string(), "swing".to_string());
+    let config = load(&env).unwrap();
+    assert_eq!(config.subscription_profiles.len(), 1);
+    assert_eq!(config.subscription_profiles[0].stream_names(), vec!["solusdt@kline_4h"]);
+
+    env.insert("ACTIVE_SUBSCRIPTION_PROFILES".to_string(), "unknown".to_string());
+    assert!(load(&env).is_err());
+}
@@ -0,0 +1,36 @@
+// tests/currency_tests.rs
+
+//! This file contains tests for converting reported amounts into the reporting currency.
+
+use trading_bot::currency::*;
+use trading_bot::performance::ClosedTrade;
+
+#[test]
+fn test_parse_and_format_currencies() {
+    assert_eq!(ReportingCurrency::parse("usdt").unwrap(), ReportingCurrency::Usd);
+    assert_eq!(ReportingCurrency::parse(" eur ").unwrap(), ReportingCurrency::Eur);
+    assert!(ReportingCurrency::parse("JPY").is_err());
+
+    assert_eq!(ReportingCurrency::Usd.format(-12.345), "$-12.35");
+    assert_eq!(ReportingCurrency::Eur.format(10.0), "€10.00");
+    assert_eq!(ReportingCurrency::Btc.format(0.000123456), "0.00012346 BTC");
+    assert_eq!(ReportingCurrency::Btc.conversion_symbol(), Some("BTCUSDT"));
+}
+
+#[test]
+fn test_converts_at_historical_prices() {
+    let converter = CurrencyConverter::historical(ReportingCurrency::Btc, vec![(2_000, 50_000.0), (1_000, 40_000.0)]).unwrap();
+    assert_eq!(converter.convert(400.0, 500), 0.01); // Before the first price: first price
+    assert_eq!(converter.convert(400.0, 1_999), 0.01);
+    assert_eq!(converter.convert(500.0, 2_000), 0.01);
+    assert_eq!(converter.convert_latest(100.0), 0.002);
+
+    let trades = vec![ClosedTrade { closed_at_ms: 2_500, symbol: "ETHUSDT".to_string(), pnl: 1_000.0, fees: 50.0, variant: None }];
+    let converted = converter.convert_trades(&trades);
+    assert_eq!((converted[0].pnl, converted[0].fees), (0.02, 0.001));
+
+    let usd = CurrencyConverter::fixed(ReportingCurrency::Usd, 1.1).unwrap();
+    assert_eq!(usd.convert(123.0, 0), 123.0); // The quote asset needs no conversion
+    assert!(CurrencyConverter::historical(ReportingCurrency::Eur, vec![]).is_err());
+    assert!(CurrencyConverter::fixed(ReportingCurrency::Eur, 0.0).is_err());
+}
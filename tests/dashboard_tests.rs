@@ -0,0 +1,60 @@
+// tests/dashboard_tests.rs
+
+//! This file contains tests for the dashboard event stream.
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use serde_json::Value;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use trading_bot::dashboard::*;
+use trading_bot::events::{BotEvent, EventLog};
+
+fn fill(realized_pnl: f64) -> BotEvent {
+    BotEvent::Fill {
+        symbol: "BTCUSDT".to_string(),
+        client_order_id: "whb1".to_string(),
+        side: "BUY".to_string(),
+        position_side: None,
+        price: 60000.0,
+        quantity: 0.01,
+        realized_pnl,
+        commission: 0.5,
+    }
+}
+
+#[tokio::test]
+async fn test_clients_get_a_snapshot_then_live_events() {
+    let dir = std::env::temp_dir().join(format!("dashboard_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let log = Arc::new(EventLog::open(dir.join("events.jsonl")).unwrap());
+    log.append(1_000, BotEvent::Signal { symbol: "BTCUSDT".to_string(), signal: "buy".to_string(), position_side: None, quote_quantity: None, variant: None }).unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let app = router(log.clone(), Some("dash-token"));
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    assert!(connect_async(format!("ws://{}/events", address)).await.is_err());
+    let plain = reqwest::get(format!("http://{}/events?token=dash-token", address)).await.unwrap();
+    assert!(plain.status().is_client_error()); // Not a WebSocket upgrade
+    let (mut socket, _) = connect_async(format!("ws://{}/events?token=dash-token", address)).await.unwrap();
+    let mut next = async || -> Value {
+        let Some(Ok(Message::Text(text))) = socket.next().await else { panic!("Expected a text message") };
+        serde_json::from_str(&text).unwrap()
+    };
+    let snapshot = next().await;
+    assert_eq!(snapshot["type"], "snapshot");
+    assert_eq!(snapshot["state"]["signals"], 1);
+
+    log.append(2_000, fill(10.0)).unwrap();
+    let event = next().await;
+    assert_eq!((event["type"].as_str(), event["seq"].as_u64()), (Some("event"), Some(2)));
+    assert_eq!(event["event"]["type"], "fill");
+    let pnl = next().await;
+    assert_eq!(pnl["type"], "pnl");
+    assert_eq!(pnl["realizedPnl"], 9.5);
+    assert_eq!(pnl["positions"]["BTCUSDT"]["quantity"], 0.01);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,119 @@
+// tests/dca_tests.rs
+
+//! This file contains offline tests for the DCA scale-in strategy (no network access needed).
+
+use serde_json::json;
+use trading_bot::market_event::{Candle, Symbol};
+use trading_bot::metrics::MetricsConfig;
+use trading_bot::order::bracket::{order_update_from_message, BracketAction};
+use trading_bot::order::{OrderSide, OrderType};
+use trading_bot::strategy::backtest;
+use trading_bot::strategy::dca::*;
+use trading_bot::streams::FuturesOrderUpdate;
+use trading_bot::websocket_stream::BinanceWsMessage;
+
+fn config() -> DcaConfig {
+    DcaConfig {
+        base_order_quantity: 1.0,
+        safety_order_quantity: 1.0,
+        max_safety_orders: 3,
+        price_deviation: 0.1,
+        step_scale: 1.0,
+        volume_scale: 2.0,
+        take_profit: 0.05,
+        step_size: 0.001,
+        tick_size: 0.01,
+        fee_rate: 0.0,
+        ..DcaConfig::default()
+    }
+}
+
+fn candle(open: f64, high: f64, low: f64, close: f64) -> Candle {
+    Candle {
+        symbol: Symbol::new("BTCUSDT").unwrap(),
+        event_time: 0,
+        interval_ms: 60_000,
+        open_time: 0,
+        close_time: 59_999,
+        open,
+        high,
+        low,
+        close,
+        volume: 1.0,
+        quote_volume: close,
+        trades: 1,
+        is_closed: true,
+    }
+}
+
+fn filled(client_order_id: &str, price: &str, quantity: &str) -> FuturesOrderUpdate {
+    let message = BinanceWsMessage::Raw(json!({
+        "e": "ORDER_TRADE_UPDATE", "E": 1, "T": 1,
+        "o": {
+            "s": "BTCUSDT", "c": client_order_id, "S": "BUY", "o": "LIMIT", "f": "GTC",
+            "q": quantity, "p": price, "ap": price, "x": "TRADE", "X": "FILLED", "i": 1,
+            "l": quantity, "z": quantity, "L": price, "T": 1, "t": 1
+        }
+    }));
+    order_update_from_message(&message).unwrap()
+}
+
+#[test]
+fn test_safety_orders_scale_and_average_entry() {
+    let levels = config().safety_orders(100.0);
+    assert_eq!(levels, vec![(90.0, 1.0), (80.0, 2.0), (70.0, 4.0)]);
+
+    let mut engine = DcaEngine::new(config()).unwrap();
+    engine.on_base_fill(100.0, 1.0).unwrap();
+    assert_eq!(engine.take_profit_price(), Some(105.0));
+    let take_profit = engine.on_safety_fill(0, 90.0, 1.0).unwrap();
+    assert_eq!(engine.cycle().unwrap().average_entry(), 95.0);
+    assert_eq!(take_profit, 99.75);
+
+    assert_eq!(engine.on_take_profit_fill(99.75), Some(9.5));
+    assert_eq!(engine.stats().cycles_completed, 1);
+    assert!(engine.cycle().is_none());
+
+    assert!(DcaEngine::new(DcaConfig { price_deviation: 0.5, ..config() }).is_err());
+}
+
+#[test]
+fn test_dca_backtest() {
+    let mut strategy = DcaStrategy::new(config()).unwrap();
+    let candles = vec![
+        candle(100.0, 100.0, 100.0, 100.0), // Base order at 100
+        candle(100.0, 95.0, 89.0, 92.0), // Red: no TP, first safety order at 90
+        candle(92.0, 99.5, 91.0, 99.0), // Green: TP at 99.75 not reached
+        candle(99.0, 101.0, 98.0, 100.0), // TP hit
+    ];
+    let result = backtest(&mut strategy, &candles, 1000.0, &MetricsConfig::default());
+    assert_eq!(result.fills.len(), 3);
+    assert_eq!(result.fills[2].side, OrderSide::Sell);
+    assert!((result.fills[2].realized_pnl - 9.5).abs() < 1e-9);
+    assert!((result.final_equity - 1009.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_dca_manager_cycle() {
+    let mut manager = DcaManager::new("dca1", config(), None, true).unwrap();
+    let actions = manager.start();
+    assert!(matches!(&actions[..], [BracketAction::PlaceOrder(r)] if r.order_type == OrderType::Market));
+
+    let actions = manager.on_order_update(&filled("dca1-c1b", "100", "1"));
+    assert_eq!(actions.len(), 4); // Three safety orders and the take profit
+    let BracketAction::PlaceOrder(take_profit) = &actions[3] else { panic!("expected the take profit") };
+    assert_eq!(take_profit.price, Some(105.0));
+    assert!(take_profit.reduce_only);
+
+    let actions = manager.on_order_update(&filled("dca1-c1s1", "90", "1"));
+    assert_eq!(actions[0], BracketAction::CancelOrder { symbol: "BTCUSDT".to_string(), client_order_id: "dca1-c1t1".to_string() });
+    let BracketAction::PlaceOrder(take_profit) = &actions[1] else { panic!("expected the new take profit") };
+    assert_eq!(take_profit.price, Some(99.75));
+    assert_eq!(take_profit.quantity, Some(2.0));
+
+    // Take profit fill cancels the remaining safety orders and restarts
+    let actions = manager.on_order_update(&filled("dca1-c1t2", "99.75", "2"));
+    assert_eq!(actions.len(), 3);
+    let BracketAction::PlaceOrder(next_base) = &actions[2] else { panic!("expected the next base order") };
+    assert_eq!(next_base.new_client_order_id.as_deref(), Some("dca1-c2b"));
+}
@@ -0,0 +1,102 @@
+// tests/downloader_tests.rs
+
+//! This file contains tests for the historical data downloader: request pacing, checkpoints and
+//! paginated kline downloads against a local mock of the klines endpoint.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use axum::{extract::Query, routing::get, Json, Router};
+use serde_json::{json, Value};
+use trading_bot::data::downloader::*;
+use trading_bot::market_data::KlineInterval;
+use trading_bot::rest_api::RestClient;
+
+const START_MS: u64 = 1_609_459_200_000; // 2021-01-01
+const MINUTE_MS: u64 = 60_000;
+const KLINES: u64 = 2_500;
+
+/// Serves 2500 one-minute klines from 2021-01-01, honoring startTime, endTime and limit.
+async fn klines(Query(params): Query<HashMap<String, String>>) -> Json<Value> {
+    let param = |name: &str| params.get(name).and_then(|v| v.parse::<u64>().ok());
+    let start = param("startTime").unwrap_or(START_MS);
+    let end = param("endTime").unwrap_or(u64::MAX);
+    let limit = param("limit").unwrap_or(500) as usize;
+    let rows: Vec<Value> = (0..KLINES)
+        .map(|i| START_MS + i * MINUTE_MS)
+        .filter(|open_time| *open_time >= start && *open_time <= end)
+        .take(limit)
+        .map(|open_time| json!([open_time, "1.0", "2.0", "0.5", "1.5", "10", open_time + MINUTE_MS - 1, "15", 3, "5", "7.5", "0"]))
+        .collect();
+    Json(Value::Array(rows))
+}
+
+async fn mock_client() -> RestClient {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, Router::new().route("/fapi/v1/klines", get(klines))).await.unwrap();
+    });
+    RestClient::new(String::new(), String::new(), format!("http://{}", addr))
+}
+
+#[test]
+fn test_weight_limiter_waits_for_next_window() {
+    let start = Instant::now();
+    let mut limiter = WeightLimiter::new(10);
+    assert_eq!(limiter.reserve(5, start), Duration::ZERO);
+    assert_eq!(limiter.reserve(5, start + Duration::from_secs(10)), Duration::ZERO);
+    assert_eq!(limiter.reserve(5, start + Duration::from_secs(20)), Duration::from_secs(40));
+    // The delayed request opened a window at 60s, which already holds its weight
+    assert_eq!(limiter.reserve(5, start + Duration::from_secs(60)), Duration::ZERO);
+    assert_eq!(limiter.reserve(5, start + Duration::from_secs(70)), Duration::from_secs(50));
+    assert_eq!(klines_weight(PAGE_SIZE), 5);
+}
+
+#[test]
+fn test_checkpoint_round_trip() {
+    let dir = std::env::temp_dir().join(format!("checkpoint_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = Checkpoint::path_for(&dir.join("BTCUSDT-1m-0.csv"));
+    assert!(path.to_str().unwrap().ends_with("BTCUSDT-1m-0.csv.checkpoint.json"));
+    assert_eq!(Checkpoint::load(&path).unwrap(), None);
+
+    let checkpoint = Checkpoint { next_start_ms: 42, next_from_id: Some(7), rows: 3 };
+    checkpoint.save(&path).unwrap();
+    assert_eq!(Checkpoint::load(&path).unwrap(), Some(checkpoint));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(OutputFormat::parse("parquet").unwrap(), OutputFormat::Parquet);
+    assert_eq!(OutputFormat::parse("columnar").unwrap(), OutputFormat::Parquet);
+    assert_eq!(OutputFormat::Parquet.extension(), "parquet");
+    assert!(OutputFormat::parse("json").is_err());
+}
+
+#[tokio::test]
+async fn test_download_resumes_and_extends_klines() {
+    let client = mock_client().await;
+    let dir = std::env::temp_dir().join(format!("downloader_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut request = DownloadRequest::new("btcusdt", KlineInterval::M1, START_MS, START_MS + 1_199 * MINUTE_MS);
+    request.output_dir = dir.clone();
+
+    let report = download(&client, &request).await.unwrap();
+    assert_eq!(report.klines_rows, 1_200); // Two pages
+    assert_eq!(report.klines_path, dir.join(format!("BTCUSDT-1m-{}.csv", START_MS)));
+
+    request.end_ms = START_MS + (KLINES - 1) * MINUTE_MS;
+    assert_eq!(download(&client, &request).await.unwrap().klines_rows, 1_300); // Resumed after the first run
+    assert_eq!(download(&client, &request).await.unwrap().klines_rows, 0);
+
+    let candles = trading_bot::strategy::load_candles(report.klines_path.to_str().unwrap(), "BTCUSDT").unwrap();
+    assert_eq!(candles.len(), KLINES as usize);
+    assert!(candles.windows(2).all(|w| w[1].open_time == w[0].open_time + MINUTE_MS));
+    assert_eq!(candles[0].close, 1.5);
+
+    request.format = OutputFormat::Parquet;
+    let report = download(&client, &request).await.unwrap();
+    assert_eq!(report.klines_rows, KLINES);
+    let columnar = trading_bot::strategy::load_candles(report.klines_path.to_str().unwrap(), "BTCUSDT").unwrap();
+    assert_eq!(columnar, candles);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
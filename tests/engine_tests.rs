@@ -0,0 +1,259 @@
+// tests/engine_tests.rs
+
+//! This file contains tests for the event-driven engine, its simulated and live execution, and the
+//! EMA pullback strategy running on it.
+
+use trading_bot::engine::live::{fill_from_update, to_order_request};
+use trading_bot::engine::*;
+use trading_bot::market_event::{Candle, MarkPrice, MarketEvent, Symbol};
+use trading_bot::metrics::MetricsConfig;
+use trading_bot::order::bracket::BracketAction;
+use trading_bot::order::OrderSide;
+use trading_bot::risk::{MaxExposure, RiskPercent, RiskPolicy};
+use trading_bot::strategy::ema_pullback::{self, EmaPullback};
+use trading_bot::strategy::margin::MarginConfig;
+use trading_bot::strategy::report::ExitReason;
+use trading_bot::strategy::{self, BacktestConfig};
+use trading_bot::streams::FuturesOrderUpdate;
+
+fn symbol() -> Symbol {
+    Symbol::new("BTCUSDT").unwrap()
+}
+
+fn kline(time_ms: u64, low: f64, high: f64, close: f64) -> MarketEvent {
+    MarketEvent::Kline(Candle {
+        symbol: symbol(),
+        event_time: time_ms,
+        interval_ms: 60_000,
+        open_time: time_ms - 59_999,
+        close_time: time_ms,
+        open: close,
+        high,
+        low,
+        close,
+        volume: 1.0,
+        quote_volume: close,
+        trades: 1,
+        is_closed: true,
+    })
+}
+
+fn order(client_id: &str, side: OrderSide, kind: OrderKind, quantity: f64, reduce_only: bool) -> OrderRequest {
+    OrderRequest { client_id: client_id.to_string(), symbol: symbol(), side, kind, quantity, reduce_only }
+}
+
+fn fill(side: OrderSide, price: f64, quantity: f64, fee: f64) -> FillEvent {
+    FillEvent { client_id: "f".to_string(), symbol: symbol(), time_ms: 0, side, price, quantity, fee }
+}
+
+/// Places the given orders on the first event it sees.
+struct Scripted {
+    orders: Vec<OrderEvent>,
+}
+
+impl EventStrategy for Scripted {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn on_market_event(&mut self, _event: &MarketEvent, _ctx: &StrategyContext) -> Vec<OrderEvent> {
+        std::mem::take(&mut self.orders)
+    }
+}
+
+#[test]
+fn test_portfolio_realizes_pnl_on_reductions_and_flips() {
+    let mut portfolio = Portfolio::new(1000.0);
+    assert_eq!(portfolio.apply(&fill(OrderSide::Buy, 100.0, 2.0, 0.2)), 0.0);
+    assert_eq!(portfolio.apply(&fill(OrderSide::Buy, 110.0, 2.0, 0.2)), 0.0);
+    assert_eq!(portfolio.positions[&symbol()].entry_price, 105.0);
+
+    // Selling 6 closes the 4 long at 120 and opens 2 short there
+    assert_eq!(portfolio.apply(&fill(OrderSide::Sell, 120.0, 6.0, 0.0)), 60.0);
+    assert_eq!(portfolio.position(symbol()), -2.0);
+    assert_eq!(portfolio.positions[&symbol()].entry_price, 120.0);
+    assert!((portfolio.cash - (1000.0 + 60.0 - 0.4)).abs() < 1e-9);
+
+    portfolio.mark(symbol(), 115.0);
+    assert_eq!(portfolio.exposure(symbol()), 230.0);
+    assert!((portfolio.equity() - (portfolio.cash + 10.0)).abs() < 1e-9);
+}
+
+#[test]
+fn test_simulated_stops_fill_before_limits_and_trim_to_the_position() {
+    let orders = vec![
+        OrderEvent::Place(order("en", OrderSide::Buy, OrderKind::Market, 1.0, false)),
+        OrderEvent::Place(order("tp", OrderSide::Sell, OrderKind::Limit(110.0), 1.0, true)),
+        OrderEvent::Place(order("sl", OrderSide::Sell, OrderKind::StopMarket(95.0), 5.0, true)),
+    ];
+    let mut execution = SimulatedExecution::new(0.001);
+    execution.slippage = 0.01;
+    let mut engine = Engine::new(Scripted { orders }, execution, 1000.0);
+
+    let fills = engine.on_market_event(&kline(60_000, 99.0, 101.0, 100.0));
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].price, 101.0); // Market buy pays the slippage
+    assert!((fills[0].fee - 0.101).abs() < 1e-9);
+    assert_eq!(engine.execution.resting_orders().len(), 2);
+
+    // The candle reaches both exits: the stop wins, capped at the one unit held, and the target is dropped
+    let fills = engine.on_market_event(&kline(120_000, 90.0, 120.0, 100.0));
+    assert_eq!(fills.len(), 1);
+    assert_eq!((fills[0].client_id.as_str(), fills[0].price, fills[0].quantity), ("sl", 95.0, 1.0));
+    assert!(engine.execution.resting_orders().is_empty());
+    assert_eq!(engine.portfolio.position(symbol()), 0.0);
+    assert!((engine.portfolio.cash - (1000.0 - 6.0 - 0.101 - 0.095)).abs() < 1e-9);
+}
+
+#[test]
+fn test_risk_policies_reject_orders_that_add_exposure() {
+    let orders = vec![
+        OrderEvent::Place(order("big", OrderSide::Buy, OrderKind::Market, 50.0, false)),
+        OrderEvent::Place(order("small", OrderSide::Buy, OrderKind::Market, 1.0, false)),
+    ];
+    let policies: Vec<Box<dyn RiskPolicy>> = vec![Box::new(MaxExposure { max_total_notional: 1000.0, max_symbol_notional: None })];
+    let mut engine = Engine::new(Scripted { orders }, SimulatedExecution::new(0.0), 1000.0).with_risk_policies(policies);
+    engine.on_market_event(&kline(60_000, 99.0, 101.0, 100.0));
+
+    assert_eq!(engine.rejections.len(), 1);
+    assert_eq!(engine.rejections[0].order.client_id, "big");
+    assert_eq!(engine.fills.len(), 1);
+    assert_eq!(engine.portfolio.position(symbol()), 1.0);
+}
+
+/// Goes long on every event unless funding is extremely positive.
+struct FundingAware {
+    seen: Vec<Option<f64>>,
+}
+
+impl EventStrategy for FundingAware {
+    fn name(&self) -> &str {
+        "funding_aware"
+    }
+
+    fn on_market_event(&mut self, _event: &MarketEvent, ctx: &StrategyContext) -> Vec<OrderEvent> {
+        self.seen.push(ctx.funding_rate);
+        if ctx.funding_rate.is_some_and(|rate| rate > 0.001) || ctx.position != 0.0 {
+            return vec![];
+        }
+        vec![OrderEvent::Place(order("long", OrderSide::Buy, OrderKind::Market, 1.0, false))]
+    }
+}
+
+#[test]
+fn test_strategies_see_the_predicted_funding_rate() {
+    let mut engine = Engine::new(FundingAware { seen: vec![] }, SimulatedExecution::new(0.0), 1000.0);
+    engine.set_funding(symbol(), 0.002, 28_800_000);
+    engine.on_market_event(&kline(60_000, 99.0, 101.0, 100.0));
+    assert!(engine.fills.is_empty());
+
+    // The mark price stream updates the rate
+    engine.on_market_event(&MarketEvent::MarkPrice(MarkPrice {
+        symbol: symbol(), event_time: 61_000, mark_price: 100.0, index_price: 100.0, funding_rate: 0.0001, next_funding_time: 28_800_000,
+    }));
+    assert_eq!(engine.strategy.seen, [Some(0.002), Some(0.0001)]);
+    assert_eq!(engine.fills.len(), 1);
+
+    // The position is valued at the mark price, not the last trade price
+    engine.on_market_event(&kline(120_000, 99.0, 106.0, 105.0));
+    assert!((engine.portfolio.equity() - 1000.0).abs() < 1e-9);
+    engine.on_market_event(&MarketEvent::MarkPrice(MarkPrice {
+        symbol: symbol(), event_time: 121_000, mark_price: 101.0, index_price: 101.0, funding_rate: 0.0001, next_funding_time: 28_800_000,
+    }));
+    assert!((engine.portfolio.unrealized_pnl() - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_live_execution_queues_exchange_orders_and_maps_fills() {
+    let request = to_order_request(&order("x-sl", OrderSide::Sell, OrderKind::StopMarket(95.0), 0.0129, true), Some(0.001)).unwrap();
+    let params = request.to_params();
+    assert_eq!((params["quantity"].as_str(), params["stopPrice"].as_str()), (Some("0.012"), Some("95")));
+    assert_eq!((params["reduceOnly"].as_str(), params["newClientOrderId"].as_str()), (Some("true"), Some("x-sl")));
+    assert!(to_order_request(&order("tiny", OrderSide::Buy, OrderKind::Market, 0.0004, false), Some(0.001)).is_err());
+
+    let mut execution = LiveExecution::new().with_step_size(symbol(), 0.001);
+    execution.submit(order("x-en", OrderSide::Buy, OrderKind::Market, 0.5, false), 0).unwrap();
+    execution.cancel(symbol(), "x-en").unwrap();
+    execution.cancel(symbol(), "someone-else").unwrap();
+    let actions = execution.take_actions();
+    assert_eq!(actions.len(), 2);
+    assert!(matches!(&actions[1], BracketAction::CancelOrder { client_order_id, .. } if client_order_id == "x-en"));
+
+    let update: FuturesOrderUpdate = serde_json::from_value(serde_json::json!({
+        "s": "BTCUSDT", "c": "x-en", "S": "BUY", "o": "MARKET", "f": "GTC", "q": "0.5", "p": "0", "ap": "100",
+        "sp": "0", "x": "TRADE", "X": "FILLED", "i": 1, "l": "0.5", "z": "0.5", "L": "100", "N": "USDT", "n": "0.02",
+        "T": 1000, "t": 7, "b": "0", "a": "0", "m": false, "R": false, "wt": "CONTRACT_PRICE", "ot": "MARKET",
+        "ps": "BOTH", "cp": false, "rp": "0",
+    })).unwrap();
+    let mapped = fill_from_update(&update).unwrap();
+    assert_eq!((mapped.side, mapped.price, mapped.quantity, mapped.fee), (OrderSide::Buy, 100.0, 0.5, 0.02));
+    execution.on_order_update(&update);
+    assert_eq!(execution.drain_fills().len(), 1);
+}
+
+#[test]
+fn test_ema_pullback_exits_at_the_liquidation_price_above_its_stop() {
+    let sizing = Box::new(RiskPercent { risk_fraction: 0.01 });
+    let margin = MarginConfig { leverage: 50.0, maintenance_margin_rate: 0.004 };
+    let mut engine = Engine::new(EmaPullback::new(symbol(), 2, 3, 1.0, sizing).with_margin(margin), SimulatedExecution::new(0.0), 1000.0);
+
+    // An uptrend, a pullback below the fast EMA, then a recovery whose low puts the stop 14% below the entry
+    for (i, close) in [100.0, 101.0, 102.0, 103.0, 104.0, 101.0].into_iter().enumerate() {
+        engine.on_market_event(&kline(60_000 * (i as u64 + 1), close - 0.5, close + 0.5, close));
+    }
+    let fills = engine.on_market_event(&kline(420_000, 90.0, 105.5, 105.0));
+    assert_eq!(fills.len(), 1);
+    let quantity = fills[0].quantity;
+    assert!((quantity - 10.0 / 15.0).abs() < 1e-9);
+    let liquidation_price = margin.open(OrderSide::Buy, 105.0, quantity).liquidation_price();
+    assert!(liquidation_price > 90.0);
+    assert_eq!(engine.execution.resting_orders().len(), 3);
+
+    // The liquidation comes before the stop and cancels the other exits
+    let fills = engine.on_market_event(&kline(480_000, 95.0, 104.0, 96.0));
+    assert_eq!(fills.len(), 1);
+    assert_eq!(ema_pullback::exit_reason(&fills[0].client_id), Some(ExitReason::Liquidation));
+    assert!((fills[0].price - liquidation_price).abs() < 1e-9);
+    assert!(engine.execution.resting_orders().is_empty());
+    assert_eq!(engine.portfolio.position(symbol()), 0.0);
+}
+
+#[tokio::test]
+async fn test_ema_pullback_matches_the_candle_backtest() {
+    let dir = std::env::temp_dir().join(format!("engine_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("candles.csv");
+    let output = dir.join("report.json");
+
+    // A rising market with regular pullbacks below the fast EMA
+    let mut csv = String::from("Open time,Open,High,Low,Close,Volume,Close time,Quote asset volume,Number of trades,Taker buy base asset volume,Taker buy quote asset volume,Ignore\n");
+    for i in 0..400u64 {
+        let close = 100.0 + i as f64 * 0.5 + 4.0 * (i as f64 * 0.7).sin();
+        let open_time = 1_609_459_200_000 + i * 14_400_000;
+        csv.push_str(&format!("{},{},{},{},{},1,{},1,1,0,0,0\n", open_time, close, close + 1.5, close - 1.5, close, open_time + 14_399_999));
+    }
+    std::fs::write(&data, csv).unwrap();
+
+    // Stops at the signal candle's low, then 1.5 ATRs below the entry, then volatility-target sizing
+    for stops in [&[][..], &["--atr-period", "14", "--atr-stop", "1.5"][..], &["--sizing", "volatility_target:0.01:2"][..]] {
+        let mut args: Vec<String> = ["--data", data.to_str().unwrap(), "--fast-ema", "5", "--slow-ema", "12", "--rr", "1.5", "--fee-rate", "0.0004", "--output", output.to_str().unwrap()]
+            .iter().map(|s| s.to_string()).collect();
+        args.extend(stops.iter().map(|s| s.to_string()));
+        let config = BacktestConfig::from_args(&args).unwrap();
+        strategy::run(&config).await.unwrap();
+        let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+        let trades = report["trades"].as_array().unwrap();
+        assert!(trades.len() > 3);
+
+        let candles = strategy::load_candles(data.to_str().unwrap(), "BTCUSDT").unwrap();
+        let mut engine = Engine::new(EmaPullback::from_config(symbol(), &config), SimulatedExecution::new(config.fee_rate), config.account_balance);
+        let result = backtest(&mut engine, candles.into_iter().map(MarketEvent::Kline), &MetricsConfig::default());
+
+        let exits: Vec<&FillEvent> = result.fills.iter().filter(|f| f.side == OrderSide::Sell).collect();
+        assert_eq!(exits.len(), trades.len());
+        assert!(result.rejections.is_empty());
+        let net: f64 = trades.iter().map(|t| t["pnl"].as_f64().unwrap()).sum();
+        assert!((result.final_equity - (config.account_balance + net)).abs() < 1e-6);
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
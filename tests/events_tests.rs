@@ -0,0 +1,74 @@
+// tests/events_tests.rs
+
+//! This file contains tests for the event log and the deterministic replay of the bot's state.
+
+use serde_json::json;
+use trading_bot::events::*;
+
+fn fill(side: &str, price: f64, quantity: f64, realized_pnl: f64) -> BotEvent {
+    BotEvent::Fill {
+        symbol: "BTCUSDT".to_string(),
+        client_order_id: "wh1".to_string(),
+        side: side.to_string(),
+        position_side: None,
+        price,
+        quantity,
+        realized_pnl,
+        commission: 0.0,
+    }
+}
+
+fn records() -> Vec<EventRecord> {
+    let events = vec![
+        BotEvent::ConfigChange { key: "experiment".to_string(), value: json!(null) },
+        BotEvent::Signal { symbol: "BTCUSDT".to_string(), signal: "buy".to_string(), position_side: None, quote_quantity: None, variant: None },
+        fill("BUY", 100.0, 1.0, 0.0),
+        fill("BUY", 110.0, 1.0, 0.0),
+        BotEvent::Signal { symbol: "BTCUSDT".to_string(), signal: "close_long".to_string(), position_side: None, quote_quantity: None, variant: None },
+        fill("SELL", 120.0, 2.0, 30.0),
+    ];
+    events.into_iter().enumerate()
+        .map(|(i, event)| EventRecord { seq: i as u64 + 1, time_ms: 1_000 * (i as i64 + 1), event })
+        .collect()
+}
+
+#[test]
+fn test_replay_reconstructs_past_state() {
+    let records = records();
+    let at = replay(&records, Some(4_000));
+    assert_eq!(at.last_seq, 4);
+    assert_eq!(at.positions["BTCUSDT"], PositionState { quantity: 2.0, entry_price: 105.0 });
+
+    let now = replay(&records, None);
+    assert!(now.positions.is_empty());
+    assert_eq!(now.realized_pnl, 30.0);
+    assert_eq!(now.signals, 2);
+    assert_eq!(replay(&records, None), now); // Deterministic
+
+    let (before_close, record) = state_before(&records, 5).unwrap();
+    assert!(matches!(&record.event, BotEvent::Signal { signal, .. } if signal == "close_long"));
+    assert_eq!(before_close.positions["BTCUSDT"].quantity, 2.0);
+    assert_eq!(records_between(&records, 2_000, 3_000).len(), 2);
+}
+
+#[test]
+fn test_event_log_round_trip() {
+    let path = std::env::temp_dir().join(format!("events_test_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let log = EventLog::open(&path).unwrap();
+        for record in records().into_iter().take(3) {
+            log.append(record.time_ms, record.event).unwrap();
+        }
+    }
+    // Reopening continues the sequence
+    let log = EventLog::open(&path).unwrap();
+    let record = log.append(9_000, fill("SELL", 90.0, 1.0, -10.0)).unwrap();
+    assert_eq!(record.seq, 4);
+
+    let loaded = load_events(&path).unwrap();
+    assert_eq!(loaded.len(), 4);
+    assert_eq!(loaded[..3], records()[..3]);
+    assert!(replay(&loaded, None).positions.is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
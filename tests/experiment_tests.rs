@@ -0,0 +1,61 @@
+// tests/experiment_tests.rs
+
+//! This file contains tests for live A/B testing of strategy parameters.
+
+use trading_bot::experiment::*;
+use trading_bot::performance::ClosedTrade;
+
+const EXPERIMENT_JSON: &str = r#"{
+    "name": "ema-periods",
+    "strategy": "ema_crossover",
+    "variants": [
+        {"tag": "a", "budgetShare": 0.5, "params": {"fastEma": 21, "slowEma": 55}},
+        {"tag": "b", "budgetShare": 0.5, "params": {"fastEma": 13, "slowEma": 34}}
+    ]
+}"#;
+
+fn trade(variant: &str, pnl: f64) -> ClosedTrade {
+    ClosedTrade { closed_at_ms: 0, symbol: "BTCUSDT".to_string(), pnl, fees: 0.0, variant: Some(variant.to_string()) }
+}
+
+#[test]
+fn test_parse_and_budget_split() {
+    let experiment = parse_experiment(EXPERIMENT_JSON).unwrap();
+    assert_eq!(experiment.variant("b").unwrap().params["fastEma"], 13.0);
+    assert_eq!(experiment.budget_for("a", 1000.0).unwrap(), 500.0);
+    assert!(experiment.budget_for("c", 1000.0).is_err());
+
+    let oversubscribed = EXPERIMENT_JSON.replace("\"budgetShare\": 0.5, \"params\": {\"fastEma\": 13", "\"budgetShare\": 0.6, \"params\": {\"fastEma\": 13");
+    assert!(parse_experiment(&oversubscribed).is_err());
+    let duplicate = EXPERIMENT_JSON.replace("\"tag\": \"b\"", "\"tag\": \"a\"");
+    assert!(parse_experiment(&duplicate).is_err());
+}
+
+#[test]
+fn test_client_order_id_tagging() {
+    let id = tag_client_order_id("whb123456", "a");
+    assert_eq!(id, "whb123456-va");
+    assert_eq!(variant_of(&id), Some("a"));
+    assert_eq!(variant_of("whb123456"), None);
+}
+
+#[test]
+fn test_compare_variants_attributes_trades() {
+    let experiment = parse_experiment(EXPERIMENT_JSON).unwrap();
+    let mut trades = vec![ClosedTrade { variant: None, ..trade("a", 100.0) }];
+    for i in 0..40 {
+        trades.push(trade("a", 10.0 + (i % 3) as f64));
+        trades.push(trade("b", -5.0 + (i % 4) as f64));
+    }
+
+    let report = compare_variants(&experiment, &trades, 1000.0);
+    assert_eq!(report.variants[0].trades, 40);
+    assert_eq!(report.variants[1].trades, 40);
+    assert!((report.variants[0].mean_return - 11.0 * 40.0 / 40.0 / 500.0).abs() < 1e-3);
+    assert!(report.t_statistic.unwrap() > 0.0);
+    assert!(report.significant);
+
+    // Too few trades are never significant
+    let report = compare_variants(&experiment, &trades[..11], 1000.0);
+    assert!(!report.significant);
+}
@@ -0,0 +1,31 @@
+// tests/funding_tests.rs
+
+//! This file contains tests for decoding the premium index and funding rate history responses.
+
+use trading_bot::market_data::{next_funding_page_start, FundingRate, PremiumIndex};
+
+#[test]
+fn test_parse_premium_indexes() {
+    let body = r#"[{"symbol":"BTCUSDT","markPrice":"30010.50000000","indexPrice":"30008.21000000","estimatedSettlePrice":"30009.10000000","lastFundingRate":"0.00010000","interestRate":"0.00010000","nextFundingTime":1700006400000,"time":1700000000000},
+                   {"symbol":"ETHUSDT","markPrice":"1810.20","indexPrice":"1810.00","estimatedSettlePrice":"1810.10","lastFundingRate":"-0.00025000","interestRate":"0.00010000","nextFundingTime":1700006400000,"time":1700000000000}]"#;
+    let indexes: Vec<PremiumIndex> = serde_json::from_str(body).unwrap();
+    assert_eq!(indexes.len(), 2);
+    assert_eq!(indexes[0].funding_rate(), 0.0001);
+    assert_eq!(indexes[1].funding_rate(), -0.00025);
+    assert_eq!(indexes[1].next_funding_time, 1_700_006_400_000);
+}
+
+#[test]
+fn test_parse_funding_history_and_paginate() {
+    // Old settlements come without a mark price
+    let body = r#"[{"symbol":"BTCUSDT","fundingRate":"0.00010000","fundingTime":1000},
+                   {"symbol":"BTCUSDT","fundingRate":"-0.00003000","fundingTime":2000,"markPrice":"30000.1"}]"#;
+    let page: Vec<FundingRate> = serde_json::from_str(body).unwrap();
+    assert_eq!(page[0].mark_price, "");
+    assert_eq!(page[1].rate(), -0.00003);
+
+    assert_eq!(next_funding_page_start(&page, 2, 10_000), Some(2001));
+    assert_eq!(next_funding_page_start(&page, 3, 10_000), None); // Short page
+    assert_eq!(next_funding_page_start(&page, 2, 2000), None); // Reached the end
+    assert_eq!(next_funding_page_start(&[], 2, 10_000), None);
+}
@@ -0,0 +1,69 @@
+// tests/genetic_tests.rs
+
+//! This file contains tests for the genetic parameter optimizer.
+
+use trading_bot::strategy::genetic::*;
+use trading_bot::strategy::BacktestConfig;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_genetic_settings() {
+    assert_eq!(parse_bounds("1:4").unwrap(), (1.0, 4.0));
+    assert!(parse_bounds("4:1").is_err());
+    assert!(parse_bounds("0:1").is_err());
+
+    let (config, rest) = GeneticConfig::from_args(&args(&["--population", "10", "--rr-bounds", "1:3", "--seed", "7", "--balance", "2000"])).unwrap();
+    assert_eq!((config.population, config.rr_bounds, config.seed), (10, (1.0, 3.0), Some(7)));
+    assert_eq!(rest, args(&["--balance", "2000"]));
+
+    assert!(GeneticConfig::from_args(&args(&["--population", "4", "--elite", "4"])).is_err());
+    assert!(GeneticConfig::from_args(&args(&["--mutation-rate", "1.5"])).is_err());
+    assert!(GeneticConfig::from_args(&args(&["--fast-range", "30:40", "--slow-range", "10:30"])).is_err());
+}
+
+#[tokio::test]
+async fn test_genetic_search_improves_and_is_reproducible() {
+    let dir = std::env::temp_dir().join(format!("genetic_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("candles.csv");
+    let output = dir.join("genetic.json");
+
+    // A rising market with regular pullbacks below the fast EMA
+    let mut csv = String::from("Open time,Open,High,Low,Close,Volume,Close time,Quote asset volume,Number of trades,Taker buy base asset volume,Taker buy quote asset volume,Ignore\n");
+    for i in 0..400u64 {
+        let close = 100.0 + i as f64 * 0.5 + 4.0 * (i as f64 * 0.7).sin();
+        let open_time = 1_609_459_200_000 + i * 14_400_000;
+        csv.push_str(&format!("{},{},{},{},{},1,{},1,1,0,0,0\n", open_time, close, close + 1.5, close - 1.5, close, open_time + 14_399_999));
+    }
+    std::fs::write(&data, csv).unwrap();
+
+    let (genetic, rest) = GeneticConfig::from_args(&args(&[
+        "--data", data.to_str().unwrap(), "--fast-range", "2:10", "--slow-range", "5:30", "--rr-bounds", "0.5:3", "--risk-bounds", "0.005:0.02",
+        "--population", "12", "--generations", "6", "--objective", "net_pnl", "--seed", "11", "--output", output.to_str().unwrap(),
+    ])).unwrap();
+    let config = BacktestConfig::from_args(&rest).unwrap();
+    let report = run(&config, &genetic).await.unwrap();
+
+    assert_eq!(report.generations.len(), 6);
+    // The elite carries over, so the best fitness never drops
+    assert!(report.generations.windows(2).all(|w| w[1].best_score >= w[0].best_score));
+    assert_eq!(report.best[0].score, report.generations[5].best_score);
+    assert!(report.best.windows(2).all(|w| w[0].score >= w[1].score));
+    let evaluated: usize = report.generations.iter().map(|g| g.evaluated).sum();
+    assert_eq!(report.best.len(), evaluated);
+    for result in &report.best {
+        assert!(result.fast_ema_period < result.slow_ema_period);
+        assert!((2..=10).contains(&result.fast_ema_period) && (5..=30).contains(&result.slow_ema_period));
+        assert!((0.5..=3.0).contains(&result.risk_reward_ratio) && (0.005..=0.02).contains(&result.risk_percentage));
+    }
+
+    // The same seed reproduces the search
+    let again = run(&BacktestConfig { output: None, ..config.clone() }, &GeneticConfig { threads: 1, ..genetic.clone() }).await.unwrap();
+    assert_eq!(again.best, report.best);
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(written["generations"].as_array().map(Vec::len), Some(6));
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,104 @@
+// tests/grid_tests.rs
+
+//! This file contains offline tests for the grid trading strategy (no network access needed).
+
+use serde_json::json;
+use trading_bot::market_event::{Candle, Symbol};
+use trading_bot::metrics::MetricsConfig;
+use trading_bot::order::bracket::{order_update_from_message, BracketAction};
+use trading_bot::order::OrderSide;
+use trading_bot::strategy::grid::*;
+use trading_bot::strategy::{backtest, Strategy};
+use trading_bot::websocket_stream::BinanceWsMessage;
+
+fn config() -> GridConfig {
+    GridConfig {
+        symbol: "BTCUSDT".to_string(),
+        reference_price: 100.0,
+        levels_per_side: 2,
+        spacing: GridSpacing::Arithmetic(10.0),
+        quantity_per_level: 1.0,
+        tick_size: 0.1,
+        fee_rate: 0.0,
+    }
+}
+
+fn candle(open: f64, high: f64, low: f64, close: f64) -> Candle {
+    Candle {
+        symbol: Symbol::new("BTCUSDT").unwrap(),
+        event_time: 0,
+        interval_ms: 60_000,
+        open_time: 0,
+        close_time: 59_999,
+        open,
+        high,
+        low,
+        close,
+        volume: 1.0,
+        quote_volume: close,
+        trades: 1,
+        is_closed: true,
+    }
+}
+
+#[test]
+fn test_grid_levels_and_rearm() {
+    let mut engine = GridEngine::new(config()).unwrap();
+    assert_eq!(engine.prices(), &[80.0, 90.0, 100.0, 110.0, 120.0]);
+    let sides: Vec<OrderSide> = engine.working_orders().iter().map(|o| o.side).collect();
+    assert_eq!(sides, vec![OrderSide::Buy, OrderSide::Buy, OrderSide::Sell, OrderSide::Sell]);
+
+    // A filled buy re-arms a sell one level up; the round trip captures one grid step
+    let rearmed = engine.on_fill(1, 90.0).unwrap();
+    assert_eq!((rearmed.level, rearmed.side, rearmed.price), (2, OrderSide::Sell, 100.0));
+    let rearmed = engine.on_fill(2, 100.0).unwrap();
+    assert_eq!((rearmed.level, rearmed.side), (1, OrderSide::Buy));
+    assert_eq!(engine.stats().realized_pnl, 10.0);
+    assert_eq!(engine.stats().position, 0.0);
+
+    let geometric = GridConfig { spacing: GridSpacing::Geometric(0.5), levels_per_side: 3, ..config() };
+    assert!(GridEngine::new(geometric).unwrap().prices()[0] > 0.0);
+    assert!(GridEngine::new(GridConfig { spacing: GridSpacing::Arithmetic(60.0), ..config() }).is_err());
+}
+
+#[test]
+fn test_grid_backtest() {
+    let mut strategy = GridStrategy::new(config()).unwrap();
+    let candles = vec![
+        candle(100.0, 101.0, 89.0, 95.0), // Buys at 90
+        candle(95.0, 101.0, 94.0, 100.0), // Sells at 100
+        candle(100.0, 100.5, 99.0, 100.0), // Nothing
+    ];
+    let result = backtest(&mut strategy, &candles, 1000.0, &MetricsConfig::default());
+    assert_eq!(result.fills.len(), 2);
+    assert_eq!(result.fills[1].realized_pnl, 10.0);
+    assert_eq!(result.final_equity, 1010.0);
+    assert_eq!(strategy.pnl(100.0), 10.0);
+    assert_eq!(result.equity_curve, vec![1005.0, 1010.0, 1010.0]);
+}
+
+#[test]
+fn test_grid_manager_rearms_on_fill() {
+    let mut manager = GridManager::new("grid1", config(), None).unwrap();
+    let actions = manager.start();
+    assert_eq!(actions.len(), 4);
+
+    let message = BinanceWsMessage::Raw(json!({
+        "e": "ORDER_TRADE_UPDATE", "E": 1, "T": 1,
+        "o": {
+            "s": "BTCUSDT", "c": "grid1-g2", "S": "BUY", "o": "LIMIT", "f": "GTC",
+            "q": "1", "p": "90", "ap": "90", "x": "TRADE", "X": "FILLED", "i": 1,
+            "l": "1", "z": "1", "L": "90", "T": 1, "t": 1
+        }
+    }));
+    let actions = manager.on_order_update(&order_update_from_message(&message).unwrap());
+    match actions.as_slice() {
+        [BracketAction::PlaceOrder(request)] => {
+            assert_eq!(request.side, OrderSide::Sell);
+            assert_eq!(request.price, Some(100.0));
+            assert_eq!(request.new_client_order_id.as_deref(), Some("grid1-g5"));
+        },
+        other => panic!("expected a re-armed order, got {:?}", other),
+    }
+    assert_eq!(manager.stop().len(), 4);
+}
@@ -0,0 +1,98 @@
+// tests/iceberg_tests.rs
+
+//! This file contains offline tests for iceberg order slicing (no network access needed).
+
+use serde_json::json;
+use trading_bot::order::bracket::{order_update_from_message, BracketAction};
+use trading_bot::order::iceberg::*;
+use trading_bot::order::{NewOrderRequest, OrderSide, TimeInForce};
+use trading_bot::streams::FuturesOrderUpdate;
+use trading_bot::websocket_stream::BinanceWsMessage;
+
+fn iceberg() -> IcebergOrder {
+    IcebergOrder {
+        id: "ice1".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        price: 100.0,
+        total_quantity: 1.0,
+        clip_quantity: 0.4,
+        step_size: 0.001,
+        time_in_force: TimeInForce::Gtx,
+        reduce_only: false,
+        position_side: None,
+    }
+}
+
+fn update(client_order_id: &str, status: &str, cumulative: &str) -> FuturesOrderUpdate {
+    let message = BinanceWsMessage::Raw(json!({
+        "e": "ORDER_TRADE_UPDATE", "E": 1, "T": 1,
+        "o": {
+            "s": "BTCUSDT", "c": client_order_id, "S": "BUY", "o": "LIMIT", "f": "GTX",
+            "q": "0.4", "p": "100", "ap": "100", "x": "TRADE", "X": status, "i": 1,
+            "l": cumulative, "z": cumulative, "L": "100", "T": 1, "t": 1
+        }
+    }));
+    order_update_from_message(&message).unwrap()
+}
+
+fn placed(actions: &[BracketAction]) -> &NewOrderRequest {
+    match actions {
+        [BracketAction::PlaceOrder(request)] => request,
+        other => panic!("expected a single placed order, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_iceberg_posts_clips_until_filled() {
+    let mut manager = IcebergManager::new();
+    let first = manager.start(iceberg()).unwrap();
+    let clip = placed(&first);
+    assert_eq!(clip.quantity, Some(0.4));
+    assert_eq!(clip.price, Some(100.0));
+    assert_eq!(clip.time_in_force, Some(TimeInForce::Gtx));
+    assert_eq!(clip.new_client_order_id.as_deref(), Some("ice1-c1"));
+
+    // Partial fills of the working clip do not post anything
+    assert!(manager.on_order_update(&update("ice1-c1", "PARTIALLY_FILLED", "0.1")).is_empty());
+    assert!((manager.get("ice1").unwrap().remaining_quantity() - 0.9).abs() < 1e-9);
+
+    let second = manager.on_order_update(&update("ice1-c1", "FILLED", "0.4"));
+    assert_eq!(placed(&second).quantity, Some(0.4));
+
+    // The last clip only covers what is left
+    let third = manager.on_order_update(&update("ice1-c2", "FILLED", "0.4"));
+    assert!((placed(&third).quantity.unwrap() - 0.2).abs() < 1e-9);
+
+    assert!(manager.on_order_update(&update("ice1-c3", "FILLED", "0.2")).is_empty());
+    let tracked = manager.get("ice1").unwrap();
+    assert_eq!(tracked.state, IcebergState::Filled);
+    assert_eq!(tracked.clips_posted, 3);
+    assert!((tracked.filled_quantity - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_iceberg_cancel_and_exchange_expiry() {
+    let mut manager = IcebergManager::new();
+    manager.start(iceberg()).unwrap();
+    let actions = manager.cancel("ice1").unwrap();
+    assert_eq!(actions, vec![BracketAction::CancelOrder { symbol: "BTCUSDT".to_string(), client_order_id: "ice1-c1".to_string() }]);
+    assert_eq!(manager.get("ice1").unwrap().state, IcebergState::Cancelled);
+
+    // A post-only clip expired by the exchange stops the iceberg
+    let mut manager = IcebergManager::new();
+    manager.start(iceberg()).unwrap();
+    assert!(manager.on_order_update(&update("ice1-c1", "EXPIRED", "0")).is_empty());
+    let tracked = manager.get("ice1").unwrap();
+    assert_eq!(tracked.state, IcebergState::Cancelled);
+    assert_eq!(tracked.working_clip(), None);
+}
+
+#[test]
+fn test_iceberg_validation() {
+    let mut manager = IcebergManager::new();
+    assert!(manager.start(IcebergOrder { clip_quantity: 2.0, ..iceberg() }).is_err());
+    assert!(manager.start(IcebergOrder { clip_quantity: 0.0001, ..iceberg() }).is_err());
+    manager.start(iceberg()).unwrap();
+    assert!(manager.start(iceberg()).is_err());
+}
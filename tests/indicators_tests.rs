@@ -0,0 +1,160 @@
+// tests/indicators_tests.rs
+
+//! This file contains tests for the incremental indicators, checked against direct computations
+//! over the whole window.
+
+use trading_bot::indicators::*;
+
+fn closes() -> Vec<f64> {
+    (0..120).map(|i| 100.0 + i as f64 * 0.3 + 5.0 * (i as f64 * 0.4).sin()).collect()
+}
+
+fn close_enough(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9 * b.abs().max(1.0)
+}
+
+#[test]
+fn test_sma_and_ema() {
+    let closes = closes();
+    let mut sma = Sma::new(10);
+    let mut ema = Ema::new(10);
+    let mut reference_ema = 0.0;
+    for (i, &close) in closes.iter().enumerate() {
+        let value = sma.update(close);
+        let ema_value = ema.update(close);
+        if i < 9 {
+            assert!(value.is_none() && ema_value.is_none());
+            continue;
+        }
+        let window = &closes[i - 9..=i];
+        assert!(close_enough(value.unwrap(), window.iter().sum::<f64>() / 10.0));
+        reference_ema = if i == 9 { value.unwrap() } else { (close - reference_ema) * 2.0 / 11.0 + reference_ema };
+        assert!(close_enough(ema_value.unwrap(), reference_ema));
+    }
+}
+
+#[test]
+fn test_macd_is_the_ema_difference_with_its_signal() {
+    let closes = closes();
+    let mut macd = Macd::new(12, 26, 9);
+    let (mut fast, mut slow, mut signal) = (Ema::new(12), Ema::new(26), Ema::new(9));
+    let mut seen = 0;
+    for &close in &closes {
+        let value = macd.update(close);
+        let line = fast.update(close).zip(slow.update(close)).map(|(f, s)| f - s);
+        let expected = line.and_then(|line| signal.update(line).map(|signal| (line, signal)));
+        match (value, expected) {
+            (Some(value), Some((line, signal))) => {
+                seen += 1;
+                assert!(close_enough(value.macd, line) && close_enough(value.signal, signal));
+                assert!(close_enough(value.histogram, line - signal));
+            },
+            (None, None) => {},
+            other => panic!("MACD availability differs: {:?}", other),
+        }
+    }
+    // The signal needs 26 + 9 - 1 closes
+    assert_eq!(seen, closes.len() - 33);
+}
+
+#[test]
+fn test_bollinger_bands() {
+    let closes = closes();
+    let mut bands = BollingerBands::new(20, 2.0);
+    for (i, &close) in closes.iter().enumerate() {
+        let value = bands.update(close);
+        if i < 19 {
+            assert!(value.is_none());
+            continue;
+        }
+        let window = &closes[i - 19..=i];
+        let mean = window.iter().sum::<f64>() / 20.0;
+        let deviation = (window.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / 20.0).sqrt();
+        let value = value.unwrap();
+        assert!(close_enough(value.middle, mean));
+        assert!((value.upper - (mean + 2.0 * deviation)).abs() < 1e-6);
+        assert!((value.lower - (mean - 2.0 * deviation)).abs() < 1e-6);
+    }
+    // A flat window has no width
+    let mut flat = BollingerBands::new(5, 2.0);
+    let value = (0..5).map(|_| flat.update(0.1)).last().flatten().unwrap();
+    assert!(close_enough(value.upper, 0.1) && close_enough(value.lower, 0.1));
+}
+
+#[test]
+fn test_stochastic() {
+    let closes = closes();
+    let bars: Vec<(f64, f64, f64)> = closes.iter().enumerate().map(|(i, &c)| (c + 1.0 + (i % 3) as f64, c - 1.0 - (i % 4) as f64, c)).collect();
+    let mut stochastic = Stochastic::new(14, 3);
+    let mut ks = Vec::new();
+    for (i, &(high, low, close)) in bars.iter().enumerate() {
+        let value = stochastic.update(high, low, close);
+        if i < 13 {
+            assert!(value.is_none());
+            continue;
+        }
+        let window = &bars[i - 13..=i];
+        let highest = window.iter().map(|b| b.0).fold(f64::MIN, f64::max);
+        let lowest = window.iter().map(|b| b.1).fold(f64::MAX, f64::min);
+        ks.push(100.0 * (close - lowest) / (highest - lowest));
+        match value {
+            Some(value) => {
+                assert!(close_enough(value.k, *ks.last().unwrap()));
+                assert!(close_enough(value.d, ks[ks.len() - 3..].iter().sum::<f64>() / 3.0));
+                assert!((0.0..=100.0).contains(&value.k));
+            },
+            None => assert!(ks.len() < 3),
+        }
+    }
+    // Without a range %K sits in the middle
+    let mut flat = Stochastic::new(2, 1);
+    flat.update(10.0, 10.0, 10.0);
+    assert_eq!(flat.update(10.0, 10.0, 10.0).map(|v| v.k), Some(50.0));
+}
+
+#[test]
+fn test_atr_and_rsi_warm_up() {
+    let mut atr = Atr::new(3);
+    assert!(atr.update(11.0, 9.0, 10.0).is_none());
+    assert!(atr.update(12.0, 10.0, 11.0).is_none());
+    assert_eq!(atr.update(14.0, 11.0, 13.0), Some((2.0 + 2.0 + 3.0) / 3.0));
+
+    let mut rsi = Rsi::new(2);
+    assert!(rsi.update(10.0).is_none());
+    assert!(rsi.update(11.0).is_none());
+    assert_eq!(rsi.update(12.0), Some(100.0));
+}
+
+#[test]
+fn test_vwap_sessions() {
+    assert_eq!(VwapSession::parse("daily").unwrap(), VwapSession::daily());
+    assert_eq!(VwapSession::parse("funding").unwrap().period_ms, 8 * 3_600_000);
+    let session = VwapSession::parse("daily+13h30m").unwrap();
+    assert_eq!(session.offset_ms, 13 * 3_600_000 + 30 * 60_000);
+    let day = 86_400_000;
+    assert_eq!(session.start(10 * day + 14 * 3_600_000), 10 * day + session.offset_ms);
+    assert_eq!(session.start(10 * day + 3_600_000), 9 * day + session.offset_ms);
+    assert!(VwapSession::parse("4x").is_err());
+    assert!(VwapSession::parse("0h").is_err());
+}
+
+#[test]
+fn test_vwap_resets_at_the_session_boundary() {
+    let hour = 3_600_000;
+    let mut vwap = Vwap::new(VwapSession::funding());
+    assert!(vwap.update(hour, 100.0, 0.0).is_none());
+    vwap.update(hour, 100.0, 1.0);
+    let value = vwap.update(2 * hour, 103.0, 2.0).unwrap();
+    assert!(close_enough(value.vwap, 102.0));
+    // Prices 100 (weight 1) and 103 (weight 2): variance (4 + 2 * 1) / 3
+    assert!(close_enough(value.deviation, 2.0f64.sqrt()));
+    let (upper, lower) = value.band(2.0);
+    assert!(close_enough(upper - lower, 4.0 * 2.0f64.sqrt()));
+    assert!(close_enough(value.z_score(102.0 + 2.0f64.sqrt()), 1.0));
+
+    // 08:00 UTC starts a new session
+    let value = vwap.update_bar(8 * hour, 92.0, 88.0, 90.0, 5.0).unwrap();
+    assert_eq!((value.vwap, value.deviation, value.session_start_ms), (90.0, 0.0, 8 * hour));
+    // Late updates of the previous session are ignored
+    assert_eq!(vwap.update(7 * hour, 200.0, 10.0).unwrap().vwap, 90.0);
+}
@@ -0,0 +1,109 @@
+// tests/journal_tests.rs
+
+//! This file contains tests for the trade journal: grouping fills into round trips, linking them
+//! to their signals, notes, and the CSV export.
+
+use serde_json::json;
+
+use trading_bot::journal::{load_journal, round_trips, to_csv};
+use trading_bot::storage::{FillRecord, SignalRecord, SqliteStorage, Storage};
+
+fn fill(time_ms: i64, client_order_id: &str, side: &str, price: f64, quantity: f64, realized_pnl: f64) -> FillRecord {
+    FillRecord {
+        time_ms,
+        symbol: "BTCUSDT".to_string(),
+        client_order_id: client_order_id.to_string(),
+        side: side.to_string(),
+        position_side: None,
+        price,
+        quantity,
+        realized_pnl,
+        commission: 0.1,
+    }
+}
+
+fn signal(client_order_id: &str) -> SignalRecord {
+    SignalRecord {
+        time_ms: 1_000,
+        tracking_id: Some("sig-1".to_string()),
+        symbol: "BTCUSDT".to_string(),
+        signal: "buy".to_string(),
+        payload: json!({ "symbol": "BTCUSDT", "signal": "buy", "strategy": "breakout" }),
+        outcome: "placed".to_string(),
+        reason: None,
+        client_order_id: Some(client_order_id.to_string()),
+    }
+}
+
+#[test]
+fn test_fills_are_grouped_into_round_trips() {
+    let fills = vec![
+        fill(1_000, "whb1", "BUY", 100.0, 1.0, 0.0),
+        fill(2_000, "whb2", "BUY", 110.0, 1.0, 0.0), // Scales in
+        fill(61_000, "whs1", "SELL", 120.0, 2.0, 30.0),
+        fill(70_000, "whs2", "SELL", 118.0, 0.5, 0.0), // Opens a short, still open
+    ];
+    let trips = round_trips(&fills, &[signal("whb1")]);
+    assert_eq!(trips.len(), 2);
+
+    let long = &trips[0];
+    assert_eq!(long.id, "BTCUSDT-LONG-1000");
+    assert_eq!(long.quantity, 2.0);
+    assert_eq!(long.entry_price, 105.0);
+    assert_eq!(long.exit_price, Some(120.0));
+    assert_eq!(long.holding_secs, Some(60.0));
+    assert_eq!(long.fills, 3);
+    assert!((long.fees - 0.3).abs() < 1e-9);
+    assert!((long.net_pnl - 29.7).abs() < 1e-9);
+    assert_eq!(long.tracking_id.as_deref(), Some("sig-1"));
+    assert_eq!(long.strategy.as_deref(), Some("breakout"));
+
+    let short = &trips[1];
+    assert_eq!(short.side, "SHORT");
+    assert_eq!(short.exit_time_ms, None);
+    assert_eq!(short.tracking_id, None);
+}
+
+#[test]
+fn test_reversing_fill_closes_the_trip_and_opens_the_next() {
+    let fills = vec![
+        fill(1_000, "whb1", "BUY", 100.0, 1.0, 0.0),
+        fill(5_000, "whs1", "SELL", 90.0, 3.0, -10.0),
+        fill(9_000, "whb2", "BUY", 80.0, 2.0, 20.0),
+    ];
+    let trips = round_trips(&fills, &[]);
+    assert_eq!(trips.len(), 2);
+    assert_eq!(trips[0].side, "LONG");
+    assert_eq!(trips[0].exit_time_ms, Some(5_000));
+    assert_eq!(trips[0].realized_pnl, -10.0);
+    assert_eq!(trips[1].id, "BTCUSDT-SHORT-5000");
+    assert_eq!(trips[1].quantity, 2.0);
+    assert_eq!(trips[1].entry_price, 90.0);
+    assert_eq!(trips[1].exit_price, Some(80.0));
+    assert_eq!(trips[1].realized_pnl, 20.0);
+}
+
+#[tokio::test]
+async fn test_journal_carries_notes_and_exports_csv() {
+    let dir = std::env::temp_dir().join(format!("journal_notes_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let storage = SqliteStorage::open(dir.join("trading_bot.sqlite3")).unwrap();
+    storage.record_signal(&signal("whb1")).await.unwrap();
+    storage.record_fill(&fill(1_000, "whb1", "BUY", 100.0, 1.0, 0.0)).await.unwrap();
+    storage.record_fill(&fill(4_000, "whs1", "SELL", 105.0, 1.0, 5.0)).await.unwrap();
+    storage.record_fill(&fill(9_000, "whb2", "BUY", 101.0, 1.0, 0.0)).await.unwrap();
+    storage.set_note("BTCUSDT-LONG-1000", "chased the breakout", 5_000).await.unwrap();
+
+    let trips = load_journal(&storage, 0).await.unwrap();
+    assert_eq!(trips.len(), 2);
+    assert_eq!(trips[0].note.as_deref(), Some("chased the breakout"));
+    assert_eq!(trips[1].note, None);
+    assert_eq!(load_journal(&storage, 5_000).await.unwrap().len(), 1);
+
+    let csv = to_csv(&trips).unwrap();
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("id,symbol,side,entryTimeMs,exitTimeMs,holdingSecs,"));
+    assert!(lines.next().unwrap().contains("breakout,chased the breakout"));
+    assert_eq!(lines.count(), 1);
+}
@@ -0,0 +1,118 @@
+// tests/ladder_tests.rs
+
+//! This file contains offline tests for the partial take-profit ladder (no network access needed).
+
+use serde_json::json;
+use trading_bot::order::bracket::{order_update_from_message, BracketAction};
+use trading_bot::order::ladder::*;
+use trading_bot::order::{round_down_to_step, OrderSide, OrderType};
+use trading_bot::streams::FuturesOrderUpdate;
+use trading_bot::websocket_stream::BinanceWsMessage;
+
+fn long_position() -> LadderPosition {
+    LadderPosition {
+        id: "lad1".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        entry_price: 100.0,
+        stop_price: 90.0,
+        quantity: 1.0,
+        step_size: 0.001,
+        position_side: None,
+    }
+}
+
+fn filled(client_order_id: &str) -> FuturesOrderUpdate {
+    let message = BinanceWsMessage::Raw(json!({
+        "e": "ORDER_TRADE_UPDATE", "E": 1, "T": 1,
+        "o": {
+            "s": "BTCUSDT", "c": client_order_id, "S": "SELL", "o": "LIMIT", "f": "GTC",
+            "q": "0.5", "p": "110", "ap": "110", "x": "TRADE", "X": "FILLED", "i": 1,
+            "l": "0.5", "z": "0.5", "L": "110", "T": 1, "t": 1
+        }
+    }));
+    order_update_from_message(&message).unwrap()
+}
+
+fn placed(action: &BracketAction) -> &trading_bot::order::NewOrderRequest {
+    match action {
+        BracketAction::PlaceOrder(request) => request,
+        other => panic!("expected a placed order, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_round_down_to_step() {
+    assert_eq!(round_down_to_step(0.3, 0.1), 0.3);
+    assert_eq!(round_down_to_step(0.1239, 0.001), 0.123);
+    assert_eq!(round_down_to_step(5.0, 0.0), 5.0);
+}
+
+#[test]
+fn test_ladder_places_tranches_and_stop() {
+    let mut manager = LadderManager::new();
+    let actions = manager.open(long_position(), &LadderConfig::default()).unwrap();
+    assert_eq!(actions.len(), 4);
+
+    let prices: Vec<f64> = actions[..3].iter().map(|a| placed(a).price.unwrap()).collect();
+    assert_eq!(prices, vec![110.0, 120.0, 130.0]);
+    let quantities: Vec<f64> = actions[..3].iter().map(|a| placed(a).quantity.unwrap()).collect();
+    assert_eq!(quantities, vec![0.5, 0.3, 0.2]);
+    assert!(actions[..3].iter().all(|a| placed(a).reduce_only && placed(a).order_type == OrderType::Limit));
+
+    let stop = placed(&actions[3]);
+    assert_eq!(stop.order_type, OrderType::StopMarket);
+    assert_eq!(stop.stop_price, Some(90.0));
+    assert_eq!(stop.quantity, Some(1.0));
+}
+
+#[test]
+fn test_stop_follows_filled_tranches() {
+    let mut manager = LadderManager::new();
+    let config = LadderConfig { stop_adjustment: StopAdjustment::PreviousTarget, ..LadderConfig::default() };
+    manager.open(long_position(), &config).unwrap();
+
+    let actions = manager.on_order_update(&filled("lad1-tp1"));
+    assert_eq!(actions[0], BracketAction::CancelOrder { symbol: "BTCUSDT".to_string(), client_order_id: "lad1-sl1".to_string() });
+    let stop = placed(&actions[1]);
+    assert_eq!(stop.stop_price, Some(100.0)); // Breakeven after the first tranche
+    assert!((stop.quantity.unwrap() - 0.5).abs() < 1e-9);
+
+    let actions = manager.on_order_update(&filled("lad1-tp2"));
+    let stop = placed(&actions[1]);
+    assert_eq!(stop.stop_price, Some(110.0)); // Previous target
+    assert!((stop.quantity.unwrap() - 0.2).abs() < 1e-9);
+
+    let actions = manager.on_order_update(&filled("lad1-tp3"));
+    assert_eq!(actions, vec![BracketAction::CancelOrder { symbol: "BTCUSDT".to_string(), client_order_id: "lad1-sl3".to_string() }]);
+    assert_eq!(manager.get("lad1").unwrap().state, LadderState::Completed);
+}
+
+#[test]
+fn test_stop_fill_cancels_open_tranches() {
+    let mut manager = LadderManager::new();
+    manager.open(long_position(), &LadderConfig::default()).unwrap();
+    manager.on_order_update(&filled("lad1-tp1"));
+
+    let actions = manager.on_order_update(&filled("lad1-sl2"));
+    assert_eq!(actions.len(), 2);
+    assert_eq!(manager.get("lad1").unwrap().state, LadderState::StoppedOut);
+}
+
+#[test]
+fn test_config_resolution_and_validation() {
+    let mut set = LadderConfigSet::default();
+    let scalp = LadderConfig {
+        tranches: vec![LadderTranche { fraction: 1.0, r_multiple: 1.5 }],
+        stop_adjustment: StopAdjustment::KeepOriginal,
+    };
+    set.set(Some("scalper"), None, scalp.clone()).unwrap();
+    assert_eq!(set.resolve("scalper", "ETHUSDT"), &scalp);
+    assert_eq!(set.resolve("swing", "ETHUSDT"), &LadderConfig::default());
+
+    let invalid = LadderConfig {
+        tranches: vec![LadderTranche { fraction: 0.8, r_multiple: 1.0 }, LadderTranche { fraction: 0.5, r_multiple: 2.0 }],
+        stop_adjustment: StopAdjustment::Breakeven,
+    };
+    assert!(set.set(None, Some("BTCUSDT"), invalid).is_err());
+}
@@ -0,0 +1,66 @@
+// tests/lifecycle_tests.rs
+
+//! This file contains tests for the ordered startup, shutdown and restart of subsystems.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use trading_bot::lifecycle::{Stage, Supervisor};
+
+type Journal = Arc<Mutex<Vec<String>>>;
+
+async fn start_recorded(supervisor: &mut Supervisor, journal: &Journal, stage: Stage, name: &'static str) -> Result<(), String> {
+    let journal = journal.clone();
+    supervisor.start(stage, name, Duration::from_secs(1), move |ready, mut shutdown| {
+        let journal = journal.clone();
+        async move {
+            journal.lock().unwrap().push(format!("start {}", name));
+            ready.ready();
+            shutdown.wait().await;
+            journal.lock().unwrap().push(format!("stop {}", name));
+            Ok(())
+        }
+    }).await
+}
+
+#[tokio::test]
+async fn test_subsystems_stop_in_reverse_order_and_restart_dependents() {
+    let journal = Journal::default();
+    let mut supervisor = Supervisor::new(Duration::from_secs(1));
+    start_recorded(&mut supervisor, &journal, Stage::Persistence, "db").await.unwrap();
+    start_recorded(&mut supervisor, &journal, Stage::ExchangeClients, "exchange").await.unwrap();
+    start_recorded(&mut supervisor, &journal, Stage::Webhook, "webhook").await.unwrap();
+
+    // Dependencies must be started first
+    let err = start_recorded(&mut supervisor, &journal, Stage::UserDataStream, "user_data").await.unwrap_err();
+    assert!(err.contains("webhook"));
+
+    supervisor.restart("exchange").await.unwrap();
+    assert!(supervisor.status().iter().all(|s| s.running));
+    supervisor.shutdown().await;
+    assert!(supervisor.status().iter().all(|s| !s.running));
+
+    let expected = [
+        "start db", "start exchange", "start webhook",
+        "stop webhook", "stop exchange", "start exchange", "start webhook",
+        "stop webhook", "stop exchange", "stop db",
+    ];
+    assert_eq!(*journal.lock().unwrap(), expected);
+}
+
+#[tokio::test]
+async fn test_startup_fails_when_a_subsystem_is_not_ready() {
+    let mut supervisor = Supervisor::new(Duration::from_secs(1));
+    let err = supervisor.start(Stage::ExchangeClients, "exchange", Duration::from_secs(1), |_ready, _shutdown| async {
+        Err::<(), String>("logon rejected".to_string())
+    }).await.unwrap_err();
+    assert!(err.contains("logon rejected"));
+    assert!(supervisor.status().is_empty());
+
+    let err = supervisor.start(Stage::ExchangeClients, "slow", Duration::from_millis(50), |ready, _shutdown| async move {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        ready.ready();
+        Ok(())
+    }).await.unwrap_err();
+    assert!(err.contains("not ready within"));
+}
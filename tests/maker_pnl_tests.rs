@@ -0,0 +1,47 @@
+// tests/maker_pnl_tests.rs
+
+//! This file contains tests for the maker PnL decomposition.
+
+use trading_bot::maker_pnl::*;
+use trading_bot::order::OrderSide;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const BASE: i64 = 1_704_067_200_000; // 2024-01-01 00:00 UTC
+
+fn fill(timestamp_ms: i64, side: OrderSide, price: f64) -> Fill {
+    Fill { timestamp_ms, side, price, quantity: 1.0, fee: 0.1, is_maker: true }
+}
+
+fn mid(timestamp_ms: i64, price: f64) -> MidPrice {
+    MidPrice { timestamp_ms, price }
+}
+
+#[test]
+fn test_decomposition_matches_realized_pnl() {
+    let fills = vec![fill(BASE + 10, OrderSide::Buy, 99.0), fill(BASE + 30, OrderSide::Sell, 103.0)];
+    let mids = vec![mid(BASE, 100.0), mid(BASE + 20, 102.0), mid(BASE + 30, 102.0)];
+
+    let breakdown = decompose_pnl(&fills, &mids, 0.0);
+    assert_eq!(breakdown.len(), 1);
+    let day = &breakdown[0];
+    assert!((day.spread_capture - 2.0).abs() < 1e-9); // 1.0 on the buy, 1.0 on the sell
+    assert!((day.inventory_pnl - 2.0).abs() < 1e-9); // Held 1 unit while the mid moved 100 -> 102
+    assert!((day.fees - 0.2).abs() < 1e-9);
+    // Realized PnL: 103 - 99 - fees
+    assert!((day.net_pnl - 3.8).abs() < 1e-9);
+    assert_eq!(day.fills, 2);
+    assert!(day.ending_inventory.abs() < 1e-9);
+}
+
+#[test]
+fn test_inventory_pnl_is_split_by_day() {
+    let fills = vec![fill(BASE + 10, OrderSide::Buy, 100.0)];
+    let mids = vec![mid(BASE, 100.0), mid(BASE + DAY_MS, 90.0)];
+
+    let breakdown = decompose_pnl(&fills, &mids, 0.0);
+    assert_eq!(breakdown.len(), 2);
+    assert!(breakdown[0].inventory_pnl.abs() < 1e-9);
+    assert!((breakdown[1].inventory_pnl + 10.0).abs() < 1e-9); // Accidental directional loss
+    assert!((breakdown[1].ending_inventory - 1.0).abs() < 1e-9);
+    assert_eq!(breakdown[1].fills, 0);
+}
@@ -0,0 +1,38 @@
+// tests/margin_tests.rs
+
+//! This file contains tests for the futures margin and liquidation math used by the backtester.
+
+use trading_bot::order::OrderSide;
+use trading_bot::strategy::margin::*;
+use trading_bot::strategy::BacktestConfig;
+
+#[test]
+fn test_liquidation_prices() {
+    let config = MarginConfig { leverage: 20.0, maintenance_margin_rate: 0.004 };
+    let long = config.open(OrderSide::Buy, 100.0, 2.0);
+    assert_eq!(long.margin, 10.0);
+    assert!((long.liquidation_price() - 95.0 / 0.996).abs() < 1e-9);
+    assert!(long.is_liquidated(95.3, 101.0));
+    assert!(!long.is_liquidated(95.5, 101.0));
+
+    let short = config.open(OrderSide::Sell, 100.0, 2.0);
+    assert!((short.liquidation_price() - 105.0 / 1.004).abs() < 1e-9);
+    assert!(short.is_liquidated(99.0, 104.6));
+    assert_eq!(short.unrealized_pnl(90.0), 20.0);
+
+    // Without leverage a long can only be liquidated near zero
+    let spot_like = MarginConfig { leverage: 1.0, maintenance_margin_rate: 0.004 }.open(OrderSide::Buy, 100.0, 1.0);
+    assert_eq!(spot_like.liquidation_price(), 0.0);
+}
+
+#[test]
+fn test_margin_limits() {
+    let config = MarginConfig::default();
+    assert_eq!(config.max_quantity(1000.0, 100.0), 200.0);
+    assert!(config.validate().is_ok());
+    assert!(MarginConfig { leverage: 200.0, ..config }.validate().is_err());
+    assert!(MarginConfig { leverage: 50.0, maintenance_margin_rate: 0.05 }.validate().is_err());
+
+    let backtest = BacktestConfig::from_args(&["--leverage".to_string(), "5".to_string()]).unwrap();
+    assert_eq!(backtest.margin().leverage, 5.0);
+}
@@ -0,0 +1,163 @@
+// tests/market_event_tests.rs
+
+//! This file contains tests for decoding stream frames into normalized market events.
+
+use trading_bot::market_event::*;
+use trading_bot::order_book::LocalOrderBook;
+use trading_bot::market_data::OrderBookSnapshot;
+use trading_bot::market_data::KlineInterval;
+use trading_bot::streams::{continuous_kline_stream, force_order_stream, index_price_kline_stream, mark_price_kline_stream, mark_price_stream, ContinuousKlineStream, DepthLevel, ForceOrderStream, MarkPriceStream, PriceKlineStream};
+
+#[test]
+fn test_symbol_is_inline_and_uppercased() {
+    let symbol = Symbol::new("btcusdt").unwrap();
+    assert_eq!(symbol.as_str(), "BTCUSDT");
+    assert_eq!(symbol, Symbol::new("BTCUSDT").unwrap());
+    assert!(Symbol::new("").is_err());
+    assert!(Symbol::new(&"X".repeat(MAX_SYMBOL_LEN + 1)).is_err());
+}
+
+#[test]
+fn test_parse_agg_trade_and_combined_stream() {
+    let raw = r#"{"e":"aggTrade","E":123456789,"s":"BTCUSDT","a":5933014,"p":"30000.10","q":"0.500","f":100,"l":105,"T":123456785,"m":true}"#;
+    let combined = format!(r#"{{"stream":"btcusdt@aggTrade","data":{}}}"#, raw);
+
+    for text in [raw.to_string(), combined] {
+        match parse_market_event(&text).unwrap() {
+            Some(MarketEvent::AggTrade(trade)) => {
+                assert_eq!(trade.symbol.as_str(), "BTCUSDT");
+                assert_eq!(trade.trade_id, 5933014);
+                assert_eq!(trade.price, 30000.10);
+                assert_eq!(trade.quantity, 0.5);
+                assert!(trade.is_buyer_maker);
+            },
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_parse_kline_and_mark_price() {
+    let kline = r#"{"e":"kline","E":1,"s":"ETHUSDT","k":{"t":0,"T":59999,"s":"ETHUSDT","i":"1m","f":1,"L":2,"o":"1800","c":"1810","h":"1815","l":"1795","v":"12.5","n":42,"x":true,"q":"22500","V":"6","Q":"10800","B":"0"}}"#;
+    match parse_market_event(kline).unwrap() {
+        Some(MarketEvent::Kline(candle)) => {
+            assert_eq!(candle.interval_ms, 60_000);
+            assert_eq!(candle.close, 1810.0);
+            assert_eq!(candle.trades, 42);
+            assert!(candle.is_closed);
+        },
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    let mark = r#"{"e":"markPriceUpdate","E":2,"s":"BTCUSDT","p":"30010.5","i":"30008.2","P":"30009","r":"0.0001","T":1700000000000}"#;
+    let event = parse_market_event(mark).unwrap().unwrap();
+    assert_eq!(event.event_time(), 2);
+    match event {
+        MarketEvent::MarkPrice(mp) => {
+            assert_eq!(mp.mark_price, 30010.5);
+            assert_eq!(mp.funding_rate, 0.0001);
+        },
+        other => panic!("unexpected event {:?}", other),
+    }
+}
+
+#[test]
+fn test_mark_price_stream() {
+    assert_eq!(mark_price_stream("BTCUSDT", false), "btcusdt@markPrice");
+    assert_eq!(mark_price_stream("ethusdt", true), "ethusdt@markPrice@1s");
+
+    let mark = r#"{"e":"markPriceUpdate","E":2,"s":"BTCUSDT","p":"30010.5","i":"30008.2","P":"30009","r":"0.0001","T":1700000000000}"#;
+    let message: MarkPriceStream = serde_json::from_str(mark).unwrap();
+    assert_eq!(message.mark_price, "30010.5");
+    assert_eq!(message.funding_rate, "0.0001");
+    assert_eq!(message.next_funding_time, 1_700_000_000_000);
+}
+
+#[test]
+fn test_parse_liquidations_into_a_feed() {
+    assert_eq!(force_order_stream(Some("BTCUSDT")), "btcusdt@forceOrder");
+    assert_eq!(force_order_stream(None), "!forceOrder@arr");
+
+    let frame = |time: u64, side: &str, quantity: &str| format!(
+        r#"{{"e":"forceOrder","E":{time},"o":{{"s":"BTCUSDT","S":"{side}","o":"LIMIT","f":"IOC","q":"{quantity}","p":"29900","ap":"30000","X":"FILLED","l":"{quantity}","z":"{quantity}","T":{time}}}}}"#
+    );
+    let typed: ForceOrderStream = serde_json::from_str(&frame(1_000, "SELL", "0.5")).unwrap();
+    assert_eq!(typed.order.average_price, "30000");
+
+    let mut feed = LiquidationFeed::new(60_000, 10);
+    for (time, side, quantity) in [(1_000, "SELL", "0.5"), (2_000, "SELL", "1"), (3_000, "BUY", "0.1")] {
+        let event = parse_market_event(&frame(time, side, quantity)).unwrap().unwrap();
+        assert!(feed.on_event(&event));
+    }
+    assert_eq!(feed.long_notional(), 45_000.0);
+    assert_eq!(feed.short_notional(), 3_000.0);
+
+    // Only the last minute is kept
+    let late = parse_market_event(&frame(61_500, "BUY", "0.2")).unwrap().unwrap();
+    feed.on_event(&late);
+    assert_eq!(feed.recent.len(), 3);
+    assert_eq!(feed.long_notional(), 30_000.0);
+}
+
+#[test]
+fn test_parse_pair_klines() {
+    assert_eq!(continuous_kline_stream("BTCUSDT", ContractType::Perpetual, KlineInterval::M1), "btcusdt_perpetual@continuousKline_1m");
+    assert_eq!(index_price_kline_stream("BTCUSDT", KlineInterval::H1), "btcusdt@indexPriceKline_1h");
+    assert_eq!(mark_price_kline_stream("ETHUSDT", KlineInterval::M5), "ethusdt@markPriceKline_5m");
+
+    let kline = r#"{"t":0,"T":59999,"i":"1m","f":1,"L":2,"o":"30000","c":"30050","h":"30100","l":"29950","v":"0","n":60,"x":false,"q":"0","V":"0","Q":"0","B":"0"}"#;
+    let continuous = format!(r#"{{"e":"continuous_kline","E":7,"ps":"BTCUSDT","ct":"CURRENT_QUARTER","k":{}}}"#, kline);
+    let typed: ContinuousKlineStream = serde_json::from_str(&continuous).unwrap();
+    assert_eq!(typed.contract_type, "CURRENT_QUARTER");
+    match parse_market_event(&continuous).unwrap() {
+        Some(MarketEvent::PriceKline(k)) => {
+            assert_eq!(k.source, KlineSource::Continuous(ContractType::CurrentQuarter));
+            assert_eq!(k.candle.symbol.as_str(), "BTCUSDT");
+            assert_eq!(k.candle.close, 30050.0);
+            assert!(!k.candle.is_closed);
+        },
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    let index = format!(r#"{{"e":"indexPrice_kline","E":8,"ps":"BTCUSDT","k":{}}}"#, kline);
+    let typed: PriceKlineStream = serde_json::from_str(&index).unwrap();
+    assert_eq!(typed.kline.number_of_trades, 60);
+    let event = parse_market_event(&index).unwrap().unwrap();
+    assert_eq!(event.event_time(), 8);
+    assert!(matches!(event, MarketEvent::PriceKline(PriceKline { source: KlineSource::IndexPrice, .. })));
+
+    let mark = format!(r#"{{"e":"markPrice_kline","E":9,"ps":"BTCUSDT","k":{}}}"#, kline);
+    assert!(matches!(parse_market_event(&mark).unwrap(), Some(MarketEvent::PriceKline(PriceKline { source: KlineSource::MarkPrice, .. }))));
+
+    let unknown = continuous.replace("CURRENT_QUARTER", "SOMEDAY");
+    assert!(parse_market_event(&unknown).is_err());
+}
+
+#[test]
+fn test_non_market_messages_are_skipped() {
+    assert_eq!(parse_market_event(r#"{"result":null,"id":1}"#).unwrap(), None);
+    assert_eq!(parse_market_event(r#"{"e":"24hrMiniTicker","E":1}"#).unwrap(), None);
+    assert!(parse_market_event(r#"{"e":"aggTrade","E":1,"s":"BTCUSDT","a":1,"p":"abc","q":"1","T":1,"m":false}"#).is_err());
+}
+
+#[test]
+fn test_normalized_depth_applies_to_book() {
+    let mut book = LocalOrderBook::new("BTCUSDT");
+    book.apply_snapshot(&OrderBookSnapshot {
+        last_update_id: 10,
+        event_time: 0,
+        transaction_time: 0,
+        bids: vec![DepthLevel::Array("100.0".to_string(), "1.0".to_string())],
+        asks: vec![DepthLevel::Array("101.0".to_string(), "1.0".to_string())],
+    }).unwrap();
+
+    let text = r#"{"e":"depthUpdate","E":5,"T":5,"s":"BTCUSDT","U":9,"u":12,"pu":8,"b":[["100.5","2.0"]],"a":[["101.0","0"]]}"#;
+    let Some(MarketEvent::Depth(update)) = parse_market_event(text).unwrap() else {
+        panic!("expected a depth update");
+    };
+    assert_eq!(update.bids, vec![Level { price: 100.5, quantity: 2.0 }]);
+    assert!(book.apply_depth_update(&update).unwrap());
+    assert_eq!(book.best_bid(), Some((100.5, 2.0)));
+    assert_eq!(book.best_ask(), None);
+    assert_eq!(book.last_update_id, 12);
+}
@@ -0,0 +1,67 @@
+// tests/metrics_tests.rs
+
+//! This file contains tests for risk-adjusted performance metrics.
+
+use trading_bot::metrics::*;
+
+fn config(risk_free_rate: f64, benchmark: Benchmark) -> MetricsConfig {
+    MetricsConfig { risk_free_rate, periods_per_year: 365.0, benchmark }
+}
+
+#[test]
+fn test_risk_free_rate_lowers_sharpe() {
+    let returns = [0.01, -0.005, 0.02, 0.0, 0.015, -0.01];
+    let without = sharpe_ratio(&returns, &config(0.0, Benchmark::None)).unwrap();
+    let with = sharpe_ratio(&returns, &config(0.05, Benchmark::None)).unwrap();
+    assert!(with < without);
+
+    let per_period = config(0.05, Benchmark::None).risk_free_per_period();
+    assert!(((1.0 + per_period).powf(365.0) - 1.05).abs() < 1e-9);
+}
+
+#[test]
+fn test_sortino_needs_downside() {
+    let cfg = config(0.0, Benchmark::None);
+    assert_eq!(sortino_ratio(&[0.01, 0.02, 0.03], &cfg), None);
+    assert!(sortino_ratio(&[0.01, -0.02, 0.03], &cfg).unwrap() > 0.0);
+}
+
+#[test]
+fn test_alpha_beta_against_benchmark() {
+    let benchmark = [100.0, 102.0, 101.0, 104.0, 103.0];
+    // Strategy equity moves exactly twice the benchmark's returns
+    let bench_returns = simple_returns(&benchmark);
+    let mut equity = vec![1000.0];
+    for r in &bench_returns {
+        let last = *equity.last().unwrap();
+        equity.push(last * (1.0 + 2.0 * r));
+    }
+
+    let cfg = config(0.0, Benchmark::Series(benchmark.to_vec()));
+    let metrics = compute_risk_metrics(&equity, None, &cfg);
+    assert!((metrics.beta.unwrap() - 2.0).abs() < 1e-9);
+    assert!(metrics.alpha.unwrap().abs() < 1e-9);
+    assert!((metrics.benchmark_return.unwrap() - 0.03).abs() < 1e-9);
+
+    // Buy-and-hold uses the instrument prices supplied by the caller
+    let metrics = compute_risk_metrics(&equity, Some(&benchmark), &config(0.0, Benchmark::BuyAndHold));
+    assert!((metrics.beta.unwrap() - 2.0).abs() < 1e-9);
+    let metrics = compute_risk_metrics(&equity, Some(&benchmark), &config(0.0, Benchmark::None));
+    assert_eq!(metrics.beta, None);
+}
+
+#[test]
+fn test_max_drawdown_and_calmar() {
+    assert_eq!(max_drawdown(&[100.0, 120.0, 90.0, 110.0, 60.0, 130.0]), 0.5);
+    assert_eq!(max_drawdown(&[100.0, 110.0, 120.0]), 0.0);
+    assert_eq!(calmar_ratio(0.3, 0.15), Some(2.0));
+    assert_eq!(calmar_ratio(0.3, 0.0), None);
+
+    // One year of daily equity doubling with a 20% dip along the way
+    let mut equity: Vec<f64> = (0..=365).map(|i| 100.0 * 2f64.powf(i as f64 / 365.0)).collect();
+    equity[200] *= 0.8;
+    let metrics = compute_risk_metrics(&equity, None, &config(0.0, Benchmark::None));
+    assert!((metrics.annualized_return - 1.0).abs() < 1e-9);
+    assert!((metrics.max_drawdown - 0.2).abs() < 1e-2);
+    assert!((metrics.calmar_ratio.unwrap() - metrics.annualized_return / metrics.max_drawdown).abs() < 1e-12);
+}
@@ -0,0 +1,60 @@
+// tests/monte_carlo_tests.rs
+
+//! This file contains tests for the Monte Carlo resampling of backtest trades.
+
+use trading_bot::strategy::monte_carlo::*;
+use trading_bot::strategy::BacktestConfig;
+
+const PNL: [f64; 8] = [100.0, -50.0, 80.0, -40.0, -60.0, 120.0, -30.0, 90.0];
+
+#[test]
+fn test_percentiles_interpolate() {
+    let mut values = vec![5.0, 1.0, 3.0, 2.0, 4.0];
+    let p = Percentiles::of(&mut values);
+    assert_eq!(values, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert_eq!(p.p50, 3.0);
+    assert!((p.p5 - 1.2).abs() < 1e-12);
+    assert!((p.p95 - 4.8).abs() < 1e-12);
+    assert_eq!(percentile(&[], 0.5), 0.0);
+}
+
+#[test]
+fn test_trade_returns_compound_to_the_final_equity() {
+    let returns = trade_returns(&PNL, 1000.0);
+    let final_equity = returns.iter().fold(1000.0, |equity, r| equity * (1.0 + r));
+    assert!((final_equity - (1000.0 + PNL.iter().sum::<f64>())).abs() < 1e-9);
+    assert_eq!(returns[0], 0.1);
+}
+
+#[test]
+fn test_shuffle_keeps_final_equity_and_varies_drawdown() {
+    let result = simulate(&PNL, 1000.0, 2_000, Resampling::Shuffle, Some(7)).unwrap();
+    let expected = 1000.0 + PNL.iter().sum::<f64>();
+    assert!((result.final_equity.p5 - expected).abs() < 1e-6);
+    assert!((result.final_equity.p95 - expected).abs() < 1e-6);
+    assert_eq!(result.probability_of_loss, 0.0);
+    assert!(result.max_drawdown.p5 < result.max_drawdown.p95);
+
+    // The same seed reproduces the same distribution
+    assert_eq!(simulate(&PNL, 1000.0, 2_000, Resampling::Shuffle, Some(7)).unwrap(), result);
+}
+
+#[test]
+fn test_bootstrap_spreads_final_equity() {
+    let result = simulate(&PNL, 1000.0, 5_000, Resampling::Bootstrap, Some(42)).unwrap();
+    assert!(result.final_equity.p5 < result.final_equity.p50 && result.final_equity.p50 < result.final_equity.p95);
+    assert!(result.probability_of_loss > 0.0 && result.probability_of_loss < 1.0);
+    assert_eq!(result.runs, 5_000);
+
+    assert!(simulate(&[], 1000.0, 100, Resampling::Bootstrap, None).is_none());
+    assert!(simulate(&PNL, 1000.0, 0, Resampling::Bootstrap, None).is_none());
+}
+
+#[test]
+fn test_monte_carlo_settings() {
+    let args: Vec<String> = ["--monte-carlo", "1000", "--mc-method", "bootstrap", "--mc-seed", "3"].iter().map(|s| s.to_string()).collect();
+    let config = BacktestConfig::from_args(&args).unwrap();
+    assert_eq!((config.monte_carlo_runs, config.monte_carlo_method, config.monte_carlo_seed), (1000, Resampling::Bootstrap, Some(3)));
+    assert_eq!(BacktestConfig::default().monte_carlo_runs, 0);
+    assert!(Resampling::parse("jackknife").is_err());
+}
@@ -0,0 +1,331 @@
+// tests/notify_tests.rs
+
+//! This file contains tests for the notifications and their Telegram delivery.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::{Path, State}, routing::post, Json, Router};
+use futures_util::future::BoxFuture;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use trading_bot::arming::Interlock;
+use trading_bot::events::{BotEvent, EventLog};
+use trading_bot::notify::email::{batch_mail, build_message, EmailConfig, EmailNotifier, SmtpTls};
+use trading_bot::notify::telegram::{handle_arming_command, serve_arming_commands, TelegramConfig, TelegramNotifier};
+use trading_bot::notify::*;
+use trading_bot::websocket_stream::BinanceWsMessage;
+
+type Received = Arc<Mutex<Vec<(String, Value)>>>; // Bot path segment and body of each request
+type Batches = Arc<Mutex<Vec<Vec<Notification>>>>;
+
+#[test]
+fn test_notifications_from_events() {
+    let rejected = BotEvent::SignalRejected { symbol: "BTCUSDT".to_string(), signal: "buy".to_string(), reason: "Trading is disarmed".to_string() };
+    let notification = Notification::from_event(&rejected).unwrap();
+    assert_eq!(notification.text(), "Signal rejected: buy BTCUSDT\nTrading is disarmed");
+    let placed = BotEvent::OrderPlaced {
+        symbol: "ETHUSDT".to_string(),
+        signal: "sell".to_string(),
+        client_order_id: "wh1".to_string(),
+        side: "SELL".to_string(),
+        quantity: 0.5,
+        price: 3000.0,
+    };
+    assert_eq!(Notification::from_event(&placed).unwrap().title(), "Order placed: SELL ETHUSDT");
+    let config = BotEvent::ConfigChange { key: "experiment".to_string(), value: json!(null) };
+    assert_eq!(Notification::from_event(&config), None);
+}
+
+#[tokio::test]
+async fn test_event_log_and_interlock_notify() {
+    let path = std::env::temp_dir().join(format!("notify_events_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (notifications, mut receiver) = Notifications::channel(8);
+    let log = EventLog::open(&path).unwrap().with_notifications(notifications.clone());
+    log.append(1, BotEvent::ConfigChange { key: "experiment".to_string(), value: json!(null) }).unwrap();
+    log.append(2, BotEvent::Fill {
+        symbol: "BTCUSDT".to_string(),
+        client_order_id: "wh1".to_string(),
+        side: "BUY".to_string(),
+        position_side: None,
+        price: 100.0,
+        quantity: 1.0,
+        realized_pnl: 0.0,
+        commission: 0.0,
+    }).unwrap();
+    assert!(matches!(receiver.try_recv(), Ok(Notification::Fill { .. })));
+
+    let interlock = Interlock::in_memory(true).with_notifications(notifications);
+    interlock.trip("max_drawdown", "drawdown 12% over 10%");
+    assert!(!interlock.is_armed());
+    assert_eq!(receiver.try_recv().unwrap(), Notification::BreakerTripped {
+        breaker: "max_drawdown".to_string(),
+        reason: "drawdown 12% over 10%".to_string(),
+    });
+    assert!(receiver.try_recv().is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_telegram_sends_messages() {
+    let received: Received = Arc::default();
+    let app = Router::new()
+        .route("/{bot}/sendMessage", post(|State(received): State<Received>, Path(bot): Path<String>, Json(body): Json<Value>| async move {
+            received.lock().unwrap().push((bot, body));
+            Json(json!({"ok": true}))
+        }))
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let config = TelegramConfig { bot_token: "123:abc".to_string(), chat_id: "42".to_string(), min_severity: Severity::Info, operators: vec![] };
+    let notifier = TelegramNotifier::with_api_base_url(config, &format!("http://{}/", addr));
+    notifier.send_message("Disconnected").await.unwrap();
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].0, "bot123:abc");
+    assert_eq!((received[0].1["chat_id"].as_str(), received[0].1["text"].as_str()), (Some("42"), Some("Disconnected")));
+}
+
+#[test]
+fn test_telegram_arming_commands() {
+    let interlock = Interlock::in_memory(false);
+    let operators = [1001];
+    assert_eq!(handle_arming_command("hello", 1001, None, &operators, &interlock), None);
+    let refused = handle_arming_command("/arm", 2002, Some("mallory"), &operators, &interlock).unwrap();
+    assert!(refused.contains("not allowed"));
+    assert!(!interlock.is_armed());
+
+    let armed = handle_arming_command("/arm@TradingBot", 1001, Some("alice"), &operators, &interlock).unwrap();
+    assert_eq!(armed, "Live trading is ARMED (by telegram:@alice).");
+    assert!(interlock.is_armed());
+    let disarmed = handle_arming_command("/disarm  news event", 1001, None, &operators, &interlock).unwrap();
+    assert_eq!(disarmed, "Live trading is DISARMED: news event (by telegram:1001).");
+    assert!(!interlock.is_armed());
+}
+
+#[tokio::test]
+async fn test_telegram_arming_commands_skip_the_backlog_and_other_chats() {
+    let received: Received = Arc::default();
+    let update = |id: i64, chat: i64, from: i64, text: &str| json!({
+        "update_id": id, "message": {"message_id": id, "chat": {"id": chat}, "from": {"id": from}, "text": text}
+    });
+    let (backlog, updates) = (vec![update(5, 42, 1001, "/arm")], vec![update(6, 7, 1001, "/arm"), update(7, 42, 1001, "/arm")]);
+    let app = Router::new()
+        .route("/{bot}/getUpdates", post(move |Json(body): Json<Value>| {
+            let result = match body["offset"].as_i64() {
+                Some(-1) => backlog.clone(), // The latest update, sent before the bot started
+                Some(6) => updates.clone(),
+                _ => vec![],
+            };
+            async move { Json(json!({"ok": true, "result": result})) }
+        }))
+        .route("/{bot}/sendMessage", post(|State(received): State<Received>, Path(bot): Path<String>, Json(body): Json<Value>| async move {
+            received.lock().unwrap().push((bot, body));
+            Json(json!({"ok": true}))
+        }))
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let interlock = Arc::new(Interlock::in_memory(false));
+    let config = TelegramConfig { bot_token: "123:abc".to_string(), chat_id: "42".to_string(), min_severity: Severity::Info, operators: vec![1001] };
+    let notifier = TelegramNotifier::with_api_base_url(config, &format!("http://{}/", addr));
+    let commands = tokio::spawn(serve_arming_commands(notifier, interlock.clone(), Duration::from_millis(10)));
+    for _ in 0..100 {
+        if !received.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    commands.abort();
+
+    // Only the command of update 7 (the configured chat, after the bot started) was run
+    assert!(interlock.is_armed());
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].1["text"], "Live trading is ARMED (by telegram:1001).");
+}
+
+#[test]
+fn test_critical_notifications_from_user_data() {
+    let margin_call = BinanceWsMessage::Raw(json!({
+        "e": "MARGIN_CALL", "E": 1587727187525u64, "cw": "3.16812045",
+        "p": [{"s": "ETHUSDT", "ps": "LONG", "pa": "1.327", "mt": "CROSSED", "iw": "0", "mp": "187.17127", "up": "-1.166074", "mm": "1.614445"}]
+    }));
+    let notifications = Notification::from_user_data(&margin_call);
+    assert_eq!(notifications, vec![Notification::MarginCall {
+        symbol: "ETHUSDT".to_string(),
+        position_side: "LONG".to_string(),
+        unrealized_pnl: -1.166074,
+        maintenance_margin: 1.614445,
+    }]);
+    assert_eq!(notifications[0].severity(), Severity::Critical);
+
+    let liquidation = BinanceWsMessage::Raw(json!({
+        "e": "ORDER_TRADE_UPDATE", "E": 1568879465651u64, "T": 1568879465650u64,
+        "o": {"s": "BTCUSDT", "c": "autoclose-1568879465650", "S": "SELL", "o": "LIQUIDATION", "f": "IOC", "q": "0.002", "p": "9000",
+              "ap": "9000", "sp": "0", "x": "TRADE", "X": "FILLED", "i": 8886774, "l": "0.002", "z": "0.002", "L": "9000", "T": 1568879465650u64,
+              "t": 1, "b": "0", "a": "0", "m": false, "R": false, "wt": "CONTRACT_PRICE", "ot": "LIQUIDATION", "ps": "LONG", "cp": false, "rp": "-1.5"}
+    }));
+    assert!(matches!(Notification::from_user_data(&liquidation)[..], [Notification::Liquidation { quantity: 0.002, price: 9000.0, .. }]));
+    assert!(Notification::from_user_data(&BinanceWsMessage::Raw(json!({"e": "ACCOUNT_UPDATE"}))).is_empty());
+}
+
+#[test]
+fn test_repeated_order_rejections() {
+    let (notifications, mut receiver) = Notifications::channel(8);
+    notifications.order_rejected("BTCUSDT", "Margin is insufficient");
+    notifications.order_rejected("BTCUSDT", "Margin is insufficient");
+    assert!(receiver.try_recv().is_err());
+    notifications.order_rejected("ETHUSDT", "Order would immediately trigger");
+    assert_eq!(receiver.try_recv().unwrap(), Notification::RepeatedRejections {
+        count: REPEATED_REJECTIONS_THRESHOLD,
+        last_symbol: "ETHUSDT".to_string(),
+        last_reason: "Order would immediately trigger".to_string(),
+    });
+    notifications.order_rejected("ETHUSDT", "Order would immediately trigger");
+    assert!(receiver.try_recv().is_err());
+}
+
+#[test]
+fn test_run_marker_detects_unclean_shutdown() {
+    let path = std::env::temp_dir().join(format!("notify_running_{}", std::process::id()));
+    clear_run_marker(&path).unwrap();
+    assert_eq!(mark_running(&path).unwrap(), None);
+    assert!(matches!(mark_running(&path).unwrap(), Some(Notification::Restarted { .. })));
+    clear_run_marker(&path).unwrap();
+    assert_eq!(mark_running(&path).unwrap(), None);
+    clear_run_marker(&path).unwrap();
+}
+
+/// Accepts one SMTP session, answers every command, and returns the transcript.
+async fn fake_smtp_server(listener: tokio::net::TcpListener) -> Vec<String> {
+    let (stream, _) = listener.accept().await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    writer.write_all(b"220 mail.example.com ESMTP\r\n").await.unwrap();
+    let mut transcript = Vec::new();
+    let mut in_data = false;
+    while let Some(line) = lines.next_line().await.unwrap() {
+        transcript.push(line.clone());
+        let reply: &[u8] = match line.as_str() {
+            "." if in_data => {
+                in_data = false;
+                b"250 OK queued\r\n"
+            },
+            _ if in_data => continue,
+            "DATA" => {
+                in_data = true;
+                b"354 End data with <CR><LF>.<CR><LF>\r\n"
+            },
+            "QUIT" => {
+                writer.write_all(b"221 Bye\r\n").await.unwrap();
+                break;
+            },
+            _ if line.starts_with("EHLO") => b"250-mail.example.com\r\n250 AUTH PLAIN\r\n",
+            _ if line.starts_with("AUTH") => b"235 Authenticated\r\n",
+            _ => b"250 OK\r\n",
+        };
+        writer.write_all(reply).await.unwrap();
+    }
+    transcript
+}
+
+#[tokio::test]
+async fn test_email_alert_is_sent_over_smtp() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(fake_smtp_server(listener));
+    let config = EmailConfig {
+        smtp_host: "127.0.0.1".to_string(),
+        smtp_port: port,
+        tls: SmtpTls::None,
+        username: Some("bot".to_string()),
+        password: Some("hunter2".to_string()),
+        from: "Trading bot <bot@example.com>".to_string(),
+        to: vec!["ops@example.com".to_string(), "alice@example.com".to_string()],
+        batch_window: Duration::from_secs(60),
+        min_severity: Severity::Critical,
+    };
+    assert!(!format!("{:?}", config).contains("hunter2"));
+    let batch = vec![
+        Notification::Liquidation { symbol: "BTCUSDT".to_string(), side: "SELL".to_string(), quantity: 0.002, price: 9000.0 },
+        Notification::Panicked { message: ".panicked at src/main.rs".to_string() },
+    ];
+    let (subject, body) = batch_mail(&batch);
+    assert_eq!(subject, "[trading bot] 2 events");
+    EmailNotifier::new(config).send_mail(&subject, &body).await.unwrap();
+
+    let transcript = server.await.unwrap();
+    assert_eq!(transcript[2], "MAIL FROM:<bot@example.com>");
+    assert_eq!(&transcript[3..5], ["RCPT TO:<ops@example.com>", "RCPT TO:<alice@example.com>"]);
+    assert!(transcript.contains(&"Subject: [trading bot] 2 events".to_string()));
+    assert!(transcript.contains(&"..panicked at src/main.rs".to_string())); // Dot-stuffed
+    assert_eq!(transcript.last().map(String::as_str), Some("QUIT"));
+}
+
+#[test]
+fn test_email_subjects_cannot_inject_headers() {
+    let to = vec!["ops@example.com".to_string()];
+    let message = build_message("bot@example.com", &to, "Rejected\r\nBcc: attacker@example.com", "body").unwrap();
+    let formatted = String::from_utf8(message.formatted()).unwrap();
+    assert!(!formatted.lines().any(|line| line.starts_with("Bcc:")), "{}", formatted);
+    assert_eq!(message.envelope().to().len(), 1);
+    assert!(build_message("not an address", &to, "subject", "body").is_err());
+}
+
+/// Records the batches it is sent.
+struct RecordingNotifier(Batches);
+
+impl Notifier for RecordingNotifier {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn send<'a>(&'a self, batch: &'a [Notification]) -> BoxFuture<'a, Result<(), String>> {
+        self.0.lock().unwrap().push(batch.to_vec());
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::test]
+async fn test_fanout_filters_by_severity_and_batches() {
+    let (everything, critical): (Batches, Batches) = Default::default();
+    let notifications = Notifications::start(vec![
+        Channel::new(RecordingNotifier(everything.clone()), Severity::Info),
+        Channel { notifier: Box::new(RecordingNotifier(critical.clone())), min_severity: Severity::Critical, batch_window: Some(Duration::from_millis(300)) },
+    ]);
+    let disconnected = Notification::Disconnected { connection: "orders".to_string() };
+    let panicked = |message: &str| Notification::Panicked { message: message.to_string() };
+    notifications.notify(disconnected.clone());
+    notifications.notify(panicked("first"));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    notifications.notify(panicked("second"));
+    notifications.notify(panicked("third"));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(critical.lock().unwrap().clone(), vec![vec![panicked("first")]]); // The rest waits for the window
+    tokio::time::sleep(Duration::from_millis(400)).await;
+
+    assert_eq!(critical.lock().unwrap().clone(), vec![vec![panicked("first")], vec![panicked("second"), panicked("third")]]);
+    let everything = everything.lock().unwrap().clone();
+    assert_eq!(everything.len(), 4);
+    assert_eq!(everything[0], vec![disconnected]);
+}
+
+#[test]
+fn test_kill_switch_notification_lists_its_problems() {
+    let notification = Notification::KillSwitch {
+        source: "admin API".to_string(),
+        cancelled_orders: 3,
+        closed_positions: 2,
+        problems: vec!["Failed to cancel order 7 on ETHUSDT: unknown order".to_string()],
+    };
+    assert_eq!(notification.severity(), Severity::Critical);
+    assert_eq!(notification.text(), "Kill switch triggered by the admin API\n\
+        Bot disarmed; cancelled 3 open order(s) and closed 2 position(s)\n\
+        Failed to cancel order 7 on ETHUSDT: unknown order");
+}
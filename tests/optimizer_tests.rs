@@ -0,0 +1,84 @@
+// tests/optimizer_tests.rs
+
+//! This file contains tests for the parallel parameter grid search.
+
+use trading_bot::strategy::optimizer::*;
+use trading_bot::strategy::walk_forward::Objective;
+use trading_bot::strategy::BacktestConfig;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_parse_values() {
+    assert_eq!(parse_values("1,2,3").unwrap(), vec![1.0, 2.0, 3.0]);
+    assert_eq!(parse_values("0.5").unwrap(), vec![0.5]);
+    let risks = parse_values("0.1:0.3:0.1").unwrap();
+    assert_eq!(risks.len(), 3);
+    assert!((risks[2] - 0.3).abs() < 1e-12);
+    assert!(parse_values("3:1:1").is_err());
+    assert!(parse_values("1:2").is_err());
+    assert!(parse_values("a,b").is_err());
+}
+
+#[test]
+fn test_grid_candidates() {
+    let (grid, rest) = GridSearchConfig::from_args(&args(&[
+        "--fast-range", "5:10:5", "--slow-range", "10:20:10", "--rr-values", "1,2", "--threads", "3", "--balance", "1000", "--heatmap", "h.csv",
+    ])).unwrap();
+    assert_eq!(rest, args(&["--balance", "1000"]));
+    assert_eq!((grid.threads, grid.thread_count(), grid.heatmap_output.as_deref()), (3, 3, Some("h.csv")));
+
+    let base = BacktestConfig { output: Some("report.json".to_string()), ..BacktestConfig::default() };
+    let candidates = grid.candidates(&base);
+    // (5,10) (5,20) (10,20) x two ratios; (10,10) is skipped
+    assert_eq!(candidates.len(), 6);
+    assert!(candidates.iter().all(|c| c.risk_percentage == base.risk_percentage && c.output.is_none()));
+    assert_eq!((candidates[1].fast_ema_period, candidates[1].slow_ema_period, candidates[1].risk_reward_ratio), (5, 10, 2.0));
+    assert!(GridSearchConfig::default().thread_count() >= 1);
+}
+
+#[tokio::test]
+async fn test_grid_search_ranks_and_writes_heatmap() {
+    let dir = std::env::temp_dir().join(format!("optimizer_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("candles.csv");
+    let output = dir.join("results.json");
+    let heatmap_output = dir.join("heatmap.csv");
+
+    // A rising market with regular pullbacks below the fast EMA
+    let mut csv = String::from("Open time,Open,High,Low,Close,Volume,Close time,Quote asset volume,Number of trades,Taker buy base asset volume,Taker buy quote asset volume,Ignore\n");
+    for i in 0..400u64 {
+        let close = 100.0 + i as f64 * 0.5 + 4.0 * (i as f64 * 0.7).sin();
+        let open_time = 1_609_459_200_000 + i * 14_400_000;
+        csv.push_str(&format!("{},{},{},{},{},1,{},1,1,0,0,0\n", open_time, close, close + 1.5, close - 1.5, close, open_time + 14_399_999));
+    }
+    std::fs::write(&data, csv).unwrap();
+
+    let (grid, rest) = GridSearchConfig::from_args(&args(&[
+        "--data", data.to_str().unwrap(), "--fast-range", "3:5", "--slow-range", "8:12:2", "--rr-values", "1,2", "--objective", "net_pnl",
+        "--threads", "4", "--output", output.to_str().unwrap(), "--heatmap", heatmap_output.to_str().unwrap(),
+    ])).unwrap();
+    let config = BacktestConfig::from_args(&rest).unwrap();
+    let results = run(&config, &grid).await.unwrap();
+
+    assert_eq!(results.len(), 3 * 3 * 2);
+    assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+    assert!(results.iter().all(|r| r.score == r.summary.net_pnl));
+
+    // Running on one thread gives the same ranking
+    let single = GridSearchConfig { threads: 1, heatmap_output: None, ..grid.clone() };
+    let sequential = run(&BacktestConfig { output: None, ..config.clone() }, &single).await.unwrap();
+    assert_eq!(sequential, results);
+
+    let written: Vec<GridResult> = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    let key = |r: &GridResult| (r.fast_ema_period, r.slow_ema_period, r.risk_reward_ratio);
+    assert_eq!(written.iter().map(key).collect::<Vec<_>>(), results.iter().map(key).collect::<Vec<_>>());
+    let heatmap_csv = std::fs::read_to_string(&heatmap_output).unwrap();
+    assert_eq!(heatmap_csv.lines().count(), 1 + 9);
+    let best = heatmap(&results).into_iter().find(|&(fast, slow, _)| (fast, slow) == (results[0].fast_ema_period, results[0].slow_ema_period)).unwrap();
+    assert_eq!(best.2, results[0].score);
+    assert_eq!(grid.objective, Objective::NetPnl);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,83 @@
+// tests/order_book_tests.rs
+
+//! This file contains tests for the local order book and the features derived from it.
+
+use trading_bot::market_data::OrderBookSnapshot;
+use trading_bot::order_book::*;
+use trading_bot::streams::{DepthLevel, DepthStream};
+
+fn level(price: &str, quantity: &str) -> DepthLevel {
+    DepthLevel::Array(price.to_string(), quantity.to_string())
+}
+
+fn snapshot() -> OrderBookSnapshot {
+    OrderBookSnapshot {
+        last_update_id: 100,
+        event_time: 0,
+        transaction_time: 0,
+        bids: vec![level("99.0", "3.0"), level("98.0", "2.0"), level("97.0", "5.0")],
+        asks: vec![level("101.0", "1.0"), level("102.0", "2.0"), level("103.0", "4.0")],
+    }
+}
+
+fn diff(first: u64, last: u64, prev: u64, bids: Vec<DepthLevel>, asks: Vec<DepthLevel>) -> DepthStream {
+    DepthStream {
+        event_type: "depthUpdate".to_string(),
+        event_time: 1,
+        symbol: "BTCUSDT".to_string(),
+        first_update_id: first,
+        final_update_id: last,
+        prev_final_update_id: Some(prev),
+        bids,
+        asks,
+    }
+}
+
+#[test]
+fn test_local_book_applies_diffs_in_sequence() {
+    let mut book = LocalOrderBook::new("btcusdt");
+    book.apply_snapshot(&snapshot()).unwrap();
+
+    // Stale event is dropped
+    assert!(!book.apply_diff(&diff(90, 95, 89, vec![], vec![])).unwrap());
+    // First event straddles the snapshot id
+    assert!(book.apply_diff(&diff(98, 105, 97, vec![level("99.0", "0")], vec![level("100.5", "2.0")])).unwrap());
+    assert_eq!(book.best_bid(), Some((98.0, 2.0)));
+    assert_eq!(book.best_ask(), Some((100.5, 2.0)));
+    // A gap in `pu` is reported
+    assert!(book.apply_diff(&diff(110, 112, 109, vec![], vec![])).is_err());
+}
+
+#[test]
+fn test_imbalance_microprice_and_slope() {
+    let mut book = LocalOrderBook::new("BTCUSDT");
+    book.apply_snapshot(&snapshot()).unwrap();
+
+    // Top level: bid 3.0 vs ask 1.0
+    assert!((imbalance(&book, 1).unwrap() - 0.5).abs() < 1e-9);
+    // Microprice leans towards the ask because the bid is heavier
+    let micro = microprice(&book).unwrap();
+    assert!((micro - 100.5).abs() < 1e-9);
+    assert!(micro > book.mid_price().unwrap());
+
+    let slope = book_slope(&book.top_bids(3), book.mid_price().unwrap()).unwrap();
+    assert!(slope > 0.0);
+    assert_eq!(book_slope(&book.top_bids(1), 100.0), None);
+}
+
+#[test]
+fn test_feature_engine_rolling_imbalance() {
+    let config = FeatureConfig { levels: 1, rolling_window: 2, snapshot_limit: 5 };
+    let mut engine = OrderBookFeatureEngine::new("BTCUSDT", config);
+    engine.apply_snapshot(&snapshot()).unwrap();
+
+    let first = engine.on_depth_update(&diff(100, 101, 99, vec![], vec![])).unwrap().unwrap();
+    assert!((first.imbalance - 0.5).abs() < 1e-9);
+    assert!((first.rolling_imbalance - 0.5).abs() < 1e-9);
+
+    // Ask size grows to match the bid: imbalance drops to 0, rolling mean to 0.25
+    let second = engine.on_depth_update(&diff(102, 102, 101, vec![], vec![level("101.0", "3.0")])).unwrap().unwrap();
+    assert!(second.imbalance.abs() < 1e-9);
+    assert!((second.rolling_imbalance - 0.25).abs() < 1e-9);
+    assert!((second.spread - 2.0).abs() < 1e-9);
+}
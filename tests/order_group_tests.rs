@@ -0,0 +1,88 @@
+// tests/order_group_tests.rs
+
+//! This file contains offline tests for order group tracking and group-level cancellation.
+
+use serde_json::json;
+use trading_bot::order::bracket::*;
+use trading_bot::order::group::*;
+use trading_bot::order::{NewOrderRequest, OrderSide, OrderType};
+use trading_bot::streams::FuturesOrderUpdate;
+use trading_bot::websocket_stream::BinanceWsMessage;
+
+fn limit(client_order_id: &str, price: f64) -> NewOrderRequest {
+    NewOrderRequest::new("BTCUSDT", OrderSide::Buy, OrderType::Limit)
+        .price(price)
+        .quantity(0.01)
+        .new_client_order_id(client_order_id)
+}
+
+fn update(client_order_id: &str, status: &str, filled: &str) -> FuturesOrderUpdate {
+    let message = BinanceWsMessage::Raw(json!({
+        "e": "ORDER_TRADE_UPDATE", "E": 1, "T": 1,
+        "o": {
+            "s": "BTCUSDT", "c": client_order_id, "S": "BUY", "o": "LIMIT", "f": "GTC",
+            "q": "0.01", "p": "30000", "ap": "30000", "x": "TRADE", "X": status, "i": 1,
+            "l": filled, "z": filled, "L": "30000", "T": 1, "t": 1
+        }
+    }));
+    order_update_from_message(&message).expect("valid order update")
+}
+
+#[test]
+fn test_group_status_and_cancel() {
+    let mut tracker = OrderTracker::new();
+    for (i, price) in [30000.0, 29900.0, 29800.0].into_iter().enumerate() {
+        tracker.track(&limit(&format!("grid-{}", i), price), Some("grid")).unwrap();
+    }
+    tracker.track(&limit("solo", 29000.0), None).unwrap();
+    assert!(tracker.track(&limit("grid-0", 1.0), Some("grid")).is_err()); // Duplicate client order ID
+    assert!(tracker.track(&NewOrderRequest::new("BTCUSDT", OrderSide::Buy, OrderType::Market), Some("grid")).is_err());
+
+    tracker.on_order_update(&update("grid-0", "FILLED", "0.01"));
+    tracker.on_order_update(&update("grid-1", "PARTIALLY_FILLED", "0.004"));
+    tracker.on_order_update(&update("grid-2", "NEW", "0"));
+    let status = tracker.group_status("grid").unwrap();
+    assert_eq!((status.orders, status.working, status.filled, status.cancelled), (3, 2, 1, 0));
+    assert!((status.filled_quantity - 0.014).abs() < 1e-12);
+
+    let cancels: Vec<String> = tracker.cancel_group("grid").unwrap().into_iter().map(|action| match action {
+        BracketAction::CancelOrder { client_order_id, .. } => client_order_id,
+        other => panic!("unexpected {:?}", other),
+    }).collect();
+    assert_eq!(cancels, vec!["grid-1", "grid-2"]); // Filled and ungrouped orders are left alone
+    assert!(tracker.remove_group("grid").is_err()); // Still working until the cancels are confirmed
+
+    tracker.on_order_update(&update("grid-1", "CANCELED", "0.004"));
+    tracker.on_order_update(&update("grid-2", "CANCELED", "0"));
+    assert!(tracker.group_status("grid").unwrap().is_done());
+    assert!(tracker.cancel_group("grid").unwrap().is_empty());
+    tracker.remove_group("grid").unwrap();
+    assert_eq!(tracker.group_status("grid"), None);
+    assert!(tracker.cancel_group("grid").is_err());
+    assert_eq!(tracker.get("solo").unwrap().state, TrackedOrderState::Submitted);
+}
+
+#[test]
+fn test_tracks_bracket_legs_as_group() {
+    let mut manager = BracketManager::new();
+    let mut tracker = OrderTracker::new();
+    let bracket = BracketOrder {
+        id: "br1".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        quantity: 0.01,
+        entry_price: None,
+        stop_loss: 29000.0,
+        take_profit: 32000.0,
+        position_side: None,
+    };
+    tracker.track_actions(&manager.register(bracket).unwrap(), "br1").unwrap();
+    let fill = update("br1-en", "FILLED", "0.01");
+    tracker.on_order_update(&fill);
+    tracker.track_actions(&manager.on_order_update(&fill), "br1").unwrap();
+
+    let ids: Vec<&str> = tracker.group_orders("br1").iter().map(|o| o.client_order_id.as_str()).collect();
+    assert_eq!(ids, vec!["br1-en", "br1-sl", "br1-tp"]);
+    assert_eq!(tracker.cancel_group("br1").unwrap().len(), 2); // Both protective legs
+    assert_eq!(tracker.groups(), vec!["br1"]);
+}
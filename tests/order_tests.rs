@@ -0,0 +1,120 @@
+// tests/order_tests.rs
+
+//! This file contains offline tests for building order requests (no network access needed).
+
+use trading_bot::order::{quote_to_base_quantity, ExecutionType, NewOrderRequest, OrderSide, OrderStatus, OrderType, PositionSide, WorkingType};
+
+#[test]
+fn test_reduce_only_params() {
+    let request = NewOrderRequest::new("btcusdt", OrderSide::Sell, OrderType::Market)
+        .quantity(0.01)
+        .reduce_only(true);
+    assert!(request.validate().is_ok());
+
+    let params = request.to_params();
+    assert_eq!(params["symbol"], "BTCUSDT");
+    assert_eq!(params["side"], "SELL");
+    assert_eq!(params["type"], "MARKET");
+    assert_eq!(params["quantity"], "0.01");
+    assert_eq!(params["reduceOnly"], "true");
+    assert!(params.get("closePosition").is_none());
+}
+
+#[test]
+fn test_close_position_validation() {
+    let without_quantity = NewOrderRequest::new("BTCUSDT", OrderSide::Sell, OrderType::StopMarket)
+        .stop_price(25000.0)
+        .close_position(true);
+    assert!(without_quantity.validate().is_ok());
+    assert_eq!(without_quantity.to_params()["closePosition"], "true");
+    assert!(without_quantity.to_params().get("quantity").is_none());
+
+    let with_quantity = without_quantity.clone().quantity(1.0);
+    assert!(with_quantity.validate().is_err());
+
+    let with_reduce_only = without_quantity.reduce_only(true);
+    assert!(with_reduce_only.validate().is_err());
+
+    let missing_quantity = NewOrderRequest::new("BTCUSDT", OrderSide::Buy, OrderType::Market);
+    assert!(missing_quantity.validate().is_err());
+}
+
+#[test]
+fn test_reduce_only_rejected_in_hedge_mode() {
+    let request = NewOrderRequest::new("BTCUSDT", OrderSide::Sell, OrderType::Market)
+        .quantity(0.01)
+        .position_side(PositionSide::Long)
+        .reduce_only(true);
+    assert!(request.validate().is_err());
+
+    let hedge_close = NewOrderRequest::new("BTCUSDT", OrderSide::Sell, OrderType::Market)
+        .quantity(0.01)
+        .position_side(PositionSide::Long);
+    assert_eq!(hedge_close.to_params()["positionSide"], "LONG");
+}
+
+#[test]
+fn test_protective_order_params() {
+    let stop = NewOrderRequest::new("BTCUSDT", OrderSide::Sell, OrderType::StopMarket)
+        .quantity(0.01)
+        .stop_price(25000.5)
+        .reduce_only(true);
+    assert!(stop.validate().is_ok());
+    let params = stop.to_params();
+    assert_eq!(params["type"], "STOP_MARKET");
+    assert_eq!(params["stopPrice"], "25000.5");
+
+    let missing_stop = NewOrderRequest::new("BTCUSDT", OrderSide::Sell, OrderType::TakeProfitMarket)
+        .quantity(0.01);
+    assert!(missing_stop.validate().is_err());
+
+    let trailing = NewOrderRequest::new("BTCUSDT", OrderSide::Sell, OrderType::TrailingStopMarket)
+        .quantity(0.01)
+        .activation_price(30000.0)
+        .callback_rate(1.5);
+    assert!(trailing.validate().is_ok());
+    let params = trailing.to_params();
+    assert_eq!(params["type"], "TRAILING_STOP_MARKET");
+    assert_eq!(params["activationPrice"], "30000");
+    assert_eq!(params["callbackRate"], "1.5");
+
+    assert!(trailing.clone().callback_rate(20.0).validate().is_err());
+    assert!(trailing.close_position(true).validate().is_err());
+}
+
+#[test]
+fn test_quote_quantity_conversion() {
+    // $500 of BTC at 60,000 with a 0.001 step rounds down to 0.008
+    assert_eq!(quote_to_base_quantity(500.0, 60000.0, 0.001).unwrap(), 0.008);
+    // Exact multiples are not lost to floating point error
+    assert_eq!(quote_to_base_quantity(30.0, 100.0, 0.1).unwrap(), 0.3);
+    assert_eq!(quote_to_base_quantity(1000.0, 3.0, 1.0).unwrap(), 333.0);
+    assert!(quote_to_base_quantity(10.0, 60000.0, 0.001).is_err());
+    assert!(quote_to_base_quantity(10.0, 0.0, 0.001).is_err());
+
+    let request = NewOrderRequest::new("BTCUSDT", OrderSide::Buy, OrderType::Market).quote_quantity(500.0);
+    assert!(request.validate().is_err()); // Step size is required for the conversion
+    assert!(request.clone().step_size(0.001).validate().is_ok());
+    assert!(request.step_size(0.001).quantity(0.01).validate().is_err());
+}
+
+#[test]
+fn test_typed_order_enums() {
+    let status: OrderStatus = serde_json::from_str("\"PARTIALLY_FILLED\"").unwrap();
+    assert_eq!(status, OrderStatus::PartiallyFilled);
+    assert!(!status.is_final());
+    assert_eq!(serde_json::to_string(&OrderStatus::ExpiredInMatch).unwrap(), "\"EXPIRED_IN_MATCH\"");
+    assert!(OrderStatus::Filled.is_final());
+
+    assert_eq!(serde_json::from_str::<ExecutionType>("\"TRADE\"").unwrap(), ExecutionType::Trade);
+    assert_eq!(serde_json::from_str::<WorkingType>("\"MARK_PRICE\"").unwrap(), WorkingType::MarkPrice);
+    assert_eq!(serde_json::from_str::<PositionSide>("\"SHORT\"").unwrap(), PositionSide::Short);
+    assert_eq!(PositionSide::Long.to_string(), "LONG");
+
+    // Values added by the exchange later are kept rather than failing the whole message
+    let unknown: OrderStatus = serde_json::from_str("\"NEW_INSURANCE\"").unwrap();
+    assert_eq!(unknown, OrderStatus::Other("NEW_INSURANCE".to_string()));
+    assert_eq!(serde_json::to_string(&unknown).unwrap(), "\"NEW_INSURANCE\"");
+    assert_eq!(serde_json::from_str::<ExecutionType>("\"AMENDMENT\"").unwrap(), ExecutionType::Amendment);
+    assert_eq!(serde_json::from_str::<PositionSide>("\"SIDEWAYS\"").unwrap().as_str(), "SIDEWAYS");
+}
@@ -0,0 +1,92 @@
+// tests/patterns_tests.rs
+
+//! This file contains tests for candlestick pattern detection and the pattern entry filter.
+
+use trading_bot::market_event::{Candle, Symbol};
+use trading_bot::metrics::MetricsConfig;
+use trading_bot::patterns::*;
+use trading_bot::strategy::backtest;
+use trading_bot::strategy::rsi::{RsiConfig, RsiStrategy};
+
+fn candle(open: f64, high: f64, low: f64, close: f64) -> Candle {
+    Candle {
+        symbol: Symbol::new("BTCUSDT").unwrap(),
+        event_time: 0,
+        interval_ms: 60_000,
+        open_time: 0,
+        close_time: 59_999,
+        open,
+        high,
+        low,
+        close,
+        volume: 1.0,
+        quote_volume: close,
+        trades: 1,
+        is_closed: true,
+    }
+}
+
+#[test]
+fn test_detects_two_candle_patterns() {
+    let config = PatternConfig::default();
+    let bullish = [candle(105.0, 106.0, 99.0, 100.0), candle(99.0, 108.0, 98.0, 107.0)];
+    assert!(Pattern::BullishEngulfing.matches(&bullish, &config));
+    assert!(!Pattern::BearishEngulfing.matches(&bullish, &config));
+    assert!(!Pattern::BullishEngulfing.matches(&bullish[1..], &config)); // Needs the previous candle
+
+    let bearish = [candle(100.0, 106.0, 99.0, 105.0), candle(106.0, 107.0, 97.0, 98.0)];
+    assert!(Pattern::BearishEngulfing.matches(&bearish, &config));
+
+    let inside = [candle(100.0, 110.0, 90.0, 105.0), candle(104.0, 108.0, 95.0, 101.0)];
+    assert_eq!(detect(&inside, &config), vec![Pattern::InsideBar]);
+}
+
+#[test]
+fn test_detects_pin_bars_and_dojis() {
+    let config = PatternConfig::default();
+    let hammer = [candle(108.0, 110.0, 100.0, 109.0)];
+    assert!(Pattern::BullishPinBar.matches(&hammer, &config));
+    assert_eq!(Pattern::BullishPinBar.bias(), Bias::Bullish);
+
+    let shooting_star = [candle(101.0, 110.0, 100.0, 102.0)];
+    assert!(Pattern::BearishPinBar.matches(&shooting_star, &config));
+
+    let dragonfly = [candle(110.0, 110.0, 100.0, 109.5)];
+    let patterns = detect(&dragonfly, &config);
+    assert!(patterns.contains(&Pattern::Doji) && patterns.contains(&Pattern::DragonflyDoji));
+    assert!(!patterns.contains(&Pattern::GravestoneDoji));
+
+    let long_legged = [candle(105.0, 110.0, 100.0, 105.2)];
+    assert!(Pattern::LongLeggedDoji.matches(&long_legged, &config));
+    assert!(detect(&[candle(100.0, 100.0, 100.0, 100.0)], &config).is_empty());
+}
+
+#[test]
+fn test_pattern_filter_lookback() {
+    let candles = [candle(108.0, 110.0, 100.0, 109.0), candle(109.0, 115.0, 108.0, 114.0)];
+    let mut filter = PatternFilter::new(vec![Pattern::BullishPinBar]);
+    assert!(!filter.allows(&candles));
+    filter.lookback = 2;
+    assert!(filter.allows(&candles));
+    assert!(PatternFilter::new(vec![]).allows(&candles));
+
+    let parsed: PatternFilter = serde_json::from_str(r#"{"patterns": ["bullish_engulfing", "dragonfly_doji"]}"#).unwrap();
+    assert_eq!(parsed.lookback, 1);
+    assert_eq!(parsed.patterns, vec![Pattern::BullishEngulfing, Pattern::DragonflyDoji]);
+}
+
+#[test]
+fn test_entry_filter_blocks_rsi_entries_without_pattern() {
+    let closes = [100.0, 98.0, 96.0, 94.0, 95.0, 99.0, 104.0];
+    let candles: Vec<Candle> = closes.iter().map(|c| candle(*c, *c, *c, *c)).collect();
+    let config = RsiConfig {
+        period: 3,
+        quantity: 1.0,
+        fee_rate: 0.0,
+        entry_filter: Some(PatternFilter::new(vec![Pattern::BullishEngulfing])),
+        ..RsiConfig::default()
+    };
+    let mut strategy = RsiStrategy::new(config).unwrap();
+    let result = backtest(&mut strategy, &candles, 1000.0, &MetricsConfig::default());
+    assert!(result.fills.is_empty());
+}
@@ -0,0 +1,74 @@
+// tests/performance_tests.rs
+
+//! This file contains tests for weekly/monthly performance aggregation.
+
+use chrono::NaiveDate;
+use trading_bot::currency::ReportingCurrency;
+use trading_bot::metrics::{Benchmark, MetricsConfig};
+use trading_bot::performance::*;
+
+fn ms(y: i32, m: u32, d: u32) -> i64 {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp_millis()
+}
+
+fn trade(closed_at_ms: i64, pnl: f64, fees: f64) -> ClosedTrade {
+    ClosedTrade { closed_at_ms, symbol: "BTCUSDT".to_string(), pnl, fees, variant: None }
+}
+
+fn config() -> MetricsConfig {
+    MetricsConfig { risk_free_rate: 0.0, periods_per_year: 365.0, benchmark: Benchmark::None }
+}
+
+#[test]
+fn test_period_boundaries() {
+    let wednesday = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+    assert_eq!(Period::Week.start_of(wednesday), NaiveDate::from_ymd_opt(2024, 1, 29).unwrap());
+    assert_eq!(Period::Month.start_of(wednesday), NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+    assert_eq!(Period::Month.next_start(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()), NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+    assert_eq!(Period::from_str_opt("Weekly"), Some(Period::Week));
+}
+
+#[test]
+fn test_monthly_aggregation() {
+    let trades = vec![
+        trade(ms(2024, 2, 3), -50.0, 1.0),
+        trade(ms(2024, 1, 10), 100.0, 2.0),
+        trade(ms(2024, 1, 20), -40.0, 2.0),
+    ];
+    let summaries = aggregate(&trades, Period::Month, 1000.0, &config());
+    assert_eq!(summaries.len(), 2);
+
+    let january = &summaries[0];
+    assert_eq!(january.label, "2024-01");
+    assert_eq!(january.trades, 2);
+    assert_eq!((january.wins, january.losses), (1, 1));
+    assert!((january.net_pnl - 56.0).abs() < 1e-9);
+    assert!((january.fees - 4.0).abs() < 1e-9);
+    assert!((january.return_pct - 5.6).abs() < 1e-9);
+    // Peak 1098 after the win, then down to 1056
+    assert!((january.max_drawdown_pct - 42.0 / 1098.0 * 100.0).abs() < 1e-9);
+
+    let february = &summaries[1];
+    assert!((february.starting_equity - 1056.0).abs() < 1e-9);
+    assert!(format_summary_message(february, ReportingCurrency::Usd).starts_with("Performance 2024-02"));
+}
+
+#[test]
+fn test_weekly_labels() {
+    let trades = vec![trade(ms(2024, 1, 1), 10.0, 0.0), trade(ms(2024, 1, 8), 10.0, 0.0)];
+    let labels: Vec<String> = aggregate(&trades, Period::Week, 1000.0, &config()).into_iter().map(|s| s.label).collect();
+    assert_eq!(labels, vec!["2024-W01", "2024-W02"]);
+}
+
+#[test]
+fn test_load_csv_with_optional_variant() {
+    let path = std::env::temp_dir().join(format!("closed_trades_{}.csv", std::process::id()));
+    std::fs::write(&path, "closed_at_ms,symbol,pnl,fees\n1706745600000,BTCUSDT,12.5,0.5\n").unwrap();
+    let trades = load_closed_trades_csv(path.to_str().unwrap()).unwrap();
+    assert_eq!(trades[0].variant, None);
+
+    std::fs::write(&path, "closed_at_ms,symbol,pnl,fees,variant\n1706745600000,BTCUSDT,12.5,0.5,b\n").unwrap();
+    let trades = load_closed_trades_csv(path.to_str().unwrap()).unwrap();
+    assert_eq!(trades[0].variant.as_deref(), Some("b"));
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,95 @@
+// tests/reconcile_tests.rs
+
+//! This file contains tests for startup reconciliation: adopting unknown orders, flagging orphaned
+//! stops and position mismatches against the replayed event log.
+
+use serde_json::json;
+
+use trading_bot::account_info::{AccountInfo, PositionRisk};
+use trading_bot::events::{replay, BotEvent, EventRecord};
+use trading_bot::order::Order;
+use trading_bot::reconcile::{known_order_ids, reconcile, ExchangeState};
+
+fn order(client_order_id: &str, order_type: &str, side: &str, stop_price: &str, reduce_only: bool) -> Order {
+    serde_json::from_value(json!({
+        "symbol": "BTCUSDT", "orderId": 42, "clientOrderId": client_order_id, "price": "0", "origQty": "0.010",
+        "executedQty": "0", "cumQuote": "0", "status": "NEW", "timeInForce": "GTC", "type": order_type,
+        "side": side, "stopPrice": stop_price, "time": 1714564800000u64, "updateTime": 1714564800000u64,
+        "avgPrice": "0", "closePosition": false, "goodTillDate": 0, "origType": order_type,
+        "positionSide": "BOTH", "priceMatch": "NONE", "priceProtect": false, "reduceOnly": reduce_only,
+        "selfTradePreventionMode": "NONE", "workingType": "MARK_PRICE"
+    })).unwrap()
+}
+
+fn position(symbol: &str, amount: &str) -> PositionRisk {
+    serde_json::from_value(json!({
+        "symbol": symbol, "positionSide": "BOTH", "positionAmt": amount, "entryPrice": "60000",
+        "markPrice": "60100", "unRealizedProfit": "1", "liquidationPrice": "0", "notional": "600", "updateTime": 0
+    })).unwrap()
+}
+
+fn account() -> AccountInfo {
+    serde_json::from_value(json!({
+        "totalInitialMargin": "0", "totalMaintMargin": "0", "totalWalletBalance": "1000",
+        "totalUnrealizedProfit": "1", "totalMarginBalance": "1001", "totalPositionInitialMargin": "0",
+        "totalOpenOrderInitialMargin": "0", "totalCrossWalletBalance": "1000", "totalCrossUnPnl": "1",
+        "availableBalance": "900", "maxWithdrawAmount": "900", "assets": [], "positions": []
+    })).unwrap()
+}
+
+fn records() -> Vec<EventRecord> {
+    let events = [
+        BotEvent::OrderPlaced { symbol: "BTCUSDT".to_string(), signal: "buy".to_string(), client_order_id: "whb123-vA".to_string(), side: "BUY".to_string(), quantity: 0.01, price: 60000.0 },
+        BotEvent::Fill { symbol: "BTCUSDT".to_string(), client_order_id: "whb123-vA".to_string(), side: "BUY".to_string(), position_side: None, price: 60000.0, quantity: 0.01, realized_pnl: 0.0, commission: 0.1 },
+        BotEvent::Fill { symbol: "ETHUSDT".to_string(), client_order_id: "manual".to_string(), side: "SELL".to_string(), position_side: None, price: 3000.0, quantity: 1.0, realized_pnl: 0.0, commission: 0.1 },
+    ];
+    events.into_iter().enumerate().map(|(i, event)| EventRecord { seq: i as u64 + 1, time_ms: 1_000 * i as i64, event }).collect()
+}
+
+#[test]
+fn test_unknown_orders_are_adopted_and_protective_orders_are_known() {
+    let records = records();
+    let exchange = ExchangeState {
+        orders: vec![
+            order("whb123sl-vA", "STOP_MARKET", "SELL", "58000", true), // Stop-loss of the logged entry
+            order("web_abc", "LIMIT", "BUY", "0", false), // Placed by hand
+        ],
+        positions: vec![position("BTCUSDT", "0.010")],
+        account: account(),
+    };
+    let report = reconcile(&replay(&records, None), &known_order_ids(&records), &exchange, None);
+    assert_eq!(report.known_orders, 1);
+    assert_eq!(report.adopted_orders.len(), 1);
+    assert_eq!(report.adopted_orders[0].client_order_id, "web_abc");
+    assert!(matches!(&report.adoption_events()[0], BotEvent::OrderAdopted { client_order_id, .. } if client_order_id == "web_abc"));
+    assert!(report.orphaned_stops.is_empty());
+    assert_eq!(report.balances.wallet_balance, 1000.0);
+
+    // The ETHUSDT short of the log is gone on the exchange
+    assert_eq!(report.position_mismatches.len(), 1);
+    assert_eq!(report.position_mismatches[0].key, "ETHUSDT");
+    assert_eq!((report.position_mismatches[0].local_quantity, report.position_mismatches[0].exchange_quantity), (-1.0, 0.0));
+
+    // Once adopted, the order is known on the next start
+    let mut records = records;
+    records.push(EventRecord { seq: 4, time_ms: 4_000, event: report.adoption_events().remove(0) });
+    assert!(reconcile(&replay(&records, None), &known_order_ids(&records), &exchange, None).adopted_orders.is_empty());
+}
+
+#[test]
+fn test_stops_without_a_position_are_orphaned_and_unknown_positions_flagged() {
+    let exchange = ExchangeState {
+        orders: vec![order("whs9tp", "TAKE_PROFIT_MARKET", "BUY", "2800", true)],
+        positions: vec![position("BTCUSDT", "0"), position("SOLUSDT", "-3")],
+        account: account(),
+    };
+    let report = reconcile(&Default::default(), &Default::default(), &exchange, None);
+    assert_eq!(report.orphaned_stops.len(), 1);
+    assert_eq!(report.orphaned_stops[0].client_order_id, "whs9tp");
+    assert_eq!(report.position_mismatches.len(), 1);
+    assert_eq!(report.position_mismatches[0].key, "SOLUSDT");
+    let issues = report.issues();
+    assert_eq!(issues.len(), 3); // Adopted, orphaned, unrecognized position
+    assert!(issues[1].starts_with("Orphaned TAKE_PROFIT_MARKET whs9tp"));
+    assert!(issues[2].starts_with("Unrecognized position SOLUSDT of -3"));
+}
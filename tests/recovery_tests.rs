@@ -0,0 +1,136 @@
+// tests/recovery_tests.rs
+
+//! This file contains tests for crash recovery: persisting working brackets, trailing stops and
+//! the risk counters, and resuming from the stored state.
+
+use std::sync::{Arc, Mutex};
+
+use serde_json::json;
+use trading_bot::order::bracket::{order_update_from_message, BracketManager, BracketOrder, BracketState};
+use trading_bot::order::trailing::{TrailDistance, TrailingAction, TrailingStopConfig, TrailingStopManager};
+use trading_bot::order::OrderSide;
+use trading_bot::recovery::StateStore;
+use trading_bot::risk::{BreakerCheck, DailyLossBreaker, ExecutionPolicies, RiskState};
+use trading_bot::websocket_stream::BinanceWsMessage;
+
+fn state_file(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("recovery_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("bot_state.json")
+}
+
+fn bracket(id: &str) -> BracketOrder {
+    BracketOrder {
+        id: id.to_string(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        quantity: 0.01,
+        entry_price: Some(30000.0),
+        stop_loss: 29000.0,
+        take_profit: 32000.0,
+        position_side: None,
+    }
+}
+
+#[test]
+fn test_brackets_resume_after_a_restart() {
+    let path = state_file("brackets");
+    let store = StateStore::load(&path).unwrap();
+    assert!(store.state().brackets.is_empty());
+
+    let mut manager = BracketManager::new();
+    manager.register(bracket("br1")).unwrap();
+    manager.register(bracket("br2")).unwrap();
+    manager.cancel("br2").unwrap();
+    store.update(|state| state.brackets = manager.snapshot()).unwrap();
+    drop(manager);
+
+    // The entry fills after the restart: the restored manager still protects it
+    let mut manager = BracketManager::restore(StateStore::load(&path).unwrap().state().brackets);
+    assert!(manager.get("br2").is_none());
+    let message = BinanceWsMessage::Raw(json!({
+        "e": "ORDER_TRADE_UPDATE", "E": 1, "T": 1,
+        "o": {
+            "s": "BTCUSDT", "c": "br1-en", "S": "BUY", "o": "LIMIT", "f": "GTC", "q": "0.01", "p": "30000",
+            "ap": "30000", "x": "TRADE", "X": "FILLED", "i": 1, "l": "0.01", "z": "0.01", "L": "30000", "T": 1, "t": 1
+        }
+    }));
+    let actions = manager.on_order_update(&order_update_from_message(&message).unwrap());
+    assert_eq!(actions.len(), 2);
+    assert_eq!(manager.get("br1").unwrap().state, BracketState::Protected);
+}
+
+#[test]
+fn test_trailing_stops_keep_their_water_mark() {
+    let path = state_file("trailing");
+    let store = StateStore::load(&path).unwrap();
+    let mut manager = TrailingStopManager::default();
+    manager.start(TrailingStopConfig {
+        id: "ts1".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        position: OrderSide::Buy,
+        quantity: 0.01,
+        distance: TrailDistance::Percent(1.0),
+        min_step: 1.0,
+        initial_stop: None,
+        position_side: None,
+    }).unwrap();
+    manager.on_price("BTCUSDT", 31000.0);
+    store.update(|state| state.trailing_stops = manager.snapshot()).unwrap();
+
+    let mut manager = TrailingStopManager::restore(14, StateStore::load(&path).unwrap().state().trailing_stops);
+    let stop = manager.get("ts1").unwrap();
+    assert_eq!((stop.water_mark, stop.stop_client_order_id()), (Some(31000.0), Some("ts1-ts1")));
+    // A lower price after the restart does not loosen the stop; a new high replaces the same order
+    assert!(manager.on_price("BTCUSDT", 30500.0).is_empty());
+    let actions = manager.on_price("BTCUSDT", 32000.0);
+    assert_eq!(actions[0], TrailingAction::CancelOrder { symbol: "BTCUSDT".to_string(), client_order_id: "ts1-ts1".to_string() });
+    assert_eq!(manager.get("ts1").unwrap().stop_client_order_id(), Some("ts1-ts2"));
+}
+
+#[test]
+fn test_corrupt_state_file_is_an_error() {
+    let path = state_file("corrupt");
+    std::fs::write(&path, "{ not json").unwrap();
+    assert!(StateStore::load(&path).unwrap_err().starts_with("Invalid bot state"));
+}
+
+#[test]
+fn test_risk_counters_and_a_tripped_loss_breaker_hold_after_a_restart() {
+    let path = state_file("risk");
+    let day_ms = 86_400_000;
+    let policies = |store: StateStore, risk: RiskState| ExecutionPolicies {
+        loss_breaker: Some(DailyLossBreaker { max_loss: 300.0, flatten: false }),
+        state: Arc::new(Mutex::new(risk)),
+        store: Some(Arc::new(store)),
+        ..ExecutionPolicies::default()
+    };
+    let running = policies(StateStore::load(&path).unwrap(), RiskState::default());
+    assert_eq!(running.check_loss_breaker(-100.0, day_ms + 1_000), BreakerCheck::Clear);
+    assert_eq!(StateStore::load(&path).unwrap().state().risk.unwrap().loss_session.unwrap().unrealized_at_start, -100.0);
+    // A losing close: the day's PnL and the cooldown are saved with it
+    let message = BinanceWsMessage::Raw(json!({
+        "e": "ORDER_TRADE_UPDATE", "E": 1, "T": day_ms + 2_000,
+        "o": {
+            "s": "BTCUSDT", "c": "whs1", "S": "SELL", "o": "MARKET", "f": "GTC", "q": "0.01", "p": "0",
+            "ap": "29000", "x": "TRADE", "X": "FILLED", "i": 2, "l": "0.01", "z": "0.01", "L": "29000",
+            "n": "0.5", "rp": "-149.5", "T": day_ms + 2_000, "t": 2
+        }
+    }));
+    running.on_order_update(&order_update_from_message(&message).unwrap());
+    let saved = StateStore::load(&path).unwrap().state().risk.unwrap();
+    assert_eq!((saved.realized_pnl_today, saved.last_loss_ms), (-150.0, Some(day_ms + 2_000)));
+    assert!(matches!(running.check_loss_breaker(-300.0, day_ms + 3_000), BreakerCheck::Tripped(_)));
+    drop(running);
+
+    // The restarted bot restores the counters: still tripped, with the same baseline and PnL
+    let store = StateStore::load(&path).unwrap();
+    let restored = store.state().risk.unwrap();
+    assert_eq!(restored.realized_pnl_on(day_ms + 4_000), -150.0);
+    let restarted = policies(store, restored);
+    assert!(matches!(restarted.check_loss_breaker(0.0, day_ms + 4_000), BreakerCheck::Holding(reason) if reason.starts_with("Daily loss 350.00")));
+    assert_eq!(restarted.check_loss_breaker(0.0, 2 * day_ms), BreakerCheck::Clear); // The next UTC day
+    assert_eq!(StateStore::load(&path).unwrap().state().risk.unwrap().loss_session.unwrap().tripped, None);
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
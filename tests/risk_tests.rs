@@ -0,0 +1,177 @@
+// tests/risk_tests.rs
+
+//! This file contains tests for the pluggable sizing and risk policies.
+
+use trading_bot::order::OrderSide;
+use trading_bot::risk::schedule::TradingSchedule;
+use trading_bot::risk::*;
+
+fn sizing_context() -> SizingContext {
+    SizingContext { symbol: "BTCUSDT".to_string(), price: 100.0, equity: 10_000.0, stop_price: Some(95.0), atr: Some(2.5) }
+}
+
+#[test]
+fn test_builtin_sizing_policies() {
+    let ctx = sizing_context();
+    assert_eq!(FixedQuantity(0.5).quantity(&ctx).unwrap(), 0.5);
+    assert_eq!(FixedNotional(500.0).quantity(&ctx).unwrap(), 5.0);
+    assert_eq!(RiskPercent { risk_fraction: 0.01 }.quantity(&ctx).unwrap(), 20.0); // $100 risk / $5 stop
+    assert_eq!(AtrRisk { risk_fraction: 0.01, atr_multiple: 2.0 }.quantity(&ctx).unwrap(), 20.0);
+    assert!(RiskPercent { risk_fraction: 0.01 }.quantity(&SizingContext { stop_price: None, ..ctx.clone() }).is_err());
+    assert!(DEFAULT_ATR_RISK.quantity(&SizingContext { atr: None, ..ctx.clone() }).is_err());
+    // A 2 ATR stop of the 2.5 ATR is the 5 point stop risk-percent sizing was given
+    assert_eq!(DEFAULT_ATR_RISK.stop_price(OrderSide::Buy, ctx.price, 2.5), 95.0);
+    assert_eq!(DEFAULT_ATR_RISK.stop_price(OrderSide::Sell, ctx.price, 2.5), 105.0);
+
+    // One ATR (2.5) moves 0.5% of equity, until the 3x leverage cap
+    let volatility_target = VolatilityTarget { target_fraction: 0.005, max_leverage: 3.0 };
+    assert_eq!(volatility_target.quantity(&ctx).unwrap(), 20.0);
+    assert_eq!(volatility_target.quantity(&SizingContext { atr: Some(0.1), ..ctx.clone() }).unwrap(), 300.0);
+
+    let kelly = Kelly { win_rate: 0.6, payoff_ratio: 1.0, kelly_multiplier: 0.5, max_fraction: 0.25 };
+    assert!((kelly.fraction() - 0.1).abs() < 1e-9);
+    assert!((kelly.quantity(&ctx).unwrap() - 10.0).abs() < 1e-9);
+    let no_edge = Kelly { win_rate: 0.4, ..kelly };
+    assert!(no_edge.quantity(&ctx).is_err());
+}
+
+#[test]
+fn test_position_sizers_are_parsed_from_config() {
+    let ctx = sizing_context();
+    let sizer = PositionSizer::parse("risk_percent:0.01").unwrap();
+    assert_eq!(sizer, PositionSizer::RiskPercent(RiskPercent { risk_fraction: 0.01 }));
+    assert_eq!((sizer.name(), sizer.quantity(&ctx).unwrap()), ("risk_percent", 20.0));
+    assert_eq!(PositionSizer::parse("ATR_RISK:0.01:2").unwrap(), PositionSizer::AtrRisk(DEFAULT_ATR_RISK));
+    let kelly = PositionSizer::parse("kelly:0.6:1:0.5:0.25").unwrap();
+    assert!((kelly.quantity(&ctx).unwrap() - 10.0).abs() < 1e-9);
+    assert_eq!(PositionSizer::parse("volatility_target:0.005:3").unwrap().name(), "volatility_target");
+
+    assert!(PositionSizer::parse("fixed_quantity").unwrap_err().contains("wrong number"));
+    assert!(PositionSizer::parse("fixed_notional:-5").is_err());
+    assert!(PositionSizer::parse("risk_percent:2").is_err());
+    assert!(PositionSizer::parse("martingale:2").unwrap_err().contains("Unknown"));
+}
+
+#[test]
+fn test_builtin_risk_policies() {
+    let policies: Vec<Box<dyn RiskPolicy>> = vec![
+        Box::new(MaxExposure { max_total_notional: 5_000.0, max_symbol_notional: Some(2_000.0) }),
+        Box::new(DailyLossLimit { max_loss: 200.0 }),
+        Box::new(Cooldown { after_loss_ms: 60_000 }),
+    ];
+    let ctx = RiskContext {
+        symbol: "BTCUSDT".to_string(),
+        now_ms: 1_000_000,
+        order_notional: 500.0,
+        total_exposure: 3_000.0,
+        symbol_exposure: 1_000.0,
+        equity: 10_000.0,
+        ..Default::default()
+    };
+    assert!(check_all(&policies, &ctx).is_ok());
+
+    let err = check_all(&policies, &RiskContext { symbol_exposure: 1_800.0, ..ctx.clone() }).unwrap_err();
+    assert!(err.starts_with("max_exposure"));
+    let err = check_all(&policies, &RiskContext { realized_pnl_today: -250.0, ..ctx.clone() }).unwrap_err();
+    assert!(err.starts_with("daily_loss_limit"));
+    let err = check_all(&policies, &RiskContext { last_loss_ms: Some(970_000), ..ctx.clone() }).unwrap_err();
+    assert!(err.starts_with("cooldown"));
+}
+
+#[test]
+fn test_risk_state_rolls_over_daily() {
+    let day_ms = 86_400_000;
+    let mut state = RiskState::default();
+    state.record_realized(-50.0, 10 * day_ms + 1_000);
+    state.record_realized(20.0, 10 * day_ms + 2_000);
+    assert_eq!(state.realized_pnl_on(10 * day_ms + 3_000), -30.0);
+    assert_eq!(state.last_loss_ms, Some(10 * day_ms + 1_000));
+    assert_eq!(state.realized_pnl_on(11 * day_ms), 0.0);
+
+    state.record_realized(5.0, 11 * day_ms + 1);
+    assert_eq!(state.realized_pnl_today, 5.0);
+}
+
+#[test]
+fn test_daily_loss_breaker_counts_unrealized_pnl_and_holds_until_the_next_day() {
+    let breaker = DailyLossBreaker { max_loss: 300.0, flatten: false };
+    let day_ms = 86_400_000;
+    let mut state = RiskState::default();
+    // A position carried over with -100 unrealized is the day's starting point
+    assert_eq!(breaker.check(&mut state, -100.0, day_ms + 1_000), BreakerCheck::Clear);
+    state.record_realized(-150.0, day_ms + 2_000);
+    assert_eq!(breaker.check(&mut state, -200.0, day_ms + 3_000), BreakerCheck::Clear); // -150 - 100
+    assert!(matches!(breaker.check(&mut state, -260.0, day_ms + 4_000), BreakerCheck::Tripped(reason) if reason.starts_with("Daily loss 310.00")));
+    // Still blocked after the positions recover, until 00:00 UTC
+    assert!(matches!(breaker.check(&mut state, 0.0, day_ms + 5_000), BreakerCheck::Holding(_)));
+    assert_eq!(breaker.check(&mut state, -260.0, 2 * day_ms), BreakerCheck::Clear);
+}
+
+#[test]
+fn test_exposure_limits_cap_positions_and_notional_per_quote_asset() {
+    let limits = ExposureLimits {
+        max_open_positions: Some(2),
+        max_total_notional: Some(10_000.0),
+        max_quote_notional: ExposureLimits::parse_quote_limits("usdt:6000, USDC:1000").unwrap(),
+    };
+    let ctx = RiskContext {
+        symbol: "ETHUSDT".to_string(),
+        order_notional: 1_000.0,
+        total_exposure: 5_000.0,
+        quote_exposure: 4_000.0,
+        open_positions: 2,
+        symbol_exposure: 500.0, // Adds to an open position
+        ..Default::default()
+    };
+    assert!(limits.check(&ctx).is_ok());
+    let err = limits.check(&RiskContext { symbol: "SOLUSDT".to_string(), symbol_exposure: 0.0, ..ctx.clone() }).unwrap_err();
+    assert_eq!(err, "2 positions are open, the limit is 2");
+    let err = limits.check(&RiskContext { quote_exposure: 5_500.0, ..ctx.clone() }).unwrap_err();
+    assert!(err.starts_with("USDT exposure 6500.00"));
+    let err = limits.check(&RiskContext { total_exposure: 9_500.0, ..ctx.clone() }).unwrap_err();
+    assert!(err.starts_with("Total exposure 10500.00"));
+    // Other quote assets have their own budget
+    assert!(limits.check(&RiskContext { symbol: "BTCUSDC".to_string(), quote_exposure: 0.0, order_notional: 900.0, ..ctx.clone() }).is_ok());
+
+    assert_eq!(quote_asset("btcfdusd"), Some("FDUSD"));
+    assert_eq!(quote_asset("ETHBTC"), Some("BTC"));
+    assert_eq!(quote_asset("USDT"), None);
+    assert!(ExposureLimits::parse_quote_limits("USDT=5000").is_err());
+}
+
+#[test]
+fn test_trading_schedule_allows_entries_during_sessions_only() {
+    let monday_ms = 1_704_067_200_000; // 2024-01-01 00:00 UTC
+    let at = |day: i64, hour: i64, minute: i64| monday_ms + ((day * 24 + hour) * 60 + minute) * 60_000;
+    let schedule = TradingSchedule::parse("mon-fri 07:00-21:30, sun-thu 23-01", false).unwrap();
+    assert!(schedule.is_open(at(0, 7, 0)));
+    assert!(!schedule.is_open(at(0, 21, 30))); // The end is exclusive
+    assert!(schedule.is_open(at(4, 12, 0))); // Friday
+    assert!(!schedule.is_open(at(5, 12, 0))); // Saturday
+    assert!(schedule.is_open(at(6, 23, 30))); // Sunday evening session...
+    assert!(schedule.is_open(at(7, 0, 30))); // ...runs into Monday
+    assert!(!schedule.is_open(at(5, 0, 30))); // Not after Friday, which has no evening session
+
+    let ctx = RiskContext { symbol: "BTCUSDT".to_string(), now_ms: at(5, 4, 0), ..Default::default() };
+    let err = check_all(&[Box::new(schedule) as Box<dyn RiskPolicy>], &ctx).unwrap_err();
+    assert!(err.starts_with("trading_schedule: Outside the trading sessions"));
+
+    assert!(TradingSchedule::parse("daily 00:00-24:00", false).unwrap().is_open(at(5, 4, 0)));
+    assert!(TradingSchedule::parse("weekdays 7-21", false).is_err());
+    assert!(TradingSchedule::parse("mon 07:00-25:00", false).is_err());
+    assert!(TradingSchedule::parse("mon 9-9", false).is_err());
+}
+
+#[test]
+fn test_funding_blackout_blocks_entries_around_settlements() {
+    let midnight_ms = 1_704_067_200_000; // 2024-01-01 00:00 UTC, a settlement
+    let at = |minutes: i64| RiskContext { symbol: "BTCUSDT".to_string(), now_ms: midnight_ms + minutes * 60_000, ..Default::default() };
+    assert_eq!(minutes_from_funding(midnight_ms - 5 * 60_000), -5);
+    assert_eq!(minutes_from_funding(midnight_ms + 8 * 60 * 60_000 + 3 * 60_000), 3);
+    let blackout = FundingBlackout { minutes: 10 };
+    assert_eq!(blackout.check(&at(-10)).unwrap_err(), "Funding settles in 10 minute(s)");
+    assert_eq!(blackout.check(&at(9)).unwrap_err(), "Funding settled 9 minute(s) ago");
+    assert!(blackout.check(&at(-11)).is_ok());
+    assert!(blackout.check(&at(10)).is_ok());
+    assert!(blackout.check(&at(4 * 60)).is_ok());
+}
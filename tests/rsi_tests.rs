@@ -0,0 +1,57 @@
+// tests/rsi_tests.rs
+
+//! This file contains tests for the RSI indicator and the RSI mean-reversion strategy.
+
+use trading_bot::indicators::Rsi;
+use trading_bot::market_event::{Candle, Symbol};
+use trading_bot::metrics::MetricsConfig;
+use trading_bot::order::OrderSide;
+use trading_bot::strategy::backtest;
+use trading_bot::strategy::rsi::*;
+
+fn candle(close: f64) -> Candle {
+    Candle {
+        symbol: Symbol::new("BTCUSDT").unwrap(),
+        event_time: 0,
+        interval_ms: 60_000,
+        open_time: 0,
+        close_time: 59_999,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 1.0,
+        quote_volume: close,
+        trades: 1,
+        is_closed: true,
+    }
+}
+
+#[test]
+fn test_rsi_values() {
+    let mut rsi = Rsi::new(3);
+    assert_eq!(rsi.update(10.0), None);
+    assert_eq!(rsi.update(11.0), None);
+    assert_eq!(rsi.update(12.0), None);
+    assert_eq!(rsi.update(13.0), Some(100.0)); // Only gains
+    // Average gain (1*2 + 0)/3, average loss (0*2 + 3)/3 -> RS = 2/3
+    let value = rsi.update(10.0).unwrap();
+    assert!((value - 40.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_rsi_strategy_round_trip() {
+    let config = RsiConfig { period: 3, entry_below: 30.0, exit_above: 70.0, quantity: 1.0, fee_rate: 0.0, ..RsiConfig::default() };
+    let mut strategy = RsiStrategy::new(config).unwrap();
+    let closes = [100.0, 98.0, 96.0, 94.0, 95.0, 99.0, 104.0];
+    let candles: Vec<Candle> = closes.iter().map(|c| candle(*c)).collect();
+
+    let result = backtest(&mut strategy, &candles, 1000.0, &MetricsConfig::default());
+    let sides: Vec<OrderSide> = result.fills.iter().map(|f| f.side).collect();
+    assert_eq!(sides, vec![OrderSide::Buy, OrderSide::Sell]);
+    assert_eq!(result.fills[0].price, 94.0);
+    assert_eq!(result.fills[1].realized_pnl, result.fills[1].price - 94.0);
+    assert!(result.final_equity > 1000.0);
+
+    assert!(RsiStrategy::new(RsiConfig { entry_below: 80.0, ..RsiConfig::default() }).is_err());
+}
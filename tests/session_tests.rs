@@ -0,0 +1,64 @@
+// tests/session_tests.rs
+
+//! This file contains tests for session tagging of trades and the per-tag performance breakdown.
+
+use trading_bot::session::*;
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+
+#[test]
+fn test_market_session_by_hour() {
+    assert_eq!(MarketSession::from_utc_hour(3), MarketSession::Asia);
+    assert_eq!(MarketSession::from_utc_hour(9), MarketSession::Europe);
+    assert_eq!(MarketSession::from_utc_hour(15), MarketSession::Us);
+    assert_eq!(MarketSession::from_utc_hour(22), MarketSession::Overnight);
+}
+
+#[test]
+fn test_funding_window_tags() {
+    let tagger = SessionTagger::default();
+    // 2024-01-01 07:30 UTC is 30 minutes before the 08:00 settlement
+    let base = 1_704_067_200_000; // 2024-01-01 00:00 UTC
+    let pre = tagger.tag(base + 7 * HOUR_MS + 30 * 60 * 1000, None).unwrap();
+    assert_eq!(pre.funding, FundingWindow::PreFunding);
+    // 23:30 the previous day is before the 00:00 settlement
+    let pre_midnight = tagger.tag(base - 30 * 60 * 1000, None).unwrap();
+    assert_eq!(pre_midnight.funding, FundingWindow::PreFunding);
+    let post = tagger.tag(base + 16 * HOUR_MS + 10 * 60 * 1000, None).unwrap();
+    assert_eq!(post.funding, FundingWindow::PostFunding);
+    let clear = tagger.tag(base + 12 * HOUR_MS, None).unwrap();
+    assert_eq!(clear.funding, FundingWindow::Clear);
+    assert_eq!(clear.volatility, VolatilityBucket::Unknown);
+}
+
+#[test]
+fn test_volatility_buckets() {
+    let tagger = SessionTagger::default();
+    let base = 1_704_067_200_000;
+    assert_eq!(tagger.tag(base, Some(0.001)).unwrap().volatility, VolatilityBucket::Low);
+    assert_eq!(tagger.tag(base, Some(0.01)).unwrap().volatility, VolatilityBucket::Normal);
+    assert_eq!(tagger.tag(base, Some(0.05)).unwrap().volatility, VolatilityBucket::High);
+
+    assert_eq!(realized_volatility(&[100.0]), None);
+    assert_eq!(realized_volatility(&[100.0, 100.0, 100.0]), Some(0.0));
+}
+
+#[test]
+fn test_performance_by_tag() {
+    let tagger = SessionTagger::default();
+    let base = 1_704_067_200_000;
+    let asia = tagger.tag(base + 3 * HOUR_MS, Some(0.01)).unwrap();
+    let us = tagger.tag(base + 15 * HOUR_MS, Some(0.01)).unwrap();
+    let trades = vec![(asia, 100.0), (asia, -50.0), (us, -20.0)];
+
+    let breakdown = performance_by_tag(&trades);
+    let asia_row = breakdown.iter().find(|r| r.tag == "session:asia").unwrap();
+    assert_eq!(asia_row.trades, 2);
+    assert_eq!(asia_row.wins, 1);
+    assert!((asia_row.net_pnl - 50.0).abs() < 1e-9);
+    assert!((asia_row.profit_factor - 2.0).abs() < 1e-9);
+
+    let normal_row = breakdown.iter().find(|r| r.tag == "volatility:normal").unwrap();
+    assert_eq!(normal_row.trades, 3);
+    assert!((normal_row.net_pnl - 30.0).abs() < 1e-9);
+}
@@ -0,0 +1,162 @@
+// tests/telemetry_tests.rs
+
+//! This file contains tests for the log filter, the span context of log lines, the JSON log files
+//! and the export of spans.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use tracing::{info, info_span, Instrument};
+use tracing_subscriber::layer::SubscriberExt;
+use trading_bot::telemetry::*;
+
+/// Keeps the bytes written.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Buffer {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap().lines().map(str::to_string).collect()
+    }
+}
+
+#[test]
+fn test_log_filter_directives() {
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::registry()
+        .with(parse_filter("warn,trading_bot::webhook=debug,hyper=off").unwrap())
+        .with(text_layer(move || writer.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::debug!(target: "trading_bot::webhook::dedup", "webhook debug");
+        tracing::debug!(target: "trading_bot::rest_api", "rest debug");
+        tracing::warn!(target: "trading_bot::rest_api", "rest warning");
+        tracing::info!(target: "trading_bot::rest_api", "rest info");
+        tracing::error!(target: "hyper::proto", "hyper error");
+    });
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 2, "{:?}", lines);
+    assert!(lines[0].ends_with("webhook debug") && lines[1].ends_with("rest warning"));
+    assert!(parse_filter("loud=verbose").is_err());
+}
+
+#[tokio::test]
+async fn test_lines_carry_their_spans_across_tasks() {
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::registry()
+        .with(parse_filter("info").unwrap())
+        .with(text_layer(move || writer.clone()));
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let signal = info_span!("signal", id = "sig-1");
+    async {
+        let order = info_span!("order", client_order_id = "whb1");
+        async { info!(quantity = 0.5, "Order placed") }.instrument(order).await;
+        tracing::debug!("Not logged");
+    }.instrument(signal).await;
+    info!("Outside");
+
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains(" INFO signal{id=\"sig-1\"}:order{client_order_id=\"whb1\"}: telemetry_tests: Order placed quantity=0.5"), "{}", lines[0]);
+    assert!(lines[1].contains(" INFO telemetry_tests: Outside"), "{}", lines[1]);
+}
+
+#[test]
+fn test_json_log_files_rotate_daily_and_keep_the_retention() {
+    use chrono::{NaiveDate, Utc};
+    use trading_bot::telemetry::file::{daily_appender, log_file_path};
+
+    let dir = std::env::temp_dir().join(format!("telemetry_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for day in 25..=28 {
+        std::fs::write(log_file_path(&dir, NaiveDate::from_ymd_opt(2024, 4, day).unwrap()), "").unwrap();
+    }
+    std::fs::write(dir.join("notes.txt"), "").unwrap(); // Not a log file
+
+    let subscriber = tracing_subscriber::registry()
+        .with(parse_filter("info").unwrap())
+        .with(json_layer(daily_appender(&dir, 3).unwrap()));
+    tracing::subscriber::with_default(subscriber, || {
+        info_span!("signal", id = "sig-1").in_scope(|| info!(quantity = 0.5, "Order placed"));
+    });
+
+    let today = std::fs::read_to_string(log_file_path(&dir, Utc::now().date_naive())).unwrap();
+    let lines: Vec<serde_json::Value> = today.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["level"], "INFO");
+    assert_eq!(lines[0]["spans"][0]["name"], "signal");
+    assert_eq!(lines[0]["spans"][0]["id"], "sig-1");
+    assert_eq!(lines[0]["fields"]["message"], "Order placed");
+    assert_eq!(lines[0]["fields"]["quantity"], 0.5);
+    let log_files = std::fs::read_dir(&dir).unwrap()
+        .filter(|entry| entry.as_ref().unwrap().file_name().to_str().unwrap().ends_with(".jsonl"))
+        .count();
+    assert_eq!(log_files, 3); // Today's and the two most recent older files
+    assert!(dir.join("notes.txt").exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(feature = "otlp")]
+#[tokio::test]
+async fn test_otlp_spans_form_a_trace() {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use trading_bot::telemetry::otlp::{layer, OtlpConfig};
+
+    let config = OtlpConfig {
+        endpoint: "http://127.0.0.1:9".to_string(),
+        service_name: "bot".to_string(),
+        span_export_delay: std::time::Duration::from_secs(60),
+        metric_export_interval: std::time::Duration::from_secs(60),
+    };
+    let subscriber = tracing_subscriber::registry()
+        .with(parse_filter("info").unwrap())
+        .with(layer(&config).unwrap());
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let signal = info_span!(target: "trading_bot::webhook", "signal", id = "sig-1");
+    let order = signal.in_scope(|| info_span!(target: "trading_bot::webhook", "order", client_order_id = "whb1"));
+    let other = info_span!(target: "trading_bot::webhook", "signal", id = "sig-2");
+    let context = |span: &tracing::Span| span.context().span().span_context().clone();
+    assert!(context(&signal).is_valid());
+    assert_eq!(context(&order).trace_id(), context(&signal).trace_id());
+    assert_ne!(context(&order).span_id(), context(&signal).span_id());
+    assert_ne!(context(&other).trace_id(), context(&signal).trace_id());
+    assert!(!context(&info_span!("dependency")).is_valid()); // Only the bot's spans are exported
+}
+
+#[cfg(feature = "otlp")]
+#[test]
+fn test_metric_expositions_are_parsed_for_export() {
+    use trading_bot::telemetry::otlp::parse_exposition;
+
+    let exposition = "# HELP trading_bot_uptime_seconds Seconds since the trading bot started.\n\
+                      # TYPE trading_bot_uptime_seconds counter\n\
+                      trading_bot_uptime_seconds 42\n\
+                      # TYPE trading_bot_position gauge\n\
+                      trading_bot_position{symbol=\"BTCUSDT\",side=\"LONG\"} 0.5\n\
+                      trading_bot_position{symbol=\"ETHUSDT\",side=\"SHORT\"} -2\n";
+    let samples = parse_exposition(exposition);
+    assert_eq!(samples.len(), 3);
+    assert_eq!(samples[0].name, "trading_bot_uptime_seconds");
+    assert_eq!(samples[0].description, "Seconds since the trading bot started.");
+    assert!(samples[0].counter);
+    assert_eq!(samples[0].value, 42.0);
+    assert!(!samples[2].counter);
+    assert_eq!(samples[2].value, -2.0);
+    assert_eq!(samples[2].labels, [("symbol".to_string(), "ETHUSDT".to_string()), ("side".to_string(), "SHORT".to_string())]);
+}
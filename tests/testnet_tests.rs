@@ -0,0 +1,48 @@
+// tests/testnet_tests.rs
+
+//! This file contains offline tests for the testnet reset helper (`testnet-tools` feature).
+
+#![cfg(feature = "testnet-tools")]
+
+use serde_json::json;
+use trading_bot::account_info::PositionInfo;
+use trading_bot::order::{OrderSide, PositionSide};
+use trading_bot::testnet::*;
+
+fn position(position_side: &str, amount: &str) -> PositionInfo {
+    serde_json::from_value(json!({
+        "symbol": "BTCUSDT", "positionSide": position_side, "positionAmt": amount, "unrealizedProfit": "0",
+        "isolatedMargin": "0", "notional": "0", "isolatedWallet": "0", "initialMargin": "0", "maintMargin": "0",
+        "updateTime": 0
+    })).unwrap()
+}
+
+#[test]
+fn test_refuses_production_urls() {
+    assert!(ensure_testnet("https://testnet.binancefuture.com", "wss://testnet.binancefuture.com/ws-fapi/v1").is_ok());
+    assert!(ensure_testnet("https://fapi.binance.com", "wss://testnet.binancefuture.com/ws-fapi/v1").is_err());
+}
+
+#[test]
+fn test_close_position_requests() {
+    let long = close_position_request(&position("BOTH", "0.5"), "reset0").unwrap();
+    assert_eq!((long.side, long.quantity, long.reduce_only), (OrderSide::Sell, Some(0.5), true));
+
+    let short = close_position_request(&position("SHORT", "-2"), "reset1").unwrap();
+    assert_eq!((short.side, short.quantity, short.position_side), (OrderSide::Buy, Some(2.0), Some(PositionSide::Short)));
+    assert!(!short.reduce_only);
+
+    assert!(close_position_request(&position("BOTH", "0.000"), "reset2").is_none());
+}
+
+#[test]
+fn test_clear_state_dir() {
+    let dir = std::env::temp_dir().join(format!("testnet_reset_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("events.jsonl"), "").unwrap();
+    std::fs::write(dir.join("runtime.json"), "{}").unwrap();
+    assert_eq!(clear_state_dir(&dir).unwrap().len(), 2);
+    assert!(dir.exists());
+    assert!(clear_state_dir(&dir).unwrap().is_empty());
+    std::fs::remove_dir(&dir).unwrap();
+}
@@ -0,0 +1,121 @@
+// tests/ticks_tests.rs
+
+//! This file contains tests for resolving backtest exits from aggregated trades.
+
+use trading_bot::engine::{ExecutionHandler, OrderKind, OrderRequest, SimulatedExecution};
+use trading_bot::market_event::{MarketEvent, Symbol, Trade};
+use trading_bot::order::OrderSide;
+use trading_bot::strategy::ticks::*;
+use trading_bot::strategy::{self, BacktestConfig};
+
+fn tick(time_ms: u64, price: f64) -> Trade {
+    Trade { symbol: Symbol::new("BTCUSDT").unwrap(), event_time: time_ms, trade_time: time_ms, trade_id: time_ms, price, quantity: 1.0, is_buyer_maker: false }
+}
+
+#[test]
+fn test_load_agg_trades_with_and_without_headers() {
+    let dir = std::env::temp_dir().join(format!("ticks_load_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let with_headers = dir.join("with.csv");
+    let without_headers = dir.join("without.csv");
+    let rows = "2,101.5,0.2,11,12,2000,false\n1,100.0,0.1,10,10,1000,True\n";
+    std::fs::write(&with_headers, format!("agg_trade_id,price,quantity,first_trade_id,last_trade_id,transact_time,is_buyer_maker\n{}", rows)).unwrap();
+    std::fs::write(&without_headers, rows).unwrap();
+
+    for path in [&with_headers, &without_headers] {
+        let trades = load_agg_trades(path.to_str().unwrap(), "BTCUSDT").unwrap();
+        assert_eq!(trades.len(), 2);
+        // Sorted by time
+        assert_eq!((trades[0].trade_id, trades[0].price, trades[0].is_buyer_maker), (1, 100.0, true));
+        assert_eq!((trades[1].trade_time, trades[1].quantity), (2000, 0.2));
+    }
+    std::fs::write(&without_headers, "1,abc,0.1,10,10,1000,true\n").unwrap();
+    assert!(load_agg_trades(without_headers.to_str().unwrap(), "BTCUSDT").is_err());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_replayed_trades_fill_the_first_exit_reached() {
+    let trades: Vec<Trade> = [(1_000, 100.0), (2_000, 104.0), (3_000, 111.0), (4_000, 94.0)].iter().map(|&(t, p)| tick(t, p)).collect();
+    assert_eq!(trades_between(&trades, 2_000, 3_000).len(), 2);
+    assert!(trades_between(&trades, 5_000, 6_000).is_empty());
+
+    // A long opened at 100 with its exits working, then the trades replayed
+    let replay = |exits: &[(&str, OrderKind)]| {
+        let order = |client_id: &str, side: OrderSide, kind: OrderKind, reduce_only: bool| OrderRequest {
+            client_id: client_id.to_string(), symbol: Symbol::new("BTCUSDT").unwrap(), side, kind, quantity: 1.0, reduce_only,
+        };
+        let mut execution = SimulatedExecution::new(0.0);
+        execution.on_market_event(&MarketEvent::AggTrade(tick(500, 100.0)));
+        execution.submit(order("en", OrderSide::Buy, OrderKind::Market, false), 500).unwrap();
+        for &(client_id, kind) in exits {
+            execution.submit(order(client_id, OrderSide::Sell, kind, true), 500).unwrap();
+        }
+        execution.drain_fills();
+        for trade in &trades {
+            execution.on_market_event(&MarketEvent::AggTrade(*trade));
+        }
+        execution.drain_fills().iter().map(|f| (f.client_id.clone(), f.time_ms, f.price)).collect::<Vec<_>>()
+    };
+
+    // The bar reaches both exits, but the target trades first
+    let fills = replay(&[("sl", OrderKind::StopMarket(95.0)), ("tp", OrderKind::Limit(110.0))]);
+    assert_eq!(fills, [("tp".to_string(), 3_000, 110.0)]);
+
+    // A stop crossed by a gap fills at the trade's price
+    let fills = replay(&[("sl", OrderKind::StopMarket(96.0)), ("tp", OrderKind::Limit(120.0))]);
+    assert_eq!(fills, [("sl".to_string(), 4_000, 94.0)]);
+
+    // The liquidation stop sits above the stop loss and closes the position first
+    let fills = replay(&[("lq", OrderKind::StopMarket(95.0)), ("sl", OrderKind::StopMarket(90.0)), ("tp", OrderKind::Limit(120.0))]);
+    assert_eq!(fills, [("lq".to_string(), 4_000, 94.0)]);
+    assert!(replay(&[("sl", OrderKind::StopMarket(90.0)), ("tp", OrderKind::Limit(120.0))]).is_empty());
+}
+
+#[tokio::test]
+async fn test_backtest_resolves_exits_from_agg_trades() {
+    let dir = std::env::temp_dir().join(format!("ticks_backtest_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("candles.csv");
+    let agg_trades = dir.join("aggTrades.csv");
+    let output = dir.join("report.json");
+
+    // A rising market with regular pullbacks below the fast EMA
+    let mut csv = String::from("Open time,Open,High,Low,Close,Volume,Close time,Quote asset volume,Number of trades,Taker buy base asset volume,Taker buy quote asset volume,Ignore\n");
+    for i in 0..200u64 {
+        let close = 100.0 + i as f64 * 0.5 + 4.0 * (i as f64 * 0.7).sin();
+        let open_time = 1_609_459_200_000 + i * 14_400_000;
+        csv.push_str(&format!("{},{},{},{},{},1,{},1,1,0,0,0\n", open_time, close, close + 1.5, close - 1.5, close, open_time + 14_399_999));
+    }
+    std::fs::write(&data, csv).unwrap();
+
+    let run = |agg_trades: Option<&str>| {
+        let mut args = vec!["--data", data.to_str().unwrap(), "--fast-ema", "5", "--slow-ema", "12", "--rr", "1", "--output", output.to_str().unwrap()];
+        if let Some(path) = agg_trades {
+            args.extend(["--agg-trades", path]);
+        }
+        BacktestConfig::from_args(&args.iter().map(|s| s.to_string()).collect::<Vec<_>>()).unwrap()
+    };
+    let read_trades = || {
+        let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+        report["trades"].as_array().unwrap().clone()
+    };
+
+    strategy::run(&run(None)).await.unwrap();
+    let bar_trades = read_trades();
+    let first = &bar_trades[0];
+    let exit_bar_ms = first["exit_time_ms"].as_u64().unwrap();
+    let entry_price = first["entry_price"].as_f64().unwrap();
+
+    // Within the first trade's exit bar the price trades through the target before anything else
+    let target = entry_price + 1.5;
+    std::fs::write(&agg_trades, format!("1,{},1,1,1,{},false\n2,{},1,2,2,{},true\n", entry_price, exit_bar_ms + 1_000, target + 0.1, exit_bar_ms + 2_000)).unwrap();
+    strategy::run(&run(agg_trades.to_str())).await.unwrap();
+    let tick_trades = read_trades();
+    assert_eq!(tick_trades[0]["exit_reason"], "take_profit");
+    assert_eq!(tick_trades[0]["exit_time_ms"].as_u64(), Some(exit_bar_ms + 2_000));
+    assert!((tick_trades[0]["exit_price"].as_f64().unwrap() - target).abs() < 1e-9);
+    // Later bars have no trades in the file and keep the bar-based exits
+    assert_eq!(tick_trades.last().unwrap()["exit_time_ms"], bar_trades.last().unwrap()["exit_time_ms"]);
+    std::fs::remove_dir_all(&dir).ok();
+}
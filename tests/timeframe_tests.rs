@@ -0,0 +1,85 @@
+// tests/timeframe_tests.rs
+
+//! This file contains tests for multi-timeframe backtesting (resampling and alignment without lookahead).
+
+use trading_bot::market_event::{Candle, Symbol};
+use trading_bot::metrics::MetricsConfig;
+use trading_bot::strategy::timeframe::*;
+use trading_bot::strategy::{backtest, SimulatedFill};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+
+fn hourly(index: u64, close: f64) -> Candle {
+    Candle {
+        symbol: Symbol::new("BTCUSDT").unwrap(),
+        event_time: (index + 1) * HOUR_MS - 1,
+        interval_ms: HOUR_MS,
+        open_time: index * HOUR_MS,
+        close_time: (index + 1) * HOUR_MS - 1,
+        open: close - 1.0,
+        high: close + 1.0,
+        low: close - 2.0,
+        close,
+        volume: 1.0,
+        quote_volume: close,
+        trades: 1,
+        is_closed: true,
+    }
+}
+
+/// Records the higher-timeframe close seen at every lower-timeframe bar.
+#[derive(Default)]
+struct Recorder {
+    seen: Vec<Option<f64>>,
+}
+
+impl MultiTimeframeStrategy for Recorder {
+    fn name(&self) -> &str {
+        "recorder"
+    }
+
+    fn on_candle(&mut self, _candle: &Candle, higher: Option<&Candle>) -> Vec<SimulatedFill> {
+        self.seen.push(higher.map(|h| h.close));
+        vec![]
+    }
+
+    fn pnl(&self, _mark_price: f64) -> f64 {
+        0.0
+    }
+}
+
+fn hourly_series() -> Vec<Candle> {
+    (0..10).map(|i| hourly(i, 100.0 + i as f64)).collect()
+}
+
+#[test]
+fn test_resample_to_four_hours() {
+    let four_hour = resample(&hourly_series(), 4 * HOUR_MS).unwrap();
+    assert_eq!(four_hour.len(), 2); // The last two hours do not complete a bucket
+    assert_eq!(four_hour[0].open_time, 0);
+    assert_eq!(four_hour[0].close_time, 4 * HOUR_MS - 1);
+    assert_eq!((four_hour[0].open, four_hour[0].high, four_hour[0].low, four_hour[0].close), (99.0, 104.0, 98.0, 103.0));
+    assert_eq!(four_hour[1].volume, 4.0);
+    assert!(four_hour.iter().all(|c| c.is_closed));
+}
+
+#[test]
+fn test_higher_timeframe_has_no_lookahead() {
+    let lower = hourly_series();
+    let expected = vec![None, None, None, Some(103.0), Some(103.0), Some(103.0), Some(103.0), Some(107.0), Some(107.0), Some(107.0)];
+
+    let mut resampled = Resampled::new(Recorder::default(), 4 * HOUR_MS).unwrap();
+    backtest(&mut resampled, &lower, 1000.0, &MetricsConfig::default());
+    assert_eq!(resampled.inner().seen, expected);
+
+    // A separately loaded higher timeframe gives the same view
+    let higher = resample(&lower, 4 * HOUR_MS).unwrap();
+    let mut aligned = Aligned::new(Recorder::default(), &higher);
+    backtest(&mut aligned, &lower, 1000.0, &MetricsConfig::default());
+    assert_eq!(aligned.inner().seen, expected);
+
+    let indices = align_higher_timeframe(&lower, &higher);
+    assert_eq!(indices[2], None);
+    assert_eq!(indices[3], Some(0));
+    assert_eq!(indices[9], Some(1));
+}
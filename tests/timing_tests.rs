@@ -0,0 +1,89 @@
+// tests/timing_tests.rs
+
+//! This file contains tests for the candle store and the per-strategy execution timing.
+
+use trading_bot::market_event::{Candle, Symbol};
+use trading_bot::order::OrderSide;
+use trading_bot::strategy::rsi::{RsiConfig, RsiStrategy};
+use trading_bot::strategy::timing::*;
+
+fn kline(bar: u64, event_time: u64, close: f64, is_closed: bool) -> Candle {
+    Candle {
+        symbol: Symbol::new("BTCUSDT").unwrap(),
+        event_time,
+        interval_ms: 60_000,
+        open_time: bar * 60_000,
+        close_time: bar * 60_000 + 59_999,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 1.0,
+        quote_volume: close,
+        trades: 1,
+        is_closed,
+    }
+}
+
+fn rsi_strategy() -> RsiStrategy {
+    RsiStrategy::new(RsiConfig { period: 3, quantity: 1.0, fee_rate: 0.0, ..RsiConfig::default() }).unwrap()
+}
+
+#[test]
+fn test_parse_execution_timing() {
+    assert_eq!(ExecutionTiming::parse("close").unwrap(), ExecutionTiming::OnClose);
+    assert_eq!(ExecutionTiming::parse("Update").unwrap(), ExecutionTiming::EveryUpdate);
+    assert_eq!(ExecutionTiming::parse("intrabar:5000").unwrap(), ExecutionTiming::Intrabar { throttle_ms: 5000 });
+    assert!(ExecutionTiming::parse("intrabar:soon").is_err());
+    assert!(ExecutionTiming::parse("tick").is_err());
+}
+
+#[test]
+fn test_candle_store_drops_stale_updates() {
+    let mut store = CandleStore::new(2);
+    assert_eq!(store.update(&kline(0, 10, 100.0, false)), CandleUpdate::Forming);
+    assert_eq!(store.update(&kline(0, 59_999, 101.0, true)), CandleUpdate::Closed);
+    assert_eq!(store.update(&kline(0, 60_010, 101.0, true)), CandleUpdate::Stale); // Repeated close
+    assert_eq!(store.update(&kline(0, 60_020, 102.0, false)), CandleUpdate::Stale); // Late update
+    assert!(store.forming().is_none());
+
+    assert_eq!(store.update(&kline(1, 60_030, 99.0, false)), CandleUpdate::Forming);
+    assert_eq!(store.update(&kline(1, 119_999, 98.0, true)), CandleUpdate::Closed);
+    assert_eq!(store.update(&kline(2, 120_999, 97.0, true)), CandleUpdate::Closed);
+    let opens: Vec<u64> = store.closed().iter().map(|c| c.open_time).collect();
+    assert_eq!(opens, vec![60_000, 120_000]); // Capacity of two
+}
+
+#[test]
+fn test_on_close_ignores_forming_candles() {
+    let mut timed = TimedStrategy::new(rsi_strategy(), ExecutionTiming::OnClose, 10);
+    let mut fills = Vec::new();
+    for (bar, close) in [100.0, 98.0, 96.0, 94.0].into_iter().enumerate() {
+        fills.extend(timed.on_kline(&kline(bar as u64, 0, 90.0, false))); // Dips intrabar, never acted on
+        fills.extend(timed.on_kline(&kline(bar as u64, 0, close, true)));
+        fills.extend(timed.on_kline(&kline(bar as u64, 0, close, true))); // Duplicate close
+    }
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].price, 94.0); // Entered on the close of the fourth bar only
+}
+
+#[test]
+fn test_intrabar_entry_acts_once_per_bar() {
+    let timing = ExecutionTiming::Intrabar { throttle_ms: 1_000 };
+    let mut timed = TimedStrategy::new(rsi_strategy(), timing, 10);
+    for (bar, close) in [100.0, 98.0, 96.0].into_iter().enumerate() {
+        assert!(timed.on_kline(&kline(bar as u64, 0, close, true)).is_empty());
+    }
+
+    // At 99 the bar would close with an RSI above the entry threshold, at 94 below it
+    let start = 180_000;
+    assert!(timed.on_kline(&kline(3, start, 99.0, false)).is_empty());
+    assert!(timed.on_kline(&kline(3, start + 500, 94.0, false)).is_empty()); // Throttled
+    let fills = timed.on_kline(&kline(3, start + 1_000, 94.0, false));
+    assert_eq!(fills.len(), 1);
+    assert_eq!((fills[0].side, fills[0].price, fills[0].time_ms), (OrderSide::Buy, 94.0, start + 1_000));
+
+    assert!(timed.on_kline(&kline(3, start + 5_000, 93.0, false)).is_empty()); // Already acted on this bar
+    assert!(timed.on_kline(&kline(3, 239_999, 93.0, true)).is_empty()); // Close does not enter again
+    assert_eq!(timed.store().closed().len(), 4);
+}
@@ -0,0 +1,99 @@
+// tests/trailing_tests.rs
+
+//! This file contains offline tests for the client-side trailing stop engine (no network access needed).
+
+use trading_bot::order::trailing::*;
+use trading_bot::order::{OrderSide, OrderType};
+
+fn config(position: OrderSide, distance: TrailDistance) -> TrailingStopConfig {
+    TrailingStopConfig {
+        id: "ts1".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        position,
+        quantity: 0.01,
+        distance,
+        min_step: 1.0,
+        initial_stop: None,
+        position_side: None,
+    }
+}
+
+fn placed_stop(actions: &[TrailingAction]) -> f64 {
+    match actions.last() {
+        Some(TrailingAction::PlaceOrder(request)) => {
+            assert_eq!(request.order_type, OrderType::StopMarket);
+            assert!(request.reduce_only);
+            request.stop_price.unwrap()
+        },
+        other => panic!("expected a placed stop, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_long_stop_trails_high_water_mark() {
+    let mut manager = TrailingStopManager::default();
+    assert!(manager.start(config(OrderSide::Buy, TrailDistance::Percent(1.0))).unwrap().is_empty());
+
+    let actions = manager.on_price("BTCUSDT", 30000.0);
+    assert_eq!(actions.len(), 1);
+    assert!((placed_stop(&actions) - 29700.0).abs() < 1e-9);
+
+    // Price falls: the stop never moves down
+    assert!(manager.on_price("BTCUSDT", 29800.0).is_empty());
+    // Improvement below `min_step` is ignored
+    assert!(manager.on_price("BTCUSDT", 30000.5).is_empty());
+
+    let actions = manager.on_price("BTCUSDT", 31000.0);
+    assert_eq!(actions.len(), 2);
+    assert_eq!(actions[0], TrailingAction::CancelOrder {
+        symbol: "BTCUSDT".to_string(),
+        client_order_id: "ts1-ts1".to_string(),
+    });
+    assert!((placed_stop(&actions) - 30690.0).abs() < 1e-9);
+    assert_eq!(manager.get("ts1").unwrap().stop_client_order_id(), Some("ts1-ts2"));
+    assert!(manager.on_price("ETHUSDT", 99999.0).is_empty());
+}
+
+#[test]
+fn test_short_stop_trails_low_water_mark() {
+    let mut manager = TrailingStopManager::default();
+    manager.start(config(OrderSide::Sell, TrailDistance::Percent(2.0))).unwrap();
+    let first = placed_stop(&manager.on_price("BTCUSDT", 100.0));
+    assert!((first - 102.0).abs() < 1e-9);
+    assert!(manager.on_price("BTCUSDT", 101.0).is_empty());
+    let moved = placed_stop(&manager.on_price("BTCUSDT", 50.0));
+    assert!((moved - 51.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_atr_distance_waits_for_atr() {
+    let mut manager = TrailingStopManager::new(3);
+    manager.start(config(OrderSide::Buy, TrailDistance::AtrMultiple(2.0))).unwrap();
+    assert!(manager.on_price("BTCUSDT", 100.0).is_empty());
+
+    for _ in 0..3 {
+        manager.on_closed_bar("BTCUSDT", 105.0, 95.0, 100.0);
+    }
+    assert!((manager.atr("btcusdt").unwrap() - 10.0).abs() < 1e-9);
+    let stop = placed_stop(&manager.on_price("BTCUSDT", 100.0));
+    assert!((stop - 80.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_atr_wilder_smoothing() {
+    let mut atr = Atr::new(2);
+    assert_eq!(atr.update(10.0, 8.0, 9.0), None);
+    assert_eq!(atr.update(11.0, 9.0, 10.0), Some(2.0));
+    // True range uses the gap from the previous close: max(1, |14-10|, |13-10|) = 4
+    assert_eq!(atr.update(14.0, 13.0, 13.5), Some(3.0));
+}
+
+#[test]
+fn test_stop_cancels_resting_order() {
+    let mut manager = TrailingStopManager::default();
+    manager.start(TrailingStopConfig { initial_stop: Some(29000.0), ..config(OrderSide::Buy, TrailDistance::Percent(1.0)) }).unwrap();
+    let actions = manager.stop("ts1").unwrap();
+    assert_eq!(actions.len(), 1);
+    assert_eq!(manager.get("ts1").unwrap().state, TrailingState::Stopped);
+    assert!(manager.on_price("BTCUSDT", 40000.0).is_empty());
+}
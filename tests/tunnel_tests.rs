@@ -0,0 +1,41 @@
+// tests/tunnel_tests.rs
+
+//! This file contains tests for the tunnels exposing the webhook.
+
+use trading_bot::tunnel::*;
+
+#[test]
+fn test_quick_tunnel_url_is_read_from_cloudflared_output() {
+    let banner = "2024-06-10T12:00:00Z INF |  https://plain-words-sample.trycloudflare.com                                        |";
+    assert_eq!(quick_tunnel_url(banner).as_deref(), Some("https://plain-words-sample.trycloudflare.com"));
+    assert_eq!(quick_tunnel_url("INF Requesting new quick Tunnel on trycloudflare.com..."), None);
+    assert_eq!(quick_tunnel_url("INF Visit https://developers.cloudflare.com/ for docs"), None);
+    assert!(is_connected_line("INF Registered tunnel connection connIndex=0 location=fra08 protocol=quic"));
+    assert_eq!(forward_addr("0.0.0.0:8080"), "127.0.0.1:8080");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_cloudflare_tunnel_waits_for_the_connection() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Stands in for cloudflared: prints a quick tunnel banner and a connection, then keeps running
+    let dir = std::env::temp_dir().join(format!("tunnel_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let binary = dir.join("cloudflared");
+    std::fs::write(&binary, "#!/bin/sh\n\
+        echo 'INF |  https://fake-tunnel.trycloudflare.com  |' >&2\n\
+        echo 'INF Registered tunnel connection connIndex=0' >&2\n\
+        sleep 30\n").unwrap();
+    std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let config = TunnelConfig::Cloudflare { token: None, hostname: None, binary: binary.display().to_string() };
+    let mut tunnel = config.build().unwrap();
+    assert_eq!(tunnel.name(), "cloudflare");
+    assert_eq!(tunnel.open("0.0.0.0:8080").await.unwrap(), "https://fake-tunnel.trycloudflare.com");
+    drop(tunnel); // Stops the process
+
+    let missing = TunnelConfig::Cloudflare { token: None, hostname: None, binary: dir.join("missing").display().to_string() };
+    assert!(missing.build().unwrap().open("127.0.0.1:8080").await.unwrap_err().contains("Failed to start"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
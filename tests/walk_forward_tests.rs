@@ -0,0 +1,87 @@
+// tests/walk_forward_tests.rs
+
+//! This file contains tests for the walk-forward analysis of the EMA crossover backtest.
+
+use trading_bot::strategy::walk_forward::*;
+use trading_bot::strategy::BacktestConfig;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_param_range() {
+    assert_eq!(ParamRange::parse("8:14:3").unwrap().values(), vec![8, 11, 14]);
+    assert_eq!(ParamRange::parse("5:7").unwrap().values(), vec![5, 6, 7]);
+    assert!(ParamRange::parse("10:5:1").is_err());
+    assert!(ParamRange::parse("5:10:0").is_err());
+    assert!(ParamRange::parse("5").is_err());
+}
+
+#[test]
+fn test_windows_roll_by_the_out_of_sample_length() {
+    let config = WalkForwardConfig { in_sample_bars: 100, out_of_sample_bars: 30, ..WalkForwardConfig::default() };
+    let windows = config.windows(200);
+    assert_eq!(windows, vec![(0..100, 100..130), (30..130, 130..160), (60..160, 160..190), (90..190, 190..200)]);
+    assert!(config.windows(100).is_empty());
+}
+
+#[test]
+fn test_flags_are_split_from_the_backtest_settings() {
+    let (config, rest) = WalkForwardConfig::from_args(&args(&[
+        "--balance", "1000", "--in-sample", "200", "--fast-range", "3:9:3", "--slow-range", "8:20:4", "--objective", "net-pnl", "--rr", "2",
+    ])).unwrap();
+    assert_eq!(rest, args(&["--balance", "1000", "--rr", "2"]));
+    assert_eq!((config.in_sample_bars, config.out_of_sample_bars, config.objective), (200, 250, Objective::NetPnl));
+    // Pairs with the fast period at or above the slow one are skipped
+    assert_eq!(config.parameter_pairs(), vec![(3, 8), (3, 12), (3, 16), (3, 20), (6, 8), (6, 12), (6, 16), (6, 20), (9, 12), (9, 16), (9, 20)]);
+
+    // The in-sample window must warm up the slowest EMA
+    assert!(WalkForwardConfig::from_args(&args(&["--in-sample", "50", "--slow-range", "20:60:10"])).is_err());
+    assert!(WalkForwardConfig::from_args(&args(&["--objective", "sortino"])).is_err());
+}
+
+#[tokio::test]
+async fn test_walk_forward_aggregates_out_of_sample_trades() {
+    let dir = std::env::temp_dir().join(format!("walk_forward_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("candles.csv");
+    let output = dir.join("walk_forward.json");
+
+    // A rising market with regular pullbacks below the fast EMA
+    let mut csv = String::from("Open time,Open,High,Low,Close,Volume,Close time,Quote asset volume,Number of trades,Taker buy base asset volume,Taker buy quote asset volume,Ignore\n");
+    for i in 0..600u64 {
+        let close = 100.0 + i as f64 * 0.5 + 4.0 * (i as f64 * 0.7).sin();
+        let open_time = 1_609_459_200_000 + i * 14_400_000;
+        csv.push_str(&format!("{},{},{},{},{},1,{},1,1,0,0,0\n", open_time, close, close + 1.5, close - 1.5, close, open_time + 14_399_999));
+    }
+    std::fs::write(&data, csv).unwrap();
+
+    let (walk_forward, rest) = WalkForwardConfig::from_args(&args(&[
+        "--data", data.to_str().unwrap(), "--in-sample", "200", "--out-of-sample", "100", "--fast-range", "3:6:1", "--slow-range", "8:16:4",
+        "--objective", "net_pnl", "--rr", "1", "--output", output.to_str().unwrap(),
+    ])).unwrap();
+    let config = BacktestConfig::from_args(&rest).unwrap();
+    let report = run(&config, &walk_forward).await.unwrap();
+
+    assert_eq!(report.windows.len(), 4);
+    for window in &report.windows {
+        assert!(window.fast_ema_period < window.slow_ema_period);
+        assert!(window.out_of_sample_start_ms > window.in_sample_start_ms);
+        assert!(window.out_of_sample.total_trades > 0);
+        for trade in report.trades.iter().filter(|t| t.entry_time_ms >= window.out_of_sample_start_ms && t.entry_time_ms <= window.out_of_sample_end_ms) {
+            assert!(trade.exit_time_ms <= window.out_of_sample_end_ms);
+        }
+    }
+    // Only out-of-sample trades count: none is entered before the first out-of-sample bar
+    assert!(report.trades.iter().all(|t| t.entry_time_ms >= report.windows[0].out_of_sample_start_ms));
+    let window_trades: usize = report.windows.iter().map(|w| w.out_of_sample.total_trades).sum();
+    assert_eq!(report.summary.total_trades, window_trades);
+    let net: f64 = report.trades.iter().map(|t| t.pnl).sum();
+    assert!((report.summary.final_balance - (config.account_balance + net)).abs() < 1e-6);
+    assert_eq!(report.equity_curve.len(), 1 + 400);
+
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(written["windows"].as_array().map(Vec::len), Some(4));
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,670 @@
+// tests/webhook_tests.rs
+
+//! This file contains tests for authenticating incoming webhook requests, filtering their
+//! source addresses and interpreting the optional order fields of the payload.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::{middleware, routing::{get, post}, Json, Router};
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use trading_bot::account_info::PositionRisk;
+use trading_bot::arming::Interlock;
+use trading_bot::market_data::SymbolFilters;
+use trading_bot::order::{OrderSide, OrderType, PositionSide};
+use trading_bot::rest_api::RestClient;
+use trading_bot::risk::ExecutionPolicies;
+use trading_bot::websocket::WebSocketClient;
+use trading_bot::webhook::allowlist::*;
+use trading_bot::webhook::control::*;
+use trading_bot::webhook::dedup::*;
+use trading_bot::webhook::queue::*;
+use trading_bot::webhook::ratelimit::*;
+use trading_bot::webhook::response::*;
+use trading_bot::webhook::status::*;
+use trading_bot::webhook::strategies::*;
+use trading_bot::webhook::symbols::*;
+use trading_bot::webhook::tls::*;
+use trading_bot::webhook::*;
+
+const SECRET: &str = "tv-shared-secret";
+
+/// State of an armed bot whose REST API is `rest_base_url`; the WebSocket API is unreachable.
+async fn offline_state(rest_base_url: &str) -> AppState {
+    AppState {
+        ws_client: Arc::new(WebSocketClient::new("key".to_string(), "secret".to_string(), "ws://127.0.0.1:9".to_string()).await),
+        rest_client: Arc::new(RestClient::new("key".to_string(), "secret".to_string(), rest_base_url.to_string())),
+        experiment: None,
+        policies: Arc::new(ExecutionPolicies::default()),
+        event_log: None,
+        storage: None,
+        interlock: Some(Arc::new(Interlock::in_memory(true))),
+        webhook_secret: None,
+        ip_allowlist: None,
+        admin_token: None,
+        admin_allowlist: None,
+        symbol_configs: Arc::new(SymbolConfigs::default()),
+        strategies: Arc::new(HashMap::new()),
+        deduplicator: None,
+        rate_limiter: None,
+        notifications: None,
+        signals: Arc::new(SignalTracker::new()),
+        signal_queue: None,
+        started: Instant::now(),
+    }
+}
+
+/// Serves `app` on a random local port and returns its address.
+async fn serve(app: Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await });
+    address
+}
+
+fn sign(body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[test]
+fn test_signature_header_is_verified_against_the_raw_body() {
+    let body = br#"{"symbol":"BTCUSDT","signal":"buy"}"#;
+    let signature = sign(body);
+    assert!(authenticate(SECRET, body, Some(&signature), None).is_ok());
+    assert!(authenticate(SECRET, body, Some(&format!("sha256={}", signature)), None).is_ok());
+
+    // Any change to the body invalidates the signature
+    let tampered = br#"{"symbol":"BTCUSDT","signal":"sell"}"#;
+    assert_eq!(authenticate(SECRET, tampered, Some(&signature), None).unwrap_err(), "Invalid signature");
+    assert_eq!(authenticate(SECRET, body, Some("not-hex"), None).unwrap_err(), "Malformed signature");
+    // A wrong signature is not rescued by the payload secret
+    assert!(authenticate(SECRET, tampered, Some(&signature), Some(SECRET)).is_err());
+}
+
+#[test]
+fn test_payload_secret_authenticates_unsigned_requests() {
+    let body = br#"{"symbol":"BTCUSDT","signal":"buy","secret":"tv-shared-secret"}"#;
+    let payload: WebhookPayload = serde_json::from_slice(body).unwrap();
+    assert!(authenticate(SECRET, body, None, payload.secret.as_deref()).is_ok());
+    // The secret is never written back out
+    assert!(serde_json::to_value(&payload).unwrap().get("secret").is_none());
+
+    assert_eq!(authenticate(SECRET, body, None, Some("tv-shared-secreT")).unwrap_err(), "Invalid secret");
+    assert_eq!(authenticate(SECRET, body, None, None).unwrap_err(), "Missing signature or secret");
+}
+
+#[test]
+fn test_ip_networks() {
+    let network = IpNetwork::parse("203.0.113.0/24").unwrap();
+    assert!(network.contains("203.0.113.77".parse().unwrap()));
+    assert!(!network.contains("203.0.114.1".parse().unwrap()));
+    // IPv4-mapped IPv6 peers match IPv4 networks
+    assert!(network.contains("::ffff:203.0.113.5".parse().unwrap()));
+    assert!(IpNetwork::parse("2001:db8::/32").unwrap().contains("2001:db8::1".parse().unwrap()));
+    assert!(IpNetwork::parse("0.0.0.0/0").unwrap().contains("8.8.8.8".parse().unwrap()));
+    assert_eq!(IpNetwork::parse("52.89.214.238").unwrap().prefix_len, 32);
+    assert!(IpNetwork::parse("10.0.0.0/33").is_err());
+    assert!(IpNetwork::parse("tradingview.com").is_err());
+}
+
+#[test]
+fn test_allowlist_reads_forwarded_for_only_from_trusted_proxies() {
+    let allowlist = IpAllowlist::parse("tradingview, 203.0.113.0/24", None).unwrap();
+    assert_eq!(allowlist.allowed.len(), TRADINGVIEW_WEBHOOK_IPS.len() + 1);
+    assert!(allowlist.allows("52.32.178.7".parse().unwrap()));
+    assert!(!allowlist.allows("198.51.100.1".parse().unwrap()));
+    assert!(IpAllowlist::parse(" , ", None).is_err());
+
+    let forwarded = |value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(FORWARDED_FOR_HEADER, value.parse().unwrap());
+        headers
+    };
+    let loopback = "127.0.0.1".parse().unwrap();
+    let remote = "198.51.100.1".parse().unwrap();
+    // Through the local tunnel the rightmost entry is the client; a prepended entry is ignored
+    assert_eq!(allowlist.client_ip(loopback, &forwarded("52.89.214.238, 198.51.100.1")), Some(remote));
+    assert_eq!(allowlist.client_ip(loopback, &forwarded("198.51.100.1, 52.89.214.238")), Some("52.89.214.238".parse().unwrap()));
+    assert_eq!(allowlist.client_ip(loopback, &forwarded("garbage")), None);
+    assert_eq!(allowlist.client_ip(loopback, &HeaderMap::new()), Some(loopback));
+    // Direct connections cannot spoof their address
+    assert_eq!(allowlist.client_ip(remote, &forwarded("52.89.214.238")), Some(remote));
+
+    // Extra trusted proxies, e.g. the Docker network of a reverse proxy
+    let allowlist = IpAllowlist::parse("tradingview", Some("172.16.0.0/12")).unwrap();
+    assert_eq!(allowlist.client_ip("172.18.0.2".parse().unwrap(), &forwarded("52.89.214.238")), Some("52.89.214.238".parse().unwrap()));
+    assert_eq!(allowlist.client_ip(loopback, &forwarded("52.89.214.238")), Some(loopback));
+}
+
+#[tokio::test]
+async fn test_allowlist_middleware_rejects_other_sources() {
+    let allowlist = Arc::new(IpAllowlist::parse("tradingview", None).unwrap());
+    let app = Router::new()
+        .route("/webhook", post(|| async { "ok" }).route_layer(middleware::from_fn_with_state(allowlist, enforce_allowlist)));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await });
+
+    let client = reqwest::Client::new();
+    let send = |forwarded_for: Option<&'static str>| {
+        let mut request = client.post(format!("http://{}/webhook", address));
+        if let Some(value) = forwarded_for {
+            request = request.header(FORWARDED_FOR_HEADER, value);
+        }
+        request.send()
+    };
+    assert_eq!(send(Some("52.89.214.238")).await.unwrap().status(), StatusCode::OK);
+    assert_eq!(send(Some("198.51.100.1")).await.unwrap().status(), StatusCode::FORBIDDEN);
+    // Loopback itself is not an allowed source
+    assert_eq!(send(None).await.unwrap().status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn test_payload_order_fields_are_optional() {
+    let payload: WebhookPayload = serde_json::from_str(r#"{"symbol":"BTCUSDT","signal":"buy"}"#).unwrap();
+    assert_eq!(payload.order_type().unwrap(), OrderType::Market);
+    assert!(payload.quantity.is_none() && payload.leverage.is_none() && !payload.reduce_only && !payload.dry_run);
+
+    let payload: WebhookPayload = serde_json::from_str(r#"{"symbol":"BTCUSDT","signal":"sell","quantity":0.01,
+        "price":65000,"leverage":5,"stopLoss":66000,"takeProfit":62000,"reduceOnly":false}"#).unwrap();
+    assert_eq!(payload.order_type().unwrap(), OrderType::Limit);
+    assert_eq!((payload.quantity, payload.leverage), (Some(0.01), Some(5)));
+    assert_eq!((payload.stop_loss, payload.take_profit), (Some(66000.0), Some(62000.0)));
+}
+
+#[test]
+fn test_payload_order_type_needs_a_consistent_price() {
+    let parse = |json: &str| serde_json::from_str::<WebhookPayload>(json).unwrap().order_type();
+    assert_eq!(parse(r#"{"symbol":"BTCUSDT","signal":"buy","orderType":"LIMIT","price":60000}"#).unwrap(), OrderType::Limit);
+    assert_eq!(parse(r#"{"symbol":"BTCUSDT","signal":"buy","orderType":"market"}"#).unwrap(), OrderType::Market);
+    assert!(parse(r#"{"symbol":"BTCUSDT","signal":"buy","orderType":"limit"}"#).is_err());
+    assert!(parse(r#"{"symbol":"BTCUSDT","signal":"buy","orderType":"market","price":60000}"#).is_err());
+    assert!(parse(r#"{"symbol":"BTCUSDT","signal":"buy","price":-1}"#).is_err());
+    assert!(parse(r#"{"symbol":"BTCUSDT","signal":"buy","orderType":"stop"}"#).is_err());
+}
+
+#[test]
+fn test_exit_levels_must_bracket_the_entry() {
+    assert!(check_exit_levels(OrderSide::Buy, 100.0, Some(95.0), Some(110.0)).is_ok());
+    assert!(check_exit_levels(OrderSide::Sell, 100.0, Some(105.0), Some(90.0)).is_ok());
+    assert!(check_exit_levels(OrderSide::Buy, 100.0, None, None).is_ok());
+    assert!(check_exit_levels(OrderSide::Buy, 100.0, Some(105.0), None).is_err());
+    assert!(check_exit_levels(OrderSide::Sell, 100.0, None, Some(110.0)).is_err());
+    assert!(check_exit_levels(OrderSide::Buy, 100.0, Some(100.0), None).is_err());
+}
+
+#[test]
+fn test_explicit_quantities_must_fit_the_lot_size() {
+    let filters = SymbolFilters {
+        symbol: "BTCUSDT".to_string(),
+        tick_size: 0.1,
+        step_size: 0.001,
+        min_qty: 0.001,
+        max_qty: 1000.0,
+        market_step_size: 0.01,
+        market_max_qty: 100.0,
+        min_notional: 100.0,
+    };
+    assert!(check_quantity(&filters, OrderType::Limit, 0.003).is_ok());
+    assert!(check_quantity(&filters, OrderType::Market, 0.3).is_ok());
+    assert!(check_quantity(&filters, OrderType::Market, 0.003).is_err()); // Below the market step
+    assert!(check_quantity(&filters, OrderType::Limit, 0.0035).is_err());
+    assert!(check_quantity(&filters, OrderType::Limit, 0.0005).is_err());
+}
+
+#[test]
+fn test_protective_orders_close_the_position() {
+    let orders = protective_orders("BTCUSDT", OrderSide::Buy, Some(95.0), Some(110.0), None, |suffix| format!("wh1{}", suffix));
+    assert_eq!(orders.len(), 2);
+    assert_eq!((orders[0].order_type, orders[0].side, orders[0].stop_price), (OrderType::StopMarket, OrderSide::Sell, Some(95.0)));
+    assert_eq!((orders[1].order_type, orders[1].stop_price), (OrderType::TakeProfitMarket, Some(110.0)));
+    assert!(orders.iter().all(|o| o.close_position && o.quantity.is_none() && o.position_side.is_none()));
+    assert_eq!(orders[1].new_client_order_id.as_deref(), Some("wh1tp"));
+
+    let orders = protective_orders("BTCUSDT", OrderSide::Sell, None, Some(90.0), Some(PositionSide::Short), |suffix| suffix.to_string());
+    assert_eq!(orders.len(), 1);
+    assert_eq!((orders[0].side, orders[0].position_side.clone()), (OrderSide::Buy, Some(PositionSide::Short)));
+    assert!(orders[0].validate().is_ok());
+}
+
+#[test]
+fn test_symbol_configs_resolve_with_fallback() {
+    assert_eq!(SymbolConfigs::default().resolve("BTCUSDT").unwrap(), None);
+
+    let configs = parse_symbol_configs(r#"{"BTCUSDT": {"quoteQuantity": 200}, "ethusdt": {"enabled": false}}"#).unwrap();
+    assert_eq!(configs.resolve("btcusdt").unwrap().unwrap().quote_quantity, Some(200.0));
+    assert!(!configs.resolve("ETHUSDT").unwrap().unwrap().enabled);
+    assert!(configs.resolve("SOLUSDT").is_err());
+
+    let configs = parse_symbol_configs(r#"{"BTCUSDT": {"quantity": 0.01}, "*": {"leverage": 2}}"#).unwrap();
+    assert_eq!(configs.resolve("SOLUSDT").unwrap().unwrap().leverage, Some(2));
+
+    assert!(parse_symbol_configs(r#"{"BTCUSDT": {"quantity": 0.01, "quoteQuantity": 100}}"#).is_err());
+    assert!(parse_symbol_configs(r#"{"BTCUSDT": {"maxPosition": 0}}"#).is_err());
+    assert!(parse_symbol_configs(r#"{"BTCUSDT": {"leverage": 0}}"#).is_err());
+    assert!(parse_symbol_configs(r#"{"BTCUSDT": {"size": 1}}"#).is_err());
+    assert!(parse_symbol_configs(r#"{"BTCUSDT": {}, "btcusdt": {}}"#).is_err());
+}
+
+#[test]
+fn test_symbol_config_fills_in_defaults_and_restricts_signals() {
+    let config = SymbolConfig {
+        quote_quantity: Some(200.0),
+        leverage: Some(5),
+        allowed_signals: Some(vec!["buy".to_string(), "close_long".to_string()]),
+        ..Default::default()
+    };
+    let mut payload: WebhookPayload = serde_json::from_str(r#"{"symbol":"BTCUSDT","signal":"BUY"}"#).unwrap();
+    config.apply(&mut payload).unwrap();
+    assert_eq!((payload.quantity, payload.quote_quantity, payload.leverage), (None, Some(200.0), Some(5)));
+
+    // The payload's own size and leverage win
+    let mut payload: WebhookPayload = serde_json::from_str(r#"{"symbol":"BTCUSDT","signal":"buy","quantity":0.01,"leverage":3}"#).unwrap();
+    config.apply(&mut payload).unwrap();
+    assert_eq!((payload.quantity, payload.quote_quantity, payload.leverage), (Some(0.01), None, Some(3)));
+
+    // Closes are not given the default size, so they close the whole position
+    let mut payload: WebhookPayload = serde_json::from_str(r#"{"symbol":"BTCUSDT","signal":"close_long"}"#).unwrap();
+    config.apply(&mut payload).unwrap();
+    assert_eq!((payload.quantity, payload.quote_quantity), (None, None));
+
+    let mut payload: WebhookPayload = serde_json::from_str(r#"{"symbol":"BTCUSDT","signal":"sell"}"#).unwrap();
+    assert!(config.apply(&mut payload).unwrap_err().contains("not allowed"));
+    let disabled = SymbolConfig { enabled: false, ..Default::default() };
+    assert!(disabled.apply(&mut payload).unwrap_err().contains("disabled"));
+}
+
+#[test]
+fn test_symbol_config_limits_the_position() {
+    let config = SymbolConfig { max_position: Some(0.05), ..Default::default() };
+    assert!(config.check_position("BTCUSDT", 0.03, 0.02).is_ok());
+    assert!(config.check_position("BTCUSDT", 0.03, 0.03).is_err());
+    assert!(config.check_position("BTCUSDT", 0.03, -0.07).is_ok());
+    assert!(config.check_position("BTCUSDT", -0.04, -0.02).is_err());
+    assert!(SymbolConfig::default().check_position("BTCUSDT", 10.0, 10.0).is_ok());
+}
+
+#[test]
+fn test_retried_alerts_are_deduplicated_within_the_window() {
+    let dedup = AlertDeduplicator::new(Duration::from_secs(60));
+    let parse = |json: &str| serde_json::from_str::<WebhookPayload>(json).unwrap();
+    let buy = parse(r#"{"symbol":"BTCUSDT","signal":"buy","quoteQuantity":100}"#);
+    assert!(dedup.register(&buy, 0).is_ok());
+    assert!(dedup.register(&buy, 30_000).unwrap_err().starts_with("Duplicate alert hash:"));
+    // A different payload is a different alert; the secret is not part of the hash
+    assert!(dedup.register(&parse(r#"{"symbol":"BTCUSDT","signal":"sell","quoteQuantity":100}"#), 30_000).is_ok());
+    assert!(dedup.register(&parse(r#"{"symbol":"BTCUSDT","signal":"buy","quoteQuantity":100,"secret":"s"}"#), 40_000).is_err());
+    // After the window the same payload is a new alert
+    assert!(dedup.register(&buy, 60_000).is_ok());
+    // So is a forgotten one, e.g. after it was rate limited
+    dedup.forget(&buy);
+    assert!(dedup.register(&buy, 61_000).is_ok());
+}
+
+#[test]
+fn test_alert_ids_identify_alerts() {
+    let dedup = AlertDeduplicator::new(Duration::from_secs(60));
+    let parse = |json: &str| serde_json::from_str::<WebhookPayload>(json).unwrap();
+    let first = parse(r#"{"symbol":"BTCUSDT","signal":"buy","alertId":"a-1","price":60000}"#);
+    assert_eq!(AlertDeduplicator::alert_key(&first), "id:a-1");
+    assert!(dedup.register(&first, 0).is_ok());
+    // The ID wins over the payload: a changed field does not make a repeated ID new
+    assert!(dedup.register(&parse(r#"{"symbol":"BTCUSDT","signal":"buy","alert_id":"a-1","price":60010}"#), 1_000).is_err());
+    assert!(dedup.register(&parse(r#"{"symbol":"BTCUSDT","signal":"buy","alertId":"a-2","price":60000}"#), 1_000).is_ok());
+}
+
+#[test]
+fn test_signal_tracker_records_outcomes() {
+    let tracker = SignalTracker::new();
+    let first = tracker.start(1_000, "BTCUSDT", "buy");
+    let second = tracker.start(1_000, "BTCUSDT", "buy");
+    assert_ne!(first, second);
+    // Tracking IDs are random UUIDs, not derived from the time received
+    assert!(uuid::Uuid::parse_str(&first).is_ok_and(|id| id.get_version_num() == 4));
+    assert_eq!(tracker.get(&first), Some(SignalStatus::Queued));
+
+    tracker.set(&first, SignalStatus::Rejected { code: ErrorCode::Rejected, reason: "Disarmed: not armed".to_string() });
+    let json = serde_json::to_value(tracker.get(&first).unwrap()).unwrap();
+    assert_eq!(json, serde_json::json!({"status": "rejected", "code": "rejected", "reason": "Disarmed: not armed"}));
+    assert_eq!(tracker.get(&second), Some(SignalStatus::Queued));
+    assert_eq!(tracker.get("unknown"), None);
+}
+
+#[test]
+fn test_signal_tracker_keeps_the_most_recent_signals() {
+    let tracker = SignalTracker::new();
+    let oldest = tracker.start(0, "BTCUSDT", "buy");
+    let ids: Vec<String> = (0..TRACKED_SIGNALS).map(|i| tracker.start(i as i64, "BTCUSDT", "buy")).collect();
+    assert_eq!(tracker.get(&oldest), None);
+    assert!(ids.iter().all(|id| tracker.get(id).is_some()));
+}
+
+#[test]
+fn test_signal_tracker_lists_recent_signals_newest_first() {
+    let tracker = SignalTracker::new();
+    let first = tracker.start(1_000, "BTCUSDT", "buy");
+    let second = tracker.start(2_000, "ethusdt", "sell");
+    let recent: Vec<String> = tracker.recent().into_iter().map(|signal| signal.tracking_id).collect();
+    assert_eq!(recent, [second, first]);
+}
+
+#[test]
+fn test_webhook_responses_map_to_status_codes() {
+    let cases = [
+        (WebhookResponse::placed("1-0", 42, "whb1", vec![]), StatusCode::OK),
+        (WebhookResponse::accepted("1-0"), StatusCode::ACCEPTED),
+        (WebhookResponse::duplicate("Duplicate alert id:a-1"), StatusCode::OK),
+        (WebhookError::invalid("Invalid payload").into(), StatusCode::BAD_REQUEST),
+        (WebhookError::new(ErrorCode::Unauthorized, "Invalid secret").into(), StatusCode::UNAUTHORIZED),
+        (WebhookError::rejected("Rejected by risk policy").into(), StatusCode::UNPROCESSABLE_ENTITY),
+        (WebhookError::exchange("Error placing order").into(), StatusCode::BAD_GATEWAY),
+        (WebhookError::new(ErrorCode::Unavailable, "Signal queue is full").into(), StatusCode::SERVICE_UNAVAILABLE),
+    ];
+    for (response, status) in cases {
+        assert_eq!(response.http_status(), status, "{:?}", response);
+    }
+}
+
+#[test]
+fn test_webhook_response_json() {
+    let placed = serde_json::to_value(WebhookResponse::placed("1-0", 42, "whb1", vec![])).unwrap();
+    assert_eq!(placed, serde_json::json!({"status": "placed", "tracking_id": "1-0", "order_id": 42, "client_order_id": "whb1"}));
+
+    let rejected = serde_json::to_value(WebhookResponse::from(WebhookError::rejected("Notional value too small"))).unwrap();
+    assert_eq!(rejected, serde_json::json!({"status": "error", "error": {"code": "rejected", "message": "Notional value too small"}}));
+
+    let flattened = WebhookResponse::flattened("Disarmed; cancelled 2 open order(s) and closed 1 position(s)", vec!["Failed to close ETHUSDT position: timeout".to_string()]);
+    assert_eq!(flattened.http_status(), StatusCode::OK);
+    assert_eq!(serde_json::to_value(flattened).unwrap(), serde_json::json!({
+        "status": "flattened",
+        "message": "Disarmed; cancelled 2 open order(s) and closed 1 position(s)",
+        "warnings": ["Failed to close ETHUSDT position: timeout"]
+    }));
+}
+
+fn position(symbol: &str, position_side: &str, amount: &str) -> PositionRisk {
+    serde_json::from_value(serde_json::json!({
+        "symbol": symbol, "positionSide": position_side, "positionAmt": amount, "entryPrice": "60000",
+        "breakEvenPrice": "60030", "markPrice": "61000", "unRealizedProfit": "10", "liquidationPrice": "0",
+        "notional": "610", "updateTime": 1718000000000u64
+    })).unwrap()
+}
+
+#[test]
+fn test_closing_position_in_one_way_mode() {
+    let positions = vec![position("ETHUSDT", "BOTH", "3"), position("BTCUSDT", "BOTH", "-0.015")];
+    assert_eq!(closing_position(&positions, "btcusdt", false, None), Some((0.015, PositionSide::Both)));
+    // A short cannot be closed by close_long, and a flat symbol has nothing to close
+    assert_eq!(closing_position(&positions, "BTCUSDT", true, None), None);
+    assert_eq!(closing_position(&[position("BTCUSDT", "BOTH", "0")], "BTCUSDT", true, None), None);
+    assert_eq!(closing_position(&positions, "SOLUSDT", true, None), None);
+}
+
+#[test]
+fn test_closing_position_in_hedge_mode() {
+    let positions = vec![position("BTCUSDT", "LONG", "0.02"), position("BTCUSDT", "SHORT", "-0.01")];
+    assert_eq!(closing_position(&positions, "BTCUSDT", true, None), Some((0.02, PositionSide::Long)));
+    assert_eq!(closing_position(&positions, "BTCUSDT", false, None), Some((0.01, PositionSide::Short)));
+    assert_eq!(closing_position(&positions, "BTCUSDT", true, Some(PositionSide::Short)), None);
+    assert_eq!(closing_position(&[position("BTCUSDT", "LONG", "0")], "BTCUSDT", true, None), None);
+}
+
+#[test]
+fn test_status_summarizes_open_positions() {
+    let positions = vec![position("BTCUSDT", "BOTH", "-0.015"), position("ETHUSDT", "BOTH", "0")];
+    let summary = summarize_positions(&positions);
+    assert_eq!(summary.len(), 1);
+    assert_eq!((summary[0].symbol.as_str(), summary[0].amount, summary[0].entry_price), ("BTCUSDT", -0.015, 60000.0));
+    assert_eq!((summary[0].mark_price, summary[0].unrealized_pnl), (61000.0, 10.0));
+}
+
+#[test]
+fn test_tracker_reports_the_last_processed_signal() {
+    let tracker = SignalTracker::new();
+    assert!(tracker.last_processed().is_none());
+    let first = tracker.start(1_000, "BTCUSDT", "buy");
+    tracker.start(2_000, "ETHUSDT", "sell"); // Still queued
+    tracker.set(&first, SignalStatus::Skipped { reason: "No open long position".to_string() });
+    let last = tracker.last_processed().unwrap();
+    assert_eq!((last.tracking_id, last.symbol.as_str(), last.received_ms), (first, "BTCUSDT", 1_000));
+}
+
+#[test]
+fn test_operator_requests_need_the_bearer_secret() {
+    assert!(authorize_bearer(SECRET, Some(&format!("Bearer {}", SECRET))).is_ok());
+    assert_eq!(authorize_bearer(SECRET, Some("Bearer wrong")).unwrap_err(), "Invalid token");
+    assert_eq!(authorize_bearer(SECRET, Some(SECRET)).unwrap_err(), "Missing bearer token");
+    assert_eq!(authorize_bearer(SECRET, None).unwrap_err(), "Missing bearer token");
+}
+
+#[test]
+fn test_close_all_closes_every_open_leg() {
+    let positions = vec![position("BTCUSDT", "BOTH", "-0.015"), position("ETHUSDT", "LONG", "3"), position("SOLUSDT", "BOTH", "0")];
+    let requests = close_all_requests(&positions, |index| format!("ctl{}", index));
+    assert_eq!(requests.len(), 2);
+    assert_eq!((requests[0].side, requests[0].quantity, requests[0].reduce_only), (OrderSide::Buy, Some(0.015), true));
+    assert_eq!((requests[1].side, requests[1].position_side.clone(), requests[1].reduce_only), (OrderSide::Sell, Some(PositionSide::Long), false));
+    assert_eq!(requests[1].new_client_order_id.as_deref(), Some("ctl1"));
+}
+
+#[test]
+fn test_dry_run_responses_list_the_orders() {
+    let payload: WebhookPayload = serde_json::from_str(r#"{"symbol":"BTCUSDT","signal":"buy","dryRun":true}"#).unwrap();
+    assert!(payload.dry_run);
+
+    let entry = trading_bot::order::NewOrderRequest::new("BTCUSDT", OrderSide::Buy, OrderType::Market).quantity(0.01);
+    let response = WebhookResponse::dry_run(vec![entry.to_params()]);
+    assert_eq!(response.http_status(), StatusCode::OK);
+    let json = serde_json::to_value(response).unwrap();
+    assert_eq!(json["status"], "dry_run");
+    assert_eq!((json["orders"][0]["side"].as_str(), json["orders"][0]["quantity"].as_str()), (Some("BUY"), Some("0.01")));
+}
+
+#[test]
+fn test_rate_limits_allow_bursts_and_refill() {
+    assert_eq!(RateLimit::parse("60/2").unwrap(), Some(RateLimit { per_minute: 60, burst: 2 }));
+    assert_eq!(RateLimit::parse("off").unwrap(), None);
+    assert!(RateLimit::parse("60/0").is_err());
+
+    let limiter = RateLimiter::new(RateLimitSettings { global: None, per_source: RateLimit::parse("60/2").unwrap(), symbol_signals_per_minute: None });
+    let (a, b) = ("203.0.113.7".parse().unwrap(), "203.0.113.8".parse().unwrap());
+    assert!(limiter.check_request(Some(a), 0).is_ok());
+    assert!(limiter.check_request(Some(a), 0).is_ok());
+    assert!(limiter.check_request(Some(a), 10).unwrap_err().contains("203.0.113.7"));
+    assert!(limiter.check_request(Some(b), 10).is_ok()); // Sources have their own buckets
+    assert!(limiter.check_request(Some(a), 1_010).is_ok()); // One token per second
+}
+
+#[test]
+fn test_a_flood_of_sources_evicts_the_least_recently_seen() {
+    let limiter = RateLimiter::new(RateLimitSettings { global: None, per_source: RateLimit::parse("1/1").unwrap(), symbol_signals_per_minute: None });
+    let (a, b) = ("203.0.113.7".parse().unwrap(), "203.0.113.8".parse().unwrap());
+    assert!(limiter.check_request(Some(a), 0).is_ok());
+    assert!(limiter.check_request(Some(a), 1).is_err());
+    assert!(limiter.check_request(Some(b), 2).is_ok());
+    for i in 0..MAX_TRACKED_SOURCES - 2 {
+        assert!(limiter.check_request(Some(IpAddr::from([10, 0, (i >> 8) as u8, i as u8])), 3).is_ok());
+    }
+    // No bucket is idle, so the next new source pushes out the least recently seen one (a)
+    assert!(limiter.check_request(Some("198.51.100.1".parse().unwrap()), 4).is_ok());
+    assert!(limiter.check_request(Some(a), 5).is_ok()); // Tracked afresh, in place of b
+    assert!(limiter.check_request(Some(IpAddr::from([10, 0, 0, 0])), 6).is_err()); // Recently seen sources keep their buckets
+}
+
+#[tokio::test]
+async fn test_rate_limited_alerts_are_not_duplicates_when_retried() {
+    let mut state = offline_state("http://127.0.0.1:9").await;
+    state.deduplicator = Some(Arc::new(AlertDeduplicator::new(Duration::from_secs(60))));
+    state.rate_limiter = Some(Arc::new(RateLimiter::new(RateLimitSettings { global: None, per_source: None, symbol_signals_per_minute: Some(0) })));
+    let address = serve(router(state)).await;
+
+    let alert = json!({"symbol": "BTCUSDT", "signal": "buy", "alertId": "a-1", "quoteQuantity": 100});
+    for _ in 0..2 {
+        let response = reqwest::Client::new().post(format!("http://{}/webhook", address)).json(&alert).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
+
+#[tokio::test]
+async fn test_alerts_dropped_by_a_full_queue_are_not_duplicates_when_retried() {
+    let mut state = offline_state("http://127.0.0.1:9").await;
+    state.deduplicator = Some(Arc::new(AlertDeduplicator::new(Duration::from_secs(60))));
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(1); // No worker drains it
+    state.signal_queue = Some(sender);
+    let address = serve(router(state)).await;
+    let send = |alert: serde_json::Value| reqwest::Client::new().post(format!("http://{}/webhook", address)).json(&alert).send();
+
+    let first = json!({"symbol": "BTCUSDT", "signal": "buy", "alertId": "a-1", "quoteQuantity": 100});
+    let second = json!({"symbol": "ETHUSDT", "signal": "buy", "alertId": "a-2", "quoteQuantity": 100});
+    assert_eq!(send(first).await.unwrap().status(), StatusCode::ACCEPTED);
+    assert_eq!(send(second.clone()).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // Once there is room again, TradingView's retry of the dropped alert is queued
+    assert!(receiver.recv().await.is_some());
+    let retry = send(second).await.unwrap();
+    assert_eq!(retry.status(), StatusCode::ACCEPTED);
+    let body: serde_json::Value = retry.json().await.unwrap();
+    assert_eq!(body["status"], "accepted");
+}
+
+#[test]
+fn test_signals_are_limited_per_symbol_per_minute() {
+    let limiter = RateLimiter::new(RateLimitSettings { global: None, per_source: None, symbol_signals_per_minute: Some(2) });
+    assert!(limiter.check_symbol("BTCUSDT", 0).is_ok());
+    assert!(limiter.check_symbol("btcusdt", 1_000).is_ok());
+    assert!(limiter.check_symbol("BTCUSDT", 2_000).is_err());
+    assert!(limiter.check_symbol("ETHUSDT", 2_000).is_ok());
+    assert!(limiter.check_symbol("BTCUSDT", 60_000).is_ok()); // The first signal left the window
+    assert_eq!(ErrorCode::RateLimited.http_status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[test]
+fn test_strategy_configs_override_symbols_sizing_and_account() {
+    let strategies = parse_strategy_configs(r#"{
+        "Trend": {"symbols": {"BTCUSDT": {"leverage": 3}}, "sizing": "risk_percent:0.01"},
+        "scalp": {"apiKey": "key", "secretKey": "secret"}
+    }"#).unwrap();
+    let trend = strategies.get("trend").unwrap();
+    assert!(trend.symbols.as_ref().unwrap().resolve("ETHUSDT").is_err()); // Only its own symbols
+    assert!(trend.sizing.is_some() && trend.credentials.is_none());
+    let scalp = strategies.get("SCALP").unwrap();
+    assert_eq!(scalp.credentials.as_ref().map(|c| c.api_key.as_str()), Some("key"));
+    assert!(!format!("{:?}", scalp).contains("secret"));
+    assert!(strategies.get("swing").is_none());
+
+    assert!(parse_strategy_configs(r#"{"a": {"apiKey": "key"}}"#).unwrap_err().contains("secretKey"));
+    assert!(parse_strategy_configs(r#"{"a": {"sizing": "martingale"}}"#).is_err());
+    assert!(parse_strategy_configs(r#"{"a": {"budget": 5}}"#).is_err());
+
+    let payload: WebhookPayload = serde_json::from_str(r#"{"symbol":"BTCUSDT","signal":"buy","strategy":"trend"}"#).unwrap();
+    assert_eq!(payload.strategy.as_deref(), Some("trend"));
+}
+
+#[test]
+fn test_tls_files_must_hold_a_certificate_and_key() {
+    let dir = std::env::temp_dir().join(format!("webhook_tls_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (cert_path, key_path) = (dir.join("cert.pem"), dir.join("key.pem"));
+    std::fs::write(&cert_path, "not a certificate").unwrap();
+    let files = TlsFiles { cert_path: cert_path.clone(), key_path: key_path.clone() };
+    assert!(files.load().unwrap_err().contains("No certificate found"));
+    std::fs::remove_file(&cert_path).unwrap();
+    assert!(files.load().unwrap_err().contains("Failed to open"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_kill_switch_signal_needs_a_webhook_secret() {
+    let state = offline_state("http://127.0.0.1:9").await;
+    let interlock = state.interlock.clone().unwrap();
+    let address = serve(router(state)).await;
+
+    let response = reqwest::Client::new().post(format!("http://{}/webhook", address))
+        .json(&json!({"symbol": "BTCUSDT", "signal": "kill_switch"}))
+        .send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(interlock.is_armed());
+}
+
+#[tokio::test]
+async fn test_control_routes_need_the_admin_token_and_allowlist() {
+    let mut state = offline_state("http://127.0.0.1:9").await;
+    state.webhook_secret = Some(SECRET.to_string());
+    let interlock = state.interlock.clone().unwrap();
+    let pause = |address: SocketAddr, token: &str| reqwest::Client::new()
+        .post(format!("http://{}/control/pause", address))
+        .bearer_auth(token)
+        .send();
+
+    let address = serve(router(state.clone())).await;
+    assert_eq!(pause(address, SECRET).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE); // No admin token configured
+
+    state.admin_token = Some("admin-token".to_string());
+    let address = serve(router(state.clone())).await;
+    assert_eq!(pause(address, SECRET).await.unwrap().status(), StatusCode::UNAUTHORIZED); // The webhook secret is not enough
+    assert!(interlock.is_armed());
+
+    state.admin_allowlist = Some(Arc::new(IpAllowlist::parse("10.0.0.0/8", None).unwrap()));
+    let address = serve(router(state.clone())).await;
+    assert_eq!(pause(address, "admin-token").await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert!(interlock.is_armed());
+
+    state.admin_allowlist = Some(Arc::new(IpAllowlist::parse("127.0.0.1", None).unwrap()));
+    let address = serve(router(state)).await;
+    assert_eq!(pause(address, "admin-token").await.unwrap().status(), StatusCode::OK);
+    assert!(!interlock.is_armed());
+}
+
+#[tokio::test]
+async fn test_status_needs_the_admin_token_and_allowlist() {
+    let mut state = offline_state("http://127.0.0.1:9").await;
+    state.webhook_secret = Some(SECRET.to_string());
+    let status = |address: SocketAddr, token: Option<&str>| {
+        let request = reqwest::Client::new().get(format!("http://{}/status", address));
+        match token {
+            Some(token) => request.bearer_auth(token).send(),
+            None => request.send(),
+        }
+    };
+
+    let address = serve(router(state.clone())).await;
+    assert_eq!(status(address, None).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE); // No admin token configured
+    assert_eq!(reqwest::get(format!("http://{}/health", address)).await.unwrap().status(), StatusCode::OK);
+
+    state.admin_token = Some("admin-token".to_string());
+    let address = serve(router(state.clone())).await;
+    assert_eq!(status(address, None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(status(address, Some(SECRET)).await.unwrap().status(), StatusCode::UNAUTHORIZED); // The webhook secret is not enough
+
+    state.admin_allowlist = Some(Arc::new(IpAllowlist::parse("10.0.0.0/8", None).unwrap()));
+    let address = serve(router(state.clone())).await;
+    assert_eq!(status(address, Some("admin-token")).await.unwrap().status(), StatusCode::FORBIDDEN);
+
+    state.admin_allowlist = Some(Arc::new(IpAllowlist::parse("127.0.0.1", None).unwrap()));
+    let address = serve(router(state)).await;
+    let response = status(address, Some("admin-token")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["positions_error"].is_string()); // The exchange is unreachable
+}
+
+#[tokio::test]
+async fn test_flatten_closes_positions_when_open_orders_fail() {
+    let exchange = Router::new()
+        .route("/fapi/v1/openOrders", get(|| async { (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"code": -1001, "msg": "Internal error"}))) }))
+        .route("/fapi/v3/positionRisk", get(|| async { Json(json!([])) }));
+    let exchange = serve(exchange).await;
+    let state = offline_state(&format!("http://{}", exchange)).await;
+
+    let report = panic_close_all(&state, "test").await.unwrap();
+    assert!(!state.interlock.as_ref().unwrap().is_armed());
+    assert_eq!((report.cancelled_orders, report.closed_positions), (0, 0));
+    assert!(report.problems.iter().any(|problem| problem.contains("Could not get open orders")), "{:?}", report.problems);
+}
@@ -13,19 +13,19 @@ use serde_json::{from_value, Value};
 use std::env;
 use tokio::time::{self, Duration};
 use tokio::sync::mpsc;
-use log::{info, error, debug, warn};
+use tracing::{info, error, debug, warn};
 use serde_json::json;
 
 // Initialize logging for tests (optional, but good for debugging)
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn setup_logging_stream() {
-    let _ = env_logger::builder().is_test(true).try_init();
+    let _ = trading_bot::telemetry::try_init();
 }
 
 /// Comprehensive test for WebSocket stream: subscribe, get data, unsubscribe
 #[tokio::test]
 async fn test_websocket_stream_lifecycle() {
-    env_logger::init();
+    trading_bot::telemetry::init();
     info!("=== Starting WebSocket stream lifecycle test ===");
 
     // Create channel for receiving stream data
@@ -143,3 +143,132 @@ async fn test_websocket_stream_lifecycle() {
 
     info!("=== Test completed successfully ===");
 }
+
+#[test]
+fn test_combined_stream_url_and_payload() {
+    use trading_bot::websocket_stream::combined_stream_url;
+
+    let streams = vec!["btcusdt@aggTrade".to_string(), "ethusdt@kline_1m".to_string()];
+    let expected = "wss://fstream.binance.com/stream?streams=btcusdt@aggTrade/ethusdt@kline_1m";
+    assert_eq!(combined_stream_url("wss://fstream.binance.com/ws", &streams), expected);
+    assert_eq!(combined_stream_url("wss://fstream.binance.com/ws/", &streams), expected);
+    assert_eq!(combined_stream_url("wss://fstream.binance.com/stream?streams=x", &streams), expected);
+    assert_eq!(combined_stream_url("wss://fstream.binance.com", &[]), "wss://fstream.binance.com/stream");
+
+    let wrapped: BinanceWsMessage = serde_json::from_value(json!({"stream": "btcusdt@aggTrade", "data": {"e": "aggTrade"}})).unwrap();
+    assert!(matches!(&wrapped, BinanceWsMessage::StreamData { stream, .. } if stream == "btcusdt@aggTrade"));
+    let raw: BinanceWsMessage = serde_json::from_value(json!({"e": "aggTrade"})).unwrap();
+    assert_eq!(wrapped.payload(), raw.payload());
+    let result: BinanceWsMessage = serde_json::from_value(json!({"result": null, "id": 1})).unwrap();
+    assert!(result.payload().is_none());
+}
+
+/// Serves one market stream connection on localhost: confirms every request with a result and
+/// sends `frames` after the first SUBSCRIBE.
+async fn mock_market_stream(frames: Vec<String>) -> String {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let mut frames = Some(frames);
+        while let Some(Ok(Message::Text(text))) = socket.next().await {
+            let request: Value = serde_json::from_str(&text).unwrap();
+            socket.send(Message::Text(json!({"result": null, "id": request["id"]}).to_string().into())).await.unwrap();
+            for frame in frames.take().unwrap_or_default() {
+                socket.send(Message::Text(frame.into())).await.unwrap();
+            }
+        }
+    });
+    format!("ws://{}/ws", address)
+}
+
+#[tokio::test]
+async fn test_typed_subscriptions_get_their_own_receivers() {
+    use trading_bot::market_data::KlineInterval;
+
+    let frames = vec![
+        r#"{"e":"aggTrade","E":1,"s":"BTCUSDT","a":7,"p":"30000.5","q":"0.1","f":1,"l":1,"T":1,"m":false}"#.to_string(),
+        r#"{"e":"kline","E":2,"s":"BTCUSDT","k":{"t":0,"T":59999,"s":"BTCUSDT","i":"1m","f":1,"L":2,"o":"1","c":"2","h":"3","l":"0.5","v":"10","n":2,"x":false,"q":"15","V":"5","Q":"7","B":"0"}}"#.to_string(),
+        r#"{"e":"aggTrade","E":3,"s":"ETHUSDT","a":8,"p":"2000","q":"1","f":2,"l":2,"T":3,"m":true}"#.to_string(),
+    ];
+    let (data_sender, mut data_receiver) = mpsc::channel::<BinanceWsMessage>(10);
+    let client = MarketStreamClient::new(mock_market_stream(frames).await, data_sender).await;
+
+    let mut trades = client.subscribe_agg_trades("BTCUSDT").await.unwrap();
+    let trade = time::timeout(Duration::from_secs(5), trades.recv()).await.unwrap().unwrap();
+    assert_eq!((trade.agg_trade_id, trade.price.as_str()), (7, "30000.5"));
+
+    // Not subscribed through a typed helper: still forwarded as a generic message
+    let other = time::timeout(Duration::from_secs(5), data_receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(other.payload().unwrap()["e"], "kline");
+    let other = time::timeout(Duration::from_secs(5), data_receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(other.payload().unwrap()["s"], "ETHUSDT");
+
+    let klines = client.subscribe_klines("BTCUSDT", KlineInterval::M1).await;
+    assert!(klines.is_ok());
+}
+
+#[tokio::test]
+async fn test_backpressure_policies() {
+    use trading_bot::websocket_stream::BackpressurePolicy;
+
+    assert_eq!("drop_oldest".parse::<BackpressurePolicy>().unwrap(), BackpressurePolicy::DropOldest);
+    assert!("drop_all".parse::<BackpressurePolicy>().is_err());
+
+    let frames: Vec<String> = (1..=4).map(|id| json!({"e": "bookTicker", "u": id}).to_string()).collect();
+    // A channel of one message: `DropOldest` buffers one more and keeps the latest
+    for (policy, dropped, expected) in [(BackpressurePolicy::DropNewest, 3, vec![1]), (BackpressurePolicy::DropOldest, 2, vec![1, 4])] {
+        let (data_sender, mut data_receiver) = mpsc::channel::<BinanceWsMessage>(1);
+        let client = MarketStreamClient::new(mock_market_stream(frames.clone()).await, data_sender).await;
+        client.set_backpressure_policy(policy);
+        client.subscribe(vec!["btcusdt@bookTicker".to_string()]).await.unwrap();
+
+        // The consumer stalls until all frames are in
+        time::timeout(Duration::from_secs(5), async {
+            while client.data_queue_stats().dropped < dropped {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+        let stats = client.data_queue_stats();
+        assert_eq!((stats.capacity, stats.depth), (1, expected.len()));
+        assert!(stats.render_metrics().contains(&format!("trading_bot_market_data_dropped_total{{policy=\"{}\"}} {}", policy.as_str(), dropped)));
+
+        for id in expected {
+            let message = time::timeout(Duration::from_secs(5), data_receiver.recv()).await.unwrap().unwrap();
+            assert_eq!(message.payload().unwrap()["u"], id);
+        }
+    }
+}
+
+#[test]
+fn test_stream_latency_and_staleness() {
+    use trading_bot::websocket_stream::latency::*;
+
+    assert_eq!(stream_key("BTCUSDT@depth20@100ms"), "btcusdt@depth");
+    assert_eq!(stream_key("btcusdt@markPrice@1s"), "btcusdt@markPrice");
+    let kline = r#"{"e":"kline","E":1000,"s":"BTCUSDT","k":{"t":0,"T":59999,"s":"BTCUSDT","i":"1m"}}"#;
+    assert_eq!(frame_info(kline), Some(FrameInfo { stream: "btcusdt@kline_1m".to_string(), event_time: Some(1000) }));
+    let combined = format!(r#"{{"stream":"btcusdt@depth@100ms","data":{{"e":"depthUpdate","E":2000,"s":"BTCUSDT"}}}}"#);
+    assert_eq!(frame_info(&combined).unwrap().stream, "btcusdt@depth");
+    assert_eq!(frame_info(r#"{"result":null,"id":1}"#), None);
+
+    let mut monitor = StreamMonitor::new(100);
+    monitor.set_watched(&["btcusdt@aggTrade".to_string(), "ethusdt@aggTrade".to_string()], 0);
+    for i in 1..=100u64 {
+        // Latencies of 1..=100 ms
+        monitor.record(&FrameInfo { stream: "btcusdt@aggTrade".to_string(), event_time: Some(i * 1000) }, i * 1000 + i);
+    }
+    let summary = &monitor.summaries()[0];
+    assert_eq!((summary.messages, summary.p50_ms, summary.p90_ms, summary.p99_ms, summary.max_ms), (100, 50, 90, 99, 100));
+    assert!(monitor.render_metrics(100_100).contains("trading_bot_stream_latency_ms{stream=\"btcusdt@aggTrade\",quantile=\"0.99\"} 99"));
+
+    // ETHUSDT never delivered a message since it was subscribed
+    assert_eq!(monitor.stale_streams(100_100, 30_000), vec![("ethusdt@aggTrade".to_string(), 100_100)]);
+    assert_eq!(monitor.stale_streams(140_000, 30_000).len(), 2);
+    monitor.set_watched(&["btcusdt@aggTrade".to_string()], 140_000);
+    assert_eq!(monitor.stale_streams(140_000, 30_000).len(), 1);
+}